@@ -2,7 +2,7 @@
  * STEM Adventures Admin Console
  * ================================
  * Single-page admin UI for content moderation, user management,
- * and dashboard statistics.
+ * dashboard statistics and gameplay analytics.
  */
 
 (function () {
@@ -50,6 +50,7 @@
         if (page === 'dashboard') loadDashboard();
         if (page === 'queue') loadQueue();
         if (page === 'reports') loadReports();
+        if (page === 'analytics') loadAnalytics();
         if (page === 'log') { logOffset = 0; loadLog(); }
     }
 
@@ -368,6 +369,133 @@
         document.getElementById('user-detail').style.display = 'none';
     });
 
+    // =========================================
+    // Analytics
+    // =========================================
+
+    async function loadAnalytics() {
+        const days = document.getElementById('analytics-days').value;
+        const gameId = document.getElementById('analytics-game').value.trim();
+        const query = `days=${days}` + (gameId ? `&gameId=${encodeURIComponent(gameId)}` : '');
+
+        loadFunnel(query);
+        loadSessions(query);
+        if (gameId) {
+            loadDeaths(query);
+        } else {
+            document.getElementById('analytics-deaths').innerHTML =
+                '<p class="empty-state">Enter a game ID to see where players die.</p>';
+        }
+    }
+
+    async function loadFunnel(query) {
+        const list = document.getElementById('analytics-funnel');
+        try {
+            const data = await api('GET', `/admin/analytics/funnel?${query}`);
+            if (data.games.length === 0) {
+                list.innerHTML = '<p class="empty-state">No sessions in this period.</p>';
+                return;
+            }
+            let html = data.games.map(g => `
+                <div class="content-item">
+                    <div class="item-header">
+                        <div><strong>${escHtml(g.gameId)}</strong></div>
+                        <span class="item-meta">${pct(g.completionRate)} complete a level</span>
+                    </div>
+                    <div class="item-meta">Started: ${g.started} | Completed a level: ${g.completed} | Reached game over: ${g.ended}</div>
+                    <div class="funnel-bar"><span style="width:${pct(g.completionRate)}"></span></div>
+                </div>`).join('');
+            if (data.levels) {
+                html += data.levels.map(l => `
+                    <div class="content-item">
+                        <div class="item-header">
+                            <div>Level ${l.level + 1}</div>
+                            <span class="item-meta">${l.sessions} sessions (${pct(l.rate)})</span>
+                        </div>
+                        <div class="funnel-bar"><span style="width:${pct(l.rate)}"></span></div>
+                    </div>`).join('');
+            }
+            list.innerHTML = html;
+        } catch (err) {
+            list.innerHTML = `<p class="empty-state">Error: ${err.message}</p>`;
+        }
+    }
+
+    async function loadSessions(query) {
+        const list = document.getElementById('analytics-sessions');
+        try {
+            const data = await api('GET', `/admin/analytics/sessions?${query}`);
+            if (data.games.length === 0) {
+                list.innerHTML = '<p class="empty-state">No finished sessions in this period.</p>';
+                return;
+            }
+            list.innerHTML = data.games.map(g => `
+                <div class="content-item">
+                    <div class="item-header">
+                        <div><strong>${escHtml(g.gameId)}</strong></div>
+                        <span class="item-meta">${g.sessions} sessions</span>
+                    </div>
+                    <div class="item-meta">Average: ${fmtDuration(g.avgDurationMs)} | Median: ${fmtDuration(g.medianDurationMs)}</div>
+                </div>`).join('');
+        } catch (err) {
+            list.innerHTML = `<p class="empty-state">Error: ${err.message}</p>`;
+        }
+    }
+
+    async function loadDeaths(query) {
+        const container = document.getElementById('analytics-deaths');
+        try {
+            const data = await api('GET', `/admin/analytics/deaths?${query}`);
+            if (data.cells.length === 0) {
+                container.innerHTML = '<p class="empty-state">No deaths recorded for this game.</p>';
+                return;
+            }
+            container.innerHTML = `<p class="item-meta">${data.totalDeaths} deaths, ${data.cell}px cells</p>
+                <canvas id="heatmap-canvas" class="heatmap-canvas" width="800" height="450"></canvas>`;
+            drawHeatmap(document.getElementById('heatmap-canvas'), data);
+        } catch (err) {
+            container.innerHTML = `<p class="empty-state">Error: ${err.message}</p>`;
+        }
+    }
+
+    function drawHeatmap(canvas, data) {
+        const ctx = canvas.getContext('2d');
+        const xs = data.cells.map(c => c.x);
+        const ys = data.cells.map(c => c.y);
+        const minX = Math.min(...xs), maxX = Math.max(...xs) + data.cell;
+        const minY = Math.min(...ys), maxY = Math.max(...ys) + data.cell;
+        const scale = Math.min(canvas.width / (maxX - minX), canvas.height / (maxY - minY));
+        const maxCount = Math.max(...data.cells.map(c => c.count));
+
+        ctx.clearRect(0, 0, canvas.width, canvas.height);
+        data.cells.forEach(c => {
+            const heat = c.count / maxCount;
+            ctx.fillStyle = `rgba(239, 83, 80, ${0.15 + heat * 0.85})`;
+            // World y points up; canvas y points down.
+            ctx.fillRect(
+                (c.x - minX) * scale,
+                (maxY - c.y - data.cell) * scale,
+                data.cell * scale,
+                data.cell * scale
+            );
+        });
+    }
+
+    function pct(rate) {
+        return `${Math.round(rate * 100)}%`;
+    }
+
+    function fmtDuration(ms) {
+        const secs = Math.round(ms / 1000);
+        return secs >= 60 ? `${Math.floor(secs / 60)}m ${secs % 60}s` : `${secs}s`;
+    }
+
+    document.getElementById('btn-refresh-analytics').addEventListener('click', loadAnalytics);
+    document.getElementById('analytics-days').addEventListener('change', loadAnalytics);
+    document.getElementById('analytics-game').addEventListener('keydown', (e) => {
+        if (e.key === 'Enter') loadAnalytics();
+    });
+
     // =========================================
     // Audit Log
     // =========================================
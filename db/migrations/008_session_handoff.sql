@@ -0,0 +1,26 @@
+-- Migration 008: Session Handoff
+-- ==============================
+-- Parks an in-progress run server-side behind a short code so a player
+-- can resume it on another device (e.g. classroom PC -> home tablet).
+-- A code is single-use: claiming it restores the snapshot and
+-- invalidates the parked original.
+
+CREATE TABLE IF NOT EXISTS session_handoffs (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id   TEXT NOT NULL DEFAULT 'stem_default',
+    player_id   UUID NOT NULL,
+    code        TEXT NOT NULL,
+    game_id     TEXT NOT NULL,
+    snapshot    JSONB NOT NULL DEFAULT '{}',
+    status      TEXT NOT NULL DEFAULT 'parked',     -- parked, claimed, superseded, expired
+    source_device TEXT,
+    target_device TEXT,
+    expires_at  TIMESTAMPTZ NOT NULL,
+    claimed_at  TIMESTAMPTZ,
+    created_at  TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_session_handoffs_code
+    ON session_handoffs(tenant_id, code) WHERE status = 'parked';
+CREATE INDEX IF NOT EXISTS idx_session_handoffs_player
+    ON session_handoffs(tenant_id, player_id, status);
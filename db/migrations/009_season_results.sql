@@ -0,0 +1,54 @@
+-- Migration 009: Season Results
+-- =============================
+-- Final standings captured when a season is closed by the rotation worker,
+-- plus the rewards that were paid out for each placement.
+
+ALTER TABLE seasons ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
+
+-- Match results upsert into leaderboard_entries by season, which is NULL
+-- while no season is active. Treat NULLs as equal in its unique key, so
+-- those upserts hit one row per player instead of adding a row each time.
+-- Duplicates already there keep the row with the most matches.
+DELETE FROM leaderboard_entries le
+USING leaderboard_entries other
+WHERE le.season_id IS NULL AND other.season_id IS NULL
+    AND le.tenant_id = other.tenant_id AND le.player_id = other.player_id
+    AND le.game_id = other.game_id AND le.region = other.region
+    AND (le.matches_played, le.updated_at, le.id) < (other.matches_played, other.updated_at, other.id);
+
+DO $$
+DECLARE
+    c TEXT;
+BEGIN
+    FOR c IN SELECT conname FROM pg_constraint
+        WHERE conrelid = 'leaderboard_entries'::regclass AND contype = 'u'
+    LOOP
+        EXECUTE format('ALTER TABLE leaderboard_entries DROP CONSTRAINT %I', c);
+    END LOOP;
+END $$;
+
+ALTER TABLE leaderboard_entries ADD CONSTRAINT leaderboard_entries_season_key
+    UNIQUE NULLS NOT DISTINCT (tenant_id, player_id, game_id, season_id, region);
+
+CREATE TABLE IF NOT EXISTS season_results (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    season_id       INT NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+    player_id       UUID NOT NULL,
+    game_id         TEXT NOT NULL,
+    region          TEXT NOT NULL DEFAULT 'global',
+    final_rank      INT NOT NULL,
+    score           BIGINT NOT NULL DEFAULT 0,
+    skill_rating    INT NOT NULL DEFAULT 1000,
+    wins            INT NOT NULL DEFAULT 0,
+    matches_played  INT NOT NULL DEFAULT 0,
+    reward_currency TEXT,
+    reward_amount   BIGINT NOT NULL DEFAULT 0,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(tenant_id, season_id, player_id, game_id, region)
+);
+
+CREATE INDEX IF NOT EXISTS idx_season_results_ranking
+    ON season_results(tenant_id, season_id, game_id, region, final_rank);
+CREATE INDEX IF NOT EXISTS idx_season_results_player
+    ON season_results(tenant_id, player_id);
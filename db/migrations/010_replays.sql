@@ -0,0 +1,22 @@
+-- Migration 010: Replays
+-- ======================
+-- Stores a player's personal-best run per game as a compact list of
+-- sampled frames. Used by the engine's ghost-race mode to replay a
+-- friend's or the player's own best run alongside the live player.
+
+CREATE TABLE IF NOT EXISTS replays (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    game_id         TEXT NOT NULL,
+    score           BIGINT NOT NULL DEFAULT 0,
+    duration_ms     INT NOT NULL DEFAULT 0,
+    frames          JSONB NOT NULL DEFAULT '[]',
+    engine_version  TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(tenant_id, player_id, game_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_replays_game_score
+    ON replays(tenant_id, game_id, score DESC);
@@ -0,0 +1,28 @@
+-- Migration 011: Gifts & Trades
+-- =============================
+-- Two-party trade offers between friends. Each side can put store items
+-- and currency on the table; a counter-offer is a new offer linked to the
+-- one it replaces. Gifts and settled trades are recorded in
+-- economy_transactions (currency_type = 'item' for inventory moves).
+
+CREATE TABLE IF NOT EXISTS trade_offers (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id           TEXT NOT NULL DEFAULT 'stem_default',
+    from_player_id      UUID NOT NULL,
+    to_player_id        UUID NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'pending',  -- pending, countered, accepted, cancelled
+    offer_items         JSONB NOT NULL DEFAULT '[]',      -- [{ "itemId": "...", "quantity": 1 }]
+    offer_currency      JSONB NOT NULL DEFAULT '{}',      -- { "coins": 100 }
+    request_items       JSONB NOT NULL DEFAULT '[]',
+    request_currency    JSONB NOT NULL DEFAULT '{}',
+    message             TEXT,
+    parent_offer_id     UUID REFERENCES trade_offers(id) ON DELETE SET NULL,
+    cancelled_by        UUID,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at         TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_offers_to
+    ON trade_offers(tenant_id, to_player_id, status);
+CREATE INDEX IF NOT EXISTS idx_trade_offers_from
+    ON trade_offers(tenant_id, from_player_id, status);
@@ -0,0 +1,23 @@
+-- Migration 012: Telemetry
+-- ========================
+-- Gameplay events batched up by the engine's analytics module
+-- (game_start, game_end, death, level_complete, powerup_used).
+-- `session_id` groups the events of a single run; `occurred_at` is the
+-- client clock, `received_at` the server's.
+
+CREATE TABLE IF NOT EXISTS telemetry_events (
+    id              BIGSERIAL PRIMARY KEY,
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID,
+    event_type      TEXT NOT NULL,
+    game_id         TEXT NOT NULL,
+    session_id      TEXT NOT NULL,
+    occurred_at     TIMESTAMPTZ NOT NULL,
+    data            JSONB NOT NULL DEFAULT '{}',
+    received_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_game_type
+    ON telemetry_events(tenant_id, game_id, event_type, occurred_at DESC);
+CREATE INDEX IF NOT EXISTS idx_telemetry_session
+    ON telemetry_events(tenant_id, session_id);
@@ -0,0 +1,88 @@
+-- Migration 013: Telemetry Partitions
+-- ===================================
+-- Partitions telemetry_events by tenant so one tenant's event volume never
+-- bloats another's indexes, and a tenant's events can be dropped or
+-- archived as a whole. Every tenant gets its own LIST partition (created
+-- automatically when the tenant is added); events for tenants without one
+-- land in telemetry_events_default.
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_partitioned_table pt
+        JOIN pg_class c ON c.oid = pt.partrelid
+        WHERE c.relname = 'telemetry_events'
+    ) THEN
+        ALTER TABLE telemetry_events RENAME TO telemetry_events_unpartitioned;
+        ALTER INDEX IF EXISTS idx_telemetry_game_type RENAME TO idx_telemetry_game_type_old;
+        ALTER INDEX IF EXISTS idx_telemetry_session RENAME TO idx_telemetry_session_old;
+
+        CREATE TABLE telemetry_events (
+            id              BIGSERIAL,
+            tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+            player_id       UUID,
+            event_type      TEXT NOT NULL,
+            game_id         TEXT NOT NULL,
+            session_id      TEXT NOT NULL,
+            occurred_at     TIMESTAMPTZ NOT NULL,
+            data            JSONB NOT NULL DEFAULT '{}',
+            received_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (tenant_id, id)
+        ) PARTITION BY LIST (tenant_id);
+
+        CREATE TABLE telemetry_events_default PARTITION OF telemetry_events DEFAULT;
+
+        INSERT INTO telemetry_events
+            (tenant_id, player_id, event_type, game_id, session_id, occurred_at, data, received_at)
+        SELECT tenant_id, player_id, event_type, game_id, session_id, occurred_at, data, received_at
+        FROM telemetry_events_unpartitioned;
+
+        DROP TABLE telemetry_events_unpartitioned;
+    END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_game_type
+    ON telemetry_events(tenant_id, game_id, event_type, occurred_at DESC);
+CREATE INDEX IF NOT EXISTS idx_telemetry_session
+    ON telemetry_events(tenant_id, session_id);
+
+-- Creates the partition for one tenant, moving any of its events out of
+-- the default partition first.
+CREATE OR REPLACE FUNCTION create_telemetry_partition(p_tenant TEXT)
+RETURNS VOID AS $$
+DECLARE
+    part_name TEXT := 'telemetry_events_'
+        || left(regexp_replace(lower(p_tenant), '[^a-z0-9_]', '_', 'g'), 40)
+        || '_' || left(md5(p_tenant), 8);
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_class WHERE relname = part_name) THEN
+        RETURN;
+    END IF;
+
+    EXECUTE format('CREATE TABLE %I (LIKE telemetry_events INCLUDING DEFAULTS)', part_name);
+    EXECUTE format(
+        'WITH moved AS (DELETE FROM telemetry_events_default WHERE tenant_id = %L RETURNING *)
+         INSERT INTO %I SELECT * FROM moved',
+        p_tenant, part_name
+    );
+    EXECUTE format(
+        'ALTER TABLE telemetry_events ATTACH PARTITION %I FOR VALUES IN (%L)',
+        part_name, p_tenant
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT create_telemetry_partition(id) FROM tenants;
+
+CREATE OR REPLACE FUNCTION tenants_create_telemetry_partition()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM create_telemetry_partition(NEW.id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_tenants_telemetry_partition ON tenants;
+CREATE TRIGGER trg_tenants_telemetry_partition
+    AFTER INSERT ON tenants
+    FOR EACH ROW EXECUTE FUNCTION tenants_create_telemetry_partition();
@@ -0,0 +1,41 @@
+-- Migration 014: Reward Crates
+-- ============================
+-- Store items with item_type = 'crate' are opened server-side for one item
+-- from their drop table. Each drop's chance is weight / total weight of
+-- the crate's table; the odds are published at GET /economy/crates.
+--
+-- Every opening records its random seed, the roll derived from it and a
+-- snapshot of the drop table at the time, so any result can be re-checked
+-- later even if the table has since been changed.
+
+CREATE TABLE IF NOT EXISTS crate_drops (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    crate_id        TEXT NOT NULL REFERENCES store_items(id) ON DELETE CASCADE,
+    item_id         TEXT NOT NULL REFERENCES store_items(id),
+    quantity        INT NOT NULL DEFAULT 1,
+    weight          INT NOT NULL,
+    rarity          TEXT NOT NULL DEFAULT 'common',  -- common, uncommon, rare, epic, legendary
+    UNIQUE(tenant_id, crate_id, item_id),
+    CONSTRAINT positive_weight CHECK (weight > 0),
+    CONSTRAINT positive_quantity CHECK (quantity > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_crate_drops_crate ON crate_drops(tenant_id, crate_id);
+
+CREATE TABLE IF NOT EXISTS crate_openings (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    crate_id        TEXT NOT NULL,
+    seed            TEXT NOT NULL,      -- hex; roll = first 8 bytes of SHA-256(seed) mod total_weight
+    roll            BIGINT NOT NULL,
+    total_weight    BIGINT NOT NULL,
+    drop_table      JSONB NOT NULL,     -- drops in roll order: [{ "itemId", "quantity", "weight", "rarity" }]
+    item_id         TEXT NOT NULL,
+    quantity        INT NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_crate_openings_player
+    ON crate_openings(tenant_id, player_id, created_at DESC);
@@ -0,0 +1,16 @@
+-- Migration 015: Game Configs
+-- ===========================
+-- Server-driven tuning parameters per game (spawn intervals, gravity,
+-- scoring weights, ...), fetched by the engine at game start so games can
+-- be rebalanced without shipping a new WASM build. `params` is a flat JSON
+-- object; keys a game doesn't know are ignored and missing keys fall back
+-- to the game's built-in defaults.
+
+CREATE TABLE IF NOT EXISTS game_configs (
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    game_id         TEXT NOT NULL,
+    params          JSONB NOT NULL DEFAULT '{}',
+    version         INT NOT NULL DEFAULT 1,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, game_id)
+);
@@ -0,0 +1,40 @@
+-- Migration 016: Game Config Profiles
+-- ===================================
+-- Versioned tuning profiles replace the single row per game from 015.
+-- Each save creates a numbered version that starts as a draft; publishing
+-- it with a rollout percentage serves it to that share of players. A
+-- player's share is a stable bucket (0-99) derived from their id and the
+-- game, so raising the percentage only adds players and an A/B split
+-- stays put between sessions. Each player gets the newest published
+-- profile whose rollout covers their bucket.
+
+CREATE TABLE IF NOT EXISTS game_config_profiles (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id           TEXT NOT NULL DEFAULT 'stem_default',
+    game_id             TEXT NOT NULL,
+    version             INT NOT NULL,
+    name                TEXT NOT NULL,
+    params              JSONB NOT NULL DEFAULT '{}',
+    notes               TEXT,
+    status              TEXT NOT NULL DEFAULT 'draft',   -- draft, published, archived
+    rollout_percent     INT NOT NULL DEFAULT 100,
+    created_by          UUID,
+    published_by        UUID,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at        TIMESTAMPTZ,
+    UNIQUE(tenant_id, game_id, version),
+    CONSTRAINT rollout_range CHECK (rollout_percent BETWEEN 0 AND 100)
+);
+
+CREATE INDEX IF NOT EXISTS idx_config_profiles_published
+    ON game_config_profiles(tenant_id, game_id, version DESC) WHERE status = 'published';
+
+-- Carry over existing configs as fully rolled-out profiles.
+INSERT INTO game_config_profiles
+    (tenant_id, game_id, version, name, params, status, rollout_percent, created_at, updated_at, published_at)
+SELECT tenant_id, game_id, version, 'Imported', params, 'published', 100, updated_at, updated_at, updated_at
+FROM game_configs
+ON CONFLICT (tenant_id, game_id, version) DO NOTHING;
+
+DROP TABLE IF EXISTS game_configs;
@@ -0,0 +1,23 @@
+-- Migration 017: Idempotency Keys
+-- ===============================
+-- Response snapshots for mutating requests sent with an `Idempotency-Key`
+-- header (score submission, purchases, battle-pass claims, billing). A
+-- retry with the same key gets the stored response instead of running the
+-- request again.
+--
+-- `status_code` is NULL while the first request is still in flight. Keys
+-- are scoped to the player and kept for 24 hours.
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash    TEXT NOT NULL,      -- hex SHA-256 of method, path and body
+    status_code     INT,
+    response_body   BYTEA,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at    TIMESTAMPTZ,
+    PRIMARY KEY (tenant_id, player_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created ON idempotency_keys(created_at);
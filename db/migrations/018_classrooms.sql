@@ -0,0 +1,48 @@
+-- Migration 018: Classrooms & Assignments
+-- =======================================
+-- Teachers (organisation members with role owner, admin or teacher) group
+-- students of their organisation into classrooms and set assignments:
+-- reach `target_score` in `game_id` by `due_at`.
+--
+-- Completion isn't stored: it is derived from score_history, counting
+-- scores submitted after the assignment was created. A student completes
+-- an assignment with their first score at or above the target; completing
+-- after `due_at` is reported as late.
+
+CREATE TABLE IF NOT EXISTS classrooms (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    organisation_id VARCHAR(64) NOT NULL REFERENCES organisations(id) ON DELETE CASCADE,
+    name            TEXT NOT NULL,
+    teacher_id      UUID NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_classrooms_org ON classrooms(tenant_id, organisation_id);
+
+CREATE TABLE IF NOT EXISTS classroom_students (
+    classroom_id    UUID NOT NULL REFERENCES classrooms(id) ON DELETE CASCADE,
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    added_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (classroom_id, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_classroom_students_player
+    ON classroom_students(tenant_id, player_id);
+
+CREATE TABLE IF NOT EXISTS assignments (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    classroom_id    UUID NOT NULL REFERENCES classrooms(id) ON DELETE CASCADE,
+    game_id         TEXT NOT NULL,
+    title           TEXT NOT NULL,
+    target_score    BIGINT NOT NULL,
+    due_at          TIMESTAMPTZ NOT NULL,
+    created_by      UUID NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT positive_target CHECK (target_score > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_assignments_classroom
+    ON assignments(classroom_id, due_at);
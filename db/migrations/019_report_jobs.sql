@@ -0,0 +1,24 @@
+-- Migration 019: Classroom Report Exports
+-- =======================================
+-- Asynchronous CSV/PDF exports of classroom progress reports. A job row is
+-- created by POST /organisations/:id/reports and filled in by a background
+-- task, mirroring gdpr_requests: the rendered file is stored as a data URL
+-- and expires after 7 days.
+
+CREATE TABLE IF NOT EXISTS report_jobs (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    organisation_id VARCHAR(64) NOT NULL REFERENCES organisations(id) ON DELETE CASCADE,
+    classroom_id    UUID NOT NULL REFERENCES classrooms(id) ON DELETE CASCADE,
+    requested_by    UUID NOT NULL,
+    format          TEXT NOT NULL,    -- csv, pdf
+    status          TEXT NOT NULL DEFAULT 'pending',  -- pending, processing, completed, failed
+    download_url    TEXT,
+    error           TEXT,
+    completed_at    TIMESTAMPTZ,
+    expires_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT valid_format CHECK (format IN ('csv', 'pdf'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_jobs_requester ON report_jobs(tenant_id, requested_by);
@@ -0,0 +1,27 @@
+-- Migration 020: Chat
+-- ===================
+-- Direct messages between friends and room chat during multiplayer.
+--
+-- `channel` is `dm:<lower player id>:<higher player id>` for direct
+-- messages and `room:<room id>` for room chat, so both kinds page through
+-- the same index. Messages caught by the profanity filter are stored as
+-- `held` and reported to the moderation queue (content_reports,
+-- content_type 'chat_message') instead of being delivered.
+
+CREATE TABLE IF NOT EXISTS chat_messages (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    channel         TEXT NOT NULL,
+    sender_id       UUID NOT NULL,
+    recipient_id    UUID,             -- direct messages only
+    body            TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'visible',  -- visible, held, removed
+    report_count    INTEGER NOT NULL DEFAULT 0,
+    read_at         TIMESTAMPTZ,      -- direct messages only
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_channel
+    ON chat_messages(tenant_id, channel, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_chat_unread
+    ON chat_messages(tenant_id, recipient_id) WHERE read_at IS NULL;
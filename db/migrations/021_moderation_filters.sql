@@ -0,0 +1,22 @@
+-- Migration 021: Moderation Filters
+-- =================================
+-- Per-tenant blocklists for the content filter that screens comments,
+-- reviews and chat before they're published.
+--
+-- Terms are stored normalised (lowercase, leetspeak folded). A tenant's
+-- entry overrides the severity of a built-in term with the same text, or
+-- exempts it with severity 'allow'. Severity decides what happens to
+-- matching content:
+--   low    - published, and reported to the moderation queue
+--   medium - held for a moderator (comments/reviews 'pending', chat 'held')
+--   high   - rejected outright
+
+CREATE TABLE IF NOT EXISTS moderation_terms (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    term            TEXT NOT NULL,
+    severity        TEXT NOT NULL CHECK (severity IN ('low', 'medium', 'high', 'allow')),
+    created_by      UUID,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, term)
+);
@@ -0,0 +1,17 @@
+-- Migration 022: GDPR Export Worker
+-- =================================
+-- Export requests are fulfilled by a background worker. The archive is a
+-- ZIP uploaded to object storage under `storage_key`; the status endpoint
+-- hands out short-lived presigned URLs for it until `expires_at`, when the
+-- object is deleted and the request marked 'expired'.
+--
+-- status: pending, processing, completed, failed, expired
+
+ALTER TABLE gdpr_requests ADD COLUMN IF NOT EXISTS started_at TIMESTAMPTZ;
+ALTER TABLE gdpr_requests ADD COLUMN IF NOT EXISTS storage_key TEXT;
+ALTER TABLE gdpr_requests ADD COLUMN IF NOT EXISTS size_bytes BIGINT;
+ALTER TABLE gdpr_requests ADD COLUMN IF NOT EXISTS error TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_gdpr_queue
+    ON gdpr_requests(request_type, created_at)
+    WHERE status IN ('pending', 'processing');
@@ -0,0 +1,26 @@
+-- Migration 023: Account Deletion
+-- ===============================
+-- Deletion requests are executed by the GDPR worker once the grace period
+-- (GDPR_DELETION_GRACE_DAYS, default 30) has passed. Personal data is
+-- deleted; the player row is kept as an anonymous tombstone so comments,
+-- reviews and match history other players took part in stay consistent.
+--
+-- Each execution writes one audit row with per-table counts. The audit
+-- holds no personal data beyond the (now anonymous) player id.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS gdpr_deletion_audit (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    request_id              UUID NOT NULL REFERENCES gdpr_requests(id),
+    tenant_id               TEXT NOT NULL,
+    player_id               UUID NOT NULL,
+    rows_affected           JSONB NOT NULL DEFAULT '{}',   -- table -> count
+    subscriptions_canceled  TEXT[] NOT NULL DEFAULT '{}',  -- Stripe subscription ids
+    exports_deleted         INT NOT NULL DEFAULT 0,
+    requested_at            TIMESTAMPTZ NOT NULL,
+    completed_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deletion_audit_tenant
+    ON gdpr_deletion_audit(tenant_id, completed_at DESC);
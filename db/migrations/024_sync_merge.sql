@@ -0,0 +1,8 @@
+-- Migration 024: Batch Sync Merge Policy
+-- ======================================
+-- Profile fields synced from offline clients are last-write-wins on the
+-- client timestamp. This records when the profile was last written, by
+-- either the profile endpoint or a sync, so older offline edits lose.
+-- Settings use the existing player_settings.updated_at.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS profile_updated_at TIMESTAMPTZ;
@@ -0,0 +1,27 @@
+-- Migration 025: Glicko-2 Ratings
+-- ===============================
+-- Ranked match results update each player's Glicko-2 rating on their
+-- global leaderboard entry. skill_rating stays the displayed rating; the
+-- deviation and volatility it needs are stored alongside. rated_at drives
+-- inactivity decay: every idle rating period widens the deviation.
+--
+-- game_rating_configs overrides the server defaults (RATING_* env vars)
+-- per game. NULL columns use the default.
+
+ALTER TABLE leaderboard_entries
+    ADD COLUMN IF NOT EXISTS rating_deviation DOUBLE PRECISION NOT NULL DEFAULT 350,
+    ADD COLUMN IF NOT EXISTS rating_volatility DOUBLE PRECISION NOT NULL DEFAULT 0.06,
+    ADD COLUMN IF NOT EXISTS rated_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS game_rating_configs (
+    tenant_id           TEXT NOT NULL,
+    game_id             TEXT NOT NULL,
+    initial_rating      INT,
+    initial_deviation   DOUBLE PRECISION,
+    initial_volatility  DOUBLE PRECISION,
+    tau                 DOUBLE PRECISION,
+    rating_period_days  DOUBLE PRECISION,
+    updated_by          UUID,
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, game_id)
+);
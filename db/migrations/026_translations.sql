@@ -0,0 +1,23 @@
+-- Migration 026: Translations
+-- ===========================
+-- Per-tenant translations of API response text, served in the request
+-- locale (?locale= or Accept-Language). Namespaces:
+--
+--   error             key = the English error message
+--   achievement       key = {achievement id}.name / .description
+--   store_item        key = {item id}.name / .description
+--   battle_pass_tier  key = {battle pass id}.{tier}, or {tier} for any pass
+--
+-- Locales are stored canonically (pt-BR). A request for pt-BR falls back
+-- to pt rows, then to the stored text.
+
+CREATE TABLE IF NOT EXISTS translations (
+    tenant_id   TEXT NOT NULL,
+    locale      TEXT NOT NULL,
+    namespace   TEXT NOT NULL CHECK (namespace IN ('error', 'achievement', 'store_item', 'battle_pass_tier')),
+    key         TEXT NOT NULL,
+    value       TEXT NOT NULL,
+    updated_by  UUID,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, locale, namespace, key)
+);
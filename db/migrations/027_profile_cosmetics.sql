@@ -0,0 +1,15 @@
+-- Migration 027: Profile Cosmetics
+-- ================================
+-- Players equip cosmetics they own into three profile slots. Each slot
+-- takes store items of one item_type:
+--
+--   equipped_avatar_frame  avatar_frame
+--   equipped_title         title
+--   equipped_banner        banner
+--
+-- Ownership is checked against player_inventory when equipping. Equipped
+-- ids are returned with the player in leaderboards and friends lists.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS equipped_avatar_frame TEXT REFERENCES store_items(id) ON DELETE SET NULL;
+ALTER TABLE players ADD COLUMN IF NOT EXISTS equipped_title        TEXT REFERENCES store_items(id) ON DELETE SET NULL;
+ALTER TABLE players ADD COLUMN IF NOT EXISTS equipped_banner       TEXT REFERENCES store_items(id) ON DELETE SET NULL;
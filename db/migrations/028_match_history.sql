@@ -0,0 +1,14 @@
+-- Migration 028: Match History
+-- ============================
+-- submit_match now records every multiplayer match with each player's
+-- result, so players can browse their history and rematch from it.
+-- room_id links a match to the in-memory room it was played in; the
+-- rematch endpoint looks up the room's last match to find who to invite.
+
+ALTER TABLE multiplayer_matches ADD COLUMN IF NOT EXISTS room_id TEXT;
+
+ALTER TABLE multiplayer_match_players ALTER COLUMN score TYPE BIGINT;
+ALTER TABLE multiplayer_match_players ADD COLUMN IF NOT EXISTS skill_rating  INT;
+ALTER TABLE multiplayer_match_players ADD COLUMN IF NOT EXISTS rating_change INT;
+
+CREATE INDEX IF NOT EXISTS idx_mp_matches_room ON multiplayer_matches(tenant_id, room_id);
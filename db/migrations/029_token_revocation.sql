@@ -0,0 +1,19 @@
+-- Migration 029: Token Revocation
+-- ===============================
+-- Refresh tokens carry an id (jti) and are single use: refreshing or
+-- logging out lists the token here until it would have expired anyway.
+-- players.tokens_revoked_at invalidates every token issued before it
+-- ("log out everywhere", bans, account deletion); access tokens are also
+-- checked against a Redis marker set at the same time.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS tokens_revoked_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti         TEXT PRIMARY KEY,
+    tenant_id   TEXT NOT NULL,
+    player_id   UUID NOT NULL,
+    expires_at  TIMESTAMPTZ NOT NULL,
+    revoked_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires ON revoked_tokens(expires_at);
@@ -0,0 +1,20 @@
+-- Migration 030: Auth Sessions
+-- ============================
+-- One row per signed-in device. Both tokens of a sign-in carry the
+-- session id; refreshing keeps the session and updates its device, IP
+-- and last seen time. Revoking a session stops its refresh token here and
+-- its access token through a Redis marker.
+
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    id            UUID PRIMARY KEY,
+    tenant_id     TEXT NOT NULL,
+    player_id     UUID NOT NULL,
+    device        TEXT,
+    ip            TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at    TIMESTAMPTZ NOT NULL,
+    revoked_at    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_auth_sessions_player ON auth_sessions(tenant_id, player_id);
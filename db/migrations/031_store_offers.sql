@@ -0,0 +1,41 @@
+-- Migration 031: Store Offers
+-- ===========================
+-- Bundles, sales, purchase limits and the featured rotation.
+--
+-- Store items with item_type = 'bundle' grant their bundle_contents when
+-- bought instead of going into the inventory themselves. A sale takes a
+-- percentage off one item (or every item when item_id is NULL) between
+-- starts_at and ends_at; the biggest running discount applies. Prices are
+-- always computed on the server. Purchase limits count the player's
+-- 'spend' transactions from the store for the item.
+
+ALTER TABLE store_items ADD COLUMN IF NOT EXISTS purchase_limit  INT;
+ALTER TABLE store_items ADD COLUMN IF NOT EXISTS featured        BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE store_items ADD COLUMN IF NOT EXISTS available_from  TIMESTAMPTZ;
+ALTER TABLE store_items ADD COLUMN IF NOT EXISTS available_until TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS bundle_contents (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    bundle_id       TEXT NOT NULL REFERENCES store_items(id) ON DELETE CASCADE,
+    item_id         TEXT NOT NULL REFERENCES store_items(id),
+    quantity        INT NOT NULL DEFAULT 1,
+    UNIQUE(tenant_id, bundle_id, item_id),
+    CONSTRAINT positive_quantity CHECK (quantity > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bundle_contents_bundle ON bundle_contents(tenant_id, bundle_id);
+
+CREATE TABLE IF NOT EXISTS store_sales (
+    id               UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id        TEXT NOT NULL DEFAULT 'stem_default',
+    item_id          TEXT REFERENCES store_items(id) ON DELETE CASCADE,  -- NULL: every item
+    discount_percent INT NOT NULL,
+    starts_at        TIMESTAMPTZ NOT NULL,
+    ends_at          TIMESTAMPTZ NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT discount_range CHECK (discount_percent BETWEEN 1 AND 90),
+    CONSTRAINT sale_window CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_store_sales_window ON store_sales(tenant_id, ends_at);
@@ -0,0 +1,45 @@
+-- Migration 032: Auctions
+-- =======================
+-- Players list tradeable items for a minimum bid and a duration. Listing
+-- moves the item out of the seller's inventory, and the highest bid is
+-- taken from the bidder's wallet, both held until settlement; an outbid
+-- player is refunded at once. A bid in the last minute pushes ends_at back
+-- (anti-sniping). A worker settles auctions past ends_at: the item goes to
+-- the highest bidder and the bid to the seller, or the item back to the
+-- seller if nobody bid. Every move is logged in economy_transactions with
+-- source 'auction' and the auction id as reference_id.
+
+CREATE TABLE IF NOT EXISTS auctions (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    seller_id       UUID NOT NULL,
+    item_id         TEXT NOT NULL REFERENCES store_items(id),
+    quantity        INT NOT NULL DEFAULT 1,
+    currency_type   TEXT NOT NULL DEFAULT 'coins',
+    min_bid         BIGINT NOT NULL,
+    current_bid     BIGINT,
+    high_bidder_id  UUID,
+    bid_count       INT NOT NULL DEFAULT 0,
+    status          TEXT NOT NULL DEFAULT 'open',   -- open, sold, expired, cancelled
+    ends_at         TIMESTAMPTZ NOT NULL,
+    extensions      INT NOT NULL DEFAULT 0,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at      TIMESTAMPTZ,
+    CONSTRAINT positive_quantity CHECK (quantity > 0),
+    CONSTRAINT positive_min_bid CHECK (min_bid > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_auctions_open ON auctions(tenant_id, status, ends_at);
+CREATE INDEX IF NOT EXISTS idx_auctions_seller ON auctions(tenant_id, seller_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS auction_bids (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    auction_id      UUID NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+    bidder_id       UUID NOT NULL,
+    amount          BIGINT NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_auction_bids_auction ON auction_bids(auction_id, amount DESC);
+CREATE INDEX IF NOT EXISTS idx_auction_bids_bidder ON auction_bids(tenant_id, bidder_id);
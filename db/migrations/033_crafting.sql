@@ -0,0 +1,64 @@
+-- Migration 033: Crafting
+-- =======================
+-- Recipes consume input items (and optionally currency) to produce an
+-- output item. A recipe with success_percent below 100 can fail; the
+-- inputs and cost are consumed either way. As with crate openings, every
+-- attempt stores the seed and roll it was decided by.
+--
+-- Hidden recipes are only listed to, and crafted by, players who have
+-- discovered them by holding all of the inputs at once.
+
+CREATE TABLE IF NOT EXISTS crafting_recipes (
+    id              TEXT PRIMARY KEY,
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    name            TEXT NOT NULL,
+    description     TEXT,
+    output_item_id  TEXT NOT NULL REFERENCES store_items(id),
+    output_quantity INT NOT NULL DEFAULT 1,
+    currency_type   TEXT NOT NULL DEFAULT 'coins',
+    currency_cost   BIGINT NOT NULL DEFAULT 0,
+    success_percent INT NOT NULL DEFAULT 100,
+    hidden          BOOLEAN NOT NULL DEFAULT FALSE,
+    is_active       BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT positive_output CHECK (output_quantity > 0),
+    CONSTRAINT non_negative_cost CHECK (currency_cost >= 0),
+    CONSTRAINT success_range CHECK (success_percent BETWEEN 1 AND 100)
+);
+
+CREATE TABLE IF NOT EXISTS recipe_inputs (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    recipe_id       TEXT NOT NULL REFERENCES crafting_recipes(id) ON DELETE CASCADE,
+    item_id         TEXT NOT NULL REFERENCES store_items(id),
+    quantity        INT NOT NULL DEFAULT 1,
+    UNIQUE(tenant_id, recipe_id, item_id),
+    CONSTRAINT positive_quantity CHECK (quantity > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_inputs_recipe ON recipe_inputs(tenant_id, recipe_id);
+
+CREATE TABLE IF NOT EXISTS player_recipes (
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    recipe_id       TEXT NOT NULL REFERENCES crafting_recipes(id) ON DELETE CASCADE,
+    discovered_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    crafted_count   INT NOT NULL DEFAULT 0,
+    failed_count    INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (tenant_id, player_id, recipe_id)
+);
+
+CREATE TABLE IF NOT EXISTS crafting_attempts (
+    id              UUID PRIMARY KEY,
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    player_id       UUID NOT NULL,
+    recipe_id       TEXT NOT NULL,
+    seed            TEXT NOT NULL,      -- hex; roll = first 8 bytes of SHA-256(seed) mod 100
+    roll            BIGINT NOT NULL,
+    success_percent INT NOT NULL,       -- succeeded if roll < success_percent
+    success         BOOLEAN NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_crafting_attempts_player
+    ON crafting_attempts(tenant_id, player_id, created_at DESC);
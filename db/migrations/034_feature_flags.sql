@@ -0,0 +1,15 @@
+-- Migration 034: Feature Flags
+-- ============================
+-- Per-tenant overrides of the server's feature flags. Built-in flags
+-- (economy, comments, multiplayer) default to on and switch off whole
+-- route groups, so a school tenant can run a stripped-down deployment.
+-- Any other flag is passed to clients as it is.
+
+CREATE TABLE IF NOT EXISTS feature_flags (
+    tenant_id       TEXT NOT NULL,
+    flag            TEXT NOT NULL,
+    enabled         BOOLEAN NOT NULL,
+    updated_by      UUID,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, flag)
+);
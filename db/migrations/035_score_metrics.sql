@@ -0,0 +1,22 @@
+-- Migration 035: Score Metrics
+-- ============================
+-- Per-game stat dimensions beyond the score: best distance, fastest time,
+-- accuracy. Which metrics a game records, and whether higher or lower is
+-- better, is declared on the server (models::game_progress::game_metrics);
+-- this table keeps each player's best value per metric so leaderboards can
+-- rank by any of them. Each submission's raw metrics go to score_history.
+
+CREATE TABLE IF NOT EXISTS game_progress_metrics (
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    game_id         VARCHAR(64) NOT NULL,
+    metric          VARCHAR(32) NOT NULL,
+    best_value      BIGINT NOT NULL,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, player_id, game_id, metric)
+);
+
+CREATE INDEX IF NOT EXISTS idx_progress_metrics_leaderboard
+    ON game_progress_metrics(tenant_id, game_id, metric, best_value);
+
+ALTER TABLE score_history ADD COLUMN IF NOT EXISTS metrics JSONB;
@@ -0,0 +1,24 @@
+-- Migration 036: Moderation Appeals
+-- =================================
+-- Players can appeal a warning or ban they received. Each appeal points at
+-- the moderation_log entry for the action, and a player gets one appeal
+-- per action. A moderator either upholds the action or reverses it;
+-- reversing a ban lifts it and restores the content the ban hid. Both
+-- outcomes are written back to moderation_log.
+
+CREATE TABLE IF NOT EXISTS moderation_appeals (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    action_id       VARCHAR(64) NOT NULL,       -- moderation_log.id
+    message         TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'open',  -- open, upheld, reversed
+    resolved_by     UUID,
+    resolution_note TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at     TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_appeals_action ON moderation_appeals(tenant_id, action_id);
+CREATE INDEX IF NOT EXISTS idx_appeals_queue ON moderation_appeals(tenant_id, status, created_at);
+CREATE INDEX IF NOT EXISTS idx_appeals_player ON moderation_appeals(tenant_id, player_id, created_at DESC);
@@ -0,0 +1,44 @@
+-- Migration 037: Background Jobs
+-- ==============================
+-- A Postgres-backed queue for background work, so it survives restarts
+-- and is shared by every API instance. Workers claim due jobs with
+-- FOR UPDATE SKIP LOCKED and hold them until locked_until (the
+-- visibility timeout); a job whose worker died is claimed again after
+-- that. Failures are retried with backoff until max_attempts.
+--
+-- Recurring jobs are driven by job_schedules: one row per schedule with
+-- the next time it fires. The instance that advances next_run_at is the
+-- one that enqueues the job.
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind            VARCHAR(64) NOT NULL,
+    payload         JSONB NOT NULL DEFAULT '{}',
+    status          TEXT NOT NULL DEFAULT 'queued',  -- queued, running, completed, failed
+    dedupe_key      TEXT,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    max_attempts    INTEGER NOT NULL DEFAULT 5,
+    timeout_secs    INTEGER NOT NULL DEFAULT 300,
+    run_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until    TIMESTAMPTZ,
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_queued ON jobs (run_at) WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS idx_jobs_running ON jobs (locked_until) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_jobs_finished ON jobs (finished_at) WHERE status IN ('completed', 'failed');
+
+-- At most one pending job per dedupe key.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_dedupe
+    ON jobs (dedupe_key) WHERE status IN ('queued', 'running');
+
+CREATE TABLE IF NOT EXISTS job_schedules (
+    name            VARCHAR(64) PRIMARY KEY,
+    kind            VARCHAR(64) NOT NULL,
+    cron            TEXT NOT NULL,
+    next_run_at     TIMESTAMPTZ NOT NULL,
+    last_run_at     TIMESTAMPTZ
+);
@@ -0,0 +1,12 @@
+-- Migration 038: Cache Versions
+-- =============================
+-- A per-tenant counter bumped by every admin write to catalog data
+-- (games, categories, store, crates, recipes, translations, feature
+-- flags). ETags remembered for catalog responses are keyed by it, so an
+-- admin change makes clients download the new version on their next poll.
+
+CREATE TABLE IF NOT EXISTS tenant_cache_versions (
+    tenant_id       VARCHAR(64) PRIMARY KEY,
+    version         BIGINT NOT NULL DEFAULT 0,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+-- Migration 039: Job Trace Context
+-- ================================
+-- The W3C trace context (traceparent, tracestate) of the request or job
+-- that queued a job, so the job's spans join the same trace. Empty when
+-- tracing is off.
+
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS trace_context JSONB;
@@ -0,0 +1,22 @@
+-- Migration 040: Daily Runs
+-- =========================
+-- Each game has a daily challenge: a seed and modifiers derived from the
+-- game and the UTC date, so every player gets the same levels and
+-- obstacles. A player gets one attempt per game per day; the attempt is
+-- kept here and ranked on the day's own leaderboard, apart from the
+-- regular high scores in game_progress.
+
+CREATE TABLE IF NOT EXISTS daily_runs (
+    tenant_id       VARCHAR(64) NOT NULL,
+    game_id         VARCHAR(64) NOT NULL,
+    day             DATE NOT NULL,
+    player_id       UUID NOT NULL,
+    seed            BIGINT NOT NULL,
+    score           BIGINT NOT NULL,
+    level           INT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, game_id, day, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_daily_runs_leaderboard
+    ON daily_runs(tenant_id, game_id, day, score DESC);
@@ -0,0 +1,20 @@
+-- Migration 041: Review Votes
+-- ===========================
+-- Players can mark other players' reviews as helpful, once per review.
+-- Each vote is kept in review_votes, whose key enforces the one vote;
+-- game_reviews.helpful_count caches the total so reviews can be listed
+-- most helpful first.
+
+ALTER TABLE game_reviews ADD COLUMN IF NOT EXISTS helpful_count INT NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS review_votes (
+    review_id       UUID NOT NULL,
+    player_id       UUID NOT NULL,
+    tenant_id       VARCHAR(64) NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (review_id, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_votes_player ON review_votes(player_id, tenant_id);
+CREATE INDEX IF NOT EXISTS idx_reviews_helpful
+    ON game_reviews(tenant_id, game_id, status, helpful_count DESC);
@@ -0,0 +1,17 @@
+-- Migration 042: Comment Reactions
+-- ================================
+-- Emoji reactions on comments. A player can leave each reaction type once
+-- per comment, and several types on the same comment. Counts are
+-- aggregated from this table when comments are listed.
+
+CREATE TABLE IF NOT EXISTS comment_reactions (
+    comment_id      UUID NOT NULL,
+    player_id       UUID NOT NULL,
+    tenant_id       VARCHAR(64) NOT NULL,
+    reaction        VARCHAR(16) NOT NULL,
+    -- reaction: like, love, laugh, wow, sad, celebrate
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (comment_id, player_id, reaction)
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_reactions_player ON comment_reactions(player_id, tenant_id);
@@ -0,0 +1,13 @@
+-- Migration 043: Privacy Settings
+-- ===============================
+-- Per-player privacy preferences, kept on players so the queries that
+-- show one player to another can filter on them directly:
+--   profile_visibility     who finds the player in search: public, friends, private
+--   friend_requests        who may send a friend request: everyone, friends_of_friends, nobody
+--   show_online            whether friends see the player's presence
+--   leaderboard_anonymous  shown as "Anonymous" on public leaderboards
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS profile_visibility TEXT NOT NULL DEFAULT 'public';
+ALTER TABLE players ADD COLUMN IF NOT EXISTS friend_requests TEXT NOT NULL DEFAULT 'everyone';
+ALTER TABLE players ADD COLUMN IF NOT EXISTS show_online BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE players ADD COLUMN IF NOT EXISTS leaderboard_anonymous BOOLEAN NOT NULL DEFAULT FALSE;
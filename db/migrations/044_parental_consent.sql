@@ -0,0 +1,28 @@
+-- Migration 044: Parental Consent
+-- ===============================
+-- COPPA-style age gate. Registration asks for a birth year; players who
+-- may be under 13 start with consent_status 'pending' and can't chat,
+-- post comments or reviews, or make purchases until a parent grants
+-- consent through the link emailed to them.
+--   consent_status: not_required, pending, granted, denied
+-- Each email sent is a parental_consents row; only a hash of its token is
+-- stored, and a newer request supersedes older pending ones.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS birth_year SMALLINT;
+ALTER TABLE players ADD COLUMN IF NOT EXISTS consent_status TEXT NOT NULL DEFAULT 'not_required';
+
+CREATE TABLE IF NOT EXISTS parental_consents (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    parent_email    TEXT NOT NULL,
+    token_hash      TEXT NOT NULL UNIQUE,
+    status          TEXT NOT NULL DEFAULT 'pending',
+    -- status: pending, granted, denied, superseded
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at      TIMESTAMPTZ NOT NULL,
+    responded_at    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_parental_consents_player ON parental_consents(tenant_id, player_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_players_consent ON players(tenant_id, consent_status) WHERE consent_status IN ('pending', 'denied');
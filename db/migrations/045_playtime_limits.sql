@@ -0,0 +1,20 @@
+-- Migration 045: Playtime Limits
+-- ==============================
+-- Daily playtime budgets. A player can set their own limit, and an
+-- organisation can set one for its members; the smaller applies. Time in
+-- game is added up per UTC day from presence heartbeats.
+
+ALTER TABLE players ADD COLUMN IF NOT EXISTS daily_playtime_limit_mins INT;
+ALTER TABLE organisations ADD COLUMN IF NOT EXISTS daily_playtime_limit_mins INT;
+
+CREATE TABLE IF NOT EXISTS playtime_days (
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    day             DATE NOT NULL,
+    seconds         INT NOT NULL DEFAULT 0,
+    -- When time was last added; the next heartbeat adds the time since.
+    last_tick_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, player_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_playtime_days_day ON playtime_days(day);
@@ -0,0 +1,30 @@
+-- Migration 046: Engine Games
+-- ===========================
+-- The games built into the deployed game engine, as listed by its
+-- get_available_games() manifest. The engine is the same for every
+-- tenant, so the list is global; each sync adds new games and flags the
+-- ones no longer in the build with removed_at rather than deleting them,
+-- so their scores and category assignments survive a rollback. Tenants
+-- switch engine games off in tenant_engine_games; a game without a row
+-- there is on.
+
+CREATE TABLE IF NOT EXISTS engine_games (
+    id              VARCHAR(64) PRIMARY KEY,
+    title           TEXT NOT NULL,
+    description     TEXT NOT NULL DEFAULT '',
+    modes           TEXT[] NOT NULL DEFAULT '{}',
+    inputs          TEXT[] NOT NULL DEFAULT '{}',
+    thumbnail_color TEXT NOT NULL DEFAULT '#333',
+    render          VARCHAR(8) NOT NULL DEFAULT '2d',
+    removed_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS tenant_engine_games (
+    tenant_id       VARCHAR(64) NOT NULL,
+    game_id         VARCHAR(64) NOT NULL REFERENCES engine_games(id) ON DELETE CASCADE,
+    is_active       BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, game_id)
+);
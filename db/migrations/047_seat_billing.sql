@@ -0,0 +1,14 @@
+-- Migration 047: Per-Seat and Metered Billing
+-- ===========================================
+-- A subscription can carry two Stripe items: the plan's price, whose
+-- quantity is the organisation's seat count on per-seat plans, and a
+-- metered price billed by game sessions. The item ids are kept so seat
+-- changes and usage can be sent to the right item. Usage is reported as
+-- increments; reported_count records how much of each meter period Stripe
+-- has already been told about.
+
+ALTER TABLE subscriptions ADD COLUMN IF NOT EXISTS seat_item_id VARCHAR(255);
+ALTER TABLE subscriptions ADD COLUMN IF NOT EXISTS seat_quantity INTEGER;
+ALTER TABLE subscriptions ADD COLUMN IF NOT EXISTS metered_item_id VARCHAR(255);
+
+ALTER TABLE usage_meters ADD COLUMN IF NOT EXISTS reported_count BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Migration 048: Dunning
+-- ======================
+-- A failed invoice payment flags the organisation and starts a grace
+-- period, during which Stripe retries the payment and the plan keeps
+-- working. A successful payment clears both. If the grace period runs out
+-- first, the organisation drops to the free plan's entitlements and
+-- billing_grace_until is cleared, leaving payment_failed_at set until a
+-- payment goes through.
+
+ALTER TABLE organisations ADD COLUMN IF NOT EXISTS payment_failed_at TIMESTAMPTZ;
+ALTER TABLE organisations ADD COLUMN IF NOT EXISTS billing_grace_until TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_org_billing_grace
+    ON organisations(billing_grace_until) WHERE billing_grace_until IS NOT NULL;
@@ -0,0 +1,40 @@
+-- Migration 049: Promo Codes
+-- ==========================
+-- Codes created by tenant admins that players redeem for currency, items
+-- and battle pass tiers. A code can be redeemed max_redemptions times in
+-- all (NULL for no limit) and per_player_limit times by each player, until
+-- it expires or is switched off. Every redemption is kept, with what it
+-- granted, as the code's audit log.
+
+CREATE TABLE IF NOT EXISTS promo_codes (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id           VARCHAR(64) NOT NULL,
+    code                VARCHAR(32) NOT NULL,
+    currency            JSONB NOT NULL DEFAULT '{}',
+    -- currency: {"coins": 500, "gems": 10}
+    items               JSONB NOT NULL DEFAULT '[]',
+    -- items: [{"itemId": "...", "quantity": 1}]
+    battle_pass_tiers   INTEGER NOT NULL DEFAULT 0,
+    max_redemptions     INTEGER,
+    per_player_limit    INTEGER NOT NULL DEFAULT 1,
+    redemption_count    INTEGER NOT NULL DEFAULT 0,
+    expires_at          TIMESTAMPTZ,
+    is_active           BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by          UUID,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, code)
+);
+
+CREATE TABLE IF NOT EXISTS promo_code_redemptions (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    promo_code_id   UUID NOT NULL REFERENCES promo_codes(id) ON DELETE CASCADE,
+    player_id       UUID NOT NULL,
+    rewards         JSONB NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_promo_redemptions_code
+    ON promo_code_redemptions(promo_code_id, player_id);
+CREATE INDEX IF NOT EXISTS idx_promo_redemptions_player
+    ON promo_code_redemptions(tenant_id, player_id, created_at DESC);
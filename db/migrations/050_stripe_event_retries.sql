@@ -0,0 +1,15 @@
+-- Migration 050: Stripe Event Retries
+-- ===================================
+-- Webhook events are stored as they arrive, before they are processed, so
+-- an event whose processing fails is not lost. It's retried by the job
+-- worker; after the last attempt it stays 'failed' until an admin replays
+-- it. status: pending (stored, being processed), retrying, processed,
+-- failed. processed_at is now only set once an event has been processed.
+
+ALTER TABLE stripe_events ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE stripe_events ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE stripe_events ADD COLUMN IF NOT EXISTS last_error TEXT;
+ALTER TABLE stripe_events ALTER COLUMN processed_at DROP DEFAULT;
+
+CREATE INDEX IF NOT EXISTS idx_stripe_events_unprocessed
+    ON stripe_events(status, received_at DESC) WHERE status <> 'processed';
@@ -0,0 +1,38 @@
+-- Migration 051: xAPI Export
+-- =========================
+-- Tenants can have score submissions and earned achievements sent to
+-- their Learning Record Store as xAPI statements. A statement is written
+-- to the outbox in the same transaction as the event it records, only for
+-- tenants with an active LRS, and the export job delivers each tenant's
+-- pending statements oldest first. Statement ids are fixed when they are
+-- written, so the LRS ignores a statement it already has and sent or
+-- failed statements can be safely replayed by resetting them to pending.
+
+CREATE TABLE IF NOT EXISTS lrs_endpoints (
+    tenant_id       VARCHAR(64) PRIMARY KEY,
+    endpoint        TEXT NOT NULL,
+    -- HTTP Basic credentials issued by the LRS
+    username        TEXT NOT NULL,
+    password        TEXT NOT NULL,
+    is_active       BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by      UUID,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS xapi_outbox (
+    id              UUID PRIMARY KEY,
+    -- the statement id
+    tenant_id       VARCHAR(64) NOT NULL,
+    statement       JSONB NOT NULL,
+    status          VARCHAR(16) NOT NULL DEFAULT 'pending',
+    -- status: pending, sent, failed
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at         TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_xapi_outbox_pending
+    ON xapi_outbox(tenant_id, created_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_xapi_outbox_status
+    ON xapi_outbox(tenant_id, status, created_at DESC);
@@ -0,0 +1,24 @@
+-- Migration 052: API Keys
+-- =======================
+-- Tenant backends call selected endpoints (score submission on behalf of
+-- a player, classroom roster sync) with an API key instead of a player
+-- JWT. Only a SHA-256 hash of each key is stored; the key itself is shown
+-- once, when it's created. A key can do what its scopes allow and nothing
+-- else, and stops working once revoked or expired.
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    name            VARCHAR(128) NOT NULL,
+    -- the key's first characters, to tell keys apart in listings
+    key_prefix      VARCHAR(16) NOT NULL,
+    key_hash        VARCHAR(64) NOT NULL UNIQUE,
+    scopes          TEXT[] NOT NULL DEFAULT '{}',
+    created_by      UUID,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at    TIMESTAMPTZ,
+    expires_at      TIMESTAMPTZ,
+    revoked_at      TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_tenant ON api_keys(tenant_id, created_at DESC);
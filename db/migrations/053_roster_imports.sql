@@ -0,0 +1,44 @@
+-- Migration 053: Roster Imports
+-- =============================
+-- Organisations import their students and classes from CSV exported by
+-- their school information system. Imports run in the background and
+-- produce a validation report; a dry run stops there. Students and classes
+-- are keyed by the school's own ids, so importing the same files again
+-- updates what an earlier import created instead of duplicating it. New
+-- students get guest accounts and join the organisation as members.
+
+CREATE TABLE IF NOT EXISTS roster_imports (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    organisation_id VARCHAR(64) NOT NULL REFERENCES organisations(id) ON DELETE CASCADE,
+    -- null when imported with an API key
+    requested_by    UUID,
+    dry_run         BOOLEAN NOT NULL DEFAULT FALSE,
+    -- the uploaded files, cleared once the import has run
+    students_csv    TEXT,
+    classes_csv     TEXT,
+    status          VARCHAR(16) NOT NULL DEFAULT 'pending',
+    -- status: pending, processing, completed, invalid, failed
+    report          JSONB,
+    error           TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at    TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_roster_imports_org
+    ON roster_imports(tenant_id, organisation_id, created_at DESC);
+
+-- Which player an organisation's student id was imported as.
+CREATE TABLE IF NOT EXISTS roster_students (
+    organisation_id VARCHAR(64) NOT NULL REFERENCES organisations(id) ON DELETE CASCADE,
+    external_id     VARCHAR(128) NOT NULL,
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    imported_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organisation_id, external_id)
+);
+
+ALTER TABLE classrooms ADD COLUMN IF NOT EXISTS external_id VARCHAR(128);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_classrooms_external
+    ON classrooms(organisation_id, external_id) WHERE external_id IS NOT NULL;
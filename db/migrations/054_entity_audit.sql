@@ -0,0 +1,31 @@
+-- Migration 054: Soft Deletion & Entity Audit
+-- ===========================================
+-- Games, categories, store items and battle passes deleted through the
+-- admin routes are kept with deleted_at set and is_active cleared, so
+-- player inventories and progress that point at them stay intact and an
+-- admin can restore them. Every admin change to one of them is recorded in
+-- entity_audit with the row as it was before and after.
+
+ALTER TABLE custom_games ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE game_categories ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE store_items ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE battle_passes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS entity_audit (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    entity_type     VARCHAR(32) NOT NULL,
+    -- entity_type: game, category, store_item, battle_pass
+    entity_id       VARCHAR(64) NOT NULL,
+    action          VARCHAR(16) NOT NULL,
+    -- action: create, update, delete, restore
+    before          JSONB,
+    after           JSONB,
+    actor_id        UUID,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_entity_audit_entity
+    ON entity_audit(tenant_id, entity_type, entity_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_entity_audit_tenant
+    ON entity_audit(tenant_id, created_at DESC);
@@ -0,0 +1,45 @@
+-- Migration 055: Crash Reports
+-- ============================
+-- The game engine reports panics with the game being played, the player's
+-- last inputs and browser details. Reports with the same signature (panic
+-- location and message, with numbers masked) form a crash group, which is
+-- what admins triage. A report for a resolved group reopens it.
+
+CREATE TABLE IF NOT EXISTS crash_groups (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    signature       VARCHAR(64) NOT NULL,
+    -- from the first report
+    message         TEXT NOT NULL,
+    location        VARCHAR(256) NOT NULL DEFAULT '',
+    status          VARCHAR(16) NOT NULL DEFAULT 'open',
+    -- status: open, investigating, resolved, ignored
+    notes           TEXT,
+    report_count    INT NOT NULL DEFAULT 0,
+    first_seen_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at     TIMESTAMPTZ,
+    updated_by      UUID,
+    UNIQUE (tenant_id, signature)
+);
+
+CREATE INDEX IF NOT EXISTS idx_crash_groups_tenant
+    ON crash_groups(tenant_id, status, last_seen_at DESC);
+
+CREATE TABLE IF NOT EXISTS crash_reports (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id       VARCHAR(64) NOT NULL,
+    group_id        UUID NOT NULL REFERENCES crash_groups(id) ON DELETE CASCADE,
+    -- null when the player wasn't signed in
+    player_id       UUID,
+    game_id         VARCHAR(64),
+    message         TEXT NOT NULL,
+    location        VARCHAR(256) NOT NULL DEFAULT '',
+    engine_version  VARCHAR(32),
+    inputs          JSONB NOT NULL DEFAULT '[]',
+    browser         JSONB NOT NULL DEFAULT '{}',
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_crash_reports_group
+    ON crash_reports(group_id, created_at DESC);
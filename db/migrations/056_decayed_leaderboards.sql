@@ -0,0 +1,33 @@
+-- Migration 056: Decayed Leaderboards
+-- ===================================
+-- A game with a game_leaderboard_configs row also has a decayed board:
+-- each submission in score_history counts for less the older it is,
+-- halving every decay_half_life_days, and a player ranks by their best
+-- decayed submission. Old records fade instead of holding the top spots
+-- forever.
+--
+-- decayed_rankings holds the board as last computed by the nightly
+-- decay_leaderboards job; the endpoint computes it on the fly until the
+-- first run.
+
+CREATE TABLE IF NOT EXISTS game_leaderboard_configs (
+    tenant_id             TEXT NOT NULL,
+    game_id               TEXT NOT NULL,
+    decay_half_life_days  DOUBLE PRECISION NOT NULL CHECK (decay_half_life_days > 0),
+    updated_by            UUID,
+    updated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, game_id)
+);
+
+CREATE TABLE IF NOT EXISTS decayed_rankings (
+    tenant_id      TEXT NOT NULL,
+    game_id        TEXT NOT NULL,
+    player_id      TEXT NOT NULL,
+    decayed_score  DOUBLE PRECISION NOT NULL,
+    rank           BIGINT NOT NULL,
+    computed_at    TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (tenant_id, game_id, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_decayed_rankings_rank
+    ON decayed_rankings(tenant_id, game_id, rank);
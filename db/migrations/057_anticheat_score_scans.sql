@@ -0,0 +1,26 @@
+-- Migration 057: Anti-Cheat Score Scans
+-- ======================================
+-- The detect_anomalies job scans recent score_history rows for
+-- submissions that look cheated and raises anticheat_flags for them, the
+-- admin "suspected cheaters" queue:
+--   score_anomaly       far above the game's recent mean
+--   submission_cadence  submitted sooner after the previous run than the
+--                       run itself lasted
+--   shared_device       one device fingerprint submitting for many players
+-- Score flags name the game and the suspect submissions. A player has at
+-- most one open flag per game and type; later detections are added to
+-- it. Voiding a flag sets voided_at on its submissions, which
+-- leaderboards then ignore.
+
+ALTER TABLE score_history ADD COLUMN IF NOT EXISTS device_fingerprint VARCHAR(128);
+ALTER TABLE score_history ADD COLUMN IF NOT EXISTS voided_at TIMESTAMPTZ;
+
+ALTER TABLE anticheat_flags ADD COLUMN IF NOT EXISTS game_id VARCHAR(64);
+ALTER TABLE anticheat_flags ADD COLUMN IF NOT EXISTS score_ids BIGINT[];   -- score_history ids
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ac_open_score_flag
+    ON anticheat_flags(tenant_id, player_id, game_id, flag_type) WHERE status = 'open';
+
+-- Submissions already flagged are skipped on the next scan.
+CREATE INDEX IF NOT EXISTS idx_ac_score_ids
+    ON anticheat_flags USING GIN (score_ids);
@@ -0,0 +1,69 @@
+-- Migration 058: Battle Pass Tiers & Windows
+-- ==========================================
+-- A battle pass's tiers are defined in battle_pass_tiers, each with the
+-- total XP needed to reach it, and rewarded through battle_pass_rewards:
+-- at most one free and one premium reward per tier, either currency or a
+-- store item. battle_passes.max_tier, free_rewards and premium_rewards are
+-- rewritten from them whenever the tiers change, for older clients.
+--
+-- A pass with starts_at is switched on by the battle_pass_windows job when
+-- its window opens. Once ends_at passes the job switches it off, grants
+-- every player the free rewards they reached but never claimed, and sets
+-- closed_at.
+
+ALTER TABLE battle_passes ADD COLUMN IF NOT EXISTS starts_at TIMESTAMPTZ;
+ALTER TABLE battle_passes ADD COLUMN IF NOT EXISTS ends_at   TIMESTAMPTZ;
+ALTER TABLE battle_passes ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_battle_passes_window
+    ON battle_passes(starts_at, ends_at) WHERE closed_at IS NULL AND deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS battle_pass_tiers (
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    battle_pass_id  INT NOT NULL REFERENCES battle_passes(id) ON DELETE CASCADE,
+    tier            INT NOT NULL,
+    xp_threshold    INT NOT NULL,       -- total XP on the pass to reach the tier
+    PRIMARY KEY (battle_pass_id, tier),
+    CONSTRAINT positive_tier CHECK (tier > 0),
+    CONSTRAINT positive_threshold CHECK (xp_threshold > 0)
+);
+
+CREATE TABLE IF NOT EXISTS battle_pass_rewards (
+    tenant_id       TEXT NOT NULL DEFAULT 'stem_default',
+    battle_pass_id  INT NOT NULL,
+    tier            INT NOT NULL,
+    track           TEXT NOT NULL CHECK (track IN ('free', 'premium')),
+    reward_type     TEXT NOT NULL CHECK (reward_type IN ('currency', 'item')),
+    currency_type   TEXT,               -- currency rewards
+    item_id         TEXT REFERENCES store_items(id),   -- item rewards
+    amount          INT NOT NULL DEFAULT 1,
+    PRIMARY KEY (battle_pass_id, tier, track),
+    FOREIGN KEY (battle_pass_id, tier) REFERENCES battle_pass_tiers(battle_pass_id, tier) ON DELETE CASCADE,
+    CONSTRAINT positive_amount CHECK (amount > 0),
+    CONSTRAINT reward_target CHECK (
+        (reward_type = 'currency' AND currency_type IS NOT NULL AND item_id IS NULL)
+        OR (reward_type = 'item' AND item_id IS NOT NULL AND currency_type IS NULL))
+);
+
+-- Existing passes get evenly spaced tiers from xp_per_tier, and the
+-- currency and item rewards of their reward lists.
+INSERT INTO battle_pass_tiers (tenant_id, battle_pass_id, tier, xp_threshold)
+SELECT bp.tenant_id, bp.id, t, t * bp.xp_per_tier
+FROM battle_passes bp, generate_series(1, bp.max_tier) t
+WHERE bp.xp_per_tier > 0
+ON CONFLICT DO NOTHING;
+
+INSERT INTO battle_pass_rewards (tenant_id, battle_pass_id, tier, track, reward_type, currency_type, item_id, amount)
+SELECT bp.tenant_id, bp.id, (r->>'tier')::int, l.track, r->>'reward_type',
+    CASE WHEN r->>'reward_type' = 'currency' THEN COALESCE(r->'reward_data'->>'currency_type', 'coins') END,
+    CASE WHEN r->>'reward_type' = 'item' THEN r->'reward_data'->>'item_id' END,
+    GREATEST(COALESCE((r->'reward_data'->>'amount')::int, (r->'reward_data'->>'quantity')::int, 1), 1)
+FROM battle_passes bp
+CROSS JOIN LATERAL (VALUES ('free', bp.free_rewards), ('premium', bp.premium_rewards)) AS l(track, rewards)
+CROSS JOIN LATERAL jsonb_array_elements(CASE WHEN jsonb_typeof(l.rewards) = 'array' THEN l.rewards ELSE '[]'::jsonb END) r
+WHERE r->>'reward_type' IN ('currency', 'item')
+    AND (r->>'tier') ~ '^[0-9]+$'
+    AND EXISTS (SELECT 1 FROM battle_pass_tiers bt WHERE bt.battle_pass_id = bp.id AND bt.tier = (r->>'tier')::int)
+    AND (r->>'reward_type' = 'currency'
+        OR EXISTS (SELECT 1 FROM store_items si WHERE si.id = r->'reward_data'->>'item_id'))
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,45 @@
+-- Migration 059: Battle Pass XP Sources
+-- =====================================
+-- Battle pass XP is awarded by the server for what players do, instead of
+-- being sent by the client:
+--   score            a validated score submission
+--   daily_challenge  the day's daily challenge run
+--   achievement      an achievement unlocked
+--   match_win        a multiplayer match won
+--   match_loss       a multiplayer match lost
+-- Each source gives a flat amount of XP, plus XP per 1000 points scored
+-- for scores and matches, up to an optional daily cap per player. Tenants
+-- override the built-in settings in battle_pass_xp_sources.
+--
+-- Every award is kept in battle_pass_xp_awards, once per source and
+-- reference (the score, run, achievement or match it was for), which also
+-- counts towards the daily caps.
+
+CREATE TABLE IF NOT EXISTS battle_pass_xp_sources (
+    tenant_id           VARCHAR(64) NOT NULL,
+    source              VARCHAR(32) NOT NULL,
+    xp                  INT NOT NULL DEFAULT 0,
+    xp_per_1000_points  INT NOT NULL DEFAULT 0,
+    daily_cap           INT,                    -- NULL for no cap
+    is_enabled          BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by          UUID,
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, source),
+    CONSTRAINT non_negative_xp CHECK (xp >= 0 AND xp_per_1000_points >= 0),
+    CONSTRAINT positive_cap CHECK (daily_cap IS NULL OR daily_cap > 0)
+);
+
+CREATE TABLE IF NOT EXISTS battle_pass_xp_awards (
+    id              BIGSERIAL PRIMARY KEY,
+    tenant_id       VARCHAR(64) NOT NULL,
+    player_id       UUID NOT NULL,
+    battle_pass_id  INT NOT NULL REFERENCES battle_passes(id) ON DELETE CASCADE,
+    source          VARCHAR(32) NOT NULL,   -- a source above, or 'api'
+    reference_id    TEXT,
+    xp              INT NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, player_id, source, reference_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bp_xp_awards_player
+    ON battle_pass_xp_awards(tenant_id, player_id, source, created_at DESC);
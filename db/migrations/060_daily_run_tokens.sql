@@ -0,0 +1,15 @@
+-- Migration 060: Daily Run Tokens
+-- ===============================
+-- A daily challenge attempt is recorded when the run starts, not when its
+-- score comes in, so a player can't play the day's seed several times and
+-- submit only their best run. Starting hands out run_token, which the
+-- submission must carry; score and submitted_at stay NULL until then.
+-- Only submitted runs are ranked.
+
+ALTER TABLE daily_runs ADD COLUMN IF NOT EXISTS run_token UUID;
+ALTER TABLE daily_runs ADD COLUMN IF NOT EXISTS submitted_at TIMESTAMPTZ;
+ALTER TABLE daily_runs ALTER COLUMN score DROP NOT NULL;
+
+UPDATE daily_runs SET submitted_at = created_at WHERE submitted_at IS NULL AND score IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_daily_runs_token ON daily_runs(run_token);
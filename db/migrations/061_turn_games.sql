@@ -0,0 +1,21 @@
+-- Migration 061: Turn Games
+-- =========================
+-- Turn-based rooms can run for days (up to seven per turn), so the room
+-- manager keeps each one here as well as in memory: the room as the API
+-- returns it, including its turn state, and every turn played. Rooms are
+-- restored from here at startup, and loaded when one is asked for that
+-- isn't in memory. The row goes once the match result is submitted.
+--
+-- player_ids lists the room's players, for GDPR export and erasure.
+
+CREATE TABLE IF NOT EXISTS turn_games (
+    room_id     TEXT PRIMARY KEY,
+    tenant_id   TEXT NOT NULL,
+    player_ids  UUID[] NOT NULL,
+    room        JSONB NOT NULL,
+    turns       JSONB NOT NULL DEFAULT '[]',
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_turn_games_players ON turn_games USING GIN (player_ids);
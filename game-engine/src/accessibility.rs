@@ -0,0 +1,536 @@
+//! Accessibility.
+//!
+//! Colour-matching games tag their colour-coded entities with
+//! [`ColorCoded`] and take their colours from
+//! [`AccessibilitySettings::color`] instead of hard-coding them.  This
+//! module then:
+//!
+//! * swaps in a palette that stays distinguishable for the selected kind of
+//!   colour blindness, and
+//! * when `patterns` is on, overlays each colour with its own texture —
+//!   stripes for red, dots for green, vertical bars for blue — or, for small
+//!   objects such as keys and doors, the channel's letter.
+//!
+//! `high_contrast` saturates those colours, spreads their shades further
+//! apart and puts a dark backing behind HUD text.  `reduced_motion` stops
+//! screen shake, the characters' idle breathing and blinking, and the
+//! stretch and squash of falling pieces.  Both start from the browser's
+//! `prefers-contrast` and `prefers-reduced-motion` settings.
+//!
+//! For screen readers, key moments of a run — the game starting, score
+//! milestones, lives lost, levels completed and the final score — are
+//! written to a visually hidden ARIA live region.  Games can send an
+//! [`Announce`] for anything else worth reading out.
+//!
+//! The shell changes the settings with `set_accessibility(json)`; the pause
+//! menu cycles the colorblind mode too.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::analytics::{AnalyticsEvent, EventKind};
+use crate::games::GameplaySet;
+use crate::{catalog, AppState, BevyBridge};
+
+/// Opacity of pattern stripes/dots drawn over a coloured surface.
+const PATTERN_ALPHA: f32 = 0.4;
+/// Backing put behind HUD text in high-contrast mode.
+const TEXT_BACKING: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+/// The first score read out; later milestones go 1–2–5 from there.
+const FIRST_MILESTONE: i64 = 100;
+const LIVE_REGION_ID: &str = "bevy-live-region";
+/// Keeps the live region readable by screen readers but off screen.
+const VISUALLY_HIDDEN: &str = "position:absolute;width:1px;height:1px;margin:-1px;\
+    padding:0;border:0;overflow:hidden;clip:rect(0 0 0 0);white-space:nowrap";
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AccessibilitySettings>()
+            .init_resource::<Announcer>()
+            .add_event::<Announce>()
+            .add_systems(Startup, detect_preferences)
+            .add_systems(
+                Update,
+                (
+                    (apply_pending_settings, attach_overlays, recolor, show_overlays, back_text).chain(),
+                    (
+                        announce_progress.run_if(in_state(AppState::Playing)).after(GameplaySet),
+                        speak,
+                    )
+                        .chain(),
+                ),
+            )
+            .add_systems(OnEnter(AppState::Playing), announce_start)
+            .add_systems(OnEnter(AppState::GameOver), announce_game_over);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Settings
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorblindMode {
+    #[default]
+    Off,
+    /// Red–green (green-weak); the most common form.
+    Deuteranopia,
+    /// Red–green (red-weak).
+    Protanopia,
+    /// Blue–yellow.
+    Tritanopia,
+}
+
+impl ColorblindMode {
+    /// Next mode in the pause-menu cycle.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Deuteranopia,
+            Self::Deuteranopia => Self::Protanopia,
+            Self::Protanopia => Self::Tritanopia,
+            Self::Tritanopia => Self::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Protanopia => "Protanopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AccessibilitySettings {
+    #[serde(default)]
+    pub colorblind: ColorblindMode,
+    /// Pattern overlays on colour-coded surfaces and letter icons on
+    /// colour-coded objects.
+    #[serde(default)]
+    pub patterns: bool,
+    /// Stronger colours and backed HUD text.
+    #[serde(default)]
+    pub high_contrast: bool,
+    /// No screen shake, idle animations or squash and stretch.
+    #[serde(default)]
+    pub reduced_motion: bool,
+}
+
+/// The three gameplay colours used by colour-matching games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+
+impl ColorChannel {
+    fn letter(self) -> &'static str {
+        match self {
+            Self::Red => "R",
+            Self::Green => "G",
+            Self::Blue => "B",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shade {
+    Dark,
+    #[default]
+    Base,
+    Bright,
+}
+
+impl AccessibilitySettings {
+    /// Display colour for a channel under the current palette.
+    pub fn color(&self, channel: ColorChannel, shade: Shade) -> Color {
+        let color = self.palette_color(channel, shade);
+        if !self.high_contrast {
+            return color;
+        }
+        let mut hsla = Hsla::from(color);
+        hsla.saturation = 1.0;
+        hsla.lightness = match shade {
+            Shade::Dark => hsla.lightness * 0.6,
+            Shade::Base => hsla.lightness,
+            Shade::Bright => hsla.lightness + (1.0 - hsla.lightness) * 0.6,
+        };
+        hsla.into()
+    }
+
+    fn palette_color(&self, channel: ColorChannel, shade: Shade) -> Color {
+        use ColorChannel::*;
+
+        // The standard palette keeps the games' original colours.
+        if self.colorblind == ColorblindMode::Off {
+            return match (shade, channel) {
+                (Shade::Dark, Red) => Color::srgb(0.7, 0.1, 0.1),
+                (Shade::Dark, Green) => Color::srgb(0.1, 0.7, 0.1),
+                (Shade::Dark, Blue) => Color::srgb(0.1, 0.1, 0.7),
+                (Shade::Base, Red) => Color::srgb(0.85, 0.2, 0.2),
+                (Shade::Base, Green) => Color::srgb(0.2, 0.8, 0.2),
+                (Shade::Base, Blue) => Color::srgb(0.2, 0.3, 0.9),
+                (Shade::Bright, Red) => Color::srgb(1.0, 0.4, 0.4),
+                (Shade::Bright, Green) => Color::srgb(0.4, 1.0, 0.4),
+                (Shade::Bright, Blue) => Color::srgb(0.4, 0.5, 1.0),
+            };
+        }
+
+        // Okabe–Ito colours, chosen so the three channels differ along an
+        // axis the viewer can still see (and in lightness).
+        let (r, g, b) = match (self.colorblind, channel) {
+            (ColorblindMode::Tritanopia, Red) => (0.84, 0.37, 0.0),
+            (ColorblindMode::Tritanopia, Green) => (0.0, 0.62, 0.45),
+            (ColorblindMode::Tritanopia, Blue) => (0.8, 0.47, 0.65),
+            (_, Red) => (0.84, 0.37, 0.0),
+            (_, Green) => (0.94, 0.89, 0.26),
+            (_, Blue) => (0.0, 0.45, 0.7),
+        };
+        match shade {
+            Shade::Dark => Color::srgb(r * 0.75, g * 0.75, b * 0.75),
+            Shade::Base => Color::srgb(r, g, b),
+            Shade::Bright => Color::srgb(r + (1.0 - r) * 0.35, g + (1.0 - g) * 0.35, b + (1.0 - b) * 0.35),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+/// How a colour-coded entity is marked when patterns are on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMarker {
+    /// Stripes/dots across the whole sprite (platforms, tiles).
+    Pattern,
+    /// The channel's letter (keys, doors, pickups).
+    Icon,
+}
+
+/// An entity whose body sprite encodes a [`ColorChannel`].  Its sprite is
+/// recoloured when the palette changes (keeping its alpha) and it gets a
+/// pattern/icon child that shows while `patterns` is on.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ColorCoded {
+    pub channel: ColorChannel,
+    pub shade: Shade,
+    pub marker: ColorMarker,
+}
+
+impl ColorCoded {
+    pub fn pattern(channel: ColorChannel, shade: Shade) -> Self {
+        Self { channel, shade, marker: ColorMarker::Pattern }
+    }
+
+    pub fn icon(channel: ColorChannel, shade: Shade) -> Self {
+        Self { channel, shade, marker: ColorMarker::Icon }
+    }
+}
+
+#[derive(Component)]
+struct PatternOverlay;
+
+/// A [`TEXT_BACKING`] added to a text node for high contrast, removed
+/// again when it's turned off.
+#[derive(Component)]
+struct ContrastBacking;
+
+// ---------------------------------------------------------------------------
+// Announcements
+// ---------------------------------------------------------------------------
+
+/// Text for screen readers to read out.  Announcements sent in the same
+/// frame are read together.
+#[derive(Event, Clone, Debug)]
+pub struct Announce(pub String);
+
+#[derive(Resource, Default)]
+struct Announcer {
+    /// Highest score milestone announced this run.
+    milestone: i64,
+    /// Alternates a trailing space, so an announcement the same as the
+    /// last one still changes the region and is read again.
+    flip: bool,
+}
+
+/// The highest milestone — 100, 200, 500, 1000, 2000, … — `score` has
+/// reached, or 0 below the first.
+fn milestone(score: i64) -> i64 {
+    let mut reached = 0;
+    let mut next = FIRST_MILESTONE;
+    while next <= score {
+        reached = next;
+        let magnitude = 10i64.pow(next.ilog10());
+        next = if next / magnitude == 2 { next / 2 * 5 } else { next * 2 };
+    }
+    reached
+}
+
+/// The page's live region, created on first use.
+fn live_region() -> Option<web_sys::Element> {
+    let document = web_sys::window()?.document()?;
+    if let Some(region) = document.get_element_by_id(LIVE_REGION_ID) {
+        return Some(region);
+    }
+    let region = document.create_element("div").ok()?;
+    region.set_id(LIVE_REGION_ID);
+    for (name, value) in [
+        ("role", "status"),
+        ("aria-live", "polite"),
+        ("aria-atomic", "true"),
+        ("style", VISUALLY_HIDDEN),
+    ] {
+        region.set_attribute(name, value).ok()?;
+    }
+    document.body()?.append_child(&region).ok()?;
+    Some(region)
+}
+
+fn media_matches(query: &str) -> bool {
+    web_sys::window()
+        .and_then(|w| w.match_media(query).ok().flatten())
+        .is_some_and(|m| m.matches())
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Update accessibility options, e.g.
+/// `set_accessibility('{"colorblind":"deuteranopia","patterns":true}')` or
+/// `set_accessibility('{"high_contrast":true,"reduced_motion":true}')`.
+/// Omitted keys keep their current value, except that choosing a
+/// colorblind mode without `patterns` turns patterns on (or off for `"off"`).
+#[wasm_bindgen]
+pub fn set_accessibility(json: &str) {
+    crate::set_js_global("__bevy_pending_accessibility", json);
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn apply_pending_settings(mut settings: ResMut<AccessibilitySettings>) {
+    let Some(raw) = crate::get_js_global("__bevy_pending_accessibility") else {
+        return;
+    };
+    if raw.is_empty() {
+        return;
+    }
+    crate::delete_js_global("__bevy_pending_accessibility");
+
+    let Ok(update) = serde_json::from_str::<serde_json::Value>(&raw) else {
+        return;
+    };
+    if let Some(mode) = update
+        .get("colorblind")
+        .and_then(|m| serde_json::from_value::<ColorblindMode>(m.clone()).ok())
+    {
+        settings.colorblind = mode;
+        settings.patterns = mode != ColorblindMode::Off;
+    }
+    if let Some(patterns) = update.get("patterns").and_then(|p| p.as_bool()) {
+        settings.patterns = patterns;
+    }
+    if let Some(high_contrast) = update.get("high_contrast").and_then(|p| p.as_bool()) {
+        settings.high_contrast = high_contrast;
+    }
+    if let Some(reduced_motion) = update.get("reduced_motion").and_then(|p| p.as_bool()) {
+        settings.reduced_motion = reduced_motion;
+    }
+}
+
+fn detect_preferences(mut settings: ResMut<AccessibilitySettings>) {
+    settings.high_contrast = media_matches("(prefers-contrast: more)");
+    settings.reduced_motion = media_matches("(prefers-reduced-motion: reduce)");
+}
+
+fn attach_overlays(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    q: Query<(Entity, &ColorCoded, &Sprite), Added<ColorCoded>>,
+) {
+    let visibility = if settings.patterns {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for (entity, coded, sprite) in &q {
+        let size = sprite.custom_size.unwrap_or(Vec2::splat(32.0));
+        commands.entity(entity).with_children(|parent| match coded.marker {
+            ColorMarker::Icon => {
+                parent.spawn((
+                    Text2d::new(coded.channel.letter()),
+                    TextFont {
+                        font_size: (size.y * 0.55).max(10.0),
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Transform::from_xyz(0.0, 0.0, 0.2),
+                    visibility,
+                    PatternOverlay,
+                ));
+            }
+            ColorMarker::Pattern => {
+                parent
+                    .spawn((Transform::from_xyz(0.0, 0.0, 0.1), visibility, PatternOverlay))
+                    .with_children(|p| spawn_pattern(p, coded.channel, size));
+            }
+        });
+    }
+}
+
+/// Red: horizontal stripes.  Green: dots.  Blue: vertical bars.
+fn spawn_pattern(parent: &mut ChildBuilder, channel: ColorChannel, size: Vec2) {
+    let ink = Color::srgba(0.0, 0.0, 0.0, PATTERN_ALPHA);
+    let mut mark = |w: f32, h: f32, x: f32, y: f32| {
+        parent.spawn((
+            Sprite {
+                color: ink,
+                custom_size: Some(Vec2::new(w, h)),
+                ..default()
+            },
+            Transform::from_xyz(x, y, 0.0),
+        ));
+    };
+
+    match channel {
+        ColorChannel::Red => {
+            let rows = ((size.y / 6.0) as i32).max(1);
+            for i in 0..rows {
+                let y = -size.y / 2.0 + 3.0 + i as f32 * 6.0;
+                mark(size.x, 2.0, 0.0, y);
+            }
+        }
+        ColorChannel::Green => {
+            let cols = ((size.x / 8.0) as i32).clamp(1, 40);
+            let rows = ((size.y / 8.0) as i32).clamp(1, 40);
+            for cx in 0..cols {
+                for cy in 0..rows {
+                    let x = -size.x / 2.0 + 4.0 + cx as f32 * 8.0;
+                    let y = -size.y / 2.0 + 4.0 + cy as f32 * 8.0;
+                    mark(3.0, 3.0, x, y);
+                }
+            }
+        }
+        ColorChannel::Blue => {
+            let cols = ((size.x / 8.0) as i32).max(1);
+            for i in 0..cols {
+                let x = -size.x / 2.0 + 4.0 + i as f32 * 8.0;
+                mark(2.0, size.y, x, 0.0);
+            }
+        }
+    }
+}
+
+fn recolor(
+    settings: Res<AccessibilitySettings>,
+    mut q: Query<(Ref<ColorCoded>, &mut Sprite)>,
+) {
+    for (coded, mut sprite) in &mut q {
+        if !settings.is_changed() && !coded.is_added() {
+            continue;
+        }
+        let alpha = sprite.color.alpha();
+        sprite.color = settings.color(coded.channel, coded.shade).with_alpha(alpha);
+    }
+}
+
+fn show_overlays(
+    settings: Res<AccessibilitySettings>,
+    mut q: Query<&mut Visibility, With<PatternOverlay>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut vis in &mut q {
+        *vis = if settings.patterns {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Backs UI text with [`TEXT_BACKING`] while high contrast is on.  Text
+/// that already has a background keeps it.
+fn back_text(
+    mut commands: Commands,
+    settings: Res<AccessibilitySettings>,
+    q: Query<(Entity, Ref<Text>, Has<BackgroundColor>, Has<ContrastBacking>)>,
+) {
+    for (entity, text, has_background, backed) in &q {
+        if !settings.is_changed() && !text.is_added() {
+            continue;
+        }
+        if settings.high_contrast && !has_background {
+            commands.entity(entity).insert((BackgroundColor(TEXT_BACKING), ContrastBacking));
+        } else if !settings.high_contrast && backed {
+            commands.entity(entity).remove::<(BackgroundColor, ContrastBacking)>();
+        }
+    }
+}
+
+fn announce_start(
+    bridge: Res<BevyBridge>,
+    mut announcer: ResMut<Announcer>,
+    mut announce: EventWriter<Announce>,
+) {
+    announcer.milestone = 0;
+    let title = catalog::find(&bridge.game_id).map_or("Game", |g| g.title);
+    announce.send(Announce(format!("{title} started")));
+}
+
+/// Score milestones, lives lost and levels completed.
+fn announce_progress(
+    bridge: Res<BevyBridge>,
+    mut events: EventReader<AnalyticsEvent>,
+    mut announcer: ResMut<Announcer>,
+    mut announce: EventWriter<Announce>,
+) {
+    for event in events.read() {
+        match event.kind {
+            EventKind::Death => {
+                announce.send(Announce("Life lost".into()));
+            }
+            EventKind::LevelComplete => {
+                let level = event.data.get("level").and_then(|l| l.as_u64()).unwrap_or(0) + 1;
+                announce.send(Announce(format!("Level {level} complete")));
+            }
+            _ => {}
+        }
+    }
+    let reached = milestone(i64::from(bridge.current_score));
+    if reached > announcer.milestone {
+        announcer.milestone = reached;
+        announce.send(Announce(format!("Score {reached}")));
+    }
+}
+
+fn announce_game_over(bridge: Res<BevyBridge>, mut announce: EventWriter<Announce>) {
+    announce.send(Announce(format!("Game over. Final score {}", bridge.current_score)));
+}
+
+/// Writes the frame's announcements to the live region.
+fn speak(mut announcements: EventReader<Announce>, mut announcer: ResMut<Announcer>) {
+    let text: Vec<&str> = announcements.read().map(|a| a.0.as_str()).collect();
+    if text.is_empty() {
+        return;
+    }
+    let Some(region) = live_region() else { return };
+    announcer.flip = !announcer.flip;
+    let pad = if announcer.flip { "\u{a0}" } else { "" };
+    region.set_text_content(Some(&format!("{}{pad}", text.join(". "))));
+}
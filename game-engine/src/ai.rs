@@ -0,0 +1,254 @@
+//! Computer opponents.
+//!
+//! A bot match is started with a `bot` entry in the `start_game` options:
+//! the bot the matchmaker found when no human opponent turned up in time
+//! (`match_found` with a `bot`), or one the shell asks for directly.
+//! Only games marked `bot` in the [`catalog`](crate::catalog) play them.
+//!
+//! ```json
+//! {"bot": {"difficulty": "hard"}}
+//! {"bot": {"difficulty": "easy", "reactionMs": 2000, "accuracy": 0.4, "displayName": "Robo"}}
+//! ```
+//!
+//! [`Bot`] holds how long the bot takes to act and how close its shots
+//! come to the ideal one, both set by the [`Difficulty`] unless given.
+//! Games work out the ideal shot and let [`Bot::plan`] and [`Bot::aim`]
+//! turn it into one a person might take:
+//!
+//! * `stem_project_volley` and `physics_master_billiards` play a bot match
+//!   as a [versus](crate::versus) match with the bot in player two's seat.
+//! * `formula_stem` adds a rival car that reacts to the start and laps at
+//!   a pace set by its accuracy; the race ends when either finishes.
+//!
+//! When a bot match ends, `get_bot_result()` returns its outcome for the
+//! shell to submit with the match.
+
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::versus::{VersusPlayer, VersusState};
+use crate::{catalog, AppState, BevyBridge, GameOptions};
+
+/// Longest reaction time the options may ask for.
+const MAX_REACTION_SECS: f32 = 5.0;
+const MAX_NAME_CHARS: usize = 24;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bot>()
+            .add_systems(OnEnter(AppState::Playing), load_bot)
+            .add_systems(OnEnter(AppState::GameOver), publish_result);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Default reaction time in seconds, and accuracy.
+    fn skill(self) -> (f32, f32) {
+        match self {
+            Self::Easy => (1.6, 0.55),
+            Self::Medium => (1.0, 0.8),
+            Self::Hard => (0.5, 0.95),
+        }
+    }
+}
+
+/// How a bot match ended, for the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// What the bot does with a planned shot this frame.
+#[derive(Clone, Copy, Debug)]
+pub enum Aim {
+    /// Still lining it up; show this aim.
+    Lining(Vec2),
+    /// Take the shot with this aim.
+    Take(Vec2),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Plan {
+    from: Vec2,
+    to: Vec2,
+    elapsed: f32,
+}
+
+/// The computer opponent of the current run.
+#[derive(Resource, Clone, Debug)]
+pub struct Bot {
+    /// Whether this run is a bot match.
+    pub active: bool,
+    pub difficulty: Difficulty,
+    pub name: String,
+    /// Seconds from the bot's turn starting to it acting.
+    pub reaction_secs: f32,
+    /// How close shots come to the ideal one: at 1.0 they're perfect, at
+    /// 0.0 anywhere in the spread the game allows.
+    pub accuracy: f32,
+    /// Set by games that decide the result themselves; versus games leave
+    /// it to the [`VersusState`] winner.
+    pub outcome: Option<Outcome>,
+    plan: Option<Plan>,
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Self::new(Difficulty::default())
+    }
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        let (reaction_secs, accuracy) = difficulty.skill();
+        Self {
+            active: false,
+            difficulty,
+            name: "Bot".to_string(),
+            reaction_secs,
+            accuracy,
+            outcome: None,
+            plan: None,
+        }
+    }
+
+    /// The bot the options ask for, or an inactive one at the default
+    /// difficulty.
+    pub fn from_options(options: &GameOptions) -> Self {
+        let Some(opts) = options.get("bot").filter(|b| b.is_object()) else {
+            return Self::default();
+        };
+        let difficulty = opts
+            .get("difficulty")
+            .and_then(|d| serde_json::from_value(d.clone()).ok())
+            .unwrap_or_default();
+        let mut bot = Self::new(difficulty);
+        bot.active = true;
+        if let Some(ms) = opts.get("reactionMs").and_then(|v| v.as_f64()) {
+            bot.reaction_secs = (ms as f32 / 1000.0).clamp(0.0, MAX_REACTION_SECS);
+        }
+        if let Some(accuracy) = opts.get("accuracy").and_then(|v| v.as_f64()) {
+            bot.accuracy = (accuracy as f32).clamp(0.0, 1.0);
+        }
+        if let Some(name) = opts.get("displayName").and_then(|v| v.as_str()).filter(|n| !n.is_empty()) {
+            bot.name = name.chars().take(MAX_NAME_CHARS).collect();
+        }
+        bot
+    }
+
+    /// Whether the bot sits in `player`'s seat of a versus match.
+    pub fn plays(&self, player: VersusPlayer) -> bool {
+        self.active && player == VersusPlayer::Two
+    }
+
+    /// The name to show for `player`: the bot's in its seat.
+    pub fn label(&self, player: VersusPlayer) -> &str {
+        if self.plays(player) { &self.name } else { player.label() }
+    }
+
+    /// `ideal` off by up to `spread` on each axis, less the more accurate
+    /// the bot is.
+    pub fn miss(&self, ideal: Vec2, spread: Vec2) -> Vec2 {
+        let mut rng = rand::thread_rng();
+        let error = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+        ideal + error * spread * (1.0 - self.accuracy)
+    }
+
+    /// Starts lining up a shot from the current aim `from` towards `ideal`,
+    /// missed by up to `spread`.  Does nothing while a shot is planned.
+    pub fn plan(&mut self, from: Vec2, ideal: Vec2, spread: Vec2) {
+        if self.plan.is_none() {
+            self.plan = Some(Plan { from, to: self.miss(ideal, spread), elapsed: 0.0 });
+        }
+    }
+
+    /// Advances the planned shot by `dt`.  The aim sweeps from where it was
+    /// to the planned one over the reaction time, then the shot is taken
+    /// and the plan ends.  `None` without a plan.
+    pub fn aim(&mut self, dt: f32) -> Option<Aim> {
+        let plan = self.plan.as_mut()?;
+        plan.elapsed += dt;
+        let t = if self.reaction_secs > 0.0 { (plan.elapsed / self.reaction_secs).min(1.0) } else { 1.0 };
+        let aim = plan.from.lerp(plan.to, t * t * (3.0 - 2.0 * t));
+        if t < 1.0 {
+            return Some(Aim::Lining(aim));
+        }
+        self.plan = None;
+        Some(Aim::Take(aim))
+    }
+}
+
+/// Whether the options start a bot match of `game_id`.
+pub fn bot_match(options: &GameOptions, game_id: &str) -> bool {
+    options.get("bot").is_some_and(|b| b.is_object()) && catalog::find(game_id).is_some_and(|g| g.bot)
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Sets up [`Bot`] for the run.  Game setups that read it run after this.
+pub fn load_bot(options: Res<GameOptions>, bridge: Res<BevyBridge>, mut bot: ResMut<Bot>) {
+    *bot = Bot::from_options(&options);
+    bot.active = bot_match(&options, &bridge.game_id);
+    *LAST_RESULT.lock().unwrap() = String::new();
+}
+
+fn publish_result(bot: Res<Bot>, versus: Res<VersusState>) {
+    if !bot.active {
+        return;
+    }
+    let outcome = bot.outcome.or_else(|| {
+        versus.finished.then(|| match versus.winner {
+            Some(VersusPlayer::One) => Outcome::Win,
+            Some(VersusPlayer::Two) => Outcome::Loss,
+            None => Outcome::Draw,
+        })
+    });
+    let result = serde_json::json!({
+        "opponent": bot.name,
+        "difficulty": bot.difficulty,
+        "outcome": outcome,
+    });
+    *LAST_RESULT.lock().unwrap() = result.to_string();
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+static LAST_RESULT: Mutex<String> = Mutex::new(String::new());
+
+/// The result of the last bot match once it's over, e.g.
+/// `{"opponent":"Bot","difficulty":"hard","outcome":"win"}`, or `""`.
+/// `outcome` is `null` if the match was stopped before it was decided.
+#[wasm_bindgen]
+pub fn get_bot_result() -> String {
+    LAST_RESULT.lock().unwrap().clone()
+}
@@ -0,0 +1,275 @@
+//! Structured gameplay telemetry.
+//!
+//! Games report what happens during a run by sending [`AnalyticsEvent`]s
+//! (completed levels, power-ups used) — deaths come in through
+//! [`diagnostics`](crate::diagnostics); `game_start` and `game_end` are
+//! recorded automatically.  Each
+//! event is stamped with the wall-clock time, the game id and a per-run
+//! session id, and kept in a bounded ring buffer — when the buffer is full
+//! the oldest events are dropped.
+//!
+//! The buffer is flushed to `POST /api/v1/telemetry` every
+//! [`FLUSH_INTERVAL_SECS`] and whenever a run ends.  Nothing is sent until
+//! the shell has called `set_api_config`; a failed upload is put back at
+//! the front of the buffer and retried on the next flush.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::remote_config::RemoteConfig;
+use crate::spectator::is_live;
+use crate::{AppState, BevyBridge};
+
+/// Events kept while offline or between flushes.
+pub const BUFFER_CAPACITY: usize = 512;
+/// Seconds between uploads.
+pub const FLUSH_INTERVAL_SECS: f32 = 10.0;
+/// Events per upload; matches the server's batch limit.
+pub const MAX_BATCH: usize = 100;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct AnalyticsPlugin;
+
+impl Plugin for AnalyticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnalyticsEvent>()
+            .init_resource::<TelemetryBuffer>()
+            .insert_resource(FlushTimer(Timer::from_seconds(
+                FLUSH_INTERVAL_SECS,
+                TimerMode::Repeating,
+            )))
+            // Spectated runs are the host's, not the viewer's.
+            .add_systems(OnEnter(AppState::Playing), start_session.run_if(is_live))
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                (end_session.run_if(is_live), flush).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    requeue_failed,
+                    collect_events,
+                    tick_flush_timer,
+                    flush.run_if(flush_due),
+                )
+                    .chain(),
+            );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    GameStart,
+    GameEnd,
+    Death,
+    LevelComplete,
+    PowerupUsed,
+}
+
+/// A gameplay event sent by a game system.  `data` carries event-specific
+/// fields (e.g. `x`/`y` for deaths, `level` for completed levels).
+#[derive(Event, Clone, Debug)]
+pub struct AnalyticsEvent {
+    pub kind: EventKind,
+    pub data: serde_json::Value,
+}
+
+impl AnalyticsEvent {
+    /// The player died (or failed the attempt) at `pos`, in world units,
+    /// on the zero-based `level`.  Games report these as
+    /// [`Failure`](crate::diagnostics::Failure)s.
+    pub fn death(pos: Vec2, level: usize) -> Self {
+        Self {
+            kind: EventKind::Death,
+            data: json!({ "x": pos.x.round(), "y": pos.y.round(), "level": level }),
+        }
+    }
+
+    /// `level` is zero-based; `score` is the run score after completing it.
+    pub fn level_complete(level: usize, score: i32) -> Self {
+        Self {
+            kind: EventKind::LevelComplete,
+            data: json!({ "level": level, "score": score }),
+        }
+    }
+
+    pub fn powerup_used(powerup: &str) -> Self {
+        Self {
+            kind: EventKind::PowerupUsed,
+            data: json!({ "powerup": powerup }),
+        }
+    }
+}
+
+/// An event as uploaded: `t` is milliseconds since the Unix epoch.
+#[derive(Clone, Debug, Serialize)]
+struct TelemetryRecord {
+    #[serde(rename = "type")]
+    kind: EventKind,
+    #[serde(rename = "gameId")]
+    game_id: String,
+    #[serde(rename = "sessionId")]
+    session_id: String,
+    t: i64,
+    data: serde_json::Value,
+}
+
+// ---------------------------------------------------------------------------
+// Resources / static queues
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Default)]
+pub struct TelemetryBuffer {
+    events: VecDeque<TelemetryRecord>,
+    session_id: String,
+    session_started_ms: i64,
+    /// Events dropped because the buffer was full.
+    pub dropped: u64,
+}
+
+impl TelemetryBuffer {
+    fn push(&mut self, record: TelemetryRecord) {
+        if self.events.len() >= BUFFER_CAPACITY {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(record);
+    }
+
+    fn record(&mut self, game_id: &str, kind: EventKind, data: serde_json::Value) {
+        let record = TelemetryRecord {
+            kind,
+            game_id: game_id.to_string(),
+            session_id: self.session_id.clone(),
+            t: now_ms(),
+            data,
+        };
+        self.push(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[derive(Resource)]
+struct FlushTimer(Timer);
+
+/// Batches whose upload failed, waiting to be put back into the buffer.
+static FAILED_BATCHES: Mutex<Vec<Vec<TelemetryRecord>>> = Mutex::new(Vec::new());
+/// One upload at a time, so retries can't overtake newer batches.
+static UPLOAD_IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+
+fn now_ms() -> i64 {
+    js_sys::Date::now() as i64
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn start_session(bridge: Res<BevyBridge>, mut buffer: ResMut<TelemetryBuffer>) {
+    buffer.session_id = format!(
+        "{:x}{:08x}",
+        now_ms(),
+        (js_sys::Math::random() * u32::MAX as f64) as u32
+    );
+    buffer.session_started_ms = now_ms();
+    buffer.record(&bridge.game_id, EventKind::GameStart, json!({}));
+}
+
+/// `configVersion` ties the run to the remote config profile it was played
+/// with, so rollout groups can be compared.
+fn end_session(
+    bridge: Res<BevyBridge>,
+    config: Res<RemoteConfig>,
+    mut buffer: ResMut<TelemetryBuffer>,
+) {
+    let duration_ms = now_ms() - buffer.session_started_ms;
+    buffer.record(
+        &bridge.game_id,
+        EventKind::GameEnd,
+        json!({
+            "score": bridge.current_score,
+            "durationMs": duration_ms,
+            "configVersion": config.version,
+        }),
+    );
+}
+
+fn collect_events(
+    mut events: EventReader<AnalyticsEvent>,
+    bridge: Res<BevyBridge>,
+    mut buffer: ResMut<TelemetryBuffer>,
+) {
+    for ev in events.read() {
+        buffer.record(&bridge.game_id, ev.kind, ev.data.clone());
+    }
+}
+
+fn requeue_failed(mut buffer: ResMut<TelemetryBuffer>) {
+    let failed = match FAILED_BATCHES.lock() {
+        Ok(mut f) => std::mem::take(&mut *f),
+        Err(_) => return,
+    };
+    // Oldest batch ends up first.  If the buffer filled up meanwhile, the
+    // re-queued events are the oldest and are the ones dropped.
+    for batch in failed.into_iter().rev() {
+        for record in batch.into_iter().rev() {
+            if buffer.events.len() >= BUFFER_CAPACITY {
+                buffer.dropped += 1;
+                continue;
+            }
+            buffer.events.push_front(record);
+        }
+    }
+}
+
+/// Real time, so a paused game still uploads.
+fn tick_flush_timer(time: Res<Time<Real>>, mut timer: ResMut<FlushTimer>) {
+    timer.0.tick(time.delta());
+}
+
+fn flush_due(timer: Res<FlushTimer>) -> bool {
+    timer.0.just_finished()
+}
+
+fn flush(mut buffer: ResMut<TelemetryBuffer>) {
+    if buffer.is_empty() || !crate::api::is_configured() {
+        return;
+    }
+    if UPLOAD_IN_FLIGHT.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let n = buffer.len().min(MAX_BATCH);
+    let batch: Vec<TelemetryRecord> = buffer.events.drain(..n).collect();
+    let body = json!({ "events": batch, "dropped": buffer.dropped });
+    buffer.dropped = 0;
+
+    crate::api::post_json("/telemetry", &body, move |resp| {
+        if resp.is_none() {
+            if let Ok(mut failed) = FAILED_BATCHES.lock() {
+                failed.push(batch);
+            }
+        }
+        UPLOAD_IN_FLIGHT.store(false, Ordering::Release);
+    });
+}
@@ -0,0 +1,169 @@
+//! Minimal HTTP bridge to the STEM Adventures API.
+//!
+//! The React shell owns authentication; it hands the engine a base URL and
+//! the current access token via [`set_api_config`].  Engine features that
+//! talk to the server (ghost replays, telemetry, …) go through
+//! [`get_json`] / [`post_json`], which run on the browser's event loop and
+//! report back through a callback.  Callbacks must not touch the Bevy
+//! world directly — push the result into a static queue that a system
+//! drains, the same way `asset_loader` handles uploads.
+
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+struct ApiConfig {
+    base_url: String,
+    token: String,
+}
+
+static API_CONFIG: Mutex<Option<ApiConfig>> = Mutex::new(None);
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Configure the API endpoint, e.g. `set_api_config("/api/v1", accessToken)`.
+/// Call again whenever the shell refreshes its access token.
+#[wasm_bindgen]
+pub fn set_api_config(base_url: &str, token: &str) {
+    if let Ok(mut cfg) = API_CONFIG.lock() {
+        *cfg = Some(ApiConfig {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        });
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Requests
+// ---------------------------------------------------------------------------
+
+/// Whether the shell has configured the API yet.
+pub fn is_configured() -> bool {
+    API_CONFIG.lock().map(|c| c.is_some()).unwrap_or(false)
+}
+
+/// `GET {base_url}{path}`; `on_done` receives the parsed body, or `None` on
+/// network/HTTP/parse failure or when the API isn't configured.
+pub fn get_json(path: &str, on_done: impl FnOnce(Option<serde_json::Value>) + 'static) {
+    send("GET", path, None, on_done);
+}
+
+/// `POST {base_url}{path}` with a JSON body.
+pub fn post_json(
+    path: &str,
+    body: &serde_json::Value,
+    on_done: impl FnOnce(Option<serde_json::Value>) + 'static,
+) {
+    send("POST", path, Some(body.to_string()), on_done);
+}
+
+/// `POST {base_url}{path}` for callers that are already async (e.g. a
+/// sync loop that must await the upload before touching local storage).
+pub async fn post_json_async(path: &str, body: &serde_json::Value) -> Option<serde_json::Value> {
+    let request = build_request("POST", path, Some(&body.to_string()), false)?;
+    fetch(request).await
+}
+
+/// `POST {base_url}{path}` without waiting for the response.  The request
+/// is dispatched before this returns and marked keep-alive, so it goes out
+/// even if the engine never runs again — for the panic hook in [`crash`].
+/// Returns false if it couldn't be sent.
+///
+/// [`crash`]: crate::crash
+pub fn post_json_detached(path: &str, body: &serde_json::Value) -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    match build_request("POST", path, Some(&body.to_string()), true) {
+        Some(request) => {
+            // The promise is dropped unobserved; nothing is left to poll it.
+            let _ = window.fetch_with_request(&request);
+            true
+        }
+        None => false,
+    }
+}
+
+/// WebSocket URL for an API path such as `/presence/ws`.  Browsers can't
+/// set headers on the upgrade, so the access token goes in the query
+/// string.  A relative base URL is resolved against the page's origin.
+pub fn socket_url(path: &str) -> Option<String> {
+    let cfg = API_CONFIG.lock().ok()?;
+    let cfg = cfg.as_ref()?;
+    let base = if cfg.base_url.starts_with("http") {
+        // http -> ws, https -> wss
+        cfg.base_url.replacen("http", "ws", 1)
+    } else {
+        let location = web_sys::window()?.location();
+        let scheme = if location.protocol().ok()? == "https:" { "wss" } else { "ws" };
+        format!("{}://{}{}", scheme, location.host().ok()?, cfg.base_url)
+    };
+    Some(format!("{}{}?token={}", base, path, cfg.token))
+}
+
+fn send(
+    method: &str,
+    path: &str,
+    body: Option<String>,
+    on_done: impl FnOnce(Option<serde_json::Value>) + 'static,
+) {
+    let request = match build_request(method, path, body.as_deref(), false) {
+        Some(r) => r,
+        None => {
+            on_done(None);
+            return;
+        }
+    };
+
+    wasm_bindgen_futures::spawn_local(async move {
+        on_done(fetch(request).await);
+    });
+}
+
+fn build_request(
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+    keepalive: bool,
+) -> Option<web_sys::Request> {
+    let (url, token) = {
+        let cfg = API_CONFIG.lock().ok()?;
+        let cfg = cfg.as_ref()?;
+        (format!("{}{}", cfg.base_url, path), cfg.token.clone())
+    };
+
+    let init = web_sys::RequestInit::new();
+    init.set_method(method);
+    init.set_mode(web_sys::RequestMode::Cors);
+    if keepalive {
+        // web-sys has no setter for it; RequestInit is a plain dictionary.
+        js_sys::Reflect::set(&init, &JsValue::from_str("keepalive"), &JsValue::TRUE).ok()?;
+    }
+    if let Some(b) = body {
+        init.set_body(&JsValue::from_str(b));
+    }
+
+    let request = web_sys::Request::new_with_str_and_init(&url, &init).ok()?;
+    let headers = request.headers();
+    headers.set("Content-Type", "application/json").ok()?;
+    if !token.is_empty() {
+        headers
+            .set("Authorization", &format!("Bearer {}", token))
+            .ok()?;
+    }
+    Some(request)
+}
+
+async fn fetch(request: web_sys::Request) -> Option<serde_json::Value> {
+    let window = web_sys::window()?;
+    let resp = JsFuture::from(window.fetch_with_request(&request)).await.ok()?;
+    let resp: web_sys::Response = resp.dyn_into().ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    let text = JsFuture::from(resp.text().ok()?).await.ok()?;
+    serde_json::from_str(&text.as_string()?).ok()
+}
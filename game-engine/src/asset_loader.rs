@@ -7,6 +7,9 @@
 //!
 //! **glTF uploads** are stored as raw bytes.  A helper function creates a
 //! browser Blob URL that Bevy's asset server can `load()`.
+//!
+//! **Font uploads** (.ttf/.otf) are stored as `Handle<Font>` keyed by
+//! script; [`i18n`](crate::i18n) draws text in that script with them.
 
 use bevy::prelude::*;
 use bevy::render::render_asset::RenderAssetUsages;
@@ -44,6 +47,9 @@ pub struct CustomAssets {
     pub gltf_data: HashMap<String, Vec<u8>>,
     /// Blob URLs created for uploaded .glb files.
     pub gltf_urls: HashMap<String, String>,
+    /// Fonts keyed by script (`"cjk"`, `"arabic"`, `"devanagari"`, or
+    /// `"latin"` to replace the built-in one).
+    pub fonts: HashMap<String, Handle<Font>>,
 }
 
 // ---------------------------------------------------------------------------
@@ -62,10 +68,16 @@ enum UploadKind {
     Sprite,
     Background,
     Gltf,
+    Font,
 }
 
 static PENDING_UPLOADS: Mutex<Vec<PendingUpload>> = Mutex::new(Vec::new());
 
+/// Uploads received but not yet turned into assets.
+pub(crate) fn pending_uploads() -> usize {
+    PENDING_UPLOADS.lock().map(|q| q.len()).unwrap_or(0)
+}
+
 // ---------------------------------------------------------------------------
 // wasm-bindgen exports  (called from JavaScript / React)
 // ---------------------------------------------------------------------------
@@ -124,11 +136,30 @@ pub fn upload_gltf(name: &str, data: &[u8]) {
     }
 }
 
+/// Upload a .ttf or .otf font for text in `script` — `"cjk"`, `"arabic"`
+/// or `"devanagari"`, or `"latin"` to replace the built-in font.
+#[wasm_bindgen]
+pub fn upload_font(script: &str, data: &[u8]) {
+    if let Ok(mut q) = PENDING_UPLOADS.lock() {
+        q.push(PendingUpload {
+            role: script.to_string(),
+            kind: UploadKind::Font,
+            data: data.to_vec(),
+            width: 0,
+            height: 0,
+        });
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Bevy system — drains the queue and creates Bevy assets
 // ---------------------------------------------------------------------------
 
-fn process_uploads(mut custom: ResMut<CustomAssets>, mut images: ResMut<Assets<Image>>) {
+fn process_uploads(
+    mut custom: ResMut<CustomAssets>,
+    mut images: ResMut<Assets<Image>>,
+    mut fonts: ResMut<Assets<Font>>,
+) {
     let uploads: Vec<PendingUpload> = match PENDING_UPLOADS.lock() {
         Ok(mut q) => q.drain(..).collect(),
         Err(_) => return,
@@ -177,6 +208,12 @@ fn process_uploads(mut custom: ResMut<CustomAssets>, mut images: ResMut<Assets<I
                 }
                 custom.gltf_data.insert(up.role, up.data);
             }
+            UploadKind::Font => {
+                if let Ok(font) = Font::try_from_bytes(up.data) {
+                    let handle = fonts.add(font);
+                    custom.fonts.insert(up.role, handle);
+                }
+            }
         }
     }
 }
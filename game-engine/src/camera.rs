@@ -0,0 +1,186 @@
+//! Camera follow, screen shake and zoom.
+//!
+//! The 2‑D camera spawned at startup looks at the origin, where every game
+//! centres its scene.  This module moves it:
+//!
+//! * **Follow** — a game puts [`CameraFollow`] on the entity to track.  The
+//!   camera stays put while the target is inside the deadzone, then eases
+//!   after it, optionally kept within `bounds`.
+//! * **Shake** — trauma-based.  Impacts add [`trauma`](CameraRig::trauma);
+//!   the camera's offset and roll grow with its square and it drains over
+//!   about a second, so small knocks barely register and big ones stack.
+//!   Every [`Rumble`] a game sends for a collision adds trauma in
+//!   proportion to its strength; games send [`Shake`] for anything else,
+//!   such as explosions.  Reduced motion turns it off.
+//! * **Zoom** — `=` and `-` (or the numpad's `+` and `-`) zoom in and out
+//!   between [`MIN_ZOOM`] and [`MAX_ZOOM`].
+//!
+//! Everything resets when the game ends, so the next one starts centred.
+
+use bevy::prelude::*;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::games::GameplaySet;
+use crate::input::Rumble;
+use crate::AppState;
+
+/// Trauma added by a full-strength [`Rumble`].
+const RUMBLE_TRAUMA: f32 = 0.5;
+/// Trauma drained per second.
+const TRAUMA_DECAY: f32 = 1.2;
+/// Furthest the camera is thrown at full trauma, in world units.
+const MAX_OFFSET: f32 = 18.0;
+/// Largest roll at full trauma, in radians.
+const MAX_ROLL: f32 = 0.05;
+/// How fast the shake wobbles.
+const SHAKE_FREQ: f32 = 35.0;
+
+pub const MIN_ZOOM: f32 = 0.75;
+pub const MAX_ZOOM: f32 = 2.0;
+/// Each key press zooms by this factor.
+const ZOOM_STEP: f32 = 1.25;
+/// How quickly the zoom eases to its target, per second.
+const ZOOM_RATE: f32 = 10.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRig>()
+            .add_event::<Shake>()
+            .add_systems(
+                Update,
+                (
+                    zoom_controls.in_set(GameplaySet),
+                    (add_trauma, follow, apply).chain().after(GameplaySet),
+                ),
+            )
+            .add_systems(OnExit(AppState::Playing), reset);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components, resources and events
+// ---------------------------------------------------------------------------
+
+/// Put on the entity the camera should track.  Only one at a time.
+#[derive(Component, Debug, Clone)]
+pub struct CameraFollow {
+    /// Half-size of the box around the camera centre that the target can
+    /// move in without the camera following.
+    pub deadzone: Vec2,
+    /// How quickly the camera catches up, per second; higher is snappier.
+    pub smoothing: f32,
+    /// Where the camera centre may go, so it doesn't show past the level.
+    pub bounds: Option<Rect>,
+}
+
+impl CameraFollow {
+    pub fn new(deadzone: Vec2) -> Self {
+        Self { deadzone, smoothing: 6.0, bounds: None }
+    }
+
+    /// Keeps the camera centre inside `bounds`.
+    pub fn within(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+}
+
+/// Where the camera looks and how it shakes and zooms.
+#[derive(Resource, Debug)]
+pub struct CameraRig {
+    /// The camera centre, before shake.
+    pub focus: Vec2,
+    /// `0.0..=1.0`; shake strength is its square.
+    pub trauma: f32,
+    /// Magnification the camera eases towards; 1.0 is the games' own scale.
+    pub zoom: f32,
+    current_zoom: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self { focus: Vec2::ZERO, trauma: 0.0, zoom: 1.0, current_zoom: 1.0 }
+    }
+}
+
+/// Adds this much trauma (`0.0..=1.0`) to the camera shake.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Shake(pub f32);
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn zoom_controls(keys: Res<ButtonInput<KeyCode>>, mut rig: ResMut<CameraRig>) {
+    let factor = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        ZOOM_STEP
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        1.0 / ZOOM_STEP
+    } else {
+        return;
+    };
+    rig.zoom = (rig.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+}
+
+fn add_trauma(
+    settings: Res<AccessibilitySettings>,
+    mut rig: ResMut<CameraRig>,
+    mut rumbles: EventReader<Rumble>,
+    mut shakes: EventReader<Shake>,
+) {
+    let added: f32 = rumbles
+        .read()
+        .map(|r| r.strength * RUMBLE_TRAUMA)
+        .chain(shakes.read().map(|s| s.0))
+        .sum();
+    if added > 0.0 && !settings.reduced_motion {
+        rig.trauma = (rig.trauma + added).min(1.0);
+    }
+}
+
+fn follow(time: Res<Time>, mut rig: ResMut<CameraRig>, targets: Query<(&Transform, &CameraFollow)>) {
+    let Ok((tf, follow)) = targets.get_single() else { return };
+    let offset = tf.translation.truncate() - rig.focus;
+    let mut goal = rig.focus + offset - offset.clamp(-follow.deadzone, follow.deadzone);
+    if let Some(bounds) = follow.bounds {
+        goal = goal.clamp(bounds.min, bounds.max);
+    }
+    let t = 1.0 - (-follow.smoothing * time.delta_secs()).exp();
+    rig.focus = rig.focus.lerp(goal, t);
+}
+
+fn apply(
+    time: Res<Time>,
+    mut rig: ResMut<CameraRig>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let dt = time.delta_secs();
+    rig.trauma = (rig.trauma - TRAUMA_DECAY * dt).max(0.0);
+    rig.current_zoom += (rig.zoom - rig.current_zoom) * (1.0 - (-ZOOM_RATE * dt).exp());
+
+    let shake = rig.trauma * rig.trauma;
+    let t = time.elapsed_secs() * SHAKE_FREQ;
+    let offset = Vec2::new(wobble(t, 0.0), wobble(t, 1.7)) * MAX_OFFSET * shake;
+    let roll = wobble(t, 3.1) * MAX_ROLL * shake;
+
+    for (mut tf, mut projection) in &mut cameras {
+        tf.translation = (rig.focus + offset).extend(tf.translation.z);
+        tf.rotation = Quat::from_rotation_z(roll);
+        projection.scale = 1.0 / rig.current_zoom;
+    }
+}
+
+/// Smooth noise in `-1.0..=1.0`; `seed` gives each axis its own pattern.
+fn wobble(t: f32, seed: f32) -> f32 {
+    0.6 * (t + seed).sin() + 0.4 * (2.3 * t + 1.3 * seed).sin()
+}
+
+fn reset(mut rig: ResMut<CameraRig>) {
+    *rig = CameraRig::default();
+}
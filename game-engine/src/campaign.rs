@@ -0,0 +1,287 @@
+//! Campaign: games chained into themed worlds.
+//!
+//! Every campaign level is one of the existing games.  When a run ends, the
+//! final score is turned into 0–3 stars using the level's score thresholds,
+//! and the best result is kept in `localStorage`.  Levels unlock in order:
+//! a level becomes playable once the level before it — across world
+//! boundaries, so finishing a world opens the next one — has at least one
+//! star.
+//!
+//! Runs count towards the campaign however they were started; the shell
+//! uses `get_campaign_progress()` to render the world map and decide which
+//! levels to offer.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::{AppState, BevyBridge};
+
+const STORAGE_KEY: &str = "stem_campaign";
+
+// ---------------------------------------------------------------------------
+// Campaign data
+// ---------------------------------------------------------------------------
+
+pub struct CampaignWorld {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub levels: &'static [CampaignLevel],
+}
+
+pub struct CampaignLevel {
+    pub game_id: &'static str,
+    /// Scores needed for one, two and three stars.
+    pub star_scores: [i32; 3],
+}
+
+impl CampaignLevel {
+    pub fn stars_for(&self, score: i32) -> u8 {
+        self.star_scores.iter().filter(|&&s| score >= s).count() as u8
+    }
+}
+
+const fn level(game_id: &'static str, star_scores: [i32; 3]) -> CampaignLevel {
+    CampaignLevel { game_id, star_scores }
+}
+
+pub const WORLDS: &[CampaignWorld] = &[
+    CampaignWorld {
+        id: "chemistry",
+        name: "Chemistry World",
+        levels: &[
+            level("chemistry_escape", [1000, 1000, 1000]),
+            level("molecular_split", [500, 1500, 3000]),
+            level("color_lab_quest", [500, 800, 1000]),
+            level("element_match", [1500, 3000, 5000]),
+        ],
+    },
+    CampaignWorld {
+        id: "physics",
+        name: "Physics World",
+        levels: &[
+            level("gravity_shift_run", [100, 300, 600]),
+            level("physics_master_billiards", [300, 600, 1000]),
+            level("cable_car_conundrum", [500, 1500, 3000]),
+        ],
+    },
+    CampaignWorld {
+        id: "engineering",
+        name: "Engineering World",
+        levels: &[
+            level("heavy_gear_delivery", [100, 300, 600]),
+            level("rover_field_test", [100, 300, 600]),
+            level("aero_engineering", [250, 750, 1500]),
+            level("drone_defense", [500, 1500, 3000]),
+            level("bridge_builder", [1000, 2500, 3500]),
+            level("power_grid", [2000, 4000, 6000]),
+        ],
+    },
+    CampaignWorld {
+        id: "logic",
+        name: "Logic World",
+        levels: &[
+            level("robot_repair_bay", [500, 500, 500]),
+            level("logicrons_grid_shift", [500, 1000, 1500]),
+            level("hydro_logic_puzzles", [500, 1000, 1500]),
+            level("code_runner", [900, 1400, 1800]),
+        ],
+    },
+];
+
+/// Every level in play order.
+fn all_levels() -> impl Iterator<Item = &'static CampaignLevel> {
+    WORLDS.iter().flat_map(|w| w.levels.iter())
+}
+
+pub fn find_level(game_id: &str) -> Option<&'static CampaignLevel> {
+    all_levels().find(|l| l.game_id == game_id)
+}
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CampaignProgress>()
+            .add_systems(Startup, load_progress)
+            .add_systems(OnEnter(AppState::GameOver), record_result)
+            .add_systems(Update, publish_progress);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Progress
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub stars: u8,
+    #[serde(rename = "bestScore")]
+    pub best_score: i32,
+}
+
+/// Outcome of the most recent campaign run, for the results screen.
+#[derive(Clone, Debug, Serialize)]
+pub struct LevelResult {
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub score: i32,
+    pub stars: u8,
+    #[serde(rename = "newBest")]
+    pub new_best: bool,
+    /// Levels this run unlocked.
+    pub unlocked: Vec<&'static str>,
+}
+
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    #[serde(default)]
+    pub levels: HashMap<String, LevelRecord>,
+    #[serde(skip)]
+    pub last_result: Option<LevelResult>,
+}
+
+impl CampaignProgress {
+    pub fn stars(&self, game_id: &str) -> u8 {
+        self.levels.get(game_id).map_or(0, |r| r.stars)
+    }
+
+    pub fn is_unlocked(&self, game_id: &str) -> bool {
+        let mut prev: Option<&CampaignLevel> = None;
+        for level in all_levels() {
+            if level.game_id == game_id {
+                return prev.is_none_or(|p| self.stars(p.game_id) > 0);
+            }
+            prev = Some(level);
+        }
+        false
+    }
+
+    fn unlocked_levels(&self) -> Vec<&'static str> {
+        all_levels()
+            .map(|l| l.game_id)
+            .filter(|id| self.is_unlocked(id))
+            .collect()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut total_stars = 0u32;
+        let mut max_stars = 0u32;
+
+        let worlds: Vec<_> = WORLDS
+            .iter()
+            .map(|world| {
+                let levels: Vec<_> = world
+                    .levels
+                    .iter()
+                    .map(|l| {
+                        let record = self.levels.get(l.game_id).cloned().unwrap_or_default();
+                        json!({
+                            "gameId": l.game_id,
+                            "unlocked": self.is_unlocked(l.game_id),
+                            "stars": record.stars,
+                            "bestScore": record.best_score,
+                            "starScores": l.star_scores,
+                        })
+                    })
+                    .collect();
+                let stars: u32 = world.levels.iter().map(|l| self.stars(l.game_id) as u32).sum();
+                let world_max = world.levels.len() as u32 * 3;
+                total_stars += stars;
+                max_stars += world_max;
+                json!({
+                    "id": world.id,
+                    "name": world.name,
+                    "unlocked": world.levels.first().is_some_and(|l| self.is_unlocked(l.game_id)),
+                    "completed": world.levels.iter().all(|l| self.stars(l.game_id) > 0),
+                    "stars": stars,
+                    "maxStars": world_max,
+                    "levels": levels,
+                })
+            })
+            .collect();
+
+        json!({
+            "worlds": worlds,
+            "totalStars": total_stars,
+            "maxStars": max_stars,
+            "lastResult": self.last_result,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Campaign progress as JSON: every world with its levels' stars, best
+/// scores and lock state, plus the result of the last campaign run, e.g.
+/// `{"worlds":[{"id":"chemistry","name":"Chemistry World","unlocked":true,
+/// "stars":4,"maxStars":9,"levels":[...]}],"totalStars":4,"maxStars":39,
+/// "lastResult":{"gameId":"molecular_split","score":1800,"stars":2,...}}`.
+#[wasm_bindgen]
+pub fn get_campaign_progress() -> String {
+    crate::get_js_global("__bevy_campaign").unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn load_progress(mut progress: ResMut<CampaignProgress>) {
+    let saved = crate::persistence::local_storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<CampaignProgress>(&json).ok());
+    if let Some(saved) = saved {
+        progress.levels = saved.levels;
+    }
+}
+
+fn record_result(bridge: Res<BevyBridge>, mut progress: ResMut<CampaignProgress>) {
+    let Some(level) = find_level(&bridge.game_id) else {
+        return;
+    };
+
+    let score = bridge.current_score;
+    let stars = level.stars_for(score);
+    let unlocked_before = progress.unlocked_levels();
+
+    let record = progress.levels.entry(level.game_id.to_string()).or_default();
+    let new_best = score > record.best_score;
+    record.stars = record.stars.max(stars);
+    record.best_score = record.best_score.max(score);
+
+    let unlocked = progress
+        .unlocked_levels()
+        .into_iter()
+        .filter(|id| !unlocked_before.contains(id))
+        .collect();
+    progress.last_result = Some(LevelResult {
+        game_id: level.game_id.to_string(),
+        score,
+        stars,
+        new_best,
+        unlocked,
+    });
+
+    if let (Some(storage), Ok(json)) = (
+        crate::persistence::local_storage(),
+        serde_json::to_string(&*progress),
+    ) {
+        storage.set_item(STORAGE_KEY, &json).ok();
+    }
+}
+
+fn publish_progress(progress: Res<CampaignProgress>) {
+    if !progress.is_changed() {
+        return;
+    }
+    crate::set_js_global("__bevy_campaign", &progress.to_json().to_string());
+}
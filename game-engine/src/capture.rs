@@ -0,0 +1,233 @@
+//! Screenshots and short clips for sharing.
+//!
+//! * `capture_screenshot()` resolves to the PNG bytes (a `Uint8Array`) of
+//!   the next rendered frame, or `null` if it couldn't be encoded.  It's
+//!   read back from the GPU with Bevy's [`Screenshot`], since the WebGL
+//!   canvas itself is cleared after every frame.
+//! * `capture_clip()` returns the last 5–10 seconds of the game canvas as
+//!   a video `Blob` — WebM, or MP4 where that's all the browser records —
+//!   or `undefined` when nothing is being recorded.  Two `MediaRecorder`s
+//!   take turns: each restarts every 2 × [`CLIP_SECS`], half a cycle apart
+//!   from the other, so one of them always holds at least [`CLIP_SECS`].
+//!
+//! Recording starts with a game and runs until the engine returns to the
+//! menu, so a clip can still be taken from the game over screen.  The
+//! shell decides whether to download or upload what it gets.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::window::PrimaryWindow;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::AppState;
+
+/// Shortest clip `capture_clip` returns, once a game has run this long.
+pub const CLIP_SECS: f32 = 5.0;
+const CLIP_FPS: f64 = 30.0;
+/// Recorders hand over their data this often, so a clip lags the game by
+/// at most this much.
+const CHUNK_MS: i32 = 500;
+/// Tried in order; Safari only records MP4.
+const MIME_TYPES: &[&str] = &["video/webm;codecs=vp9", "video/webm", "video/mp4"];
+
+/// A screenshot has been requested from the GPU and not yet delivered.
+static SCREENSHOT_IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), start_recording)
+            .add_systems(Update, (request_screenshot, rotate_recorders))
+            .add_systems(OnEnter(AppState::Menu), stop_recording);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Recording
+// ---------------------------------------------------------------------------
+
+/// One `MediaRecorder` and the chunks it has produced since it started.
+struct Recorder {
+    recorder: web_sys::MediaRecorder,
+    chunks: Rc<RefCell<Vec<web_sys::Blob>>>,
+    started: f32,
+    _on_data: Closure<dyn FnMut(web_sys::BlobEvent)>,
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Detach first: the final `dataavailable` arrives after the
+        // closure is gone.
+        self.recorder.set_ondataavailable(None);
+        self.recorder.stop().ok();
+    }
+}
+
+struct Recording {
+    stream: web_sys::MediaStream,
+    mime: &'static str,
+    recorders: [Option<Recorder>; 2],
+    /// When each recorder next (re)starts, in real seconds.
+    due: [f32; 2],
+}
+
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+    /// `resolve` functions of pending `capture_screenshot` promises.
+    static SCREENSHOT_WAITERS: RefCell<Vec<js_sys::Function>> = const { RefCell::new(Vec::new()) };
+}
+
+fn start_recorder(stream: &web_sys::MediaStream, mime: &str, now: f32) -> Option<Recorder> {
+    let options = web_sys::MediaRecorderOptions::new();
+    options.set_mime_type(mime);
+    let recorder =
+        web_sys::MediaRecorder::new_with_media_stream_and_media_recorder_options(stream, &options).ok()?;
+
+    let chunks = Rc::new(RefCell::new(Vec::new()));
+    let on_data = {
+        let chunks = chunks.clone();
+        Closure::<dyn FnMut(web_sys::BlobEvent)>::new(move |event: web_sys::BlobEvent| {
+            if let Some(blob) = event.data() {
+                chunks.borrow_mut().push(blob);
+            }
+        })
+    };
+    recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+    recorder.start_with_time_slice(CHUNK_MS).ok()?;
+    Some(Recorder { recorder, chunks, started: now, _on_data: on_data })
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// PNG bytes of the next frame, as a promise of a `Uint8Array`.
+#[wasm_bindgen]
+pub fn capture_screenshot() -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, _reject| {
+        SCREENSHOT_WAITERS.with(|w| w.borrow_mut().push(resolve));
+    })
+}
+
+/// The last few seconds of play as a video `Blob`, if recording.
+#[wasm_bindgen]
+pub fn capture_clip() -> Option<web_sys::Blob> {
+    RECORDING.with(|r| {
+        let r = r.borrow();
+        let recording = r.as_ref()?;
+        // The longer-running recorder holds the longer clip.
+        let recorder = recording
+            .recorders
+            .iter()
+            .flatten()
+            .min_by(|a, b| a.started.total_cmp(&b.started))?;
+        let parts = js_sys::Array::new();
+        for chunk in recorder.chunks.borrow().iter() {
+            parts.push(chunk);
+        }
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(recording.mime);
+        web_sys::Blob::new_with_blob_sequence_and_options(&parts, &options).ok()
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn start_recording(windows: Query<&Window, With<PrimaryWindow>>, time: Res<Time<Real>>) {
+    if RECORDING.with(|r| r.borrow().is_some()) {
+        return;
+    }
+    let Some(mime) = MIME_TYPES
+        .iter()
+        .copied()
+        .find(|m| web_sys::MediaRecorder::is_type_supported(m))
+    else {
+        return;
+    };
+    let Some(selector) = windows.get_single().ok().and_then(|w| w.canvas.clone()) else {
+        return;
+    };
+    let canvas = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.query_selector(&selector).ok().flatten())
+        .and_then(|e| e.dyn_into::<web_sys::HtmlCanvasElement>().ok());
+    let Some(stream) = canvas.and_then(|c| c.capture_stream_with_frame_request_rate(CLIP_FPS).ok()) else {
+        return;
+    };
+
+    let now = time.elapsed_secs();
+    RECORDING.with(|r| {
+        *r.borrow_mut() = Some(Recording {
+            stream,
+            mime,
+            recorders: [None, None],
+            due: [now, now + CLIP_SECS],
+        });
+    });
+}
+
+/// Restarts each recorder once it holds two clips' worth, so neither
+/// grows without bound.
+fn rotate_recorders(time: Res<Time<Real>>) {
+    let now = time.elapsed_secs();
+    RECORDING.with(|r| {
+        let mut r = r.borrow_mut();
+        let Some(recording) = r.as_mut() else { return };
+        for i in 0..recording.recorders.len() {
+            if now >= recording.due[i] {
+                // Replacing the old recorder stops it.
+                recording.recorders[i] = start_recorder(&recording.stream, recording.mime, now);
+                recording.due[i] = now + 2.0 * CLIP_SECS;
+            }
+        }
+    });
+}
+
+fn stop_recording() {
+    RECORDING.with(|r| {
+        if let Some(recording) = r.borrow_mut().take() {
+            for track in recording.stream.get_tracks().iter() {
+                if let Ok(track) = track.dyn_into::<web_sys::MediaStreamTrack>() {
+                    track.stop();
+                }
+            }
+        }
+    });
+}
+
+fn request_screenshot(mut commands: Commands) {
+    let waiting = SCREENSHOT_WAITERS.with(|w| !w.borrow().is_empty());
+    if !waiting || SCREENSHOT_IN_FLIGHT.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    commands.spawn(Screenshot::primary_window()).observe(deliver_screenshot);
+}
+
+fn deliver_screenshot(trigger: Trigger<ScreenshotCaptured>) {
+    SCREENSHOT_IN_FLIGHT.store(false, Ordering::Release);
+    let png = encode_png(&trigger.event().0);
+    let bytes: JsValue = png.map_or(JsValue::NULL, |png| js_sys::Uint8Array::from(png.as_slice()).into());
+    for resolve in SCREENSHOT_WAITERS.with(|w| std::mem::take(&mut *w.borrow_mut())) {
+        resolve.call1(&JsValue::NULL, &bytes).ok();
+    }
+}
+
+fn encode_png(frame: &Image) -> Option<Vec<u8>> {
+    let rgba = frame.clone().try_into_dynamic().ok()?.to_rgba8();
+    let mut png = Vec::new();
+    rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
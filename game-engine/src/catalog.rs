@@ -0,0 +1,156 @@
+//! The games this engine build can play, for the shell's game list.
+//!
+//! `get_available_games()` returns [`GAMES`] as a JSON array, so the list
+//! the shell shows always matches the engine it loaded:
+//!
+//! ```json
+//! [{"id":"campus_dash","title":"Campus Dash","description":"...","modes":["classic","time_attack","endless","zen"],
+//!   "inputs":["keyboard","gamepad","pointer"],"thumbnailColor":"#ff9926","bot":false,"render":"2d"}]
+//! ```
+//!
+//! Every game plays Classic, Time Attack and Endless (see
+//! [`game_mode`](crate::game_mode)); Zen is only listed for games that
+//! forgive mistakes in it, and Versus for the games with a second player.
+//! `"bot": true` marks the games that can play a bot match (see
+//! [`ai`](crate::ai)), which the matchmaker falls back to.
+//! A game added to [`GamePlugin`](crate::games::GamePlugin) needs its entry
+//! here to be offered.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::game_mode::GameMode;
+
+/// How a game can be controlled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    Keyboard,
+    Gamepad,
+    /// Mouse or touch.
+    Pointer,
+}
+
+use Input::{Gamepad, Keyboard, Pointer};
+
+pub struct GameInfo {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub inputs: &'static [Input],
+    /// Background of the game's tile until it has a thumbnail.
+    pub thumbnail_color: &'static str,
+    /// Honours [`GameMode::can_lose`].
+    pub zen: bool,
+    pub versus: bool,
+    /// Plays a bot match.
+    pub bot: bool,
+    pub three_d: bool,
+}
+
+impl GameInfo {
+    pub fn modes(&self) -> Vec<GameMode> {
+        let mut modes = vec![GameMode::Classic, GameMode::TimeAttack, GameMode::Endless];
+        if self.zen {
+            modes.push(GameMode::Zen);
+        }
+        if self.versus {
+            modes.push(GameMode::Versus);
+        }
+        modes
+    }
+
+    pub fn json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "title": self.title,
+            "description": self.description,
+            "modes": self.modes(),
+            "inputs": self.inputs,
+            "thumbnailColor": self.thumbnail_color,
+            "bot": self.bot,
+            "render": if self.three_d { "3d" } else { "2d" },
+        })
+    }
+}
+
+const fn game(
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    inputs: &'static [Input],
+    thumbnail_color: &'static str,
+) -> GameInfo {
+    GameInfo { id, title, description, inputs, thumbnail_color, zen: false, versus: false, bot: false, three_d: false }
+}
+
+const fn zen(info: GameInfo) -> GameInfo {
+    GameInfo { zen: true, ..info }
+}
+
+const fn versus(info: GameInfo) -> GameInfo {
+    GameInfo { versus: true, ..info }
+}
+
+const fn bot(info: GameInfo) -> GameInfo {
+    GameInfo { bot: true, ..info }
+}
+
+const fn three_d(info: GameInfo) -> GameInfo {
+    GameInfo { three_d: true, ..info }
+}
+
+const KEYS: &[Input] = &[Keyboard];
+const POINTER: &[Input] = &[Pointer];
+const KEYS_POINTER: &[Input] = &[Keyboard, Pointer];
+const ACTIONS: &[Input] = &[Keyboard, Gamepad, Pointer];
+
+pub const GAMES: &[GameInfo] = &[
+    game("aero_engineering", "Aero Engineering", "Fly a top-down jet with full 360-degree turns and shoot down drones.", KEYS_POINTER, "#3f8cf2"),
+    game("binary_blitz", "Binary Blitz", "Flip the switches to match falling binary numbers before they land.", KEYS_POINTER, "#33cc66"),
+    game("bridge_builder", "Bridge Builder", "Build a bridge within budget and watch whether it holds the load.", KEYS_POINTER, "#cc8033"),
+    game("cable_car_conundrum", "Cable Car Conundrum", "Ride momentum along the cable, dodging obstacles and grabbing pickups.", KEYS_POINTER, "#33cccc"),
+    zen(game("campus_dash", "Campus Dash", "Run and jump across campus at ever higher speed.", ACTIONS, "#ff9926")),
+    game("campus_guard", "Campus Guard", "Place turrets to stop enemies marching along the path.", POINTER, "#9933e6"),
+    game("chemistry_escape", "Chemistry Escape", "Platform through the lab past hazards and chemical key-locks.", KEYS, "#d92619"),
+    game("code_runner", "Code Runner", "Queue up moves and loops to program a robot to the goal.", KEYS_POINTER, "#26a6d9"),
+    game("color_lab_quest", "Color Lab Quest", "Collect colour orbs to stand on the platforms that match.", KEYS, "#ff80b3"),
+    game("demo_day", "Demo Day", "Place explosives just right to bring the structure down.", KEYS_POINTER, "#e6b319"),
+    zen(game("drone_defense", "Drone Defense", "Jetpack around and aim in any direction to hold off the drones.", ACTIONS, "#40a6f2")),
+    game("element_match", "Element Match", "Pair chemical elements with their names and atomic numbers.", POINTER, "#4db3b3"),
+    game("find_the_principal", "Find the Principal", "Climb ladders and stomp enemies to reach the principal.", KEYS, "#ff6640"),
+    bot(game("formula_stem", "Formula STEM", "Drift through the waypoints and beat the rival car to the line.", KEYS, "#e63326")),
+    game("geology_deep_dive", "Geology Deep Dive", "Dig for minerals while keeping an eye on your fuel.", KEYS, "#8c5933"),
+    game("gravity_shift_run", "Gravity Shift Run", "Flip gravity with one button to run along the floor or ceiling.", ACTIONS, "#9966e6"),
+    game("heavy_gear_delivery", "Heavy Gear Delivery", "Drive a truck over rough ground without dropping the cargo.", KEYS_POINTER, "#b38c33"),
+    game("history_vault_escape", "History Vault Escape", "Work out the traps and switches to escape each vault.", KEYS, "#ccad66"),
+    game("hydro_logic_puzzles", "Hydro Logic Puzzles", "Push blocks and drop orbs to route the water.", KEYS, "#3399e6"),
+    game("lab_breach", "Lab Breach", "Run and gun through the breached lab.", ACTIONS, "#66cc33"),
+    game("logicrons_grid_shift", "Logicron's Grid Shift", "Roll the block across the grid to the exit without falling off.", KEYS, "#00e680"),
+    game("molecular_split", "Molecular Split", "Fire the harpoon to split molecules into ever smaller pieces.", KEYS, "#8c1ab3"),
+    game("outbreak_control", "Outbreak Control", "Vaccinate and quarantine to stop the infection spreading.", KEYS_POINTER, "#b34d4d"),
+    game("parkour_lab", "Parkour Lab", "Time your jumps to keep your momentum through the course.", ACTIONS, "#ffd91a"),
+    bot(versus(game("physics_master_billiards", "Physics Master Billiards", "Drag to aim and set the power, then pot the balls.", KEYS_POINTER, "#338c40"))),
+    zen(game("power_grid", "Power Grid", "Build solar, wind and storage to keep the city lit all day.", KEYS_POINTER, "#f2c21a")),
+    game("robot_repair_bay", "Robot Repair Bay", "Rotate the pipes to connect power and reboot the robots.", POINTER, "#99a6b3"),
+    three_d(game("rover_3d_expedition", "Rover 3D Expedition", "Drive a rover over 3-D terrain collecting samples before the battery runs out.", &[Keyboard, Gamepad], "#d99e73")),
+    game("rover_field_test", "Rover Field Test", "Drive the rover over hilly terrain as far as its fuel allows.", KEYS_POINTER, "#ff9926"),
+    game("safety_first_defense", "Safety First Defense", "Shoot down the hazards before they reach the bottom.", KEYS_POINTER, "#e64d33"),
+    game("stem_celebration", "STEM Celebration", "Hit the notes in time with the beat.", KEYS, "#ff66b3"),
+    bot(versus(game("stem_project_volley", "STEM Project Volley", "Aim your arcs to knock out the other side's platform.", KEYS_POINTER, "#3f8cf2"))),
+];
+
+pub fn find(game_id: &str) -> Option<&'static GameInfo> {
+    GAMES.iter().find(|g| g.id == game_id)
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Every game this build can play, as a JSON array (see the module docs).
+#[wasm_bindgen]
+pub fn get_available_games() -> String {
+    Value::Array(GAMES.iter().map(GameInfo::json).collect()).to_string()
+}
@@ -0,0 +1,401 @@
+//! Equipped cosmetics on the player's hero.
+//!
+//! The shell passes the player's equipped cosmetics in the `start_game`
+//! options; every key is optional:
+//!
+//! ```json
+//! {"cosmetics": {
+//!     "hat": "top_hat",
+//!     "skin": "sunset",
+//!     "trail": {"style": "sparkle", "color": "#ffd700"}
+//! }}
+//! ```
+//!
+//! * **hat** – `cap`, `top_hat`, `crown`, `beanie` or `propeller`, or
+//!   `{"style": ..., "color": "#rrggbb"}`.  Drawn on top of the hero.
+//! * **skin** – a preset palette (`sunset`, `ocean`, `forest`, `galaxy`,
+//!   `gold`), a body colour `"#rrggbb"`, or `{"body": ..., "eyes": ...}`.
+//!   Replaces the hero's body (and optionally iris) colour.
+//! * **trail** – `sparkle`, `smoke` or `rainbow`, or `{"style": ...,
+//!   "color": ...}`.  Particles left behind the hero as it moves; in
+//!   runners, where the world scrolls past a fixed hero, they stream away
+//!   behind it.
+//!
+//! Games spawn their player through [`spawn_hero`] instead of
+//! [`pixar::spawn_character`].  Games without a player character (turret
+//! defense, puzzles played on the board, billiards) ignore cosmetics.
+//! Unknown values are ignored so older engines keep working with newer
+//! store items.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::games::GameplaySet;
+use crate::perf::Quality;
+use crate::pixar::{self, CharacterConfig, PixarAssets};
+use crate::{AppState, BevyBridge, GameOptions};
+
+/// Games whose hero stays put while the world scrolls left.
+const RUNNER_GAMES: &[&str] = &["campus_dash", "gravity_shift_run", "parkour_lab", "lab_breach"];
+/// Speed at which runner trails stream away, in px/s.
+const RUNNER_DRIFT: f32 = 260.0;
+/// Seconds between trail particles.
+const TRAIL_INTERVAL: f32 = 0.04;
+/// Outside runners, the hero must move this far (px) between particles.
+const TRAIL_MIN_STEP: f32 = 2.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (emit_trail, fade_trail).in_set(GameplaySet))
+            .add_systems(OnExit(AppState::Playing), cleanup_trail);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cosmetics
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HatStyle {
+    Cap,
+    TopHat,
+    Crown,
+    Beanie,
+    Propeller,
+}
+
+impl HatStyle {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cap" => Some(Self::Cap),
+            "top_hat" => Some(Self::TopHat),
+            "crown" => Some(Self::Crown),
+            "beanie" => Some(Self::Beanie),
+            "propeller" => Some(Self::Propeller),
+            _ => None,
+        }
+    }
+
+    fn default_color(self) -> Color {
+        match self {
+            Self::Cap => Color::srgb(0.85, 0.2, 0.2),
+            Self::TopHat => Color::srgb(0.12, 0.12, 0.15),
+            Self::Crown => pixar::palette::GOLD,
+            Self::Beanie => Color::srgb(0.2, 0.45, 0.85),
+            Self::Propeller => Color::srgb(0.95, 0.75, 0.1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrailStyle {
+    Sparkle,
+    Smoke,
+    Rainbow,
+}
+
+impl TrailStyle {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sparkle" => Some(Self::Sparkle),
+            "smoke" => Some(Self::Smoke),
+            "rainbow" => Some(Self::Rainbow),
+            _ => None,
+        }
+    }
+
+    fn default_color(self) -> Color {
+        match self {
+            Self::Sparkle => pixar::palette::GOLD,
+            Self::Smoke => Color::srgb(0.75, 0.75, 0.8),
+            // Hue-cycled per particle.
+            Self::Rainbow => Color::WHITE,
+        }
+    }
+
+    /// Particle lifetime in seconds.
+    fn lifetime(self) -> f32 {
+        match self {
+            Self::Sparkle => 0.45,
+            Self::Smoke => 0.8,
+            Self::Rainbow => 0.6,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hat {
+    pub style: HatStyle,
+    pub color: Color,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Skin {
+    pub body: Color,
+    pub eyes: Option<Color>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Trail {
+    pub style: TrailStyle,
+    pub color: Color,
+}
+
+/// The player's equipped cosmetics, from the `"cosmetics"` option.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cosmetics {
+    pub hat: Option<Hat>,
+    pub skin: Option<Skin>,
+    pub trail: Option<Trail>,
+}
+
+impl Cosmetics {
+    pub fn from_options(options: &GameOptions) -> Self {
+        let Some(raw) = options.get("cosmetics") else {
+            return Self::default();
+        };
+        Self {
+            hat: parse_styled(&raw["hat"], HatStyle::parse)
+                .map(|(style, color)| Hat { style, color: color.unwrap_or(style.default_color()) }),
+            skin: parse_skin(&raw["skin"]),
+            trail: parse_styled(&raw["trail"], TrailStyle::parse)
+                .map(|(style, color)| Trail { style, color: color.unwrap_or(style.default_color()) }),
+        }
+    }
+
+    /// Recolours `config` with the equipped skin.
+    pub fn apply(&self, config: &mut CharacterConfig) {
+        if let Some(skin) = self.skin {
+            config.body_color = skin.body;
+            if let Some(eyes) = skin.eyes {
+                config.eye_color = eyes;
+            }
+        }
+    }
+}
+
+fn parse_color(value: &Value) -> Option<Color> {
+    let hex = value.as_str()?;
+    Srgba::hex(hex.trim_start_matches('#')).ok().map(Color::from)
+}
+
+/// `"style"` or `{"style": ..., "color": ...}`.
+fn parse_styled<S>(value: &Value, parse: fn(&str) -> Option<S>) -> Option<(S, Option<Color>)> {
+    match value {
+        Value::String(name) => Some((parse(name)?, None)),
+        Value::Object(_) => Some((parse(value["style"].as_str()?)?, parse_color(&value["color"]))),
+        _ => None,
+    }
+}
+
+fn skin_preset(name: &str) -> Option<Skin> {
+    let (body, eyes) = match name {
+        "sunset" => (Color::srgb(1.0, 0.45, 0.3), Color::srgb(0.55, 0.2, 0.6)),
+        "ocean" => (Color::srgb(0.1, 0.6, 0.75), Color::srgb(0.0, 0.3, 0.55)),
+        "forest" => (Color::srgb(0.25, 0.6, 0.3), Color::srgb(0.45, 0.3, 0.1)),
+        "galaxy" => (Color::srgb(0.25, 0.15, 0.5), Color::srgb(0.9, 0.4, 1.0)),
+        "gold" => (pixar::palette::GOLD, Color::srgb(0.55, 0.35, 0.05)),
+        _ => return None,
+    };
+    Some(Skin { body, eyes: Some(eyes) })
+}
+
+/// A preset name, a body colour, or `{"body": ..., "eyes": ...}`.
+fn parse_skin(value: &Value) -> Option<Skin> {
+    match value {
+        Value::String(name) => skin_preset(name).or_else(|| {
+            let body = parse_color(value)?;
+            Some(Skin { body, eyes: None })
+        }),
+        Value::Object(_) => Some(Skin {
+            body: parse_color(&value["body"])?,
+            eyes: parse_color(&value["eyes"]),
+        }),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Spawning
+// ---------------------------------------------------------------------------
+
+/// Emits trail particles behind the hero it's attached to.
+#[derive(Component)]
+pub struct TrailEmitter {
+    trail: Trail,
+    timer: f32,
+    last: Vec2,
+    emitted: u32,
+}
+
+#[derive(Component)]
+struct TrailParticle {
+    age: f32,
+    lifetime: f32,
+    velocity: Vec2,
+    size: f32,
+    alpha: f32,
+    grows: bool,
+}
+
+/// Spawns the player's hero like [`pixar::spawn_character`], with the
+/// cosmetics equipped in `options` applied.
+pub fn spawn_hero(
+    commands: &mut Commands,
+    assets: &PixarAssets,
+    options: &GameOptions,
+    config: &CharacterConfig,
+    position: Vec3,
+    bundle: impl Bundle,
+) -> Entity {
+    let cosmetics = Cosmetics::from_options(options);
+    let mut config = config.clone();
+    cosmetics.apply(&mut config);
+
+    let entity = pixar::spawn_character(commands, assets, &config, position, bundle);
+    if let Some(hat) = cosmetics.hat {
+        spawn_hat(commands, assets, entity, hat, config.body_size);
+    }
+    if let Some(trail) = cosmetics.trail {
+        commands.entity(entity).insert(TrailEmitter {
+            trail,
+            timer: 0.0,
+            last: position.truncate(),
+            emitted: 0,
+        });
+    }
+    entity
+}
+
+/// Draws `hat` as children of `hero`, sitting on top of a body of `size`.
+fn spawn_hat(commands: &mut Commands, assets: &PixarAssets, hero: Entity, hat: Hat, size: Vec2) {
+    let w = size.x;
+    // Top edge of the body, slightly sunk in so the hat sits on it.
+    let top = size.y * 0.5 - size.y * 0.06;
+    let rect = |color: Color, size: Vec2, x: f32, y: f32| {
+        (Sprite { color, custom_size: Some(size), ..default() }, Transform::from_xyz(x, y, 0.3))
+    };
+    let round = |color: Color, size: Vec2, x: f32, y: f32| {
+        (pixar::round_sprite(assets, color, size), Transform::from_xyz(x, y, 0.3))
+    };
+    let color = hat.color;
+    let trim = color.darker(0.15);
+
+    commands.entity(hero).with_children(|parent| match hat.style {
+        HatStyle::Cap => {
+            parent.spawn(round(color, Vec2::new(w * 0.7, w * 0.45), 0.0, top + w * 0.1));
+            parent.spawn(rect(trim, Vec2::new(w * 0.45, w * 0.1), w * 0.3, top));
+        }
+        HatStyle::TopHat => {
+            parent.spawn(rect(color, Vec2::new(w * 0.85, w * 0.1), 0.0, top));
+            parent.spawn(rect(color, Vec2::new(w * 0.55, w * 0.6), 0.0, top + w * 0.35));
+            parent.spawn(rect(Color::srgb(0.8, 0.15, 0.2), Vec2::new(w * 0.55, w * 0.1), 0.0, top + w * 0.12));
+        }
+        HatStyle::Crown => {
+            parent.spawn(rect(color, Vec2::new(w * 0.6, w * 0.2), 0.0, top + w * 0.08));
+            for x in [-0.22, 0.0, 0.22] {
+                parent.spawn(round(color, Vec2::splat(w * 0.16), w * x, top + w * 0.24));
+            }
+            parent.spawn(round(pixar::palette::VILLAIN_RED, Vec2::splat(w * 0.1), 0.0, top + w * 0.08));
+        }
+        HatStyle::Beanie => {
+            parent.spawn(round(color, Vec2::new(w * 0.75, w * 0.5), 0.0, top + w * 0.08));
+            parent.spawn(rect(trim, Vec2::new(w * 0.78, w * 0.12), 0.0, top));
+            parent.spawn(round(Color::WHITE, Vec2::splat(w * 0.18), 0.0, top + w * 0.35));
+        }
+        HatStyle::Propeller => {
+            parent.spawn(round(color, Vec2::new(w * 0.65, w * 0.4), 0.0, top + w * 0.08));
+            parent.spawn(rect(trim, Vec2::new(w * 0.05, w * 0.15), 0.0, top + w * 0.32));
+            parent.spawn(rect(pixar::palette::HERO_RED, Vec2::new(w * 0.6, w * 0.06), 0.0, top + w * 0.4));
+        }
+    });
+}
+
+// ---------------------------------------------------------------------------
+// Trail systems
+// ---------------------------------------------------------------------------
+
+fn emit_trail(
+    mut commands: Commands,
+    time: Res<Time>,
+    bridge: Res<BevyBridge>,
+    assets: Res<PixarAssets>,
+    quality: Res<Quality>,
+    mut q: Query<(&GlobalTransform, &Sprite, &mut TrailEmitter)>,
+) {
+    let runner = RUNNER_GAMES.contains(&bridge.game_id.as_str());
+    let mut rng = rand::thread_rng();
+
+    for (gtf, sprite, mut emitter) in &mut q {
+        emitter.timer -= time.delta_secs();
+        if emitter.timer > 0.0 {
+            continue;
+        }
+        let pos = gtf.translation();
+        if !runner && pos.truncate().distance(emitter.last) < TRAIL_MIN_STEP {
+            continue;
+        }
+        emitter.timer = TRAIL_INTERVAL / quality.particle_scale();
+        emitter.last = pos.truncate();
+        emitter.emitted = emitter.emitted.wrapping_add(1);
+
+        let hero = sprite.custom_size.unwrap_or(Vec2::splat(30.0));
+        let trail = emitter.trail;
+        let (color, size, jitter) = match trail.style {
+            TrailStyle::Sparkle => (trail.color, hero.x * 0.25, hero.y * 0.35),
+            TrailStyle::Smoke => (trail.color.with_alpha(0.5), hero.x * 0.4, hero.y * 0.15),
+            TrailStyle::Rainbow => {
+                let hue = (emitter.emitted as f32 * 12.0) % 360.0;
+                (Color::hsl(hue, 0.9, 0.6), hero.x * 0.35, 0.0)
+            }
+        };
+        let offset = Vec2::new(-hero.x * 0.4, rng.gen_range(-jitter..=jitter) - hero.y * 0.1);
+        let velocity = if runner { Vec2::new(-RUNNER_DRIFT, 0.0) } else { Vec2::ZERO };
+
+        commands.spawn((
+            pixar::round_sprite(&assets, color, Vec2::splat(size)),
+            Transform::from_translation(pos + offset.extend(-0.2)),
+            TrailParticle {
+                age: 0.0,
+                lifetime: trail.style.lifetime(),
+                velocity,
+                size,
+                alpha: color.alpha(),
+                grows: trail.style == TrailStyle::Smoke,
+            },
+        ));
+    }
+}
+
+fn fade_trail(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut Transform, &mut Sprite, &mut TrailParticle)>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut tf, mut sprite, mut p) in &mut q {
+        p.age += dt;
+        if p.age >= p.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let k = p.age / p.lifetime;
+        tf.translation += (p.velocity * dt).extend(0.0);
+        let scale = if p.grows { 1.0 + k } else { 1.0 - k * 0.7 };
+        sprite.custom_size = Some(Vec2::splat(p.size * scale));
+        sprite.color.set_alpha(p.alpha * (1.0 - k));
+    }
+}
+
+fn cleanup_trail(mut commands: Commands, q: Query<Entity, With<TrailParticle>>) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
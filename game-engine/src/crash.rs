@@ -0,0 +1,142 @@
+//! Crash reporting.
+//!
+//! [`install`] sets a panic hook that logs the panic to the browser console
+//! and posts it to `POST /api/v1/crash-reports` with the game being played,
+//! the last [`MAX_INPUTS`] inputs and some browser details.  The server
+//! groups reports by signature so admins can triage each distinct crash
+//! once (`/admin/crash-reports`).
+//!
+//! A panic aborts the WASM module, so the hook can't reach the Bevy world
+//! and nothing after it runs: inputs are kept in a static ring buffer by
+//! [`CrashPlugin`], and the report goes out as a keep-alive request that
+//! doesn't need the engine to keep running.  Nothing is sent before the
+//! shell has called `set_api_config`.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once};
+
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use wasm_bindgen::JsValue;
+
+use crate::api;
+use crate::input::{ActionState, GameAction};
+
+/// Inputs kept for the report.
+pub const MAX_INPUTS: usize = 30;
+/// Longest panic message sent; the server truncates there too.
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct CrashPlugin;
+
+impl Plugin for CrashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, record_inputs.after(bevy::input::InputSystem))
+            .add_systems(Update, record_movement);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Input history
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, Clone, Debug)]
+struct InputRecord {
+    /// Milliseconds since the Unix epoch.
+    t: i64,
+    input: String,
+}
+
+static RECENT_INPUTS: Mutex<VecDeque<InputRecord>> = Mutex::new(VecDeque::new());
+
+fn push_input(input: String) {
+    if let Ok(mut inputs) = RECENT_INPUTS.lock() {
+        if inputs.len() == MAX_INPUTS {
+            inputs.pop_front();
+        }
+        inputs.push_back(InputRecord { t: js_sys::Date::now() as i64, input });
+    }
+}
+
+fn record_inputs(mut actions: EventReader<GameAction>) {
+    for action in actions.read() {
+        push_input(format!("{:?}", action));
+    }
+}
+
+/// Records the movement direction when it changes, e.g. `Move(-1,0)`.
+fn record_movement(state: Res<ActionState>, mut last: Local<IVec2>) {
+    let direction = state.movement.round().as_ivec2();
+    if direction != *last {
+        *last = direction;
+        push_input(format!("Move({},{})", direction.x, direction.y));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Panic hook
+// ---------------------------------------------------------------------------
+
+static INSTALL: Once = Once::new();
+
+/// Install the panic hook.  Called by `init_engine`; later calls do
+/// nothing.
+pub fn install() {
+    INSTALL.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            let location = info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+                .unwrap_or_default();
+
+            web_sys::console::error_1(&format!("panicked at {}:\n{}", location, message).into());
+            report(&message, &location);
+        }));
+    });
+}
+
+fn report(message: &str, location: &str) {
+    // The panic may have happened while the buffer was locked.
+    let inputs: Vec<InputRecord> = RECENT_INPUTS
+        .try_lock()
+        .map(|inputs| inputs.iter().cloned().collect())
+        .unwrap_or_default();
+    let game_id = crate::get_js_global("__bevy_game_id").filter(|id| !id.is_empty());
+
+    let body = json!({
+        "gameId": game_id,
+        "message": message.chars().take(MAX_MESSAGE_CHARS).collect::<String>(),
+        "location": location,
+        "engineVersion": env!("CARGO_PKG_VERSION"),
+        "inputs": inputs,
+        "browser": browser_info(),
+    });
+    api::post_json_detached("/crash-reports", &body);
+}
+
+fn browser_info() -> serde_json::Value {
+    let Some(window) = web_sys::window() else {
+        return json!({});
+    };
+    let navigator = window.navigator();
+    let dimension = |v: Result<JsValue, JsValue>| v.ok().and_then(|v| v.as_f64()).map(|v| v as i64);
+    json!({
+        "userAgent": navigator.user_agent().ok(),
+        "language": navigator.language(),
+        "platform": navigator.platform().ok(),
+        "viewportWidth": dimension(window.inner_width()),
+        "viewportHeight": dimension(window.inner_height()),
+        "pixelRatio": window.device_pixel_ratio(),
+    })
+}
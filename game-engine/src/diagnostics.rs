@@ -0,0 +1,59 @@
+//! Failure diagnostics for level design.
+//!
+//! Games send a [`Failure`] wherever the player dies or fails an attempt —
+//! loses a life, crashes, falls out of the level — with the position it
+//! happened at in world units.  The plugin stamps each one with the
+//! zero-based level being played, taken from the level the game reports in
+//! [`GameStats`](crate::GameStats), and hands it to the telemetry buffer
+//! as a `death` event, so failures are batched and uploaded with the rest
+//! of the run's events.
+//!
+//! The server bins them per game and level (`GET /admin/analytics/deaths`),
+//! which shows designers where players keep failing.
+
+use bevy::prelude::*;
+
+use crate::analytics::AnalyticsEvent;
+use crate::BevyBridge;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Failure>()
+            .add_systems(Update, record_failures);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+/// The player died or failed the attempt at this position, in world units.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Failure(pub Vec2);
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Games without levels, and games that have not reported one yet, count
+/// as level 0.
+fn level_index(bridge: &BevyBridge) -> usize {
+    bridge.stats.level.map_or(0, |level| (level - 1).max(0) as usize)
+}
+
+fn record_failures(
+    mut failures: EventReader<Failure>,
+    bridge: Res<BevyBridge>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+) {
+    let level = level_index(&bridge);
+    for Failure(pos) in failures.read() {
+        analytics.send(AnalyticsEvent::death(*pos, level));
+    }
+}
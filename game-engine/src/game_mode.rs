@@ -0,0 +1,189 @@
+//! Game modes: Classic, Time Attack, Endless, Zen and Versus.
+//!
+//! The shell picks a mode through the `start_game` options, e.g.
+//! `{"mode":"time_attack","timeLimit":90}`; without one the game runs in
+//! Classic.  The mode is available to game systems as the [`GameMode`]
+//! resource:
+//!
+//! * **Time Attack** – a shared [`ModeTimer`] counts down `timeLimit`
+//!   seconds (default [`DEFAULT_TIME_LIMIT_SECS`]) and the run ends when it
+//!   expires, whatever game is running.  A countdown is shown at the top of
+//!   the screen.
+//! * **Endless** – no level cap or final stage; runners and shooters are
+//!   already endless, so for them it plays like Classic.
+//! * **Zen** – the run never ends on its own.  Games check
+//!   [`GameMode::can_lose`] before ending a run and forgive the mistake
+//!   instead; only `stop_game` or the pause menu's Quit end a Zen run.
+//! * **Versus** – two players on one keyboard, in the games that support
+//!   it (see [`versus`](crate::versus)); Classic elsewhere.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::games::GameplaySet;
+use crate::{AppState, GameOptions};
+
+pub const DEFAULT_TIME_LIMIT_SECS: f32 = 60.0;
+/// Time left at which the countdown turns red.
+const WARNING_SECS: f32 = 10.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<ModeTimer>()
+            .add_systems(OnEnter(AppState::Playing), (apply_mode, spawn_hud).chain())
+            .add_systems(
+                Update,
+                (tick_timer, end_on_timeout, update_hud)
+                    .chain()
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), despawn_hud);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Classic,
+    TimeAttack,
+    Endless,
+    Zen,
+    Versus,
+}
+
+impl GameMode {
+    /// Mode requested in the `start_game` options; Classic if absent or
+    /// unknown.
+    pub fn from_options(options: &GameOptions) -> Self {
+        options
+            .get("mode")
+            .and_then(|m| serde_json::from_value(m.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether a mistake (collision, running out of HP) may end the run.
+    pub fn can_lose(self) -> bool {
+        self != Self::Zen
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::TimeAttack => "Time Attack",
+            Self::Endless => "Endless",
+            Self::Zen => "Zen",
+            Self::Versus => "Versus",
+        }
+    }
+}
+
+/// Countdown shared by every game.  Only ticks in Time Attack.
+#[derive(Resource, Debug)]
+pub struct ModeTimer(pub Timer);
+
+impl Default for ModeTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(DEFAULT_TIME_LIMIT_SECS, TimerMode::Once))
+    }
+}
+
+impl ModeTimer {
+    pub fn remaining_secs(&self) -> f32 {
+        self.0.remaining_secs()
+    }
+}
+
+#[derive(Component)]
+struct ModeHud;
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn apply_mode(options: Res<GameOptions>, mut mode: ResMut<GameMode>, mut timer: ResMut<ModeTimer>) {
+    *mode = GameMode::from_options(&options);
+    let limit = options
+        .get("timeLimit")
+        .and_then(|t| t.as_f64())
+        .map_or(DEFAULT_TIME_LIMIT_SECS, |t| (t as f32).clamp(10.0, 600.0));
+    timer.0 = Timer::from_seconds(limit, TimerMode::Once);
+}
+
+fn spawn_hud(mut commands: Commands, mode: Res<GameMode>) {
+    let text = match *mode {
+        GameMode::Classic => return,
+        GameMode::TimeAttack => String::new(),
+        other => other.label().to_string(),
+    };
+    commands.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 26.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ModeHud,
+    ));
+}
+
+fn tick_timer(time: Res<Time>, mode: Res<GameMode>, mut timer: ResMut<ModeTimer>) {
+    if *mode == GameMode::TimeAttack {
+        timer.0.tick(time.delta());
+    }
+}
+
+fn end_on_timeout(
+    mode: Res<GameMode>,
+    timer: Res<ModeTimer>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if *mode == GameMode::TimeAttack && timer.0.just_finished() {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+fn update_hud(
+    mode: Res<GameMode>,
+    timer: Res<ModeTimer>,
+    mut q: Query<(&mut Text, &mut TextColor), With<ModeHud>>,
+) {
+    if *mode != GameMode::TimeAttack {
+        return;
+    }
+    let remaining = timer.remaining_secs();
+    let secs = remaining.ceil() as u32;
+    for (mut text, mut color) in &mut q {
+        **text = format!("{}:{:02}", secs / 60, secs % 60);
+        color.0 = if remaining <= WARNING_SECS {
+            Color::srgb(1.0, 0.35, 0.3)
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn despawn_hud(mut commands: Commands, q: Query<Entity, With<ModeHud>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
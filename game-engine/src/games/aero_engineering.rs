@@ -1,9 +1,15 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::perf::Quality;
+use crate::pool::{Parked, Pool};
+use super::boss::{self, Boss, BossShot, BossSpec, BossTarget, Spawn, Waves};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -21,52 +27,79 @@ const SPAWN_INTERVAL: f32 = 1.5;
 const HALF_W: f32 = 480.0;
 const HALF_H: f32 = 320.0;
 const MAX_HP: i32 = 5;
+const BOSS_EVERY: u32 = 3;
+
+static BOSS: BossSpec = BossSpec {
+    name: "Storm Cell",
+    size: Vec2::new(110.0, 110.0),
+    color: palette::VILLAIN_PURPLE,
+    hp: 36,
+    weak_points: &[Vec2::new(0.0, 0.0), Vec2::new(-35.0, 35.0), Vec2::new(35.0, 35.0)],
+    anchor: Vec2::new(0.0, 120.0),
+    sweep: Vec2::new(300.0, 120.0),
+    fire_interval: 1.8,
+    shot_speed: 200.0,
+    phase_bonus: 400,
+    defeat_bonus: 2000,
+};
 
 // ---------------------------------------------------------------------------
 // Components
 // ---------------------------------------------------------------------------
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     vx: f32,
     vy: f32,
 }
 
 #[derive(Component)]
-struct Enemy;
+pub struct Enemy;
 
 #[derive(Component)]
-struct Bullet {
+pub struct Bullet {
     dx: f32,
     dy: f32,
 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct HpText;
+pub struct HpText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     hp: i32,
     spawn_timer: f32,
+    waves: Waves,
+    shots: i32,
+    hits: i32,
 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
     commands.insert_resource(GameState {
         score: 0,
         hp: MAX_HP,
         spawn_timer: 0.0,
+        waves: Waves::default(),
+        shots: 0,
+        hits: 0,
     });
+    commands.insert_resource(Pool::<Bullet>::default());
 
     // Background
     let bg_sprite = if let Some(ref bg) = custom_assets.background {
@@ -77,12 +110,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     commands.spawn((bg_sprite, Transform::from_xyz(0.0, 0.0, -1.0), GameEntity));
 
     // Player
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::vehicle(palette::HERO_TEAL, PLAYER_SIZE),
         Vec3::new(0.0, 0.0, 1.0),
-        (Player { vx: 0.0, vy: 0.0 }, GameEntity),
+        (Player { vx: 0.0, vy: 0.0 }, BossTarget, GameEntity),
     );
 
     // HUD - Score
@@ -104,33 +138,38 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         HpText,
         GameEntity,
     ));
+
+    boss::spawn_health_bar(&mut commands, GameEntity);
 }
 
 // ---------------------------------------------------------------------------
 // Systems
 // ---------------------------------------------------------------------------
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
     touches: Res<Touches>,
     time: Res<Time>,
     mut pq: Query<(&mut Transform, &mut Player)>,
+    mut state: ResMut<GameState>,
+    mut bullets: ResMut<Pool<Bullet>>,
     mut commands: Commands,
 ) {
     let dt = time.delta_secs();
     let Ok((mut tf, mut player)) = pq.get_single_mut() else { return };
 
-    if keys.pressed(KeyCode::ArrowLeft) {
+    if input.pressed(Control::MoveLeft) {
         tf.rotate_z(PLAYER_ROTATE_SPEED * dt);
     }
-    if keys.pressed(KeyCode::ArrowRight) {
+    if input.pressed(Control::MoveRight) {
         tf.rotate_z(-PLAYER_ROTATE_SPEED * dt);
     }
 
     let angle = tf.rotation.to_euler(EulerRot::XYZ).2;
 
-    if keys.pressed(KeyCode::ArrowUp) {
+    if input.pressed(Control::MoveUp) {
         player.vx += angle.cos() * PLAYER_THRUST * dt;
         player.vy += angle.sin() * PLAYER_THRUST * dt;
     }
@@ -148,14 +187,16 @@ pub fn player_input(
     if tf.translation.y < -HALF_H { tf.translation.y = HALF_H; }
 
     // Shoot
-    let shoot = keys.just_pressed(KeyCode::Space)
+    let shoot = input.just_pressed(Control::Fire)
+        || input.just_pressed(Control::Action)
         || mouse.just_pressed(MouseButton::Left)
         || touches.any_just_pressed();
 
     if shoot {
+        state.shots += 1;
         let dir_x = (angle + std::f32::consts::FRAC_PI_2).sin();
         let dir_y = (angle + std::f32::consts::FRAC_PI_2).cos();
-        commands.spawn((
+        bullets.acquire(&mut commands, (
             Sprite { color: palette::HERO_YELLOW, custom_size: Some(BULLET_SIZE), ..default() },
             Transform::from_xyz(tf.translation.x, tf.translation.y, 0.5),
             Bullet { dx: dir_x * BULLET_SPEED, dy: dir_y * BULLET_SPEED },
@@ -167,22 +208,31 @@ pub fn player_input(
 pub fn move_bullets(
     time: Res<Time>,
     mut commands: Commands,
-    mut q: Query<(Entity, &mut Transform, &Bullet)>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    mut q: Query<(Entity, &mut Transform, &Bullet), Without<Parked>>,
 ) {
     let dt = time.delta_secs();
     for (e, mut tf, b) in &mut q {
         tf.translation.x += b.dx * dt;
         tf.translation.y += b.dy * dt;
         if tf.translation.x.abs() > HALF_W + 50.0 || tf.translation.y.abs() > HALF_H + 50.0 {
-            commands.entity(e).despawn();
+            bullets.release(&mut commands, e);
         }
     }
 }
 
-pub fn spawn_enemies(time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
+pub fn spawn_enemies(time: Res<Time>, quality: Res<Quality>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
     state.spawn_timer += time.delta_secs();
-    if state.spawn_timer < SPAWN_INTERVAL { return; }
+    if state.spawn_timer < SPAWN_INTERVAL * quality.spawn_interval_scale() { return; }
     state.spawn_timer = 0.0;
+    match state.waves.next(BOSS_EVERY) {
+        Spawn::Enemy => {}
+        Spawn::Boss => {
+            boss::spawn_boss(&mut commands, &pixar_assets, &BOSS, state.waves.bosses_defeated, GameEntity);
+            return;
+        }
+        Spawn::Hold => return,
+    }
     let mut rng = rand::thread_rng();
     let (x, y) = match rng.gen_range(0..4) {
         0 => (rng.gen_range(-HALF_W..HALF_W), HALF_H + 20.0),
@@ -215,13 +265,16 @@ pub fn move_enemies(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn check_collisions(
     mut commands: Commands,
     mut state: ResMut<GameState>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut bullets: ResMut<Pool<Bullet>>,
     pq: Query<&Transform, With<Player>>,
     eq: Query<(Entity, &Transform), With<Enemy>>,
-    bq: Query<(Entity, &Transform), With<Bullet>>,
+    bq: Query<(Entity, &Transform), (With<Bullet>, Without<Parked>)>,
+    mut failures: EventWriter<Failure>,
 ) {
     let Ok(ptf) = pq.get_single() else { return };
 
@@ -231,9 +284,10 @@ pub fn check_collisions(
             let dx = (btf.translation.x - etf.translation.x).abs();
             let dy = (btf.translation.y - etf.translation.y).abs();
             if dx < (BULLET_SIZE.x + ENEMY_SIZE.x) / 2.0 && dy < (BULLET_SIZE.y + ENEMY_SIZE.y) / 2.0 {
-                commands.entity(be).despawn();
+                bullets.release(&mut commands, be);
                 commands.entity(ee).despawn();
                 state.score += 50;
+                state.hits += 1;
                 break;
             }
         }
@@ -245,6 +299,7 @@ pub fn check_collisions(
         let dy = (ptf.translation.y - etf.translation.y).abs();
         if dx < (PLAYER_SIZE.x + ENEMY_SIZE.x) / 2.0 && dy < (PLAYER_SIZE.y + ENEMY_SIZE.y) / 2.0 {
             state.hp -= 1;
+            failures.send(Failure(ptf.translation.truncate()));
             commands.entity(_ee).despawn();
             if state.hp <= 0 {
                 next_state.set(crate::AppState::GameOver);
@@ -254,8 +309,55 @@ pub fn check_collisions(
     }
 }
 
+/// Bullets against the boss, and its shots against the player.
+pub fn boss_collisions(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    pq: Query<&Transform, With<Player>>,
+    mut boss_q: Query<(Entity, &Transform, &mut Boss), With<GameEntity>>,
+    bq: Query<(Entity, &Transform), (With<Bullet>, Without<Parked>)>,
+    sq: Query<(Entity, &Transform), (With<BossShot>, With<GameEntity>)>,
+    mut failures: EventWriter<Failure>,
+) {
+    let Ok(ptf) = pq.get_single() else { return };
+    let Ok((boss_e, boss_tf, mut boss)) = boss_q.get_single_mut() else { return };
+    let boss_pos = boss_tf.translation.truncate();
+
+    for (be, btf) in &bq {
+        let Some(damage) = boss.damage_at(boss_pos, btf.translation.truncate()) else { continue };
+        bullets.release(&mut commands, be);
+        let hit = boss.take_damage(damage);
+        state.score += hit.points;
+        state.hits += 1;
+        if hit.defeated {
+            commands.entity(boss_e).despawn_recursive();
+            state.waves.boss_defeated();
+            return;
+        }
+    }
+
+    for (se, stf) in &sq {
+        let dx = (stf.translation.x - ptf.translation.x).abs();
+        let dy = (stf.translation.y - ptf.translation.y).abs();
+        if dx < (PLAYER_SIZE.x + boss::SHOT_SIZE.x) / 2.0 && dy < (PLAYER_SIZE.y + boss::SHOT_SIZE.y) / 2.0 {
+            state.hp -= 1;
+            failures.send(Failure(ptf.translation.truncate()));
+            commands.entity(se).despawn();
+            if state.hp <= 0 {
+                next_state.set(crate::AppState::GameOver);
+                return;
+            }
+        }
+    }
+}
+
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    bridge.stats.level = Some(state.waves.wave as i32 + 1);
+    bridge.stats.deaths = Some(MAX_HP - state.hp.max(0));
 }
 
 pub fn update_hud(
@@ -272,6 +374,7 @@ pub fn update_hud(
 // ---------------------------------------------------------------------------
 
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
-    for e in &q { commands.entity(e).despawn(); }
+    for e in &q { commands.entity(e).despawn_recursive(); }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Bullet>>();
 }
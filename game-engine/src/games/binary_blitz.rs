@@ -0,0 +1,410 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{BevyBridge, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
+use crate::pixar::palette;
+use crate::asset_loader::CustomAssets;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const LANE_COUNT: usize = 3;
+const LANE_SPACING: f32 = 220.0;
+const LANE_START_X: f32 = -220.0;
+const HIT_LINE_Y: f32 = -130.0;
+const SPAWN_Y: f32 = 320.0;
+const NUMBER_SIZE: Vec2 = Vec2::new(150.0, 44.0);
+const SPEED_INCREASE: f32 = 0.6;
+const MAX_MISSES: i32 = 5;
+/// Numbers matched before the next, wider stage.
+const STAGE_HITS: i32 = 8;
+const BASE_POINTS: f32 = 100.0;
+
+const SWITCH_Y: f32 = -215.0;
+const SWITCH_SIZE: Vec2 = Vec2::new(34.0, 46.0);
+const SWITCH_SPACING: f32 = 40.0;
+/// Extra space between bytes.
+const BYTE_GAP: f32 = 16.0;
+const MAX_BITS: usize = 16;
+
+/// `hex` is the share of numbers written in hex rather than decimal.
+struct Stage {
+    bits: usize,
+    speed: f32,
+    interval: f32,
+    hex: f32,
+}
+
+const STAGES: [Stage; 3] = [
+    Stage { bits: 4, speed: 45.0, interval: 4.0, hex: 0.0 },
+    Stage { bits: 8, speed: 35.0, interval: 5.0, hex: 0.5 },
+    Stage { bits: 16, speed: 25.0, interval: 6.5, hex: 0.8 },
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+#[derive(Component)]
+pub struct Number {
+    lane: usize,
+    value: u32,
+}
+
+/// Bit switch; bit 0 is the least significant.
+#[derive(Component)]
+pub struct Switch(usize);
+
+#[derive(Component)]
+pub struct SwitchText(usize);
+
+#[derive(Component)]
+pub struct ValueText;
+
+#[derive(Component)]
+pub struct ComboText;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Resource)]
+pub struct GameState {
+    score: i32,
+    combo: i32,
+    hits: i32,
+    misses: i32,
+    stage: usize,
+    /// Matches in the current stage.
+    stage_hits: i32,
+    switches: u32,
+    spawn_timer: f32,
+    speed: f32,
+    /// Seconds into the current stage.
+    elapsed: f32,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn lane_x(lane: usize) -> f32 {
+    LANE_START_X + (lane as f32) * LANE_SPACING
+}
+
+/// Switches run most significant bit first, centred for the stage's width.
+fn switch_x(bit: usize, bits: usize) -> f32 {
+    let bytes_gap = (bits.saturating_sub(1) / 8) as f32 * BYTE_GAP;
+    let width = (bits as f32 - 1.0) * SWITCH_SPACING + bytes_gap;
+    let pos = bits - 1 - bit;
+    -width / 2.0 + pos as f32 * SWITCH_SPACING + (pos / 8) as f32 * BYTE_GAP
+}
+
+fn label(value: u32, hex: bool, bits: usize) -> String {
+    if hex {
+        format!("0x{:0width$X}", value, width = bits / 4)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Left to right over the visible switches: the number row for the first
+/// byte, the row below for the second.
+const SWITCH_KEYS: [KeyCode; MAX_BITS] = [
+    KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8,
+    KeyCode::KeyQ, KeyCode::KeyW, KeyCode::KeyE, KeyCode::KeyR,
+    KeyCode::KeyT, KeyCode::KeyY, KeyCode::KeyU, KeyCode::KeyI,
+];
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, custom_assets: Res<CustomAssets>) {
+    commands.insert_resource(GameState {
+        score: 0, combo: 0, hits: 0, misses: 0,
+        stage: 0, stage_hits: 0, switches: 0,
+        spawn_timer: STAGES[0].interval, speed: STAGES[0].speed, elapsed: 0.0,
+    });
+
+    // Background
+    if let Some(ref bg) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0), GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::NIGHT_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0), GameEntity,
+        ));
+    }
+
+    // Hit line
+    commands.spawn((
+        Sprite { color: Color::srgba(1.0, 0.3, 0.3, 0.5), custom_size: Some(Vec2::new(LANE_COUNT as f32 * LANE_SPACING, 4.0)), ..default() },
+        Transform::from_xyz(0.0, HIT_LINE_Y, 0.5),
+        GameEntity,
+    ));
+
+    // Switches; positions and visibility follow the stage in `update_visuals`
+    for bit in 0..MAX_BITS {
+        commands.spawn((
+            Sprite { color: palette::SHADOW, custom_size: Some(SWITCH_SIZE), ..default() },
+            Transform::from_xyz(0.0, SWITCH_Y, 2.0),
+            Switch(bit), GameEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text2d::new("0"),
+                TextFont { font_size: 24.0, ..default() },
+                TextColor(Color::WHITE),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                SwitchText(bit),
+            ));
+            parent.spawn((
+                Text2d::new((1u32 << bit).to_string()),
+                TextFont { font_size: 10.0, ..default() },
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.7)),
+                Transform::from_xyz(0.0, SWITCH_SIZE.y / 2.0 + 10.0, 0.1),
+            ));
+        });
+    }
+
+    commands.spawn((
+        Text2d::new(""),
+        TextFont { font_size: 22.0, ..default() },
+        TextColor(palette::ELECTRIC_CYAN),
+        Transform::from_xyz(0.0, SWITCH_Y - 50.0, 2.0),
+        ValueText, GameEntity,
+    ));
+
+    // Combo text
+    commands.spawn((
+        Text::new("Combo: 0"),
+        TextFont { font_size: 28.0, ..default() },
+        TextColor(Color::srgb(1.0, 0.6, 0.1)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(10.0), right: Val::Px(10.0), ..default() },
+        ComboText, GameEntity,
+    ));
+
+    // Score text
+    commands.spawn((
+        Text::new("Score: 0 | Misses: 0/5"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.9, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(10.0), left: Val::Px(10.0), ..default() },
+        ScoreText, GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+pub fn spawn_numbers(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut commands: Commands,
+    number_q: Query<(&Number, &Transform)>,
+) {
+    let dt = time.delta_secs();
+    let stage = &STAGES[state.stage];
+    state.elapsed += dt;
+    state.speed = stage.speed + state.elapsed * SPEED_INCREASE;
+    let interval = (stage.interval - state.elapsed * 0.02).max(stage.interval / 2.0);
+    state.spawn_timer += dt;
+    if state.spawn_timer < interval { return; }
+
+    // Only lanes whose last number has cleared the top
+    let mut rng = rand::thread_rng();
+    let free: Vec<usize> = (0..LANE_COUNT)
+        .filter(|&lane| !number_q.iter().any(|(n, tf)| n.lane == lane && tf.translation.y > SPAWN_Y - NUMBER_SIZE.y * 2.0))
+        .collect();
+    if free.is_empty() { return; }
+    state.spawn_timer = 0.0;
+
+    let lane = free[rng.gen_range(0..free.len())];
+    let value = rng.gen_range(1..(1u32 << stage.bits));
+    let text = label(value, rng.gen::<f32>() < stage.hex, stage.bits);
+    commands.spawn((
+        Sprite { color: palette::HERO_PURPLE, custom_size: Some(NUMBER_SIZE), ..default() },
+        Transform::from_xyz(lane_x(lane), SPAWN_Y, 1.0),
+        Number { lane, value }, GameEntity,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text2d::new(text),
+            TextFont { font_size: 26.0, ..default() },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(0.0, 0.0, 0.1),
+        ));
+    });
+}
+
+pub fn move_numbers(time: Res<Time>, state: Res<GameState>, mut q: Query<&mut Transform, With<Number>>) {
+    let dt = time.delta_secs();
+    for mut tf in &mut q {
+        tf.translation.y -= state.speed * dt;
+    }
+}
+
+/// Switches flip with the number keys (and Q-I for the second byte) or a
+/// click or tap; Space clears them all.  When the switches spell out a
+/// falling number, the lowest such number is cleared.
+#[allow(clippy::too_many_arguments)]
+pub fn player_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    number_q: Query<(Entity, &Transform, &Number)>,
+) {
+    let bits = STAGES[state.stage].bits;
+    let before = state.switches;
+
+    for (pos, &key) in SWITCH_KEYS.iter().take(bits).enumerate() {
+        if keys.just_pressed(key) {
+            state.switches ^= 1 << (bits - 1 - pos);
+        }
+    }
+    if input.just_pressed(Control::Action) {
+        state.switches = 0;
+    }
+
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let cursor = screen_pos.and_then(|pos| {
+        let (camera, cam_tf) = camera_q.get_single().ok()?;
+        camera.viewport_to_world_2d(cam_tf, pos).ok()
+    });
+    if let Some(cursor) = cursor {
+        for bit in 0..bits {
+            let d = (cursor - Vec2::new(switch_x(bit, bits), SWITCH_Y)).abs();
+            if d.x <= SWITCH_SIZE.x / 2.0 && d.y <= SWITCH_SIZE.y / 2.0 {
+                state.switches ^= 1 << bit;
+            }
+        }
+    }
+
+    if state.switches == before || state.switches == 0 { return; }
+
+    // Lowest matching number
+    let mut best: Option<(Entity, f32)> = None;
+    for (e, tf, number) in &number_q {
+        if number.value != state.switches { continue; }
+        if let Some((_, lowest)) = best {
+            if lowest <= tf.translation.y { continue; }
+        }
+        best = Some((e, tf.translation.y));
+    }
+    let Some((entity, y)) = best else { return };
+
+    // Earlier catches are worth more, as are wider numbers
+    let height = ((y - HIT_LINE_Y) / (SPAWN_Y - HIT_LINE_Y)).clamp(0.0, 1.0);
+    let multiplier = (1.0 + state.combo as f32 / 10.0) * (bits as f32 / 4.0);
+    state.score += (BASE_POINTS * (0.5 + height) * multiplier) as i32;
+    state.combo += 1;
+    state.hits += 1;
+    state.switches = 0;
+    commands.entity(entity).despawn_recursive();
+
+    state.stage_hits += 1;
+    if state.stage_hits >= STAGE_HITS && state.stage + 1 < STAGES.len() {
+        state.stage += 1;
+        state.stage_hits = 0;
+        state.elapsed = 0.0;
+    }
+}
+
+pub fn missed_numbers(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    number_q: Query<(Entity, &Transform), With<Number>>,
+    mut failures: EventWriter<Failure>,
+) {
+    for (e, tf) in &number_q {
+        if tf.translation.y < HIT_LINE_Y {
+            failures.send(Failure(tf.translation.truncate()));
+            commands.entity(e).despawn_recursive();
+            state.combo = 0;
+            state.misses += 1;
+        }
+    }
+}
+
+pub fn check_game_over(
+    state: Res<GameState>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    if state.misses >= MAX_MISSES {
+        next_state.set(crate::AppState::GameOver);
+    }
+}
+
+pub fn update_visuals(
+    state: Res<GameState>,
+    mut switch_q: Query<(&Switch, &mut Transform, &mut Sprite, &mut Visibility)>,
+    mut bit_q: Query<(&SwitchText, &mut Text2d), Without<ValueText>>,
+    mut value_q: Query<&mut Text2d, (With<ValueText>, Without<SwitchText>)>,
+) {
+    let bits = STAGES[state.stage].bits;
+    for (switch, mut tf, mut sprite, mut visibility) in &mut switch_q {
+        let on = state.switches & (1 << switch.0) != 0;
+        tf.translation.x = switch_x(switch.0, bits);
+        sprite.color = if on { palette::HERO_GREEN } else { palette::VILLAIN_DARK };
+        *visibility = if switch.0 < bits { Visibility::Visible } else { Visibility::Hidden };
+    }
+    for (bit, mut text) in &mut bit_q {
+        **text = if state.switches & (1 << bit.0) != 0 { "1".into() } else { "0".into() };
+    }
+    for mut text in &mut value_q {
+        **text = format!("= {} ({})", state.switches, label(state.switches, true, bits));
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score;
+    bridge.stats.level = Some(state.stage as i32 + 1);
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.hits + state.misses);
+}
+
+pub fn update_hud(
+    state: Res<GameState>,
+    mut combo_q: Query<&mut Text, (With<ComboText>, Without<ScoreText>)>,
+    mut score_q: Query<&mut Text, (With<ScoreText>, Without<ComboText>)>,
+) {
+    for mut t in &mut combo_q {
+        **t = format!("Combo: {}", state.combo);
+    }
+    for mut t in &mut score_q {
+        **t = format!(
+            "Stage {} ({}-bit) | Score: {} | Misses: {}/{}",
+            state.stage + 1,
+            STAGES[state.stage].bits,
+            state.score,
+            state.misses,
+            MAX_MISSES,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
@@ -0,0 +1,428 @@
+//! Boss encounters shared by the shooters: `aero_engineering`,
+//! `drone_defense` and `safety_first_defense`.
+//!
+//! Regular enemies come in waves of [`WAVE_SIZE`].  Every few waves a boss
+//! spawns instead and regular spawns stop until it's defeated.  A boss has
+//! three phases, by remaining health, each with its own [`Attack`] pattern
+//! and a faster fire rate than the last.  Weak points take triple damage.
+//! Breaking a phase and defeating the boss are worth large bonuses.
+//!
+//! Movement, volleys and the HUD health bar are generic systems that each
+//! game registers for its own `GameEntity` marker, so a game only sees its
+//! own boss.  Games spawn the boss from their spawn timer via [`Waves`] and
+//! handle collisions themselves, since what a hit costs differs per game.
+
+use bevy::prelude::*;
+
+use crate::pixar::{self, palette, CharacterConfig, PixarAssets};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Regular enemies per wave.
+pub const WAVE_SIZE: u32 = 8;
+pub const SHOT_SIZE: Vec2 = Vec2::new(10.0, 10.0);
+const WEAK_POINT_RADIUS: f32 = 10.0;
+const WEAK_POINT_MULTIPLIER: i32 = 3;
+/// Points per point of damage dealt.
+const HIT_SCORE: i32 = 10;
+/// Each boss after the first has this much more health, as a fraction of
+/// the base.
+const HP_GROWTH: f32 = 0.5;
+const BAR_WIDTH: f32 = 320.0;
+/// Shots leaving this (plus a margin) are despawned.
+const SCREEN_HALF: Vec2 = Vec2::new(480.0, 320.0);
+
+// ---------------------------------------------------------------------------
+// Specs
+// ---------------------------------------------------------------------------
+
+/// How a game's boss looks, moves and fights.
+pub struct BossSpec {
+    pub name: &'static str,
+    pub size: Vec2,
+    pub color: Color,
+    pub hp: i32,
+    /// Weak points, as offsets from the centre.
+    pub weak_points: &'static [Vec2],
+    /// The boss sweeps around this point ...
+    pub anchor: Vec2,
+    /// ... this far along each axis.
+    pub sweep: Vec2,
+    /// Seconds between volleys in phase 1.
+    pub fire_interval: f32,
+    pub shot_speed: f32,
+    pub phase_bonus: i32,
+    pub defeat_bonus: i32,
+}
+
+/// A volley.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attack {
+    /// `count` shots at the target, `spread` radians apart.
+    Aimed { count: u32, spread: f32 },
+    /// `count` shots fanned across `arc` radians, centred on the target.
+    Fan { count: u32, arc: f32 },
+    /// `count` shots in every direction.
+    Ring { count: u32 },
+}
+
+impl Attack {
+    /// Velocities of the shots fired from `from` at `target`.
+    pub fn velocities(self, from: Vec2, target: Vec2, speed: f32) -> Vec<Vec2> {
+        let aim = (target - from).normalize_or(Vec2::NEG_Y).to_angle();
+        let spaced = |count: u32, step: f32| {
+            let start = aim - step * (count.saturating_sub(1)) as f32 / 2.0;
+            (0..count)
+                .map(|i| Vec2::from_angle(start + step * i as f32) * speed)
+                .collect()
+        };
+        match self {
+            Self::Aimed { count, spread } => spaced(count, spread),
+            Self::Fan { count, arc } => spaced(count, arc / count.saturating_sub(1).max(1) as f32),
+            Self::Ring { count } => (0..count)
+                .map(|i| Vec2::from_angle(std::f32::consts::TAU * i as f32 / count as f32) * speed)
+                .collect(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Waves
+// ---------------------------------------------------------------------------
+
+/// What a game's spawn timer should produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spawn {
+    Enemy,
+    Boss,
+    /// A boss is up; spawn nothing.
+    Hold,
+}
+
+/// Wave bookkeeping, kept in each game's `GameState`.
+#[derive(Default, Debug)]
+pub struct Waves {
+    /// Waves completed.
+    pub wave: u32,
+    spawned: u32,
+    pub boss_active: bool,
+    pub bosses_defeated: u32,
+}
+
+impl Waves {
+    /// Call each time the spawn timer fires.
+    pub fn next(&mut self, boss_every: u32) -> Spawn {
+        if self.boss_active {
+            return Spawn::Hold;
+        }
+        if self.spawned >= WAVE_SIZE {
+            self.spawned = 0;
+            self.wave += 1;
+            if self.wave % boss_every.max(1) == 0 {
+                self.boss_active = true;
+                return Spawn::Boss;
+            }
+        }
+        self.spawned += 1;
+        Spawn::Enemy
+    }
+
+    pub fn boss_defeated(&mut self) {
+        self.boss_active = false;
+        self.bosses_defeated += 1;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct Boss {
+    pub hp: i32,
+    pub max_hp: i32,
+    spec: &'static BossSpec,
+    phase: u8,
+    time: f32,
+    fire_timer: f32,
+    volleys: u32,
+}
+
+/// Glowing spot on a boss, a child of the boss entity.
+#[derive(Component)]
+struct WeakPoint;
+
+/// Marks the player bosses aim at.
+#[derive(Component)]
+pub struct BossTarget;
+
+#[derive(Component)]
+pub struct BossShot {
+    pub velocity: Vec2,
+}
+
+/// Root of the HUD health bar.  Hidden while no boss is up.
+#[derive(Component)]
+pub struct BossHealthBar {
+    fill: Entity,
+    label: Entity,
+}
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
+#[derive(Component)]
+pub struct BossHealthLabel;
+
+/// Result of damaging a boss.
+#[derive(Default, Debug)]
+pub struct BossHit {
+    pub points: i32,
+    pub phase_broken: bool,
+    pub defeated: bool,
+}
+
+impl Boss {
+    pub fn phase(&self) -> u8 {
+        self.phase
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.spec.name
+    }
+
+    fn phase_for(hp: i32, max_hp: i32) -> u8 {
+        if hp * 3 > max_hp * 2 {
+            1
+        } else if hp * 3 > max_hp {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// The next volley.  Phase 3 alternates a ring with an aimed burst.
+    fn attack(&self) -> Attack {
+        match self.phase {
+            1 => Attack::Aimed { count: 3, spread: 0.15 },
+            2 => Attack::Fan { count: 5, arc: 1.2 },
+            _ if self.volleys % 2 == 0 => Attack::Ring { count: 12 },
+            _ => Attack::Aimed { count: 5, spread: 0.1 },
+        }
+    }
+
+    /// Moves the boss along its sweep and returns the velocities of any
+    /// shots it fires this frame, aimed at `target`.
+    pub fn update(&mut self, tf: &mut Transform, target: Vec2, dt: f32) -> Vec<Vec2> {
+        let pace = 1.0 + 0.35 * f32::from(self.phase - 1);
+        self.time += dt * pace;
+        let spec = self.spec;
+        tf.translation.x = spec.anchor.x + spec.sweep.x * (self.time * 0.6).sin();
+        tf.translation.y = spec.anchor.y + spec.sweep.y * (self.time * 1.1).sin();
+
+        self.fire_timer -= dt;
+        if self.fire_timer > 0.0 {
+            return Vec::new();
+        }
+        self.fire_timer = spec.fire_interval / pace;
+        let attack = self.attack();
+        self.volleys += 1;
+        attack.velocities(tf.translation.truncate(), target, spec.shot_speed)
+    }
+
+    /// Damage a bullet at `point` does, if it hits.
+    pub fn damage_at(&self, boss_pos: Vec2, point: Vec2) -> Option<i32> {
+        let local = point - boss_pos;
+        if self
+            .spec
+            .weak_points
+            .iter()
+            .any(|wp| wp.distance(local) < WEAK_POINT_RADIUS)
+        {
+            return Some(WEAK_POINT_MULTIPLIER);
+        }
+        let half = self.spec.size / 2.0;
+        (local.x.abs() < half.x && local.y.abs() < half.y).then_some(1)
+    }
+
+    pub fn take_damage(&mut self, damage: i32) -> BossHit {
+        let mut hit = BossHit { points: damage * HIT_SCORE, ..default() };
+        self.hp = (self.hp - damage).max(0);
+        let phase = Self::phase_for(self.hp, self.max_hp);
+        if phase > self.phase {
+            self.phase = phase;
+            hit.phase_broken = true;
+            hit.points += self.spec.phase_bonus;
+        }
+        if self.hp == 0 {
+            hit.defeated = true;
+            hit.points += self.spec.defeat_bonus;
+        }
+        hit
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Spawning
+// ---------------------------------------------------------------------------
+
+/// Spawns the boss at the start of its sweep.  `level` is how many bosses
+/// this run has already beaten.
+pub fn spawn_boss(
+    commands: &mut Commands,
+    assets: &PixarAssets,
+    spec: &'static BossSpec,
+    level: u32,
+    bundle: impl Bundle,
+) -> Entity {
+    let hp = spec.hp + (spec.hp as f32 * HP_GROWTH * level as f32) as i32;
+    let boss = pixar::spawn_character(
+        commands,
+        assets,
+        &CharacterConfig::enemy(spec.color, spec.size),
+        spec.anchor.extend(0.8),
+        (
+            Boss { hp, max_hp: hp, spec, phase: 1, time: 0.0, fire_timer: spec.fire_interval, volleys: 0 },
+            bundle,
+        ),
+    );
+    commands.entity(boss).with_children(|parent| {
+        for wp in spec.weak_points {
+            parent.spawn((
+                Sprite {
+                    image: assets.circle.clone(),
+                    color: palette::HERO_YELLOW,
+                    custom_size: Some(Vec2::splat(WEAK_POINT_RADIUS * 2.0)),
+                    ..default()
+                },
+                Transform::from_translation(wp.extend(0.3)),
+                WeakPoint,
+            ));
+        }
+    });
+    boss
+}
+
+pub fn shot(origin: Vec2, velocity: Vec2) -> impl Bundle {
+    (
+        Sprite { color: palette::VILLAIN_PURPLE, custom_size: Some(SHOT_SIZE), ..default() },
+        Transform::from_translation(origin.extend(0.6)),
+        BossShot { velocity },
+    )
+}
+
+/// Spawns the hidden health bar along the top of the screen.
+pub fn spawn_health_bar(commands: &mut Commands, bundle: impl Bundle) {
+    let mut label = Entity::PLACEHOLDER;
+    let mut fill = Entity::PLACEHOLDER;
+    let root = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(40.0),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-BAR_WIDTH / 2.0)),
+                width: Val::Px(BAR_WIDTH),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            label = parent
+                .spawn((
+                    Text::new(""),
+                    TextFont { font_size: 18.0, ..default() },
+                    TextColor(Color::srgb(1.0, 0.75, 0.3)),
+                    BossHealthLabel,
+                ))
+                .id();
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(12.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.15, 0.1, 0.15)),
+                    BorderColor(Color::srgb(0.9, 0.9, 0.9)),
+                ))
+                .with_children(|track| {
+                    fill = track
+                        .spawn((
+                            Node { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                            BackgroundColor(palette::VILLAIN_RED),
+                            BossHealthFill,
+                        ))
+                        .id();
+                });
+        })
+        .id();
+    commands.entity(root).insert((BossHealthBar { fill, label }, bundle));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+//
+// Generic over the game's `GameEntity` marker `M`; each game registers its
+// own instances alongside its other systems.
+
+/// Moves `M`'s boss and fires its volleys at the `BossTarget`.
+pub fn update_bosses<M: Component + Default>(
+    time: Res<Time>,
+    mut commands: Commands,
+    targets: Query<&Transform, (With<BossTarget>, With<M>, Without<Boss>)>,
+    mut bosses: Query<(&mut Transform, &mut Boss), With<M>>,
+) {
+    let dt = time.delta_secs();
+    let target = targets.get_single().map(|tf| tf.translation.truncate()).unwrap_or_default();
+    for (mut tf, mut boss) in &mut bosses {
+        for velocity in boss.update(&mut tf, target, dt) {
+            commands.spawn((shot(tf.translation.truncate(), velocity), M::default()));
+        }
+    }
+}
+
+pub fn move_shots<M: Component>(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Transform, &BossShot), With<M>>,
+) {
+    let dt = time.delta_secs();
+    for (e, mut tf, shot) in &mut q {
+        tf.translation += (shot.velocity * dt).extend(0.0);
+        if tf.translation.x.abs() > SCREEN_HALF.x + 40.0 || tf.translation.y.abs() > SCREEN_HALF.y + 40.0 {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Shows `M`'s boss health on its bar, or hides the bar when there's no
+/// boss.
+pub fn update_health_bar<M: Component>(
+    bosses: Query<&Boss, With<M>>,
+    mut bars: Query<(&BossHealthBar, &mut Visibility), With<M>>,
+    mut fills: Query<&mut Node, With<BossHealthFill>>,
+    mut labels: Query<&mut Text, With<BossHealthLabel>>,
+) {
+    let boss = bosses.iter().next();
+    for (bar, mut visibility) in &mut bars {
+        let Some(boss) = boss else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        if let Ok(mut node) = fills.get_mut(bar.fill) {
+            node.width = Val::Percent(100.0 * boss.hp as f32 / boss.max_hp.max(1) as f32);
+        }
+        if let Ok(mut text) = labels.get_mut(bar.label) {
+            **text = format!("{} - Phase {}", boss.name(), boss.phase());
+        }
+    }
+}
@@ -0,0 +1,613 @@
+use bevy::prelude::*;
+use crate::BevyBridge;
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::asset_loader::CustomAssets;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Beams snap to a grid of this spacing.
+const GRID: f32 = 40.0;
+const DECK_Y: f32 = 0.0;
+const GRID_MIN_Y: f32 = -160.0;
+const GRID_MAX_Y: f32 = 120.0;
+const MAX_BEAM_LEN: f32 = 3.0 * GRID;
+/// Budget units per unit of beam length.
+const COST_PER_UNIT: f32 = 1.0;
+
+// Spring model.  Every joint has unit mass; beams pull along their length
+// with `STIFFNESS * strain` and snap beyond `BREAK_FORCE`.
+const GRAVITY: f32 = 100.0;
+const STIFFNESS: f32 = 2_000_000.0;
+const DAMPING: f32 = 400.0;
+const DRAG: f32 = 2.0;
+const BREAK_FORCE: f32 = 15_000.0;
+const SUBSTEPS: usize = 16;
+
+const VEHICLE_WEIGHT: f32 = 2000.0;
+const VEHICLE_SPEED: f32 = 80.0;
+/// Vehicles cross one after another, each heavier than the last.
+const LOADS: [f32; 3] = [1.0, 1.6, 2.2];
+/// How long the bridge settles under its own weight before the first
+/// vehicle sets off.
+const SETTLE_SECS: f32 = 1.0;
+const LOAD_POINTS: f32 = 200.0;
+/// Awarded in full for a bridge that costs nothing, scaled down with the
+/// share of the budget spent.
+const EFFICIENCY_POINTS: f32 = 400.0;
+
+struct Level {
+    gap: f32,
+    budget: f32,
+}
+
+const LEVELS: [Level; 3] = [
+    Level { gap: 240.0, budget: 1000.0 },
+    Level { gap: 320.0, budget: 1300.0 },
+    Level { gap: 400.0, budget: 1700.0 },
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+/// Despawned when the next level is built.
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Component)]
+pub struct JointVisual(usize);
+
+#[derive(Component)]
+pub struct BeamVisual(usize);
+
+#[derive(Component)]
+pub struct Vehicle;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+struct Joint {
+    /// Where the player put it.
+    design: Vec2,
+    pos: Vec2,
+    vel: Vec2,
+    /// Anchors on the cliffs never move.
+    fixed: bool,
+}
+
+struct Beam {
+    a: usize,
+    b: usize,
+    rest: f32,
+    /// Last axial force, positive in tension.
+    force: f32,
+    broken: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase { Build, Simulate, Result }
+
+#[derive(Resource)]
+pub struct GameState {
+    score: i32,
+    level: usize,
+    joints: Vec<Joint>,
+    beams: Vec<Beam>,
+    selected: Option<usize>,
+    phase: Phase,
+    /// Seconds since the simulation started, or left to show the result.
+    timer: f32,
+    vehicle: usize,
+    vehicle_x: f32,
+    vehicle_y: f32,
+    crossed: usize,
+    failed_runs: i32,
+    message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn left_edge(level: usize) -> f32 { -LEVELS[level].gap / 2.0 }
+fn right_edge(level: usize) -> f32 { LEVELS[level].gap / 2.0 }
+
+/// The anchors on each cliff: one at deck height, one on the face below.
+fn anchors(level: usize) -> [Vec2; 4] {
+    let (l, r) = (left_edge(level), right_edge(level));
+    [Vec2::new(l, DECK_Y), Vec2::new(l, DECK_Y - 2.0 * GRID), Vec2::new(r, DECK_Y), Vec2::new(r, DECK_Y - 2.0 * GRID)]
+}
+
+fn new_joints(level: usize) -> Vec<Joint> {
+    anchors(level).iter().map(|&p| Joint { design: p, pos: p, vel: Vec2::ZERO, fixed: true }).collect()
+}
+
+/// The grid point nearest `p`, if it lies over the gap.
+fn snap(level: usize, p: Vec2) -> Option<Vec2> {
+    let x = left_edge(level) + ((p.x - left_edge(level)) / GRID).round() * GRID;
+    let y = (p.y / GRID).round() * GRID;
+    let inside = x >= left_edge(level) - 0.5 && x <= right_edge(level) + 0.5 && (GRID_MIN_Y..=GRID_MAX_Y).contains(&y);
+    inside.then_some(Vec2::new(x, y))
+}
+
+fn joint_at(state: &GameState, p: Vec2) -> Option<usize> {
+    state.joints.iter().position(|j| j.design.distance(p) < 1.0)
+}
+
+fn cost(state: &GameState) -> f32 {
+    state.beams.iter().map(|b| b.rest * COST_PER_UNIT).sum()
+}
+
+/// A road beam is a horizontal beam at deck height: vehicles drive on it.
+fn is_road(state: &GameState, beam: &Beam) -> bool {
+    let (a, b) = (state.joints[beam.a].design, state.joints[beam.b].design);
+    a.y == DECK_Y && b.y == DECK_Y
+}
+
+/// The road under `x` and how far along it, if the road there is intact.
+fn road_at(state: &GameState, x: f32) -> Option<(usize, f32)> {
+    state.beams.iter().enumerate().find_map(|(i, beam)| {
+        if beam.broken || !is_road(state, beam) { return None; }
+        let (a, b) = (state.joints[beam.a].design.x, state.joints[beam.b].design.x);
+        let (lo, hi) = (a.min(b), a.max(b));
+        if x < lo || x > hi { return None; }
+        let t = (x - a) / (b - a);
+        Some((i, t))
+    })
+}
+
+/// Puts the design back as the player built it.
+fn reset_design(state: &mut GameState) {
+    for j in &mut state.joints {
+        j.pos = j.design;
+        j.vel = Vec2::ZERO;
+    }
+    for b in &mut state.beams {
+        b.force = 0.0;
+        b.broken = false;
+    }
+    state.vehicle = 0;
+    state.crossed = 0;
+    state.vehicle_x = left_edge(state.level) - 120.0;
+    state.vehicle_y = DECK_Y;
+}
+
+/// Removes the last beam, and the joints it leaves unconnected.
+fn undo(state: &mut GameState) {
+    state.beams.pop();
+    while let Some(last) = state.joints.len().checked_sub(1) {
+        let joint = &state.joints[last];
+        if joint.fixed || state.beams.iter().any(|b| b.a == last || b.b == last) { break; }
+        state.joints.pop();
+    }
+    state.selected = state.selected.filter(|&s| s < state.joints.len());
+}
+
+/// Adds a beam from the selected joint to the grid point `p`.
+fn place(state: &mut GameState, p: Vec2) {
+    let Some(from) = state.selected else {
+        // Beams start at a joint that's already part of the bridge.
+        state.selected = joint_at(state, p);
+        if state.selected.is_none() {
+            state.message = "Start from an anchor or a joint".into();
+        }
+        return;
+    };
+
+    let start = state.joints[from].design;
+    let len = start.distance(p);
+    if len < 1.0 {
+        state.selected = None;
+        return;
+    }
+    if len > MAX_BEAM_LEN {
+        state.message = "Too long for one beam".into();
+        return;
+    }
+    if cost(state) + len * COST_PER_UNIT > LEVELS[state.level].budget {
+        state.message = "Over budget".into();
+        return;
+    }
+
+    let to = joint_at(state, p).unwrap_or_else(|| {
+        state.joints.push(Joint { design: p, pos: p, vel: Vec2::ZERO, fixed: false });
+        state.joints.len() - 1
+    });
+    let exists = state.beams.iter().any(|b| (b.a == from && b.b == to) || (b.a == to && b.b == from));
+    if !exists {
+        state.beams.push(Beam { a: from, b: to, rest: len, force: 0.0, broken: false });
+    }
+    state.selected = Some(to);
+    state.message.clear();
+}
+
+/// One spring-model step of `dt` seconds, with `load` pressing down on the
+/// road at the vehicle.
+fn step_physics(state: &mut GameState, dt: f32, load: Option<(usize, f32, f32)>) {
+    let mut forces = vec![Vec2::new(0.0, -GRAVITY); state.joints.len()];
+    if let Some((beam, t, weight)) = load {
+        let (a, b) = (state.beams[beam].a, state.beams[beam].b);
+        forces[a].y -= weight * (1.0 - t);
+        forces[b].y -= weight * t;
+    }
+
+    for beam in state.beams.iter_mut().filter(|b| !b.broken) {
+        let (ja, jb) = (&state.joints[beam.a], &state.joints[beam.b]);
+        let d = jb.pos - ja.pos;
+        let len = d.length().max(0.001);
+        let dir = d / len;
+        beam.force = STIFFNESS * (len - beam.rest) / beam.rest;
+        let f = beam.force + DAMPING * (jb.vel - ja.vel).dot(dir);
+        forces[beam.a] += dir * f;
+        forces[beam.b] -= dir * f;
+    }
+
+    for (joint, force) in state.joints.iter_mut().zip(forces) {
+        if joint.fixed { continue; }
+        joint.vel += force * dt;
+        joint.vel *= 1.0 - DRAG * dt;
+        joint.pos += joint.vel * dt;
+    }
+
+    for beam in &mut state.beams {
+        if beam.force.abs() > BREAK_FORCE {
+            beam.broken = true;
+        }
+    }
+}
+
+fn stress_color(force: f32) -> Color {
+    let t = (force.abs() / BREAK_FORCE).clamp(0.0, 1.0);
+    Color::srgb(0.3 + 0.65 * t, 0.8 - 0.6 * t, 0.3 - 0.15 * t)
+}
+
+fn spawn_level(commands: &mut Commands, level: usize) {
+    let (l, r) = (left_edge(level), right_edge(level));
+    // Cliffs
+    for (x0, x1) in [(-480.0, l), (r, 480.0)] {
+        commands.spawn((
+            Sprite { color: palette::GROUND_BROWN, custom_size: Some(Vec2::new(x1 - x0, 320.0)), ..default() },
+            Transform::from_xyz((x0 + x1) / 2.0, DECK_Y - 160.0, 0.0),
+            LevelEntity,
+            GameEntity,
+        ));
+    }
+    // Grid points
+    let mut x = l;
+    while x <= r + 0.5 {
+        let mut y = GRID_MIN_Y;
+        while y <= GRID_MAX_Y + 0.5 {
+            commands.spawn((
+                Sprite { color: palette::HIGHLIGHT, custom_size: Some(Vec2::splat(4.0)), ..default() },
+                Transform::from_xyz(x, y, 0.1),
+                LevelEntity,
+                GameEntity,
+            ));
+            y += GRID;
+        }
+        x += GRID;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+    let mut state = GameState {
+        score: 0,
+        level: 0,
+        joints: new_joints(0),
+        beams: Vec::new(),
+        selected: None,
+        phase: Phase::Build,
+        timer: 0.0,
+        vehicle: 0,
+        vehicle_x: 0.0,
+        vehicle_y: DECK_Y,
+        crossed: 0,
+        failed_runs: 0,
+        message: String::new(),
+    };
+    reset_design(&mut state);
+    commands.insert_resource(state);
+
+    // Background
+    if let Some(ref bg_handle) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::SKY_BLUE, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    }
+
+    spawn_level(&mut commands, 0);
+
+    let config = CharacterConfig::vehicle(palette::HERO_ORANGE, Vec2::new(56.0, 28.0));
+    pixar::spawn_character(&mut commands, &pixar_assets, &config, Vec3::new(-600.0, DECK_Y + 14.0, 3.0), (
+        Vehicle,
+        GameEntity,
+    ));
+
+    commands.spawn((
+        Text::new("Level 1"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+        ScoreText,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Building: click a joint, then grid points, to lay beams.  Right click or
+/// Escape lets go of the joint, Backspace undoes the last beam and Space
+/// sends the vehicles.  While they run, R goes back to building.
+pub fn player_input(
+    input: Res<ActionState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut state: ResMut<GameState>,
+) {
+    if state.phase == Phase::Simulate {
+        if input.just_pressed(Control::Reload) {
+            reset_design(&mut state);
+            state.phase = Phase::Build;
+        }
+        return;
+    }
+    if state.phase != Phase::Build { return; }
+
+    if input.just_pressed(Control::Pause) || mouse.just_pressed(MouseButton::Right) {
+        state.selected = None;
+    }
+    if input.just_pressed(Control::Undo) {
+        undo(&mut state);
+    }
+    if input.just_pressed(Control::Action) {
+        if state.beams.is_empty() {
+            state.message = "Build something first".into();
+        } else {
+            reset_design(&mut state);
+            state.selected = None;
+            state.phase = Phase::Simulate;
+            state.timer = 0.0;
+            state.message.clear();
+        }
+        return;
+    }
+
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let Some(screen_pos) = screen_pos else { return };
+    let Ok((camera, cam_tf)) = camera_q.get_single() else { return };
+    let Ok(cursor) = camera.viewport_to_world_2d(cam_tf, screen_pos) else { return };
+    let level = state.level;
+    if let Some(p) = snap(level, cursor) {
+        place(&mut state, p);
+    }
+}
+
+/// Runs the spring model and drives the vehicles across.
+pub fn simulate(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut failures: EventWriter<Failure>,
+) {
+    if state.phase != Phase::Simulate { return; }
+    let dt = time.delta_secs().min(1.0 / 30.0);
+    state.timer += dt;
+
+    let (l, r) = (left_edge(state.level), right_edge(state.level));
+    let driving = state.timer > SETTLE_SECS;
+    if driving {
+        state.vehicle_x += VEHICLE_SPEED * dt;
+    }
+    let x = state.vehicle_x;
+    let over_gap = x > l && x < r;
+    let weight = VEHICLE_WEIGHT * LOADS[state.vehicle];
+
+    let road = if over_gap { road_at(&state, x) } else { None };
+    if over_gap && road.is_none() {
+        failures.send(Failure(Vec2::new(x, state.vehicle_y)));
+        if state.crossed == 0 {
+            state.failed_runs += 1;
+            state.message = "The bridge gave way!".into();
+        } else {
+            state.message = format!("{} of {} vehicles made it", state.crossed, LOADS.len());
+        }
+        state.phase = Phase::Result;
+        state.timer = 2.0;
+        return;
+    }
+
+    let load = road.map(|(beam, t)| (beam, t, weight));
+    let sub_dt = dt / SUBSTEPS as f32;
+    for _ in 0..SUBSTEPS {
+        step_physics(&mut state, sub_dt, load);
+    }
+
+    state.vehicle_y = match road {
+        Some((beam, t)) => {
+            let (a, b) = (state.beams[beam].a, state.beams[beam].b);
+            let (ya, yb) = (state.joints[a].pos.y, state.joints[b].pos.y);
+            ya + (yb - ya) * t
+        }
+        None => DECK_Y,
+    };
+
+    if x > r + 80.0 {
+        state.crossed += 1;
+        state.vehicle += 1;
+        if state.vehicle >= LOADS.len() {
+            state.message = "Every vehicle made it across!".into();
+            state.phase = Phase::Result;
+            state.timer = 2.0;
+        } else {
+            state.vehicle_x = l - 120.0;
+        }
+    }
+}
+
+/// Scores a finished run and moves to the next level, or back to building
+/// if nothing got across.
+pub fn finish_run(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut commands: Commands,
+    level_entities: Query<Entity, With<LevelEntity>>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    if state.phase != Phase::Result { return; }
+    state.timer -= time.delta_secs();
+    if state.timer > 0.0 { return; }
+
+    if state.crossed == 0 {
+        reset_design(&mut state);
+        state.phase = Phase::Build;
+        return;
+    }
+
+    let budget = LEVELS[state.level].budget;
+    let load_points: f32 = LOADS[..state.crossed].iter().map(|l| LOAD_POINTS * l).sum();
+    let efficiency = EFFICIENCY_POINTS * ((budget - cost(&state)) / budget).max(0.0);
+    state.score += (load_points + efficiency) as i32;
+    analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
+
+    state.level += 1;
+    if state.level >= LEVELS.len() {
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+    for e in &level_entities { commands.entity(e).despawn_recursive(); }
+    let level = state.level;
+    spawn_level(&mut commands, level);
+    state.joints = new_joints(level);
+    state.beams.clear();
+    state.selected = None;
+    reset_design(&mut state);
+    state.phase = Phase::Build;
+    state.message.clear();
+}
+
+/// Keeps a sprite per joint and beam, coloured by stress.
+pub fn update_visuals(
+    mut commands: Commands,
+    state: Res<GameState>,
+    mut joints_q: Query<(Entity, &JointVisual, &mut Transform, &mut Sprite), (Without<BeamVisual>, Without<Vehicle>)>,
+    mut beams_q: Query<(Entity, &BeamVisual, &mut Transform, &mut Sprite, &mut Visibility), (Without<JointVisual>, Without<Vehicle>)>,
+    mut vehicle_q: Query<&mut Transform, (With<Vehicle>, Without<JointVisual>, Without<BeamVisual>)>,
+) {
+    let mut has_joint = vec![false; state.joints.len()];
+    for (e, JointVisual(i), mut tf, mut sprite) in &mut joints_q {
+        let Some(joint) = state.joints.get(*i) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        has_joint[*i] = true;
+        tf.translation = joint.pos.extend(2.0);
+        sprite.color = if state.selected == Some(*i) {
+            palette::GOLD
+        } else if joint.fixed {
+            palette::VILLAIN_DARK
+        } else {
+            palette::SILVER
+        };
+    }
+    for (i, _) in has_joint.iter().enumerate().filter(|(_, has)| !**has) {
+        commands.spawn((
+            Sprite { color: palette::SILVER, custom_size: Some(Vec2::splat(12.0)), ..default() },
+            Transform::from_translation(state.joints[i].pos.extend(2.0)),
+            JointVisual(i),
+            LevelEntity,
+            GameEntity,
+        ));
+    }
+
+    let mut has_beam = vec![false; state.beams.len()];
+    for (e, BeamVisual(i), mut tf, mut sprite, mut visibility) in &mut beams_q {
+        let Some(beam) = state.beams.get(*i) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        has_beam[*i] = true;
+        let (a, b) = (state.joints[beam.a].pos, state.joints[beam.b].pos);
+        let d = b - a;
+        tf.translation = ((a + b) / 2.0).extend(1.0);
+        tf.rotation = Quat::from_rotation_z(d.y.atan2(d.x));
+        sprite.custom_size = Some(Vec2::new(d.length(), if is_road(&state, beam) { 8.0 } else { 5.0 }));
+        sprite.color = stress_color(beam.force);
+        *visibility = if beam.broken { Visibility::Hidden } else { Visibility::Visible };
+    }
+    for (i, _) in has_beam.iter().enumerate().filter(|(_, has)| !**has) {
+        commands.spawn((
+            Sprite { color: stress_color(0.0), custom_size: Some(Vec2::new(1.0, 5.0)), ..default() },
+            Transform::default(),
+            BeamVisual(i),
+            LevelEntity,
+            GameEntity,
+        ));
+    }
+
+    for mut tf in &mut vehicle_q {
+        tf.translation = Vec3::new(state.vehicle_x, state.vehicle_y + 14.0, 3.0);
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score;
+    bridge.stats.level = Some(state.level.min(LEVELS.len() - 1) as i32 + 1);
+    bridge.stats.deaths = Some(state.failed_runs);
+}
+
+pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+    let level = state.level.min(LEVELS.len() - 1);
+    let hint = match state.phase {
+        Phase::Build => "Click joints to lay beams | Space: test",
+        Phase::Simulate => "R: rebuild",
+        Phase::Result => "",
+    };
+    for mut t in &mut q {
+        **t = format!(
+            "Level {} | Cost: {:.0}/{:.0} | Score: {} | {}  {}",
+            level + 1,
+            cost(&state),
+            LEVELS[level].budget,
+            state.score,
+            hint,
+            state.message,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
 
 // ---------------------------------------------------------------------------
@@ -17,6 +20,7 @@ const BRAKE_RATE: f32 = 2.0;
 const MAX_VEL: f32 = 4.0;
 const DANGER_SPEED: f32 = 1.5;
 const NUM_WAYPOINTS: usize = 20;
+const PASSENGERS: i32 = 5;
 
 // ---------------------------------------------------------------------------
 // Components
@@ -26,7 +30,7 @@ const NUM_WAYPOINTS: usize = 20;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct CableCar {
+pub struct CableCar {
     path_t: f32,
     velocity: f32,
     passengers: i32,
@@ -38,12 +42,12 @@ struct Waypoint {
 }
 
 #[derive(Component)]
-struct Obstacle {
+pub struct Obstacle {
     path_t: f32,
 }
 
 #[derive(Component)]
-struct Collectible {
+pub struct Collectible {
     path_t: f32,
 }
 
@@ -51,13 +55,14 @@ struct Collectible {
 struct CableLine;
 
 #[derive(Component)]
-struct HudText;
+pub struct HudText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     waypoints: Vec<Vec2>,
     finished: bool,
+    collected: i32,
 }
 
 // ---------------------------------------------------------------------------
@@ -78,7 +83,12 @@ fn path_position(waypoints: &[Vec2], t: f32) -> Vec2 {
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
     let mut rng = rand::thread_rng();
 
     // Generate waypoints along a winding path
@@ -157,15 +167,16 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 
     // Cable car
     let start = path_position(&wps, 0.0);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::vehicle(palette::HERO_ORANGE, CAR_SIZE),
         Vec3::new(start.x, start.y, 2.0),
-        (CableCar { path_t: 0.0, velocity: 0.5, passengers: 5 }, GameEntity),
+        (CableCar { path_t: 0.0, velocity: 0.5, passengers: PASSENGERS }, GameEntity),
     );
 
-    commands.insert_resource(GameState { score: 0, waypoints: wps, finished: false });
+    commands.insert_resource(GameState { score: 0, waypoints: wps, finished: false, collected: 0 });
 
     // HUD
     commands.spawn((
@@ -182,14 +193,14 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn car_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
     time: Res<Time>,
     mut q: Query<&mut CableCar>,
 ) {
     let dt = time.delta_secs();
     for mut car in &mut q {
-        let accel = keys.pressed(KeyCode::Space) || mouse.pressed(MouseButton::Left);
+        let accel = input.pressed(Control::Action) || mouse.pressed(MouseButton::Left);
         if accel {
             car.velocity = (car.velocity + ACCEL_RATE * dt).min(MAX_VEL);
         } else {
@@ -219,17 +230,19 @@ pub fn move_car(
 }
 
 pub fn check_obstacles(
-    mut car_q: Query<&mut CableCar>,
+    mut car_q: Query<(&Transform, &mut CableCar)>,
     obs_q: Query<(Entity, &Obstacle)>,
     mut commands: Commands,
+    mut failures: EventWriter<Failure>,
 ) {
-    for mut car in &mut car_q {
+    for (tf, mut car) in &mut car_q {
         for (oe, obs) in &obs_q {
             let dist = (car.path_t - obs.path_t).abs();
             if dist < 0.3 {
                 if car.velocity > DANGER_SPEED {
                     car.passengers -= 1;
                     car.velocity *= 0.4;
+                    failures.send(Failure(tf.translation.truncate()));
                 }
                 commands.entity(oe).despawn();
             }
@@ -248,6 +261,7 @@ pub fn check_collectibles(
             let dist = (car.path_t - col.path_t).abs();
             if dist < 0.4 {
                 state.score += 50;
+                state.collected += 1;
                 commands.entity(ce).despawn();
             }
         }
@@ -275,8 +289,12 @@ pub fn check_finish(
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, car_q: Query<&CableCar>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.collectibles = Some(state.collected);
+    if let Ok(car) = car_q.get_single() {
+        bridge.stats.deaths = Some(PASSENGERS - car.passengers.max(0));
+    }
 }
 
 pub fn update_hud(
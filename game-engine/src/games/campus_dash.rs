@@ -1,23 +1,38 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
+use crate::game_mode::GameMode;
+use crate::remote_config::RemoteConfig;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::ghost::GhostTracked;
+use crate::input::{GameAction, Rumble};
+use crate::levelgen::{self, LevelRng};
+use crate::pool::{Parked, Pool};
+use crate::powerups::{self, ActiveEffects, Pickup, Powerup};
+use crate::timestep::Interpolated;
 
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
 
+// Defaults for the remotely tunable values, read through `RemoteConfig`
+// under the camelCase key noted next to each.
+
 const GROUND_Y: f32 = -250.0;
 const PLAYER_X: f32 = -300.0;
 const PLAYER_SIZE: Vec2 = Vec2::new(30.0, 50.0);
-const GRAVITY: f32 = -1400.0;
-const JUMP_VELOCITY: f32 = 600.0;
-const BASE_SPEED: f32 = 300.0;
-const SPEED_INCREASE: f32 = 5.0; // per second
-const OBSTACLE_MIN_GAP: f32 = 250.0;
-const OBSTACLE_MAX_GAP: f32 = 400.0;
+const GRAVITY: f32 = -1400.0; // gravity
+const JUMP_VELOCITY: f32 = 600.0; // jumpVelocity
+const BASE_SPEED: f32 = 300.0; // baseSpeed
+const SPEED_INCREASE: f32 = 5.0; // speedIncrease, per second
+const OBSTACLE_MIN_GAP: f32 = 250.0; // obstacleMinGap
+const OBSTACLE_MAX_GAP: f32 = 400.0; // obstacleMaxGap
+const POWERUP_CHANCE: f32 = 0.2; // powerupChance, per obstacle
+const POWERUPS: &[Powerup] = &[Powerup::Shield, Powerup::Magnet, Powerup::SlowMotion, Powerup::DoubleScore];
 
 // ---------------------------------------------------------------------------
 // Components
@@ -28,40 +43,57 @@ const OBSTACLE_MAX_GAP: f32 = 400.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     vy: f32,
     on_ground: bool,
 }
 
 #[derive(Component)]
-struct Obstacle;
+pub struct Obstacle;
 
 #[derive(Component)]
 struct Ground;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
-/// Tracks elapsed time and scroll speed.
+/// Tracks elapsed time and scroll speed.  `score` is distance, counted
+/// double under `DoubleScore`; `distance` is the plain distance.
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
+    base_speed: f32,
     speed: f32,
+    score: f32,
     distance: f32,
     spawn_timer: f32,
     next_gap: f32,
+    crashes: i32,
+    /// Obstacle heights and gaps; seeded in daily runs.
+    rng: LevelRng,
 }
 
 // ---------------------------------------------------------------------------
 // Setup – runs on `OnEnter(AppState::Playing)`
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    config: Res<RemoteConfig>,
+    options: Res<GameOptions>,
+) {
     // -- Game state resource -----------------------------------------------
+    let base_speed = config.f32("baseSpeed", BASE_SPEED);
     commands.insert_resource(GameState {
-        speed: BASE_SPEED,
+        base_speed,
+        speed: base_speed,
+        score: 0.0,
         distance: 0.0,
         spawn_timer: 0.0,
-        next_gap: OBSTACLE_MIN_GAP,
+        next_gap: config.f32("obstacleMinGap", OBSTACLE_MIN_GAP),
+        crashes: 0,
+        rng: levelgen::run_rng(&options),
     });
 
     // -- Background --------------------------------------------------------
@@ -85,12 +117,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 
     // -- Player ------------------------------------------------------------
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_BLUE, PLAYER_SIZE),
         Vec3::new(PLAYER_X, GROUND_Y + PLAYER_SIZE.y / 2.0, 1.0),
-        (Player { vy: 0.0, on_ground: true }, GameEntity),
+        (Player { vy: 0.0, on_ground: true }, ActiveEffects::default(), Interpolated::default(), GameEntity, GhostTracked("campus_dash")),
     );
 
     // -- HUD ---------------------------------------------------------------
@@ -110,10 +143,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         ScoreText,
         GameEntity,
     ));
+    powerups::spawn_hud(&mut commands, GameEntity);
 
     // Spawn a couple of initial obstacles off-screen right.
-    spawn_obstacle(&mut commands, &pixar_assets, 500.0, 60.0);
-    spawn_obstacle(&mut commands, &pixar_assets, 850.0, 80.0);
+    let mut pool = Pool::<Obstacle>::default();
+    spawn_obstacle(&mut commands, &mut pool, &pixar_assets, 500.0, 60.0);
+    spawn_obstacle(&mut commands, &mut pool, &pixar_assets, 850.0, 80.0);
+    commands.insert_resource(pool);
 }
 
 // ---------------------------------------------------------------------------
@@ -121,28 +157,31 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    touches: Res<Touches>,
+    mut actions: EventReader<GameAction>,
+    config: Res<RemoteConfig>,
     mut q: Query<&mut Player>,
 ) {
-    let jump = keys.just_pressed(KeyCode::Space)
-        || keys.just_pressed(KeyCode::ArrowUp)
-        || mouse.just_pressed(MouseButton::Left)
-        || touches.any_just_pressed();
+    let jump = actions
+        .read()
+        .any(|a| matches!(a, GameAction::Jump | GameAction::Pointer));
 
     for mut player in &mut q {
         if jump && player.on_ground {
-            player.vy = JUMP_VELOCITY;
+            player.vy = config.f32("jumpVelocity", JUMP_VELOCITY);
             player.on_ground = false;
         }
     }
 }
 
-pub fn player_physics(time: Res<Time>, mut q: Query<(&mut Transform, &mut Player)>) {
+pub fn player_physics(
+    time: Res<Time>,
+    config: Res<RemoteConfig>,
+    mut q: Query<(&mut Transform, &mut Player)>,
+) {
     let dt = time.delta_secs();
+    let gravity = config.f32("gravity", GRAVITY);
     for (mut tf, mut player) in &mut q {
-        player.vy += GRAVITY * dt;
+        player.vy += gravity * dt;
         tf.translation.y += player.vy * dt;
 
         let floor = GROUND_Y + PLAYER_SIZE.y / 2.0;
@@ -154,54 +193,87 @@ pub fn player_physics(time: Res<Time>, mut q: Query<(&mut Transform, &mut Player
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn scroll_world(
     time: Res<Time>,
+    config: Res<RemoteConfig>,
     mut state: ResMut<GameState>,
-    mut obstacles: Query<&mut Transform, With<Obstacle>>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut obstacles: Query<(Entity, &mut Transform), (With<Obstacle>, Without<Parked>)>,
+    mut pickups: Query<&mut Transform, (With<Pickup>, With<GameEntity>, Without<Obstacle>)>,
+    mut pool: ResMut<Pool<Obstacle>>,
     mut commands: Commands,
-    entities: Query<Entity, With<Obstacle>>,
 ) {
-    let dt = time.delta_secs();
-    state.speed += SPEED_INCREASE * dt;
-    state.distance += state.speed * dt;
+    let fx = effects.get_single().ok();
+    let dt = time.delta_secs() * fx.map_or(1.0, ActiveEffects::world_scale);
+    state.speed += config.f32("speedIncrease", SPEED_INCREASE) * dt;
+    state.score += state.speed * dt * fx.map_or(1, ActiveEffects::score_multiplier) as f32;
 
     let scroll = state.speed * dt;
-    for mut tf in &mut obstacles {
+    state.distance += scroll;
+    // Park obstacles that have scrolled off the left edge.
+    for (entity, mut tf) in &mut obstacles {
         tf.translation.x -= scroll;
-    }
-
-    // Despawn obstacles that have scrolled off the left edge.
-    for (entity, tf) in entities.iter().zip(obstacles.iter()) {
         if tf.translation.x < -600.0 {
-            commands.entity(entity).despawn();
+            pool.release(&mut commands, entity);
         }
     }
+    for mut tf in &mut pickups {
+        tf.translation.x -= scroll;
+    }
 }
 
-pub fn spawn_obstacles(time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
+pub fn spawn_obstacles(
+    time: Res<Time>,
+    config: Res<RemoteConfig>,
+    mut state: ResMut<GameState>,
+    mut pool: ResMut<Pool<Obstacle>>,
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+) {
     let dt = time.delta_secs();
     state.spawn_timer += state.speed * dt;
 
     if state.spawn_timer >= state.next_gap {
         state.spawn_timer = 0.0;
-        let mut rng = rand::thread_rng();
-        let h = rng.gen_range(40.0..120.0);
-        state.next_gap = rng.gen_range(OBSTACLE_MIN_GAP..OBSTACLE_MAX_GAP);
-        spawn_obstacle(&mut commands, &pixar_assets, 550.0, h);
+        let h = state.rng.gen_range(40.0..120.0);
+        let min_gap = config.f32("obstacleMinGap", OBSTACLE_MIN_GAP);
+        let max_gap = config.f32("obstacleMaxGap", OBSTACLE_MAX_GAP).max(min_gap + 1.0);
+        state.next_gap = state.rng.gen_range(min_gap..max_gap);
+        spawn_obstacle(&mut commands, &mut pool, &pixar_assets, 550.0, h);
+
+        // Floats over the middle of the gap, at jump height
+        if state.rng.gen_bool(f64::from(config.f32("powerupChance", POWERUP_CHANCE).clamp(0.0, 1.0))) {
+            powerups::spawn_pickup(
+                &mut commands,
+                &pixar_assets,
+                Powerup::random(POWERUPS),
+                Vec3::new(550.0 + state.next_gap / 2.0, GROUND_Y + 140.0, 0.6),
+                10.0,
+                (Interpolated::default(), GameEntity),
+            );
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn check_collisions(
-    player_q: Query<&Transform, With<Player>>,
-    obstacle_q: Query<(&Transform, &Sprite), With<Obstacle>>,
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mode: Res<GameMode>,
+    mut player_q: Query<(&Transform, &mut ActiveEffects), With<Player>>,
+    obstacle_q: Query<(Entity, &Transform, &Sprite), (With<Obstacle>, Without<Parked>)>,
+    mut pool: ResMut<Pool<Obstacle>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+    mut rumble: EventWriter<Rumble>,
 ) {
-    let Ok(ptf) = player_q.get_single() else {
+    let Ok((ptf, mut fx)) = player_q.get_single_mut() else {
         return;
     };
     let phalf = PLAYER_SIZE / 2.0;
 
-    for (otf, sprite) in &obstacle_q {
+    for (entity, otf, sprite) in &obstacle_q {
         let osize = sprite.custom_size.unwrap_or(Vec2::new(30.0, 60.0));
         let ohalf = osize / 2.0;
 
@@ -209,19 +281,41 @@ pub fn check_collisions(
         let overlap_y = (ptf.translation.y - otf.translation.y).abs() < phalf.y + ohalf.y;
 
         if overlap_x && overlap_y {
-            next_state.set(crate::AppState::GameOver);
-            return;
+            if fx.absorb_hit() {
+                pool.release(&mut commands, entity);
+                rumble.send(Rumble::light());
+                continue;
+            }
+            failures.send(Failure(ptf.translation.truncate()));
+            rumble.send(Rumble::impact());
+            state.crashes += 1;
+            if mode.can_lose() {
+                next_state.set(crate::AppState::GameOver);
+                return;
+            }
+            // Zen: knock the obstacle away and start again from base speed.
+            pool.release(&mut commands, entity);
+            state.speed = state.base_speed;
         }
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
-    bridge.current_score = state.distance as i32;
+pub fn update_score(
+    state: Res<GameState>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut bridge: ResMut<BevyBridge>,
+) {
+    bridge.current_score = state.score as i32;
+    bridge.stats.distance = GameStats::metres(state.distance);
+    bridge.stats.deaths = Some(state.crashes);
+    if let Ok(fx) = effects.get_single() {
+        bridge.stats.collectibles = Some(fx.collected());
+    }
 }
 
 pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
     for mut text in &mut q {
-        **text = format!("Score: {}", state.distance as i32);
+        **text = format!("Score: {}", state.score as i32);
     }
 }
 
@@ -231,20 +325,22 @@ pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>
 
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
     for e in &q {
-        commands.entity(e).despawn();
+        commands.entity(e).despawn_recursive();
     }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Obstacle>>();
 }
 
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn spawn_obstacle(commands: &mut Commands, pixar_assets: &PixarAssets, x: f32, height: f32) {
-    commands.spawn((
+fn spawn_obstacle(commands: &mut Commands, pool: &mut Pool<Obstacle>, pixar_assets: &PixarAssets, x: f32, height: f32) {
+    pool.acquire(commands, (
         pixar::round_sprite(pixar_assets, palette::VILLAIN_RED, Vec2::new(30.0, height)),
         Transform::from_xyz(x, GROUND_Y + height / 2.0, 0.5),
         Obstacle,
+        Interpolated::default(),
         GameEntity,
     ));
 }
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::BevyBridge;
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
 
@@ -31,25 +32,25 @@ const PATH: [(i32, i32); 12] = [
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Turret { cooldown: f32 }
+pub struct Turret { cooldown: f32 }
 
 #[derive(Component)]
-struct Enemy { hp: i32, path_idx: usize, progress: f32 }
+pub struct Enemy { hp: i32, path_idx: usize, progress: f32 }
 
 #[derive(Component)]
-struct Bullet { dx: f32, dy: f32 }
+pub struct Bullet { dx: f32, dy: f32 }
 
 #[derive(Component)]
 struct PathMarker;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct InfoText;
+pub struct InfoText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32, gold: i32, lives: i32, wave: i32,
     wave_timer: f32, enemies_per_wave: i32, spawned: i32, spawn_cd: f32,
 }
@@ -168,12 +169,14 @@ pub fn move_enemies(
     time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands,
     mut eq: Query<(Entity, &mut Transform, &mut Enemy)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
     let dt = time.delta_secs();
     for (e, mut tf, mut enemy) in &mut eq {
         if enemy.path_idx + 1 >= PATH.len() {
             commands.entity(e).despawn();
             state.lives -= 1;
+            failures.send(Failure(tf.translation.truncate()));
             if state.lives <= 0 { next_state.set(crate::AppState::GameOver); return; }
             continue;
         }
@@ -255,6 +258,8 @@ pub fn bullet_hit(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.level = Some(state.wave);
+    bridge.stats.deaths = Some(START_LIVES - state.lives.max(0));
 }
 
 pub fn update_hud(
@@ -1,8 +1,14 @@
 use bevy::prelude::*;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::accessibility::{AccessibilitySettings, ColorChannel, ColorCoded, Shade};
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::tween::MoveTo;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -23,14 +29,14 @@ const GRAVITY_TICK: f32 = 0.12;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     gx: i32,
     gy: i32,
     keys: [bool; 3], // R, G, B
 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Clone, Copy, PartialEq)]
 enum TileKind {
@@ -44,14 +50,14 @@ enum TileKind {
 }
 
 #[derive(Component)]
-struct Tile {
+pub struct Tile {
     gx: i32,
     gy: i32,
     kind: TileKind,
 }
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     move_cooldown: f32,
     gravity_timer: f32,
@@ -61,7 +67,13 @@ struct GameState {
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    a11y: Res<AccessibilitySettings>,
+    options: Res<GameOptions>,
+) {
     commands.insert_resource(GameState { score: 0, move_cooldown: 0.0, gravity_timer: 0.0 });
 
     // Background
@@ -88,7 +100,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
             if kind == TileKind::Empty { continue; }
             let (px, py) = grid_to_world(col, row);
             let tile_size = Vec2::new(TILE - 2.0, TILE - 2.0);
-            spawn_tile(&mut commands, &pixar_assets, kind, tile_size, Vec3::new(px, py, 0.0), col, row);
+            spawn_tile(&mut commands, &pixar_assets, &a11y, kind, tile_size, Vec3::new(px, py, 0.0), (col, row));
         }
     }
 
@@ -96,12 +108,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     let (px, py) = grid_to_world(1, 1);
     let player_size = Vec2::new(TILE - 8.0, TILE - 8.0);
     let player_config = CharacterConfig::hero(palette::HERO_GREEN, player_size);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &player_config,
         Vec3::new(px, py, 1.0),
-        (Player { gx: 1, gy: 1, keys: [false; 3] }, GameEntity),
+        (Player { gx: 1, gy: 1, keys: [false; 3] }, MoveTo::at(Vec3::new(px, py, 1.0)), GameEntity),
     );
 
     // HUD
@@ -120,23 +133,25 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_move(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    mut pq: Query<(&mut MoveTo, &mut Player)>,
     tiles: Query<(Entity, &Tile, &Sprite)>,
     mut commands: Commands,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut failures: EventWriter<Failure>,
 ) {
     state.move_cooldown -= time.delta_secs();
     if state.move_cooldown > 0.0 { return; }
 
-    let Ok((mut ptf, mut player)) = pq.get_single_mut() else { return };
+    let Ok((mut mv, mut player)) = pq.get_single_mut() else { return };
 
     let (mut dx, mut dy) = (0i32, 0i32);
-    if keys.pressed(KeyCode::ArrowLeft) { dx = -1; }
-    else if keys.pressed(KeyCode::ArrowRight) { dx = 1; }
-    else if keys.pressed(KeyCode::ArrowUp) { dy = 1; }
+    if input.pressed(Control::MoveLeft) { dx = -1; }
+    else if input.pressed(Control::MoveRight) { dx = 1; }
+    else if input.pressed(Control::MoveUp) { dy = 1; }
 
     if dx == 0 && dy == 0 { return; }
 
@@ -166,8 +181,7 @@ pub fn player_move(
     player.gx = nx;
     player.gy = ny;
     let (wx, wy) = grid_to_world(nx, ny);
-    ptf.translation.x = wx;
-    ptf.translation.y = wy;
+    mv.go(Vec3::new(wx, wy, 1.0));
     state.move_cooldown = 0.15;
 
     // Check interactions at new position
@@ -182,12 +196,14 @@ pub fn player_move(
                 despawn_tile = Some(ent);
             }
             TileKind::Acid => {
+                failures.send(Failure(Vec2::new(wx, wy)));
                 next_state.set(crate::AppState::GameOver);
                 return;
             }
             TileKind::Exit => {
                 if player.keys.iter().all(|k| *k) {
                     state.score += 1000;
+                    analytics.send(AnalyticsEvent::level_complete(0, state.score));
                 }
                 next_state.set(crate::AppState::GameOver);
                 return;
@@ -203,14 +219,14 @@ pub fn player_move(
 pub fn gravity(
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    mut pq: Query<(&mut MoveTo, &mut Player)>,
     tiles: Query<&Tile>,
 ) {
     state.gravity_timer += time.delta_secs();
     if state.gravity_timer < GRAVITY_TICK { return; }
     state.gravity_timer = 0.0;
 
-    let Ok((mut ptf, mut player)) = pq.get_single_mut() else { return };
+    let Ok((mut mv, mut player)) = pq.get_single_mut() else { return };
     if player.gy <= 0 { return; }
 
     let below = player.gy - 1;
@@ -226,13 +242,15 @@ pub fn gravity(
     if !supported {
         player.gy = below;
         let (wx, wy) = grid_to_world(player.gx, player.gy);
-        ptf.translation.x = wx;
-        ptf.translation.y = wy;
+        mv.fall(Vec3::new(wx, wy, 1.0));
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, pq: Query<&Player>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    if let Ok(player) = pq.get_single() {
+        bridge.stats.collectibles = Some(player.keys.iter().filter(|k| **k).count() as i32);
+    }
 }
 
 pub fn update_hud(
@@ -264,22 +282,34 @@ fn grid_to_world(gx: i32, gy: i32) -> (f32, f32) {
     (ORIGIN_X + gx as f32 * TILE, ORIGIN_Y + gy as f32 * TILE)
 }
 
-fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, kind: TileKind, size: Vec2, position: Vec3, gx: i32, gy: i32) {
+fn key_channel(c: usize) -> ColorChannel {
+    match c {
+        0 => ColorChannel::Red,
+        1 => ColorChannel::Green,
+        _ => ColorChannel::Blue,
+    }
+}
+
+fn spawn_tile(
+    commands: &mut Commands,
+    pixar_assets: &PixarAssets,
+    a11y: &AccessibilitySettings,
+    kind: TileKind,
+    size: Vec2,
+    position: Vec3,
+    (gx, gy): (i32, i32),
+) {
     match kind {
         TileKind::Key(c) => {
             // Keys are collectibles with faces
-            let color = match c {
-                0 => Color::srgb(1.0, 0.2, 0.2),
-                1 => Color::srgb(0.2, 1.0, 0.2),
-                _ => Color::srgb(0.2, 0.2, 1.0),
-            };
-            let config = CharacterConfig::collectible(color, size.x);
+            let coded = ColorCoded::icon(key_channel(c), Shade::Bright);
+            let config = CharacterConfig::collectible(a11y.color(coded.channel, coded.shade), size.x);
             pixar::spawn_character(
                 commands,
                 pixar_assets,
                 &config,
                 position,
-                (Tile { gx, gy, kind }, GameEntity),
+                (Tile { gx, gy, kind }, coded, GameEntity),
             );
         }
         TileKind::Exit => {
@@ -324,18 +354,14 @@ fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, kind: TileKin
             );
         }
         TileKind::Door(c) => {
-            let color = match c {
-                0 => Color::srgb(0.7, 0.1, 0.1),
-                1 => Color::srgb(0.1, 0.7, 0.1),
-                _ => Color::srgb(0.1, 0.1, 0.7),
-            };
-            let config = CharacterConfig::prop(color, size, false);
+            let coded = ColorCoded::icon(key_channel(c), Shade::Dark);
+            let config = CharacterConfig::prop(a11y.color(coded.channel, coded.shade), size, false);
             pixar::spawn_character(
                 commands,
                 pixar_assets,
                 &config,
                 position,
-                (Tile { gx, gy, kind }, GameEntity),
+                (Tile { gx, gy, kind }, coded, GameEntity),
             );
         }
         TileKind::Empty => {} // never reached
@@ -0,0 +1,566 @@
+use bevy::prelude::*;
+use crate::BevyBridge;
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::asset_loader::CustomAssets;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const COLS: i32 = 7;
+const ROWS: i32 = 7;
+const TILE: f32 = 52.0;
+const ORIGIN_X: f32 = -((COLS as f32) * TILE) / 2.0 + TILE / 2.0;
+const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0 + 50.0;
+const STEP_SECS: f32 = 0.35;
+/// Commands the queue holds.  Later levels only fit with loops.
+const MAX_COMMANDS: usize = 12;
+/// Moves one run may make once its loops are unrolled.
+const MAX_STEPS: usize = 200;
+const LEVEL_SCORE: i32 = 300;
+/// Bonus for each command under `par + PAR_SLACK`.
+const PAR_BONUS: i32 = 100;
+const PAR_SLACK: i32 = 3;
+const PROGRAM_Y: f32 = -190.0;
+const BUTTON_Y: f32 = -265.0;
+const BUTTON_SIZE: Vec2 = Vec2::new(84.0, 44.0);
+const BUTTON_GAP: f32 = 6.0;
+
+/// Mazes, top row first: `#` wall, `.` floor, `S` start (facing east),
+/// `G` goal.  `par` is the fewest commands that solve the level.
+struct Level {
+    rows: [&'static str; ROWS as usize],
+    par: i32,
+}
+
+const LEVELS: [Level; 3] = [
+    // Sequencing: forward, turn, forward.
+    Level {
+        rows: ["#######", "#S...##", "####.##", "####G##", "#######", "#######", "#######"],
+        par: 6,
+    },
+    // A staircase: one loop around a four-command pattern.
+    Level {
+        rows: ["#######", "#S.####", "##..###", "###..##", "####..#", "#####G#", "#######"],
+        par: 6,
+    },
+    // Three sides of a square: a loop inside a loop.
+    Level {
+        rows: ["#######", "#S...##", "####.##", "####.##", "#G...##", "#######", "#######"],
+        par: 6,
+    },
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+/// Despawned when the next level is built.
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Component)]
+pub struct Robot;
+
+/// Marker in front of the robot showing which way it faces.
+#[derive(Component)]
+pub struct Heading;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Command { Forward, Left, Right, Repeat(u8), End }
+
+#[derive(Clone, Copy, PartialEq)]
+enum Action { Add(Command), Undo, Clear, Run }
+
+#[derive(Component)]
+pub struct PaletteButton(Action);
+
+#[derive(Component)]
+pub struct ProgramText;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase { Editing, Running, Cleared }
+
+/// Grid position, row 0 at the top, and facing: 0 north, 1 east, 2 south,
+/// 3 west.
+#[derive(Clone, Copy, PartialEq)]
+struct Pose { col: i32, row: i32, facing: usize }
+
+#[derive(Resource)]
+pub struct GameState {
+    score: i32,
+    level: usize,
+    program: Vec<Command>,
+    phase: Phase,
+    /// The running program with its loops unrolled, and the next step.
+    steps: Vec<Command>,
+    step: usize,
+    timer: f32,
+    robot: Pose,
+    crashes: i32,
+    message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn wp(col: i32, row: i32, z: f32) -> Vec3 {
+    Vec3::new(ORIGIN_X + col as f32 * TILE, ORIGIN_Y + (ROWS - 1 - row) as f32 * TILE, z)
+}
+
+fn cell(level: usize, col: i32, row: i32) -> char {
+    if col < 0 || col >= COLS || row < 0 || row >= ROWS { return '#'; }
+    LEVELS[level].rows[row as usize].as_bytes()[col as usize] as char
+}
+
+fn start_pose(level: usize) -> Pose {
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            if cell(level, col, row) == 'S' {
+                return Pose { col, row, facing: 1 };
+            }
+        }
+    }
+    Pose { col: 1, row: 1, facing: 1 }
+}
+
+fn offset(facing: usize) -> (i32, i32) {
+    match facing {
+        0 => (0, -1),
+        1 => (1, 0),
+        2 => (0, 1),
+        _ => (-1, 0),
+    }
+}
+
+fn open_loops(program: &[Command]) -> usize {
+    program.iter().fold(0, |depth, c| match c {
+        Command::Repeat(_) => depth + 1,
+        Command::End => depth.saturating_sub(1),
+        _ => depth,
+    })
+}
+
+/// Unrolls `program` from `pos` into moves, returning at its `End`.  A loop
+/// left open runs to the end of the program.
+fn unroll(program: &[Command], pos: &mut usize, out: &mut Vec<Command>) {
+    while *pos < program.len() && out.len() < MAX_STEPS {
+        let cmd = program[*pos];
+        *pos += 1;
+        match cmd {
+            Command::End => return,
+            Command::Repeat(n) => {
+                let body = *pos;
+                for _ in 0..n {
+                    *pos = body;
+                    unroll(program, pos, out);
+                }
+            }
+            step => out.push(step),
+        }
+    }
+}
+
+fn label(cmd: Command) -> String {
+    match cmd {
+        Command::Forward => "F".into(),
+        Command::Left => "L".into(),
+        Command::Right => "R".into(),
+        Command::Repeat(n) => format!("{}x[", n),
+        Command::End => "]".into(),
+    }
+}
+
+fn action_label(action: Action) -> String {
+    match action {
+        Action::Add(Command::Forward) => "Forward".into(),
+        Action::Add(Command::Left) => "Left".into(),
+        Action::Add(Command::Right) => "Right".into(),
+        Action::Add(Command::Repeat(n)) => format!("Loop x{}", n),
+        Action::Add(Command::End) => "End loop".into(),
+        Action::Undo => "Undo".into(),
+        Action::Clear => "Clear".into(),
+        Action::Run => "Run".into(),
+    }
+}
+
+const PALETTE: [Action; 10] = [
+    Action::Add(Command::Forward),
+    Action::Add(Command::Left),
+    Action::Add(Command::Right),
+    Action::Add(Command::Repeat(2)),
+    Action::Add(Command::Repeat(3)),
+    Action::Add(Command::Repeat(4)),
+    Action::Add(Command::End),
+    Action::Undo,
+    Action::Clear,
+    Action::Run,
+];
+
+fn button_x(i: usize) -> f32 {
+    let width = PALETTE.len() as f32 * (BUTTON_SIZE.x + BUTTON_GAP) - BUTTON_GAP;
+    -width / 2.0 + BUTTON_SIZE.x / 2.0 + i as f32 * (BUTTON_SIZE.x + BUTTON_GAP)
+}
+
+fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize) {
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let color = match cell(level, col, row) {
+                '#' => palette::VILLAIN_DARK,
+                'G' => palette::GOLD,
+                _ => palette::SHADOW,
+            };
+            let config = CharacterConfig::prop(color, Vec2::splat(TILE - 2.0), cell(level, col, row) == 'G');
+            pixar::spawn_character(commands, pixar_assets, &config, wp(col, row, 0.0), (
+                LevelEntity,
+                GameEntity,
+            ));
+        }
+    }
+
+    let start = start_pose(level);
+    let config = CharacterConfig::robot(palette::ELECTRIC_CYAN, Vec2::splat(TILE * 0.7));
+    let robot = pixar::spawn_character(commands, pixar_assets, &config, wp(start.col, start.row, 1.0), (
+        Robot,
+        LevelEntity,
+        GameEntity,
+    ));
+    commands.entity(robot).with_children(|parent| {
+        parent.spawn((
+            Sprite { color: palette::HERO_YELLOW, custom_size: Some(Vec2::splat(TILE * 0.18)), ..default() },
+            Transform::from_xyz(TILE * 0.38, 0.0, 0.5),
+            Heading,
+        ));
+    });
+}
+
+fn apply(action: Action, state: &mut GameState) {
+    if state.phase == Phase::Cleared { return; }
+    if state.phase == Phase::Running {
+        // Run doubles as stop; the queue is locked while the robot moves.
+        if action == Action::Run {
+            state.phase = Phase::Editing;
+            state.robot = start_pose(state.level);
+            state.message = "Stopped".into();
+        }
+        return;
+    }
+
+    match action {
+        Action::Add(cmd) => {
+            if state.program.len() >= MAX_COMMANDS {
+                state.message = format!("The queue holds {} commands - try a loop", MAX_COMMANDS);
+            } else if cmd == Command::End && open_loops(&state.program) == 0 {
+                state.message = "No loop to end".into();
+            } else {
+                state.program.push(cmd);
+                state.message.clear();
+            }
+        }
+        Action::Undo => { state.program.pop(); }
+        Action::Clear => state.program.clear(),
+        Action::Run => {
+            let mut steps = Vec::new();
+            unroll(&state.program, &mut 0, &mut steps);
+            if steps.is_empty() {
+                state.message = "Add some moves first".into();
+                return;
+            }
+            state.steps = steps;
+            state.step = 0;
+            state.timer = STEP_SECS;
+            state.robot = start_pose(state.level);
+            state.phase = Phase::Running;
+            state.message.clear();
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+    commands.insert_resource(GameState {
+        score: 0,
+        level: 0,
+        program: Vec::new(),
+        phase: Phase::Editing,
+        steps: Vec::new(),
+        step: 0,
+        timer: 0.0,
+        robot: start_pose(0),
+        crashes: 0,
+        message: String::new(),
+    });
+
+    // Background
+    if let Some(ref bg_handle) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::LAB_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    }
+
+    spawn_level(&mut commands, &pixar_assets, 0);
+
+    // Command palette
+    for (i, &action) in PALETTE.iter().enumerate() {
+        let color = match action {
+            Action::Run => palette::HERO_GREEN,
+            Action::Undo | Action::Clear => palette::HERO_RED,
+            Action::Add(Command::Repeat(_)) | Action::Add(Command::End) => palette::HERO_PURPLE,
+            Action::Add(_) => palette::HERO_BLUE,
+        };
+        commands.spawn((
+            Sprite { color, custom_size: Some(BUTTON_SIZE), ..default() },
+            Transform::from_xyz(button_x(i), BUTTON_Y, 2.0),
+            PaletteButton(action),
+            GameEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text2d::new(action_label(action)),
+                TextFont { font_size: 15.0, ..default() },
+                TextColor(Color::WHITE),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            ));
+        });
+    }
+
+    commands.spawn((
+        Text2d::new(""),
+        TextFont { font_size: 22.0, ..default() },
+        TextColor(Color::WHITE),
+        Transform::from_xyz(0.0, PROGRAM_Y, 2.0),
+        ProgramText,
+        GameEntity,
+    ));
+
+    commands.spawn((
+        Text::new("Level 1"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+        ScoreText,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Palette clicks and taps, with keyboard shortcuts: arrows for moves,
+/// 2–4 for loops, E to end a loop, Backspace to undo, Enter to run.
+#[allow(clippy::too_many_arguments)]
+pub fn player_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    buttons: Query<(&PaletteButton, &Transform)>,
+    mut state: ResMut<GameState>,
+) {
+    let controls = [
+        (Control::MoveUp, Action::Add(Command::Forward)),
+        (Control::MoveLeft, Action::Add(Command::Left)),
+        (Control::MoveRight, Action::Add(Command::Right)),
+        (Control::Undo, Action::Undo),
+        (Control::Action, Action::Run),
+    ];
+    for (control, action) in controls {
+        if input.just_pressed(control) {
+            apply(action, &mut state);
+        }
+    }
+    let shortcuts = [
+        (KeyCode::Digit2, Action::Add(Command::Repeat(2))),
+        (KeyCode::Digit3, Action::Add(Command::Repeat(3))),
+        (KeyCode::Digit4, Action::Add(Command::Repeat(4))),
+        (KeyCode::KeyE, Action::Add(Command::End)),
+    ];
+    for (key, action) in shortcuts {
+        if keys.just_pressed(key) {
+            apply(action, &mut state);
+        }
+    }
+
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let Some(screen_pos) = screen_pos else { return };
+    let Ok((camera, cam_tf)) = camera_q.get_single() else { return };
+    let Ok(cursor) = camera.viewport_to_world_2d(cam_tf, screen_pos) else { return };
+
+    for (button, tf) in &buttons {
+        let d = (cursor - tf.translation.truncate()).abs();
+        if d.x <= BUTTON_SIZE.x / 2.0 && d.y <= BUTTON_SIZE.y / 2.0 {
+            apply(button.0, &mut state);
+            break;
+        }
+    }
+}
+
+/// Steps the robot through the running program.
+pub fn run_program(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut failures: EventWriter<Failure>,
+) {
+    if state.phase != Phase::Running { return; }
+    state.timer -= time.delta_secs();
+    if state.timer > 0.0 { return; }
+    state.timer += STEP_SECS;
+
+    let next = state.steps.get(state.step).copied();
+    let Some(step) = next else {
+        let robot = state.robot;
+        failures.send(Failure(wp(robot.col, robot.row, 0.0).truncate()));
+        state.crashes += 1;
+        state.phase = Phase::Editing;
+        state.robot = start_pose(state.level);
+        state.message = "The robot stopped before the goal".into();
+        return;
+    };
+    state.step += 1;
+
+    let mut robot = state.robot;
+    match step {
+        Command::Left => robot.facing = (robot.facing + 3) % 4,
+        Command::Right => robot.facing = (robot.facing + 1) % 4,
+        _ => {
+            let (dc, dr) = offset(robot.facing);
+            if cell(state.level, robot.col + dc, robot.row + dr) == '#' {
+                failures.send(Failure(wp(robot.col + dc, robot.row + dr, 0.0).truncate()));
+                state.crashes += 1;
+                state.phase = Phase::Editing;
+                state.robot = start_pose(state.level);
+                state.message = "Crashed into a wall!".into();
+                return;
+            }
+            robot.col += dc;
+            robot.row += dr;
+        }
+    }
+    state.robot = robot;
+
+    if cell(state.level, robot.col, robot.row) == 'G' {
+        let over_par = state.program.len() as i32 - LEVELS[state.level].par;
+        let bonus = PAR_BONUS * (PAR_SLACK - over_par).clamp(0, PAR_SLACK);
+        state.score += LEVEL_SCORE + bonus;
+        analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
+        state.message = if over_par <= 0 {
+            format!("Solved at par! +{}", LEVEL_SCORE + bonus)
+        } else {
+            format!("Solved in {} over par. +{}", over_par, LEVEL_SCORE + bonus)
+        };
+        state.phase = Phase::Cleared;
+        state.timer = 1.5;
+    }
+}
+
+/// Moves on to the next level once a cleared one has been shown.
+pub fn advance_level(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut commands: Commands,
+    level_entities: Query<Entity, With<LevelEntity>>,
+    pixar_assets: Res<PixarAssets>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    if state.phase != Phase::Cleared { return; }
+    state.timer -= time.delta_secs();
+    if state.timer > 0.0 { return; }
+
+    state.level += 1;
+    if state.level >= LEVELS.len() {
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+    for e in &level_entities { commands.entity(e).despawn_recursive(); }
+    spawn_level(&mut commands, &pixar_assets, state.level);
+    state.program.clear();
+    state.robot = start_pose(state.level);
+    state.phase = Phase::Editing;
+    state.message.clear();
+}
+
+pub fn update_visuals(
+    state: Res<GameState>,
+    mut robot_q: Query<&mut Transform, (With<Robot>, Without<Heading>)>,
+    mut heading_q: Query<&mut Transform, (With<Heading>, Without<Robot>)>,
+    mut program_q: Query<&mut Text2d, With<ProgramText>>,
+) {
+    let pose = state.robot;
+    for mut tf in &mut robot_q {
+        tf.translation = wp(pose.col, pose.row, 1.0);
+    }
+    let (dc, dr) = offset(pose.facing);
+    for mut tf in &mut heading_q {
+        tf.translation = Vec3::new(dc as f32 * TILE * 0.38, -dr as f32 * TILE * 0.38, 0.5);
+    }
+
+    // The queue, with the current command bracketed during a run
+    let mut text = state.program.iter().map(|&c| label(c)).collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        text = "Build a program with the buttons below".into();
+    }
+    if state.phase == Phase::Running {
+        text = format!("{}   (step {}/{})", text, state.step, state.steps.len());
+    }
+    for mut t in &mut program_q {
+        **t = text.clone();
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score;
+    bridge.stats.level = Some(state.level.min(LEVELS.len() - 1) as i32 + 1);
+    bridge.stats.deaths = Some(state.crashes);
+}
+
+pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+    let level = state.level.min(LEVELS.len() - 1);
+    for mut t in &mut q {
+        **t = format!(
+            "Level {} | Commands: {}/{} (par {}) | Score: {}  {}",
+            level + 1,
+            state.program.len(),
+            MAX_COMMANDS,
+            LEVELS[level].par,
+            state.score,
+            state.message,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::accessibility::{AccessibilitySettings, ColorChannel, ColorCoded, Shade};
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
 
 // ---------------------------------------------------------------------------
@@ -28,39 +33,46 @@ pub struct GameEntity;
 enum GameColor { Red, Green, Blue }
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     vy: f32,
     on_ground: bool,
     active: GameColor,
 }
 
 #[derive(Component)]
-struct Platform {
+pub struct Platform {
     color: GameColor,
 }
 
 #[derive(Component)]
-struct Orb {
+pub struct Orb {
     color: GameColor,
 }
 
 #[derive(Component)]
-struct Goal;
+pub struct Goal;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
+    orbs: i32,
 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    a11y: Res<AccessibilitySettings>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState { score: 0, orbs: 0 });
 
     // Background
     if let Some(ref bg_handle) = custom_assets.background {
@@ -102,13 +114,17 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ];
     for (x, y, w, c) in &plats {
         let plat_size = Vec2::new(*w, PLAT_H);
-        let plat_config = CharacterConfig::prop(gc_color(*c), plat_size, false);
+        let plat_config = CharacterConfig::prop(gc_color(&a11y, *c), plat_size, false);
         pixar::spawn_character(
             &mut commands,
             &pixar_assets,
             &plat_config,
             Vec3::new(*x, *y, 0.0),
-            (Platform { color: *c }, GameEntity),
+            (
+                Platform { color: *c },
+                ColorCoded::pattern(gc_channel(*c), Shade::Base),
+                GameEntity,
+            ),
         );
     }
 
@@ -121,14 +137,18 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         (0.0,    170.0,  GameColor::Blue),
     ];
     for (x, y, c) in &orbs {
-        let orb_color = gc_bright(*c);
+        let orb_color = a11y.color(gc_channel(*c), Shade::Bright);
         let config = CharacterConfig::collectible(orb_color, ORB_SIZE);
         pixar::spawn_character(
             &mut commands,
             &pixar_assets,
             &config,
             Vec3::new(*x, *y, 0.5),
-            (Orb { color: *c }, GameEntity),
+            (
+                Orb { color: *c },
+                ColorCoded::icon(gc_channel(*c), Shade::Bright),
+                GameEntity,
+            ),
         );
     }
 
@@ -144,9 +164,10 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 
     // Player — hero with HERO_PURPLE
     let player_config = CharacterConfig::hero(palette::HERO_PURPLE, PLAYER_SIZE);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &player_config,
         Vec3::new(-400.0, -HALF_H + 20.0 + PLAYER_SIZE.y / 2.0, 1.0),
         (Player { vy: 0.0, on_ground: true, active: GameColor::Red }, GameEntity),
@@ -169,6 +190,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 
 pub fn player_input(
     keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut pq: Query<(&mut Transform, &mut Player)>,
 ) {
@@ -179,14 +201,21 @@ pub fn player_input(
     if keys.just_pressed(KeyCode::Digit1) { player.active = GameColor::Red; }
     if keys.just_pressed(KeyCode::Digit2) { player.active = GameColor::Blue; }
     if keys.just_pressed(KeyCode::Digit3) { player.active = GameColor::Green; }
+    if input.just_pressed(Control::SwitchColor) {
+        player.active = match player.active {
+            GameColor::Red => GameColor::Blue,
+            GameColor::Blue => GameColor::Green,
+            GameColor::Green => GameColor::Red,
+        };
+    }
 
     // Horizontal
-    if keys.pressed(KeyCode::ArrowLeft) { tf.translation.x -= MOVE_SPEED * dt; }
-    if keys.pressed(KeyCode::ArrowRight) { tf.translation.x += MOVE_SPEED * dt; }
+    if input.pressed(Control::MoveLeft) { tf.translation.x -= MOVE_SPEED * dt; }
+    if input.pressed(Control::MoveRight) { tf.translation.x += MOVE_SPEED * dt; }
     tf.translation.x = tf.translation.x.clamp(-HALF_W + PLAYER_SIZE.x / 2.0, HALF_W - PLAYER_SIZE.x / 2.0);
 
     // Jump
-    if keys.just_pressed(KeyCode::ArrowUp) && player.on_ground {
+    if input.just_pressed(Control::Jump) && player.on_ground {
         player.vy = JUMP_VEL;
         player.on_ground = false;
     }
@@ -197,6 +226,7 @@ pub fn physics(
     mut pq: Query<(&mut Transform, &mut Player)>,
     plats: Query<(&Transform, &Platform, &Sprite), Without<Player>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
     let dt = time.delta_secs();
     let Ok((mut tf, mut player)) = pq.get_single_mut() else { return };
@@ -227,6 +257,7 @@ pub fn physics(
 
     // Fall off screen
     if tf.translation.y < -HALF_H - 50.0 {
+        failures.send(Failure(Vec2::new(tf.translation.x, -HALF_H)));
         next_state.set(crate::AppState::GameOver);
     }
 }
@@ -243,6 +274,7 @@ pub fn collect_orbs(
         let dy = (ptf.translation.y - otf.translation.y).abs();
         if dx < 24.0 && dy < 24.0 && orb.color == player.active {
             state.score += 100;
+            state.orbs += 1;
             commands.entity(e).despawn_recursive();
         }
     }
@@ -253,6 +285,7 @@ pub fn check_goal(
     gq: Query<&Transform, With<Goal>>,
     mut state: ResMut<GameState>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut analytics: EventWriter<AnalyticsEvent>,
 ) {
     let Ok(ptf) = pq.get_single() else { return };
     for gtf in &gq {
@@ -260,27 +293,30 @@ pub fn check_goal(
         let dy = (ptf.translation.y - gtf.translation.y).abs();
         if dx < 30.0 && dy < 30.0 {
             state.score += 500;
+            analytics.send(AnalyticsEvent::level_complete(0, state.score));
             next_state.set(crate::AppState::GameOver);
         }
     }
 }
 
 pub fn update_platform_vis(
+    a11y: Res<AccessibilitySettings>,
     pq: Query<&Player>,
     mut plats: Query<(&Platform, &mut Sprite), Without<Player>>,
 ) {
     let Ok(player) = pq.get_single() else { return };
     for (plat, mut spr) in &mut plats {
         if plat.color == player.active {
-            spr.color = gc_color(plat.color);
+            spr.color = gc_color(&a11y, plat.color);
         } else {
-            spr.color = gc_color(plat.color).with_alpha(0.2);
+            spr.color = gc_color(&a11y, plat.color).with_alpha(0.2);
         }
     }
 }
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.collectibles = Some(state.orbs);
 }
 
 pub fn update_hud(state: Res<GameState>, pq: Query<&Player>, mut sq: Query<&mut Text, With<ScoreText>>) {
@@ -302,18 +338,14 @@ pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn gc_color(c: GameColor) -> Color {
+fn gc_channel(c: GameColor) -> ColorChannel {
     match c {
-        GameColor::Red => Color::srgb(0.85, 0.2, 0.2),
-        GameColor::Green => Color::srgb(0.2, 0.8, 0.2),
-        GameColor::Blue => Color::srgb(0.2, 0.3, 0.9),
+        GameColor::Red => ColorChannel::Red,
+        GameColor::Green => ColorChannel::Green,
+        GameColor::Blue => ColorChannel::Blue,
     }
 }
 
-fn gc_bright(c: GameColor) -> Color {
-    match c {
-        GameColor::Red => Color::srgb(1.0, 0.4, 0.4),
-        GameColor::Green => Color::srgb(0.4, 1.0, 0.4),
-        GameColor::Blue => Color::srgb(0.4, 0.5, 1.0),
-    }
+fn gc_color(a11y: &AccessibilitySettings, c: GameColor) -> Color {
+    a11y.color(gc_channel(c), Shade::Base)
 }
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::BevyBridge;
+use crate::input::{ActionState, Control};
+use crate::camera::Shake;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
 
@@ -26,25 +28,25 @@ const SETTLE_TICK: f32 = 0.08;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Block {
+pub struct Block {
     gx: i32,
     gy: i32,
     marked: bool,
 }
 
 #[derive(Component)]
-struct ExplosionVfx {
+pub struct ExplosionVfx {
     timer: f32,
 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
 struct CursorVis;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     explosives_placed: i32,
     detonated: bool,
@@ -158,14 +160,15 @@ pub fn place_explosive(
 }
 
 pub fn detonate(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mut state: ResMut<GameState>,
     mut commands: Commands,
     pixar_assets: Res<PixarAssets>,
     blocks: Query<(Entity, &Block, &Transform)>,
+    mut shakes: EventWriter<Shake>,
 ) {
     if state.detonated || state.done { return; }
-    if !keys.just_pressed(KeyCode::Space) { return; }
+    if !input.just_pressed(Control::Action) { return; }
 
     state.detonated = true;
 
@@ -206,6 +209,11 @@ pub fn detonate(
         );
     }
 
+    // Bigger blasts shake harder
+    if !marked.is_empty() {
+        shakes.send(Shake(0.4 + 0.12 * marked.len() as f32));
+    }
+
     state.settling = true;
     state.settle_timer = 0.5; // delay before gravity
 }
@@ -1,65 +1,111 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
+use crate::game_mode::GameMode;
+use crate::remote_config::RemoteConfig;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::input::{ActionState, GameAction, Rumble};
+use crate::perf::Quality;
+use crate::pool::{Parked, Pool};
+use crate::powerups::{self, ActiveEffects, Powerup};
+use super::boss::{self, Boss, BossShot, BossSpec, BossTarget, Spawn, Waves};
 
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
 
+// Defaults for the remotely tunable values, read through `RemoteConfig`
+// under the camelCase key noted next to each.
+
 const GROUND_Y: f32 = -270.0;
 const PLAYER_SIZE: Vec2 = Vec2::new(28.0, 40.0);
 const BULLET_SIZE: Vec2 = Vec2::new(8.0, 4.0);
 const ENEMY_SIZE: Vec2 = Vec2::new(22.0, 22.0);
 const MOVE_SPEED: f32 = 280.0;
-const GRAVITY: f32 = -800.0;
+const GRAVITY: f32 = -800.0; // gravity
 const JET_THRUST: f32 = 1200.0;
 const MAX_FUEL: f32 = 100.0;
 const FUEL_DRAIN: f32 = 50.0;
 const FUEL_REGEN: f32 = 40.0;
 const BULLET_SPEED: f32 = 500.0;
-const ENEMY_SPEED: f32 = 100.0;
-const SPAWN_INTERVAL: f32 = 1.8;
+const ENEMY_SPEED: f32 = 100.0; // enemySpeed
+const SPAWN_INTERVAL: f32 = 1.8; // spawnInterval, seconds
 const HALF_W: f32 = 480.0;
 const HALF_H: f32 = 320.0;
 const MAX_HP: i32 = 5;
+const KILL_SCORE: i32 = 50; // killScore
+const BOSS_EVERY: i32 = 3; // bossEveryWaves
+const POWERUP_DROP_CHANCE: f32 = 0.12; // powerupDropChance, per kill
+const POWERUP_LIFETIME: f32 = 6.0;
+
+static BOSS: BossSpec = BossSpec {
+    name: "Mothership",
+    size: Vec2::new(140.0, 70.0),
+    color: palette::VILLAIN_DARK,
+    hp: 40,
+    weak_points: &[Vec2::new(-40.0, -20.0), Vec2::new(40.0, -20.0)],
+    anchor: Vec2::new(0.0, 200.0),
+    sweep: Vec2::new(320.0, 40.0),
+    fire_interval: 1.6,
+    shot_speed: 220.0,
+    phase_bonus: 500,
+    defeat_bonus: 2500,
+};
 
 // ---------------------------------------------------------------------------
 // Components
 // ---------------------------------------------------------------------------
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { vy: f32, fuel: f32, on_ground: bool }
+pub struct Player { vy: f32, fuel: f32, on_ground: bool }
 
 #[derive(Component)]
-struct Enemy { time: f32, base_y: f32 }
+pub struct Enemy { time: f32, base_y: f32 }
 
 #[derive(Component)]
-struct Bullet { dx: f32, dy: f32 }
+pub struct Bullet { dx: f32, dy: f32 }
 
 #[derive(Component)]
-struct FuelBar;
+pub struct FuelBar;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct HpText;
+pub struct HpText;
 
 #[derive(Resource)]
-struct GameState { score: i32, hp: i32, spawn_timer: f32 }
+pub struct GameState { score: i32, hp: i32, spawn_timer: f32, kill_score: i32, waves: Waves, boss_every: u32, shots: i32, hits: i32 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, hp: MAX_HP, spawn_timer: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    config: Res<RemoteConfig>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState {
+        score: 0,
+        hp: MAX_HP,
+        spawn_timer: 0.0,
+        kill_score: config.i32("killScore", KILL_SCORE),
+        waves: Waves::default(),
+        boss_every: config.i32("bossEveryWaves", BOSS_EVERY).max(1) as u32,
+        shots: 0,
+        hits: 0,
+    });
+    commands.insert_resource(Pool::<Bullet>::default());
 
     // Background
     let bg_sprite = if let Some(ref bg) = custom_assets.background {
@@ -76,12 +122,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 
     // Player
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::vehicle(palette::HERO_TEAL, PLAYER_SIZE),
         Vec3::new(0.0, GROUND_Y + PLAYER_SIZE.y / 2.0, 1.0),
-        (Player { vy: 0.0, fuel: MAX_FUEL, on_ground: true }, GameEntity),
+        (Player { vy: 0.0, fuel: MAX_FUEL, on_ground: true }, ActiveEffects::default(), BossTarget, GameEntity),
     );
 
     // Fuel bar background
@@ -110,30 +157,34 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Node { position_type: PositionType::Absolute, top: Val::Px(10.0), right: Val::Px(10.0), ..default() },
         HpText, GameEntity,
     ));
+    boss::spawn_health_bar(&mut commands, GameEntity);
+    powerups::spawn_hud(&mut commands, GameEntity);
 }
 
 // ---------------------------------------------------------------------------
 // Systems
 // ---------------------------------------------------------------------------
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    touches: Res<Touches>,
+    mut actions: EventReader<GameAction>,
+    input: Res<ActionState>,
     time: Res<Time>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    config: Res<RemoteConfig>,
+    mut pq: Query<(&mut Transform, &mut Player, &ActiveEffects)>,
+    mut state: ResMut<GameState>,
+    mut bullets: ResMut<Pool<Bullet>>,
     mut commands: Commands,
 ) {
     let dt = time.delta_secs();
-    let Ok((mut tf, mut p)) = pq.get_single_mut() else { return };
+    let Ok((mut tf, mut p, fx)) = pq.get_single_mut() else { return };
 
     // Horizontal
-    if keys.pressed(KeyCode::ArrowLeft) { tf.translation.x -= MOVE_SPEED * dt; }
-    if keys.pressed(KeyCode::ArrowRight) { tf.translation.x += MOVE_SPEED * dt; }
+    tf.translation.x += input.movement.x * MOVE_SPEED * fx.speed_multiplier() * dt;
     tf.translation.x = tf.translation.x.clamp(-HALF_W + 15.0, HALF_W - 15.0);
 
     // Jetpack
-    let jetting = keys.pressed(KeyCode::Space);
+    let jetting = input.jump_held;
     if jetting && p.fuel > 0.0 {
         p.vy += JET_THRUST * dt;
         p.fuel -= FUEL_DRAIN * dt;
@@ -142,7 +193,7 @@ pub fn player_input(
     }
 
     // Gravity
-    p.vy += GRAVITY * dt;
+    p.vy += config.f32("gravity", GRAVITY) * dt;
     tf.translation.y += p.vy * dt;
 
     let floor = GROUND_Y + PLAYER_SIZE.y / 2.0;
@@ -158,11 +209,11 @@ pub fn player_input(
     }
 
     // Shoot
-    let shoot = mouse.just_pressed(MouseButton::Left) || touches.any_just_pressed()
-        || keys.just_pressed(KeyCode::KeyF);
+    let shoot = actions.read().any(|a| matches!(a, GameAction::Fire | GameAction::Pointer));
     if shoot {
+        state.shots += 1;
         // Fire rightward by default (keyboard), or toward cursor could be added
-        commands.spawn((
+        bullets.acquire(&mut commands, (
             Sprite { color: palette::ELECTRIC_CYAN, custom_size: Some(BULLET_SIZE), ..default() },
             Transform::from_xyz(tf.translation.x + 16.0, tf.translation.y, 0.5),
             Bullet { dx: BULLET_SPEED, dy: 0.0 }, GameEntity,
@@ -173,22 +224,40 @@ pub fn player_input(
 pub fn move_bullets(
     time: Res<Time>,
     mut commands: Commands,
-    mut q: Query<(Entity, &mut Transform, &Bullet)>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    mut q: Query<(Entity, &mut Transform, &Bullet), Without<Parked>>,
 ) {
     let dt = time.delta_secs();
     for (e, mut tf, b) in &mut q {
         tf.translation.x += b.dx * dt;
         tf.translation.y += b.dy * dt;
         if tf.translation.x.abs() > HALF_W + 30.0 || tf.translation.y.abs() > HALF_H + 30.0 {
-            commands.entity(e).despawn();
+            bullets.release(&mut commands, e);
         }
     }
 }
 
-pub fn spawn_enemies(time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
+pub fn spawn_enemies(
+    time: Res<Time>,
+    config: Res<RemoteConfig>,
+    quality: Res<Quality>,
+    mut state: ResMut<GameState>,
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+) {
     state.spawn_timer += time.delta_secs();
-    if state.spawn_timer < SPAWN_INTERVAL { return; }
+    let interval = config.f32("spawnInterval", SPAWN_INTERVAL) * quality.spawn_interval_scale();
+    if state.spawn_timer < interval { return; }
     state.spawn_timer = 0.0;
+    let boss_every = state.boss_every;
+    match state.waves.next(boss_every) {
+        Spawn::Enemy => {}
+        Spawn::Boss => {
+            boss::spawn_boss(&mut commands, &pixar_assets, &BOSS, state.waves.bosses_defeated, GameEntity);
+            return;
+        }
+        Spawn::Hold => return,
+    }
     let mut rng = rand::thread_rng();
     let side = if rng.gen_bool(0.5) { HALF_W + 20.0 } else { -HALF_W - 20.0 };
     let y = rng.gen_range(GROUND_Y + 40.0..HALF_H - 40.0);
@@ -203,30 +272,41 @@ pub fn spawn_enemies(time: Res<Time>, mut state: ResMut<GameState>, mut commands
 
 pub fn move_enemies(
     time: Res<Time>,
-    pq: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    config: Res<RemoteConfig>,
+    pq: Query<(&Transform, &ActiveEffects), (With<Player>, Without<Enemy>)>,
     mut eq: Query<(&mut Transform, &mut Enemy)>,
 ) {
-    let Ok(ptf) = pq.get_single() else { return };
-    let dt = time.delta_secs();
+    let Ok((ptf, fx)) = pq.get_single() else { return };
+    let dt = time.delta_secs() * fx.world_scale();
+    let speed = config.f32("enemySpeed", ENEMY_SPEED);
     for (mut tf, mut e) in &mut eq {
         e.time += dt;
         let dx = ptf.translation.x - tf.translation.x;
         let dir = if dx > 0.0 { 1.0 } else { -1.0 };
-        tf.translation.x += dir * ENEMY_SPEED * dt;
+        tf.translation.x += dir * speed * dt;
         // Sine-wave bobbing
         tf.translation.y = e.base_y + (e.time * 3.0).sin() * 30.0;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn check_collisions(
     mut commands: Commands,
     mut state: ResMut<GameState>,
+    mode: Res<GameMode>,
+    config: Res<RemoteConfig>,
+    pixar_assets: Res<PixarAssets>,
     mut next_state: ResMut<NextState<crate::AppState>>,
-    pq: Query<&Transform, With<Player>>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    mut pq: Query<(&Transform, &mut ActiveEffects), With<Player>>,
     eq: Query<(Entity, &Transform), With<Enemy>>,
-    bq: Query<(Entity, &Transform), With<Bullet>>,
+    bq: Query<(Entity, &Transform), (With<Bullet>, Without<Parked>)>,
+    mut rumble: EventWriter<Rumble>,
+    mut failures: EventWriter<Failure>,
 ) {
-    let Ok(ptf) = pq.get_single() else { return };
+    let Ok((ptf, mut fx)) = pq.get_single_mut() else { return };
+    let drop_chance = config.f32("powerupDropChance", POWERUP_DROP_CHANCE).clamp(0.0, 1.0);
+    let mut rng = rand::thread_rng();
 
     // Bullet-enemy
     for (be, btf) in &bq {
@@ -234,9 +314,21 @@ pub fn check_collisions(
             let dx = (btf.translation.x - etf.translation.x).abs();
             let dy = (btf.translation.y - etf.translation.y).abs();
             if dx < 15.0 && dy < 15.0 {
-                commands.entity(be).despawn();
+                bullets.release(&mut commands, be);
                 commands.entity(ee).despawn();
-                state.score += 50;
+                state.score += state.kill_score * fx.score_multiplier();
+                state.hits += 1;
+                rumble.send(Rumble::light());
+                if rng.gen_bool(f64::from(drop_chance)) {
+                    powerups::spawn_pickup(
+                        &mut commands,
+                        &pixar_assets,
+                        Powerup::random(&Powerup::ALL),
+                        etf.translation.with_z(0.6),
+                        POWERUP_LIFETIME,
+                        GameEntity,
+                    );
+                }
                 break;
             }
         }
@@ -248,12 +340,69 @@ pub fn check_collisions(
         let dy = (ptf.translation.y - etf.translation.y).abs();
         if dx < (PLAYER_SIZE.x + ENEMY_SIZE.x) / 2.0 && dy < (PLAYER_SIZE.y + ENEMY_SIZE.y) / 2.0 {
             commands.entity(ee).despawn();
+            rumble.send(Rumble::impact());
+            // Zen: hits cost nothing.
+            if !mode.can_lose() || fx.absorb_hit() { continue; }
             state.hp -= 1;
+            failures.send(Failure(ptf.translation.truncate()));
             if state.hp <= 0 { next_state.set(crate::AppState::GameOver); return; }
         }
     }
 }
 
+/// Bullets against the boss, and its shots against the player.
+#[allow(clippy::too_many_arguments)]
+pub fn boss_collisions(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mode: Res<GameMode>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    mut pq: Query<(&Transform, &mut ActiveEffects), With<Player>>,
+    mut boss_q: Query<(Entity, &Transform, &mut Boss), With<GameEntity>>,
+    bq: Query<(Entity, &Transform), (With<Bullet>, Without<Parked>)>,
+    sq: Query<(Entity, &Transform), (With<BossShot>, With<GameEntity>)>,
+    mut rumble: EventWriter<Rumble>,
+    mut failures: EventWriter<Failure>,
+) {
+    let Ok((ptf, mut fx)) = pq.get_single_mut() else { return };
+    let Ok((boss_e, boss_tf, mut boss)) = boss_q.get_single_mut() else { return };
+    let boss_pos = boss_tf.translation.truncate();
+
+    for (be, btf) in &bq {
+        let Some(damage) = boss.damage_at(boss_pos, btf.translation.truncate()) else { continue };
+        bullets.release(&mut commands, be);
+        let hit = boss.take_damage(damage);
+        state.score += hit.points * fx.score_multiplier();
+        state.hits += 1;
+        if hit.defeated {
+            commands.entity(boss_e).despawn_recursive();
+            state.waves.boss_defeated();
+            rumble.send(Rumble::impact());
+            return;
+        }
+        rumble.send(if hit.phase_broken { Rumble::impact() } else { Rumble::light() });
+    }
+
+    let player_pos = ptf.translation.truncate();
+    let mut hits = 0;
+    for (se, stf) in &sq {
+        let d = (stf.translation.truncate() - player_pos).abs();
+        if d.x < (PLAYER_SIZE.x + boss::SHOT_SIZE.x) / 2.0 && d.y < (PLAYER_SIZE.y + boss::SHOT_SIZE.y) / 2.0 {
+            commands.entity(se).despawn();
+            hits += 1;
+        }
+    }
+    if hits == 0 { return; }
+    rumble.send(Rumble::impact());
+    if fx.absorb_hit() { hits -= 1; }
+    // Zen: hits cost nothing.
+    if !mode.can_lose() { return; }
+    state.hp -= hits;
+    if hits > 0 { failures.send(Failure(player_pos)); }
+    if state.hp <= 0 { next_state.set(crate::AppState::GameOver); }
+}
+
 pub fn update_fuel_bar(
     pq: Query<&Player>,
     mut fq: Query<(&mut Sprite, &mut Transform), With<FuelBar>>,
@@ -266,8 +415,18 @@ pub fn update_fuel_bar(
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(
+    state: Res<GameState>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut bridge: ResMut<BevyBridge>,
+) {
     bridge.current_score = state.score;
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    bridge.stats.level = Some(state.waves.wave as i32 + 1);
+    bridge.stats.deaths = Some(MAX_HP - state.hp.max(0));
+    if let Ok(fx) = effects.get_single() {
+        bridge.stats.collectibles = Some(fx.collected());
+    }
 }
 
 pub fn update_hud(
@@ -284,6 +443,7 @@ pub fn update_hud(
 // ---------------------------------------------------------------------------
 
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
-    for e in &q { commands.entity(e).despawn(); }
+    for e in &q { commands.entity(e).despawn_recursive(); }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Bullet>>();
 }
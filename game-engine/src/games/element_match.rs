@@ -0,0 +1,415 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::{BevyBridge, GameStats};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::game_mode::GameMode;
+use crate::pixar::palette;
+use crate::asset_loader::CustomAssets;
+
+use super::grid::Grid;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const CELL: Vec2 = Vec2::new(104.0, 124.0);
+const CARD_SIZE: Vec2 = Vec2::new(94.0, 114.0);
+const BOARD_Y: f32 = -20.0;
+/// How long a mismatched pair stays face up.
+const MISMATCH_SECS: f32 = 0.9;
+const MATCH_POINTS: f32 = 100.0;
+/// Extra share of `MATCH_POINTS` for each match in an unbroken run.
+const COMBO_STEP: f32 = 0.5;
+const CLEAR_BONUS: i32 = 200;
+/// Seconds per pair a board may take before the time bonus runs out.
+const PAR_SECS_PER_PAIR: f32 = 4.0;
+const TIME_BONUS_PER_SEC: f32 = 20.0;
+
+/// `numbers` is the share of pairs whose partner card shows the atomic
+/// number instead of the name.
+struct Board {
+    cols: i32,
+    rows: i32,
+    numbers: f32,
+}
+
+/// Classic plays these in order; Time Attack and Endless keep dealing the
+/// last one.
+const BOARDS: [Board; 3] = [
+    Board { cols: 4, rows: 3, numbers: 0.0 },
+    Board { cols: 4, rows: 4, numbers: 0.3 },
+    Board { cols: 6, rows: 4, numbers: 0.5 },
+];
+
+/// Symbol, name and atomic number.
+const ELEMENTS: [(&str, &str, u32); 30] = [
+    ("H", "Hydrogen", 1),
+    ("He", "Helium", 2),
+    ("Li", "Lithium", 3),
+    ("C", "Carbon", 6),
+    ("N", "Nitrogen", 7),
+    ("O", "Oxygen", 8),
+    ("F", "Fluorine", 9),
+    ("Ne", "Neon", 10),
+    ("Na", "Sodium", 11),
+    ("Mg", "Magnesium", 12),
+    ("Al", "Aluminium", 13),
+    ("Si", "Silicon", 14),
+    ("P", "Phosphorus", 15),
+    ("S", "Sulfur", 16),
+    ("Cl", "Chlorine", 17),
+    ("Ar", "Argon", 18),
+    ("K", "Potassium", 19),
+    ("Ca", "Calcium", 20),
+    ("Fe", "Iron", 26),
+    ("Co", "Cobalt", 27),
+    ("Ni", "Nickel", 28),
+    ("Cu", "Copper", 29),
+    ("Zn", "Zinc", 30),
+    ("Ag", "Silver", 47),
+    ("Sn", "Tin", 50),
+    ("I", "Iodine", 53),
+    ("Au", "Gold", 79),
+    ("Hg", "Mercury", 80),
+    ("Pb", "Lead", 82),
+    ("U", "Uranium", 92),
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+/// Despawned when the next board is dealt.
+#[derive(Component)]
+pub struct BoardEntity;
+
+#[derive(Component)]
+pub struct CardVisual(usize);
+
+#[derive(Component)]
+pub struct CardText(usize);
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Face { Symbol, Name, Number }
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side { Down, Up, Matched }
+
+#[derive(Clone, Copy)]
+struct Card {
+    gx: i32,
+    gy: i32,
+    /// Index into `ELEMENTS`; the two cards of a pair share it.
+    element: usize,
+    face: Face,
+    side: Side,
+}
+
+#[derive(Resource)]
+pub struct GameState {
+    score: i32,
+    board: usize,
+    /// Boards cleared, including repeats of the last one.
+    cleared: i32,
+    cards: Vec<Card>,
+    /// The card turned up first, waiting for its partner.
+    first: Option<usize>,
+    /// A mismatched pair, face up until `timer` runs out.
+    mismatch: Option<(usize, usize)>,
+    timer: f32,
+    board_secs: f32,
+    combo: i32,
+    matches: i32,
+    attempts: i32,
+    message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn grid(board: &Board) -> Grid {
+    Grid { cols: board.cols, rows: board.rows, cell: CELL, center: Vec2::new(0.0, BOARD_Y) }
+}
+
+fn wp(board: &Board, gx: i32, gy: i32, z: f32) -> Vec3 {
+    grid(board).wp(gx, gy, z)
+}
+
+fn card_text(card: &Card) -> String {
+    let (symbol, name, number) = ELEMENTS[card.element];
+    match card.face {
+        Face::Symbol => symbol.to_string(),
+        Face::Name => name.to_string(),
+        Face::Number => format!("Atomic\nnumber {}", number),
+    }
+}
+
+/// Shuffles a fresh set of pairs onto the board.
+fn deal(board: &Board) -> Vec<Card> {
+    let mut rng = rand::thread_rng();
+    let pairs = (board.cols * board.rows / 2) as usize;
+    let mut elements: Vec<usize> = (0..ELEMENTS.len()).collect();
+    elements.shuffle(&mut rng);
+
+    let mut faces = Vec::new();
+    for &element in &elements[..pairs] {
+        let partner = if rng.gen::<f32>() < board.numbers { Face::Number } else { Face::Name };
+        faces.push((element, Face::Symbol));
+        faces.push((element, partner));
+    }
+    faces.shuffle(&mut rng);
+
+    faces
+        .into_iter()
+        .enumerate()
+        .map(|(i, (element, face))| Card {
+            gx: i as i32 % board.cols,
+            gy: i as i32 / board.cols,
+            element,
+            face,
+            side: Side::Down,
+        })
+        .collect()
+}
+
+fn spawn_board(commands: &mut Commands, state: &mut GameState, board: usize) {
+    let config = &BOARDS[board];
+    state.board = board;
+    state.cards = deal(config);
+    state.first = None;
+    state.mismatch = None;
+    state.board_secs = 0.0;
+
+    for (i, card) in state.cards.iter().enumerate() {
+        commands.spawn((
+            Sprite { color: palette::HERO_PURPLE, custom_size: Some(CARD_SIZE), ..default() },
+            Transform::from_translation(wp(config, card.gx, card.gy, 1.0)),
+            CardVisual(i),
+            BoardEntity,
+            GameEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text2d::new(""),
+                TextFont { font_size: 20.0, ..default() },
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                CardText(i),
+            ));
+        });
+    }
+}
+
+/// Turns a card up, scoring it against the card already up.
+fn flip(state: &mut GameState, i: usize, failures: &mut EventWriter<Failure>) {
+    // A third card hides a mismatched pair straight away.
+    if let Some((a, b)) = state.mismatch.take() {
+        state.cards[a].side = Side::Down;
+        state.cards[b].side = Side::Down;
+    }
+    if state.cards[i].side != Side::Down { return; }
+    state.cards[i].side = Side::Up;
+
+    let Some(first) = state.first.take() else {
+        state.first = Some(i);
+        return;
+    };
+
+    state.attempts += 1;
+    if state.cards[first].element == state.cards[i].element {
+        state.cards[first].side = Side::Matched;
+        state.cards[i].side = Side::Matched;
+        let points = MATCH_POINTS * (1.0 + state.combo as f32 * COMBO_STEP);
+        state.score += points as i32;
+        state.combo += 1;
+        state.matches += 1;
+        let (symbol, name, number) = ELEMENTS[state.cards[i].element];
+        state.message = format!("{} is {}, atomic number {}", symbol, name, number);
+    } else {
+        let board = &BOARDS[state.board];
+        let card = state.cards[i];
+        failures.send(Failure(wp(board, card.gx, card.gy, 0.0).truncate()));
+        state.combo = 0;
+        state.mismatch = Some((first, i));
+        state.timer = MISMATCH_SECS;
+        state.message.clear();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, custom_assets: Res<CustomAssets>) {
+    let mut state = GameState {
+        score: 0,
+        board: 0,
+        cleared: 0,
+        cards: Vec::new(),
+        first: None,
+        mismatch: None,
+        timer: 0.0,
+        board_secs: 0.0,
+        combo: 0,
+        matches: 0,
+        attempts: 0,
+        message: String::new(),
+    };
+
+    // Background
+    if let Some(ref bg_handle) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::LAB_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    }
+
+    spawn_board(&mut commands, &mut state, 0);
+    commands.insert_resource(state);
+
+    commands.spawn((
+        Text::new("Match each symbol with its element"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+        ScoreText,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Clicks and taps turn cards up.
+pub fn handle_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut state: ResMut<GameState>,
+    mut failures: EventWriter<Failure>,
+) {
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let Some(screen_pos) = screen_pos else { return };
+    let Ok((camera, cam_tf)) = camera_q.get_single() else { return };
+    let Ok(cursor) = camera.viewport_to_world_2d(cam_tf, screen_pos) else { return };
+
+    // Find which grid cell was clicked
+    let (gx, gy) = grid(&BOARDS[state.board]).cell_at(cursor);
+
+    let Some(i) = state.cards.iter().position(|c| c.gx == gx && c.gy == gy) else { return };
+    flip(&mut state, i, &mut failures);
+}
+
+/// Hides mismatched pairs, and deals the next board once one is cleared.
+pub fn advance(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    board_entities: Query<Entity, With<BoardEntity>>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    let dt = time.delta_secs();
+    state.board_secs += dt;
+
+    if let Some((a, b)) = state.mismatch {
+        state.timer -= dt;
+        if state.timer <= 0.0 {
+            state.cards[a].side = Side::Down;
+            state.cards[b].side = Side::Down;
+            state.mismatch = None;
+        }
+    }
+
+    if state.cards.iter().any(|c| c.side != Side::Matched) { return; }
+
+    let pairs = state.cards.len() as f32 / 2.0;
+    let spare_secs = (pairs * PAR_SECS_PER_PAIR - state.board_secs).max(0.0);
+    let bonus = CLEAR_BONUS + (spare_secs * TIME_BONUS_PER_SEC) as i32;
+    state.score += bonus;
+    state.cleared += 1;
+    state.message = format!("Board cleared! +{}", bonus);
+    analytics.send(AnalyticsEvent::level_complete(state.board, state.score));
+
+    let next = state.board + 1;
+    let keep_dealing = matches!(*mode, GameMode::TimeAttack | GameMode::Endless);
+    if next >= BOARDS.len() && !keep_dealing {
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+    for e in &board_entities { commands.entity(e).despawn_recursive(); }
+    spawn_board(&mut commands, &mut state, next.min(BOARDS.len() - 1));
+}
+
+pub fn update_visuals(
+    state: Res<GameState>,
+    mut card_q: Query<(&CardVisual, &mut Sprite)>,
+    mut text_q: Query<(&CardText, &mut Text2d)>,
+) {
+    for (visual, mut sprite) in &mut card_q {
+        let Some(card) = state.cards.get(visual.0) else { continue };
+        sprite.color = match (card.side, card.face) {
+            (Side::Down, _) => palette::HERO_PURPLE,
+            (Side::Matched, _) => palette::HERO_GREEN,
+            (Side::Up, Face::Symbol) => palette::HERO_BLUE,
+            (Side::Up, _) => palette::HERO_TEAL,
+        };
+    }
+    for (label, mut text) in &mut text_q {
+        let Some(card) = state.cards.get(label.0) else { continue };
+        **text = if card.side == Side::Down { "?".into() } else { card_text(card) };
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score;
+    bridge.stats.level = Some(state.cleared + 1);
+    bridge.stats.accuracy = GameStats::percent(state.matches, state.attempts);
+}
+
+pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+    let left = state.cards.iter().filter(|c| c.side != Side::Matched).count() / 2;
+    for mut t in &mut q {
+        **t = format!(
+            "Board {} | Pairs left: {} | Combo: {} | Score: {}  {}",
+            state.cleared + 1,
+            left,
+            state.combo,
+            state.score,
+            state.message,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
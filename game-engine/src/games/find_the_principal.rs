@@ -1,8 +1,12 @@
 use bevy::prelude::*;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::tween::MoveTo;
 
 const COLS: i32 = 12;
 const ROWS: i32 = 8;
@@ -12,12 +16,13 @@ const ORIGIN_Y: f32 = -175.0;
 const GRAVITY_TICK: f32 = 0.10;
 const MOVE_CD: f32 = 0.13;
 const ENEMY_TICK: f32 = 0.4;
+const START_LIVES: i32 = 3;
 
 #[derive(Component)]
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     gx: i32,
     gy: i32,
     lives: i32,
@@ -25,7 +30,7 @@ struct Player {
 }
 
 #[derive(Component)]
-struct Enemy {
+pub struct Enemy {
     gx: i32,
     gy: i32,
     dir: i32, // -1 or 1
@@ -35,20 +40,25 @@ struct Enemy {
 enum TileKind { Floor, Ladder, Goal }
 
 #[derive(Component)]
-struct Tile { gx: i32, gy: i32, kind: TileKind }
+pub struct Tile { gx: i32, gy: i32, kind: TileKind }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     move_cd: f32,
     gravity_timer: f32,
     enemy_timer: f32,
 }
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
     commands.insert_resource(GameState {
         score: 0, move_cd: 0.0, gravity_timer: 0.0, enemy_timer: 0.0,
     });
@@ -92,7 +102,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
             &pixar_assets,
             &config,
             Vec3::new(px, py, 0.8),
-            (Enemy { gx, gy, dir }, GameEntity),
+            (Enemy { gx, gy, dir }, MoveTo::at(Vec3::new(px, py, 0.8)), GameEntity),
         );
     }
 
@@ -100,12 +110,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     let (px, py) = grid_to_world(1, 1);
     let player_size = Vec2::new(TILE - 12.0, TILE - 6.0);
     let player_config = CharacterConfig::hero(palette::HERO_BLUE, player_size);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &player_config,
         Vec3::new(px, py, 1.0),
-        (Player { gx: 1, gy: 1, lives: 3, on_ladder: false }, GameEntity),
+        (Player { gx: 1, gy: 1, lives: START_LIVES, on_ladder: false }, MoveTo::at(Vec3::new(px, py, 1.0)), GameEntity),
     );
 
     // HUD
@@ -120,22 +131,22 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 }
 
 pub fn player_move(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    mut pq: Query<(&mut MoveTo, &mut Player)>,
     tiles: Query<&Tile>,
     mut next_state: ResMut<NextState<crate::AppState>>,
 ) {
     state.move_cd -= time.delta_secs();
     if state.move_cd > 0.0 { return; }
-    let Ok((mut ptf, mut player)) = pq.get_single_mut() else { return };
+    let Ok((mut mv, mut player)) = pq.get_single_mut() else { return };
 
     let (mut dx, mut dy) = (0i32, 0i32);
-    if keys.pressed(KeyCode::ArrowLeft) { dx = -1; }
-    else if keys.pressed(KeyCode::ArrowRight) { dx = 1; }
-    else if keys.pressed(KeyCode::ArrowUp) { dy = 1; }
-    else if keys.pressed(KeyCode::ArrowDown) { dy = -1; }
+    if input.pressed(Control::MoveLeft) { dx = -1; }
+    else if input.pressed(Control::MoveRight) { dx = 1; }
+    else if input.pressed(Control::MoveUp) { dy = 1; }
+    else if input.pressed(Control::MoveDown) { dy = -1; }
 
     if dx == 0 && dy == 0 { return; }
 
@@ -166,8 +177,7 @@ pub fn player_move(
     player.gx = nx;
     player.gy = ny;
     let (wx, wy) = grid_to_world(nx, ny);
-    ptf.translation.x = wx;
-    ptf.translation.y = wy;
+    mv.go(Vec3::new(wx, wy, 1.0));
     state.move_cd = MOVE_CD;
 
     // Check goal
@@ -182,14 +192,14 @@ pub fn player_move(
 pub fn gravity(
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    mut pq: Query<(&mut MoveTo, &mut Player)>,
     tiles: Query<&Tile>,
 ) {
     state.gravity_timer += time.delta_secs();
     if state.gravity_timer < GRAVITY_TICK { return; }
     state.gravity_timer = 0.0;
 
-    let Ok((mut ptf, mut player)) = pq.get_single_mut() else { return };
+    let Ok((mut mv, mut player)) = pq.get_single_mut() else { return };
     if player.gy <= 0 { return; }
 
     // Don't fall on ladder
@@ -201,22 +211,21 @@ pub fn gravity(
     if !supported {
         player.gy = below;
         let (wx, wy) = grid_to_world(player.gx, player.gy);
-        ptf.translation.x = wx;
-        ptf.translation.y = wy;
+        mv.fall(Vec3::new(wx, wy, 1.0));
     }
 }
 
 pub fn enemy_patrol(
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut enemies: Query<(&mut Transform, &mut Enemy)>,
+    mut enemies: Query<(&mut MoveTo, &mut Enemy)>,
     tiles: Query<&Tile>,
 ) {
     state.enemy_timer += time.delta_secs();
     if state.enemy_timer < ENEMY_TICK { return; }
     state.enemy_timer = 0.0;
 
-    for (mut tf, mut enemy) in &mut enemies {
+    for (mut mv, mut enemy) in &mut enemies {
         let nx = enemy.gx + enemy.dir;
         // Bounce at edges or walls
         let blocked = nx < 0 || nx >= COLS
@@ -229,8 +238,7 @@ pub fn enemy_patrol(
         } else {
             enemy.gx = nx;
             let (px, py) = grid_to_world(enemy.gx, enemy.gy);
-            tf.translation.x = px;
-            tf.translation.y = py;
+            mv.go(Vec3::new(px, py, 0.8));
         }
     }
 }
@@ -241,12 +249,14 @@ pub fn check_enemy_collision(
     mut pq: Query<(&Transform, &mut Player)>,
     enemies: Query<(Entity, &Enemy, &Transform), Without<Player>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
-    let Ok((_ptf, mut player)) = pq.get_single_mut() else { return };
+    let Ok((ptf, mut player)) = pq.get_single_mut() else { return };
     for (e, enemy, _etf) in &enemies {
         if player.gx == enemy.gx && player.gy == enemy.gy {
             // Stomp not possible at same level — damage
             player.lives -= 1;
+            failures.send(Failure(ptf.translation.truncate()));
             commands.entity(e).despawn_recursive();
             if player.lives <= 0 {
                 next_state.set(crate::AppState::GameOver);
@@ -260,8 +270,11 @@ pub fn check_enemy_collision(
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, pq: Query<&Player>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    if let Ok(player) = pq.get_single() {
+        bridge.stats.deaths = Some(START_LIVES - player.lives.max(0));
+    }
 }
 
 pub fn update_hud(state: Res<GameState>, pq: Query<&Player>, mut sq: Query<&mut Text, With<ScoreText>>) {
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::ai::{Bot, Outcome};
+use crate::input::{ActionState, Control};
+use crate::{BevyBridge, GameOptions};
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
 
 // ---------------------------------------------------------------------------
@@ -18,6 +21,14 @@ const CAR_SIZE: Vec2 = Vec2::new(20.0, 32.0);
 const WP_RADIUS: f32 = 50.0;
 const OFF_TRACK_DIST: f32 = 120.0;
 const LAPS_TO_WIN: i32 = 3;
+/// Bot rival pace at accuracy 0 and 1.
+const RIVAL_MIN_SPEED: f32 = 150.0;
+const RIVAL_MAX_SPEED: f32 = 280.0;
+/// Most a lap's pace differs from the rival's usual one at accuracy 0.
+const RIVAL_PACE_SPREAD: f32 = 60.0;
+/// How far outside the racing line the rival drives, so it doesn't sit on
+/// top of the player at the start.
+const RIVAL_LANE: f32 = 30.0;
 
 // ---------------------------------------------------------------------------
 // Components
@@ -27,16 +38,26 @@ const LAPS_TO_WIN: i32 = 3;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct PlayerCar {
+pub struct PlayerCar {
     speed: f32,
     next_wp: usize,
     lap: i32,
 }
 
 #[derive(Component)]
-struct AICar {
+pub struct AICar {
     path_t: f32,
     speed: f32,
+    /// Sideways offset from the racing line.
+    lane: f32,
+}
+
+/// The bot's car in a bot match, racing the player for the win.
+#[derive(Component)]
+pub struct Rival {
+    lap: i32,
+    /// Usual pace; each lap is driven a little off it.
+    pace: f32,
 }
 
 #[derive(Component)]
@@ -48,23 +69,31 @@ struct Waypoint {
 struct TrackVisual;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     waypoints: Vec<Vec2>,
+    /// Seconds since the start, stopped once the last lap is done.
+    race_time: f32,
 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+    bot: Res<Bot>,
+) {
     // Oval track waypoints
     let wps = build_waypoints();
 
-    commands.insert_resource(GameState { score: 0, waypoints: wps.clone() });
+    commands.insert_resource(GameState { score: 0, waypoints: wps.clone(), race_time: 0.0 });
 
     // Background
     if let Some(ref bg_handle) = custom_assets.background {
@@ -105,29 +134,42 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     // Player car at first waypoint — vehicle with HERO_RED
     let start = wps[0];
     let player_config = CharacterConfig::vehicle(palette::HERO_RED, CAR_SIZE);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &player_config,
         Vec3::new(start.x, start.y, 1.0),
         (PlayerCar { speed: 0.0, next_wp: 1, lap: 0 }, GameEntity),
     );
 
-    // AI cars — vehicles with VILLAIN_PURPLE
-    let mut rng = rand::thread_rng();
-    for i in 0..3 {
-        let t = (i as f32 + 1.0) * 0.25;
-        let idx = ((t * wps.len() as f32) as usize) % wps.len();
-        let p = wps[idx];
-        let spd = rng.gen_range(100.0..180.0);
-        let config = CharacterConfig::vehicle(palette::VILLAIN_PURPLE, CAR_SIZE);
+    // AI cars — vehicles with VILLAIN_PURPLE.  In a bot match the bot's
+    // car lines up beside the player instead.
+    let config = CharacterConfig::vehicle(palette::VILLAIN_PURPLE, CAR_SIZE);
+    if bot.active {
+        let pace = RIVAL_MIN_SPEED.lerp(RIVAL_MAX_SPEED, bot.accuracy);
         pixar::spawn_character(
             &mut commands,
             &pixar_assets,
             &config,
-            Vec3::new(p.x, p.y, 0.9),
-            (AICar { path_t: t, speed: spd }, GameEntity),
+            Vec3::new(start.x + RIVAL_LANE, start.y, 0.9),
+            (AICar { path_t: 0.0, speed: lap_pace(&bot, pace), lane: RIVAL_LANE }, Rival { lap: 0, pace }, GameEntity),
         );
+    } else {
+        let mut rng = rand::thread_rng();
+        for i in 0..3 {
+            let t = (i as f32 + 1.0) * 0.25;
+            let idx = ((t * wps.len() as f32) as usize) % wps.len();
+            let p = wps[idx];
+            let spd = rng.gen_range(100.0..180.0);
+            pixar::spawn_character(
+                &mut commands,
+                &pixar_assets,
+                &config,
+                Vec3::new(p.x, p.y, 0.9),
+                (AICar { path_t: t, speed: spd, lane: 0.0 }, GameEntity),
+            );
+        }
     }
 
     // HUD
@@ -146,26 +188,29 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_drive(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
-    state: Res<GameState>,
+    mut state: ResMut<GameState>,
     mut pq: Query<(&mut Transform, &mut PlayerCar)>,
 ) {
     let dt = time.delta_secs();
     let Ok((mut tf, mut car)) = pq.get_single_mut() else { return };
+    if car.lap < LAPS_TO_WIN {
+        state.race_time += dt;
+    }
 
     // Steering
-    if keys.pressed(KeyCode::ArrowLeft) {
+    if input.pressed(Control::MoveLeft) {
         tf.rotate_z(STEER_SPEED * dt * (car.speed / MAX_SPEED).max(0.2));
     }
-    if keys.pressed(KeyCode::ArrowRight) {
+    if input.pressed(Control::MoveRight) {
         tf.rotate_z(-STEER_SPEED * dt * (car.speed / MAX_SPEED).max(0.2));
     }
 
     // Accel / brake
-    if keys.pressed(KeyCode::ArrowUp) {
+    if input.pressed(Control::MoveUp) {
         car.speed = (car.speed + ACCEL * dt).min(MAX_SPEED);
-    } else if keys.pressed(KeyCode::ArrowDown) {
+    } else if input.pressed(Control::MoveDown) {
         car.speed = (car.speed - BRAKE * dt).max(0.0);
     } else {
         car.speed = (car.speed - DRAG * dt).max(0.0);
@@ -189,6 +234,7 @@ pub fn check_waypoints(
     mut pq: Query<(&Transform, &mut PlayerCar)>,
     state: Res<GameState>,
     mut gs: ResMut<GameState>,
+    mut bot: ResMut<Bot>,
     mut next_state: ResMut<NextState<crate::AppState>>,
 ) {
     let Ok((tf, mut car)) = pq.get_single_mut() else { return };
@@ -203,6 +249,9 @@ pub fn check_waypoints(
                 car.lap += 1;
                 gs.score += 300;
                 if car.lap >= LAPS_TO_WIN {
+                    if bot.active && bot.outcome.is_none() {
+                        bot.outcome = Some(Outcome::Win);
+                    }
                     next_state.set(crate::AppState::GameOver);
                 }
             }
@@ -213,13 +262,30 @@ pub fn check_waypoints(
 pub fn ai_drive(
     time: Res<Time>,
     state: Res<GameState>,
-    mut ai: Query<(&mut Transform, &mut AICar)>,
+    mut bot: ResMut<Bot>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+    mut ai: Query<(&mut Transform, &mut AICar, Option<&mut Rival>)>,
 ) {
     let dt = time.delta_secs();
     let n = state.waypoints.len() as f32;
-    for (mut tf, mut car) in &mut ai {
-        car.path_t += (car.speed / (n * 60.0)) * dt * 60.0;
-        if car.path_t >= 1.0 { car.path_t -= 1.0; }
+    let lap_length = lap_length(&state.waypoints);
+    for (mut tf, mut car, rival) in &mut ai {
+        // The rival waits out its reaction time before pulling away.
+        if rival.is_some() && state.race_time < bot.reaction_secs {
+            continue;
+        }
+        car.path_t += car.speed * dt / lap_length;
+        if car.path_t >= 1.0 {
+            car.path_t -= 1.0;
+            if let Some(mut rival) = rival {
+                rival.lap += 1;
+                car.speed = lap_pace(&bot, rival.pace);
+                if rival.lap >= LAPS_TO_WIN && bot.outcome.is_none() {
+                    bot.outcome = Some(Outcome::Loss);
+                    next_state.set(crate::AppState::GameOver);
+                }
+            }
+        }
 
         let idx = (car.path_t * n) as usize;
         let next_idx = (idx + 1) % state.waypoints.len();
@@ -227,25 +293,40 @@ pub fn ai_drive(
 
         let a = state.waypoints[idx % state.waypoints.len()];
         let b = state.waypoints[next_idx];
-        let pos = a.lerp(b, frac);
+        let dir = (b - a).normalize_or_zero();
+        let pos = a.lerp(b, frac) + Vec2::new(dir.y, -dir.x) * car.lane;
         tf.translation.x = pos.x;
         tf.translation.y = pos.y;
 
         // Face direction of travel
-        let dir = (b - a).normalize_or_zero();
         let angle = dir.x.atan2(dir.y);
         tf.rotation = Quat::from_rotation_z(-angle);
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, pq: Query<&PlayerCar>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    let Ok(car) = pq.get_single() else { return };
+    bridge.stats.level = Some(car.lap);
+    if car.lap >= LAPS_TO_WIN {
+        bridge.stats.time = Some((state.race_time * 1000.0) as i64);
+    }
 }
 
-pub fn update_hud(state: Res<GameState>, pq: Query<&PlayerCar>, mut sq: Query<&mut Text, With<ScoreText>>) {
+pub fn update_hud(
+    state: Res<GameState>,
+    bot: Res<Bot>,
+    pq: Query<&PlayerCar>,
+    rq: Query<&Rival>,
+    mut sq: Query<&mut Text, With<ScoreText>>,
+) {
     let Ok(car) = pq.get_single() else { return };
+    let mut hud = format!("Lap: {}/{} | Score: {} | Speed: {:.0}", car.lap, LAPS_TO_WIN, state.score, car.speed);
+    if let Ok(rival) = rq.get_single() {
+        hud += &format!(" | {}: {}/{}", bot.name, rival.lap, LAPS_TO_WIN);
+    }
     for mut t in &mut sq {
-        **t = format!("Lap: {}/{} | Score: {} | Speed: {:.0}", car.lap, LAPS_TO_WIN, state.score, car.speed);
+        **t = hud.clone();
     }
 }
 
@@ -262,6 +343,17 @@ pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Distance round the track through every waypoint.
+fn lap_length(waypoints: &[Vec2]) -> f32 {
+    waypoints.iter().zip(waypoints.iter().cycle().skip(1)).map(|(a, b)| a.distance(*b)).sum()
+}
+
+/// The rival's speed for its next lap: its usual pace, missed by up to
+/// [`RIVAL_PACE_SPREAD`] the less accurate it is.
+fn lap_pace(bot: &Bot, pace: f32) -> f32 {
+    bot.miss(Vec2::new(pace, 0.0), Vec2::new(RIVAL_PACE_SPREAD, 0.0)).x.clamp(RIVAL_MIN_SPEED, RIVAL_MAX_SPEED)
+}
+
 fn build_waypoints() -> Vec<Vec2> {
     // Oval track: 16 points in an ellipse
     let cx = 0.0_f32;
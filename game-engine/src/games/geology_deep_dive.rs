@@ -1,15 +1,22 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::camera::CameraFollow;
 
 const COLS: i32 = 10;
-const ROWS: i32 = 15;
+const ROWS: i32 = 24;
 const TILE: f32 = 40.0;
 const ORIGIN_X: f32 = -180.0;
-const ORIGIN_Y: f32 = -280.0;
+/// The sky sits at the top of the view; the shaft runs on below it.
+const ORIGIN_Y: f32 = 280.0 - (ROWS - 1) as f32 * TILE;
+/// Height of the view the games are laid out for.
+const VIEW_HEIGHT: f32 = 640.0;
 const MOVE_CD: f32 = 0.12;
 const SKY_ROWS: i32 = 2;
 const MAX_FUEL: i32 = 100;
@@ -24,7 +31,7 @@ enum MineralKind { None, Copper, Silver, Gold, Diamond }
 enum TileKind { Sky, Surface, Dirt, Rock }
 
 #[derive(Component)]
-struct Tile {
+pub struct Tile {
     gx: i32,
     gy: i32,
     kind: TileKind,
@@ -32,7 +39,7 @@ struct Tile {
 }
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     gx: i32,
     gy: i32,
     fuel: i32,
@@ -40,35 +47,43 @@ struct Player {
 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     move_cd: f32,
+    minerals: i32,
 }
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, move_cd: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState { score: 0, move_cd: 0.0, minerals: 0 });
 
-    // Background
+    // Background, tall enough to fill the view anywhere down the shaft
+    let bg_size = Vec2::new(960.0, ROWS as f32 * TILE + VIEW_HEIGHT);
+    let bg_y = ORIGIN_Y + (ROWS - 1) as f32 * TILE / 2.0;
     if let Some(ref bg_handle) = custom_assets.background {
         commands.spawn((
-            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
-            Transform::from_xyz(0.0, 0.0, -1.0),
+            Sprite { image: bg_handle.clone(), custom_size: Some(bg_size), ..default() },
+            Transform::from_xyz(0.0, bg_y, -1.0),
             GameEntity,
         ));
     } else {
         commands.spawn((
-            Sprite { color: Color::srgb(0.04, 0.04, 0.08), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
-            Transform::from_xyz(0.0, 0.0, -1.0),
+            Sprite { color: Color::srgb(0.04, 0.04, 0.08), custom_size: Some(bg_size), ..default() },
+            Transform::from_xyz(0.0, bg_y, -1.0),
             GameEntity,
         ));
     }
 
     let mut rng = rand::thread_rng();
 
-    // Generate grid (row 0 = bottom, row 14 = top)
+    // Generate grid (row 0 = bottom, row ROWS - 1 = top)
     for row in 0..ROWS {
         for col in 0..COLS {
             let from_top = ROWS - 1 - row;
@@ -78,7 +93,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
                 (TileKind::Surface, MineralKind::None)
             } else {
                 // Underground
-                let depth = from_top - SKY_ROWS; // 1..12
+                let depth = from_top - SKY_ROWS; // 1..=ROWS - 1 - SKY_ROWS
                 if rng.gen_range(0..10) == 0 {
                     (TileKind::Rock, MineralKind::None)
                 } else {
@@ -101,12 +116,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     let (px, py) = grid_to_world(COLS / 2, surface_gy + 1); // stand above surface
     let player_size = Vec2::new(TILE - 8.0, TILE - 8.0);
     let player_config = CharacterConfig::hero(palette::HERO_ORANGE, player_size);
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &player_config,
         Vec3::new(px, py, 1.0),
-        (Player { gx: COLS / 2, gy: surface_gy + 1, fuel: MAX_FUEL, cargo_value: 0 }, GameEntity),
+        (Player { gx: COLS / 2, gy: surface_gy + 1, fuel: MAX_FUEL, cargo_value: 0 }, camera_follow(), GameEntity),
     );
 
     // HUD
@@ -120,24 +136,26 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_move(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
     mut commands: Commands,
     mut pq: Query<(&mut Transform, &mut Player)>,
     tiles: Query<(Entity, &Tile)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
     state.move_cd -= time.delta_secs();
     if state.move_cd > 0.0 { return; }
     let Ok((mut ptf, mut player)) = pq.get_single_mut() else { return };
 
     let (mut dx, mut dy) = (0i32, 0i32);
-    if keys.pressed(KeyCode::ArrowLeft) { dx = -1; }
-    else if keys.pressed(KeyCode::ArrowRight) { dx = 1; }
-    else if keys.pressed(KeyCode::ArrowDown) { dy = -1; }
-    else if keys.pressed(KeyCode::ArrowUp) { dy = 1; }
+    if input.pressed(Control::MoveLeft) { dx = -1; }
+    else if input.pressed(Control::MoveRight) { dx = 1; }
+    else if input.pressed(Control::MoveDown) { dy = -1; }
+    else if input.pressed(Control::MoveUp) { dy = 1; }
 
     if dx == 0 && dy == 0 { return; }
 
@@ -161,12 +179,16 @@ pub fn player_move(
         Some((TileKind::Dirt, mineral)) => {
             // Dig: costs fuel
             if player.fuel <= 0 {
+                failures.send(Failure(ptf.translation.truncate()));
                 next_state.set(crate::AppState::GameOver);
                 return;
             }
             player.fuel -= 1;
             let value = mineral_value(mineral);
             player.cargo_value += value;
+            if mineral != MineralKind::None {
+                state.minerals += 1;
+            }
             if let Some(e) = dest_entity {
                 commands.entity(e).despawn_recursive();
             }
@@ -189,6 +211,7 @@ pub fn player_move(
     // Check fuel
     if player.fuel <= 0 {
         state.score += player.cargo_value;
+        failures.send(Failure(ptf.translation.truncate()));
         next_state.set(crate::AppState::GameOver);
     }
 }
@@ -213,6 +236,7 @@ pub fn gravity(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.collectibles = Some(state.minerals);
 }
 
 pub fn update_hud(
@@ -231,6 +255,13 @@ pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
     commands.remove_resource::<GameState>();
 }
 
+/// Follows the miner down the shaft, stopping with the bottom row at the
+/// bottom of the view.
+fn camera_follow() -> CameraFollow {
+    let lowest = ORIGIN_Y - TILE / 2.0 + VIEW_HEIGHT / 2.0;
+    CameraFollow::new(Vec2::new(0.0, 3.0 * TILE)).within(Rect::new(0.0, lowest, 0.0, 0.0))
+}
+
 fn grid_to_world(gx: i32, gy: i32) -> (f32, f32) {
     (ORIGIN_X + gx as f32 * TILE, ORIGIN_Y + gy as f32 * TILE)
 }
@@ -325,15 +356,15 @@ fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, kind: TileKin
 fn roll_mineral(rng: &mut impl Rng, depth: i32) -> MineralKind {
     let r = rng.gen_range(0..100);
     match depth {
-        0..=2 => {
+        0..=4 => {
             if r < 30 { MineralKind::Copper } else { MineralKind::None }
         }
-        3..=5 => {
+        5..=9 => {
             if r < 20 { MineralKind::Copper }
             else if r < 35 { MineralKind::Silver }
             else { MineralKind::None }
         }
-        6..=8 => {
+        10..=14 => {
             if r < 10 { MineralKind::Copper }
             else if r < 25 { MineralKind::Silver }
             else if r < 35 { MineralKind::Gold }
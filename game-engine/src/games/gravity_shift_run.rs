@@ -1,9 +1,17 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::ghost::GhostTracked;
+use crate::input::{GameAction, Rumble};
+use crate::levelgen::{self, LevelRng};
+use crate::pool::{Parked, Pool};
+use crate::powerups::{self, ActiveEffects, Pickup, Powerup};
+use crate::timestep::Interpolated;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -20,6 +28,8 @@ const FLOOR_Y: f32 = -280.0;
 const HALF_W: f32 = 480.0;
 const BORDER_THICKNESS: f32 = 20.0;
 const SPAWN_DISTANCE: f32 = 300.0;
+const POWERUP_CHANCE: f64 = 0.25;
+const POWERUPS: &[Powerup] = &[Powerup::Shield, Powerup::Magnet, Powerup::SlowMotion, Powerup::DoubleScore];
 
 // ---------------------------------------------------------------------------
 // Components
@@ -29,26 +39,32 @@ const SPAWN_DISTANCE: f32 = 300.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { vy: f32, gravity_dir: f32 }
+pub struct Player { vy: f32, gravity_dir: f32 }
 
 #[derive(Component)]
-struct Obstacle;
+pub struct Obstacle;
 
 #[derive(Component)]
 struct Border;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
+/// `rng` places the wall gaps; it is seeded in daily runs.
 #[derive(Resource)]
-struct GameState { scroll_x: f32, spawn_timer: f32 }
+pub struct GameState { score: f32, spawn_timer: f32, distance: f32, rng: LevelRng }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { scroll_x: 0.0, spawn_timer: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    let mut rng = levelgen::run_rng(&options);
 
     // Background
     let bg_sprite = if let Some(ref bg) = custom_assets.background {
@@ -71,12 +87,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 
     // Player
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_PURPLE, PLAYER_SIZE),
         Vec3::new(PLAYER_X, 0.0, 1.0),
-        (Player { vy: 0.0, gravity_dir: -1.0 }, GameEntity),
+        (Player { vy: 0.0, gravity_dir: -1.0 }, ActiveEffects::default(), Interpolated::default(), GameEntity, GhostTracked("gravity_shift_run")),
     );
 
     // HUD
@@ -87,25 +104,25 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Node { position_type: PositionType::Absolute, top: Val::Px(10.0), left: Val::Px(10.0), ..default() },
         ScoreText, GameEntity,
     ));
+    powerups::spawn_hud(&mut commands, GameEntity);
 
     // Initial obstacles
-    spawn_wall_pair(&mut commands, &pixar_assets, 300.0);
-    spawn_wall_pair(&mut commands, &pixar_assets, 600.0);
+    let mut pool = Pool::<Obstacle>::default();
+    spawn_wall_pair(&mut commands, &mut pool, &pixar_assets, &mut rng, 300.0);
+    spawn_wall_pair(&mut commands, &mut pool, &pixar_assets, &mut rng, 600.0);
+
+    commands.insert_resource(GameState { score: 0.0, spawn_timer: 0.0, distance: 0.0, rng });
+    commands.insert_resource(pool);
 }
 
 // ---------------------------------------------------------------------------
 // Systems
 // ---------------------------------------------------------------------------
 
-pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    touches: Res<Touches>,
-    mut pq: Query<&mut Player>,
-) {
-    let flip = keys.just_pressed(KeyCode::Space)
-        || mouse.just_pressed(MouseButton::Left)
-        || touches.any_just_pressed();
+pub fn player_input(mut actions: EventReader<GameAction>, mut pq: Query<&mut Player>) {
+    let flip = actions
+        .read()
+        .any(|a| matches!(a, GameAction::Jump | GameAction::Pointer));
 
     if flip {
         for mut p in &mut pq {
@@ -116,20 +133,33 @@ pub fn player_input(
 
 pub fn player_physics(
     time: Res<Time>,
-    mut pq: Query<(&mut Transform, &mut Player)>,
+    mut pq: Query<(&mut Transform, &mut Player, &mut ActiveEffects)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+    mut rumble: EventWriter<Rumble>,
 ) {
     let dt = time.delta_secs();
-    for (mut tf, mut p) in &mut pq {
+    for (mut tf, mut p, mut fx) in &mut pq {
         p.vy += GRAVITY_STRENGTH * p.gravity_dir * dt;
         tf.translation.y += p.vy * dt;
 
         // Hit ceiling/floor = game over
-        if tf.translation.y + PLAYER_SIZE.y / 2.0 > CEILING_Y {
-            next_state.set(crate::AppState::GameOver);
-            return;
-        }
-        if tf.translation.y - PLAYER_SIZE.y / 2.0 < FLOOR_Y {
+        if tf.translation.y + PLAYER_SIZE.y / 2.0 > CEILING_Y
+            || tf.translation.y - PLAYER_SIZE.y / 2.0 < FLOOR_Y
+        {
+            // A shield bounces the player off and flips gravity
+            if fx.absorb_hit() {
+                tf.translation.y = tf.translation.y.clamp(
+                    FLOOR_Y + PLAYER_SIZE.y / 2.0,
+                    CEILING_Y - PLAYER_SIZE.y / 2.0,
+                );
+                p.vy = 0.0;
+                p.gravity_dir *= -1.0;
+                rumble.send(Rumble::light());
+                continue;
+            }
+            failures.send(Failure(tf.translation.truncate()));
+            rumble.send(Rumble::impact());
             next_state.set(crate::AppState::GameOver);
             return;
         }
@@ -139,43 +169,72 @@ pub fn player_physics(
 pub fn scroll_world(
     time: Res<Time>,
     mut state: ResMut<GameState>,
-    mut oq: Query<&mut Transform, With<Obstacle>>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut oq: Query<(Entity, &mut Transform), (With<Obstacle>, Without<Parked>)>,
+    mut pickups: Query<&mut Transform, (With<Pickup>, With<GameEntity>, Without<Obstacle>)>,
+    mut pool: ResMut<Pool<Obstacle>>,
     mut commands: Commands,
-    entities: Query<Entity, With<Obstacle>>,
 ) {
-    let dt = time.delta_secs();
+    let fx = effects.get_single().ok();
+    let dt = time.delta_secs() * fx.map_or(1.0, ActiveEffects::world_scale);
     let scroll = SCROLL_SPEED * dt;
-    state.scroll_x += scroll;
+    state.distance += scroll;
+    state.score += scroll / 10.0 * fx.map_or(1, ActiveEffects::score_multiplier) as f32;
 
-    for mut tf in &mut oq {
+    // Park off-screen left
+    for (entity, mut tf) in &mut oq {
         tf.translation.x -= scroll;
-    }
-
-    // Despawn off-screen left
-    for (entity, tf) in entities.iter().zip(oq.iter()) {
         if tf.translation.x < -HALF_W - 60.0 {
-            commands.entity(entity).despawn();
+            pool.release(&mut commands, entity);
         }
     }
+    for mut tf in &mut pickups {
+        tf.translation.x -= scroll;
+    }
 }
 
-pub fn spawn_obstacles(time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
-    state.spawn_timer += SCROLL_SPEED * time.delta_secs();
+pub fn spawn_obstacles(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut pool: ResMut<Pool<Obstacle>>,
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+) {
+    let scale = effects.get_single().map_or(1.0, ActiveEffects::world_scale);
+    state.spawn_timer += SCROLL_SPEED * time.delta_secs() * scale;
     if state.spawn_timer >= SPAWN_DISTANCE {
         state.spawn_timer = 0.0;
-        spawn_wall_pair(&mut commands, &pixar_assets, HALF_W + 60.0);
+        spawn_wall_pair(&mut commands, &mut pool, &pixar_assets, &mut state.rng, HALF_W + 60.0);
+
+        // Halfway to the next pair
+        if state.rng.gen_bool(POWERUP_CHANCE) {
+            let y = state.rng.gen_range(FLOOR_Y + 60.0..CEILING_Y - 60.0);
+            powerups::spawn_pickup(
+                &mut commands,
+                &pixar_assets,
+                Powerup::random(POWERUPS),
+                Vec3::new(HALF_W + 60.0 + SPAWN_DISTANCE / 2.0, y, 0.6),
+                10.0,
+                (Interpolated::default(), GameEntity),
+            );
+        }
     }
 }
 
 pub fn check_collisions(
-    pq: Query<&Transform, With<Player>>,
-    oq: Query<(&Transform, &Sprite), With<Obstacle>>,
+    mut commands: Commands,
+    mut pq: Query<(&Transform, &mut ActiveEffects), With<Player>>,
+    oq: Query<(Entity, &Transform, &Sprite), (With<Obstacle>, Without<Parked>)>,
+    mut pool: ResMut<Pool<Obstacle>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+    mut rumble: EventWriter<Rumble>,
 ) {
-    let Ok(ptf) = pq.get_single() else { return };
+    let Ok((ptf, mut fx)) = pq.get_single_mut() else { return };
     let phalf = PLAYER_SIZE / 2.0;
 
-    for (otf, sprite) in &oq {
+    for (entity, otf, sprite) in &oq {
         let osize = sprite.custom_size.unwrap_or(Vec2::new(WALL_WIDTH, 200.0));
         let ohalf = osize / 2.0;
 
@@ -183,18 +242,33 @@ pub fn check_collisions(
         let overlap_y = (ptf.translation.y - otf.translation.y).abs() < phalf.y + ohalf.y;
 
         if overlap_x && overlap_y {
+            if fx.absorb_hit() {
+                pool.release(&mut commands, entity);
+                rumble.send(Rumble::light());
+                continue;
+            }
+            failures.send(Failure(ptf.translation.truncate()));
+            rumble.send(Rumble::impact());
             next_state.set(crate::AppState::GameOver);
             return;
         }
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
-    bridge.current_score = (state.scroll_x / 10.0) as i32;
+pub fn update_score(
+    state: Res<GameState>,
+    effects: Query<&ActiveEffects, With<Player>>,
+    mut bridge: ResMut<BevyBridge>,
+) {
+    bridge.current_score = state.score as i32;
+    bridge.stats.distance = GameStats::metres(state.distance);
+    if let Ok(fx) = effects.get_single() {
+        bridge.stats.collectibles = Some(fx.collected());
+    }
 }
 
 pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
-    let score = (state.scroll_x / 10.0) as i32;
+    let score = state.score as i32;
     for mut t in &mut q { **t = format!("Score: {}", score); }
 }
 
@@ -203,16 +277,16 @@ pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>
 // ---------------------------------------------------------------------------
 
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
-    for e in &q { commands.entity(e).despawn(); }
+    for e in &q { commands.entity(e).despawn_recursive(); }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Obstacle>>();
 }
 
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn spawn_wall_pair(commands: &mut Commands, pixar_assets: &PixarAssets, x: f32) {
-    let mut rng = rand::thread_rng();
+fn spawn_wall_pair(commands: &mut Commands, pool: &mut Pool<Obstacle>, pixar_assets: &PixarAssets, rng: &mut LevelRng, x: f32) {
     let gap_center = rng.gen_range(FLOOR_Y + 80.0..CEILING_Y - 80.0);
     let gap_top = gap_center + GAP_HEIGHT / 2.0;
     let gap_bot = gap_center - GAP_HEIGHT / 2.0;
@@ -220,18 +294,18 @@ fn spawn_wall_pair(commands: &mut Commands, pixar_assets: &PixarAssets, x: f32)
     // Top wall: from gap_top to CEILING_Y
     let top_h = CEILING_Y - gap_top;
     if top_h > 2.0 {
-        commands.spawn((
+        pool.acquire(commands, (
             pixar::round_sprite(pixar_assets, palette::VILLAIN_RED, Vec2::new(WALL_WIDTH, top_h)),
-            Transform::from_xyz(x, gap_top + top_h / 2.0, 0.5), Obstacle, GameEntity,
+            Transform::from_xyz(x, gap_top + top_h / 2.0, 0.5), Obstacle, Interpolated::default(), GameEntity,
         ));
     }
 
     // Bottom wall: from FLOOR_Y to gap_bot
     let bot_h = gap_bot - FLOOR_Y;
     if bot_h > 2.0 {
-        commands.spawn((
+        pool.acquire(commands, (
             pixar::round_sprite(pixar_assets, palette::VILLAIN_RED, Vec2::new(WALL_WIDTH, bot_h)),
-            Transform::from_xyz(x, FLOOR_Y + bot_h / 2.0, 0.5), Obstacle, GameEntity,
+            Transform::from_xyz(x, FLOOR_Y + bot_h / 2.0, 0.5), Obstacle, Interpolated::default(), GameEntity,
         ));
     }
 }
@@ -0,0 +1,38 @@
+//! Board layout shared by the grid games: `robot_repair_bay` and
+//! `element_match`.
+//!
+//! A [`Grid`] is `cols` by `rows` equal cells centred on `center`, with
+//! cell `(0, 0)` at the bottom left.  It maps cells to world positions and
+//! back, so clicks land on the cell they were drawn in.
+
+use bevy::prelude::*;
+
+#[derive(Clone, Copy)]
+pub struct Grid {
+    pub cols: i32,
+    pub rows: i32,
+    pub cell: Vec2,
+    pub center: Vec2,
+}
+
+impl Grid {
+    /// Centre of cell `(0, 0)`.
+    pub fn origin(&self) -> Vec2 {
+        let size = Vec2::new(self.cols as f32, self.rows as f32) * self.cell;
+        self.center - size / 2.0 + self.cell / 2.0
+    }
+
+    /// World position of the centre of cell `(gx, gy)` at depth `z`.
+    pub fn wp(&self, gx: i32, gy: i32, z: f32) -> Vec3 {
+        let o = self.origin();
+        Vec3::new(o.x + gx as f32 * self.cell.x, o.y + gy as f32 * self.cell.y, z)
+    }
+
+    /// The cell under world position `pos`.  It may lie outside the grid.
+    pub fn cell_at(&self, pos: Vec2) -> (i32, i32) {
+        let o = self.origin();
+        let gx = ((pos.x - o.x + self.cell.x / 2.0) / self.cell.x).floor() as i32;
+        let gy = ((pos.y - o.y + self.cell.y / 2.0) / self.cell.y).floor() as i32;
+        (gx, gy)
+    }
+}
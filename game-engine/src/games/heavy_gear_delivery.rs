@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
-use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
+use crate::pixar::{PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
 
 // ---------------------------------------------------------------------------
@@ -32,29 +35,29 @@ const DAMAGE_RATE: f32 = 30.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Truck {
+pub struct Truck {
     velocity: f32,
 }
 
 #[derive(Component)]
-struct Cargo {
+pub struct Cargo {
     balance: f32,
     hp: f32,
 }
 
 #[derive(Component)]
-struct TerrainSegment {
+pub struct TerrainSegment {
     index: i32,
 }
 
 #[derive(Component)]
-struct HudBalance;
+pub struct HudBalance;
 
 #[derive(Component)]
-struct HudInfo;
+pub struct HudInfo;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     distance: f32,
     scroll_offset: f32,
     phase: f32,
@@ -64,7 +67,12 @@ struct GameState {
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
     commands.insert_resource(GameState {
         distance: 0.0,
         scroll_offset: 0.0,
@@ -94,9 +102,10 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     }
 
     // Truck
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::vehicle(palette::HERO_BLUE, TRUCK_SIZE),
         Vec3::new(TRUCK_X, GROUND_Y, 2.0),
         (Truck { velocity: 100.0 }, GameEntity),
@@ -145,15 +154,15 @@ fn terrain_slope(x_world: f32, phase: f32) -> f32 {
 // ---------------------------------------------------------------------------
 
 pub fn truck_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
     time: Res<Time>,
     mut q: Query<&mut Truck>,
 ) {
     let dt = time.delta_secs();
     for mut tr in &mut q {
-        let accel = keys.pressed(KeyCode::ArrowRight) || mouse.pressed(MouseButton::Left);
-        let brake = keys.pressed(KeyCode::ArrowLeft);
+        let accel = input.pressed(Control::MoveRight) || mouse.pressed(MouseButton::Left);
+        let brake = input.pressed(Control::MoveLeft);
         if accel {
             tr.velocity = (tr.velocity + ACCEL * dt).min(MAX_SPEED);
         } else if brake {
@@ -216,12 +225,16 @@ pub fn cargo_balance(
     }
 }
 
+/// Where the cargo broke is reported along the terrain, not the screen.
 pub fn check_game_over(
-    cargo_q: Query<&Cargo>,
+    state: Res<GameState>,
+    cargo_q: Query<(&Transform, &Cargo)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
-    if let Ok(c) = cargo_q.get_single() {
+    if let Ok((tf, c)) = cargo_q.get_single() {
         if c.hp <= 0.0 {
+            failures.send(Failure(Vec2::new(state.scroll_offset + TRUCK_X, tf.translation.y)));
             next_state.set(crate::AppState::GameOver);
         }
     }
@@ -229,6 +242,7 @@ pub fn check_game_over(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = (state.distance / 10.0) as i32;
+    bridge.stats.distance = GameStats::metres(state.distance);
 }
 
 pub fn update_hud(
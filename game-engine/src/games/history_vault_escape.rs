@@ -1,7 +1,13 @@
 use bevy::prelude::*;
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::levelgen::{self, vault, LevelRng};
+use crate::tween::MoveTo;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -21,14 +27,14 @@ const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     gx: i32,
     gy: i32,
     keys: [bool; 3], // R, G, B
 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Clone, Copy, PartialEq)]
 enum TileKind {
@@ -43,7 +49,7 @@ enum TileKind {
 }
 
 #[derive(Component)]
-struct Tile {
+pub struct Tile {
     kind: TileKind,
     gx: i32,
     gy: i32,
@@ -51,9 +57,13 @@ struct Tile {
 }
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     move_cooldown: f32,
+    level: usize,
+    /// Set for an endless run of generated vaults; without one the game
+    /// ends at the handcrafted vault's exit.
+    seed: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -98,7 +108,7 @@ fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, gx: i32, gy:
 
     if is_collectible_tile(kind) {
         let config = CharacterConfig::collectible(color, TILE - 6.0);
-        pixar::spawn_character(commands, pixar_assets, &config, pos.extend(0.0).truncate().extend(0.0), (
+        pixar::spawn_character(commands, pixar_assets, &config, pos, (
             Tile { kind, gx, gy, active: true },
             GameEntity,
         ));
@@ -106,10 +116,14 @@ fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, gx: i32, gy:
         // Walls, floors, doors, traps, blocks are props
         let is_round = matches!(kind, TileKind::Block);
         let config = CharacterConfig::prop(color, size, is_round);
-        pixar::spawn_character(commands, pixar_assets, &config, pos.extend(0.0).truncate().extend(0.0), (
+        let entity = pixar::spawn_character(commands, pixar_assets, &config, pos, (
             Tile { kind, gx, gy, active: true },
             GameEntity,
         ));
+        // Blocks can be pushed; they slide to their new cell.
+        if kind == TileKind::Block {
+            commands.entity(entity).insert(MoveTo::at(pos));
+        }
     }
 }
 
@@ -117,8 +131,14 @@ fn spawn_tile(commands: &mut Commands, pixar_assets: &PixarAssets, gx: i32, gy:
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, move_cooldown: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    let seed = levelgen::seed(&options);
+    commands.insert_resource(GameState { score: 0, move_cooldown: 0.0, level: 0, seed });
 
     // Background
     let bg_color = palette::NIGHT_BG;
@@ -136,20 +156,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         ));
     }
 
-    // Build level layout
-    #[rustfmt::skip]
-    let layout: Vec<(i32, i32, TileKind)> = build_level();
-
-    for (gx, gy, kind) in &layout {
-        spawn_tile(&mut commands, &pixar_assets, *gx, *gy, *kind);
-    }
-
-    // Player (explorer)
-    let player_config = CharacterConfig::hero(palette::HERO_ORANGE, Vec2::splat(TILE - 8.0));
-    pixar::spawn_character(&mut commands, &pixar_assets, &player_config, world_pos(1, 1) + Vec3::Z, (
-        Player { gx: 1, gy: 1, keys: [false; 3] },
-        GameEntity,
-    ));
+    spawn_level(&mut commands, &pixar_assets, &options, seed, 0);
 
     // HUD
     commands.spawn((
@@ -162,6 +169,52 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 }
 
+/// Spawns the tiles and player of vault `level` of `seed`'s run.
+fn spawn_level(
+    commands: &mut Commands,
+    pixar_assets: &PixarAssets,
+    options: &GameOptions,
+    seed: Option<u64>,
+    level: usize,
+) {
+    let (layout, (sx, sy)) = level_layout(seed, level);
+    for (gx, gy, kind) in &layout {
+        spawn_tile(commands, pixar_assets, *gx, *gy, *kind);
+    }
+
+    // Player (explorer)
+    let player_config = CharacterConfig::hero(palette::HERO_ORANGE, Vec2::splat(TILE - 8.0));
+    cosmetics::spawn_hero(commands, pixar_assets, options, &player_config, world_pos(sx, sy) + Vec3::Z, (
+        Player { gx: sx, gy: sy, keys: [false; 3] },
+        MoveTo::at(world_pos(sx, sy) + Vec3::Z),
+        GameEntity,
+    ));
+}
+
+/// The tiles and player start of vault `level` of `seed`'s run, or the
+/// handcrafted vault without a seed (or if generation gives up).
+fn level_layout(seed: Option<u64>, level: usize) -> (Vec<(i32, i32, TileKind)>, (i32, i32)) {
+    let generated = seed.and_then(|seed| vault::generate(&mut LevelRng::new(seed, level), COLS, ROWS, level));
+    let Some(generated) = generated else { return (build_level(), (1, 1)) };
+    let tiles = generated
+        .tiles
+        .into_iter()
+        .map(|(x, y, tile)| {
+            let kind = match tile {
+                vault::Tile::Wall => TileKind::Wall,
+                vault::Tile::Floor => TileKind::Floor,
+                vault::Tile::Key(i) => TileKind::Key(i),
+                vault::Tile::Door(i) => TileKind::Door(i),
+                vault::Tile::Trap(i) => TileKind::Trap(i),
+                vault::Tile::Switch(i) => TileKind::Switch(i),
+                vault::Tile::Exit => TileKind::Exit,
+            };
+            (x, y, kind)
+        })
+        .collect();
+    (tiles, generated.start)
+}
+
 fn build_level() -> Vec<(i32, i32, TileKind)> {
     let mut tiles = Vec::new();
     for y in 0..ROWS {
@@ -216,22 +269,28 @@ fn build_level() -> Vec<(i32, i32, TileKind)> {
 // Systems
 // ---------------------------------------------------------------------------
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
     mut pq: Query<(&mut Player, &mut Transform)>,
     mut tq: Query<(&mut Tile, &mut Sprite, Entity)>,
-    _commands: Commands,
+    mut commands: Commands,
+    entities: Query<Entity, With<GameEntity>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    pixar_assets: Res<PixarAssets>,
+    options: Res<GameOptions>,
 ) {
     state.move_cooldown -= time.delta_secs();
     if state.move_cooldown > 0.0 { return; }
 
-    let (dx, dy) = if keys.just_pressed(KeyCode::ArrowUp) { (0, 1) }
-        else if keys.just_pressed(KeyCode::ArrowDown) { (0, -1) }
-        else if keys.just_pressed(KeyCode::ArrowLeft) { (-1, 0) }
-        else if keys.just_pressed(KeyCode::ArrowRight) { (1, 0) }
+    let (dx, dy) = if input.just_pressed(Control::MoveUp) { (0, 1) }
+        else if input.just_pressed(Control::MoveDown) { (0, -1) }
+        else if input.just_pressed(Control::MoveLeft) { (-1, 0) }
+        else if input.just_pressed(Control::MoveRight) { (1, 0) }
         else { return; };
 
     let Ok((mut player, _ptf)) = pq.get_single_mut() else { return };
@@ -283,6 +342,7 @@ pub fn player_input(
             }
         }
         TileKind::Trap(_) if active => {
+            failures.send(Failure(world_pos(nx, ny).truncate()));
             next_state.set(crate::AppState::GameOver);
             return;
         }
@@ -296,41 +356,65 @@ pub fn player_input(
         }
         TileKind::Exit => {
             state.score += 500;
-            next_state.set(crate::AppState::GameOver);
+            let Some(seed) = state.seed else {
+                next_state.set(crate::AppState::GameOver);
+                return;
+            };
+            // Seeded runs go on to the next vault.
+            analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
+            state.level += 1;
+            for e in &entities { commands.entity(e).despawn(); }
+            commands.spawn((
+                Sprite { color: palette::NIGHT_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+                Transform::from_xyz(0.0, 0.0, -1.0),
+                GameEntity,
+            ));
+            spawn_level(&mut commands, &pixar_assets, &options, Some(seed), state.level);
+            commands.spawn((
+                Text::new(""),
+                TextFont { font_size: 20.0, ..default() },
+                TextColor(Color::srgb(0.9, 0.85, 0.3)),
+                Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+                ScoreText,
+                GameEntity,
+            ));
         }
         _ => {}
     }
 }
 
 pub fn update_visuals(
-    pq: Query<&Player>,
-    mut tq: Query<(&Tile, &mut Transform), Without<Player>>,
-    mut ptf_q: Query<&mut Transform, With<Player>>,
+    mut pq: Query<(&Player, &mut MoveTo), Without<Tile>>,
+    mut tq: Query<(&Tile, &mut MoveTo), Without<Player>>,
 ) {
-    let Ok(player) = pq.get_single() else { return };
-    if let Ok(mut ptf) = ptf_q.get_single_mut() {
-        let pos = world_pos(player.gx, player.gy);
-        ptf.translation.x = pos.x;
-        ptf.translation.y = pos.y;
+    if let Ok((player, mut mv)) = pq.get_single_mut() {
+        mv.go(world_pos(player.gx, player.gy) + Vec3::Z);
     }
-    for (tile, mut tf) in &mut tq {
-        let pos = world_pos(tile.gx, tile.gy);
-        tf.translation.x = pos.x;
-        tf.translation.y = pos.y;
+    for (tile, mut mv) in &mut tq {
+        mv.go(world_pos(tile.gx, tile.gy));
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, pq: Query<&Player>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    if state.seed.is_some() {
+        bridge.stats.level = Some(state.level as i32 + 1);
+    }
+    if let Ok(player) = pq.get_single() {
+        bridge.stats.collectibles = Some(player.keys.iter().filter(|k| **k).count() as i32);
+    }
 }
 
-pub fn update_hud(pq: Query<&Player>, mut q: Query<&mut Text, With<ScoreText>>) {
+pub fn update_hud(state: Res<GameState>, pq: Query<&Player>, mut q: Query<&mut Text, With<ScoreText>>) {
     let Ok(player) = pq.get_single() else { return };
     let r = if player.keys[0] { "R" } else { "-" };
     let g = if player.keys[1] { "G" } else { "-" };
     let b = if player.keys[2] { "B" } else { "-" };
     for mut t in &mut q {
-        **t = format!("Keys: {}{}{} | Arrows to move", r, g, b);
+        **t = match state.seed {
+            Some(seed) => format!("Vault {} | Keys: {}{}{} | Seed {}", state.level + 1, r, g, b, seed),
+            None => format!("Keys: {}{}{} | Arrows to move", r, g, b),
+        };
     }
 }
 
@@ -1,7 +1,13 @@
 use bevy::prelude::*;
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::persistence::{self, SavedGame};
+use crate::levelgen::{self, orb_drop, LevelRng};
+use crate::tween::MoveTo;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -12,6 +18,8 @@ const ROWS: i32 = 8;
 const TILE: f32 = 56.0;
 const ORIGIN_X: f32 = -((COLS as f32) * TILE) / 2.0 + TILE / 2.0;
 const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0;
+const GAME_ID: &str = "hydro_logic_puzzles";
+const LEVELS: usize = 3;
 
 // ---------------------------------------------------------------------------
 // Components
@@ -21,26 +29,28 @@ const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { gx: i32, gy: i32 }
+pub struct Player { gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct Orb { gx: i32, gy: i32 }
+pub struct Orb { gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct Target { gx: i32, gy: i32 }
+pub struct Target { gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct Wall { gx: i32, gy: i32 }
+pub struct Wall { gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     level: usize,
     moves: i32,
     cooldown: f32,
+    /// Set for an endless run of generated levels.
+    seed: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -90,8 +100,34 @@ fn get_level(n: usize) -> LevelData {
     }
 }
 
-fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize) {
-    let data = get_level(level);
+/// Level `n` of `seed`'s run, or the handcrafted level `n` without a seed
+/// (or if generation gives up).
+fn level_data(seed: Option<u64>, n: usize) -> LevelData {
+    let generated = seed.and_then(|seed| orb_drop::generate(&mut LevelRng::new(seed, n), COLS, ROWS, n));
+    let Some(level) = generated else { return get_level(n % LEVELS) };
+    LevelData { walls: level.walls, orbs: level.orbs, targets: level.targets, player: level.player }
+}
+
+/// Spawns `level` of `seed`'s run, placing the player and orbs where
+/// `restore` left them if given.
+fn spawn_level(
+    commands: &mut Commands,
+    pixar_assets: &PixarAssets,
+    options: &GameOptions,
+    seed: Option<u64>,
+    level: usize,
+    restore: Option<&SavedGame>,
+) {
+    let mut data = level_data(seed, level);
+    if let Some(saved) = restore {
+        if let Some(grid) = saved.grid {
+            data.player = grid;
+        }
+        let orbs: Vec<(i32, i32)> = serde_json::from_value(saved.extra["orbs"].clone()).unwrap_or_default();
+        if orbs.len() == data.orbs.len() {
+            data.orbs = orbs;
+        }
+    }
 
     // Floor background
     for y in 0..ROWS {
@@ -126,6 +162,7 @@ fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize
         let config = CharacterConfig::blob(palette::HERO_BLUE, TILE - 12.0);
         pixar::spawn_character(commands, pixar_assets, &config, wp(gx, gy, 0.5), (
             Orb { gx, gy },
+            MoveTo::at(wp(gx, gy, 0.5)),
             GameEntity,
         ));
     }
@@ -133,8 +170,9 @@ fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize
     // Player (hero style)
     let (px, py) = data.player;
     let config = CharacterConfig::hero(palette::HERO_YELLOW, Vec2::splat(TILE - 14.0));
-    pixar::spawn_character(commands, pixar_assets, &config, wp(px, py, 1.0), (
+    cosmetics::spawn_hero(commands, pixar_assets, options, &config, wp(px, py, 1.0), (
         Player { gx: px, gy: py },
+        MoveTo::at(wp(px, py, 1.0)),
         GameEntity,
     ));
 }
@@ -143,8 +181,17 @@ fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, level: 0, moves: 0, cooldown: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    let restore = persistence::resume_data(&options, GAME_ID);
+    let seed = levelgen::seed(&options);
+    let last_level = if seed.is_some() { usize::MAX } else { LEVELS - 1 };
+    let (score, level, moves) = restore.as_ref().map_or((0, 0, 0), |s| (s.score, s.level.min(last_level), s.moves));
+    commands.insert_resource(GameState { score, level, moves, cooldown: 0.0, seed });
 
     // Background
     if let Some(ref bg_handle) = custom_assets.background {
@@ -161,7 +208,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         ));
     }
 
-    spawn_level(&mut commands, &pixar_assets, 0);
+    spawn_level(&mut commands, &pixar_assets, &options, seed, level, restore.as_ref());
 
     // HUD
     commands.spawn((
@@ -179,7 +226,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
     mut pq: Query<&mut Player>,
@@ -189,10 +236,10 @@ pub fn player_input(
     state.cooldown -= time.delta_secs();
     if state.cooldown > 0.0 { return; }
 
-    let (dx, dy) = if input.just_pressed(KeyCode::ArrowUp) { (0, 1) }
-        else if input.just_pressed(KeyCode::ArrowDown) { (0, -1) }
-        else if input.just_pressed(KeyCode::ArrowLeft) { (-1, 0) }
-        else if input.just_pressed(KeyCode::ArrowRight) { (1, 0) }
+    let (dx, dy) = if input.just_pressed(Control::MoveUp) { (0, 1) }
+        else if input.just_pressed(Control::MoveDown) { (0, -1) }
+        else if input.just_pressed(Control::MoveLeft) { (-1, 0) }
+        else if input.just_pressed(Control::MoveRight) { (1, 0) }
         else { return; };
 
     let Ok(mut player) = pq.get_single_mut() else { return };
@@ -255,6 +302,8 @@ pub fn check_win(
     entities: Query<Entity, With<GameEntity>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
     pixar_assets: Res<PixarAssets>,
+    options: Res<GameOptions>,
+    mut analytics: EventWriter<AnalyticsEvent>,
 ) {
     let all_on_target = tq.iter().all(|target| {
         oq.iter().any(|orb| orb.gx == target.gx && orb.gy == target.gy)
@@ -262,10 +311,11 @@ pub fn check_win(
     if !all_on_target { return; }
 
     state.score += 500;
+    analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
     state.level += 1;
     state.moves = 0;
 
-    if state.level >= 3 {
+    if state.seed.is_none() && state.level >= LEVELS {
         next_state.set(crate::AppState::GameOver);
         return;
     }
@@ -280,7 +330,7 @@ pub fn check_win(
         GameEntity,
     ));
 
-    spawn_level(&mut commands, &pixar_assets, state.level);
+    spawn_level(&mut commands, &pixar_assets, &options, state.seed, state.level, None);
 
     // Re-spawn HUD
     commands.spawn((
@@ -294,28 +344,54 @@ pub fn check_win(
 }
 
 pub fn update_visuals(
-    pq: Query<&Player>,
-    mut ptf: Query<&mut Transform, With<Player>>,
-    mut otf: Query<(&Orb, &mut Transform), Without<Player>>,
+    mut pq: Query<(&Player, &mut MoveTo), Without<Orb>>,
+    mut oq: Query<(&Orb, &mut MoveTo), Without<Player>>,
 ) {
-    if let Ok(p) = pq.get_single() {
-        if let Ok(mut tf) = ptf.get_single_mut() {
-            let pos = wp(p.gx, p.gy, 1.0);
-            tf.translation = pos;
-        }
+    if let Ok((p, mut mv)) = pq.get_single_mut() {
+        mv.go(wp(p.gx, p.gy, 1.0));
     }
-    for (orb, mut tf) in &mut otf {
-        tf.translation = wp(orb.gx, orb.gy, 0.5);
+    for (orb, mut mv) in &mut oq {
+        let to = wp(orb.gx, orb.gy, 0.5);
+        // Orbs only go down by dropping.
+        if to.y < mv.target().y {
+            mv.fall(to);
+        } else {
+            mv.go(to);
+        }
     }
 }
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    let level = if state.seed.is_some() { state.level } else { state.level.min(LEVELS - 1) };
+    bridge.stats.level = Some(level as i32 + 1);
+}
+
+pub fn save_progress(
+    state: Res<GameState>,
+    bridge: Res<BevyBridge>,
+    pq: Query<&Player>,
+    oq: Query<&Orb>,
+) {
+    if bridge.game_id != GAME_ID { return; }
+    let Ok(player) = pq.get_single() else { return };
+    let orbs: Vec<(i32, i32)> = oq.iter().map(|o| (o.gx, o.gy)).collect();
+    persistence::save(&SavedGame {
+        game_id: GAME_ID.to_string(),
+        level: state.level,
+        score: state.score,
+        moves: state.moves,
+        grid: Some((player.gx, player.gy)),
+        extra: serde_json::json!({ "orbs": orbs, "seed": state.seed }),
+    });
 }
 
 pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
     for mut t in &mut q {
         **t = format!("Level {} | Moves: {} | Score: {}", state.level + 1, state.moves, state.score);
+        if let Some(seed) = state.seed {
+            t.push_str(&format!(" | Seed {}", seed));
+        }
     }
 }
 
@@ -1,9 +1,13 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::input::{GameAction, Rumble};
+use crate::perf::Quality;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -31,32 +35,37 @@ const MAX_HP: i32 = 3;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { vy: f32, on_ground: bool }
+pub struct Player { vy: f32, on_ground: bool }
 
 #[derive(Component)]
-struct Enemy;
+pub struct Enemy;
 
 #[derive(Component)]
-struct Bullet;
+pub struct Bullet;
 
 #[derive(Component)]
 struct GroundTile;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct HpText;
+pub struct HpText;
 
 #[derive(Resource)]
-struct GameState { score: i32, hp: i32, spawn_timer: f32, distance: f32 }
+pub struct GameState { score: i32, hp: i32, spawn_timer: f32, distance: f32, shots: i32, hits: i32 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, hp: MAX_HP, spawn_timer: 0.0, distance: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState { score: 0, hp: MAX_HP, spawn_timer: 0.0, distance: 0.0, shots: 0, hits: 0 });
 
     // Background
     let bg_sprite = if let Some(ref bg) = custom_assets.background {
@@ -73,9 +82,10 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     ));
 
     // Player
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_GREEN, PLAYER_SIZE),
         Vec3::new(PLAYER_X, GROUND_Y + PLAYER_SIZE.y / 2.0, 1.0),
         (Player { vy: 0.0, on_ground: true }, GameEntity),
@@ -103,27 +113,30 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    touches: Res<Touches>,
+    mut actions: EventReader<GameAction>,
     mut pq: Query<(&Transform, &mut Player)>,
+    mut state: ResMut<GameState>,
     mut commands: Commands,
 ) {
     let Ok((tf, mut p)) = pq.get_single_mut() else { return };
+    let (mut jump, mut shoot) = (false, false);
+    for action in actions.read() {
+        match action {
+            GameAction::Jump => jump = true,
+            GameAction::Fire | GameAction::Pointer => shoot = true,
+            GameAction::Pause => {}
+        }
+    }
 
     // Jump
-    let jump = keys.just_pressed(KeyCode::Space)
-        || keys.just_pressed(KeyCode::ArrowUp);
     if jump && p.on_ground {
         p.vy = JUMP_VEL;
         p.on_ground = false;
     }
 
     // Shoot
-    let shoot = mouse.just_pressed(MouseButton::Left)
-        || touches.any_just_pressed()
-        || keys.just_pressed(KeyCode::KeyF);
     if shoot {
+        state.shots += 1;
         commands.spawn((
             Sprite { color: palette::HERO_YELLOW, custom_size: Some(BULLET_SIZE), ..default() },
             Transform::from_xyz(tf.translation.x + 20.0, tf.translation.y, 0.5),
@@ -158,9 +171,9 @@ pub fn move_bullets(
     }
 }
 
-pub fn spawn_enemies(time: Res<Time>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
+pub fn spawn_enemies(time: Res<Time>, quality: Res<Quality>, mut state: ResMut<GameState>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
     state.spawn_timer += time.delta_secs();
-    if state.spawn_timer < SPAWN_INTERVAL { return; }
+    if state.spawn_timer < SPAWN_INTERVAL * quality.spawn_interval_scale() { return; }
     state.spawn_timer = 0.0;
     let mut rng = rand::thread_rng();
     let y = GROUND_Y + ENEMY_SIZE.y / 2.0 + rng.gen_range(0.0..80.0);
@@ -185,6 +198,7 @@ pub fn move_enemies(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn check_collisions(
     mut commands: Commands,
     mut state: ResMut<GameState>,
@@ -192,6 +206,8 @@ pub fn check_collisions(
     pq: Query<&Transform, With<Player>>,
     eq: Query<(Entity, &Transform), (With<Enemy>, Without<Bullet>)>,
     bq: Query<(Entity, &Transform), With<Bullet>>,
+    mut rumble: EventWriter<Rumble>,
+    mut failures: EventWriter<Failure>,
 ) {
     let Ok(ptf) = pq.get_single() else { return };
 
@@ -204,6 +220,8 @@ pub fn check_collisions(
                 commands.entity(be).despawn();
                 commands.entity(ee).despawn();
                 state.score += 30;
+                state.hits += 1;
+                rumble.send(Rumble::light());
                 break;
             }
         }
@@ -215,7 +233,9 @@ pub fn check_collisions(
         let dy = (ptf.translation.y - etf.translation.y).abs();
         if dx < (PLAYER_SIZE.x + ENEMY_SIZE.x) / 2.0 && dy < (PLAYER_SIZE.y + ENEMY_SIZE.y) / 2.0 {
             commands.entity(ee).despawn();
+            rumble.send(Rumble::impact());
             state.hp -= 1;
+            failures.send(Failure(ptf.translation.truncate()));
             if state.hp <= 0 { next_state.set(crate::AppState::GameOver); return; }
         }
     }
@@ -227,6 +247,9 @@ pub fn advance_distance(time: Res<Time>, mut state: ResMut<GameState>) {
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.distance = GameStats::metres(state.distance);
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    bridge.stats.deaths = Some(MAX_HP - state.hp.max(0));
 }
 
 pub fn update_hud(
@@ -1,7 +1,13 @@
 use bevy::prelude::*;
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions};
+use crate::input::{ActionState, Control};
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
+use crate::persistence::{self, SavedGame};
+use crate::levelgen::{self, rolling_block, LevelRng};
+use crate::tween::MoveTo;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -12,6 +18,8 @@ const ROWS: i32 = 7;
 const TILE: f32 = 60.0;
 const ORIGIN_X: f32 = -((COLS as f32) * TILE) / 2.0 + TILE / 2.0;
 const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0;
+const GAME_ID: &str = "logicrons_grid_shift";
+const LEVELS: usize = 3;
 
 // ---------------------------------------------------------------------------
 // Components
@@ -24,26 +32,28 @@ pub struct GameEntity;
 enum BlockState { Standing, LyingH, LyingV }
 
 #[derive(Component)]
-struct Block { state: BlockState, gx: i32, gy: i32 }
+pub struct Block { state: BlockState, gx: i32, gy: i32 }
 
 #[derive(Clone, Copy, PartialEq)]
 enum FloorKind { Solid, Void, Goal }
 
 #[derive(Component)]
-struct FloorTile { kind: FloorKind, gx: i32, gy: i32 }
+pub struct FloorTile { kind: FloorKind, gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct BlockVisual(usize); // 0 or 1 for the two halves
+pub struct BlockVisual(usize); // 0 or 1 for the two halves
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     level: usize,
     moves: i32,
     cooldown: f32,
+    /// Set for an endless run of generated levels.
+    seed: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -101,6 +111,23 @@ fn get_level(n: usize) -> LevelData {
     LevelData { floor, start }
 }
 
+/// Level `n` of `seed`'s run, or the handcrafted level `n` without a seed
+/// (or if generation gives up).
+fn level_data(seed: Option<u64>, n: usize) -> LevelData {
+    let generated = seed.and_then(|seed| rolling_block::generate(&mut LevelRng::new(seed, n), COLS, ROWS, n));
+    let Some(level) = generated else { return get_level(n % LEVELS) };
+    let mut floor = Vec::new();
+    for y in 0..ROWS {
+        for x in 0..COLS {
+            let kind = if (x, y) == level.goal { FloorKind::Goal }
+                else if level.voids.contains(&(x, y)) { FloorKind::Void }
+                else { FloorKind::Solid };
+            floor.push((x, y, kind));
+        }
+    }
+    LevelData { floor, start: (level.start.0, level.start.1, BlockState::Standing) }
+}
+
 fn floor_color(kind: FloorKind) -> Color {
     match kind {
         FloorKind::Solid => palette::SHADOW,
@@ -109,8 +136,39 @@ fn floor_color(kind: FloorKind) -> Color {
     }
 }
 
-fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize) {
-    let data = get_level(level);
+fn block_state_name(state: BlockState) -> &'static str {
+    match state {
+        BlockState::Standing => "standing",
+        BlockState::LyingH => "lying_h",
+        BlockState::LyingV => "lying_v",
+    }
+}
+
+fn block_state_from_name(name: &str) -> Option<BlockState> {
+    match name {
+        "standing" => Some(BlockState::Standing),
+        "lying_h" => Some(BlockState::LyingH),
+        "lying_v" => Some(BlockState::LyingV),
+        _ => None,
+    }
+}
+
+/// Spawns `level` of `seed`'s run, placing the block where `restore` left
+/// it if given.
+fn spawn_level(
+    commands: &mut Commands,
+    pixar_assets: &PixarAssets,
+    seed: Option<u64>,
+    level: usize,
+    restore: Option<&SavedGame>,
+) {
+    let mut data = level_data(seed, level);
+    if let Some(saved) = restore {
+        if let Some((gx, gy)) = saved.grid {
+            let state = saved.extra["block"].as_str().and_then(block_state_from_name).unwrap_or(BlockState::Standing);
+            data.start = (gx, gy, state);
+        }
+    }
 
     for &(gx, gy, kind) in &data.floor {
         let color = floor_color(kind);
@@ -128,12 +186,14 @@ fn spawn_level(commands: &mut Commands, pixar_assets: &PixarAssets, level: usize
     pixar::spawn_character(commands, pixar_assets, &config, wp(sx, sy, 1.0), (
         Block { state: ss, gx: sx, gy: sy },
         BlockVisual(0),
+        MoveTo::at(wp(sx, sy, 1.0)),
         GameEntity,
     ));
     // Second visual half (only visible when lying)
     let config2 = CharacterConfig::prop(palette::HERO_BLUE, Vec2::splat(TILE - 6.0), true);
     pixar::spawn_character(commands, pixar_assets, &config2, wp(sx, sy, 1.0), (
         BlockVisual(1),
+        MoveTo::at(wp(sx, sy, 1.0)),
         GameEntity,
     ));
 }
@@ -155,8 +215,17 @@ fn tile_is_safe(gx: i32, gy: i32, fq: &Query<&FloorTile>) -> bool {
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, level: 0, moves: 0, cooldown: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    let restore = persistence::resume_data(&options, GAME_ID);
+    let seed = levelgen::seed(&options);
+    let last_level = if seed.is_some() { usize::MAX } else { LEVELS - 1 };
+    let (score, level, moves) = restore.as_ref().map_or((0, 0, 0), |s| (s.score, s.level.min(last_level), s.moves));
+    commands.insert_resource(GameState { score, level, moves, cooldown: 0.0, seed });
 
     // Background
     if let Some(ref bg_handle) = custom_assets.background {
@@ -173,7 +242,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         ));
     }
 
-    spawn_level(&mut commands, &pixar_assets, 0);
+    spawn_level(&mut commands, &pixar_assets, seed, level, restore.as_ref());
 
     commands.spawn((
         Text::new("Level 1 | Moves: 0"),
@@ -190,7 +259,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut state: ResMut<GameState>,
     mut bq: Query<&mut Block>,
@@ -199,14 +268,16 @@ pub fn player_input(
     entities: Query<Entity, With<GameEntity>>,
     mut next_state: ResMut<NextState<crate::AppState>>,
     pixar_assets: Res<PixarAssets>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut failures: EventWriter<Failure>,
 ) {
     state.cooldown -= time.delta_secs();
     if state.cooldown > 0.0 { return; }
 
-    let (dx, dy) = if input.just_pressed(KeyCode::ArrowRight) { (1, 0) }
-        else if input.just_pressed(KeyCode::ArrowLeft) { (-1, 0) }
-        else if input.just_pressed(KeyCode::ArrowUp) { (0, 1) }
-        else if input.just_pressed(KeyCode::ArrowDown) { (0, -1) }
+    let (dx, dy) = if input.just_pressed(Control::MoveRight) { (1, 0) }
+        else if input.just_pressed(Control::MoveLeft) { (-1, 0) }
+        else if input.just_pressed(Control::MoveUp) { (0, 1) }
+        else if input.just_pressed(Control::MoveDown) { (0, -1) }
         else { return; };
 
     let Ok(mut block) = bq.get_single_mut() else { return };
@@ -238,6 +309,7 @@ pub fn player_input(
     let fell = tiles.iter().any(|&(tx, ty)| !tile_is_safe(tx, ty, &fq));
 
     if fell {
+        failures.send(Failure(wp(block.gx, block.gy, 0.0).truncate()));
         // Reset level
         state.moves = 0;
         for e in &entities { commands.entity(e).despawn(); }
@@ -246,7 +318,7 @@ pub fn player_input(
             Transform::from_xyz(0.0, 0.0, -1.0),
             GameEntity,
         ));
-        spawn_level(&mut commands, &pixar_assets, state.level);
+        spawn_level(&mut commands, &pixar_assets, state.seed, state.level, None);
         commands.spawn((
             Text::new(""),
             TextFont { font_size: 20.0, ..default() },
@@ -262,9 +334,10 @@ pub fn player_input(
         let on_goal = fq.iter().any(|f| f.gx == block.gx && f.gy == block.gy && f.kind == FloorKind::Goal);
         if on_goal {
             state.score += 500;
+            analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
             state.level += 1;
             state.moves = 0;
-            if state.level >= 3 {
+            if state.seed.is_none() && state.level >= LEVELS {
                 next_state.set(crate::AppState::GameOver);
                 return;
             }
@@ -274,7 +347,7 @@ pub fn player_input(
                 Transform::from_xyz(0.0, 0.0, -1.0),
                 GameEntity,
             ));
-            spawn_level(&mut commands, &pixar_assets, state.level);
+            spawn_level(&mut commands, &pixar_assets, state.seed, state.level, None);
             commands.spawn((
                 Text::new(""),
                 TextFont { font_size: 20.0, ..default() },
@@ -286,30 +359,47 @@ pub fn player_input(
     }
 }
 
+/// Every roll tips the block onto the floor, so the halves move as falls
+/// and squash as they land.  The hidden half of a standing block follows
+/// the visible one, so it unfolds out of it when the block tips over.
 pub fn update_visuals(
     bq: Query<&Block>,
-    mut vq: Query<(&BlockVisual, &mut Transform, &mut Visibility)>,
+    mut vq: Query<(&BlockVisual, &mut MoveTo, &mut Visibility)>,
 ) {
     let Ok(block) = bq.get_single() else { return };
     let tiles = block_tiles(block);
-    for (vis, mut tf, mut visibility) in &mut vq {
-        if vis.0 < tiles.len() {
-            *visibility = Visibility::Visible;
-            let (tx, ty) = tiles[vis.0];
-            tf.translation = wp(tx, ty, 1.0);
-        } else {
-            *visibility = Visibility::Hidden;
-        }
+    for (vis, mut mv, mut visibility) in &mut vq {
+        let (tx, ty) = tiles.get(vis.0).copied().unwrap_or(tiles[0]);
+        mv.fall(wp(tx, ty, 1.0));
+        *visibility = if vis.0 < tiles.len() { Visibility::Visible } else { Visibility::Hidden };
     }
 }
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    let level = if state.seed.is_some() { state.level } else { state.level.min(LEVELS - 1) };
+    bridge.stats.level = Some(level as i32 + 1);
+}
+
+pub fn save_progress(state: Res<GameState>, bridge: Res<BevyBridge>, bq: Query<&Block>) {
+    if bridge.game_id != GAME_ID { return; }
+    let Ok(block) = bq.get_single() else { return };
+    persistence::save(&SavedGame {
+        game_id: GAME_ID.to_string(),
+        level: state.level,
+        score: state.score,
+        moves: state.moves,
+        grid: Some((block.gx, block.gy)),
+        extra: serde_json::json!({ "block": block_state_name(block.state), "seed": state.seed }),
+    });
 }
 
 pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
     for mut t in &mut q {
         **t = format!("Level {} | Moves: {} | Score: {}", state.level + 1, state.moves, state.score);
+        if let Some(seed) = state.seed {
+            t.push_str(&format!(" | Seed {}", seed));
+        }
     }
 }
 
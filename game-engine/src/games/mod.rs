@@ -1,18 +1,26 @@
 pub mod aero_engineering;
+pub mod boss;
+pub mod binary_blitz;
+pub mod bridge_builder;
 pub mod cable_car_conundrum;
 pub mod campus_dash;
 pub mod campus_guard;
 pub mod chemistry_escape;
+pub mod code_runner;
 pub mod color_lab_quest;
 pub mod demo_day;
 pub mod drone_defense;
+pub mod element_match;
 pub mod find_the_principal;
 pub mod formula_stem;
 pub mod geology_deep_dive;
 pub mod gravity_shift_run;
+pub mod grid;
 pub mod heavy_gear_delivery;
 pub mod lab_breach;
 pub mod parkour_lab;
+pub mod power_grid;
+pub mod rover_3d_expedition;
 pub mod rover_field_test;
 pub mod safety_first_defense;
 pub mod stem_celebration;
@@ -20,33 +28,78 @@ pub mod history_vault_escape;
 pub mod hydro_logic_puzzles;
 pub mod logicrons_grid_shift;
 pub mod molecular_split;
+pub mod outbreak_control;
 pub mod physics_master_billiards;
 pub mod robot_repair_bay;
 pub mod stem_project_volley;
 
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::ai::load_bot;
+use crate::powerups;
+use crate::remote_config::load_config;
+use crate::spectator::is_live;
+use crate::ui::menu::PauseState;
+use crate::{AppState, BevyBridge};
+
+/// `Update` systems that advance a running game.  Runs only while a game is
+/// `Playing`, the pause menu is closed and the run isn't being spectated.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// [`GameplaySet`] for `FixedUpdate`: the simulation of physics-driven
+/// games, stepped at a fixed rate (see [`timestep`](crate::timestep)).
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FixedGameplaySet;
+
+/// Run condition: `game_id` is the game being played.  3-D games need it
+/// (see [`scene3d`](crate::scene3d)).
+pub fn playing(game_id: &'static str) -> impl Fn(Res<BevyBridge>) -> bool + Clone {
+    move |bridge: Res<BevyBridge>| bridge.game_id == game_id
+}
 
 /// Plugin that registers all game systems with the Bevy app.
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        app.configure_sets(
+            Update,
+            GameplaySet
+                .run_if(in_state(PauseState::Running))
+                .run_if(is_live),
+        )
+        .configure_sets(
+            FixedUpdate,
+            FixedGameplaySet
+                .run_if(in_state(PauseState::Running))
+                .run_if(is_live),
+        );
+
         // -- campus_dash ---------------------------------------------------
-        app.add_systems(OnEnter(AppState::Playing), campus_dash::setup)
+        app.add_systems(OnEnter(AppState::Playing), campus_dash::setup.after(load_config))
             .add_systems(
                 Update,
                 (
                     campus_dash::player_input,
+                    powerups::collect_pickups::<campus_dash::GameEntity>,
+                    powerups::update_hud::<campus_dash::GameEntity>,
+                    campus_dash::update_score,
+                    campus_dash::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     campus_dash::player_physics,
                     campus_dash::scroll_world,
+                    powerups::magnet_pickups::<campus_dash::GameEntity>,
                     campus_dash::spawn_obstacles,
                     campus_dash::check_collisions,
-                    campus_dash::update_score,
-                    campus_dash::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), campus_dash::cleanup);
 
@@ -60,10 +113,14 @@ impl Plugin for GamePlugin {
                     aero_engineering::spawn_enemies,
                     aero_engineering::move_enemies,
                     aero_engineering::check_collisions,
+                    aero_engineering::boss_collisions,
+                    boss::update_bosses::<aero_engineering::GameEntity>,
+                    boss::move_shots::<aero_engineering::GameEntity>,
+                    boss::update_health_bar::<aero_engineering::GameEntity>,
                     aero_engineering::update_score,
                     aero_engineering::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), aero_engineering::cleanup);
 
@@ -81,12 +138,12 @@ impl Plugin for GamePlugin {
                     campus_guard::update_score,
                     campus_guard::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), campus_guard::cleanup);
 
         // -- drone_defense --------------------------------------------------
-        app.add_systems(OnEnter(AppState::Playing), drone_defense::setup)
+        app.add_systems(OnEnter(AppState::Playing), drone_defense::setup.after(load_config))
             .add_systems(
                 Update,
                 (
@@ -95,11 +152,18 @@ impl Plugin for GamePlugin {
                     drone_defense::spawn_enemies,
                     drone_defense::move_enemies,
                     drone_defense::check_collisions,
+                    drone_defense::boss_collisions,
+                    boss::update_bosses::<drone_defense::GameEntity>,
+                    boss::move_shots::<drone_defense::GameEntity>,
+                    boss::update_health_bar::<drone_defense::GameEntity>,
+                    powerups::collect_pickups::<drone_defense::GameEntity>,
+                    powerups::magnet_pickups::<drone_defense::GameEntity>,
+                    powerups::update_hud::<drone_defense::GameEntity>,
                     drone_defense::update_fuel_bar,
                     drone_defense::update_score,
                     drone_defense::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), drone_defense::cleanup);
 
@@ -109,14 +173,24 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     gravity_shift_run::player_input,
+                    powerups::collect_pickups::<gravity_shift_run::GameEntity>,
+                    powerups::update_hud::<gravity_shift_run::GameEntity>,
+                    gravity_shift_run::update_score,
+                    gravity_shift_run::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     gravity_shift_run::player_physics,
                     gravity_shift_run::scroll_world,
+                    powerups::magnet_pickups::<gravity_shift_run::GameEntity>,
                     gravity_shift_run::spawn_obstacles,
                     gravity_shift_run::check_collisions,
-                    gravity_shift_run::update_score,
-                    gravity_shift_run::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), gravity_shift_run::cleanup);
 
@@ -135,7 +209,7 @@ impl Plugin for GamePlugin {
                     lab_breach::update_score,
                     lab_breach::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), lab_breach::cleanup);
 
@@ -145,14 +219,21 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     parkour_lab::player_input,
+                    parkour_lab::update_score,
+                    parkour_lab::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     parkour_lab::player_physics,
                     parkour_lab::scroll_world,
                     parkour_lab::spawn_obstacles,
                     parkour_lab::check_collisions,
-                    parkour_lab::update_score,
-                    parkour_lab::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), parkour_lab::cleanup);
 
@@ -169,10 +250,32 @@ impl Plugin for GamePlugin {
                     rover_field_test::update_score,
                     rover_field_test::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), rover_field_test::cleanup);
 
+        // -- rover_3d_expedition ---------------------------------------------
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            rover_3d_expedition::setup.run_if(playing(rover_3d_expedition::GAME_ID)),
+        )
+        .add_systems(
+            Update,
+            (
+                rover_3d_expedition::drive,
+                rover_3d_expedition::follow_terrain,
+                rover_3d_expedition::collect_samples,
+                rover_3d_expedition::spin_samples,
+                rover_3d_expedition::check_game_over,
+                rover_3d_expedition::update_score,
+                rover_3d_expedition::update_hud,
+            )
+                .chain()
+                .in_set(GameplaySet)
+                .run_if(playing(rover_3d_expedition::GAME_ID)),
+        )
+        .add_systems(OnExit(AppState::Playing), rover_3d_expedition::cleanup);
+
         // -- heavy_gear_delivery ---------------------------------------------
         app.add_systems(OnEnter(AppState::Playing), heavy_gear_delivery::setup)
             .add_systems(
@@ -187,7 +290,7 @@ impl Plugin for GamePlugin {
                     heavy_gear_delivery::update_score,
                     heavy_gear_delivery::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), heavy_gear_delivery::cleanup);
 
@@ -201,29 +304,39 @@ impl Plugin for GamePlugin {
                     safety_first_defense::move_enemies,
                     safety_first_defense::move_bullets,
                     safety_first_defense::bullet_collisions,
+                    safety_first_defense::boss_collisions,
+                    boss::update_bosses::<safety_first_defense::GameEntity>,
+                    boss::move_shots::<safety_first_defense::GameEntity>,
+                    boss::update_health_bar::<safety_first_defense::GameEntity>,
                     safety_first_defense::enemy_reach_bottom,
                     safety_first_defense::check_game_over,
                     safety_first_defense::update_score,
                     safety_first_defense::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), safety_first_defense::cleanup);
 
         // -- stem_project_volley ---------------------------------------------
-        app.add_systems(OnEnter(AppState::Playing), stem_project_volley::setup)
+        app.add_systems(OnEnter(AppState::Playing), stem_project_volley::setup.after(load_bot))
             .add_systems(
                 Update,
                 (
                     stem_project_volley::player_fire,
                     stem_project_volley::ai_fire,
-                    stem_project_volley::move_projectiles,
-                    stem_project_volley::projectile_collisions,
+                    stem_project_volley::versus_fire,
+                    stem_project_volley::update_aim_lines,
                     stem_project_volley::check_game_over,
                     stem_project_volley::update_score,
                     stem_project_volley::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (stem_project_volley::move_projectiles, stem_project_volley::projectile_collisions)
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), stem_project_volley::cleanup);
 
@@ -240,7 +353,7 @@ impl Plugin for GamePlugin {
                     stem_celebration::update_score,
                     stem_celebration::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), stem_celebration::cleanup);
 
@@ -257,7 +370,7 @@ impl Plugin for GamePlugin {
                     cable_car_conundrum::update_score,
                     cable_car_conundrum::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), cable_car_conundrum::cleanup);
 
@@ -271,7 +384,7 @@ impl Plugin for GamePlugin {
                     chemistry_escape::update_score,
                     chemistry_escape::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), chemistry_escape::cleanup);
 
@@ -288,7 +401,7 @@ impl Plugin for GamePlugin {
                     color_lab_quest::update_score,
                     color_lab_quest::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), color_lab_quest::cleanup);
 
@@ -304,7 +417,7 @@ impl Plugin for GamePlugin {
                     demo_day::update_score,
                     demo_day::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), demo_day::cleanup);
 
@@ -320,12 +433,12 @@ impl Plugin for GamePlugin {
                     find_the_principal::update_score,
                     find_the_principal::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), find_the_principal::cleanup);
 
         // -- formula_stem ----------------------------------------------------
-        app.add_systems(OnEnter(AppState::Playing), formula_stem::setup)
+        app.add_systems(OnEnter(AppState::Playing), formula_stem::setup.after(load_bot))
             .add_systems(
                 Update,
                 (
@@ -335,7 +448,7 @@ impl Plugin for GamePlugin {
                     formula_stem::update_score,
                     formula_stem::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), formula_stem::cleanup);
 
@@ -349,7 +462,7 @@ impl Plugin for GamePlugin {
                     geology_deep_dive::update_score,
                     geology_deep_dive::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), geology_deep_dive::cleanup);
 
@@ -363,7 +476,7 @@ impl Plugin for GamePlugin {
                     history_vault_escape::update_score,
                     history_vault_escape::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), history_vault_escape::cleanup);
 
@@ -378,8 +491,9 @@ impl Plugin for GamePlugin {
                     hydro_logic_puzzles::update_visuals,
                     hydro_logic_puzzles::update_score,
                     hydro_logic_puzzles::update_hud,
+                    hydro_logic_puzzles::save_progress.run_if(crate::persistence::save_due),
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), hydro_logic_puzzles::cleanup);
 
@@ -392,8 +506,9 @@ impl Plugin for GamePlugin {
                     logicrons_grid_shift::update_visuals,
                     logicrons_grid_shift::update_score,
                     logicrons_grid_shift::update_hud,
+                    logicrons_grid_shift::save_progress.run_if(crate::persistence::save_due),
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), logicrons_grid_shift::cleanup);
 
@@ -403,32 +518,48 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     molecular_split::player_input,
+                    molecular_split::check_level_clear,
+                    molecular_split::update_score,
+                    molecular_split::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     molecular_split::move_harpoon,
                     molecular_split::move_molecules,
                     molecular_split::check_harpoon_hit,
                     molecular_split::check_player_hit,
-                    molecular_split::check_level_clear,
-                    molecular_split::update_score,
-                    molecular_split::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), molecular_split::cleanup);
 
         // -- physics_master_billiards ------------------------------------------
-        app.add_systems(OnEnter(AppState::Playing), physics_master_billiards::setup)
+        app.add_systems(OnEnter(AppState::Playing), physics_master_billiards::setup.after(load_bot))
             .add_systems(
                 Update,
                 (
                     physics_master_billiards::handle_input,
+                    physics_master_billiards::versus_shoot,
+                    physics_master_billiards::versus_turns,
                     physics_master_billiards::update_power_line,
+                    physics_master_billiards::update_score,
+                    physics_master_billiards::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
                     physics_master_billiards::physics,
                     physics_master_billiards::ball_collisions,
                     physics_master_billiards::check_pockets,
-                    physics_master_billiards::update_score,
-                    physics_master_billiards::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .chain()
+                    .in_set(FixedGameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), physics_master_billiards::cleanup);
 
@@ -444,42 +575,105 @@ impl Plugin for GamePlugin {
                     robot_repair_bay::update_score,
                     robot_repair_bay::update_hud,
                 )
-                    .run_if(in_state(AppState::Playing)),
+                    .in_set(GameplaySet),
             )
             .add_systems(OnExit(AppState::Playing), robot_repair_bay::cleanup);
 
-        // -- Game over UI ---------------------------------------------------
-        app.add_systems(OnEnter(AppState::GameOver), on_game_over)
-            .add_systems(OnExit(AppState::GameOver), cleanup_game_over);
-    }
-}
+        // -- code_runner -------------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), code_runner::setup)
+            .add_systems(
+                Update,
+                (
+                    code_runner::player_input,
+                    code_runner::run_program,
+                    code_runner::advance_level,
+                    code_runner::update_visuals,
+                    code_runner::update_score,
+                    code_runner::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), code_runner::cleanup);
 
-/// Marker for GameOver UI so we can despawn it on exit.
-#[derive(Component)]
-struct GameOverUI;
-
-fn on_game_over(mut commands: Commands, bridge: Res<crate::BevyBridge>) {
-    commands.spawn((
-        Text::new(format!("GAME OVER\nScore: {}", bridge.current_score)),
-        TextFont {
-            font_size: 48.0,
-            ..default()
-        },
-        TextColor(Color::srgb(0.9, 0.2, 0.2)),
-        TextLayout::new_with_justify(JustifyText::Center),
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Percent(35.0),
-            width: Val::Percent(100.0),
-            justify_content: JustifyContent::Center,
-            ..default()
-        },
-        GameOverUI,
-    ));
-}
+        // -- bridge_builder ----------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), bridge_builder::setup)
+            .add_systems(
+                Update,
+                (
+                    bridge_builder::player_input,
+                    bridge_builder::simulate,
+                    bridge_builder::finish_run,
+                    bridge_builder::update_visuals,
+                    bridge_builder::update_score,
+                    bridge_builder::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), bridge_builder::cleanup);
+
+        // -- outbreak_control --------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), outbreak_control::setup)
+            .add_systems(
+                Update,
+                (
+                    outbreak_control::player_input,
+                    outbreak_control::run_outbreak,
+                    outbreak_control::advance_level,
+                    outbreak_control::update_visuals,
+                    outbreak_control::update_score,
+                    outbreak_control::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), outbreak_control::cleanup);
 
-fn cleanup_game_over(mut commands: Commands, q: Query<Entity, With<GameOverUI>>) {
-    for e in &q {
-        commands.entity(e).despawn();
+        // -- binary_blitz ------------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), binary_blitz::setup)
+            .add_systems(
+                Update,
+                (
+                    binary_blitz::spawn_numbers,
+                    binary_blitz::move_numbers,
+                    binary_blitz::player_input,
+                    binary_blitz::missed_numbers,
+                    binary_blitz::check_game_over,
+                    binary_blitz::update_visuals,
+                    binary_blitz::update_score,
+                    binary_blitz::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), binary_blitz::cleanup);
+
+        // -- element_match -----------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), element_match::setup)
+            .add_systems(
+                Update,
+                (
+                    element_match::handle_click,
+                    element_match::advance,
+                    element_match::update_visuals,
+                    element_match::update_score,
+                    element_match::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), element_match::cleanup);
+
+        // -- power_grid --------------------------------------------------------
+        app.add_systems(OnEnter(AppState::Playing), power_grid::setup)
+            .add_systems(
+                Update,
+                (
+                    power_grid::handle_input,
+                    power_grid::simulate,
+                    power_grid::update_tiles,
+                    power_grid::update_city,
+                    power_grid::update_score,
+                    power_grid::update_hud,
+                )
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), power_grid::cleanup);
     }
 }
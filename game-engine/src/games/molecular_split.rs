@@ -1,9 +1,13 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::timestep::Interpolated;
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -19,6 +23,8 @@ const HARPOON_W: f32 = 4.0;
 const HARPOON_SPEED: f32 = 600.0;
 const MIN_RADIUS: f32 = 10.0;
 const SPLIT_RATIO: f32 = 0.6;
+const START_LIVES: i32 = 3;
+const LEVELS: usize = 5;
 
 // ---------------------------------------------------------------------------
 // Components
@@ -28,26 +34,28 @@ const SPLIT_RATIO: f32 = 0.6;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { x: f32 }
+pub struct Player { x: f32 }
 
 #[derive(Component)]
-struct Harpoon { active: bool }
+pub struct Harpoon { active: bool }
 
 #[derive(Component)]
-struct Molecule { radius: f32, vx: f32, vy: f32 }
+pub struct Molecule { radius: f32, vx: f32, vy: f32 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct LivesText;
+pub struct LivesText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     lives: i32,
     level: usize,
     invuln: f32,
+    shots: i32,
+    hits: i32,
 }
 
 // ---------------------------------------------------------------------------
@@ -76,6 +84,7 @@ fn spawn_molecules(commands: &mut Commands, pixar_assets: &PixarAssets, level: u
         let config = CharacterConfig::blob(color, r * 2.0);
         pixar::spawn_character(commands, pixar_assets, &config, Vec3::new(x, y, 0.5), (
             Molecule { radius: r, vx, vy },
+            Interpolated::default(),
             GameEntity,
         ));
     }
@@ -85,8 +94,13 @@ fn spawn_molecules(commands: &mut Commands, pixar_assets: &PixarAssets, level: u
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, lives: 3, level: 0, invuln: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState { score: 0, lives: START_LIVES, level: 0, invuln: 0.0, shots: 0, hits: 0 });
 
     // Background
     if let Some(ref bg_handle) = custom_assets.background {
@@ -118,7 +132,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 
     // Player (hero paddle)
     let player_config = CharacterConfig::hero(palette::HERO_GREEN, Vec2::new(PLAYER_W, PLAYER_H));
-    pixar::spawn_character(&mut commands, &pixar_assets, &player_config, Vec3::new(0.0, PLAYER_Y, 1.0), (
+    cosmetics::spawn_hero(&mut commands, &pixar_assets, &options, &player_config, Vec3::new(0.0, PLAYER_Y, 1.0), (
         Player { x: 0.0 },
         GameEntity,
     ));
@@ -147,26 +161,29 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     time: Res<Time>,
     mut pq: Query<(&mut Player, &mut Transform)>,
     mut commands: Commands,
     hq: Query<&Harpoon>,
+    mut state: ResMut<GameState>,
     pixar_assets: Res<PixarAssets>,
 ) {
     let dt = time.delta_secs();
     let Ok((mut player, mut tf)) = pq.get_single_mut() else { return };
 
-    if keys.pressed(KeyCode::ArrowLeft) { player.x -= PLAYER_SPEED * dt; }
-    if keys.pressed(KeyCode::ArrowRight) { player.x += PLAYER_SPEED * dt; }
+    if input.pressed(Control::MoveLeft) { player.x -= PLAYER_SPEED * dt; }
+    if input.pressed(Control::MoveRight) { player.x += PLAYER_SPEED * dt; }
     player.x = player.x.clamp(-HALF_W + PLAYER_W / 2.0, HALF_W - PLAYER_W / 2.0);
     tf.translation.x = player.x;
 
     // Fire harpoon (only one at a time)
-    if keys.just_pressed(KeyCode::Space) && hq.is_empty() {
+    if (input.just_pressed(Control::Fire) || input.just_pressed(Control::Action)) && hq.is_empty() {
+        state.shots += 1;
         let config = CharacterConfig::projectile(Color::WHITE, 12.0);
         pixar::spawn_character(&mut commands, &pixar_assets, &config, Vec3::new(player.x, PLAYER_Y + 15.0, 0.8), (
             Harpoon { active: true },
+            Interpolated::default(),
             GameEntity,
         ));
     }
@@ -226,6 +243,7 @@ pub fn check_harpoon_hit(
                 commands.entity(he).despawn();
                 commands.entity(me).despawn();
                 state.score += 100;
+                state.hits += 1;
 
                 // Split if big enough
                 let new_r = mol.radius * SPLIT_RATIO;
@@ -238,6 +256,7 @@ pub fn check_harpoon_hit(
                         pixar::spawn_character(&mut commands, &pixar_assets, &config,
                             Vec3::new(mtf.translation.x + dir * new_r, mtf.translation.y, 0.5), (
                             Molecule { radius: new_r, vx: dir * speed, vy: -mol.vy.abs().max(60.0) },
+                            Interpolated::default(),
                             GameEntity,
                         ));
                     }
@@ -254,6 +273,7 @@ pub fn check_player_hit(
     pq: Query<&Transform, With<Player>>,
     mq: Query<(&Transform, &Molecule)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
     state.invuln -= time.delta_secs();
     if state.invuln > 0.0 { return; }
@@ -265,6 +285,7 @@ pub fn check_player_hit(
         if dx < PLAYER_W / 2.0 + mol.radius && dy < PLAYER_H / 2.0 + mol.radius {
             state.lives -= 1;
             state.invuln = 1.5;
+            failures.send(Failure(ptf.translation.truncate()));
             if state.lives <= 0 {
                 next_state.set(crate::AppState::GameOver);
             }
@@ -283,7 +304,7 @@ pub fn check_level_clear(
     if !mq.is_empty() { return; }
 
     state.level += 1;
-    if state.level >= 5 {
+    if state.level >= LEVELS {
         next_state.set(crate::AppState::GameOver);
         return;
     }
@@ -292,6 +313,9 @@ pub fn check_level_clear(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    bridge.stats.level = Some(state.level.min(LEVELS - 1) as i32 + 1);
+    bridge.stats.deaths = Some(START_LIVES - state.lives.max(0));
 }
 
 pub fn update_hud(
@@ -0,0 +1,480 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::BevyBridge;
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::pixar::palette;
+use crate::asset_loader::CustomAssets;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const COLS: i32 = 16;
+const ROWS: i32 = 10;
+const TILE: f32 = 34.0;
+const ORIGIN_X: f32 = -((COLS as f32) * TILE) / 2.0 + TILE / 2.0;
+const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0 + 30.0;
+/// Seconds before the first infection spreads, to get the first tiles down.
+const PREP_SECS: f32 = 3.0;
+const TICK_SECS: f32 = 0.5;
+/// Ticks in a round.  The round ends early once nobody is infectious.
+const ROUND_TICKS: u32 = 40;
+/// Ticks an agent stays infectious before recovering.
+const INFECTIOUS_TICKS: u32 = 6;
+const POINTS_PER_HEALTHY: i32 = 10;
+/// Awarded when the outbreak burns out before the round ends.
+const CONTAINED_BONUS: i32 = 200;
+const BUTTON_Y: f32 = -230.0;
+const BUTTON_SIZE: Vec2 = Vec2::new(180.0, 44.0);
+
+/// `spread` is the chance per tick that an infectious agent infects each
+/// of its four neighbours; `density` the share of cells with an agent.
+struct Level {
+    spread: f32,
+    density: f32,
+    seeds: usize,
+    vaccines: u32,
+    quarantines: u32,
+}
+
+const LEVELS: [Level; 3] = [
+    Level { spread: 0.25, density: 0.75, seeds: 1, vaccines: 12, quarantines: 4 },
+    Level { spread: 0.35, density: 0.85, seeds: 2, vaccines: 14, quarantines: 5 },
+    Level { spread: 0.45, density: 0.9, seeds: 3, vaccines: 16, quarantines: 6 },
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+#[derive(Component)]
+pub struct CellVisual(usize);
+
+/// Frame drawn behind a quarantined cell.
+#[derive(Component)]
+pub struct QuarantineVisual(usize);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool { Vaccinate, Quarantine }
+
+#[derive(Component)]
+pub struct ToolButton(Tool);
+
+#[derive(Component)]
+pub struct ToolLabel(Tool);
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Health {
+    Empty,
+    Healthy,
+    /// Ticks left until recovery.
+    Infected(u32),
+    Recovered,
+    Vaccinated,
+}
+
+/// A quarantined agent neither catches nor passes on the infection.
+#[derive(Clone, Copy)]
+struct Cell {
+    health: Health,
+    quarantined: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase { Outbreak, Summary }
+
+#[derive(Resource)]
+pub struct GameState {
+    score: i32,
+    level: usize,
+    cells: Vec<Cell>,
+    tool: Tool,
+    vaccines: u32,
+    quarantines: u32,
+    phase: Phase,
+    /// Counts down to the next tick, or to the next level in `Summary`.
+    timer: f32,
+    ticks: u32,
+    /// Rounds that ended with fewer than half the agents healthy.
+    lost: i32,
+    message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn wp(col: i32, row: i32, z: f32) -> Vec3 {
+    Vec3::new(ORIGIN_X + col as f32 * TILE, ORIGIN_Y + (ROWS - 1 - row) as f32 * TILE, z)
+}
+
+fn index(col: i32, row: i32) -> Option<usize> {
+    if col < 0 || col >= COLS || row < 0 || row >= ROWS { return None; }
+    Some((row * COLS + col) as usize)
+}
+
+fn cell_at(world: Vec2) -> Option<usize> {
+    let col = ((world.x - ORIGIN_X) / TILE + 0.5).floor() as i32;
+    let row = ROWS - 1 - ((world.y - ORIGIN_Y) / TILE + 0.5).floor() as i32;
+    index(col, row)
+}
+
+/// Populates a fresh grid and infects `seeds` random agents.
+fn populate(level: usize) -> Vec<Cell> {
+    let mut rng = rand::thread_rng();
+    let config = &LEVELS[level];
+    let mut cells: Vec<Cell> = (0..COLS * ROWS)
+        .map(|_| Cell {
+            health: if rng.gen::<f32>() < config.density { Health::Healthy } else { Health::Empty },
+            quarantined: false,
+        })
+        .collect();
+
+    let mut seeded = 0;
+    while seeded < config.seeds && cells.iter().any(|c| c.health == Health::Healthy) {
+        let i = rng.gen_range(0..cells.len());
+        if cells[i].health == Health::Healthy {
+            cells[i].health = Health::Infected(INFECTIOUS_TICKS);
+            seeded += 1;
+        }
+    }
+    cells
+}
+
+fn healthy(cells: &[Cell]) -> usize {
+    cells.iter().filter(|c| matches!(c.health, Health::Healthy | Health::Vaccinated)).count()
+}
+
+fn population(cells: &[Cell]) -> usize {
+    cells.iter().filter(|c| c.health != Health::Empty).count()
+}
+
+fn infectious(cells: &[Cell]) -> bool {
+    cells.iter().any(|c| matches!(c.health, Health::Infected(_)) && !c.quarantined)
+}
+
+/// One tick of spread and recovery.  New infections are worked out from
+/// the grid as it was at the start of the tick, so an outbreak moves at
+/// most one cell per tick.
+fn spread(cells: &mut [Cell], chance: f32) {
+    let mut rng = rand::thread_rng();
+    let before = cells.to_vec();
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let i = (row * COLS + col) as usize;
+            let Health::Infected(left) = before[i].health else { continue };
+            cells[i].health = if left <= 1 { Health::Recovered } else { Health::Infected(left - 1) };
+            if before[i].quarantined { continue; }
+
+            for (dc, dr) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let Some(n) = index(col + dc, row + dr) else { continue };
+                if cells[n].health == Health::Healthy && !cells[n].quarantined && rng.gen::<f32>() < chance {
+                    cells[n].health = Health::Infected(INFECTIOUS_TICKS);
+                }
+            }
+        }
+    }
+}
+
+fn cell_color(cell: &Cell) -> Color {
+    match cell.health {
+        Health::Empty => palette::SHADOW,
+        Health::Healthy => palette::HERO_GREEN,
+        Health::Infected(_) => palette::VILLAIN_RED,
+        Health::Recovered => palette::SILVER,
+        Health::Vaccinated => palette::HERO_BLUE,
+    }
+}
+
+fn use_tool(state: &mut GameState, tool: Tool, i: usize) {
+    if state.phase != Phase::Outbreak { return; }
+    let cell = state.cells[i];
+    match tool {
+        Tool::Vaccinate => {
+            if state.vaccines == 0 {
+                state.message = "Out of vaccines".into();
+            } else if cell.health != Health::Healthy {
+                state.message = "Only healthy agents can be vaccinated".into();
+            } else {
+                state.cells[i].health = Health::Vaccinated;
+                state.vaccines -= 1;
+                state.message.clear();
+            }
+        }
+        Tool::Quarantine => {
+            if state.quarantines == 0 {
+                state.message = "Out of quarantine zones".into();
+            } else if cell.health == Health::Empty || cell.quarantined {
+                state.message = "Nothing to quarantine there".into();
+            } else {
+                state.cells[i].quarantined = true;
+                state.quarantines -= 1;
+                state.message.clear();
+            }
+        }
+    }
+}
+
+fn start_level(state: &mut GameState, level: usize) {
+    state.level = level;
+    state.cells = populate(level);
+    state.vaccines = LEVELS[level].vaccines;
+    state.quarantines = LEVELS[level].quarantines;
+    state.phase = Phase::Outbreak;
+    state.timer = PREP_SECS;
+    state.ticks = 0;
+    state.message = "An outbreak has started!".into();
+}
+
+fn tool_label(tool: Tool, state: &GameState) -> String {
+    match tool {
+        Tool::Vaccinate => format!("Vaccinate ({})", state.vaccines),
+        Tool::Quarantine => format!("Quarantine ({})", state.quarantines),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, custom_assets: Res<CustomAssets>) {
+    let mut state = GameState {
+        score: 0,
+        level: 0,
+        cells: Vec::new(),
+        tool: Tool::Vaccinate,
+        vaccines: 0,
+        quarantines: 0,
+        phase: Phase::Outbreak,
+        timer: 0.0,
+        ticks: 0,
+        lost: 0,
+        message: String::new(),
+    };
+    start_level(&mut state, 0);
+    commands.insert_resource(state);
+
+    // Background
+    if let Some(ref bg_handle) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::LAB_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    }
+
+    // Grid; colours are filled in by `update_visuals`
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let i = (row * COLS + col) as usize;
+            commands.spawn((
+                Sprite { color: palette::HERO_YELLOW, custom_size: Some(Vec2::splat(TILE)), ..default() },
+                Transform::from_translation(wp(col, row, 0.0)),
+                Visibility::Hidden,
+                QuarantineVisual(i),
+                GameEntity,
+            ));
+            commands.spawn((
+                Sprite { color: palette::SHADOW, custom_size: Some(Vec2::splat(TILE - 6.0)), ..default() },
+                Transform::from_translation(wp(col, row, 1.0)),
+                CellVisual(i),
+                GameEntity,
+            ));
+        }
+    }
+
+    // Tool buttons
+    for (tool, x) in [(Tool::Vaccinate, -100.0), (Tool::Quarantine, 100.0)] {
+        commands.spawn((
+            Sprite { color: palette::HERO_BLUE, custom_size: Some(BUTTON_SIZE), ..default() },
+            Transform::from_xyz(x, BUTTON_Y, 2.0),
+            ToolButton(tool),
+            GameEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text2d::new(""),
+                TextFont { font_size: 18.0, ..default() },
+                TextColor(Color::WHITE),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                ToolLabel(tool),
+            ));
+        });
+    }
+
+    commands.spawn((
+        Text::new("Level 1"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+        ScoreText,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Clicks and taps use the selected tool on a cell, or pick a tool from
+/// the buttons.  Right click always quarantines; 1 and 2 switch tools.
+pub fn player_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    buttons: Query<(&ToolButton, &Transform)>,
+    mut state: ResMut<GameState>,
+) {
+    if keys.just_pressed(KeyCode::Digit1) { state.tool = Tool::Vaccinate; }
+    if keys.just_pressed(KeyCode::Digit2) { state.tool = Tool::Quarantine; }
+
+    let right = mouse.just_pressed(MouseButton::Right);
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) || right {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let Some(screen_pos) = screen_pos else { return };
+    let Ok((camera, cam_tf)) = camera_q.get_single() else { return };
+    let Ok(cursor) = camera.viewport_to_world_2d(cam_tf, screen_pos) else { return };
+
+    for (button, tf) in &buttons {
+        let d = (cursor - tf.translation.truncate()).abs();
+        if d.x <= BUTTON_SIZE.x / 2.0 && d.y <= BUTTON_SIZE.y / 2.0 {
+            state.tool = button.0;
+            return;
+        }
+    }
+
+    let Some(i) = cell_at(cursor) else { return };
+    let tool = if right { Tool::Quarantine } else { state.tool };
+    use_tool(&mut state, tool, i);
+}
+
+/// Advances the outbreak a tick at a time and scores the round when time
+/// runs out or the infection burns out.
+pub fn run_outbreak(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut failures: EventWriter<Failure>,
+) {
+    if state.phase != Phase::Outbreak { return; }
+    state.timer -= time.delta_secs();
+    if state.timer > 0.0 { return; }
+    state.timer += TICK_SECS;
+
+    let chance = LEVELS[state.level].spread;
+    spread(&mut state.cells, chance);
+    state.ticks += 1;
+
+    let contained = !infectious(&state.cells);
+    if !contained && state.ticks < ROUND_TICKS { return; }
+
+    let healthy = healthy(&state.cells);
+    let population = population(&state.cells);
+    let mut points = healthy as i32 * POINTS_PER_HEALTHY;
+    if contained { points += CONTAINED_BONUS; }
+    state.score += points;
+    analytics.send(AnalyticsEvent::level_complete(state.level, state.score));
+
+    if healthy * 2 < population {
+        // Marks the middle of the board; the outbreak has no single spot.
+        failures.send(Failure(wp(COLS / 2, ROWS / 2, 0.0).truncate()));
+        state.lost += 1;
+    }
+    state.message = if contained {
+        format!("Contained! {}/{} healthy. +{}", healthy, population, points)
+    } else {
+        format!("Time's up: {}/{} healthy. +{}", healthy, population, points)
+    };
+    state.phase = Phase::Summary;
+    state.timer = 2.0;
+}
+
+/// Moves on to the next level once a round's result has been shown.
+pub fn advance_level(
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    if state.phase != Phase::Summary { return; }
+    state.timer -= time.delta_secs();
+    if state.timer > 0.0 { return; }
+
+    let next = state.level + 1;
+    if next >= LEVELS.len() {
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+    start_level(&mut state, next);
+}
+
+pub fn update_visuals(
+    state: Res<GameState>,
+    mut cell_q: Query<(&CellVisual, &mut Sprite), Without<ToolButton>>,
+    mut frame_q: Query<(&QuarantineVisual, &mut Visibility)>,
+    mut button_q: Query<(&ToolButton, &mut Sprite), Without<CellVisual>>,
+    mut label_q: Query<(&ToolLabel, &mut Text2d)>,
+) {
+    for (cell, mut sprite) in &mut cell_q {
+        sprite.color = cell_color(&state.cells[cell.0]);
+    }
+    for (frame, mut visibility) in &mut frame_q {
+        *visibility = if state.cells[frame.0].quarantined { Visibility::Visible } else { Visibility::Hidden };
+    }
+    for (button, mut sprite) in &mut button_q {
+        sprite.color = if button.0 == state.tool { palette::HERO_ORANGE } else { palette::HERO_BLUE };
+    }
+    for (label, mut text) in &mut label_q {
+        **text = tool_label(label.0, &state);
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score;
+    bridge.stats.level = Some(state.level as i32 + 1);
+    bridge.stats.deaths = Some(state.lost);
+}
+
+pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+    let clock = if state.ticks == 0 && state.phase == Phase::Outbreak {
+        format!("Spreads in {:.0}s", state.timer.ceil())
+    } else {
+        format!("Tick {}/{}", state.ticks, ROUND_TICKS)
+    };
+    for mut t in &mut q {
+        **t = format!(
+            "Level {} | {} | Healthy: {}/{} | Score: {}  {}",
+            state.level + 1,
+            clock,
+            healthy(&state.cells),
+            population(&state.cells),
+            state.score,
+            state.message,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
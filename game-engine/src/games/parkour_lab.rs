@@ -1,9 +1,16 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::ghost::GhostTracked;
+use crate::input::{ActionState, Control, GameAction, Rumble};
+use crate::levelgen::{self, LevelRng};
+use crate::pool::{Parked, Pool};
+use crate::timestep::Interpolated;
 
 // Constants
 const GROUND_Y: f32 = -250.0;
@@ -32,26 +39,32 @@ pub struct GameEntity;
 enum PlayerState { Running, Jumping, Sliding }
 
 #[derive(Component)]
-struct Player { vy: f32, state: PlayerState, momentum: f32, slide_timer: f32 }
+pub struct Player { vy: f32, state: PlayerState, momentum: f32, slide_timer: f32 }
 
 #[derive(Clone, Copy, PartialEq)]
 enum ObstacleKind { Wall, Bar, Gap }
 
 #[derive(Component)]
-struct Obstacle { kind: ObstacleKind, scored: bool }
+pub struct Obstacle { kind: ObstacleKind, scored: bool }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Component)]
-struct MomentumText;
+pub struct MomentumText;
 
+/// `rng` picks the obstacles; it is seeded in daily runs.
 #[derive(Resource)]
-struct GameState { distance: f32, spawn_timer: f32, score: i32 }
+pub struct GameState { distance: f32, spawn_timer: f32, score: i32, rng: LevelRng }
 
 // Setup
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { distance: 0.0, spawn_timer: 0.0, score: 0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    let mut rng = levelgen::run_rng(&options);
 
     let bg_sprite = if let Some(ref bg) = custom_assets.background {
         Sprite { image: bg.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() }
@@ -63,12 +76,13 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Sprite { color: palette::GROUND_GREEN, custom_size: Some(Vec2::new(960.0, 40.0)), ..default() },
         Transform::from_xyz(0.0, GROUND_Y - 20.0, 0.0), GameEntity,
     ));
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_ORANGE, Vec2::new(PLAYER_W, PLAYER_H_RUN)),
         Vec3::new(PLAYER_X, GROUND_Y + PLAYER_H_RUN / 2.0, 1.0),
-        (Player { vy: 0.0, state: PlayerState::Running, momentum: 1.0, slide_timer: 0.0 }, GameEntity),
+        (Player { vy: 0.0, state: PlayerState::Running, momentum: 1.0, slide_timer: 0.0 }, Interpolated::default(), GameEntity, GhostTracked("parkour_lab")),
     );
     commands.spawn((
         Text::new("Score: 0"),
@@ -84,18 +98,21 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Node { position_type: PositionType::Absolute, top: Val::Px(10.0), right: Val::Px(10.0), ..default() },
         MomentumText, GameEntity,
     ));
-    spawn_obstacle(&mut commands, &pixar_assets, 400.0);
-    spawn_obstacle(&mut commands, &pixar_assets, 750.0);
+    let mut pool = Pool::<Obstacle>::default();
+    spawn_obstacle(&mut commands, &mut pool, &pixar_assets, &mut rng, 400.0);
+    spawn_obstacle(&mut commands, &mut pool, &pixar_assets, &mut rng, 750.0);
+
+    commands.insert_resource(GameState { distance: 0.0, spawn_timer: 0.0, score: 0, rng });
+    commands.insert_resource(pool);
 }
 
 // Systems
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>, mouse: Res<ButtonInput<MouseButton>>,
-    touches: Res<Touches>, mut pq: Query<(&mut Player, &mut Sprite, &mut Transform)>,
+    mut actions: EventReader<GameAction>, input: Res<ActionState>,
+    mut pq: Query<(&mut Player, &mut Sprite, &mut Transform)>,
 ) {
-    let jump = keys.just_pressed(KeyCode::Space) || keys.just_pressed(KeyCode::ArrowUp)
-        || mouse.just_pressed(MouseButton::Left) || touches.any_just_pressed();
-    let slide = keys.pressed(KeyCode::ArrowDown);
+    let jump = actions.read().any(|a| matches!(a, GameAction::Jump | GameAction::Pointer));
+    let slide = input.pressed(Control::Slide);
     for (mut p, mut sp, mut tf) in &mut pq {
         match p.state {
             PlayerState::Running => {
@@ -151,34 +168,39 @@ pub fn player_physics(time: Res<Time>, mut pq: Query<(&mut Transform, &mut Playe
 
 pub fn scroll_world(
     time: Res<Time>, mut state: ResMut<GameState>, pq: Query<&Player>,
-    mut oq: Query<&mut Transform, With<Obstacle>>,
-    entities: Query<Entity, With<Obstacle>>, mut commands: Commands,
+    mut oq: Query<(Entity, &mut Transform), (With<Obstacle>, Without<Parked>)>,
+    mut pool: ResMut<Pool<Obstacle>>, mut commands: Commands,
 ) {
     let Ok(player) = pq.get_single() else { return };
     let dt = time.delta_secs();
     let scroll = BASE_SPEED * player.momentum * dt;
     state.distance += scroll;
-    for mut tf in &mut oq { tf.translation.x -= scroll; }
-    for (entity, tf) in entities.iter().zip(oq.iter()) {
-        if tf.translation.x < -HALF_W - 60.0 { commands.entity(entity).despawn(); }
+    for (entity, mut tf) in &mut oq {
+        tf.translation.x -= scroll;
+        if tf.translation.x < -HALF_W - 60.0 { pool.release(&mut commands, entity); }
     }
 }
 
-pub fn spawn_obstacles(time: Res<Time>, mut state: ResMut<GameState>, pq: Query<&Player>, mut commands: Commands, pixar_assets: Res<PixarAssets>) {
+pub fn spawn_obstacles(
+    time: Res<Time>, mut state: ResMut<GameState>, pq: Query<&Player>,
+    mut pool: ResMut<Pool<Obstacle>>, mut commands: Commands, pixar_assets: Res<PixarAssets>,
+) {
     let Ok(player) = pq.get_single() else { return };
     state.spawn_timer += BASE_SPEED * player.momentum * time.delta_secs();
     if state.spawn_timer >= OBSTACLE_GAP {
         state.spawn_timer = 0.0;
-        spawn_obstacle(&mut commands, &pixar_assets, HALF_W + 60.0);
+        spawn_obstacle(&mut commands, &mut pool, &pixar_assets, &mut state.rng, HALF_W + 60.0);
     }
 }
 
 pub fn check_collisions(
     pq: Query<(&Transform, &Player, &Sprite), Without<Obstacle>>,
-    mut oq: Query<(&Transform, &Sprite, &mut Obstacle)>,
+    mut oq: Query<(&Transform, &Sprite, &mut Obstacle), Without<Parked>>,
     mut player_q: Query<&mut Player>,
     mut state: ResMut<GameState>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+    mut rumble: EventWriter<Rumble>,
 ) {
     let Ok((ptf, player, psp)) = pq.get_single() else { return };
     let ph = psp.custom_size.unwrap_or(Vec2::new(PLAYER_W, PLAYER_H_RUN));
@@ -192,6 +214,8 @@ pub fn check_collisions(
         match obs.kind {
             ObstacleKind::Gap => {
                 if ox && ptf.translation.y - phalf.y <= GROUND_Y + 5.0 && player.state != PlayerState::Jumping {
+                    failures.send(Failure(ptf.translation.truncate()));
+                    rumble.send(Rumble::impact());
                     next_state.set(crate::AppState::GameOver); return;
                 }
                 if ox && !obs.scored && player.state == PlayerState::Jumping {
@@ -203,7 +227,7 @@ pub fn check_collisions(
             ObstacleKind::Wall => {
                 if ox && oy {
                     if let Ok(mut p) = player_q.get_single_mut() { p.momentum = (p.momentum - MOMENTUM_LOSS).max(0.5); }
-                    if !obs.scored { obs.scored = true; state.score = (state.score - 5).max(0); }
+                    if !obs.scored { obs.scored = true; state.score = (state.score - 5).max(0); rumble.send(Rumble::light()); }
                 } else if ox && !obs.scored && player.state == PlayerState::Jumping {
                     obs.scored = true;
                     if let Ok(mut p) = player_q.get_single_mut() { p.momentum = (p.momentum + MOMENTUM_BOOST).min(MAX_MOMENTUM); }
@@ -213,7 +237,7 @@ pub fn check_collisions(
             ObstacleKind::Bar => {
                 if ox && oy {
                     if let Ok(mut p) = player_q.get_single_mut() { p.momentum = (p.momentum - MOMENTUM_LOSS).max(0.5); }
-                    if !obs.scored { obs.scored = true; state.score = (state.score - 5).max(0); }
+                    if !obs.scored { obs.scored = true; state.score = (state.score - 5).max(0); rumble.send(Rumble::light()); }
                 } else if ox && !obs.scored && player.state == PlayerState::Sliding {
                     obs.scored = true;
                     if let Ok(mut p) = player_q.get_single_mut() { p.momentum = (p.momentum + MOMENTUM_BOOST).min(MAX_MOMENTUM); }
@@ -227,6 +251,7 @@ pub fn check_collisions(
 pub fn update_score(state: Res<GameState>, pq: Query<&Player>, mut bridge: ResMut<BevyBridge>) {
     let Ok(_p) = pq.get_single() else { return };
     bridge.current_score = state.score + (state.distance / 10.0) as i32;
+    bridge.stats.distance = GameStats::metres(state.distance);
 }
 
 pub fn update_hud(
@@ -244,26 +269,26 @@ pub fn update_hud(
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
     for e in &q { commands.entity(e).despawn(); }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Obstacle>>();
 }
 
 // Helpers
-fn spawn_obstacle(commands: &mut Commands, pixar_assets: &PixarAssets, x: f32) {
-    let mut rng = rand::thread_rng();
+fn spawn_obstacle(commands: &mut Commands, pool: &mut Pool<Obstacle>, pixar_assets: &PixarAssets, rng: &mut LevelRng, x: f32) {
     match rng.gen_range(0..3) {
-        0 => { commands.spawn((
+        0 => { pool.acquire(commands, (
             pixar::round_sprite(pixar_assets, palette::VILLAIN_RED, WALL_SIZE),
             Transform::from_xyz(x, GROUND_Y + WALL_SIZE.y / 2.0, 0.5),
-            Obstacle { kind: ObstacleKind::Wall, scored: false }, GameEntity,
+            Obstacle { kind: ObstacleKind::Wall, scored: false }, Interpolated::default(), GameEntity,
         )); }
-        1 => { commands.spawn((
+        1 => { pool.acquire(commands, (
             pixar::round_sprite(pixar_assets, palette::VILLAIN_PURPLE, BAR_SIZE),
             Transform::from_xyz(x, BAR_Y, 0.5),
-            Obstacle { kind: ObstacleKind::Bar, scored: false }, GameEntity,
+            Obstacle { kind: ObstacleKind::Bar, scored: false }, Interpolated::default(), GameEntity,
         )); }
-        _ => { commands.spawn((
+        _ => { pool.acquire(commands, (
             Sprite { color: palette::NIGHT_BG, custom_size: Some(Vec2::new(GAP_WIDTH, 40.0)), ..default() },
             Transform::from_xyz(x, GROUND_Y - 20.0, 0.5),
-            Obstacle { kind: ObstacleKind::Gap, scored: false }, GameEntity,
+            Obstacle { kind: ObstacleKind::Gap, scored: false }, Interpolated::default(), GameEntity,
         )); }
     }
 }
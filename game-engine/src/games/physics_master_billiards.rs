@@ -1,7 +1,13 @@
 use bevy::prelude::*;
-use crate::BevyBridge;
+use std::f32::consts::PI;
+
+use crate::ai::{Aim, Bot};
+use crate::{BevyBridge, GameStats};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
+use crate::timestep::Interpolated;
+use crate::versus::{SplitInput, VersusPlayer, VersusState};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -13,9 +19,22 @@ const HALF_W: f32 = TABLE_W / 2.0;
 const HALF_H: f32 = TABLE_H / 2.0;
 const BALL_R: f32 = 10.0;
 const POCKET_R: f32 = 20.0;
+/// Fraction of speed kept per simulation step.
 const FRICTION: f32 = 0.985;
 const MAX_POWER: f32 = 600.0;
 const MIN_SPEED: f32 = 3.0;
+/// Versus aiming: radians per second and power fraction per second.
+const AIM_SPEED: f32 = 1.5;
+const POWER_SPEED: f32 = 0.6;
+/// Speed a rolling ball loses per unit of distance: it keeps `FRICTION` of
+/// its speed per step while covering `speed / 60` of distance.
+const ROLL_DRAG: f32 = (1.0 - FRICTION) * 60.0;
+/// Bot shots: the sharpest cut it tries, the speed it wants the object
+/// ball to still have at the pocket, and how far off its aim (radians)
+/// and power can be.
+const BOT_MAX_CUT: f32 = 75.0 * PI / 180.0;
+const BOT_POT_SPEED: f32 = 60.0;
+const BOT_SPREAD: Vec2 = Vec2::new(0.12, 0.3);
 
 // ---------------------------------------------------------------------------
 // Components
@@ -25,24 +44,34 @@ const MIN_SPEED: f32 = 3.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Ball { vx: f32, vy: f32, is_cue: bool, sunk: bool }
+pub struct Ball { vx: f32, vy: f32, is_cue: bool, sunk: bool }
 
 #[derive(Component)]
-struct Pocket { x: f32, y: f32 }
+pub struct Pocket { x: f32, y: f32 }
 
 #[derive(Component)]
-struct PowerLine;
+pub struct PowerLine;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     pocketed: i32,
     dragging: bool,
     drag_start: Vec2,
     drag_end: Vec2,
+    /// Versus cue aim: direction in radians and power as a fraction.
+    aim_angle: f32,
+    aim_power: f32,
+    /// Versus: a shot is rolling; whether it sank a ball or the cue ball.
+    shot_active: bool,
+    potted_on_shot: bool,
+    scratched: bool,
+    /// Shots taken, and how many of them sank at least one ball.
+    shots: i32,
+    potting_shots: i32,
 }
 
 // ---------------------------------------------------------------------------
@@ -53,6 +82,36 @@ fn any_moving(bq: &Query<(&Transform, &Ball)>) -> bool {
     bq.iter().any(|(_, b)| !b.sunk && (b.vx.abs() > MIN_SPEED || b.vy.abs() > MIN_SPEED))
 }
 
+/// The bot's ideal shot, as an aim angle and power fraction: the easiest
+/// pot by cut angle and distance, or straight at the nearest ball when no
+/// ball can be cut into a pocket.  Ignores balls in the way.
+fn bot_shot(cue: Vec2, balls: &[Vec2], pockets: &[Vec2]) -> Vec2 {
+    let mut best: Option<(f32, Vec2)> = None;
+    for &ball in balls {
+        for &pocket in pockets {
+            let to_pocket = pocket - ball;
+            let line = to_pocket.normalize_or_zero();
+            // Where the cue ball has to be when it strikes the object ball.
+            let ghost = ball - line * BALL_R * 2.0;
+            let to_ghost = ghost - cue;
+            let cut = to_ghost.angle_to(line).abs();
+            if cut > BOT_MAX_CUT {
+                continue;
+            }
+            let cost = cut + (to_ghost.length() + to_pocket.length()) / TABLE_W;
+            if best.is_some_and(|(c, _)| c <= cost) {
+                continue;
+            }
+            let speed = to_ghost.length() * ROLL_DRAG + (to_pocket.length() * ROLL_DRAG + BOT_POT_SPEED) / cut.cos();
+            best = Some((cost, Vec2::new(to_ghost.to_angle(), (speed / MAX_POWER).clamp(0.05, 1.0))));
+        }
+    }
+    best.map(|(_, shot)| shot).unwrap_or_else(|| {
+        let nearest = balls.iter().min_by(|a, b| a.distance(cue).total_cmp(&b.distance(cue)));
+        Vec2::new(nearest.map_or(0.0, |b| (*b - cue).to_angle()), 0.6)
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
@@ -61,6 +120,9 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     commands.insert_resource(GameState {
         score: 0, pocketed: 0, dragging: false,
         drag_start: Vec2::ZERO, drag_end: Vec2::ZERO,
+        aim_angle: 0.0, aim_power: 0.5,
+        shot_active: false, potted_on_shot: false, scratched: false,
+        shots: 0, potting_shots: 0,
     });
 
     // Table background (prop)
@@ -99,6 +161,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     let cue_config = CharacterConfig::blob(Color::WHITE, BALL_R * 2.0);
     pixar::spawn_character(&mut commands, &pixar_assets, &cue_config, Vec3::new(-HALF_W * 0.5, 0.0, 1.0), (
         Ball { vx: 0.0, vy: 0.0, is_cue: true, sunk: false },
+        Interpolated::default(),
         GameEntity,
     ));
 
@@ -121,6 +184,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
             let config = CharacterConfig::blob(colors[idx], BALL_R * 2.0);
             pixar::spawn_character(&mut commands, &pixar_assets, &config, Vec3::new(x, y, 1.0), (
                 Ball { vx: 0.0, vy: 0.0, is_cue: false, sunk: false },
+                Interpolated::default(),
                 GameEntity,
             ));
             idx += 1;
@@ -153,9 +217,11 @@ pub fn handle_input(
     mouse: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
+    versus: Res<VersusState>,
     mut state: ResMut<GameState>,
     mut bq: Query<(&mut Ball, &Transform)>,
 ) {
+    if versus.active { return; }
     let moving = bq.iter().any(|(b, _)| !b.sunk && (b.vx.abs() > MIN_SPEED || b.vy.abs() > MIN_SPEED));
     if moving && !state.dragging { return; }
 
@@ -185,6 +251,8 @@ pub fn handle_input(
         let dir = state.drag_start - state.drag_end;
         let power = dir.length().min(300.0) / 300.0;
         if power > 0.02 {
+            state.shots += 1;
+            state.potted_on_shot = false;
             let norm = dir.normalize_or_zero();
             for (mut ball, _) in &mut bq {
                 if ball.is_cue && !ball.sunk {
@@ -196,11 +264,95 @@ pub fn handle_input(
     }
 }
 
+/// Versus: players take turns at the cue ball, keeping the turn while they
+/// pot balls.  The player at the table aims with left/right, sets power
+/// with up/down and shoots with their action key.  In a bot match the bot
+/// lines up its own shots.
+pub fn versus_shoot(
+    time: Res<Time>,
+    input: Res<SplitInput>,
+    versus: Res<VersusState>,
+    mut bot: ResMut<Bot>,
+    mut state: ResMut<GameState>,
+    mut bq: Query<(&mut Ball, &Transform)>,
+    pq: Query<&Pocket>,
+) {
+    if !versus.active || state.shot_active { return; }
+    let dt = time.delta_secs();
+    let shoot = if bot.plays(versus.turn) {
+        let Some(cue) = bq.iter().find(|(b, _)| b.is_cue && !b.sunk).map(|(_, t)| t.translation.truncate()) else { return };
+        let balls: Vec<Vec2> = bq.iter().filter(|(b, _)| !b.is_cue && !b.sunk).map(|(_, t)| t.translation.truncate()).collect();
+        let pockets: Vec<Vec2> = pq.iter().map(|p| Vec2::new(p.x, p.y)).collect();
+        let mut ideal = bot_shot(cue, &balls, &pockets);
+        // Turn the short way round from the current aim.
+        ideal.x = state.aim_angle + (ideal.x - state.aim_angle + PI).rem_euclid(2.0 * PI) - PI;
+        bot.plan(Vec2::new(state.aim_angle, state.aim_power), ideal, BOT_SPREAD);
+        let (aim, shoot) = match bot.aim(dt) {
+            Some(Aim::Lining(aim)) => (aim, false),
+            Some(Aim::Take(aim)) => (aim, true),
+            None => return,
+        };
+        state.aim_angle = aim.x;
+        state.aim_power = aim.y.clamp(0.05, 1.0);
+        shoot
+    } else {
+        let keys = input.player(versus.turn);
+        state.aim_angle -= keys.movement.x * AIM_SPEED * dt;
+        state.aim_power = (state.aim_power + keys.movement.y * POWER_SPEED * dt).clamp(0.05, 1.0);
+        keys.action
+    };
+
+    if shoot {
+        let dir = Vec2::from_angle(state.aim_angle);
+        for (mut ball, _) in &mut bq {
+            if ball.is_cue && !ball.sunk {
+                ball.vx = dir.x * state.aim_power * MAX_POWER;
+                ball.vy = dir.y * state.aim_power * MAX_POWER;
+            }
+        }
+        state.shot_active = true;
+        state.shots += 1;
+        state.potted_on_shot = false;
+        state.scratched = false;
+    }
+}
+
+/// Versus: once a shot stops rolling, the turn passes unless the shooter
+/// potted a ball without scratching.
+pub fn versus_turns(
+    mut versus: ResMut<VersusState>,
+    mut state: ResMut<GameState>,
+    bq: Query<(&Transform, &Ball)>,
+) {
+    if !versus.active || !state.shot_active || any_moving(&bq) { return; }
+    state.shot_active = false;
+    if !state.potted_on_shot || state.scratched {
+        versus.pass_turn();
+    }
+}
+
 pub fn update_power_line(
     state: Res<GameState>,
+    versus: Res<VersusState>,
+    bq: Query<(&Ball, &Transform), Without<PowerLine>>,
     mut plq: Query<(&mut Transform, &mut Sprite), With<PowerLine>>,
 ) {
     let Ok((mut tf, mut sprite)) = plq.get_single_mut() else { return };
+    if versus.active {
+        let cue = bq.iter().find(|(b, _)| b.is_cue && !b.sunk).map(|(_, t)| t.translation.truncate());
+        match cue {
+            Some(cue) if !state.shot_active => {
+                let dir = Vec2::from_angle(state.aim_angle);
+                let len = 20.0 + state.aim_power * 120.0;
+                tf.translation = (cue + dir * len / 2.0).extend(2.0);
+                tf.rotation = Quat::from_rotation_z(state.aim_angle - std::f32::consts::FRAC_PI_2);
+                sprite.custom_size = Some(Vec2::new(2.0, len));
+                sprite.color = versus.turn.color().with_alpha(0.6);
+            }
+            _ => sprite.custom_size = Some(Vec2::ZERO),
+        }
+        return;
+    }
     if state.dragging {
         let mid = (state.drag_start + state.drag_end) / 2.0;
         let diff = state.drag_start - state.drag_end;
@@ -283,9 +435,11 @@ pub fn ball_collisions(
 
 pub fn check_pockets(
     mut state: ResMut<GameState>,
+    mut versus: ResMut<VersusState>,
     pq: Query<&Pocket>,
     mut bq: Query<(&mut Ball, &mut Transform, &mut Visibility)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
     for (mut ball, mut tf, mut vis) in &mut bq {
         if ball.sunk { continue; }
@@ -294,11 +448,13 @@ pub fn check_pockets(
             let dy = tf.translation.y - pocket.y;
             if (dx * dx + dy * dy).sqrt() < POCKET_R {
                 if ball.is_cue {
+                    failures.send(Failure(Vec2::new(pocket.x, pocket.y)));
                     // Reset cue ball
                     tf.translation.x = 0.0;
                     tf.translation.y = 0.0;
                     ball.vx = 0.0;
                     ball.vy = 0.0;
+                    state.scratched = true;
                 } else {
                     ball.sunk = true;
                     ball.vx = 0.0;
@@ -306,7 +462,14 @@ pub fn check_pockets(
                     *vis = Visibility::Hidden;
                     state.score += 100;
                     state.pocketed += 1;
+                    if !state.potted_on_shot {
+                        state.potting_shots += 1;
+                    }
+                    state.potted_on_shot = true;
+                    let shooter = versus.turn;
+                    versus.add_score(shooter, 100);
                     if state.pocketed >= 15 {
+                        versus.finish_on_score();
                         next_state.set(crate::AppState::GameOver);
                     }
                 }
@@ -316,11 +479,32 @@ pub fn check_pockets(
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
-    bridge.current_score = state.score;
+pub fn update_score(state: Res<GameState>, versus: Res<VersusState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = if versus.active { versus.best_score() } else { state.score };
+    bridge.stats.accuracy = GameStats::percent(state.potting_shots, state.shots);
+    bridge.stats.collectibles = Some(state.pocketed);
 }
 
-pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+pub fn update_hud(
+    state: Res<GameState>,
+    versus: Res<VersusState>,
+    bot: Res<Bot>,
+    mut q: Query<&mut Text, With<ScoreText>>,
+) {
+    if versus.active {
+        for mut t in &mut q {
+            **t = format!(
+                "{}: {} | {}: {} | Pocketed: {}/15 | {} TO SHOOT",
+                bot.label(VersusPlayer::One),
+                versus.score(VersusPlayer::One),
+                bot.label(VersusPlayer::Two),
+                versus.score(VersusPlayer::Two),
+                state.pocketed,
+                bot.label(versus.turn)
+            );
+        }
+        return;
+    }
     for mut t in &mut q {
         **t = format!("Score: {} | Pocketed: {}/15", state.score, state.pocketed);
     }
@@ -0,0 +1,508 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::BevyBridge;
+use crate::analytics::AnalyticsEvent;
+use crate::diagnostics::Failure;
+use crate::game_mode::GameMode;
+use crate::pixar::palette;
+use crate::asset_loader::CustomAssets;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const COLS: i32 = 5;
+const ROWS: i32 = 3;
+const TILE: f32 = 84.0;
+const ORIGIN_X: f32 = -360.0;
+const ORIGIN_Y: f32 = -90.0;
+/// Real seconds in a simulated day.
+const DAY_SECS: f32 = 90.0;
+/// The day starts at dawn.
+const START_HOUR: f32 = 5.0;
+const START_BUDGET: f32 = 900.0;
+
+// Output in MW at full sun or wind.
+const SOLAR_MW: f32 = 12.0;
+const WIND_MW: f32 = 9.0;
+const BATTERY_MW: f32 = 10.0;
+/// Battery capacity in MWh.
+const BATTERY_MWH: f32 = 30.0;
+
+// Budget per game hour a tile is switched on.
+const SOLAR_UPKEEP: f32 = 1.0;
+const WIND_UPKEEP: f32 = 1.5;
+const BATTERY_UPKEEP: f32 = 0.5;
+/// Budget lost per MWh generated that nothing could use.
+const WASTE_COST: f32 = 2.0;
+
+/// Points per MWh delivered.
+const SERVED_POINTS: f32 = 2.0;
+/// Points lost per MWh of demand not met.
+const BLACKOUT_POINTS: f32 = 10.0;
+/// Shortfall, as a share of demand, that counts as a blackout.
+const BLACKOUT_SHARE: f32 = 0.05;
+
+const CITY_X: f32 = 250.0;
+const CITY_Y: f32 = 200.0;
+const BAR_BASE_Y: f32 = -200.0;
+const BAR_WIDTH: f32 = 36.0;
+/// Pixels per MW.
+const BAR_SCALE: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind { Solar, Wind, Battery }
+
+/// Top row first.
+const LAYOUT: [Kind; (COLS * ROWS) as usize] = [
+    Kind::Solar, Kind::Solar, Kind::Solar, Kind::Solar, Kind::Solar,
+    Kind::Wind, Kind::Wind, Kind::Wind, Kind::Wind, Kind::Wind,
+    Kind::Battery, Kind::Battery, Kind::Battery, Kind::Solar, Kind::Wind,
+];
+
+/// Each day is hungrier and the weather less steady.
+struct Day {
+    demand: f32,
+    /// How quickly cloud cover and wind drift, per game hour.
+    weather: f32,
+}
+
+const DAYS: [Day; 3] = [
+    Day { demand: 1.0, weather: 0.08 },
+    Day { demand: 1.2, weather: 0.14 },
+    Day { demand: 1.4, weather: 0.2 },
+];
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+#[derive(Component)]
+pub struct TileVisual(usize);
+
+/// Bar inside a tile showing its output, or a battery's charge.
+#[derive(Component)]
+pub struct TileFill(usize);
+
+#[derive(Component)]
+pub struct City;
+
+#[derive(Component, Clone, Copy)]
+pub enum Bar { Demand, Supply }
+
+#[derive(Component)]
+pub struct Sky;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Clone, Copy)]
+struct Tile {
+    kind: Kind,
+    active: bool,
+    /// MWh stored; batteries only.
+    charge: f32,
+    /// MW delivered last frame.
+    output: f32,
+}
+
+#[derive(Resource)]
+pub struct GameState {
+    score: f32,
+    budget: f32,
+    day: usize,
+    /// Seconds into the current day.
+    clock: f32,
+    tiles: Vec<Tile>,
+    /// Cloud cover and wind strength, 0..1.
+    clouds: f32,
+    wind: f32,
+    /// Noise on top of the demand curve, in MW.
+    demand_noise: f32,
+    demand: f32,
+    supply: f32,
+    blackout: bool,
+    blackouts: i32,
+    finished: bool,
+    message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn wp(gx: i32, gy: i32, z: f32) -> Vec3 {
+    Vec3::new(ORIGIN_X + gx as f32 * TILE, ORIGIN_Y + (ROWS - 1 - gy) as f32 * TILE, z)
+}
+
+fn hour(clock: f32) -> f32 {
+    (START_HOUR + clock / DAY_SECS * 24.0) % 24.0
+}
+
+fn peak(hour: f32, at: f32, width: f32) -> f32 {
+    (-((hour - at) / width).powi(2)).exp()
+}
+
+/// City demand in MW: a night-time base with morning and evening peaks.
+fn base_demand(hour: f32) -> f32 {
+    30.0 + 20.0 * peak(hour, 8.0, 2.0) + 12.0 * peak(hour, 13.0, 3.0) + 30.0 * peak(hour, 19.0, 2.5)
+}
+
+/// Share of full sun, zero at night.
+fn daylight(hour: f32) -> f32 {
+    if !(6.0..=18.0).contains(&hour) { return 0.0; }
+    (std::f32::consts::PI * (hour - 6.0) / 12.0).sin()
+}
+
+fn upkeep(kind: Kind) -> f32 {
+    match kind {
+        Kind::Solar => SOLAR_UPKEEP,
+        Kind::Wind => WIND_UPKEEP,
+        Kind::Battery => BATTERY_UPKEEP,
+    }
+}
+
+fn tile_color(tile: &Tile) -> Color {
+    let color = match tile.kind {
+        Kind::Solar => palette::HERO_YELLOW,
+        Kind::Wind => palette::SKY_BLUE,
+        Kind::Battery => palette::HERO_GREEN,
+    };
+    if tile.active { color } else { color.with_alpha(0.3) }
+}
+
+fn tile_label(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Solar => "Solar",
+        Kind::Wind => "Wind",
+        Kind::Battery => "Battery",
+    }
+}
+
+fn start_day(state: &mut GameState, day: usize) {
+    state.day = day;
+    state.clock = 0.0;
+    state.clouds = 0.3;
+    state.wind = 0.5;
+    state.demand_noise = 0.0;
+    for tile in &mut state.tiles {
+        tile.active = false;
+        tile.output = 0.0;
+        tile.charge = BATTERY_MWH / 2.0;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(mut commands: Commands, custom_assets: Res<CustomAssets>) {
+    let mut state = GameState {
+        score: 0.0,
+        budget: START_BUDGET,
+        day: 0,
+        clock: 0.0,
+        tiles: LAYOUT.iter().map(|&kind| Tile { kind, active: false, charge: 0.0, output: 0.0 }).collect(),
+        clouds: 0.0,
+        wind: 0.0,
+        demand_noise: 0.0,
+        demand: 0.0,
+        supply: 0.0,
+        blackout: false,
+        blackouts: 0,
+        finished: false,
+        message: String::new(),
+    };
+    start_day(&mut state, 0);
+
+    // Background; the sky darkens and brightens with the hour
+    if let Some(ref bg_handle) = custom_assets.background {
+        commands.spawn((
+            Sprite { image: bg_handle.clone(), custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            GameEntity,
+        ));
+    } else {
+        commands.spawn((
+            Sprite { color: palette::NIGHT_BG, custom_size: Some(Vec2::new(960.0, 640.0)), ..default() },
+            Transform::from_xyz(0.0, 0.0, -1.0),
+            Sky,
+            GameEntity,
+        ));
+    }
+
+    // Generator tiles
+    for (i, tile) in state.tiles.iter().enumerate() {
+        let (gx, gy) = (i as i32 % COLS, i as i32 / COLS);
+        commands.spawn((
+            Sprite { color: tile_color(tile), custom_size: Some(Vec2::splat(TILE - 8.0)), ..default() },
+            Transform::from_translation(wp(gx, gy, 1.0)),
+            TileVisual(i),
+            GameEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                Sprite { color: Color::srgba(1.0, 1.0, 1.0, 0.8), custom_size: Some(Vec2::new(TILE - 20.0, 6.0)), ..default() },
+                Transform::from_xyz(0.0, -TILE / 2.0 + 14.0, 0.1),
+                TileFill(i),
+            ));
+            parent.spawn((
+                Text2d::new(tile_label(tile.kind)),
+                TextFont { font_size: 14.0, ..default() },
+                TextColor(Color::BLACK),
+                Transform::from_xyz(0.0, 8.0, 0.1),
+            ));
+        });
+    }
+
+    // City, with demand and supply bars beside it
+    commands.spawn((
+        Sprite { color: palette::HERO_ORANGE, custom_size: Some(Vec2::new(140.0, 110.0)), ..default() },
+        Transform::from_xyz(CITY_X, CITY_Y, 1.0),
+        City,
+        GameEntity,
+    ));
+    for (bar, x, color, label) in [
+        (Bar::Demand, CITY_X - 30.0, palette::HERO_RED, "Demand"),
+        (Bar::Supply, CITY_X + 30.0, palette::HERO_GREEN, "Supply"),
+    ] {
+        commands.spawn((
+            Sprite { color, custom_size: Some(Vec2::new(BAR_WIDTH, 0.0)), ..default() },
+            Transform::from_xyz(x, BAR_BASE_Y, 1.0),
+            bar,
+            GameEntity,
+        ));
+        commands.spawn((
+            Text2d::new(label),
+            TextFont { font_size: 14.0, ..default() },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(x, BAR_BASE_Y - 14.0, 1.0),
+            GameEntity,
+        ));
+    }
+
+    commands.insert_resource(state);
+
+    commands.spawn((
+        Text::new("Day 1"),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(8.0), left: Val::Px(8.0), ..default() },
+        ScoreText,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Clicks and taps switch a tile on or off; S, W and B switch every solar,
+/// wind or battery tile at once.
+pub fn handle_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut state: ResMut<GameState>,
+) {
+    if state.finished { return; }
+
+    for (key, kind) in [(KeyCode::KeyS, Kind::Solar), (KeyCode::KeyW, Kind::Wind), (KeyCode::KeyB, Kind::Battery)] {
+        if !keys.just_pressed(key) { continue; }
+        let on = !state.tiles.iter().any(|t| t.kind == kind && t.active);
+        for tile in state.tiles.iter_mut().filter(|t| t.kind == kind) {
+            tile.active = on;
+        }
+    }
+
+    let screen_pos = if mouse.just_pressed(MouseButton::Left) {
+        windows.get_single().ok().and_then(|w| w.cursor_position())
+    } else {
+        touches.iter_just_pressed().next().map(|t| t.position())
+    };
+    let Some(screen_pos) = screen_pos else { return };
+    let Ok((camera, cam_tf)) = camera_q.get_single() else { return };
+    let Ok(cursor) = camera.viewport_to_world_2d(cam_tf, screen_pos) else { return };
+
+    // Find which grid cell was clicked
+    let gx = ((cursor.x - ORIGIN_X + TILE / 2.0) / TILE).floor() as i32;
+    let gy = ROWS - 1 - ((cursor.y - ORIGIN_Y + TILE / 2.0) / TILE).floor() as i32;
+    if gx < 0 || gx >= COLS || gy < 0 || gy >= ROWS { return; }
+    let tile = &mut state.tiles[(gy * COLS + gx) as usize];
+    tile.active = !tile.active;
+}
+
+/// Runs the grid for one frame: weather, demand, generation, batteries,
+/// then points and budget.
+pub fn simulate(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    mut state: ResMut<GameState>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+    mut failures: EventWriter<Failure>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+) {
+    if state.finished { return; }
+    let dt = time.delta_secs();
+    let hours = dt / DAY_SECS * 24.0;
+    state.clock += dt;
+    let h = hour(state.clock);
+    let day = &DAYS[state.day];
+
+    // Weather and demand drift
+    let mut rng = rand::thread_rng();
+    let drift = day.weather * hours.sqrt() * 4.0;
+    state.clouds = (state.clouds + rng.gen_range(-drift..=drift)).clamp(0.0, 0.9);
+    state.wind = (state.wind + rng.gen_range(-drift..=drift)).clamp(0.1, 1.0);
+    state.demand_noise = (state.demand_noise + rng.gen_range(-drift..=drift) * 10.0).clamp(-8.0, 8.0);
+    let demand = (base_demand(h) * day.demand + state.demand_noise).max(0.0);
+
+    // Generation, then batteries cover the gap or soak up the surplus
+    let sun = daylight(h) * (1.0 - state.clouds);
+    let wind = state.wind;
+    let mut supply = 0.0;
+    for tile in state.tiles.iter_mut() {
+        tile.output = match (tile.kind, tile.active) {
+            (Kind::Solar, true) => SOLAR_MW * sun,
+            (Kind::Wind, true) => WIND_MW * wind,
+            _ => 0.0,
+        };
+        supply += tile.output;
+    }
+    let mut balance = supply - demand;
+    for tile in state.tiles.iter_mut().filter(|t| t.kind == Kind::Battery) {
+        if tile.active && balance < 0.0 {
+            let mw = (-balance).min(BATTERY_MW).min(tile.charge / hours.max(1e-6));
+            tile.output = mw;
+            tile.charge -= mw * hours;
+            balance += mw;
+        } else if balance > 0.0 {
+            let mw = balance.min(BATTERY_MW).min((BATTERY_MWH - tile.charge) / hours.max(1e-6));
+            tile.charge += mw * hours;
+            balance -= mw;
+        }
+    }
+    state.demand = demand;
+    state.supply = demand + balance;
+
+    // Points and budget
+    let upkeep: f32 = state.tiles.iter().filter(|t| t.active).map(|t| upkeep(t.kind)).sum();
+    let shortfall = (-balance).max(0.0);
+    state.score += (SERVED_POINTS * (demand - shortfall) - BLACKOUT_POINTS * shortfall) * hours;
+    state.budget -= (upkeep + WASTE_COST * balance.max(0.0)) * hours;
+
+    let blackout = shortfall > demand * BLACKOUT_SHARE;
+    if blackout && !state.blackout {
+        state.blackouts += 1;
+        failures.send(Failure(Vec2::new(CITY_X, CITY_Y)));
+    }
+    state.blackout = blackout;
+
+    if state.budget <= 0.0 && mode.can_lose() {
+        state.budget = 0.0;
+        state.finished = true;
+        state.message = "Out of budget!".into();
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+
+    if state.clock < DAY_SECS { return; }
+    analytics.send(AnalyticsEvent::level_complete(state.day, state.score as i32));
+    if state.day + 1 >= DAYS.len() {
+        state.finished = true;
+        next_state.set(crate::AppState::GameOver);
+        return;
+    }
+    let next = state.day + 1;
+    start_day(&mut state, next);
+    state.message = format!("Day {} begins", next + 1);
+}
+
+pub fn update_tiles(
+    state: Res<GameState>,
+    mut tile_q: Query<(&TileVisual, &mut Sprite), Without<TileFill>>,
+    mut fill_q: Query<(&TileFill, &mut Transform, &mut Sprite), Without<TileVisual>>,
+) {
+    for (visual, mut sprite) in &mut tile_q {
+        sprite.color = tile_color(&state.tiles[visual.0]);
+    }
+
+    // Output share for generators, charge for batteries, filling from the left
+    let full = TILE - 20.0;
+    for (fill, mut tf, mut sprite) in &mut fill_q {
+        let tile = &state.tiles[fill.0];
+        let share = match tile.kind {
+            Kind::Solar => tile.output / SOLAR_MW,
+            Kind::Wind => tile.output / WIND_MW,
+            Kind::Battery => tile.charge / BATTERY_MWH,
+        };
+        let width = full * share.clamp(0.0, 1.0);
+        sprite.custom_size = Some(Vec2::new(width, 6.0));
+        tf.translation.x = (width - full) / 2.0;
+    }
+}
+
+pub fn update_city(
+    state: Res<GameState>,
+    mut city_q: Query<&mut Sprite, (With<City>, Without<Sky>, Without<Bar>)>,
+    mut sky_q: Query<&mut Sprite, (With<Sky>, Without<Bar>)>,
+    mut bar_q: Query<(&Bar, &mut Transform, &mut Sprite)>,
+) {
+    for mut sprite in &mut city_q {
+        sprite.color = if state.blackout { palette::SHADOW } else { palette::HERO_ORANGE };
+    }
+
+    let sun = daylight(hour(state.clock));
+    for mut sprite in &mut sky_q {
+        sprite.color = palette::NIGHT_BG.mix(&palette::SKY_BLUE, sun);
+    }
+
+    for (bar, mut tf, mut sprite) in &mut bar_q {
+        let mw = match bar {
+            Bar::Demand => state.demand,
+            Bar::Supply => state.supply,
+        };
+        let height = mw * BAR_SCALE;
+        sprite.custom_size = Some(Vec2::new(BAR_WIDTH, height));
+        tf.translation.y = BAR_BASE_Y + height / 2.0;
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.score.max(0.0) as i32;
+    bridge.stats.level = Some(state.day as i32 + 1);
+    bridge.stats.deaths = Some(state.blackouts);
+}
+
+pub fn update_hud(state: Res<GameState>, mut q: Query<&mut Text, With<ScoreText>>) {
+    let h = hour(state.clock);
+    let status = if state.blackout { "BLACKOUT" } else { &state.message };
+    for mut t in &mut q {
+        **t = format!(
+            "Day {} {:02}:{:02} | Demand {:.0} MW | Supply {:.0} MW | Budget {:.0} | Score {:.0} | Blackouts {}  {}",
+            state.day + 1,
+            h as u32,
+            (h.fract() * 60.0) as u32,
+            state.demand,
+            state.supply,
+            state.budget,
+            state.score.max(0.0),
+            state.blackouts,
+            status,
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q { commands.entity(e).despawn_recursive(); }
+    commands.remove_resource::<GameState>();
+}
@@ -5,11 +5,12 @@ use crate::BevyBridge;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
 
+use super::grid::Grid;
+
 const COLS: i32 = 6;
 const ROWS: i32 = 6;
 const TILE: f32 = 64.0;
-const ORIGIN_X: f32 = -((COLS as f32) * TILE) / 2.0 + TILE / 2.0;
-const ORIGIN_Y: f32 = -((ROWS as f32) * TILE) / 2.0 + TILE / 2.0;
+const GRID: Grid = Grid { cols: COLS, rows: ROWS, cell: Vec2::splat(TILE), center: Vec2::ZERO };
 
 #[derive(Component)]
 pub struct GameEntity;
@@ -21,7 +22,7 @@ enum PipeType { Straight, Corner, Tjunction, Cross }
 enum TileRole { Pipe, Source, Sink }
 
 #[derive(Component)]
-struct Pipe {
+pub struct Pipe {
     pipe_type: PipeType,
     rotation: u8, // 0..3 (x90 degrees)
     gx: i32,
@@ -31,19 +32,19 @@ struct Pipe {
 }
 
 #[derive(Component)]
-struct ConnectorVisual { gx: i32, gy: i32 }
+pub struct ConnectorVisual { gx: i32, gy: i32 }
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     won: bool,
 }
 
 fn wp(gx: i32, gy: i32, z: f32) -> Vec3 {
-    Vec3::new(ORIGIN_X + gx as f32 * TILE, ORIGIN_Y + gy as f32 * TILE, z)
+    GRID.wp(gx, gy, z)
 }
 
 fn connections(pipe_type: PipeType, rotation: u8) -> [bool; 4] {
@@ -217,8 +218,7 @@ pub fn handle_click(
     let Some(cursor) = window.cursor_position().and_then(|p| camera.viewport_to_world_2d(cam_tf, p).ok()) else { return };
 
     // Find which grid cell was clicked
-    let gx = ((cursor.x - ORIGIN_X + TILE / 2.0) / TILE).floor() as i32;
-    let gy = ((cursor.y - ORIGIN_Y + TILE / 2.0) / TILE).floor() as i32;
+    let (gx, gy) = GRID.cell_at(cursor);
 
     for mut pipe in &mut pq {
         if pipe.gx == gx && pipe.gy == gy {
@@ -0,0 +1,374 @@
+//! Rover 3D Expedition — drive a rover over rolling 3-D terrain and pick
+//! up rock samples before its battery runs flat.
+//!
+//! The first 3-D game, and the one to copy for the next (see
+//! [`scene3d`](crate::scene3d)).  The rover is the glTF model named
+//! [`ROVER_MODEL`] when the shell preloads or uploads one — about 2 m
+//! long and facing `-Z` — and a boxy stand-in otherwise.
+
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{BevyBridge, GameOptions};
+use crate::diagnostics::Failure;
+use crate::input::{ActionState, Rumble};
+use crate::levelgen::{self, LevelRng};
+use crate::preload::Preloaded;
+use crate::scene3d::{self, ChaseCamera, ChaseTarget};
+
+pub const GAME_ID: &str = "rover_3d_expedition";
+/// Name of the rover model in [`Preloaded::models`].
+pub const ROVER_MODEL: &str = "rover";
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+const TERRAIN_SIZE: f32 = 120.0;
+const TERRAIN_CELLS: u32 = 96;
+/// How close to the edge the rover may drive.
+const EDGE_MARGIN: f32 = 3.0;
+const MAX_SPEED: f32 = 12.0;
+const MAX_REVERSE: f32 = 4.0;
+const ACCEL: f32 = 8.0;
+const DRAG: f32 = 3.0;
+/// Turn rate at full speed, in radians per second.
+const TURN_RATE: f32 = 1.8;
+const BATTERY_MAX: f32 = 100.0;
+/// Battery used per second just for being switched on.
+const BATTERY_IDLE: f32 = 0.5;
+/// Extra per second at full throttle.
+const BATTERY_DRIVE: f32 = 2.5;
+/// Extra per second per unit of uphill slope.
+const BATTERY_CLIMB: f32 = 6.0;
+const SAMPLE_COUNT: usize = 8;
+const SAMPLE_RADIUS: f32 = 1.8;
+const SAMPLE_CHARGE: f32 = 15.0;
+const SAMPLE_POINTS: i32 = 100;
+/// Samples aren't placed this close to the rover.
+const SAMPLE_MIN_DISTANCE: f32 = 10.0;
+
+const SKY: Color = Color::srgb(0.85, 0.62, 0.45);
+const SOIL: Color = Color::srgb(0.7, 0.36, 0.2);
+const SAMPLE_GLOW: Color = Color::srgb(0.3, 0.9, 1.0);
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+pub struct GameEntity;
+
+#[derive(Component)]
+pub struct Rover {
+    /// Metres per second; negative in reverse.
+    speed: f32,
+    /// Radians about `+Y`; `0.0` faces `-Z`.
+    heading: f32,
+    battery: f32,
+}
+
+#[derive(Component)]
+pub struct Sample;
+
+#[derive(Component)]
+pub struct BatteryText;
+
+#[derive(Component)]
+pub struct SamplesText;
+
+/// The run's terrain and progress.
+#[derive(Resource)]
+pub struct GameState {
+    terrain: Terrain,
+    rng: LevelRng,
+    distance: f32,
+    samples: i32,
+    sample_mesh: Handle<Mesh>,
+    sample_material: Handle<StandardMaterial>,
+}
+
+/// Rolling hills as a sum of waves, shifted per run.
+#[derive(Clone, Copy)]
+struct Terrain {
+    phase: [f32; 4],
+}
+
+impl Terrain {
+    fn height(&self, x: f32, z: f32) -> f32 {
+        let p = self.phase;
+        3.0 * (x * 0.05 + p[0]).sin() * (z * 0.045 + p[1]).cos()
+            + 1.2 * (x * 0.13 + p[2]).sin() * (z * 0.11 + p[3]).sin()
+            + 0.3 * ((x + z) * 0.4 + p[0]).sin()
+    }
+
+    fn normal(&self, x: f32, z: f32) -> Vec3 {
+        scene3d::surface_normal(|x, z| self.height(x, z), x, z, 0.5)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Setup
+// ---------------------------------------------------------------------------
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    preloaded: Res<Preloaded>,
+    gltfs: Res<Assets<Gltf>>,
+    options: Res<GameOptions>,
+) {
+    let mut rng = levelgen::run_rng(&options);
+    let terrain = Terrain {
+        phase: std::array::from_fn(|_| rng.gen_range(0.0..std::f32::consts::TAU)),
+    };
+
+    scene3d::spawn_camera(
+        &mut commands,
+        SKY,
+        Transform::from_xyz(0.0, 8.0, 12.0).looking_at(Vec3::ZERO, Vec3::Y),
+        (ChaseCamera::new(10.0, 5.0), GameEntity),
+    );
+    scene3d::spawn_lighting(&mut commands, GameEntity);
+
+    // Terrain
+    commands.spawn((
+        Mesh3d(meshes.add(scene3d::heightmap_mesh(TERRAIN_SIZE, TERRAIN_CELLS, |x, z| terrain.height(x, z)))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: SOIL,
+            perceptual_roughness: 0.95,
+            ..default()
+        })),
+        GameEntity,
+    ));
+
+    // Rover
+    let rover_model = scene3d::model_scene(&preloaded, &gltfs, ROVER_MODEL);
+    commands
+        .spawn((
+            Transform::from_xyz(0.0, terrain.height(0.0, 0.0), 0.0),
+            Visibility::default(),
+            Rover { speed: 0.0, heading: 0.0, battery: BATTERY_MAX },
+            ChaseTarget,
+            GameEntity,
+        ))
+        .with_children(|rover| match rover_model {
+            Some(scene) => {
+                rover.spawn(SceneRoot(scene));
+            }
+            None => spawn_stand_in(rover, &mut meshes, &mut materials),
+        });
+
+    // Samples
+    let sample_mesh = meshes.add(Sphere::new(0.5));
+    let sample_material = materials.add(StandardMaterial {
+        base_color: SAMPLE_GLOW,
+        emissive: LinearRgba::from(SAMPLE_GLOW) * 2.0,
+        ..default()
+    });
+    let mut state = GameState {
+        terrain,
+        rng,
+        distance: 0.0,
+        samples: 0,
+        sample_mesh,
+        sample_material,
+    };
+    for _ in 0..SAMPLE_COUNT {
+        spawn_sample(&mut commands, &mut state, Vec3::ZERO);
+    }
+    commands.insert_resource(state);
+
+    // HUD
+    commands.spawn((
+        Text::new("Battery: 100%"), TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.2, 0.9, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(10.0), left: Val::Px(10.0), ..default() },
+        BatteryText, GameEntity,
+    ));
+    commands.spawn((
+        Text::new("Samples: 0"), TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(0.9, 0.85, 0.3)),
+        Node { position_type: PositionType::Absolute, top: Val::Px(35.0), left: Val::Px(10.0), ..default() },
+        SamplesText, GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// A body on four wheels, for when there's no rover model.
+fn spawn_stand_in(
+    rover: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let body = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.92, 0.92, 0.88),
+        metallic: 0.3,
+        ..default()
+    });
+    let tyre = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.12, 0.12, 0.14),
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+    rover.spawn((
+        Mesh3d(meshes.add(Cuboid::new(1.2, 0.5, 2.0))),
+        MeshMaterial3d(body),
+        Transform::from_xyz(0.0, 0.75, 0.0),
+    ));
+    let wheel = meshes.add(Cylinder::new(0.35, 0.25));
+    for (x, z) in [(-0.7, -0.7), (0.7, -0.7), (-0.7, 0.7), (0.7, 0.7)] {
+        rover.spawn((
+            Mesh3d(wheel.clone()),
+            MeshMaterial3d(tyre.clone()),
+            Transform::from_xyz(x, 0.35, z).with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+        ));
+    }
+}
+
+/// Places a sample somewhere on the terrain away from `avoid`.
+fn spawn_sample(commands: &mut Commands, state: &mut GameState, avoid: Vec3) {
+    let reach = TERRAIN_SIZE / 2.0 - EDGE_MARGIN * 2.0;
+    let (x, z) = loop {
+        let x = state.rng.gen_range(-reach..reach);
+        let z = state.rng.gen_range(-reach..reach);
+        if Vec2::new(x - avoid.x, z - avoid.z).length() >= SAMPLE_MIN_DISTANCE {
+            break (x, z);
+        }
+    };
+    commands.spawn((
+        Mesh3d(state.sample_mesh.clone()),
+        MeshMaterial3d(state.sample_material.clone()),
+        Transform::from_xyz(x, state.terrain.height(x, z) + 0.8, z),
+        Sample,
+        GameEntity,
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+pub fn drive(
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    mut state: ResMut<GameState>,
+    mut q: Query<(&mut Transform, &mut Rover)>,
+) {
+    let dt = time.delta_secs();
+    let Ok((mut tf, mut r)) = q.get_single_mut() else { return };
+    let throttle = if r.battery > 0.0 { actions.movement.y } else { 0.0 };
+
+    r.speed = (r.speed + throttle * ACCEL * dt).clamp(-MAX_REVERSE, MAX_SPEED);
+    if throttle == 0.0 {
+        r.speed = r.speed.signum() * (r.speed.abs() - DRAG * dt).max(0.0);
+    }
+    // Wheeled, so it only turns while moving
+    r.heading -= actions.movement.x * TURN_RATE * (r.speed / MAX_SPEED).clamp(-1.0, 1.0) * dt;
+
+    let forward = Quat::from_rotation_y(r.heading) * Vec3::NEG_Z;
+    let limit = TERRAIN_SIZE / 2.0 - EDGE_MARGIN;
+    let before = tf.translation;
+    let mut next = before + forward * r.speed * dt;
+    if next.x.abs() > limit || next.z.abs() > limit {
+        next.x = next.x.clamp(-limit, limit);
+        next.z = next.z.clamp(-limit, limit);
+        r.speed = 0.0;
+    }
+    tf.translation.x = next.x;
+    tf.translation.z = next.z;
+    state.distance += Vec2::new(next.x - before.x, next.z - before.z).length();
+
+    let terrain = state.terrain;
+    let climb = (terrain.height(next.x, next.z) - terrain.height(before.x, before.z)).max(0.0);
+    let slope = if dt > 0.0 { climb / dt / MAX_SPEED } else { 0.0 };
+    let drain = BATTERY_IDLE + throttle.abs() * BATTERY_DRIVE + slope * BATTERY_CLIMB;
+    r.battery = (r.battery - drain * dt).max(0.0);
+}
+
+pub fn follow_terrain(state: Res<GameState>, mut q: Query<(&mut Transform, &Rover)>) {
+    for (mut tf, r) in &mut q {
+        let (x, z) = (tf.translation.x, tf.translation.z);
+        tf.translation.y = state.terrain.height(x, z);
+        let tilt = Quat::from_rotation_arc(Vec3::Y, state.terrain.normal(x, z));
+        tf.rotation = tilt * Quat::from_rotation_y(r.heading);
+    }
+}
+
+pub fn collect_samples(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mut rover_q: Query<(&Transform, &mut Rover)>,
+    samples: Query<(Entity, &Transform), With<Sample>>,
+    mut rumble: EventWriter<Rumble>,
+) {
+    let Ok((rover_tf, mut r)) = rover_q.get_single_mut() else { return };
+    for (entity, tf) in &samples {
+        if tf.translation.distance(rover_tf.translation) > SAMPLE_RADIUS + 0.8 {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        state.samples += 1;
+        r.battery = (r.battery + SAMPLE_CHARGE).min(BATTERY_MAX);
+        rumble.send(Rumble::light());
+        spawn_sample(&mut commands, &mut state, rover_tf.translation);
+    }
+}
+
+pub fn spin_samples(time: Res<Time>, mut q: Query<&mut Transform, With<Sample>>) {
+    for mut tf in &mut q {
+        tf.rotate_y(1.5 * time.delta_secs());
+    }
+}
+
+/// Flat-battery positions are reported as `(x, z)` on the terrain.
+pub fn check_game_over(
+    rover_q: Query<(&Transform, &Rover)>,
+    mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
+) {
+    if let Ok((tf, r)) = rover_q.get_single() {
+        if r.battery <= 0.0 && r.speed.abs() <= 0.1 {
+            failures.send(Failure(Vec2::new(tf.translation.x, tf.translation.z)));
+            next_state.set(crate::AppState::GameOver);
+        }
+    }
+}
+
+pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = state.samples * SAMPLE_POINTS + state.distance as i32;
+    bridge.stats.distance = Some(state.distance as i64);
+    bridge.stats.collectibles = Some(state.samples);
+}
+
+pub fn update_hud(
+    rover_q: Query<&Rover>,
+    state: Res<GameState>,
+    mut battery_q: Query<&mut Text, (With<BatteryText>, Without<SamplesText>)>,
+    mut samples_q: Query<&mut Text, (With<SamplesText>, Without<BatteryText>)>,
+) {
+    if let Ok(r) = rover_q.get_single() {
+        for mut t in &mut battery_q {
+            **t = format!("Battery: {}%", r.battery.ceil() as i32);
+        }
+    }
+    for mut t in &mut samples_q {
+        **t = format!("Samples: {}", state.samples);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<GameState>();
+}
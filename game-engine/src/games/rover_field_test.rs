@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
-use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
+use crate::pixar::{PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
 
 // ---------------------------------------------------------------------------
@@ -31,24 +34,24 @@ const FUEL_ACCEL_DRAIN: f32 = 8.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Rover {
+pub struct Rover {
     velocity: f32,
     fuel: f32,
 }
 
 #[derive(Component)]
-struct TerrainSegment {
+pub struct TerrainSegment {
     index: i32,
 }
 
 #[derive(Component)]
-struct FuelText;
+pub struct FuelText;
 
 #[derive(Component)]
-struct DistText;
+pub struct DistText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     distance: f32,
     scroll_offset: f32,
     phase: f32,
@@ -58,7 +61,12 @@ struct GameState {
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
     commands.insert_resource(GameState {
         distance: 0.0,
         scroll_offset: 0.0,
@@ -81,9 +89,10 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     }
 
     // Rover
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::vehicle(palette::HERO_ORANGE, ROVER_SIZE),
         Vec3::new(ROVER_X, GROUND_Y, 2.0),
         (Rover { velocity: BASE_SPEED, fuel: FUEL_MAX }, GameEntity),
@@ -128,15 +137,15 @@ fn terrain_height(x_world: f32, phase: f32) -> f32 {
 // ---------------------------------------------------------------------------
 
 pub fn rover_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
     time: Res<Time>,
     mut q: Query<&mut Rover>,
 ) {
     let dt = time.delta_secs();
     for mut r in &mut q {
-        let accel = keys.pressed(KeyCode::ArrowRight) || mouse.pressed(MouseButton::Left);
-        let brake = keys.pressed(KeyCode::ArrowLeft);
+        let accel = input.pressed(Control::MoveRight) || mouse.pressed(MouseButton::Left);
+        let brake = input.pressed(Control::MoveLeft);
         if accel && r.fuel > 0.0 {
             r.velocity = (r.velocity + ACCEL * dt).min(MAX_SPEED);
             r.fuel = (r.fuel - FUEL_ACCEL_DRAIN * dt).max(0.0);
@@ -191,12 +200,16 @@ pub fn rover_follow_terrain(
     }
 }
 
+/// Stranded positions are reported along the terrain, not the screen.
 pub fn check_game_over(
-    rover_q: Query<&Rover>,
+    state: Res<GameState>,
+    rover_q: Query<(&Transform, &Rover)>,
     mut next_state: ResMut<NextState<crate::AppState>>,
+    mut failures: EventWriter<Failure>,
 ) {
-    if let Ok(r) = rover_q.get_single() {
+    if let Ok((tf, r)) = rover_q.get_single() {
         if r.fuel <= 0.0 && r.velocity <= 0.1 {
+            failures.send(Failure(Vec2::new(state.scroll_offset + ROVER_X, tf.translation.y)));
             next_state.set(crate::AppState::GameOver);
         }
     }
@@ -204,6 +217,7 @@ pub fn check_game_over(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = (state.distance / 10.0) as i32;
+    bridge.stats.distance = GameStats::metres(state.distance);
 }
 
 pub fn update_hud(
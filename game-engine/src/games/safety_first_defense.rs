@@ -1,9 +1,14 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::pool::{Parked, Pool};
+use super::boss::{self, Boss, BossShot, BossSpec, BossTarget, Spawn, Waves};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -16,18 +21,35 @@ const ENEMY_SIZE: Vec2 = Vec2::new(28.0, 28.0);
 const BULLET_SIZE: Vec2 = Vec2::new(6.0, 12.0);
 const ENEMY_SPEED: f32 = 60.0;
 const BULLET_SPEED: f32 = 500.0;
+const MAX_HP: i32 = 5;
 const SPAWN_INTERVAL: f32 = 1.8;
 const ENEMY_SHOOT_INTERVAL: f32 = 2.0;
+const COVER_SIZE: Vec2 = Vec2::new(70.0, 50.0);
+const BOSS_EVERY: u32 = 2;
+
+static BOSS: BossSpec = BossSpec {
+    name: "Hazard Bot",
+    size: Vec2::new(150.0, 80.0),
+    color: palette::VILLAIN_RED,
+    hp: 30,
+    weak_points: &[Vec2::new(-50.0, -30.0), Vec2::new(50.0, -30.0)],
+    anchor: Vec2::new(0.0, 220.0),
+    sweep: Vec2::new(260.0, 25.0),
+    fire_interval: 2.0,
+    shot_speed: 180.0,
+    phase_bonus: 600,
+    defeat_bonus: 3000,
+};
 
 // ---------------------------------------------------------------------------
 // Components
 // ---------------------------------------------------------------------------
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player {
+pub struct Player {
     cover_index: usize,
     exposed: bool,
     hp: i32,
@@ -35,13 +57,13 @@ struct Player {
 }
 
 #[derive(Component)]
-struct Enemy {
+pub struct Enemy {
     hp: i32,
     shoot_timer: f32,
 }
 
 #[derive(Component)]
-struct Bullet {
+pub struct Bullet {
     friendly: bool,
     vy: f32,
 }
@@ -50,20 +72,29 @@ struct Bullet {
 struct CoverBlock;
 
 #[derive(Component)]
-struct HudText;
+pub struct HudText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     spawn_timer: f32,
+    waves: Waves,
+    shots: i32,
+    hits: i32,
 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
-    commands.insert_resource(GameState { score: 0, spawn_timer: 0.0 });
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+) {
+    commands.insert_resource(GameState { score: 0, spawn_timer: 0.0, waves: Waves::default(), shots: 0, hits: 0 });
+    commands.insert_resource(Pool::<Bullet>::default());
 
     // Background
     if let Some(ref bg) = custom_assets.background {
@@ -81,18 +112,19 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
     // Cover blocks
     for &cx in &COVER_POSITIONS {
         commands.spawn((
-            Sprite { color: Color::srgb(0.35, 0.35, 0.4), custom_size: Some(Vec2::new(70.0, 50.0)), ..default() },
+            Sprite { color: Color::srgb(0.35, 0.35, 0.4), custom_size: Some(COVER_SIZE), ..default() },
             Transform::from_xyz(cx, COVER_Y, 0.5), CoverBlock, GameEntity,
         ));
     }
 
     // Player
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_GREEN, PLAYER_SIZE),
         Vec3::new(COVER_POSITIONS[1], COVER_Y, 1.0),
-        (Player { cover_index: 1, exposed: false, hp: 5, ammo: 15 }, GameEntity),
+        (Player { cover_index: 1, exposed: false, hp: MAX_HP, ammo: 15 }, BossTarget, GameEntity),
     );
 
     // HUD
@@ -103,6 +135,7 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Node { position_type: PositionType::Absolute, top: Val::Px(10.0), left: Val::Px(10.0), ..default() },
         HudText, GameEntity,
     ));
+    boss::spawn_health_bar(&mut commands, GameEntity);
 }
 
 // ---------------------------------------------------------------------------
@@ -110,29 +143,32 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
 // ---------------------------------------------------------------------------
 
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mouse: Res<ButtonInput<MouseButton>>,
     pixar_assets: Res<PixarAssets>,
+    mut state: ResMut<GameState>,
     mut player_q: Query<(&mut Player, &mut Transform)>,
+    mut bullets: ResMut<Pool<Bullet>>,
     mut commands: Commands,
 ) {
     for (mut p, mut tf) in &mut player_q {
-        if keys.just_pressed(KeyCode::ArrowLeft) || keys.just_pressed(KeyCode::KeyA) {
+        if input.just_pressed(Control::MoveLeft) {
             if p.cover_index > 0 { p.cover_index -= 1; }
         }
-        if keys.just_pressed(KeyCode::ArrowRight) || keys.just_pressed(KeyCode::KeyD) {
+        if input.just_pressed(Control::MoveRight) {
             if p.cover_index < 2 { p.cover_index += 1; }
         }
-        p.exposed = keys.pressed(KeyCode::Space);
+        p.exposed = input.pressed(Control::Action);
         tf.translation.x = COVER_POSITIONS[p.cover_index];
         tf.translation.y = if p.exposed { COVER_Y + 45.0 } else { COVER_Y };
 
         // Shoot
-        if (mouse.just_pressed(MouseButton::Left) || keys.just_pressed(KeyCode::KeyF))
+        if (mouse.just_pressed(MouseButton::Left) || input.just_pressed(Control::Fire))
             && p.exposed && p.ammo > 0
         {
             p.ammo -= 1;
-            commands.spawn((
+            state.shots += 1;
+            bullets.acquire(&mut commands, (
                 Sprite { color: palette::HERO_YELLOW, custom_size: Some(BULLET_SIZE), ..default() },
                 Transform::from_xyz(tf.translation.x, tf.translation.y + 25.0, 2.0),
                 Bullet { friendly: true, vy: BULLET_SPEED }, GameEntity,
@@ -140,7 +176,7 @@ pub fn player_input(
         }
 
         // Reload
-        if keys.just_pressed(KeyCode::KeyR) {
+        if input.just_pressed(Control::Reload) {
             p.ammo = (p.ammo + 5).min(20);
         }
     }
@@ -150,6 +186,14 @@ pub fn spawn_enemies(time: Res<Time>, mut state: ResMut<GameState>, pixar_assets
     state.spawn_timer += time.delta_secs();
     if state.spawn_timer >= SPAWN_INTERVAL {
         state.spawn_timer = 0.0;
+        match state.waves.next(BOSS_EVERY) {
+            Spawn::Enemy => {}
+            Spawn::Boss => {
+                boss::spawn_boss(&mut commands, &pixar_assets, &BOSS, state.waves.bosses_defeated, GameEntity);
+                return;
+            }
+            Spawn::Hold => return,
+        }
         let mut rng = rand::thread_rng();
         let x = rng.gen_range(-300.0..300.0);
         pixar::spawn_character(
@@ -166,6 +210,7 @@ pub fn move_enemies(
     time: Res<Time>,
     player_q: Query<&Player>,
     mut enemy_q: Query<(&mut Transform, &mut Enemy)>,
+    mut bullets: ResMut<Pool<Bullet>>,
     mut commands: Commands,
 ) {
     let dt = time.delta_secs();
@@ -176,7 +221,7 @@ pub fn move_enemies(
             e.shoot_timer -= dt;
             if e.shoot_timer <= 0.0 {
                 e.shoot_timer = ENEMY_SHOOT_INTERVAL;
-                commands.spawn((
+                bullets.acquire(&mut commands, (
                     Sprite { color: palette::VILLAIN_RED, custom_size: Some(BULLET_SIZE), ..default() },
                     Transform::from_xyz(tf.translation.x, tf.translation.y - 15.0, 2.0),
                     Bullet { friendly: false, vy: -BULLET_SPEED * 0.6 }, GameEntity,
@@ -186,7 +231,7 @@ pub fn move_enemies(
     }
 }
 
-pub fn move_bullets(time: Res<Time>, mut q: Query<(&mut Transform, &Bullet)>) {
+pub fn move_bullets(time: Res<Time>, mut q: Query<(&mut Transform, &Bullet), Without<Parked>>) {
     let dt = time.delta_secs();
     for (mut tf, b) in &mut q {
         tf.translation.y += b.vy * dt;
@@ -196,14 +241,16 @@ pub fn move_bullets(time: Res<Time>, mut q: Query<(&mut Transform, &Bullet)>) {
 pub fn bullet_collisions(
     mut commands: Commands,
     mut state: ResMut<GameState>,
-    bullet_q: Query<(Entity, &Transform, &Bullet)>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    bullet_q: Query<(Entity, &Transform, &Bullet), Without<Parked>>,
     mut enemy_q: Query<(Entity, &Transform, &mut Enemy)>,
     mut player_q: Query<&mut Player>,
+    mut failures: EventWriter<Failure>,
 ) {
     for (be, btf, bullet) in &bullet_q {
         // Off-screen cleanup
         if btf.translation.y > 350.0 || btf.translation.y < -350.0 {
-            commands.entity(be).despawn();
+            bullets.release(&mut commands, be);
             continue;
         }
         if bullet.friendly {
@@ -212,7 +259,8 @@ pub fn bullet_collisions(
                 let dy = (btf.translation.y - etf.translation.y).abs();
                 if dx < 20.0 && dy < 20.0 {
                     en.hp -= 1;
-                    commands.entity(be).despawn();
+                    state.hits += 1;
+                    bullets.release(&mut commands, be);
                     if en.hp <= 0 {
                         commands.entity(ee).despawn();
                         state.score += 100;
@@ -228,23 +276,75 @@ pub fn bullet_collisions(
                 let dy = (btf.translation.y - py).abs();
                 if dx < 20.0 && dy < 25.0 {
                     p.hp -= 1;
-                    commands.entity(be).despawn();
+                    failures.send(Failure(Vec2::new(px, py)));
+                    bullets.release(&mut commands, be);
                 }
             }
         }
     }
 }
 
+/// Friendly bullets against the boss, and its shots against the player.
+/// Cover absorbs shots; the player is only hit while exposed.
+#[allow(clippy::too_many_arguments)]
+pub fn boss_collisions(
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mut bullets: ResMut<Pool<Bullet>>,
+    bullet_q: Query<(Entity, &Transform, &Bullet), Without<Parked>>,
+    shot_q: Query<(Entity, &Transform), (With<BossShot>, With<GameEntity>)>,
+    mut boss_q: Query<(Entity, &Transform, &mut Boss), With<GameEntity>>,
+    mut player_q: Query<&mut Player>,
+    mut failures: EventWriter<Failure>,
+) {
+    let Ok((boss_e, boss_tf, mut boss)) = boss_q.get_single_mut() else { return };
+    let boss_pos = boss_tf.translation.truncate();
+
+    for (be, btf, bullet) in &bullet_q {
+        if !bullet.friendly { continue; }
+        let Some(damage) = boss.damage_at(boss_pos, btf.translation.truncate()) else { continue };
+        bullets.release(&mut commands, be);
+        state.hits += 1;
+        let hit = boss.take_damage(damage);
+        state.score += hit.points;
+        if hit.defeated {
+            commands.entity(boss_e).despawn_recursive();
+            state.waves.boss_defeated();
+            return;
+        }
+    }
+
+    let Ok(mut p) = player_q.get_single_mut() else { return };
+    for (se, stf) in &shot_q {
+        let pos = stf.translation.truncate();
+        let covered = COVER_POSITIONS.iter().any(|&cx| {
+            (pos.x - cx).abs() < COVER_SIZE.x / 2.0 && (pos.y - COVER_Y).abs() < COVER_SIZE.y / 2.0
+        });
+        let player_hit = p.exposed
+            && (pos.x - COVER_POSITIONS[p.cover_index]).abs() < 20.0
+            && (pos.y - (COVER_Y + 45.0)).abs() < 25.0;
+        if player_hit {
+            p.hp -= 1;
+            failures.send(Failure(Vec2::new(COVER_POSITIONS[p.cover_index], COVER_Y + 45.0)));
+        }
+        if covered || player_hit {
+            commands.entity(se).despawn();
+        }
+    }
+}
+
 pub fn enemy_reach_bottom(
     mut commands: Commands,
     enemy_q: Query<(Entity, &Transform), With<Enemy>>,
     mut player_q: Query<&mut Player>,
+    mut failures: EventWriter<Failure>,
 ) {
     for (e, tf) in &enemy_q {
         if tf.translation.y < COVER_Y - 30.0 {
             commands.entity(e).despawn();
             if let Ok(mut p) = player_q.get_single_mut() {
                 p.hp -= 1;
+                failures.send(Failure(tf.translation.truncate()));
             }
         }
     }
@@ -261,8 +361,13 @@ pub fn check_game_over(
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
+pub fn update_score(state: Res<GameState>, player_q: Query<&Player>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    bridge.stats.level = Some(state.waves.wave as i32 + 1);
+    if let Ok(p) = player_q.get_single() {
+        bridge.stats.deaths = Some(MAX_HP - p.hp.max(0));
+    }
 }
 
 pub fn update_hud(
@@ -282,6 +387,7 @@ pub fn update_hud(
 // ---------------------------------------------------------------------------
 
 pub fn cleanup(mut commands: Commands, q: Query<Entity, With<GameEntity>>) {
-    for e in &q { commands.entity(e).despawn(); }
+    for e in &q { commands.entity(e).despawn_recursive(); }
     commands.remove_resource::<GameState>();
+    commands.remove_resource::<Pool<Bullet>>();
 }
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::{BevyBridge, GameStats};
+use crate::input::{ActionState, Control};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
 use crate::asset_loader::CustomAssets;
 
@@ -31,7 +33,7 @@ const BASE_INTERVAL: f32 = 0.5;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Note {
+pub struct Note {
     lane: usize,
 }
 
@@ -44,15 +46,16 @@ struct LaneIndicator {
 }
 
 #[derive(Component)]
-struct ComboText;
+pub struct ComboText;
 
 #[derive(Component)]
-struct ScoreText;
+pub struct ScoreText;
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     combo: i32,
+    hits: i32,
     misses: i32,
     spawn_timer: f32,
     speed: f32,
@@ -76,11 +79,11 @@ fn lane_color(lane: usize) -> Color {
     }
 }
 
-const LANE_KEYS: [KeyCode; 4] = [
-    KeyCode::ArrowLeft,
-    KeyCode::ArrowDown,
-    KeyCode::ArrowUp,
-    KeyCode::ArrowRight,
+const LANE_CONTROLS: [Control; 4] = [
+    Control::MoveLeft,
+    Control::MoveDown,
+    Control::MoveUp,
+    Control::MoveRight,
 ];
 
 // ---------------------------------------------------------------------------
@@ -89,7 +92,7 @@ const LANE_KEYS: [KeyCode; 4] = [
 
 pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
     commands.insert_resource(GameState {
-        score: 0, combo: 0, misses: 0,
+        score: 0, combo: 0, hits: 0, misses: 0,
         spawn_timer: 0.0, speed: BASE_SPEED, elapsed: 0.0,
     });
 
@@ -174,13 +177,13 @@ pub fn move_notes(time: Res<Time>, state: Res<GameState>, mut q: Query<&mut Tran
 }
 
 pub fn player_input(
-    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<ActionState>,
     mut commands: Commands,
     mut state: ResMut<GameState>,
     note_q: Query<(Entity, &Transform, &Note)>,
 ) {
-    for (lane_idx, &key) in LANE_KEYS.iter().enumerate() {
-        if !keys.just_pressed(key) { continue; }
+    for (lane_idx, &control) in LANE_CONTROLS.iter().enumerate() {
+        if !input.just_pressed(control) { continue; }
 
         // Find closest note in this lane near the hit line
         let mut best: Option<(Entity, f32)> = None;
@@ -205,6 +208,7 @@ pub fn player_input(
             let multiplier = 1.0 + (state.combo as f32) / 10.0;
             state.score += (points as f32 * multiplier) as i32;
             state.combo += 1;
+            state.hits += 1;
             commands.entity(entity).despawn();
         } else {
             state.combo = 0;
@@ -217,9 +221,11 @@ pub fn missed_notes(
     mut commands: Commands,
     mut state: ResMut<GameState>,
     note_q: Query<(Entity, &Transform), With<Note>>,
+    mut failures: EventWriter<Failure>,
 ) {
     for (e, tf) in &note_q {
         if tf.translation.y < HIT_LINE_Y - OK_DIST - 20.0 {
+            failures.send(Failure(tf.translation.truncate()));
             commands.entity(e).despawn();
             state.combo = 0;
             state.misses += 1;
@@ -238,6 +244,7 @@ pub fn check_game_over(
 
 pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
     bridge.current_score = state.score;
+    bridge.stats.accuracy = GameStats::percent(state.hits, state.hits + state.misses);
 }
 
 pub fn update_hud(
@@ -1,9 +1,14 @@
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::BevyBridge;
+use crate::ai::{Aim, Bot};
+use crate::{BevyBridge, GameOptions, GameStats};
+use crate::diagnostics::Failure;
 use crate::pixar::{self, PixarAssets, CharacterConfig, palette};
+use crate::cosmetics;
 use crate::asset_loader::CustomAssets;
+use crate::timestep::Interpolated;
+use crate::versus::{SplitInput, VersusPlayer, VersusState};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -17,6 +22,16 @@ const CHAR_SIZE: Vec2 = Vec2::new(30.0, 40.0);
 const BLOCK_SIZE: Vec2 = Vec2::new(36.0, 36.0);
 const PROJ_SIZE: Vec2 = Vec2::new(10.0, 10.0);
 const MAX_POWER: f32 = 400.0;
+/// Versus aiming: elevation range and how fast the keys move it.
+const MIN_ELEVATION: f32 = 10.0;
+const MAX_ELEVATION: f32 = 80.0;
+const ELEVATION_SPEED: f32 = 50.0;
+const MIN_POWER: f32 = 120.0;
+const POWER_SPEED: f32 = 200.0;
+/// Bot aiming: the elevation it lobs at, and how far off its shots can be
+/// in elevation and power.
+const BOT_ELEVATION: f32 = 45.0;
+const BOT_SPREAD: Vec2 = Vec2::new(12.0, 60.0);
 
 // ---------------------------------------------------------------------------
 // Components
@@ -26,39 +41,56 @@ const MAX_POWER: f32 = 400.0;
 pub struct GameEntity;
 
 #[derive(Component)]
-struct Player { hp: i32 }
+pub struct Player { hp: i32 }
 
 #[derive(Component)]
-struct EnemyAI { hp: i32 }
+pub struct EnemyAI { hp: i32 }
 
 #[derive(Component)]
-struct Projectile { vx: f32, vy: f32, friendly: bool }
+pub struct Projectile { vx: f32, vy: f32, friendly: bool }
 
 #[derive(Component)]
-struct Platform { destructible: bool, hp: i32 }
+pub struct Platform { destructible: bool, hp: i32 }
 
 #[derive(Component)]
-struct HudText;
+pub struct HudText;
+
+/// Aim guide of a versus player.
+#[derive(Component)]
+pub struct AimLine(VersusPlayer);
 
 #[derive(Resource)]
-struct GameState {
+pub struct GameState {
     score: i32,
     player_turn: bool,
     dragging: bool,
     drag_start: Vec2,
     turn_timer: f32,
     fired: bool,
+    shots: i32,
+    hits: i32,
+    /// Versus aim per player: elevation in degrees and launch power.
+    aim: [(f32, f32); 2],
 }
 
 // ---------------------------------------------------------------------------
 // Setup
 // ---------------------------------------------------------------------------
 
-pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_assets: Res<CustomAssets>) {
+pub fn setup(
+    mut commands: Commands,
+    pixar_assets: Res<PixarAssets>,
+    custom_assets: Res<CustomAssets>,
+    options: Res<GameOptions>,
+    bridge: Res<BevyBridge>,
+) {
     commands.insert_resource(GameState {
         score: 0, player_turn: true, dragging: false,
         drag_start: Vec2::ZERO, turn_timer: 0.0, fired: false,
+        shots: 0, hits: 0,
+        aim: [(45.0, 280.0); 2],
     });
+    let versus = VersusState::from_options(&options, &bridge.game_id).active;
 
     // Background
     if let Some(ref bg) = custom_assets.background {
@@ -78,9 +110,10 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Sprite { color: Color::srgb(0.3, 0.3, 0.35), custom_size: Some(Vec2::new(80.0, 20.0)), ..default() },
         Transform::from_xyz(PLAYER_X, PLATFORM_Y, 0.0), GameEntity,
     ));
-    pixar::spawn_character(
+    cosmetics::spawn_hero(
         &mut commands,
         &pixar_assets,
+        &options,
         &CharacterConfig::hero(palette::HERO_BLUE, CHAR_SIZE),
         Vec3::new(PLAYER_X, PLATFORM_Y + 30.0, 1.0),
         (Player { hp: 3 }, GameEntity),
@@ -91,14 +124,30 @@ pub fn setup(mut commands: Commands, pixar_assets: Res<PixarAssets>, custom_asse
         Sprite { color: Color::srgb(0.3, 0.3, 0.35), custom_size: Some(Vec2::new(80.0, 20.0)), ..default() },
         Transform::from_xyz(ENEMY_X, PLATFORM_Y, 0.0), GameEntity,
     ));
+    // In versus the second player takes the enemy's place
+    let rival = if versus {
+        CharacterConfig::hero(palette::HERO_RED, CHAR_SIZE)
+    } else {
+        CharacterConfig::enemy(palette::VILLAIN_RED, CHAR_SIZE)
+    };
     pixar::spawn_character(
         &mut commands,
         &pixar_assets,
-        &CharacterConfig::enemy(palette::VILLAIN_RED, CHAR_SIZE),
+        &rival,
         Vec3::new(ENEMY_X, PLATFORM_Y + 30.0, 1.0),
         (EnemyAI { hp: 3 }, GameEntity),
     );
 
+    if versus {
+        for player in [VersusPlayer::One, VersusPlayer::Two] {
+            commands.spawn((
+                Sprite { color: player.color().with_alpha(0.6), custom_size: Some(Vec2::new(2.0, 0.0)), ..default() },
+                Transform::from_xyz(0.0, 0.0, 2.0),
+                AimLine(player), GameEntity,
+            ));
+        }
+    }
+
     // Destructible blocks in the middle
     let mut rng = rand::thread_rng();
     for row in 0..3 {
@@ -132,10 +181,11 @@ pub fn player_fire(
     windows: Query<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
     pixar_assets: Res<PixarAssets>,
+    versus: Res<VersusState>,
     mut state: ResMut<GameState>,
     mut commands: Commands,
 ) {
-    if !state.player_turn || state.fired { return; }
+    if versus.active || !state.player_turn || state.fired { return; }
     let Ok(win) = windows.get_single() else { return; };
     let Ok((cam, cam_tf)) = camera_q.get_single() else { return; };
 
@@ -156,9 +206,10 @@ pub fn player_fire(
             commands.spawn((
                 pixar::round_sprite(&pixar_assets, palette::HERO_BLUE, PROJ_SIZE),
                 Transform::from_xyz(PLAYER_X + 20.0, PLATFORM_Y + 40.0, 2.0),
-                Projectile { vx, vy, friendly: true }, GameEntity,
+                Projectile { vx, vy, friendly: true }, Interpolated::default(), GameEntity,
             ));
             state.fired = true;
+            state.shots += 1;
         }
     }
 }
@@ -166,10 +217,11 @@ pub fn player_fire(
 pub fn ai_fire(
     time: Res<Time>,
     pixar_assets: Res<PixarAssets>,
+    versus: Res<VersusState>,
     mut state: ResMut<GameState>,
     mut commands: Commands,
 ) {
-    if state.player_turn { return; }
+    if versus.active || state.player_turn { return; }
     state.turn_timer += time.delta_secs();
     if state.turn_timer >= 1.0 && !state.fired {
         state.fired = true;
@@ -181,9 +233,103 @@ pub fn ai_fire(
         commands.spawn((
             pixar::round_sprite(&pixar_assets, palette::VILLAIN_RED, PROJ_SIZE),
             Transform::from_xyz(ENEMY_X - 20.0, PLATFORM_Y + 40.0, 2.0),
-            Projectile { vx, vy, friendly: false }, GameEntity,
+            Projectile { vx, vy, friendly: false }, Interpolated::default(), GameEntity,
+        ));
+    }
+}
+
+/// Versus: the player whose turn it is aims with their keys — up/down for
+/// elevation, towards the opponent for more power — and fires with their
+/// action key.  In a bot match the bot lines up its lob at player one
+/// instead.
+pub fn versus_fire(
+    time: Res<Time>,
+    input: Res<SplitInput>,
+    pixar_assets: Res<PixarAssets>,
+    versus: Res<VersusState>,
+    mut bot: ResMut<Bot>,
+    mut state: ResMut<GameState>,
+    mut commands: Commands,
+) {
+    if !versus.active || state.fired { return; }
+    let player = current_player(&state);
+    let (facing, x) = match player {
+        VersusPlayer::One => (1.0, PLAYER_X + 20.0),
+        VersusPlayer::Two => (-1.0, ENEMY_X - 20.0),
+    };
+    let dt = time.delta_secs();
+    let fire = if bot.plays(player) {
+        let (elevation, power) = state.aim[player.index()];
+        let lob = lob_power(Vec2::new(x, PLATFORM_Y + 40.0), Vec2::new(PLAYER_X, PLATFORM_Y + 30.0));
+        bot.plan(Vec2::new(elevation, power), Vec2::new(BOT_ELEVATION, lob), BOT_SPREAD);
+        let (aim, fire) = match bot.aim(dt) {
+            Some(Aim::Lining(aim)) => (aim, false),
+            Some(Aim::Take(aim)) => (aim, true),
+            None => return,
+        };
+        state.aim[player.index()] = (aim.x.clamp(MIN_ELEVATION, MAX_ELEVATION), aim.y.clamp(MIN_POWER, MAX_POWER));
+        fire
+    } else {
+        let keys = input.player(player);
+        let (elevation, power) = &mut state.aim[player.index()];
+        *elevation = (*elevation + keys.movement.y * ELEVATION_SPEED * dt).clamp(MIN_ELEVATION, MAX_ELEVATION);
+        *power = (*power + keys.movement.x * facing * POWER_SPEED * dt).clamp(MIN_POWER, MAX_POWER);
+        keys.action
+    };
+
+    if fire {
+        let (elevation, power) = state.aim[player.index()];
+        let angle = elevation.to_radians();
+        commands.spawn((
+            pixar::round_sprite(&pixar_assets, player.color(), PROJ_SIZE),
+            Transform::from_xyz(x, PLATFORM_Y + 40.0, 2.0),
+            Projectile { vx: facing * power * angle.cos(), vy: power * angle.sin(), friendly: player == VersusPlayer::One },
+            Interpolated::default(),
+            GameEntity,
         ));
+        state.fired = true;
+    }
+}
+
+/// Shows the aim of the versus player whose turn it is.
+pub fn update_aim_lines(
+    state: Res<GameState>,
+    mut q: Query<(&AimLine, &mut Transform, &mut Sprite)>,
+) {
+    let current = current_player(&state);
+    for (line, mut tf, mut sprite) in &mut q {
+        if line.0 != current || state.fired {
+            sprite.custom_size = Some(Vec2::ZERO);
+            continue;
+        }
+        let (elevation, power) = state.aim[line.0.index()];
+        let (facing, x) = match line.0 {
+            VersusPlayer::One => (1.0, PLAYER_X + 20.0),
+            VersusPlayer::Two => (-1.0, ENEMY_X - 20.0),
+        };
+        let dir = Vec2::new(facing * elevation.to_radians().cos(), elevation.to_radians().sin());
+        let len = power * 0.25;
+        let mid = Vec2::new(x, PLATFORM_Y + 40.0) + dir * len / 2.0;
+        tf.translation = mid.extend(2.0);
+        tf.rotation = Quat::from_rotation_z(dir.y.atan2(dir.x) - std::f32::consts::FRAC_PI_2);
+        sprite.custom_size = Some(Vec2::new(2.0, len));
+    }
+}
+
+fn current_player(state: &GameState) -> VersusPlayer {
+    if state.player_turn { VersusPlayer::One } else { VersusPlayer::Two }
+}
+
+/// Launch power that lands a shot fired at [`BOT_ELEVATION`] from `from`
+/// on `to`.
+fn lob_power(from: Vec2, to: Vec2) -> f32 {
+    let (dx, dy) = ((to.x - from.x).abs(), to.y - from.y);
+    let angle = BOT_ELEVATION.to_radians();
+    let drop = dx * angle.tan() - dy;
+    if drop <= 0.0 {
+        return MAX_POWER;
     }
+    (GRAVITY * dx * dx / (2.0 * angle.cos().powi(2) * drop)).sqrt().clamp(MIN_POWER, MAX_POWER)
 }
 
 pub fn move_projectiles(
@@ -198,13 +344,16 @@ pub fn move_projectiles(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn projectile_collisions(
     mut commands: Commands,
     mut state: ResMut<GameState>,
+    mut versus: ResMut<VersusState>,
     proj_q: Query<(Entity, &Transform, &Projectile)>,
     mut player_q: Query<(&Transform, &mut Player), Without<Projectile>>,
     mut enemy_q: Query<(&Transform, &mut EnemyAI), (Without<Projectile>, Without<Player>)>,
     mut block_q: Query<(Entity, &Transform, &mut Platform), Without<Projectile>>,
+    mut failures: EventWriter<Failure>,
 ) {
     for (pe, ptf, proj) in &proj_q {
         // Off-screen
@@ -237,6 +386,8 @@ pub fn projectile_collisions(
                 if (pp - ep).length() < 25.0 {
                     en.hp -= 1;
                     state.score += 200;
+                    state.hits += 1;
+                    versus.add_score(VersusPlayer::One, 200);
                     commands.entity(pe).despawn();
                     switch_turn(&mut state);
                     break;
@@ -248,6 +399,8 @@ pub fn projectile_collisions(
                 let plp = ptf2.translation.truncate();
                 if (pp - plp).length() < 25.0 {
                     pl.hp -= 1;
+                    if !versus.active { failures.send(Failure(plp)); }
+                    versus.add_score(VersusPlayer::Two, 200);
                     commands.entity(pe).despawn();
                     switch_turn(&mut state);
                     break;
@@ -267,10 +420,13 @@ pub fn check_game_over(
     player_q: Query<&Player>,
     enemy_q: Query<&EnemyAI>,
     mut state: ResMut<GameState>,
+    mut versus: ResMut<VersusState>,
     mut next_state: ResMut<NextState<crate::AppState>>,
 ) {
     if let Ok(p) = player_q.get_single() {
         if p.hp <= 0 {
+            versus.add_score(VersusPlayer::Two, 500);
+            versus.finish(Some(VersusPlayer::Two));
             next_state.set(crate::AppState::GameOver);
             return;
         }
@@ -278,23 +434,41 @@ pub fn check_game_over(
     if let Ok(e) = enemy_q.get_single() {
         if e.hp <= 0 {
             state.score += 500;
+            versus.add_score(VersusPlayer::One, 500);
+            versus.finish(Some(VersusPlayer::One));
             next_state.set(crate::AppState::GameOver);
         }
     }
 }
 
-pub fn update_score(state: Res<GameState>, mut bridge: ResMut<BevyBridge>) {
-    bridge.current_score = state.score;
+pub fn update_score(state: Res<GameState>, versus: Res<VersusState>, mut bridge: ResMut<BevyBridge>) {
+    bridge.current_score = if versus.active { versus.best_score() } else { state.score };
+    if !versus.active {
+        bridge.stats.accuracy = GameStats::percent(state.hits, state.shots);
+    }
 }
 
 pub fn update_hud(
     player_q: Query<&Player>,
     enemy_q: Query<&EnemyAI>,
     state: Res<GameState>,
+    versus: Res<VersusState>,
+    bot: Res<Bot>,
     mut q: Query<&mut Text, With<HudText>>,
 ) {
     let php = player_q.get_single().map(|p| p.hp).unwrap_or(0);
     let ehp = enemy_q.get_single().map(|e| e.hp).unwrap_or(0);
+    if versus.active {
+        let turn = bot.label(current_player(&state));
+        for mut t in &mut q {
+            **t = format!(
+                "{} HP:{} Score:{} | {} HP:{} Score:{} | {} TURN",
+                bot.label(VersusPlayer::One), php, versus.score(VersusPlayer::One),
+                bot.label(VersusPlayer::Two), ehp, versus.score(VersusPlayer::Two), turn
+            );
+        }
+        return;
+    }
     let turn = if state.player_turn { "YOUR TURN" } else { "ENEMY TURN" };
     for mut t in &mut q {
         **t = format!("Player HP:{} | Enemy HP:{} | {}", php, ehp, turn);
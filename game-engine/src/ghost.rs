@@ -0,0 +1,354 @@
+//! Ghost-race mode for runner games.
+//!
+//! **Recording** — every run of a ghost-capable game samples the tracked
+//! player's position and score at ~20 Hz.  When the run ends the replay is
+//! published as JSON for the shell to upload (`get_replay()` →
+//! `POST /api/v1/replays/:gameId`).
+//!
+//! **Playback** — when `start_game` is given a `"ghost"` option, a replay
+//! is downloaded from the replay API (or taken inline from the options) and
+//! a translucent copy of the hero re-runs it alongside the player:
+//!
+//! ```json
+//! {"ghost": {"source": "personal_best"}}
+//! {"ghost": {"source": "friend", "playerId": "<uuid>", "label": "Sam"}}
+//! {"ghost": {"frames": [[0, -300.0, -225.0, 0], ...], "label": "Sam"}}
+//! ```
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+use crate::games::GameplaySet;
+use crate::pixar::{self, CharacterConfig, PixarAssets};
+use crate::{AppState, BevyBridge, GameOptions};
+
+/// Game ids that support ghost racing.  Each one tags its player entity
+/// with [`GhostTracked`].
+pub const GHOST_GAMES: &[&str] = &["campus_dash", "parkour_lab", "gravity_shift_run"];
+
+/// Seconds between recorded samples.
+const SAMPLE_INTERVAL: f32 = 0.05;
+/// Most samples kept per run, as many as the replay API accepts (about ten
+/// minutes).  Longer runs keep their first ten minutes.
+const MAX_FRAMES: usize = 12_000;
+/// Ghost body opacity.
+const GHOST_ALPHA: f32 = 0.35;
+/// Seconds the ghost takes to fade out after its run ends.
+const GHOST_FADE_SECS: f32 = 0.6;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostRecorder>()
+            .add_systems(OnEnter(AppState::Playing), (reset_recorder, request_ghost))
+            .add_systems(
+                Update,
+                (record_frame, receive_ghost, drive_ghost, update_ghost_hud)
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnExit(AppState::Playing), (publish_replay, cleanup_ghost));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components / resources
+// ---------------------------------------------------------------------------
+
+/// Marks the entity whose position is recorded for ghost replays.  The
+/// payload is the owning game id so only the active game's player is used.
+#[derive(Component)]
+pub struct GhostTracked(pub &'static str);
+
+#[derive(Component)]
+struct Ghost;
+
+#[derive(Component)]
+struct GhostScoreText;
+
+/// One replay sample: `[t_ms, x, y, score]`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GhostFrame(pub u32, pub f32, pub f32, pub i32);
+
+#[derive(Resource, Default)]
+pub(crate) struct GhostRecorder {
+    elapsed: f32,
+    sample_timer: f32,
+    pub(crate) frames: Vec<GhostFrame>,
+}
+
+#[derive(Resource)]
+struct GhostPlayback {
+    label: String,
+    frames: Vec<GhostFrame>,
+    elapsed: f32,
+    spawned: bool,
+}
+
+/// Replay downloaded by an async request, waiting to be picked up.
+struct PendingGhost {
+    game_id: String,
+    label: String,
+    frames: Vec<GhostFrame>,
+}
+
+static PENDING_GHOST: Mutex<Option<PendingGhost>> = Mutex::new(None);
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// JSON replay of the last finished run of a ghost-capable game, ready to
+/// POST to `/api/v1/replays/:gameId`, or `""` if there is none.
+/// Shape: `{"gameId":"campus_dash","score":812,"durationMs":14350,"frames":[[0,-300.0,-225.0,0],...]}`
+#[wasm_bindgen]
+pub fn get_replay() -> String {
+    crate::get_js_global("__bevy_last_replay").unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Recording
+// ---------------------------------------------------------------------------
+
+pub(crate) fn is_ghost_game(game_id: &str) -> bool {
+    GHOST_GAMES.contains(&game_id)
+}
+
+fn reset_recorder(mut recorder: ResMut<GhostRecorder>) {
+    *recorder = GhostRecorder::default();
+}
+
+fn record_frame(
+    time: Res<Time>,
+    bridge: Res<BevyBridge>,
+    mut recorder: ResMut<GhostRecorder>,
+    tracked: Query<(&Transform, &GhostTracked)>,
+) {
+    if !is_ghost_game(&bridge.game_id) {
+        return;
+    }
+    let Some((tf, _)) = tracked.iter().find(|(_, g)| g.0 == bridge.game_id) else {
+        return;
+    };
+
+    let dt = time.delta_secs();
+    recorder.elapsed += dt;
+    recorder.sample_timer -= dt;
+    if recorder.sample_timer > 0.0 || recorder.frames.len() >= MAX_FRAMES {
+        return;
+    }
+    recorder.sample_timer = SAMPLE_INTERVAL;
+
+    let frame = GhostFrame(
+        (recorder.elapsed * 1000.0) as u32,
+        tf.translation.x,
+        tf.translation.y,
+        bridge.current_score,
+    );
+    recorder.frames.push(frame);
+}
+
+fn publish_replay(recorder: Res<GhostRecorder>, bridge: Res<BevyBridge>) {
+    if !is_ghost_game(&bridge.game_id) || recorder.frames.is_empty() {
+        return;
+    }
+    let replay = serde_json::json!({
+        "gameId": bridge.game_id,
+        "score": bridge.current_score,
+        "durationMs": (recorder.elapsed * 1000.0) as u32,
+        "frames": recorder.frames,
+    });
+    crate::set_js_global("__bevy_last_replay", &replay.to_string());
+}
+
+// ---------------------------------------------------------------------------
+// Playback
+// ---------------------------------------------------------------------------
+
+fn parse_frames(value: &serde_json::Value) -> Vec<GhostFrame> {
+    let mut frames: Vec<GhostFrame> = serde_json::from_value(value.clone()).unwrap_or_default();
+    frames.sort_by_key(|f| f.0);
+    frames
+}
+
+fn set_pending(game_id: String, label: String, frames: Vec<GhostFrame>) {
+    if frames.is_empty() {
+        return;
+    }
+    if let Ok(mut pending) = PENDING_GHOST.lock() {
+        *pending = Some(PendingGhost { game_id, label, frames });
+    }
+}
+
+/// Reads the `"ghost"` option and either uses inline frames or starts a
+/// download from the replay API.
+fn request_ghost(options: Res<GameOptions>, bridge: Res<BevyBridge>) {
+    if let Ok(mut pending) = PENDING_GHOST.lock() {
+        *pending = None;
+    }
+    if !is_ghost_game(&bridge.game_id) {
+        return;
+    }
+    let Some(ghost) = options.get("ghost") else {
+        return;
+    };
+
+    let game_id = bridge.game_id.clone();
+    let label = ghost["label"].as_str().unwrap_or("Ghost").to_string();
+
+    if ghost.get("frames").is_some() {
+        set_pending(game_id, label, parse_frames(&ghost["frames"]));
+        return;
+    }
+
+    let path = match ghost["source"].as_str() {
+        Some("friend") => match ghost["playerId"].as_str() {
+            Some(pid) => format!("/replays/{}/player/{}", game_id, pid),
+            None => return,
+        },
+        _ => format!("/replays/{}", game_id),
+    };
+
+    crate::api::get_json(&path, move |body| {
+        if let Some(body) = body {
+            set_pending(game_id, label, parse_frames(&body["replay"]["frames"]));
+        }
+    });
+}
+
+fn receive_ghost(mut commands: Commands, bridge: Res<BevyBridge>) {
+    let pending = match PENDING_GHOST.lock() {
+        Ok(mut p) => p.take(),
+        Err(_) => return,
+    };
+    let Some(pending) = pending else {
+        return;
+    };
+    // Drop downloads that finished after the player switched games.
+    if pending.game_id != bridge.game_id {
+        return;
+    }
+    commands.insert_resource(GhostPlayback {
+        label: pending.label,
+        frames: pending.frames,
+        elapsed: 0.0,
+        spawned: false,
+    });
+}
+
+/// Linearly interpolated `(x, y, score)` at `t_ms`, or `None` past the end.
+pub(crate) fn sample(frames: &[GhostFrame], t_ms: u32) -> Option<(f32, f32, i32)> {
+    let last = frames.last()?;
+    if t_ms > last.0 {
+        return None;
+    }
+    let idx = frames.partition_point(|f| f.0 <= t_ms);
+    if idx == 0 {
+        let f = frames[0];
+        return Some((f.1, f.2, f.3));
+    }
+    let a = frames[idx - 1];
+    let Some(&b) = frames.get(idx) else {
+        return Some((a.1, a.2, a.3));
+    };
+    let span = (b.0 - a.0).max(1) as f32;
+    let k = (t_ms - a.0) as f32 / span;
+    Some((a.1 + (b.1 - a.1) * k, a.2 + (b.2 - a.2) * k, a.3))
+}
+
+fn drive_ghost(
+    mut commands: Commands,
+    time: Res<Time>,
+    bridge: Res<BevyBridge>,
+    pixar_assets: Res<PixarAssets>,
+    playback: Option<ResMut<GhostPlayback>>,
+    tracked: Query<(&Transform, &Sprite, &GhostTracked), Without<Ghost>>,
+    mut ghosts: Query<(&mut Transform, &mut Sprite), With<Ghost>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    if !playback.spawned {
+        let Some((tf, sprite, _)) = tracked.iter().find(|(_, _, g)| g.0 == bridge.game_id) else {
+            return;
+        };
+        let size = sprite.custom_size.unwrap_or(Vec2::new(30.0, 50.0));
+        let config = CharacterConfig {
+            eye_scale: 0.0,
+            has_blush: false,
+            has_highlight: false,
+            breathing: false,
+            ..CharacterConfig::hero(sprite.color.with_alpha(GHOST_ALPHA), size)
+        };
+        let pos = tf.translation - Vec3::Z * 0.1;
+        pixar::spawn_character(&mut commands, &pixar_assets, &config, pos, Ghost);
+        commands.spawn((
+            Text::new(format!("{}: 0", playback.label)),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(Color::srgba(0.8, 0.9, 1.0, 0.8)),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(40.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            GhostScoreText,
+            Ghost,
+        ));
+        playback.spawned = true;
+        return;
+    }
+
+    playback.elapsed += time.delta_secs();
+    let t_ms = (playback.elapsed * 1000.0) as u32;
+    let end_ms = playback.frames.last().map(|f| f.0).unwrap_or(0);
+
+    for (mut tf, mut sprite) in &mut ghosts {
+        match sample(&playback.frames, t_ms) {
+            Some((x, y, _)) => {
+                tf.translation.x = x;
+                tf.translation.y = y;
+            }
+            None => {
+                // The ghost's run ended here — fade it out in place.
+                let since_end = (t_ms - end_ms) as f32 / 1000.0;
+                let alpha = (GHOST_ALPHA * (1.0 - since_end / GHOST_FADE_SECS)).max(0.0);
+                sprite.color.set_alpha(alpha);
+            }
+        }
+    }
+}
+
+fn update_ghost_hud(
+    playback: Option<Res<GhostPlayback>>,
+    mut q: Query<&mut Text, With<GhostScoreText>>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+    let t_ms = (playback.elapsed * 1000.0) as u32;
+    let score = sample(&playback.frames, t_ms)
+        .map(|(_, _, s)| s)
+        .or_else(|| playback.frames.last().map(|f| f.3))
+        .unwrap_or(0);
+    for mut text in &mut q {
+        **text = format!("{}: {}", playback.label, score);
+    }
+}
+
+fn cleanup_ghost(mut commands: Commands, q: Query<Entity, With<Ghost>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<GhostPlayback>();
+}
@@ -0,0 +1,215 @@
+//! Locales and non-Latin text.
+//!
+//! Bevy's built-in font only covers Latin, so the shell uploads fonts for
+//! other scripts with `upload_font(script, bytes)` (see
+//! [`asset_loader`](crate::asset_loader)) — for example Noto Sans CJK for
+//! `"cjk"`, Noto Naskh Arabic for `"arabic"` and Noto Sans Devanagari for
+//! `"devanagari"`.  Every text entity still using the default font is then
+//! switched to the font for the script it's written in, and back when its
+//! text changes to Latin again.  Text in a script without an uploaded font
+//! keeps the default and draws as boxes.
+//!
+//! The shell picks the language with `set_locale(tag)`, e.g.
+//! `set_locale("ar-EG")`.  In right-to-left locales, HUD text pinned to
+//! one side of the screen moves to the other and is right-aligned; the
+//! glyphs of each line are ordered and joined by the text shaper.
+
+use bevy::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::asset_loader::CustomAssets;
+use crate::games::GameplaySet;
+
+/// Languages written right to left.
+const RTL_LANGUAGES: &[&str] = &["ar", "fa", "he", "ur"];
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct I18nPlugin;
+
+impl Plugin for I18nPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locale>().add_systems(
+            Update,
+            (apply_pending_locale, script_fonts, mirror_hud).chain().after(GameplaySet),
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// BCP 47 tag, e.g. `"en-US"`.
+    pub tag: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self { tag: "en-US".to_string() }
+    }
+}
+
+impl Locale {
+    /// The language part of the tag, lower-cased: `"pt-BR"` → `"pt"`.
+    pub fn language(&self) -> String {
+        self.tag.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase()
+    }
+
+    pub fn rtl(&self) -> bool {
+        RTL_LANGUAGES.contains(&self.language().as_str())
+    }
+}
+
+/// Writing systems that need a font of their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    /// Chinese, Japanese and Korean.
+    Cjk,
+    Arabic,
+    Devanagari,
+}
+
+impl Script {
+    /// Name the shell uploads the script's font under.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Latin => "latin",
+            Self::Cjk => "cjk",
+            Self::Arabic => "arabic",
+            Self::Devanagari => "devanagari",
+        }
+    }
+
+    fn of(c: char) -> Self {
+        match c as u32 {
+            0x0600..=0x06FF
+            | 0x0750..=0x077F
+            | 0x08A0..=0x08FF
+            | 0xFB50..=0xFDFF
+            | 0xFE70..=0xFEFF => Self::Arabic,
+            0x0900..=0x097F | 0xA8E0..=0xA8FF => Self::Devanagari,
+            0x1100..=0x11FF
+            | 0x2E80..=0x2FDF
+            | 0x3000..=0x30FF
+            | 0x3130..=0x318F
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7AF
+            | 0xF900..=0xFAFF
+            | 0xFF00..=0xFFEF => Self::Cjk,
+            _ => Self::Latin,
+        }
+    }
+
+    /// The script of the first character in `text` that isn't Latin, so a
+    /// score or a punctuation mark doesn't decide it.
+    pub fn detect(text: &str) -> Self {
+        text.chars().map(Self::of).find(|s| *s != Self::Latin).unwrap_or(Self::Latin)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+/// A HUD node whose `left` and `right` were swapped for a right-to-left
+/// locale, with the justification it had before.
+#[derive(Component)]
+struct Mirrored(JustifyText);
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Sets the language of the engine's text, e.g. `set_locale("ja-JP")`.
+#[wasm_bindgen]
+pub fn set_locale(tag: &str) {
+    crate::set_js_global("__bevy_pending_locale", tag);
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn apply_pending_locale(mut locale: ResMut<Locale>) {
+    let Some(tag) = crate::get_js_global("__bevy_pending_locale") else {
+        return;
+    };
+    crate::delete_js_global("__bevy_pending_locale");
+    let tag = tag.trim();
+    if !tag.is_empty() && tag != locale.tag {
+        locale.tag = tag.to_string();
+    }
+}
+
+/// The font `text` should be drawn with, or `None` to leave a font the
+/// game chose itself alone.
+fn font_for(text: &str, current: &Handle<Font>, fonts: &CustomAssets) -> Option<Handle<Font>> {
+    if *current != Handle::default() && !fonts.fonts.values().any(|f| f == current) {
+        return None;
+    }
+    let font = fonts.fonts.get(Script::detect(text).key()).cloned().unwrap_or_default();
+    (font != *current).then_some(font)
+}
+
+fn script_fonts(
+    fonts: Res<CustomAssets>,
+    mut ui: Query<(Ref<Text>, &mut TextFont)>,
+    mut world: Query<(Ref<Text2d>, &mut TextFont), Without<Text>>,
+    mut spans: Query<(Ref<TextSpan>, &mut TextFont), (Without<Text>, Without<Text2d>)>,
+) {
+    let all = fonts.is_changed();
+    for (text, mut tf) in &mut ui {
+        if all || text.is_changed() {
+            if let Some(font) = font_for(&text, &tf.font, &fonts) {
+                tf.font = font;
+            }
+        }
+    }
+    for (text, mut tf) in &mut world {
+        if all || text.is_changed() {
+            if let Some(font) = font_for(&text, &tf.font, &fonts) {
+                tf.font = font;
+            }
+        }
+    }
+    for (text, mut tf) in &mut spans {
+        if all || text.is_changed() {
+            if let Some(font) = font_for(&text, &tf.font, &fonts) {
+                tf.font = font;
+            }
+        }
+    }
+}
+
+/// Moves absolutely positioned HUD text to the other side of the screen
+/// in right-to-left locales, and back when the locale changes again.
+fn mirror_hud(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    mut q: Query<(Entity, &mut Node, &mut TextLayout, Option<&Mirrored>), With<Text>>,
+) {
+    let rtl = locale.rtl();
+    for (entity, mut node, mut layout, mirrored) in &mut q {
+        if !locale.is_changed() && !node.is_added() {
+            continue;
+        }
+        if node.position_type != PositionType::Absolute || rtl == mirrored.is_some() {
+            continue;
+        }
+        (node.left, node.right) = (node.right, node.left);
+        if let Some(Mirrored(justify)) = mirrored {
+            layout.justify = *justify;
+            commands.entity(entity).remove::<Mirrored>();
+        } else {
+            commands.entity(entity).insert(Mirrored(layout.justify));
+            layout.justify = JustifyText::Right;
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! Unified game input and haptics.
+//!
+//! Games read [`GameAction`] events and the [`ActionState`] resource rather
+//! than individual devices or keys.  Two backends fill them in `PreUpdate`,
+//! after Bevy has processed raw input.  The keyboard backend goes through
+//! [`KeyBindings`], so every [`Control`] can be rebound:
+//!
+//! | Control / action         | Default keys       | Gamepad                          |
+//! |--------------------------|--------------------|----------------------------------|
+//! | `move_up` … `move_right` | arrow keys, WASD   | left stick, D-pad                |
+//! | `jump`                   | Space, Up          | South (A / Cross)                |
+//! | `fire`                   | F                  | West (X / Square), right trigger |
+//! | `slide`                  | Down, S            | stick or D-pad down              |
+//! | `switch_color`           | C                  | North (Y / Triangle)             |
+//! | `action`                 | Space, Enter       | South (A / Cross)                |
+//! | `undo`                   | Backspace          | East (B / Circle)                |
+//! | `reload`                 | R                  | left trigger                     |
+//! | `pause`                  | Escape             | Start                            |
+//! | `Pointer`                | left click, tap    | —                                |
+//!
+//! `jump`, `fire` and `pause` are also sent as [`GameAction`]s.  `Pointer`
+//! stays separate because a click or tap means different things per game:
+//! runners jump on it, shooters fire.  `action` is the main button of games
+//! without a jump — detonate, test the bridge, run the program.
+//!
+//! The shell rebinds controls with `set_keybindings(json)`, which takes
+//! `KeyboardEvent.code` names, so bindings follow key positions rather than
+//! the layout's letters:
+//!
+//! ```json
+//! {"jump": ["KeyK"], "fire": ["KeyJ", "Enter"], "move_left": ["KeyQ", "ArrowLeft"], "slide": null}
+//! ```
+//!
+//! Controls left out keep their keys and `null` restores the default.
+//! Bindings are kept in `localStorage` and `get_keybindings()` returns all
+//! of them.  Keys that pick from a numbered or lettered list — the number
+//! keys, Binary Blitz's switch row, Code Runner's `E` and Power Grid's
+//! `S`/`W`/`B` — stay fixed, as do the two halves of the keyboard in local
+//! [versus](crate::versus).
+//!
+//! Games send [`Rumble`] on impacts.  It's played on every connected
+//! gamepad through the browser's `vibrationActuator`, or with
+//! `navigator.vibrate` (phones) when no pad is connected.  The shell can
+//! turn haptics off with `set_haptics(false)`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Stick deflection below this is ignored.
+const STICK_DEADZONE: f32 = 0.25;
+/// Stick pushed down past this slides.
+const SLIDE_THRESHOLD: f32 = 0.5;
+const STORAGE_KEY: &str = "stem_keybindings";
+/// Most keys one control can have.
+const MAX_KEYS_PER_CONTROL: usize = 4;
+
+static HAPTICS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameAction>()
+            .add_event::<Rumble>()
+            .init_resource::<ActionState>()
+            .init_resource::<KeyBindings>()
+            .add_systems(Startup, (load_keybindings, publish_keybindings).chain())
+            .add_systems(
+                PreUpdate,
+                (apply_pending_keybindings, keyboard_actions, gamepad_actions)
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(Update, play_rumble);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Actions
+// ---------------------------------------------------------------------------
+
+/// A discrete input, sent on the frame it was pressed.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameAction {
+    Jump,
+    Fire,
+    /// Left click or screen tap.
+    Pointer,
+    Pause,
+}
+
+/// Continuous input, rebuilt every frame.
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    /// Combined move keys, stick and D-pad; each axis in `-1.0..=1.0`,
+    /// `+y` up.
+    pub movement: Vec2,
+    pub jump_held: bool,
+    pressed: HashSet<Control>,
+    just_pressed: HashSet<Control>,
+}
+
+impl ActionState {
+    /// A key or button bound to `control` is held.
+    pub fn pressed(&self, control: Control) -> bool {
+        self.pressed.contains(&control)
+    }
+
+    /// A key or button bound to `control` went down this frame.
+    pub fn just_pressed(&self, control: Control) -> bool {
+        self.just_pressed.contains(&control)
+    }
+
+    fn press(&mut self, control: Control, just: bool) {
+        self.pressed.insert(control);
+        if just {
+            self.just_pressed.insert(control);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Key bindings
+// ---------------------------------------------------------------------------
+
+/// A rebindable control, named in `set_keybindings` by its snake_case name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Control {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Fire,
+    Slide,
+    SwitchColor,
+    /// The main button of games without a jump.
+    Action,
+    Undo,
+    Reload,
+    Pause,
+}
+
+impl Control {
+    pub const ALL: [Control; 12] = [
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Jump,
+        Self::Fire,
+        Self::Slide,
+        Self::SwitchColor,
+        Self::Action,
+        Self::Undo,
+        Self::Reload,
+        Self::Pause,
+    ];
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Self::MoveUp => vec![KeyCode::ArrowUp, KeyCode::KeyW],
+            Self::MoveDown => vec![KeyCode::ArrowDown, KeyCode::KeyS],
+            Self::MoveLeft => vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+            Self::MoveRight => vec![KeyCode::ArrowRight, KeyCode::KeyD],
+            Self::Jump => vec![KeyCode::Space, KeyCode::ArrowUp],
+            Self::Fire => vec![KeyCode::KeyF],
+            Self::Slide => vec![KeyCode::ArrowDown, KeyCode::KeyS],
+            Self::SwitchColor => vec![KeyCode::KeyC],
+            Self::Action => vec![KeyCode::Space, KeyCode::Enter],
+            Self::Undo => vec![KeyCode::Backspace],
+            Self::Reload => vec![KeyCode::KeyR],
+            Self::Pause => vec![KeyCode::Escape],
+        }
+    }
+}
+
+/// The keys bound to each [`Control`].
+#[derive(Resource, Debug, Clone)]
+pub struct KeyBindings(HashMap<Control, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(Control::ALL.iter().map(|&c| (c, c.default_keys())).collect())
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, control: Control) -> &[KeyCode] {
+        self.0.get(&control).map_or(&[], Vec::as_slice)
+    }
+
+    /// Applies a `set_keybindings` object: a list of key names rebinds a
+    /// control and `null` restores its default.  Unknown controls and key
+    /// names are skipped, and a list without a known key leaves the control
+    /// as it was, so a control can't be left unbound.
+    fn apply(&mut self, update: &serde_json::Map<String, serde_json::Value>) {
+        for (name, value) in update {
+            let Ok(control) = serde_json::from_value::<Control>(serde_json::Value::String(name.clone())) else {
+                continue;
+            };
+            if value.is_null() {
+                self.0.insert(control, control.default_keys());
+                continue;
+            }
+            let Some(names) = value.as_array() else {
+                continue;
+            };
+            let mut keys: Vec<KeyCode> = Vec::new();
+            for key in names.iter().filter_map(|n| n.as_str()).filter_map(key_code) {
+                if !keys.contains(&key) && keys.len() < MAX_KEYS_PER_CONTROL {
+                    keys.push(key);
+                }
+            }
+            if !keys.is_empty() {
+                self.0.insert(control, keys);
+            }
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut bindings = serde_json::Map::new();
+        for control in Control::ALL {
+            if let Ok(serde_json::Value::String(name)) = serde_json::to_value(control) {
+                let keys: Vec<&str> = self.keys(control).iter().filter_map(|&k| key_name(k)).collect();
+                bindings.insert(name, keys.into());
+            }
+        }
+        bindings.into()
+    }
+}
+
+/// Keys that can be bound, by their `KeyboardEvent.code` name (which is
+/// also the `KeyCode` variant's).
+macro_rules! bindable_keys {
+    ($($key:ident),* $(,)?) => {
+        const KEY_NAMES: &[(&str, KeyCode)] = &[$((stringify!($key), KeyCode::$key)),*];
+    };
+}
+
+bindable_keys![
+    KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM,
+    KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+    Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+    ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+    Space, Enter, Escape, Backspace, Tab,
+    ShiftLeft, ShiftRight, ControlLeft, ControlRight, AltLeft, AltRight,
+    Backquote, Minus, Equal, BracketLeft, BracketRight, Backslash, IntlBackslash,
+    Semicolon, Quote, Comma, Period, Slash,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide, NumpadDecimal, NumpadEnter,
+    Insert, Delete, Home, End, PageUp, PageDown,
+];
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, k)| k)
+}
+
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(_, k)| *k == key).map(|&(n, _)| n)
+}
+
+/// A haptic pulse.  `strength` is `0.0..=1.0`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Rumble {
+    pub strength: f32,
+    pub duration_ms: u32,
+}
+
+impl Rumble {
+    /// The player crashed or was hit.
+    pub fn impact() -> Self {
+        Self { strength: 1.0, duration_ms: 250 }
+    }
+
+    /// A minor bump, e.g. an enemy destroyed.
+    pub fn light() -> Self {
+        Self { strength: 0.4, duration_ms: 80 }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Turn gamepad rumble and phone vibration on or off (default on).
+#[wasm_bindgen]
+pub fn set_haptics(enabled: bool) {
+    HAPTICS_ENABLED.store(enabled, Ordering::Release);
+}
+
+/// Rebind controls, e.g. `set_keybindings('{"jump":["KeyK"],"fire":null}')`
+/// (see the [module docs](self)).  The bindings are saved for next time.
+#[wasm_bindgen]
+pub fn set_keybindings(json: &str) {
+    crate::set_js_global("__bevy_pending_keybindings", json);
+}
+
+/// Every control's keys, e.g. `{"move_up":["ArrowUp","KeyW"],"jump":["Space","ArrowUp"],...}`.
+#[wasm_bindgen]
+pub fn get_keybindings() -> String {
+    crate::get_js_global("__bevy_keybindings").unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Binding systems
+// ---------------------------------------------------------------------------
+
+fn load_keybindings(mut bindings: ResMut<KeyBindings>) {
+    let saved = crate::persistence::local_storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok());
+    if let Some(serde_json::Value::Object(saved)) = saved {
+        bindings.apply(&saved);
+    }
+}
+
+fn apply_pending_keybindings(mut bindings: ResMut<KeyBindings>) {
+    let Some(raw) = crate::get_js_global("__bevy_pending_keybindings") else {
+        return;
+    };
+    if raw.is_empty() {
+        return;
+    }
+    crate::delete_js_global("__bevy_pending_keybindings");
+
+    let Ok(serde_json::Value::Object(update)) = serde_json::from_str(&raw) else {
+        return;
+    };
+    bindings.apply(&update);
+    let json = bindings.to_json().to_string();
+    if let Some(storage) = crate::persistence::local_storage() {
+        storage.set_item(STORAGE_KEY, &json).ok();
+    }
+    crate::set_js_global("__bevy_keybindings", &json);
+}
+
+fn publish_keybindings(bindings: Res<KeyBindings>) {
+    crate::set_js_global("__bevy_keybindings", &bindings.to_json().to_string());
+}
+
+// ---------------------------------------------------------------------------
+// Backends
+// ---------------------------------------------------------------------------
+
+fn keyboard_actions(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    bindings: Res<KeyBindings>,
+    mut state: ResMut<ActionState>,
+    mut actions: EventWriter<GameAction>,
+) {
+    state.pressed.clear();
+    state.just_pressed.clear();
+    for control in Control::ALL {
+        let bound = bindings.keys(control);
+        if keys.any_pressed(bound.iter().copied()) {
+            state.press(control, keys.any_just_pressed(bound.iter().copied()));
+        }
+    }
+
+    if state.just_pressed(Control::Jump) {
+        actions.send(GameAction::Jump);
+    }
+    if state.just_pressed(Control::Fire) {
+        actions.send(GameAction::Fire);
+    }
+    if mouse.just_pressed(MouseButton::Left) || touches.any_just_pressed() {
+        actions.send(GameAction::Pointer);
+    }
+    if state.just_pressed(Control::Pause) {
+        actions.send(GameAction::Pause);
+    }
+
+    let axis = |neg: Control, pos: Control| {
+        f32::from(u8::from(state.pressed(pos))) - f32::from(u8::from(state.pressed(neg)))
+    };
+    state.movement = Vec2::new(
+        axis(Control::MoveLeft, Control::MoveRight),
+        axis(Control::MoveDown, Control::MoveUp),
+    );
+    state.jump_held = state.pressed(Control::Jump);
+}
+
+/// Runs after [`keyboard_actions`] and adds to what it produced, so a
+/// keyboard and a pad can be used together.
+fn gamepad_actions(
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+    mut actions: EventWriter<GameAction>,
+) {
+    for pad in &gamepads {
+        if pad.just_pressed(GamepadButton::South) {
+            actions.send(GameAction::Jump);
+        }
+        if pad.just_pressed(GamepadButton::West) || pad.just_pressed(GamepadButton::RightTrigger2) {
+            actions.send(GameAction::Fire);
+        }
+        if pad.just_pressed(GamepadButton::Start) {
+            actions.send(GameAction::Pause);
+        }
+
+        let buttons: [(&[GamepadButton], Control); 11] = [
+            (&[GamepadButton::DPadUp], Control::MoveUp),
+            (&[GamepadButton::DPadDown], Control::MoveDown),
+            (&[GamepadButton::DPadLeft], Control::MoveLeft),
+            (&[GamepadButton::DPadRight], Control::MoveRight),
+            (&[GamepadButton::South], Control::Jump),
+            (&[GamepadButton::West, GamepadButton::RightTrigger2], Control::Fire),
+            (&[GamepadButton::North], Control::SwitchColor),
+            (&[GamepadButton::South], Control::Action),
+            (&[GamepadButton::East], Control::Undo),
+            (&[GamepadButton::LeftTrigger2], Control::Reload),
+            (&[GamepadButton::Start], Control::Pause),
+        ];
+        for (bound, control) in buttons {
+            if pad.any_pressed(bound.iter().copied()) {
+                state.press(control, pad.any_just_pressed(bound.iter().copied()));
+            }
+        }
+
+        let stick = pad.left_stick();
+        if stick.length() > STICK_DEADZONE {
+            state.movement += stick;
+        }
+        state.movement += pad.dpad();
+        state.jump_held |= pad.pressed(GamepadButton::South);
+        if stick.y < -SLIDE_THRESHOLD || pad.pressed(GamepadButton::DPadDown) {
+            let just = pad.just_pressed(GamepadButton::DPadDown);
+            state.press(Control::Slide, just);
+        }
+    }
+    state.movement = state.movement.clamp(Vec2::NEG_ONE, Vec2::ONE);
+}
+
+// ---------------------------------------------------------------------------
+// Haptics
+// ---------------------------------------------------------------------------
+
+fn play_rumble(mut rumbles: EventReader<Rumble>) {
+    // Several impacts in one frame become one pulse
+    let Some(rumble) = rumbles
+        .read()
+        .copied()
+        .max_by(|a, b| a.strength.total_cmp(&b.strength))
+    else {
+        return;
+    };
+    if !HAPTICS_ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let Some(navigator) = web_sys::window().map(|w| w.navigator()) else {
+        return;
+    };
+
+    if !rumble_gamepads(&navigator, rumble) {
+        navigator.vibrate_with_duration(rumble.duration_ms);
+    }
+}
+
+/// `vibrationActuator.playEffect` on every connected pad.  Not in web-sys'
+/// stable API, so it's called by name.  Returns whether any pad took it.
+fn rumble_gamepads(navigator: &web_sys::Navigator, rumble: Rumble) -> bool {
+    let get = |target: &JsValue, name: &str| js_sys::Reflect::get(target, &JsValue::from_str(name)).ok();
+
+    let Some(pads) = get(navigator, "getGamepads")
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+        .and_then(|f| f.call0(navigator).ok())
+        .and_then(|p| p.dyn_into::<js_sys::Array>().ok())
+    else {
+        return false;
+    };
+
+    let effect = js_sys::Object::new();
+    let strength = JsValue::from_f64(f64::from(rumble.strength.clamp(0.0, 1.0)));
+    let _ = js_sys::Reflect::set(&effect, &"duration".into(), &rumble.duration_ms.into());
+    let _ = js_sys::Reflect::set(&effect, &"strongMagnitude".into(), &strength);
+    let _ = js_sys::Reflect::set(&effect, &"weakMagnitude".into(), &strength);
+
+    let mut played = false;
+    for pad in pads.iter().filter(|p| !p.is_null() && !p.is_undefined()) {
+        let Some(actuator) = get(&pad, "vibrationActuator").filter(|a| !a.is_null() && !a.is_undefined()) else {
+            continue;
+        };
+        if let Some(play) = get(&actuator, "playEffect").and_then(|f| f.dyn_into::<js_sys::Function>().ok()) {
+            played |= play.call2(&actuator, &"dual-rumble".into(), &effect).is_ok();
+        }
+    }
+    played
+}
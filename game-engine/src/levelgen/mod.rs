@@ -0,0 +1,160 @@
+//! Seeded level generation for the grid puzzles, and seeded obstacles
+//! for the runners.
+//!
+//! The puzzles ship a few handcrafted levels each.  Started with a seed,
+//! they instead play an endless run of generated levels, level `n` of a
+//! seed always being the same:
+//!
+//! * `{"seed":1234}` – the levels of that seed, e.g. to replay a friend's
+//!   run;
+//! * `{"daily":{"seed":1234}}` – the daily challenge, as served by
+//!   `GET /daily/:gameId` (pass the response's `options` on unchanged);
+//! * `{"daily":true}` – a local daily seed (UTC), for playing offline.
+//!
+//! Runners draw their obstacles from [`run_rng`], so with a seed every
+//! player faces the same sequence.
+//!
+//! A resumed run keeps the seed it was playing (games store it in their
+//! save's `extra`).
+//!
+//! Every generator checks its level with a breadth-first solver and only
+//! keeps levels whose shortest solution is long enough for the level
+//! number, so later levels are harder.  If no attempt qualifies, the game
+//! falls back to one of its handcrafted levels.
+
+pub mod orb_drop;
+pub mod rolling_block;
+pub mod vault;
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use rand::RngCore;
+
+use crate::GameOptions;
+
+/// Layouts a generator tries before giving up.
+pub const ATTEMPTS: usize = 400;
+/// States the solver visits before calling a level unsolvable.
+const STATE_LIMIT: usize = 50_000;
+
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// The seed to generate levels from, or `None` for the handcrafted ones.
+pub fn seed(options: &GameOptions) -> Option<u64> {
+    if let Some(seed) = options.get("seed").and_then(|s| s.as_u64()) {
+        return Some(seed);
+    }
+    match options.get("daily") {
+        Some(daily) if daily.as_bool() == Some(true) => return Some(daily_seed()),
+        Some(daily) => {
+            if let Some(seed) = daily.get("seed").and_then(|s| s.as_u64()) {
+                return Some(seed);
+            }
+        }
+        None => {}
+    }
+    options.get("resume")?.get("extra")?.get("seed")?.as_u64()
+}
+
+/// Today's seed: the number of whole days since the Unix epoch, in UTC.
+pub fn daily_seed() -> u64 {
+    (js_sys::Date::now() / MS_PER_DAY) as u64
+}
+
+/// SplitMix64.  Generated levels have to come out the same on every
+/// device and engine build, which `rand`'s `StdRng` doesn't promise.
+pub struct LevelRng(u64);
+
+impl LevelRng {
+    /// The generator for level `level` of `seed`.
+    pub fn new(seed: u64, level: usize) -> Self {
+        let mut rng = Self(seed ^ (level as u64).wrapping_mul(0xD1B5_4A32_D192_ED03));
+        rng.next_u64();
+        rng
+    }
+}
+
+impl RngCore for LevelRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The generator for a run's random events (obstacles, gaps, …): the
+/// seed's if there is one, otherwise a fresh one per run.
+pub fn run_rng(options: &GameOptions) -> LevelRng {
+    match seed(options) {
+        // A stream apart from the ones the puzzles draw levels from.
+        Some(seed) => LevelRng::new(seed, usize::MAX),
+        None => LevelRng(rand::random()),
+    }
+}
+
+/// Moves from `start` to every state reachable through `next`, by
+/// breadth-first search.  Stops early after [`STATE_LIMIT`] states.
+pub fn distances<S, F>(start: S, mut next: F) -> HashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> Vec<S>,
+{
+    let mut seen = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        let depth = seen[&state];
+        for n in next(&state) {
+            if seen.len() >= STATE_LIMIT { return seen; }
+            if seen.contains_key(&n) { continue; }
+            seen.insert(n.clone(), depth + 1);
+            queue.push_back(n);
+        }
+    }
+    seen
+}
+
+/// Fewest moves from `start` to a state that is `done`, or `None` if
+/// there is none within [`STATE_LIMIT`] states.
+pub fn shortest_solution<S, F, G>(start: S, mut next: F, done: G) -> Option<usize>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> Vec<S>,
+    G: Fn(&S) -> bool,
+{
+    if done(&start) { return Some(0); }
+    let mut seen = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        let depth = seen[&state];
+        for n in next(&state) {
+            if seen.contains_key(&n) { continue; }
+            if done(&n) { return Some(depth + 1); }
+            if seen.len() >= STATE_LIMIT { return None; }
+            seen.insert(n.clone(), depth + 1);
+            queue.push_back(n);
+        }
+    }
+    None
+}
+
+/// The four grid directions.
+pub const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
@@ -0,0 +1,129 @@
+//! Levels for `hydro_logic_puzzles`: the player pushes orbs that fall
+//! until they land on a wall or another orb, and every target has to end
+//! up holding one.
+//!
+//! Targets come from the solver itself: it explores the moves from a random
+//! start, and a set of orb positions that takes long enough to reach
+//! becomes the targets, so every level is solvable by construction.
+
+use std::collections::{HashMap, HashSet};
+
+use rand::Rng;
+
+use super::{distances, LevelRng, ATTEMPTS, DIRECTIONS};
+
+pub struct Level {
+    pub walls: Vec<(i32, i32)>,
+    pub orbs: Vec<(i32, i32)>,
+    pub targets: Vec<(i32, i32)>,
+    pub player: (i32, i32),
+    /// Length of the shortest solution.
+    pub moves: usize,
+}
+
+/// The player and the orbs, sorted so equal positions compare equal.
+type State = ((i32, i32), Vec<(i32, i32)>);
+
+/// Lets every orb fall, bottom ones first, as the game's gravity does.
+/// The player doesn't hold orbs up.
+pub fn settle(walls: &HashSet<(i32, i32)>, orbs: &mut [(i32, i32)]) {
+    orbs.sort_by_key(|&(x, y)| (y, x));
+    for i in 0..orbs.len() {
+        let (x, mut y) = orbs[i];
+        while y > 0 && !walls.contains(&(x, y - 1)) && !orbs[..i].contains(&(x, y - 1)) {
+            y -= 1;
+        }
+        orbs[i] = (x, y);
+    }
+    orbs.sort();
+}
+
+/// The state after the player tries to step `(dx, dy)`, if it can.
+pub fn step(walls: &HashSet<(i32, i32)>, state: &State, dx: i32, dy: i32) -> Option<State> {
+    let ((px, py), orbs) = state;
+    let next = (px + dx, py + dy);
+    if walls.contains(&next) { return None; }
+
+    let mut orbs = orbs.clone();
+    if let Some(i) = orbs.iter().position(|&o| o == next) {
+        let behind = (next.0 + dx, next.1 + dy);
+        if walls.contains(&behind) || orbs.contains(&behind) { return None; }
+        orbs[i] = behind;
+    }
+    settle(walls, &mut orbs);
+    Some((next, orbs))
+}
+
+/// A `cols`×`rows` room with border walls for level `level` (0-based),
+/// or `None` if no attempt was hard enough.  Later levels have more orbs
+/// and longer solutions.
+pub fn generate(rng: &mut LevelRng, cols: i32, rows: i32, level: usize) -> Option<Level> {
+    let orb_count = (2 + level / 3).min(4);
+    let ledge_count = 3 + level.min(5);
+    let min_moves = (6 + 2 * level).min(30);
+
+    for _ in 0..ATTEMPTS {
+        let mut walls = Vec::new();
+        for x in 0..cols {
+            walls.push((x, 0));
+            walls.push((x, rows - 1));
+        }
+        for y in 1..rows - 1 {
+            walls.push((0, y));
+            walls.push((cols - 1, y));
+        }
+        for _ in 0..ledge_count {
+            let ledge = (rng.gen_range(1..cols - 1), rng.gen_range(1..rows - 1));
+            if !walls.contains(&ledge) {
+                walls.push(ledge);
+            }
+        }
+        let wall_set: HashSet<(i32, i32)> = walls.iter().copied().collect();
+
+        let mut open = Vec::new();
+        for y in 1..rows - 1 {
+            for x in 1..cols - 1 {
+                if !wall_set.contains(&(x, y)) {
+                    open.push((x, y));
+                }
+            }
+        }
+        if open.len() < orb_count + 1 { continue; }
+
+        let mut orbs: Vec<(i32, i32)> = Vec::new();
+        while orbs.len() < orb_count {
+            let cell = open[rng.gen_range(0..open.len())];
+            if !orbs.contains(&cell) {
+                orbs.push(cell);
+            }
+        }
+        settle(&wall_set, &mut orbs);
+        let free: Vec<(i32, i32)> = open.iter().copied().filter(|c| !orbs.contains(c)).collect();
+        let player = free[rng.gen_range(0..free.len())];
+
+        let reach = distances((player, orbs.clone()), |state| {
+            DIRECTIONS
+                .iter()
+                .filter_map(|&(dx, dy)| step(&wall_set, state, dx, dy))
+                .collect()
+        });
+
+        // Fewest moves to each arrangement of orbs, wherever the player ends.
+        let mut fewest: HashMap<Vec<(i32, i32)>, usize> = HashMap::new();
+        for ((_, arrangement), moves) in reach {
+            let best = fewest.entry(arrangement).or_insert(moves);
+            *best = (*best).min(moves);
+        }
+        let mut goals: Vec<(Vec<(i32, i32)>, usize)> = fewest
+            .into_iter()
+            .filter(|(_, moves)| *moves >= min_moves)
+            .collect();
+        if goals.is_empty() { continue; }
+        // HashMap order isn't stable; sort so a seed always picks the same targets.
+        goals.sort();
+        let (targets, moves) = goals.swap_remove(rng.gen_range(0..goals.len()));
+
+        return Some(Level { walls, orbs, targets, player, moves });
+    }
+    None
+}
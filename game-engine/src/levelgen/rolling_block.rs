@@ -0,0 +1,114 @@
+//! Levels for `logicrons_grid_shift`: a 1×1×2 block rolls over a floor
+//! with holes and has to end up standing on the goal.
+
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use super::{distances, LevelRng, ATTEMPTS, DIRECTIONS};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Orientation { Standing, Horizontal, Vertical }
+
+/// A lying block covers `(x, y)` and the cell to its right (horizontal)
+/// or above it (vertical).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Pose {
+    pub x: i32,
+    pub y: i32,
+    pub orientation: Orientation,
+}
+
+pub struct Level {
+    pub voids: Vec<(i32, i32)>,
+    pub goal: (i32, i32),
+    /// Where the block starts, standing.
+    pub start: (i32, i32),
+    /// Length of the shortest solution.
+    pub moves: usize,
+}
+
+/// The pose after rolling one step in `(dx, dy)`.
+pub fn roll(pose: Pose, dx: i32, dy: i32) -> Pose {
+    use Orientation::*;
+    let Pose { x, y, orientation } = pose;
+    let (orientation, x, y) = match (orientation, dx, dy) {
+        (Standing, 1, 0) => (Horizontal, x + 1, y),
+        (Standing, -1, 0) => (Horizontal, x - 2, y),
+        (Standing, 0, 1) => (Vertical, x, y + 1),
+        (Standing, 0, -1) => (Vertical, x, y - 2),
+        (Horizontal, 1, 0) => (Standing, x + 2, y),
+        (Horizontal, -1, 0) => (Standing, x - 1, y),
+        (Horizontal, 0, _) => (Horizontal, x, y + dy),
+        (Vertical, 0, 1) => (Standing, x, y + 2),
+        (Vertical, 0, -1) => (Standing, x, y - 1),
+        (Vertical, _, 0) => (Vertical, x + dx, y),
+        _ => (orientation, x, y),
+    };
+    Pose { x, y, orientation }
+}
+
+/// The cells under the block.
+pub fn cells(pose: Pose) -> Vec<(i32, i32)> {
+    match pose.orientation {
+        Orientation::Standing => vec![(pose.x, pose.y)],
+        Orientation::Horizontal => vec![(pose.x, pose.y), (pose.x + 1, pose.y)],
+        Orientation::Vertical => vec![(pose.x, pose.y), (pose.x, pose.y + 1)],
+    }
+}
+
+/// A `cols`×`rows` floor for level `level` (0-based), or `None` if no
+/// attempt was hard enough.  Holes get more common and solutions longer
+/// as the level number grows.
+pub fn generate(rng: &mut LevelRng, cols: i32, rows: i32, level: usize) -> Option<Level> {
+    let hole_share = (0.2 + 0.04 * level as f32).min(0.45);
+    let min_moves = (4 + 2 * level).min(18);
+
+    for _ in 0..ATTEMPTS {
+        // Kept in grid order; iterating the set would differ between runs.
+        let mut tiles = Vec::new();
+        for y in 0..rows {
+            for x in 0..cols {
+                if rng.gen::<f32>() >= hole_share {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        if tiles.is_empty() { continue; }
+        let solid: HashSet<(i32, i32)> = tiles.iter().copied().collect();
+        let start = tiles[rng.gen_range(0..tiles.len())];
+
+        let on_floor = |pose: Pose| cells(pose).iter().all(|c| solid.contains(c));
+        let reach = distances(
+            Pose { x: start.0, y: start.1, orientation: Orientation::Standing },
+            |&pose| {
+                DIRECTIONS
+                    .iter()
+                    .map(|&(dx, dy)| roll(pose, dx, dy))
+                    .filter(|&p| on_floor(p))
+                    .collect()
+            },
+        );
+
+        // Any standing spot far enough away can be the goal.
+        let mut goals: Vec<(Pose, usize)> = reach
+            .into_iter()
+            .filter(|(pose, moves)| pose.orientation == Orientation::Standing && *moves >= min_moves)
+            .collect();
+        if goals.is_empty() { continue; }
+        // HashMap order isn't stable; sort so a seed always picks the same goal.
+        goals.sort_by_key(|(pose, moves)| (*moves, pose.x, pose.y));
+        let (goal, moves) = goals[rng.gen_range(0..goals.len())];
+
+        let mut voids = Vec::new();
+        for y in 0..rows {
+            for x in 0..cols {
+                if !solid.contains(&(x, y)) {
+                    voids.push((x, y));
+                }
+            }
+        }
+        return Some(Level { voids, goal: (goal.x, goal.y), start, moves });
+    }
+    None
+}
@@ -0,0 +1,153 @@
+//! Levels for `history_vault_escape`: collect coloured keys to open the
+//! matching doors, hit switches to disarm traps, and reach the exit.
+
+use rand::Rng;
+
+use super::{shortest_solution, LevelRng, ATTEMPTS, DIRECTIONS};
+
+/// Key, door, trap and switch colours; a level uses at most this many.
+pub const COLOURS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Key(usize),
+    Door(usize),
+    Trap(usize),
+    Switch(usize),
+    Exit,
+}
+
+pub struct Level {
+    /// Every cell of the room, border walls included.
+    pub tiles: Vec<(i32, i32, Tile)>,
+    pub start: (i32, i32),
+    /// Length of the shortest solution.
+    pub moves: usize,
+}
+
+/// Where the player is, and bit masks of the keys held and switches hit.
+type State = ((i32, i32), u8, u8);
+
+fn step(tile_at: &impl Fn(i32, i32) -> Tile, state: &State) -> Vec<State> {
+    let &((x, y), keys, switched) = state;
+    DIRECTIONS
+        .iter()
+        .filter_map(|&(dx, dy)| {
+            let next = (x + dx, y + dy);
+            match tile_at(next.0, next.1) {
+                Tile::Wall => None,
+                Tile::Door(i) if keys & (1 << i) == 0 => None,
+                // Stepping onto an armed trap loses the run.
+                Tile::Trap(i) if switched & (1 << i) == 0 => None,
+                Tile::Key(i) => Some((next, keys | (1 << i), switched)),
+                Tile::Switch(i) => Some((next, keys, switched | (1 << i))),
+                _ => Some((next, keys, switched)),
+            }
+        })
+        .collect()
+}
+
+/// Shuffles `items` in place (Fisher–Yates, so the order only depends on
+/// the seed).
+fn shuffle<T>(rng: &mut LevelRng, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.gen_range(0..=i));
+    }
+}
+
+/// A `cols`×`rows` vault for level `level` (0-based), or `None` if no
+/// attempt was hard enough.
+///
+/// Walls run across the room, each with a single gap that holds a door or
+/// a trap, and every gap's key or switch lies somewhere before its wall.
+/// Later levels add walls, turn more of the gaps into traps and take
+/// longer to solve.
+pub fn generate(rng: &mut LevelRng, cols: i32, rows: i32, level: usize) -> Option<Level> {
+    let gates = (1 + level / 2).min(COLOURS);
+    let traps = (level / 3).min(gates - 1);
+    let doors = gates - traps;
+    let wall_share = (0.08 + 0.02 * level as f32).min(0.2);
+    let min_moves = (8 + 2 * level).min(22);
+
+    for _ in 0..ATTEMPTS {
+        // Columns for the gated walls, at least one apart.
+        let mut columns: Vec<i32> = (2..cols - 1 - gates as i32).collect();
+        if columns.len() < gates { return None; }
+        shuffle(rng, &mut columns);
+        columns.truncate(gates);
+        columns.sort();
+        for (k, x) in columns.iter_mut().enumerate() {
+            *x += k as i32;
+        }
+
+        let mut grid = vec![Tile::Floor; (cols * rows) as usize];
+        for y in 0..rows {
+            for x in 0..cols {
+                let border = x == 0 || y == 0 || x == cols - 1 || y == rows - 1;
+                if border || columns.contains(&x) || rng.gen::<f32>() < wall_share {
+                    grid[(y * cols + x) as usize] = Tile::Wall;
+                }
+            }
+        }
+        let mut gaps: Vec<Tile> = (0..doors).map(Tile::Door).chain((0..traps).map(Tile::Trap)).collect();
+        shuffle(rng, &mut gaps);
+        for (&x, &gap) in columns.iter().zip(&gaps) {
+            let y = rng.gen_range(1..rows - 1);
+            grid[(y * cols + x) as usize] = gap;
+        }
+
+        let open: Vec<(i32, i32)> = (0..rows)
+            .flat_map(|y| (0..cols).map(move |x| (x, y)))
+            .filter(|&(x, y)| grid[(y * cols + x) as usize] == Tile::Floor)
+            .collect();
+        let before: Vec<(i32, i32)> = open.iter().copied().filter(|c| c.0 < columns[0]).collect();
+        let after: Vec<(i32, i32)> = open.iter().copied().filter(|c| c.0 > columns[gates - 1]).collect();
+        if before.is_empty() || after.is_empty() { continue; }
+        let start = before[rng.gen_range(0..before.len())];
+        let (ex, ey) = after[rng.gen_range(0..after.len())];
+        grid[(ey * cols + ex) as usize] = Tile::Exit;
+
+        let mut placed = true;
+        for (&x, &gap) in columns.iter().zip(&gaps) {
+            let spots: Vec<(i32, i32)> = open
+                .iter()
+                .copied()
+                .filter(|&(cx, cy)| cx < x && (cx, cy) != start && grid[(cy * cols + cx) as usize] == Tile::Floor)
+                .collect();
+            if spots.is_empty() {
+                placed = false;
+                break;
+            }
+            let (sx, sy) = spots[rng.gen_range(0..spots.len())];
+            grid[(sy * cols + sx) as usize] = match gap {
+                Tile::Door(i) => Tile::Key(i),
+                Tile::Trap(i) => Tile::Switch(i),
+                _ => unreachable!(),
+            };
+        }
+        if !placed { continue; }
+
+        let tile_at = |x: i32, y: i32| {
+            if x < 0 || y < 0 || x >= cols || y >= rows { return Tile::Wall; }
+            grid[(y * cols + x) as usize]
+        };
+        let at_exit = |&((x, y), _, _): &State| tile_at(x, y) == Tile::Exit;
+
+        // Stray walls can still cut a key off from the start.
+        let Some(moves) = shortest_solution((start, 0, 0), |s| step(&tile_at, s), at_exit) else {
+            continue;
+        };
+        if moves < min_moves { continue; }
+
+        let mut tiles = Vec::new();
+        for y in 0..rows {
+            for x in 0..cols {
+                tiles.push((x, y, tile_at(x, y)));
+            }
+        }
+        return Some(Level { tiles, start, moves });
+    }
+    None
+}
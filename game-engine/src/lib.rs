@@ -2,9 +2,41 @@ use bevy::prelude::*;
 use bevy::window::{PresentMode, WindowPlugin};
 use wasm_bindgen::prelude::*;
 
+pub mod accessibility;
+pub mod ai;
+pub mod analytics;
+pub mod api;
 pub mod asset_loader;
+pub mod camera;
+pub mod campaign;
+pub mod capture;
+pub mod catalog;
+pub mod cosmetics;
+pub mod crash;
+pub mod diagnostics;
+pub mod game_mode;
 pub mod games;
+pub mod ghost;
+pub mod i18n;
+pub mod input;
+pub mod levelgen;
+pub mod perf;
+pub mod persistence;
+pub mod pool;
+pub mod powerups;
 pub mod pixar;
+pub mod preload;
+pub mod records;
+pub mod remote_config;
+pub mod scene3d;
+pub mod spectator;
+pub mod sync;
+pub mod timestep;
+pub mod tutorial;
+pub mod tween;
+pub mod ui;
+pub mod versus;
+pub mod wellbeing;
 
 use games::GamePlugin;
 
@@ -14,12 +46,15 @@ use games::GamePlugin;
 
 /// Top‑level application state.
 ///
+/// * `Loading` – assets are preloading behind a progress bar (see
+///   [`preload`]); `start_game` calls wait until it's done.
 /// * `Menu`    – idle; waiting for the React shell to call `start_game`.
 /// * `Playing` – a game scene is active.
 /// * `GameOver`– the last game has ended; score is available via `get_score`.
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum AppState {
     #[default]
+    Loading,
     Menu,
     Playing,
     GameOver,
@@ -30,12 +65,13 @@ pub enum AppState {
 // ---------------------------------------------------------------------------
 
 /// Bridge resource that carries data between the Bevy world and wasm‑bindgen
-/// exported functions.  JS can poll `get_score()` or read the JSON returned
-/// by `stop_game()`.
+/// exported functions.  JS can poll `get_score()` / `get_stats()` or read the
+/// JSON returned by `stop_game()`.
 #[derive(Resource, Debug, Clone)]
 pub struct BevyBridge {
     pub current_score: i32,
     pub game_id: String,
+    pub stats: GameStats,
 }
 
 impl Default for BevyBridge {
@@ -43,10 +79,67 @@ impl Default for BevyBridge {
         Self {
             current_score: 0,
             game_id: String::new(),
+            stats: GameStats::default(),
         }
     }
 }
 
+/// World units per metre when a game reports `distance`.
+pub const UNITS_PER_METRE: f32 = 10.0;
+
+/// Run stats reported next to the score.  Each game's `update_score` fills
+/// in the fields that mean something for it; the rest stay `None` and are
+/// left out of the JSON.  `distance`, `time` and `accuracy` use the keys,
+/// units and ranges of the server's score metrics, so the shell can pass
+/// the ones a game declares as `metrics` to `POST /scores/:gameId`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct GameStats {
+    /// Metres travelled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<i64>,
+    /// Milliseconds to finish, for games with a finish line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<i64>,
+    /// Hits as a percentage of attempts, 0–100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accuracy: Option<i64>,
+    /// Highest level or wave reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<i32>,
+    /// Pickups, passengers, treasures and the like gathered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collectibles: Option<i32>,
+    /// Lives or hit points lost.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deaths: Option<i32>,
+}
+
+impl GameStats {
+    /// Metres for a distance in world units.
+    pub fn metres(units: f32) -> Option<i64> {
+        Some((units.max(0.0) / UNITS_PER_METRE) as i64)
+    }
+
+    /// `hits` as a percentage of `attempts`, or `None` before the first
+    /// attempt.
+    pub fn percent(hits: i32, attempts: i32) -> Option<i64> {
+        (attempts > 0).then(|| i64::from(hits.min(attempts)) * 100 / i64::from(attempts))
+    }
+}
+
+/// Options passed alongside `start_game`, as parsed JSON.  `Null` when the
+/// shell didn't pass any.  Features read their own keys (e.g. `"ghost"`).
+#[derive(Resource, Debug, Clone, Default)]
+pub struct GameOptions {
+    pub raw: serde_json::Value,
+}
+
+impl GameOptions {
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.raw.get(key)
+    }
+}
+
 /// Resource used to signal which game should be loaded when transitioning
 /// to the `Playing` state.
 #[derive(Resource, Debug, Clone)]
@@ -59,12 +152,15 @@ pub struct PendingGame {
 pub struct StopGameSignal;
 
 // ---------------------------------------------------------------------------
-// Global static holding the Bevy `App` (needed because wasm‑bindgen exports
-// are free functions – we cannot pass a &mut App across the FFI boundary).
+// Global holding the Bevy `App` (needed because wasm‑bindgen exports are
+// free functions – we cannot pass a &mut App across the FFI boundary).
+// Thread-local because `App` isn't `Send`; the browser runs us on one thread.
 // ---------------------------------------------------------------------------
 
-use std::sync::Mutex;
-static APP: Mutex<Option<App>> = Mutex::new(None);
+use std::cell::RefCell;
+thread_local! {
+    static APP: RefCell<Option<App>> = const { RefCell::new(None) };
+}
 
 // ---------------------------------------------------------------------------
 // wasm‑bindgen exports
@@ -75,6 +171,9 @@ static APP: Mutex<Option<App>> = Mutex::new(None);
 /// does **not** start a game scene – call `start_game` for that.
 #[wasm_bindgen]
 pub fn init_engine(canvas_id: &str) {
+    // Report panics to the server before anything can panic.
+    crash::install();
+
     // Build the CSS selector from the bare id.
     let selector = format!("#{}", canvas_id);
 
@@ -104,16 +203,104 @@ pub fn init_engine(canvas_id: &str) {
 
     // -- Resources ------------------------------------------------------
     app.init_resource::<BevyBridge>();
+    app.init_resource::<GameOptions>();
+
+    // -- Keyboard / gamepad actions and haptics -------------------------
+    app.add_plugins(input::InputPlugin);
 
     // -- Game plugins ---------------------------------------------------
     app.add_plugins(GamePlugin);
 
+    // -- Fixed-timestep simulation and interpolation --------------------
+    app.add_plugins(timestep::TimestepPlugin);
+
     // -- Pixar-style character rendering --------------------------------
     app.add_plugins(pixar::PixarPlugin);
 
     // -- Runtime asset uploads (sprites, .glb/.gltf) --------------------
     app.add_plugins(asset_loader::AssetLoaderPlugin);
 
+    // -- Asset preloading and the loading screen -----------------------
+    app.add_plugins(preload::PreloadPlugin);
+
+    // -- 3-D cameras, lighting and terrain ------------------------------
+    app.add_plugins(scene3d::Scene3dPlugin);
+
+    // -- Ghost-race recording / playback for runner games ---------------
+    app.add_plugins(ghost::GhostPlugin);
+
+    // -- Equipped hats, skins and trails on the hero -------------------
+    app.add_plugins(cosmetics::CosmeticsPlugin);
+
+    // -- Timed power-ups (shield, magnet, slow-motion, ...) -------------
+    app.add_plugins(powerups::PowerupPlugin);
+
+    // -- Save/restore of in-progress puzzles across reloads -------------
+    app.add_plugins(persistence::PersistencePlugin);
+
+    // -- Pause menu and player settings ---------------------------------
+    app.add_plugins(ui::menu::MenuPlugin);
+
+    // -- Game over screen with retry and share --------------------------
+    app.add_plugins(ui::game_over::GameOverPlugin);
+
+    // -- Colorblind palettes, contrast, motion and announcements --------
+    app.add_plugins(accessibility::AccessibilityPlugin);
+
+    // -- Locales, script fonts and right-to-left HUDs -------------------
+    app.add_plugins(i18n::I18nPlugin);
+
+    // -- Campaign worlds, stars and level unlocks -----------------------
+    app.add_plugins(campaign::CampaignPlugin);
+
+    // -- Gameplay telemetry ---------------------------------------------
+    app.add_plugins(analytics::AnalyticsPlugin);
+
+    // -- Death/failure positions for the heatmap ------------------------
+    app.add_plugins(diagnostics::DiagnosticsPlugin);
+
+    // -- Recent inputs for crash reports --------------------------------
+    app.add_plugins(crash::CrashPlugin);
+
+    // -- Frame-time overlay and adaptive quality ------------------------
+    app.add_plugins(perf::PerfPlugin);
+
+    // -- Time Attack / Endless / Zen modes ------------------------------
+    app.add_plugins(game_mode::GameModePlugin);
+
+    // -- Two-player local versus ----------------------------------------
+    app.add_plugins(versus::VersusPlugin);
+
+    // -- Bot opponents for versus games and matchmaking fallback --------
+    app.add_plugins(ai::AiPlugin);
+
+    // -- Server-driven game tuning --------------------------------------
+    app.add_plugins(remote_config::RemoteConfigPlugin);
+
+    // -- Room streaming and read-only spectating ------------------------
+    app.add_plugins(spectator::SpectatorPlugin);
+
+    // -- Offline score queue (IndexedDB) --------------------------------
+    app.add_plugins(sync::SyncPlugin);
+
+    // -- Eased grid movement --------------------------------------------
+    app.add_plugins(tween::TweenPlugin);
+
+    // -- Camera follow, screen shake and zoom ---------------------------
+    app.add_plugins(camera::CameraPlugin);
+
+    // -- Personal bests and the new-record banner ----------------------
+    app.add_plugins(records::RecordsPlugin);
+
+    // -- First-play tutorials ------------------------------------------
+    app.add_plugins(tutorial::TutorialPlugin);
+
+    // -- Screenshots and replay clips for sharing -----------------------
+    app.add_plugins(capture::CapturePlugin);
+
+    // -- Playtime limits and take-a-break nudges -------------------------
+    app.add_plugins(wellbeing::WellbeingPlugin);
+
     // -- Startup: spawn a 2‑D camera that persists across states --------
     app.add_systems(Startup, setup_camera);
 
@@ -124,29 +311,44 @@ pub fn init_engine(canvas_id: &str) {
     // We do NOT call `app.run()` here because Bevy's default runner for
     // WASM will take over the browser's requestAnimationFrame loop.
     // Instead, we call `app.run()` once, and it keeps going.
-    *APP.lock().unwrap() = Some(app);
+    APP.with(|a| *a.borrow_mut() = Some(app));
 
     // Actually start the Bevy render loop.
-    if let Some(app) = APP.lock().unwrap().take() {
+    if let Some(mut app) = APP.with(|a| a.borrow_mut().take()) {
         // `app.run()` on WASM is non‑blocking; it schedules
         // requestAnimationFrame callbacks internally.
         app.run();
     }
 }
 
-/// Load and start the game scene identified by `game_id`.
-/// Currently supported: `"campus_dash"`.
+/// Load and start the game scene identified by `game_id`, one of the ids
+/// from `get_available_games()` (see [`catalog`]).
+///
+/// `options` is an optional JSON object string, e.g.
+/// `{"ghost":{"source":"personal_best"}}` or
+/// `{"mode":"time_attack","timeLimit":90}` (see [`game_mode`]), or
+/// `{"mode":"versus"}` for two players on one keyboard (see [`versus`]), or
+/// `{"bot":{"difficulty":"hard"}}` to play a computer opponent (see [`ai`]), or
+/// `{"room":"<roomId>"}` to stream the run to the room's spectators (see
+/// [`spectator`]), or `{"cosmetics":{"hat":"crown"}}` to dress the hero
+/// (see [`cosmetics`]), or the `options` from `GET /daily/:gameId` (or
+/// `{"seed":1234}`) for seeded levels and obstacles (see [`levelgen`]).
+/// It is exposed to systems as the [`GameOptions`] resource.
 #[wasm_bindgen]
-pub fn start_game(game_id: &str) {
+pub fn start_game(game_id: &str, options: Option<String>) {
     // We cannot mutate the App after `run()` from outside.  Instead we
     // insert a resource that a Bevy system will pick up on the next frame.
     // Because `app.run()` has already been called, we use web_sys to
     // communicate via a global JS variable that a Bevy system polls.
+    set_js_global("__bevy_pending_options", options.as_deref().unwrap_or(""));
     set_js_global("__bevy_pending_game", game_id);
 }
 
-/// Stop the current game and return the final score as a JSON string.
-/// Example return value: `{"game_id":"campus_dash","score":42}`
+/// Stop the current game and return the final score and stats as a JSON
+/// string (see [`GameStats`]), with the player's best from before the run
+/// (`null` on a game's first run; see [`records`]).
+/// Example return value:
+/// `{"game_id":"campus_dash","score":42,"previous_best":35,"stats":{"distance":318,"collectibles":3}}`
 #[wasm_bindgen]
 pub fn stop_game() -> String {
     set_js_global("__bevy_stop_signal", "true");
@@ -155,7 +357,13 @@ pub fn stop_game() -> String {
         .and_then(|s| s.parse::<i32>().ok())
         .unwrap_or(0);
     let game_id = get_js_global("__bevy_game_id").unwrap_or_default();
-    format!("{{\"game_id\":\"{}\",\"score\":{}}}", game_id, score)
+    serde_json::json!({
+        "game_id": game_id,
+        "score": score,
+        "previous_best": records::previous_best(),
+        "stats": read_stats(),
+    })
+    .to_string()
 }
 
 /// Return the current score of the running game (or 0 if no game is active).
@@ -166,11 +374,24 @@ pub fn get_score() -> i32 {
         .unwrap_or(0)
 }
 
+/// Return the running game's stats as a JSON object string, e.g.
+/// `{"accuracy":72,"level":4}` (or `{}` if no game is active).
+#[wasm_bindgen]
+pub fn get_stats() -> String {
+    read_stats().to_string()
+}
+
+fn read_stats() -> serde_json::Value {
+    get_js_global("__bevy_current_stats")
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_else(|| serde_json::json!({}))
+}
+
 // ---------------------------------------------------------------------------
 // JS global helpers  (communicate between free‑fn exports and Bevy systems)
 // ---------------------------------------------------------------------------
 
-fn set_js_global(key: &str, value: &str) {
+pub(crate) fn set_js_global(key: &str, value: &str) {
     let window = web_sys::window().expect("no global window");
     js_sys::Reflect::set(
         &window,
@@ -180,13 +401,13 @@ fn set_js_global(key: &str, value: &str) {
     .ok();
 }
 
-fn get_js_global(key: &str) -> Option<String> {
+pub(crate) fn get_js_global(key: &str) -> Option<String> {
     let window = web_sys::window()?;
     let val = js_sys::Reflect::get(&window, &JsValue::from_str(key)).ok()?;
     val.as_string()
 }
 
-fn delete_js_global(key: &str) {
+pub(crate) fn delete_js_global(key: &str) {
     if let Some(window) = web_sys::window() {
         js_sys::Reflect::set(
             &window,
@@ -201,7 +422,7 @@ fn delete_js_global(key: &str) {
 // Bevy systems (run inside the Bevy schedule, have full World access)
 // ---------------------------------------------------------------------------
 
-/// Spawn a 2‑D camera once at startup.
+/// Spawn a 2‑D camera once at startup.  [`camera`] moves it during a game.
 fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2d);
 }
@@ -212,13 +433,20 @@ fn handle_stop_signal(
     mut next_state: ResMut<NextState<AppState>>,
     current_state: Res<State<AppState>>,
     mut bridge: ResMut<BevyBridge>,
+    mut options: ResMut<GameOptions>,
 ) {
     // ---- Check for "start game" signal --------------------------------
-    if let Some(game_id) = get_js_global("__bevy_pending_game") {
+    // Left pending while assets preload, so it's picked up afterwards.
+    let loading = *current_state.get() == AppState::Loading;
+    if let Some(game_id) = get_js_global("__bevy_pending_game").filter(|_| !loading) {
         if !game_id.is_empty() {
             delete_js_global("__bevy_pending_game");
+            let raw = get_js_global("__bevy_pending_options").unwrap_or_default();
+            delete_js_global("__bevy_pending_options");
+            options.raw = serde_json::from_str(&raw).unwrap_or(serde_json::Value::Null);
             bridge.game_id = game_id;
             bridge.current_score = 0;
+            bridge.stats = GameStats::default();
             next_state.set(AppState::Playing);
         }
     }
@@ -240,4 +468,8 @@ fn handle_stop_signal(
         &bridge.current_score.to_string(),
     );
     set_js_global("__bevy_game_id", &bridge.game_id);
+    if bridge.is_changed() {
+        let stats = serde_json::to_string(&bridge.stats).unwrap_or_default();
+        set_js_global("__bevy_current_stats", &stats);
+    }
 }
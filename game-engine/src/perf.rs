@@ -0,0 +1,410 @@
+//! Frame-time overlay and adaptive quality.
+//!
+//! Every frame's time is kept for the last [`GRAPH_FRAMES`] frames.  The
+//! shell can show them with `set_perf_overlay(true)`: a small panel in the
+//! corner with FPS, the average frame time and a bar per frame, green
+//! within [`FRAME_BUDGET_MS`], yellow up to twice that and red beyond.
+//! `get_perf_stats()` returns the same numbers as JSON.
+//!
+//! While a game runs, the average frame time is checked every
+//! [`CHECK_SECS`].  Two slow checks in a row drop the [`Quality`] a level;
+//! [`RECOVER_SECS`] of fast frames raise it again.  Vsync caps frames at the
+//! display's rate, so "fast" is just under the budget rather than well
+//! under it.  The level holds across runs, so a slow Chromebook settles on
+//! one instead of starting every game at full quality.
+//!
+//! | Quality   | Particles | Eye blink, breathing | Enemy spawns |
+//! |-----------|-----------|----------------------|--------------|
+//! | `High`    | all       | on                   | normal       |
+//! | `Reduced` | half      | off                  | normal       |
+//! | `Low`     | a quarter | off                  | 2/3 as often |
+//!
+//! `set_quality("auto" | "high" | "reduced" | "low")` fixes the level
+//! instead, e.g. from a settings page.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::AppState;
+
+/// Frames shown in the overlay's graph.
+pub const GRAPH_FRAMES: usize = 120;
+/// Frame time the engine aims for: 50 fps, leaving room for vsync jitter
+/// on 60 Hz displays.
+pub const FRAME_BUDGET_MS: f32 = 20.0;
+/// Seconds between quality checks.
+pub const CHECK_SECS: f32 = 2.0;
+/// Seconds of fast frames before quality goes back up.
+pub const RECOVER_SECS: f32 = 30.0;
+/// Average frame time below which frames count as fast.
+const FAST_MS: f32 = 17.5;
+/// Frames longer than this (a hidden tab, a GC pause) are left out of the
+/// checks.
+const MAX_SAMPLE_MS: f32 = 250.0;
+/// Frame time at the top of the graph.
+const GRAPH_MAX_MS: f32 = 50.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+
+/// Quality setting from the shell: 0 is auto, otherwise a fixed level.
+static MODE: AtomicU8 = AtomicU8::new(0);
+static OVERLAY: AtomicBool = AtomicBool::new(false);
+/// What `get_perf_stats` returns, refreshed with the overlay text.
+static LATEST: Mutex<Option<serde_json::Value>> = Mutex::new(None);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct PerfPlugin;
+
+impl Plugin for PerfPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quality>()
+            .init_resource::<FrameTimes>()
+            .init_resource::<Adaptation>()
+            .add_systems(OnEnter(AppState::Playing), reset_checks)
+            .add_systems(
+                Update,
+                (
+                    record_frame,
+                    adapt_quality.run_if(in_state(AppState::Playing)),
+                    toggle_overlay,
+                    update_overlay,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Quality
+// ---------------------------------------------------------------------------
+
+/// How much visual work the engine does.  Effects and games read it to
+/// scale themselves down.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quality {
+    Low,
+    Reduced,
+    #[default]
+    High,
+}
+
+impl Quality {
+    fn from_mode(mode: u8) -> Option<Self> {
+        match mode {
+            1 => Some(Quality::High),
+            2 => Some(Quality::Reduced),
+            3 => Some(Quality::Low),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quality::High => "high",
+            Quality::Reduced => "reduced",
+            Quality::Low => "low",
+        }
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            Quality::High => Quality::Reduced,
+            _ => Quality::Low,
+        }
+    }
+
+    fn higher(self) -> Self {
+        match self {
+            Quality::Low => Quality::Reduced,
+            _ => Quality::High,
+        }
+    }
+
+    /// Share of particles to emit.
+    pub fn particle_scale(self) -> f32 {
+        match self {
+            Quality::High => 1.0,
+            Quality::Reduced => 0.5,
+            Quality::Low => 0.25,
+        }
+    }
+
+    /// Whether characters blink and breathe.
+    pub fn idle_animations(self) -> bool {
+        self == Quality::High
+    }
+
+    /// Factor to stretch enemy spawn intervals by.
+    pub fn spawn_interval_scale(self) -> f32 {
+        match self {
+            Quality::Low => 1.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Run condition for the idle animations, which reduced motion also
+/// turns off.
+pub fn idle_animations_enabled(quality: Res<Quality>, settings: Res<AccessibilitySettings>) -> bool {
+    quality.idle_animations() && !settings.reduced_motion
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Show or hide the frame-time overlay (default hidden).
+#[wasm_bindgen]
+pub fn set_perf_overlay(visible: bool) {
+    OVERLAY.store(visible, Ordering::Release);
+}
+
+/// `"auto"` (the default) adapts quality to the frame rate; `"high"`,
+/// `"reduced"` or `"low"` fixes it.  Anything else is ignored.
+#[wasm_bindgen]
+pub fn set_quality(mode: &str) {
+    let mode = match mode {
+        "auto" => 0,
+        "high" => 1,
+        "reduced" => 2,
+        "low" => 3,
+        _ => return,
+    };
+    MODE.store(mode, Ordering::Release);
+}
+
+/// Frame stats as a JSON object string, e.g.
+/// `{"fps":57,"frameMs":17.4,"worstMs":31.2,"quality":"reduced","mode":"auto"}`,
+/// or `{}` before the first frames.
+#[wasm_bindgen]
+pub fn get_perf_stats() -> String {
+    LATEST
+        .lock()
+        .ok()
+        .and_then(|latest| latest.clone())
+        .unwrap_or_else(|| json!({}))
+        .to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Frame times
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Default)]
+struct FrameTimes {
+    /// Milliseconds, oldest first.
+    frames: VecDeque<f32>,
+}
+
+impl FrameTimes {
+    fn average(&self) -> f32 {
+        if self.frames.is_empty() {
+            return 0.0;
+        }
+        self.frames.iter().sum::<f32>() / self.frames.len() as f32
+    }
+
+    fn worst(&self) -> f32 {
+        self.frames.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// Frame times since the last check, and how the checks have gone.
+#[derive(Resource, Default)]
+struct Adaptation {
+    elapsed: f32,
+    total_ms: f32,
+    samples: u32,
+    slow_checks: u32,
+    fast_secs: f32,
+}
+
+fn record_frame(time: Res<Time<Real>>, mut times: ResMut<FrameTimes>, mut adaptation: ResMut<Adaptation>) {
+    let ms = time.delta_secs() * 1000.0;
+    if times.frames.len() == GRAPH_FRAMES {
+        times.frames.pop_front();
+    }
+    times.frames.push_back(ms);
+
+    adaptation.elapsed += time.delta_secs();
+    if ms <= MAX_SAMPLE_MS {
+        adaptation.total_ms += ms;
+        adaptation.samples += 1;
+    }
+}
+
+/// Loading a game is slow; start each run's checks afresh.
+fn reset_checks(mut adaptation: ResMut<Adaptation>) {
+    *adaptation = Adaptation::default();
+}
+
+fn adapt_quality(mut quality: ResMut<Quality>, mut adaptation: ResMut<Adaptation>) {
+    if let Some(fixed) = Quality::from_mode(MODE.load(Ordering::Acquire)) {
+        if *quality != fixed {
+            *quality = fixed;
+        }
+        return;
+    }
+    if adaptation.elapsed < CHECK_SECS {
+        return;
+    }
+
+    let average = if adaptation.samples > 0 {
+        adaptation.total_ms / adaptation.samples as f32
+    } else {
+        0.0
+    };
+    let checked = adaptation.elapsed;
+    adaptation.elapsed = 0.0;
+    adaptation.total_ms = 0.0;
+    adaptation.samples = 0;
+    if average == 0.0 {
+        return;
+    }
+
+    if average > FRAME_BUDGET_MS {
+        adaptation.fast_secs = 0.0;
+        adaptation.slow_checks += 1;
+        if adaptation.slow_checks >= 2 && *quality != Quality::Low {
+            *quality = quality.lower();
+            adaptation.slow_checks = 0;
+            info!("Frame time {:.1} ms over budget; quality lowered to {}", average, quality.label());
+        }
+        return;
+    }
+
+    adaptation.slow_checks = 0;
+    if average < FAST_MS {
+        adaptation.fast_secs += checked;
+        if adaptation.fast_secs >= RECOVER_SECS && *quality != Quality::High {
+            *quality = quality.higher();
+            adaptation.fast_secs = 0.0;
+            info!("Frame time back within budget; quality raised to {}", quality.label());
+        }
+    } else {
+        adaptation.fast_secs = 0.0;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Overlay
+// ---------------------------------------------------------------------------
+
+#[derive(Component)]
+struct PerfOverlay;
+
+#[derive(Component)]
+struct PerfText;
+
+/// The graph bar for the frame this many frames before the newest.
+#[derive(Component)]
+struct PerfBar(usize);
+
+fn toggle_overlay(mut commands: Commands, overlay: Query<Entity, With<PerfOverlay>>) {
+    let visible = OVERLAY.load(Ordering::Acquire);
+    match (visible, overlay.get_single()) {
+        (true, Err(_)) => spawn_overlay(&mut commands),
+        (false, Ok(entity)) => commands.entity(entity).despawn_recursive(),
+        _ => {}
+    }
+}
+
+fn spawn_overlay(commands: &mut Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.65)),
+            GlobalZIndex(120),
+            PerfOverlay,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont { font_size: 12.0, ..default() },
+                TextColor(Color::WHITE),
+                PerfText,
+            ));
+            panel
+                .spawn(Node {
+                    width: Val::Px(GRAPH_FRAMES as f32),
+                    height: Val::Px(GRAPH_HEIGHT),
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for i in (0..GRAPH_FRAMES).rev() {
+                        graph.spawn((
+                            Node { width: Val::Px(1.0), height: Val::Px(0.0), ..default() },
+                            BackgroundColor(Color::NONE),
+                            PerfBar(i),
+                        ));
+                    }
+                });
+        });
+}
+
+fn bar_color(ms: f32) -> Color {
+    if ms <= FRAME_BUDGET_MS {
+        Color::srgb(0.3, 0.85, 0.4)
+    } else if ms <= FRAME_BUDGET_MS * 2.0 {
+        Color::srgb(0.95, 0.8, 0.2)
+    } else {
+        Color::srgb(0.95, 0.3, 0.25)
+    }
+}
+
+fn update_overlay(
+    time: Res<Time<Real>>,
+    times: Res<FrameTimes>,
+    quality: Res<Quality>,
+    mut since_text: Local<f32>,
+    mut text: Query<&mut Text, With<PerfText>>,
+    mut bars: Query<(&PerfBar, &mut Node, &mut BackgroundColor)>,
+) {
+    // Text and stats change twice a second, so they stay readable.
+    *since_text += time.delta_secs();
+    if *since_text >= 0.5 {
+        *since_text = 0.0;
+        let average = times.average();
+        let fps = if average > 0.0 { (1000.0 / average).round() } else { 0.0 };
+        let mode = Quality::from_mode(MODE.load(Ordering::Acquire)).map_or("auto", |_| "fixed");
+        if let Ok(mut latest) = LATEST.lock() {
+            *latest = Some(json!({
+                "fps": fps,
+                "frameMs": (average * 10.0).round() / 10.0,
+                "worstMs": (times.worst() * 10.0).round() / 10.0,
+                "quality": quality.label(),
+                "mode": mode,
+            }));
+        }
+        if let Ok(mut text) = text.get_single_mut() {
+            text.0 = format!("{} fps  {:.1} ms  {} ({})", fps, average, quality.label(), mode);
+        }
+    }
+
+    let newest = times.frames.len();
+    for (PerfBar(age), mut node, mut color) in &mut bars {
+        let Some(&ms) = newest.checked_sub(age + 1).and_then(|i| times.frames.get(i)) else {
+            node.height = Val::Px(0.0);
+            continue;
+        };
+        node.height = Val::Px((ms / GRAPH_MAX_MS).min(1.0) * GRAPH_HEIGHT);
+        color.0 = bar_color(ms);
+    }
+}
@@ -0,0 +1,141 @@
+//! Save/restore of in-progress games across page reloads.
+//!
+//! Games that support resuming write a [`SavedGame`] to `localStorage`
+//! every [`SAVE_INTERVAL_SECS`] (their save system runs with the
+//! [`save_due`] run condition).  After a reload, the shell calls
+//! `resume_game(game_id)`, which restarts the game with a `"resume"`
+//! option that the game's `setup` picks up via [`resume_data`].
+//!
+//! The save is discarded whenever a run ends normally — game over, quit or
+//! `stop_game` — so only an interrupted run can be resumed.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{AppState, BevyBridge, GameOptions};
+
+/// Seconds between automatic saves.
+pub const SAVE_INTERVAL_SECS: f32 = 5.0;
+
+const STORAGE_PREFIX: &str = "stem_save:";
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveTimer(Timer::from_seconds(
+            SAVE_INTERVAL_SECS,
+            TimerMode::Repeating,
+        )))
+        .add_systems(OnEnter(AppState::Playing), reset_timer)
+        // PreUpdate so `save_due` is already set when game systems run.
+        .add_systems(PreUpdate, tick_timer.run_if(in_state(AppState::Playing)))
+        .add_systems(OnExit(AppState::Playing), clear_on_exit);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Snapshot of a game in progress.  `grid` is the player's grid cell;
+/// anything else a game needs to rebuild its board goes in `extra`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SavedGame {
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub level: usize,
+    pub score: i32,
+    #[serde(default)]
+    pub moves: i32,
+    #[serde(default)]
+    pub grid: Option<(i32, i32)>,
+    #[serde(default)]
+    pub extra: serde_json::Value,
+}
+
+#[derive(Resource)]
+pub struct SaveTimer(Timer);
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Resume the saved run of `game_id`, if there is one.  Returns `false`
+/// (and does nothing) when no save exists, so the shell can fall back to
+/// `start_game`.
+#[wasm_bindgen]
+pub fn resume_game(game_id: &str) -> bool {
+    let Some(saved) = load(game_id) else {
+        return false;
+    };
+    let options = serde_json::json!({ "resume": saved });
+    crate::start_game(game_id, Some(options.to_string()));
+    true
+}
+
+// ---------------------------------------------------------------------------
+// API for games
+// ---------------------------------------------------------------------------
+
+/// Run condition: true on the frames when games should write a save.
+pub fn save_due(timer: Res<SaveTimer>) -> bool {
+    timer.0.just_finished()
+}
+
+/// The save to restore for `game_id`, if the current run was started by
+/// `resume_game`.
+pub fn resume_data(options: &GameOptions, game_id: &str) -> Option<SavedGame> {
+    let saved: SavedGame = serde_json::from_value(options.get("resume")?.clone()).ok()?;
+    (saved.game_id == game_id).then_some(saved)
+}
+
+/// Write a save to `localStorage`, replacing any previous one for the game.
+pub fn save(saved: &SavedGame) {
+    let (Some(storage), Ok(json)) = (local_storage(), serde_json::to_string(saved)) else {
+        return;
+    };
+    storage
+        .set_item(&format!("{}{}", STORAGE_PREFIX, saved.game_id), &json)
+        .ok();
+}
+
+pub fn load(game_id: &str) -> Option<SavedGame> {
+    let json = local_storage()?
+        .get_item(&format!("{}{}", STORAGE_PREFIX, game_id))
+        .ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn clear(game_id: &str) {
+    if let Some(storage) = local_storage() {
+        storage
+            .remove_item(&format!("{}{}", STORAGE_PREFIX, game_id))
+            .ok();
+    }
+}
+
+pub(crate) fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn reset_timer(mut timer: ResMut<SaveTimer>) {
+    timer.0.reset();
+}
+
+fn tick_timer(time: Res<Time>, mut timer: ResMut<SaveTimer>) {
+    timer.0.tick(time.delta());
+}
+
+fn clear_on_exit(bridge: Res<BevyBridge>) {
+    clear(&bridge.game_id);
+}
@@ -3,10 +3,52 @@
 //! Provides procedurally generated circle textures, multi-layered sprite
 //! characters with big expressive eyes, highlight/shadow layers, and
 //! subtle idle animations for a Pixar movie aesthetic.
+//!
+//! **Skins.**  A [`CharacterConfig`] can name an uploaded asset to draw
+//! instead: heroes use [`HERO_ASSET`] and enemies [`ENEMY_ASSET`] unless a
+//! game says otherwise, so a tenant re-skins every game's heroes and
+//! enemies by uploading their mascot under those names.
+//!
+//! * A sprite uploaded with `upload_sprite(role, ...)` is used as the body,
+//!   cropped to its shape.
+//! * A glTF model, uploaded with `upload_gltf(name, ...)` or listed in the
+//!   preload manifest (see [`preload`](crate::preload)), is rendered to a
+//!   texture by its own offscreen camera and used the same way, so it works
+//!   in 2-D games too.  Models should stand about 2 m tall on the origin,
+//!   facing `+Z` as glTF does.
+//!
+//! Skinned characters keep their shadow and idle animation but not the
+//! drawn face.  Until the asset arrives, or if there's none, characters are
+//! drawn as usual.
 
+use std::collections::HashMap;
+
+use bevy::gltf::Gltf;
 use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
 use bevy::render::render_asset::RenderAssetUsages;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::RenderLayers;
+use bevy::scene::SceneInstanceReady;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::asset_loader::CustomAssets;
+use crate::perf::{self, Quality};
+use crate::preload::Preloaded;
+use crate::scene3d;
+
+/// Asset heroes are skinned with when uploaded.
+pub const HERO_ASSET: &str = "hero";
+/// Asset enemies are skinned with when uploaded.
+pub const ENEMY_ASSET: &str = "enemy";
+
+/// Side of the square textures models are rendered to.
+const PORTRAIT_SIZE: u32 = 256;
+/// Keeps portrait models out of every game camera's view.
+const PORTRAIT_LAYER: usize = 7;
+/// Where the first portrait is staged, far from any game's scene.
+const PORTRAIT_STAGE: Vec3 = Vec3::new(0.0, -10_000.0, 0.0);
+const PORTRAIT_SPACING: f32 = 100.0;
 
 // ---------------------------------------------------------------------------
 // Plugin
@@ -17,14 +59,26 @@ pub struct PixarPlugin;
 impl Plugin for PixarPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, init_pixar_assets);
+        app.add_systems(Update, sync_skins);
         app.add_systems(
             Update,
-            (animate_breathing, animate_scale_pulse, animate_eye_blink)
+            (
+                (animate_breathing, animate_eye_blink).run_if(perf::idle_animations_enabled),
+                animate_scale_pulse,
+                settle_idle_animations
+                    .run_if(resource_changed::<Quality>.or(resource_changed::<AccessibilitySettings>)),
+            )
+                .in_set(IdleAnimation)
                 .run_if(in_state(crate::AppState::Playing)),
         );
     }
 }
 
+/// The idle animations.  They overwrite `Transform::scale` every frame, so
+/// systems that squash or stretch a character run after this set.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdleAnimation;
+
 // ---------------------------------------------------------------------------
 // Resources
 // ---------------------------------------------------------------------------
@@ -34,11 +88,21 @@ impl Plugin for PixarPlugin {
 pub struct PixarAssets {
     /// 64x64 anti-aliased white circle — tint via `Sprite::color`.
     pub circle: Handle<Image>,
+    /// Uploaded art characters can be drawn with, by asset id.
+    pub skins: HashMap<String, Skin>,
+}
+
+/// An image a character can be drawn with instead of its shapes.
+#[derive(Clone, Debug)]
+pub struct Skin {
+    pub image: Handle<Image>,
+    /// The image's size in pixels.
+    pub size: Vec2,
 }
 
 fn init_pixar_assets(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     let circle = create_circle_texture(&mut images);
-    commands.insert_resource(PixarAssets { circle });
+    commands.insert_resource(PixarAssets { circle, skins: HashMap::new() });
 }
 
 // ---------------------------------------------------------------------------
@@ -121,6 +185,7 @@ pub struct PixarEye;
 // ---------------------------------------------------------------------------
 
 /// Describes how to render a Pixar-style character.
+#[derive(Clone)]
 pub struct CharacterConfig {
     pub body_color: Color,
     pub body_size: Vec2,
@@ -138,6 +203,9 @@ pub struct CharacterConfig {
     pub breathing: bool,
     /// Pulsing scale (for collectibles).
     pub scale_pulse: bool,
+    /// Uploaded asset to draw instead, by upload role or model name (see
+    /// the module docs).  Drawn as usual while there's no such asset.
+    pub asset: Option<String>,
 }
 
 impl CharacterConfig {
@@ -153,6 +221,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: true,
             scale_pulse: false,
+            asset: Some(HERO_ASSET.to_string()),
         }
     }
 
@@ -168,6 +237,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: true,
             scale_pulse: false,
+            asset: Some(ENEMY_ASSET.to_string()),
         }
     }
 
@@ -183,6 +253,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: false,
             scale_pulse: true,
+            asset: None,
         }
     }
 
@@ -198,6 +269,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: false,
             scale_pulse: false,
+            asset: None,
         }
     }
 
@@ -213,6 +285,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: true,
             scale_pulse: false,
+            asset: None,
         }
     }
 
@@ -228,6 +301,7 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: true,
             scale_pulse: false,
+            asset: None,
         }
     }
 
@@ -243,6 +317,7 @@ impl CharacterConfig {
             has_highlight: round,
             breathing: false,
             scale_pulse: false,
+            asset: None,
         }
     }
 
@@ -258,8 +333,15 @@ impl CharacterConfig {
             has_highlight: true,
             breathing: false,
             scale_pulse: false,
+            asset: None,
         }
     }
+
+    /// Skins the character with the uploaded asset `id`, or with nothing.
+    pub fn with_asset(mut self, id: Option<&str>) -> Self {
+        self.asset = id.map(str::to_string);
+        self
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -271,6 +353,7 @@ impl CharacterConfig {
 /// Returns the parent `Entity` which owns the body `Sprite` plus all
 /// components from `bundle`.  Child entities (eyes, highlights, shadow,
 /// blush) are attached automatically and despawn with the parent.
+/// Characters whose asset has been uploaded are drawn with it instead.
 pub fn spawn_character(
     commands: &mut Commands,
     assets: &PixarAssets,
@@ -278,6 +361,10 @@ pub fn spawn_character(
     position: Vec3,
     bundle: impl Bundle,
 ) -> Entity {
+    if let Some(skin) = config.asset.as_deref().and_then(|id| assets.skins.get(id)) {
+        return spawn_skinned(commands, assets, config, skin, position, bundle);
+    }
+
     let body_sprite = if config.is_round {
         Sprite {
             image: assets.circle.clone(),
@@ -414,8 +501,62 @@ pub fn spawn_character(
     });
 
     let entity = ec.id();
+    add_animations(commands, entity, config);
+    entity
+}
+
+/// Spawns a character drawn with `skin`, keeping its shadow and idle
+/// animation.  The body sprite keeps the configured size and alpha.
+fn spawn_skinned(
+    commands: &mut Commands,
+    assets: &PixarAssets,
+    config: &CharacterConfig,
+    skin: &Skin,
+    position: Vec3,
+    bundle: impl Bundle,
+) -> Entity {
+    let size = config.body_size;
+    let body_sprite = Sprite {
+        image: skin.image.clone(),
+        color: Color::WHITE.with_alpha(config.body_color.alpha()),
+        custom_size: Some(size),
+        rect: Some(cover_rect(skin.size, size)),
+        ..default()
+    };
+
+    let mut ec = commands.spawn((body_sprite, Transform::from_translation(position), bundle));
+    if config.has_highlight {
+        let circle = assets.circle.clone();
+        ec.with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    image: circle,
+                    color: palette::SHADOW,
+                    custom_size: Some(Vec2::new(size.x * 0.8, size.y * 0.12)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, -size.y * 0.55, -0.05),
+            ));
+        });
+    }
+
+    let entity = ec.id();
+    let config = CharacterConfig { eye_scale: 0.0, ..config.clone() };
+    add_animations(commands, entity, &config);
+    entity
+}
 
-    // -- Attach animation components ----------------------------------------
+/// The middle part of an image of size `image` with the shape of `target`,
+/// so the skin fills the body without being stretched.
+fn cover_rect(image: Vec2, target: Vec2) -> Rect {
+    if image.x <= 0.0 || image.y <= 0.0 || target.x <= 0.0 || target.y <= 0.0 {
+        return Rect::from_corners(Vec2::ZERO, image);
+    }
+    let scale = (image.x / target.x).min(image.y / target.y);
+    Rect::from_center_size(image / 2.0, target * scale)
+}
+
+fn add_animations(commands: &mut Commands, entity: Entity, config: &CharacterConfig) {
     if config.breathing {
         commands
             .entity(entity)
@@ -437,8 +578,110 @@ pub fn spawn_character(
             blinking: false,
         });
     }
+}
 
-    entity
+// ---------------------------------------------------------------------------
+// Skins
+// ---------------------------------------------------------------------------
+
+/// Marks the offscreen cameras rendering models for skins.
+#[derive(Component)]
+struct Portrait;
+
+/// Makes skins of uploaded sprites and of models as they finish loading.
+/// An uploaded sprite wins over a model of the same name.
+fn sync_skins(
+    mut commands: Commands,
+    custom: Res<CustomAssets>,
+    preloaded: Res<Preloaded>,
+    gltfs: Res<Assets<Gltf>>,
+    mut images: ResMut<Assets<Image>>,
+    mut assets: ResMut<PixarAssets>,
+    portraits: Query<(), With<Portrait>>,
+) {
+    if !custom.is_changed() && !preloaded.is_changed() && !gltfs.is_changed() {
+        return;
+    }
+
+    for (role, image) in &custom.sprites {
+        if assets.skins.get(role).is_some_and(|s| s.image == *image) {
+            continue;
+        }
+        if let Some(size) = images.get(image).map(|i| i.size().as_vec2()) {
+            assets.skins.insert(role.clone(), Skin { image: image.clone(), size });
+        }
+    }
+
+    let mut staged = portraits.iter().count();
+    for name in preloaded.models.keys() {
+        if assets.skins.contains_key(name) {
+            continue;
+        }
+        let Some(scene) = scene3d::model_scene(&preloaded, &gltfs, name) else { continue };
+        let image = spawn_portrait(&mut commands, &mut images, scene, staged);
+        staged += 1;
+        let size = Vec2::splat(PORTRAIT_SIZE as f32);
+        assets.skins.insert(name.clone(), Skin { image, size });
+    }
+}
+
+/// Stages `scene` with its own camera and light, rendering into a new
+/// transparent image.  The `index`th portrait stands apart from the others.
+fn spawn_portrait(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    scene: Handle<Scene>,
+    index: usize,
+) -> Handle<Image> {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: PORTRAIT_SIZE,
+            height: PORTRAIT_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    let handle = images.add(image);
+
+    let stage = PORTRAIT_STAGE + Vec3::X * PORTRAIT_SPACING * index as f32;
+    let layer = RenderLayers::layer(PORTRAIT_LAYER);
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: -2,
+            target: RenderTarget::Image(handle.clone()),
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        Transform::from_translation(stage + Vec3::new(0.0, 1.0, 3.2)).looking_at(stage + Vec3::Y, Vec3::Y),
+        layer.clone(),
+        Portrait,
+    ));
+    // A short-range light, so it can't reach a 3-D game's scene.
+    commands.spawn((
+        PointLight {
+            range: 20.0,
+            ..default()
+        },
+        Transform::from_translation(stage + Vec3::new(1.5, 3.0, 3.0)),
+        layer.clone(),
+    ));
+    commands
+        .spawn((SceneRoot(scene), Transform::from_translation(stage), layer))
+        .observe(layer_scene);
+    handle
+}
+
+/// Moves a portrait model's meshes onto the portrait layer once spawned.
+fn layer_scene(trigger: Trigger<SceneInstanceReady>, children: Query<&Children>, mut commands: Commands) {
+    for entity in children.iter_descendants(trigger.entity()) {
+        commands.entity(entity).insert(RenderLayers::layer(PORTRAIT_LAYER));
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -471,6 +714,26 @@ fn animate_breathing(time: Res<Time>, mut q: Query<(&mut Transform, &mut PixarBr
     }
 }
 
+/// Leaves characters at rest, eyes open, when [`Quality`] or reduced
+/// motion turns the idle animations off.
+fn settle_idle_animations(
+    quality: Res<Quality>,
+    settings: Res<AccessibilitySettings>,
+    mut breathers: Query<&mut Transform, With<PixarBreathing>>,
+    mut eyes: Query<&mut Visibility, With<PixarEye>>,
+) {
+    if perf::idle_animations_enabled(quality, settings) {
+        return;
+    }
+    for mut tf in &mut breathers {
+        tf.scale.x = 1.0;
+        tf.scale.y = 1.0;
+    }
+    for mut v in &mut eyes {
+        *v = Visibility::Inherited;
+    }
+}
+
 fn animate_scale_pulse(time: Res<Time>, mut q: Query<(&mut Transform, &mut ScalePulse)>) {
     let dt = time.delta_secs();
     for (mut tf, mut p) in &mut q {
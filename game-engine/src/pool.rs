@@ -0,0 +1,70 @@
+//! Entity pools for short-lived game objects.
+//!
+//! Shooters fire bullets and runners scroll obstacles past the player, so
+//! without pooling they spawn and despawn entities every frame or two.  A
+//! [`Pool<T>`] keeps the ones a game is done with instead:
+//! [`Pool::release`] hides the entity and marks it [`Parked`], and
+//! [`Pool::acquire`] puts a parked entity back to work with a fresh bundle,
+//! spawning a new one only when none is parked.
+//!
+//! `Parked` is stored as a sparse set, so parking and unparking don't move
+//! the entity's components between tables.  Systems that act on pooled
+//! entities filter with `Without<Parked>`.
+//!
+//! A game inserts its pools in `setup` and removes them in `cleanup`.
+//! Parked entities keep the game's cleanup marker, so they're despawned
+//! with the rest of the scene.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// A pooled entity waiting to be reused.
+#[derive(Component, Debug, Default)]
+#[component(storage = "SparseSet")]
+pub struct Parked;
+
+/// Parked entities whose bundle includes the component `T`.
+#[derive(Resource)]
+pub struct Pool<T: Component> {
+    parked: Vec<Entity>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Component> Default for Pool<T> {
+    fn default() -> Self {
+        Self { parked: Vec::new(), _marker: PhantomData }
+    }
+}
+
+impl<T: Component> Pool<T> {
+    /// Reuses a parked entity for `bundle`, or spawns one.  The bundle
+    /// should set everything the entity's systems read, since a reused
+    /// entity keeps whatever else it had.
+    pub fn acquire<B: Bundle>(&mut self, commands: &mut Commands, bundle: B) -> Entity {
+        match self.parked.pop() {
+            Some(entity) => {
+                commands
+                    .entity(entity)
+                    .remove::<Parked>()
+                    .insert((bundle, Visibility::Inherited));
+                entity
+            }
+            None => commands.spawn(bundle).id(),
+        }
+    }
+
+    /// Hides `entity` and parks it for reuse.  Releasing an entity twice,
+    /// e.g. from two collision checks in the same frame, parks it once.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.parked.contains(&entity) {
+            return;
+        }
+        commands.entity(entity).insert((Parked, Visibility::Hidden));
+        self.parked.push(entity);
+    }
+
+    pub fn parked(&self) -> usize {
+        self.parked.len()
+    }
+}
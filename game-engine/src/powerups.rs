@@ -0,0 +1,326 @@
+//! Timed power-ups shared across games.
+//!
+//! A game spawns [`Pickup`]s with [`spawn_pickup`] and gives its player an
+//! [`ActiveEffects`] component.  Touching a pickup activates its effect for
+//! [`Powerup::duration`] seconds (a new pickup of the same kind restarts
+//! the timer) and records a `powerup_used` analytics event.  What each
+//! effect does is up to the game, which asks `ActiveEffects`:
+//!
+//! | Power-up      | Helper                              | Typical use                       |
+//! |---------------|-------------------------------------|-----------------------------------|
+//! | `Shield`      | [`ActiveEffects::absorb_hit`]       | the next hit costs nothing        |
+//! | `Magnet`      | handled here                        | pickups drift to the player       |
+//! | `SlowMotion`  | [`ActiveEffects::world_scale`]      | obstacles and enemies move slower |
+//! | `DoubleScore` | [`ActiveEffects::score_multiplier`] | points count twice                |
+//! | `SpeedBoost`  | [`ActiveEffects::speed_multiplier`] | the player moves faster           |
+//!
+//! Collection, the magnet and the HUD icon row are generic systems each
+//! game registers for its own `GameEntity` marker, as with bosses.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::analytics::AnalyticsEvent;
+use crate::games::GameplaySet;
+use crate::pixar::{self, palette, PixarAssets};
+
+/// Pickup diameter.
+pub const PICKUP_SIZE: f32 = 26.0;
+/// Pickups within this distance of the player are collected.
+const COLLECT_RADIUS: f32 = 30.0;
+const MAGNET_RADIUS: f32 = 260.0;
+const MAGNET_SPEED: f32 = 420.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct PowerupPlugin;
+
+impl Plugin for PowerupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tick.in_set(GameplaySet));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Power-ups
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Powerup {
+    Shield,
+    Magnet,
+    SlowMotion,
+    DoubleScore,
+    SpeedBoost,
+}
+
+impl Powerup {
+    pub const ALL: [Powerup; 5] = [
+        Self::Shield,
+        Self::Magnet,
+        Self::SlowMotion,
+        Self::DoubleScore,
+        Self::SpeedBoost,
+    ];
+
+    /// Name used in analytics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Shield => "shield",
+            Self::Magnet => "magnet",
+            Self::SlowMotion => "slow_motion",
+            Self::DoubleScore => "double_score",
+            Self::SpeedBoost => "speed_boost",
+        }
+    }
+
+    /// Seconds the effect lasts.
+    pub fn duration(self) -> f32 {
+        match self {
+            Self::Shield => 10.0,
+            Self::Magnet => 8.0,
+            Self::SlowMotion => 5.0,
+            Self::DoubleScore => 10.0,
+            Self::SpeedBoost => 6.0,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Shield => palette::ELECTRIC_CYAN,
+            Self::Magnet => palette::CANDY_PINK,
+            Self::SlowMotion => palette::HERO_PURPLE,
+            Self::DoubleScore => palette::GOLD,
+            Self::SpeedBoost => palette::HERO_ORANGE,
+        }
+    }
+
+    /// Letter shown on the pickup and its HUD icon.
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Shield => "S",
+            Self::Magnet => "M",
+            Self::SlowMotion => "T",
+            Self::DoubleScore => "2x",
+            Self::SpeedBoost => "B",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// One of `offered`, uniformly.
+    pub fn random(offered: &[Powerup]) -> Powerup {
+        offered[rand::thread_rng().gen_range(0..offered.len())]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+/// A power-up waiting to be collected.  Despawns after `lifetime` seconds.
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: Powerup,
+    pub lifetime: f32,
+}
+
+/// Effects running on the player, as seconds remaining per power-up, and
+/// how many power-ups the player has picked up this run.
+#[derive(Component, Default, Debug)]
+pub struct ActiveEffects {
+    remaining: [f32; 5],
+    collected: i32,
+}
+
+impl ActiveEffects {
+    pub fn activate(&mut self, kind: Powerup) {
+        self.remaining[kind.index()] = kind.duration();
+        self.collected += 1;
+    }
+
+    pub fn collected(&self) -> i32 {
+        self.collected
+    }
+
+    pub fn is_active(&self, kind: Powerup) -> bool {
+        self.remaining[kind.index()] > 0.0
+    }
+
+    /// Uses up the shield, if there is one.  Returns whether the hit was
+    /// absorbed.
+    pub fn absorb_hit(&mut self) -> bool {
+        let absorbed = self.is_active(Powerup::Shield);
+        self.remaining[Powerup::Shield.index()] = 0.0;
+        absorbed
+    }
+
+    /// Multiplier for how fast the world (obstacles, enemies) moves.
+    pub fn world_scale(&self) -> f32 {
+        if self.is_active(Powerup::SlowMotion) { 0.5 } else { 1.0 }
+    }
+
+    pub fn score_multiplier(&self) -> i32 {
+        if self.is_active(Powerup::DoubleScore) { 2 } else { 1 }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.is_active(Powerup::SpeedBoost) { 1.5 } else { 1.0 }
+    }
+}
+
+/// Root of the HUD icon row.
+#[derive(Component)]
+pub struct PowerupHud {
+    icons: [Entity; 5],
+}
+
+#[derive(Component)]
+pub struct PowerupIcon;
+
+// ---------------------------------------------------------------------------
+// Spawning
+// ---------------------------------------------------------------------------
+
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    assets: &PixarAssets,
+    kind: Powerup,
+    position: Vec3,
+    lifetime: f32,
+    bundle: impl Bundle,
+) -> Entity {
+    commands
+        .spawn((
+            pixar::round_sprite(assets, kind.color(), Vec2::splat(PICKUP_SIZE)),
+            Transform::from_translation(position),
+            Pickup { kind, lifetime },
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new(kind.icon()),
+                TextFont { font_size: 14.0, ..default() },
+                TextColor(Color::BLACK),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            ));
+        })
+        .id()
+}
+
+/// Spawns the icon row below the score, one hidden icon per power-up.
+pub fn spawn_hud(commands: &mut Commands, bundle: impl Bundle) {
+    let mut icons = [Entity::PLACEHOLDER; 5];
+    let root = commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(10.0),
+            column_gap: Val::Px(6.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for kind in Powerup::ALL {
+                icons[kind.index()] = parent
+                    .spawn((
+                        Text::new(kind.icon()),
+                        TextFont { font_size: 14.0, ..default() },
+                        TextColor(Color::BLACK),
+                        BackgroundColor(kind.color()),
+                        Node { display: Display::None, padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)), ..default() },
+                        PowerupIcon,
+                    ))
+                    .id();
+            }
+        })
+        .id();
+    commands.entity(root).insert((PowerupHud { icons }, bundle));
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Counts effects and pickup lifetimes down.
+fn tick(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut effects: Query<&mut ActiveEffects>,
+    mut pickups: Query<(Entity, &mut Pickup)>,
+) {
+    let dt = time.delta_secs();
+    for mut fx in &mut effects {
+        for r in &mut fx.remaining {
+            *r = (*r - dt).max(0.0);
+        }
+    }
+    for (e, mut pickup) in &mut pickups {
+        pickup.lifetime -= dt;
+        if pickup.lifetime <= 0.0 {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Activates `M`'s pickups the player touches.
+pub fn collect_pickups<M: Component>(
+    mut commands: Commands,
+    mut players: Query<(&Transform, &mut ActiveEffects), With<M>>,
+    pickups: Query<(Entity, &Transform, &Pickup), With<M>>,
+    mut analytics: EventWriter<AnalyticsEvent>,
+) {
+    let Ok((ptf, mut fx)) = players.get_single_mut() else { return };
+    let player = ptf.translation.truncate();
+    for (e, tf, pickup) in &pickups {
+        if tf.translation.truncate().distance(player) < COLLECT_RADIUS {
+            fx.activate(pickup.kind);
+            analytics.send(AnalyticsEvent::powerup_used(pickup.kind.name()));
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Pulls `M`'s pickups toward a player with the magnet.
+pub fn magnet_pickups<M: Component>(
+    time: Res<Time>,
+    players: Query<(&Transform, &ActiveEffects), (With<M>, Without<Pickup>)>,
+    mut pickups: Query<&mut Transform, (With<Pickup>, With<M>)>,
+) {
+    let Ok((ptf, fx)) = players.get_single() else { return };
+    if !fx.is_active(Powerup::Magnet) {
+        return;
+    }
+    let player = ptf.translation.truncate();
+    let step = MAGNET_SPEED * time.delta_secs();
+    for mut tf in &mut pickups {
+        let to_player = player - tf.translation.truncate();
+        if to_player.length() < MAGNET_RADIUS {
+            tf.translation += to_player.clamp_length_max(step).extend(0.0);
+        }
+    }
+}
+
+/// Shows an icon with the seconds left for each running effect.
+pub fn update_hud<M: Component>(
+    players: Query<&ActiveEffects, With<M>>,
+    huds: Query<&PowerupHud, With<M>>,
+    mut icons: Query<(&mut Text, &mut Node), With<PowerupIcon>>,
+) {
+    let Ok(fx) = players.get_single() else { return };
+    for hud in &huds {
+        for kind in Powerup::ALL {
+            let Ok((mut text, mut node)) = icons.get_mut(hud.icons[kind.index()]) else { continue };
+            let left = fx.remaining[kind.index()];
+            if left > 0.0 {
+                node.display = Display::Flex;
+                **text = format!("{} {}", kind.icon(), left.ceil() as i32);
+            } else {
+                node.display = Display::None;
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Asset preloading and the loading screen.
+//!
+//! The engine starts in [`AppState::Loading`] and shows a progress bar
+//! until everything the shell asked for is ready, then moves on to Menu.
+//! The shell lists its assets with `set_preload_manifest()` before calling
+//! `init_engine`:
+//!
+//! ```json
+//! {"fonts":["fonts/nunito.ttf"],"audio":["/audio/jump.mp3"],"models":["models/robot.glb"]}
+//! ```
+//!
+//! * **Fonts** and **models** go through Bevy's asset server and are kept
+//!   in [`Preloaded`], so games take their handles from there instead of
+//!   loading them again.
+//! * **Audio** is played by the shell, not the engine, so it's only
+//!   fetched, to have it in the browser's cache when it's first needed.
+//! * **Uploads** sent before `init_engine` (see
+//!   [`asset_loader`](crate::asset_loader)) count too.  Uploaded models
+//!   are loaded into [`Preloaded`] under their upload name, whenever they
+//!   arrive.
+//!
+//! An asset that fails to load counts as done, so a missing file can't
+//! hold the engine on the loading screen.  `loading_progress()` returns
+//! the bar's fraction, `0.0..=1.0`, for the shell to mirror.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::asset_loader::{self, CustomAssets};
+use crate::AppState;
+
+const BAR_WIDTH: f32 = 320.0;
+const BAR_HEIGHT: f32 = 14.0;
+const BAR_COLOR: Color = Color::srgb(0.35, 0.55, 0.85);
+const TRACK_COLOR: Color = Color::srgb(0.18, 0.2, 0.3);
+
+static MANIFEST: Mutex<Option<Manifest>> = Mutex::new(None);
+/// Audio fetches that have finished, successfully or not.
+static AUDIO_DONE: AtomicU32 = AtomicU32::new(0);
+/// Bits of the `f32` progress last shown.
+static PROGRESS: AtomicU32 = AtomicU32::new(0);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct PreloadPlugin;
+
+impl Plugin for PreloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Preloaded>()
+            .init_resource::<AudioQueued>()
+            .add_systems(OnEnter(AppState::Loading), (start_loading, spawn_screen))
+            .add_systems(
+                Update,
+                (
+                    load_uploaded_models,
+                    track_progress.run_if(in_state(AppState::Loading)),
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(AppState::Loading), despawn_screen);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// What the shell wants loaded before the menu.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    fonts: Vec<String>,
+    audio: Vec<String>,
+    models: Vec<String>,
+}
+
+/// Handles of the preloaded assets, keyed by the path they were listed
+/// under (or, for uploaded models, their upload name).
+#[derive(Resource, Debug, Default)]
+pub struct Preloaded {
+    pub fonts: HashMap<String, Handle<Font>>,
+    pub models: HashMap<String, Handle<Gltf>>,
+}
+
+/// How many audio files were sent off to be fetched.
+#[derive(Resource, Default)]
+struct AudioQueued(u32);
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingBar;
+
+#[derive(Component)]
+struct LoadingLabel;
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Lists the assets to load before the menu (see the module docs).  Must
+/// be called before `init_engine`; returns `false` if `json` isn't a
+/// valid manifest.
+#[wasm_bindgen]
+pub fn set_preload_manifest(json: &str) -> bool {
+    let Ok(manifest) = serde_json::from_str::<Manifest>(json) else {
+        return false;
+    };
+    if let Ok(mut m) = MANIFEST.lock() {
+        *m = Some(manifest);
+    }
+    true
+}
+
+/// How far the loading screen has got, from `0.0` to `1.0`.
+#[wasm_bindgen]
+pub fn loading_progress() -> f32 {
+    f32::from_bits(PROGRESS.load(Ordering::Relaxed))
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn start_loading(
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<Preloaded>,
+    mut audio: ResMut<AudioQueued>,
+) {
+    PROGRESS.store(0.0f32.to_bits(), Ordering::Relaxed);
+    let manifest = MANIFEST.lock().ok().and_then(|mut m| m.take()).unwrap_or_default();
+
+    for path in manifest.fonts {
+        let handle = asset_server.load(path.clone());
+        preloaded.fonts.insert(path, handle);
+    }
+    for path in manifest.models {
+        let handle = asset_server.load(path.clone());
+        preloaded.models.insert(path, handle);
+    }
+    for url in manifest.audio {
+        audio.0 += 1;
+        wasm_bindgen_futures::spawn_local(async move {
+            fetch_audio(&url).await;
+            AUDIO_DONE.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Reads `url` to the end, so the whole file lands in the browser cache.
+async fn fetch_audio(url: &str) -> Option<()> {
+    let window = web_sys::window()?;
+    let resp = JsFuture::from(window.fetch_with_str(url)).await.ok()?;
+    let resp: web_sys::Response = resp.dyn_into().ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    JsFuture::from(resp.array_buffer().ok()?).await.ok()?;
+    Some(())
+}
+
+fn load_uploaded_models(
+    custom: Res<CustomAssets>,
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<Preloaded>,
+) {
+    if !custom.is_changed() {
+        return;
+    }
+    for (name, url) in &custom.gltf_urls {
+        if !preloaded.models.contains_key(name) {
+            preloaded.models.insert(name.clone(), asset_server.load(url.clone()));
+        }
+    }
+}
+
+fn track_progress(
+    asset_server: Res<AssetServer>,
+    preloaded: Res<Preloaded>,
+    audio: Res<AudioQueued>,
+    mut bar: Query<&mut Node, With<LoadingBar>>,
+    mut label: Query<&mut Text, With<LoadingLabel>>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let assets = preloaded
+        .fonts
+        .values()
+        .map(|h| h.id().untyped())
+        .chain(preloaded.models.values().map(|h| h.id().untyped()));
+    let mut total = audio.0 as usize + asset_loader::pending_uploads();
+    let mut done = AUDIO_DONE.load(Ordering::Relaxed) as usize;
+    for id in assets {
+        total += 1;
+        let state = asset_server.get_recursive_dependency_load_state(id);
+        if matches!(
+            state,
+            Some(RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed(_))
+        ) {
+            done += 1;
+        }
+    }
+
+    let progress = if total == 0 { 1.0 } else { (done as f32 / total as f32).min(1.0) };
+    PROGRESS.store(progress.to_bits(), Ordering::Relaxed);
+    for mut node in &mut bar {
+        node.width = Val::Percent(progress * 100.0);
+    }
+    for mut text in &mut label {
+        text.0 = format!("Loading... {}%", (progress * 100.0).round() as i32);
+    }
+
+    if done >= total {
+        next.set(AppState::Menu);
+    }
+}
+
+fn despawn_screen(mut commands: Commands, q: Query<Entity, With<LoadingScreen>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+fn spawn_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.06, 0.12)),
+            GlobalZIndex(100),
+            LoadingScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new("Loading... 0%"),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                LoadingLabel,
+            ));
+            root.spawn((
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(TRACK_COLOR),
+            ))
+            .with_children(|track| {
+                track.spawn((
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(BAR_COLOR),
+                    LoadingBar,
+                ));
+            });
+        });
+}
@@ -0,0 +1,251 @@
+//! Personal bests.
+//!
+//! The player's best score in every game is kept in `localStorage`.  When
+//! a run beats it, a "NEW RECORD!" banner pops up with a burst of confetti
+//! — once per run, and only if there was a best to beat, so a game's first
+//! run isn't celebrated for any score at all.  Versus and spectated runs
+//! don't count.
+//!
+//! The best from before the run is included in `stop_game()`'s JSON as
+//! `previous_best` (`null` on a game's first run), so the shell can show
+//! by how much it was beaten.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::games::GameplaySet;
+use crate::perf::Quality;
+use crate::spectator::is_live;
+use crate::versus::VersusState;
+use crate::{AppState, BevyBridge};
+
+const STORAGE_KEY: &str = "stem_records";
+/// JS global holding the current run's previous best, read by `stop_game`.
+const PREVIOUS_BEST_GLOBAL: &str = "__bevy_previous_best";
+
+const BANNER_SECS: f32 = 2.5;
+/// How long the banner takes to pop in, and to fade out.
+const BANNER_POP_SECS: f32 = 0.3;
+const BANNER_COLOR: Color = Color::srgb(1.0, 0.84, 0.2);
+
+const CONFETTI_COUNT: usize = 80;
+const CONFETTI_SECS: f32 = 3.0;
+const CONFETTI_GRAVITY: f32 = 300.0;
+/// Paper flutters rather than drops.
+const CONFETTI_MAX_FALL: f32 = 160.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Records>()
+            .init_resource::<RunRecord>()
+            .add_systems(Startup, load_records)
+            .add_systems(OnEnter(AppState::Playing), begin_run)
+            .add_systems(
+                Update,
+                (
+                    check_record.in_set(GameplaySet),
+                    animate_banner,
+                    animate_confetti,
+                ),
+            )
+            .add_systems(OnEnter(AppState::GameOver), save_best.run_if(is_live))
+            .add_systems(OnExit(AppState::GameOver), despawn_celebration);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+/// Best score per game id.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Records {
+    pub best: HashMap<String, i32>,
+}
+
+/// The running game's shot at a record.
+#[derive(Resource, Debug, Default)]
+pub struct RunRecord {
+    /// The best before this run; `None` on the game's first run.
+    pub previous_best: Option<i32>,
+    /// This run has beaten it and the banner has been shown.
+    pub beaten: bool,
+}
+
+#[derive(Component)]
+struct RecordBanner {
+    age: f32,
+}
+
+#[derive(Component)]
+struct Confetti {
+    age: f32,
+    velocity: Vec2,
+    spin: f32,
+    /// Offsets each piece's sway.
+    phase: f32,
+}
+
+// ---------------------------------------------------------------------------
+// API
+// ---------------------------------------------------------------------------
+
+/// The running game's previous best, as published for `stop_game`.
+pub(crate) fn previous_best() -> Option<i32> {
+    crate::get_js_global(PREVIOUS_BEST_GLOBAL).and_then(|s| s.parse().ok())
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn load_records(mut records: ResMut<Records>) {
+    let saved = crate::persistence::local_storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<Records>(&json).ok());
+    if let Some(saved) = saved {
+        *records = saved;
+    }
+}
+
+fn begin_run(bridge: Res<BevyBridge>, records: Res<Records>, mut run: ResMut<RunRecord>) {
+    run.previous_best = records.best.get(&bridge.game_id).copied();
+    run.beaten = false;
+    let published = run.previous_best.map(|b| b.to_string()).unwrap_or_default();
+    crate::set_js_global(PREVIOUS_BEST_GLOBAL, &published);
+}
+
+fn check_record(
+    mut commands: Commands,
+    bridge: Res<BevyBridge>,
+    versus: Res<VersusState>,
+    mut run: ResMut<RunRecord>,
+    quality: Res<Quality>,
+    cameras: Query<&Transform, With<Camera2d>>,
+) {
+    if run.beaten || versus.active {
+        return;
+    }
+    let Some(best) = run.previous_best else { return };
+    if bridge.current_score <= best {
+        return;
+    }
+    run.beaten = true;
+
+    commands.spawn((
+        Text::new("NEW RECORD!"),
+        TextFont {
+            font_size: 56.0,
+            ..default()
+        },
+        TextColor(BANNER_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(18.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        RecordBanner { age: 0.0 },
+    ));
+
+    // Rain down across the view, wherever the camera is
+    let centre = cameras.get_single().map_or(Vec2::ZERO, |tf| tf.translation.truncate());
+    let mut rng = rand::thread_rng();
+    let count = (CONFETTI_COUNT as f32 * quality.particle_scale()) as usize;
+    for _ in 0..count {
+        let pos = centre + Vec2::new(rng.gen_range(-480.0..480.0), rng.gen_range(320.0..420.0));
+        commands.spawn((
+            Sprite {
+                color: Color::hsl(rng.gen_range(0.0..360.0), 0.85, 0.6),
+                custom_size: Some(Vec2::new(10.0, 5.0)),
+                ..default()
+            },
+            Transform::from_translation(pos.extend(50.0))
+                .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..std::f32::consts::TAU))),
+            Confetti {
+                age: 0.0,
+                velocity: Vec2::new(rng.gen_range(-60.0..60.0), rng.gen_range(-80.0..40.0)),
+                spin: rng.gen_range(-8.0..8.0),
+                phase: rng.gen_range(0.0..std::f32::consts::TAU),
+            },
+        ));
+    }
+}
+
+fn animate_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut RecordBanner, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut banner, mut tf, mut color) in &mut q {
+        banner.age += time.delta_secs();
+        if banner.age >= BANNER_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Overshoot on the way in, then fade out at the end
+        let pop = (1.0 - banner.age / BANNER_POP_SECS).max(0.0);
+        tf.scale = Vec3::splat(1.0 + 0.4 * pop * pop);
+        let fade = ((BANNER_SECS - banner.age) / BANNER_POP_SECS).min(1.0);
+        color.0 = BANNER_COLOR.with_alpha(fade);
+    }
+}
+
+fn animate_confetti(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut Confetti, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut c, mut tf, mut sprite) in &mut q {
+        c.age += dt;
+        if c.age >= CONFETTI_SECS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        c.velocity.y = (c.velocity.y - CONFETTI_GRAVITY * dt).max(-CONFETTI_MAX_FALL);
+        let sway = 40.0 * (c.age * 5.0 + c.phase).sin();
+        tf.translation += Vec3::new(c.velocity.x + sway, c.velocity.y, 0.0) * dt;
+        tf.rotate_z(c.spin * dt);
+        sprite.color.set_alpha((CONFETTI_SECS - c.age).min(1.0));
+    }
+}
+
+fn save_best(bridge: Res<BevyBridge>, versus: Res<VersusState>, mut records: ResMut<Records>) {
+    if versus.active || bridge.game_id.is_empty() {
+        return;
+    }
+    let score = bridge.current_score;
+    if records.best.get(&bridge.game_id).is_some_and(|&best| best >= score) {
+        return;
+    }
+    records.best.insert(bridge.game_id.clone(), score);
+
+    if let (Some(storage), Ok(json)) = (
+        crate::persistence::local_storage(),
+        serde_json::to_string(&*records),
+    ) {
+        storage.set_item(STORAGE_KEY, &json).ok();
+    }
+}
+
+fn despawn_celebration(
+    mut commands: Commands,
+    q: Query<Entity, Or<(With<RecordBanner>, With<Confetti>)>>,
+) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
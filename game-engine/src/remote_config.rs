@@ -0,0 +1,112 @@
+//! Server-driven game tuning.
+//!
+//! When a game starts, its tuning parameters (spawn intervals, gravity,
+//! scoring weights, …) are fetched from `GET /api/v1/config/games/:gameId`
+//! into the [`RemoteConfig`] resource.  Games read them with a fallback to
+//! their built-in constant, e.g. `config.f32("gravity", GRAVITY)`, so a
+//! missing key or an offline start simply plays with the defaults.
+//!
+//! The last config received for each game is kept in `localStorage` and
+//! loaded synchronously at game start, before any game's `setup` that
+//! orders itself `.after(load_config)`.  The fresh copy from the server
+//! replaces it a moment later; values games read every frame pick up the
+//! change immediately, values read only in `setup` from the next run on.
+
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::{AppState, BevyBridge};
+
+const STORAGE_PREFIX: &str = "stem_config_";
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct RemoteConfigPlugin;
+
+impl Plugin for RemoteConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RemoteConfig>()
+            .add_systems(OnEnter(AppState::Playing), load_config)
+            .add_systems(Update, apply_fetched_config);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resource
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Debug, Default)]
+pub struct RemoteConfig {
+    pub game_id: String,
+    /// 0 when no config is stored for the game.
+    pub version: i64,
+    pub params: Map<String, Value>,
+}
+
+impl RemoteConfig {
+    pub fn f32(&self, key: &str, default: f32) -> f32 {
+        self.params
+            .get(key)
+            .and_then(|v| v.as_f64())
+            .map_or(default, |v| v as f32)
+    }
+
+    pub fn i32(&self, key: &str, default: i32) -> i32 {
+        self.params
+            .get(key)
+            .and_then(|v| v.as_i64())
+            .map_or(default, |v| v as i32)
+    }
+
+    fn set(&mut self, game_id: &str, body: &Value) {
+        self.game_id = game_id.to_string();
+        self.version = body["version"].as_i64().unwrap_or(0);
+        self.params = body["params"].as_object().cloned().unwrap_or_default();
+    }
+}
+
+/// `(game_id, response body)` from the last fetch, waiting to be applied.
+static FETCHED_CONFIG: Mutex<Option<(String, Value)>> = Mutex::new(None);
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Applies the cached config for the starting game and requests a fresh one.
+pub fn load_config(bridge: Res<BevyBridge>, mut config: ResMut<RemoteConfig>) {
+    let game_id = bridge.game_id.clone();
+    let cached = crate::persistence::local_storage()
+        .and_then(|s| s.get_item(&format!("{}{}", STORAGE_PREFIX, game_id)).ok().flatten())
+        .and_then(|json| serde_json::from_str::<Value>(&json).ok())
+        .unwrap_or_default();
+    config.set(&game_id, &cached);
+
+    if !crate::api::is_configured() {
+        return;
+    }
+    let path = format!("/config/games/{}", game_id);
+    crate::api::get_json(&path, move |body| {
+        if let (Some(body), Ok(mut fetched)) = (body, FETCHED_CONFIG.lock()) {
+            *fetched = Some((game_id, body));
+        }
+    });
+}
+
+fn apply_fetched_config(bridge: Res<BevyBridge>, mut config: ResMut<RemoteConfig>) {
+    let Some((game_id, body)) = FETCHED_CONFIG.lock().ok().and_then(|mut f| f.take()) else {
+        return;
+    };
+    if let Some(storage) = crate::persistence::local_storage() {
+        storage
+            .set_item(&format!("{}{}", STORAGE_PREFIX, game_id), &body.to_string())
+            .ok();
+    }
+    // A response for a game that has since been left is only cached.
+    if game_id == bridge.game_id {
+        config.set(&game_id, &body);
+    }
+}
@@ -0,0 +1,209 @@
+//! 3-D rendering mode.
+//!
+//! Games are 2-D unless they say otherwise, drawn by the camera spawned at
+//! startup.  A 3-D game spawns its own camera and lights with
+//! [`spawn_camera`] and [`spawn_lighting`], tagged with its `GameEntity` so
+//! its cleanup removes them with the rest of the scene:
+//!
+//! * The 3-D camera renders first.  While one exists the 2-D camera stops
+//!   clearing the screen and draws on top of it, so HUD text, the pause
+//!   menu and the game over screen work as they do in 2-D games.
+//! * [`ChaseCamera`] on the camera makes it trail the entity marked
+//!   [`ChaseTarget`], looking the way the target faces.
+//! * [`heightmap_mesh`] builds terrain from a height function, and
+//!   [`surface_normal`] tilts things to sit on it.
+//! * Models are taken from [`Preloaded`] with [`model_scene`]: listed in
+//!   the preload manifest under their path, or uploaded under their name.
+//!   Games bring a stand-in built from primitives for when it's missing.
+//!
+//! A 3-D game's systems only run while it's the game being played (see
+//! [`games::playing`](crate::games::playing)), since its camera would
+//! cover every 2-D scene.  Forward is `-Z` and up is `+Y`; a unit is a
+//! metre.
+
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+
+use crate::games::GameplaySet;
+use crate::preload::Preloaded;
+use crate::AppState;
+
+/// Where the sun shines from.
+const SUN_DIRECTION: Vec3 = Vec3::new(-0.4, 1.0, 0.6);
+const SUN_LUX: f32 = 10_000.0;
+const AMBIENT_BRIGHTNESS: f32 = 300.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct Scene3dPlugin;
+
+impl Plugin for Scene3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (overlay_2d, chase.after(GameplaySet)))
+            .add_systems(OnExit(AppState::Playing), reset_ambient);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Components
+// ---------------------------------------------------------------------------
+
+/// Put on a 3-D camera to have it trail the [`ChaseTarget`].
+#[derive(Component, Debug, Clone)]
+pub struct ChaseCamera {
+    /// How far behind the target the camera stays.
+    pub distance: f32,
+    /// How far above it.
+    pub height: f32,
+    /// How quickly the camera catches up, per second; higher is snappier.
+    pub smoothing: f32,
+}
+
+impl ChaseCamera {
+    pub fn new(distance: f32, height: f32) -> Self {
+        Self { distance, height, smoothing: 4.0 }
+    }
+}
+
+/// The entity the [`ChaseCamera`] follows.  Only one at a time.
+#[derive(Component, Debug, Default)]
+pub struct ChaseTarget;
+
+// ---------------------------------------------------------------------------
+// Scene setup
+// ---------------------------------------------------------------------------
+
+/// Spawns a 3-D camera that clears to `sky`, with `bundle`'s components.
+pub fn spawn_camera(commands: &mut Commands, sky: Color, transform: Transform, bundle: impl Bundle) -> Entity {
+    commands
+        .spawn((
+            Camera3d::default(),
+            Camera {
+                // Before the 2-D camera, which draws the HUD over it.
+                order: -1,
+                clear_color: ClearColorConfig::Custom(sky),
+                ..default()
+            },
+            transform,
+            bundle,
+        ))
+        .id()
+}
+
+/// Lights the scene with a shadow-casting sun and soft ambient light.
+/// The ambient light goes back to Bevy's default when the game ends.
+pub fn spawn_lighting(commands: &mut Commands, bundle: impl Bundle) {
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: AMBIENT_BRIGHTNESS,
+    });
+    commands.spawn((
+        DirectionalLight {
+            illuminance: SUN_LUX,
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_translation(SUN_DIRECTION).looking_at(Vec3::ZERO, Vec3::Y),
+        bundle,
+    ));
+}
+
+/// The scene of a preloaded or uploaded glTF model, once it has loaded.
+pub fn model_scene(preloaded: &Preloaded, gltfs: &Assets<Gltf>, name: &str) -> Option<Handle<Scene>> {
+    let gltf = gltfs.get(preloaded.models.get(name)?)?;
+    gltf.default_scene.clone().or_else(|| gltf.scenes.first().cloned())
+}
+
+// ---------------------------------------------------------------------------
+// Terrain
+// ---------------------------------------------------------------------------
+
+/// A square of terrain `size` metres across, centred on the origin and
+/// split into `cells` × `cells` quads, its heights taken from `height(x, z)`.
+pub fn heightmap_mesh(size: f32, cells: u32, height: impl Fn(f32, f32) -> f32) -> Mesh {
+    let step = size / cells as f32;
+    let half = size / 2.0;
+    let row = cells + 1;
+
+    let mut positions = Vec::with_capacity((row * row) as usize);
+    let mut normals = Vec::with_capacity((row * row) as usize);
+    let mut uvs = Vec::with_capacity((row * row) as usize);
+    for j in 0..row {
+        for i in 0..row {
+            let (x, z) = (i as f32 * step - half, j as f32 * step - half);
+            positions.push([x, height(x, z), z]);
+            normals.push(surface_normal(&height, x, z, step).to_array());
+            uvs.push([i as f32 / cells as f32, j as f32 / cells as f32]);
+        }
+    }
+
+    let mut indices = Vec::with_capacity((cells * cells * 6) as usize);
+    for j in 0..cells {
+        for i in 0..cells {
+            let a = j * row + i;
+            let (b, c, d) = (a + 1, a + row, a + row + 1);
+            // Counter-clockwise seen from above.
+            indices.extend_from_slice(&[a, c, b, b, c, d]);
+        }
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// The terrain's up direction at `(x, z)`, from the heights `step` metres
+/// either side.
+pub fn surface_normal(height: impl Fn(f32, f32) -> f32, x: f32, z: f32, step: f32) -> Vec3 {
+    Vec3::new(
+        height(x - step, z) - height(x + step, z),
+        2.0 * step,
+        height(x, z - step) - height(x, z + step),
+    )
+    .normalize()
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Has the 2-D camera draw over the 3-D one instead of clearing it away.
+/// Cameras rendering to a texture, like skin portraits, don't count.
+fn overlay_2d(
+    cameras_3d: Query<&Camera, With<Camera3d>>,
+    mut cameras_2d: Query<&mut Camera, (With<Camera2d>, Without<Camera3d>)>,
+) {
+    let overlay = cameras_3d.iter().any(|c| matches!(c.target, RenderTarget::Window(_)));
+    for mut camera in &mut cameras_2d {
+        if matches!(camera.clear_color, ClearColorConfig::None) != overlay {
+            camera.clear_color = if overlay { ClearColorConfig::None } else { ClearColorConfig::Default };
+        }
+    }
+}
+
+fn chase(
+    time: Res<Time>,
+    targets: Query<&Transform, (With<ChaseTarget>, Without<ChaseCamera>)>,
+    mut cameras: Query<(&mut Transform, &ChaseCamera)>,
+) {
+    let Ok(target) = targets.get_single() else { return };
+    // Level with the ground, so the camera doesn't tip over with the target.
+    let forward = (target.rotation * Vec3::NEG_Z).with_y(0.0).normalize_or(Vec3::NEG_Z);
+    for (mut tf, chase) in &mut cameras {
+        let goal = target.translation - forward * chase.distance + Vec3::Y * chase.height;
+        let t = 1.0 - (-chase.smoothing * time.delta_secs()).exp();
+        tf.translation = tf.translation.lerp(goal, t);
+        tf.look_at(target.translation + Vec3::Y, Vec3::Y);
+    }
+}
+
+fn reset_ambient(mut commands: Commands) {
+    commands.insert_resource(AmbientLight::default());
+}
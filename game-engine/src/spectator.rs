@@ -0,0 +1,432 @@
+//! Spectator mode over the presence socket.
+//!
+//! **Streaming** — a host starting a multiplayer run with
+//! `{"room": "<roomId>"}` in the `start_game` options opens its own socket
+//! to `/presence/ws` and sends the run to the room every
+//! [`STREAM_INTERVAL_SECS`]: the current score and, for ghost-capable
+//! games, the new [`GhostFrame`] samples since the last send.
+//!
+//! **Watching** — `spectate(room_id)` opens a socket and asks to watch the
+//! room.  Once the server confirms, the room's game is started with
+//! `{"spectate": "<roomId>"}`; such a run is read-only — [`GameplaySet`]
+//! doesn't run (see [`is_live`]) and the player entity instead follows the
+//! host's relayed samples, a short delay behind to smooth over jitter.
+//! `stop_spectating()` leaves the room and ends the run.
+
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::games::GameplaySet;
+use crate::ghost::{self, GhostFrame, GhostRecorder, GhostTracked};
+use crate::{AppState, BevyBridge, GameOptions};
+
+/// Seconds between frames sent by a streaming host.
+pub const STREAM_INTERVAL_SECS: f32 = 0.2;
+/// How far behind the newest relayed sample playback aims to stay.
+const PLAYBACK_DELAY_MS: f32 = 300.0;
+/// Playback skips ahead rather than fall further behind than this.
+const MAX_LAG_MS: f32 = 1500.0;
+/// Samples older than this behind the playback clock are dropped.
+const KEEP_MS: f32 = 2000.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), (begin_run, spawn_hud).chain())
+            .add_systems(Update, process_inbox)
+            .add_systems(Update, follow_host.run_if(in_state(AppState::Playing)))
+            .add_systems(Update, stream_frames.in_set(GameplaySet))
+            .add_systems(OnExit(AppState::Playing), (end_stream, despawn_hud));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+/// The room being watched.  Exists from the server's confirmation until
+/// `stop_spectating()` or the start of a live run.
+#[derive(Resource)]
+struct Spectating {
+    room_id: String,
+    host_name: String,
+    frames: Vec<GhostFrame>,
+    clock_ms: f32,
+    score: i32,
+    ended: bool,
+}
+
+/// A host's outgoing stream for the current run.
+#[derive(Resource)]
+struct RoomStream {
+    room_id: String,
+    /// Recorder samples already sent.
+    sent: usize,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct SpectatorHud;
+
+/// Whether the current run is played rather than spectated.  Run condition
+/// for [`GameplaySet`] and anything else that shouldn't happen while
+/// watching someone else.
+pub fn is_live(options: Res<GameOptions>) -> bool {
+    options.get("spectate").is_none()
+}
+
+// ---------------------------------------------------------------------------
+// Socket
+// ---------------------------------------------------------------------------
+
+/// The open socket and the JS callbacks attached to it, which must live as
+/// long as it does.
+struct Socket {
+    ws: web_sys::WebSocket,
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+}
+
+thread_local! {
+    static SOCKET: RefCell<Option<Socket>> = const { RefCell::new(None) };
+}
+
+/// Server messages waiting to be handled by [`process_inbox`].
+static INBOX: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// Opens a fresh socket, replacing any open one.  `hello` is sent as soon
+/// as it connects.
+fn open_socket(hello: Option<Value>) {
+    close_socket();
+    let Some(url) = crate::api::socket_url("/presence/ws") else {
+        return;
+    };
+    let Ok(ws) = web_sys::WebSocket::new(&url) else {
+        return;
+    };
+
+    let on_open = {
+        let ws = ws.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Some(hello) = &hello {
+                ws.send_with_str(&hello.to_string()).ok();
+            }
+        })
+    };
+    let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+        |event: web_sys::MessageEvent| {
+            let Some(text) = event.data().as_string() else {
+                return;
+            };
+            if let (Ok(msg), Ok(mut inbox)) = (serde_json::from_str(&text), INBOX.lock()) {
+                inbox.push(msg);
+            }
+        },
+    );
+    ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+    SOCKET.with(|s| {
+        *s.borrow_mut() = Some(Socket {
+            ws,
+            _on_open: on_open,
+            _on_message: on_message,
+        });
+    });
+}
+
+/// Sends a message if the socket is open.  Returns whether it was sent.
+fn send_message(msg: &Value) -> bool {
+    SOCKET.with(|s| match s.borrow().as_ref() {
+        Some(s) if s.ws.ready_state() == web_sys::WebSocket::OPEN => {
+            s.ws.send_with_str(&msg.to_string()).is_ok()
+        }
+        _ => false,
+    })
+}
+
+fn close_socket() {
+    SOCKET.with(|s| {
+        if let Some(s) = s.borrow_mut().take() {
+            s.ws.set_onopen(None);
+            s.ws.set_onmessage(None);
+            s.ws.close().ok();
+        }
+    });
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Watch a multiplayer room.  The room's game starts read-only once the
+/// server accepts; requires `set_api_config` to have been called.
+#[wasm_bindgen]
+pub fn spectate(room_id: &str) {
+    open_socket(Some(json!({ "type": "spectate", "roomId": room_id })));
+}
+
+/// Stop watching and end the spectated run.
+#[wasm_bindgen]
+pub fn stop_spectating() {
+    crate::set_js_global("__bevy_stop_spectating", "true");
+}
+
+/// Spectators watching the room this client hosts or watches, as last
+/// reported by the server.
+#[wasm_bindgen]
+pub fn get_spectator_count() -> i32 {
+    crate::get_js_global("__bevy_spectator_count")
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// A live run ends any spectating and, with a `"room"` option, starts
+/// streaming to the room.
+fn begin_run(
+    mut commands: Commands,
+    options: Res<GameOptions>,
+    spectating: Option<Res<Spectating>>,
+) {
+    if options.get("spectate").is_some() {
+        return;
+    }
+    if spectating.is_some() {
+        send_message(&json!({ "type": "stop_spectating" }));
+        close_socket();
+        commands.remove_resource::<Spectating>();
+    }
+    if let Some(room_id) = options.get("room").and_then(|r| r.as_str()) {
+        open_socket(None);
+        commands.insert_resource(RoomStream {
+            room_id: room_id.to_string(),
+            sent: 0,
+            timer: Timer::from_seconds(STREAM_INTERVAL_SECS, TimerMode::Repeating),
+        });
+    }
+}
+
+fn process_inbox(
+    mut commands: Commands,
+    mut spectating: Option<ResMut<Spectating>>,
+    options: Res<GameOptions>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if crate::get_js_global("__bevy_stop_spectating").as_deref() == Some("true") {
+        crate::delete_js_global("__bevy_stop_spectating");
+        send_message(&json!({ "type": "stop_spectating" }));
+        close_socket();
+        commands.remove_resource::<Spectating>();
+        if *state.get() == AppState::Playing && options.get("spectate").is_some() {
+            next_state.set(AppState::GameOver);
+        }
+        return;
+    }
+
+    let messages = match INBOX.lock() {
+        Ok(mut inbox) => std::mem::take(&mut *inbox),
+        Err(_) => return,
+    };
+    for msg in messages {
+        match msg["type"].as_str() {
+            Some("spectating") => start_watching(&mut commands, &msg["room"]),
+            Some("spectate_frame") => {
+                if let Some(spec) = spectating.as_deref_mut() {
+                    if msg["roomId"].as_str() == Some(spec.room_id.as_str()) {
+                        receive_frame(spec, &msg["frame"]);
+                    }
+                }
+            }
+            Some("spectators") => {
+                let count = msg["count"].as_i64().unwrap_or(0);
+                crate::set_js_global("__bevy_spectator_count", &count.to_string());
+            }
+            Some("error") => {
+                let message = msg["message"].as_str().unwrap_or("Spectator error");
+                web_sys::console::warn_1(&JsValue::from_str(message));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Server accepted a `spectate` request: start the room's game read-only.
+fn start_watching(commands: &mut Commands, room: &Value) {
+    let (Some(room_id), Some(game_id)) = (room["id"].as_str(), room["game_id"].as_str()) else {
+        return;
+    };
+    let host_name = room["players"]
+        .as_array()
+        .and_then(|players| players.iter().find(|p| p["id"] == room["host_id"]))
+        .and_then(|p| p["display_name"].as_str())
+        .unwrap_or("Host")
+        .to_string();
+    let count = room["spectator_count"].as_i64().unwrap_or(0);
+    crate::set_js_global("__bevy_spectator_count", &count.to_string());
+
+    commands.insert_resource(Spectating {
+        room_id: room_id.to_string(),
+        host_name,
+        frames: Vec::new(),
+        clock_ms: 0.0,
+        score: 0,
+        ended: false,
+    });
+    crate::start_game(game_id, Some(json!({ "spectate": room_id }).to_string()));
+}
+
+fn receive_frame(spec: &mut Spectating, frame: &Value) {
+    let frames: Vec<GhostFrame> = serde_json::from_value(frame["frames"].clone()).unwrap_or_default();
+    // Sample times restarting means the host started a new run.
+    let restarted = match (frames.first(), spec.frames.last()) {
+        (Some(first), Some(last)) => first.0 < last.0,
+        _ => false,
+    };
+    if restarted {
+        spec.frames.clear();
+        spec.clock_ms = 0.0;
+    }
+    spec.frames.extend(frames);
+    spec.ended = frame["ended"].as_bool().unwrap_or(false);
+    if let Some(score) = frame["score"].as_i64() {
+        spec.score = score as i32;
+    }
+}
+
+/// Moves the spectated player entity along the relayed samples.
+fn follow_host(
+    time: Res<Time>,
+    mut bridge: ResMut<BevyBridge>,
+    spectating: Option<ResMut<Spectating>>,
+    mut tracked: Query<(&mut Transform, &GhostTracked)>,
+    mut hud: Query<&mut Text, With<SpectatorHud>>,
+) {
+    let Some(mut spec) = spectating else {
+        return;
+    };
+
+    if let Some(latest) = spec.frames.last().map(|f| f.0 as f32) {
+        let ahead = (latest - PLAYBACK_DELAY_MS).max(0.0);
+        spec.clock_ms = (spec.clock_ms + time.delta_secs() * 1000.0)
+            .max(latest - MAX_LAG_MS)
+            .min(ahead);
+
+        let clock = spec.clock_ms;
+        let stale = spec.frames.partition_point(|f| (f.0 as f32) < clock - KEEP_MS);
+        spec.frames.drain(..stale);
+
+        if let Some((x, y, _)) = ghost::sample(&spec.frames, clock as u32) {
+            for (mut tf, g) in &mut tracked {
+                if g.0 == bridge.game_id {
+                    tf.translation.x = x;
+                    tf.translation.y = y;
+                }
+            }
+        }
+    }
+
+    bridge.current_score = spec.score;
+    for mut text in &mut hud {
+        **text = if spec.ended {
+            format!("Watching {} — run over ({})", spec.host_name, spec.score)
+        } else {
+            format!("Watching {} — {}", spec.host_name, spec.score)
+        };
+    }
+}
+
+/// Sends the host's new samples and score to the room.
+fn stream_frames(
+    time: Res<Time>,
+    bridge: Res<BevyBridge>,
+    recorder: Res<GhostRecorder>,
+    stream: Option<ResMut<RoomStream>>,
+) {
+    let Some(mut stream) = stream else {
+        return;
+    };
+    if !stream.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    send_stream_frame(&mut stream, &bridge, &recorder, false);
+}
+
+fn send_stream_frame(stream: &mut RoomStream, bridge: &BevyBridge, recorder: &GhostRecorder, ended: bool) {
+    let frames = if ghost::is_ghost_game(&bridge.game_id) {
+        recorder.frames.get(stream.sent..).unwrap_or_default()
+    } else {
+        &[]
+    };
+    let msg = json!({
+        "type": "room_frame",
+        "roomId": stream.room_id,
+        "frame": {
+            "score": bridge.current_score,
+            "frames": frames,
+            "ended": ended,
+        },
+    });
+    // Unsent samples (socket still connecting) go out with the next frame.
+    if send_message(&msg) {
+        stream.sent += frames.len();
+    }
+}
+
+/// Tells spectators the run is over and closes the host's socket.
+fn end_stream(
+    mut commands: Commands,
+    bridge: Res<BevyBridge>,
+    recorder: Res<GhostRecorder>,
+    stream: Option<ResMut<RoomStream>>,
+) {
+    let Some(mut stream) = stream else {
+        return;
+    };
+    send_stream_frame(&mut stream, &bridge, &recorder, true);
+    close_socket();
+    commands.remove_resource::<RoomStream>();
+}
+
+fn spawn_hud(mut commands: Commands, options: Res<GameOptions>) {
+    if options.get("spectate").is_none() {
+        return;
+    }
+    commands.spawn((
+        Text::new("Watching…"),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgba(0.8, 0.9, 1.0, 0.9)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        SpectatorHud,
+    ));
+}
+
+fn despawn_hud(mut commands: Commands, q: Query<Entity, With<SpectatorHud>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,272 @@
+//! Offline score queue.
+//!
+//! A run that ends while the browser reports itself offline
+//! (`navigator.onLine == false`) can't be submitted by the shell, so its
+//! result is queued in IndexedDB (database `stem_sync`, store `pending`)
+//! as a `score_submit` operation for `POST /api/v1/sync/batch`.
+//!
+//! Once the browser is back online and the shell has called
+//! `set_api_config`, the queue is flushed oldest first, up to
+//! [`MAX_BATCH`] operations per request.  Operations the server reports as
+//! processed are removed; after a failed upload the next attempt waits
+//! [`BASE_BACKOFF_SECS`], doubling up to [`MAX_BACKOFF_SECS`].  The queue
+//! survives reloads, and `pending_sync_count()` reports its length.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+use crate::spectator::is_live;
+use crate::{AppState, BevyBridge};
+
+/// Most operations `/sync/batch` accepts in one request.
+pub const MAX_BATCH: usize = 50;
+pub const BASE_BACKOFF_SECS: f32 = 2.0;
+pub const MAX_BACKOFF_SECS: f32 = 300.0;
+
+const DB_NAME: &str = "stem_sync";
+const STORE: &str = "pending";
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct SyncPlugin;
+
+impl Plugin for SyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SyncBackoff>()
+            .add_systems(Startup, load_pending_count)
+            .add_systems(OnEnter(AppState::GameOver), queue_offline_result.run_if(is_live))
+            .add_systems(Update, drive_sync);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One queued operation, in the shape `/sync/batch` expects.  `id` starts
+/// with the hex timestamp so IndexedDB's key order is queue order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PendingOp {
+    id: String,
+    action: String,
+    #[serde(rename = "gameId")]
+    game_id: String,
+    score: i64,
+    timestamp: i64,
+}
+
+/// Seconds until the next flush attempt, and the wait after a failure.
+#[derive(Resource, Default)]
+struct SyncBackoff {
+    retry_in: f32,
+    backoff: f32,
+}
+
+/// Mirror of the IndexedDB queue length, so `pending_sync_count()` can
+/// answer synchronously.
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+/// One flush at a time.
+static FLUSH_IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+/// Outcome of the last flush, waiting for [`drive_sync`].
+static FLUSH_RESULT: Mutex<Option<bool>> = Mutex::new(None);
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Number of finished runs waiting to be uploaded.
+#[wasm_bindgen]
+pub fn pending_sync_count() -> u32 {
+    PENDING.load(Ordering::Acquire) as u32
+}
+
+// ---------------------------------------------------------------------------
+// IndexedDB
+// ---------------------------------------------------------------------------
+
+/// Resolves when `req` succeeds, with its result.
+async fn await_request(req: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        req.set_onsuccess(Some(&resolve));
+        req.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise).await?;
+    req.result()
+}
+
+async fn open_db() -> Result<IdbDatabase, JsValue> {
+    let factory = web_sys::window()
+        .ok_or("no window")?
+        .indexed_db()?
+        .ok_or("IndexedDB unavailable")?;
+    let req = factory.open_with_u32(DB_NAME, 1)?;
+
+    let on_upgrade = Closure::once(move |event: web_sys::Event| {
+        let Some(db) = event
+            .target()
+            .and_then(|t| t.unchecked_into::<IdbRequest>().result().ok())
+        else {
+            return;
+        };
+        let params = web_sys::IdbObjectStoreParameters::new();
+        params.set_key_path(&JsValue::from_str("id"));
+        db.unchecked_into::<IdbDatabase>()
+            .create_object_store_with_optional_parameters(STORE, &params)
+            .ok();
+    });
+    req.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    let db = await_request(&req).await?;
+    Ok(db.unchecked_into())
+}
+
+fn object_store(db: &IdbDatabase, mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+    db.transaction_with_str_and_mode(STORE, mode)?.object_store(STORE)
+}
+
+async fn put(op: &PendingOp) -> Result<(), JsValue> {
+    let db = open_db().await?;
+    let value = op
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)?;
+    await_request(&object_store(&db, IdbTransactionMode::Readwrite)?.put(&value)?).await?;
+    Ok(())
+}
+
+async fn count(db: &IdbDatabase) -> Result<usize, JsValue> {
+    let n = await_request(&object_store(db, IdbTransactionMode::Readonly)?.count()?).await?;
+    Ok(n.as_f64().unwrap_or(0.0) as usize)
+}
+
+async fn oldest(db: &IdbDatabase) -> Result<Vec<PendingOp>, JsValue> {
+    let all = await_request(&object_store(db, IdbTransactionMode::Readonly)?.get_all()?).await?;
+    let all: js_sys::Array = all.unchecked_into();
+    Ok(all
+        .iter()
+        .filter_map(|v| serde_wasm_bindgen::from_value(v).ok())
+        .take(MAX_BATCH)
+        .collect())
+}
+
+async fn remove(db: &IdbDatabase, ids: &[String]) -> Result<(), JsValue> {
+    let store = object_store(db, IdbTransactionMode::Readwrite)?;
+    for id in ids {
+        await_request(&store.delete(&JsValue::from_str(id))?).await?;
+    }
+    Ok(())
+}
+
+/// Uploads one batch.  `true` if the upload went through (or there was
+/// nothing to send).
+async fn flush() -> bool {
+    let Ok(db) = open_db().await else {
+        return false;
+    };
+    let ops = oldest(&db).await.unwrap_or_default();
+    if ops.is_empty() {
+        PENDING.store(0, Ordering::Release);
+        return true;
+    }
+
+    let body = json!({ "operations": ops });
+    let Some(resp) = crate::api::post_json_async("/sync/batch", &body).await else {
+        return false;
+    };
+    let processed: Vec<String> = resp["processed"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(|id| id.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    if remove(&db, &processed).await.is_err() {
+        return false;
+    }
+    if let Ok(n) = count(&db).await {
+        PENDING.store(n, Ordering::Release);
+    }
+    true
+}
+
+fn is_online() -> bool {
+    web_sys::window().is_some_and(|w| w.navigator().on_line())
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn load_pending_count() {
+    wasm_bindgen_futures::spawn_local(async {
+        if let Ok(db) = open_db().await {
+            if let Ok(n) = count(&db).await {
+                PENDING.store(n, Ordering::Release);
+            }
+        }
+    });
+}
+
+fn queue_offline_result(bridge: Res<BevyBridge>) {
+    if is_online() || bridge.game_id.is_empty() {
+        return;
+    }
+    let now = js_sys::Date::now() as i64;
+    let op = PendingOp {
+        id: format!(
+            "{:012x}-{:08x}",
+            now,
+            (js_sys::Math::random() * u32::MAX as f64) as u32
+        ),
+        action: "score_submit".to_string(),
+        game_id: bridge.game_id.clone(),
+        score: i64::from(bridge.current_score),
+        timestamp: now,
+    };
+
+    PENDING.fetch_add(1, Ordering::AcqRel);
+    wasm_bindgen_futures::spawn_local(async move {
+        if put(&op).await.is_err() {
+            PENDING.fetch_sub(1, Ordering::AcqRel);
+        }
+    });
+}
+
+/// Starts a flush when there's something queued, the browser is online
+/// and any backoff has elapsed.
+fn drive_sync(time: Res<Time>, mut sync: ResMut<SyncBackoff>) {
+    if let Some(ok) = FLUSH_RESULT.lock().ok().and_then(|mut r| r.take()) {
+        sync.backoff = if ok {
+            0.0
+        } else {
+            (sync.backoff * 2.0).clamp(BASE_BACKOFF_SECS, MAX_BACKOFF_SECS)
+        };
+        sync.retry_in = sync.backoff;
+    }
+
+    sync.retry_in -= time.delta_secs();
+    if sync.retry_in > 0.0
+        || PENDING.load(Ordering::Acquire) == 0
+        || !is_online()
+        || !crate::api::is_configured()
+    {
+        return;
+    }
+    if FLUSH_IN_FLIGHT.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    wasm_bindgen_futures::spawn_local(async {
+        let ok = flush().await;
+        if let Ok(mut result) = FLUSH_RESULT.lock() {
+            *result = Some(ok);
+        }
+        FLUSH_IN_FLIGHT.store(false, Ordering::Release);
+    });
+}
@@ -0,0 +1,106 @@
+//! Fixed-timestep simulation.
+//!
+//! Physics-driven games step their simulation in `FixedUpdate`
+//! ([`FixedGameplaySet`](crate::games::FixedGameplaySet)) at [`FIXED_HZ`],
+//! so a ball loses the same speed to friction and a runner scrolls the same
+//! distance per second on a 60 Hz and a 144 Hz display, and a seeded run
+//! plays out the same way every time.  Input stays in `Update`, where
+//! `just_pressed` fires exactly once.
+//!
+//! A frame runs zero, one or several fixed steps, so anything moved only
+//! by the simulation would visibly stutter.  Entities that carry
+//! [`Interpolated`] hold their simulated position in `Transform` while the
+//! steps run, and are drawn between the last two steps according to how
+//! far the clock has run past the latest one.
+
+use bevy::app::RunFixedMainLoopSystem;
+use bevy::prelude::*;
+
+/// Simulation steps per second.
+pub const FIXED_HZ: f64 = 60.0;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct TimestepPlugin;
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_HZ))
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    restore_simulated.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                    interpolate.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+                ),
+            )
+            .add_systems(FixedFirst, store_previous)
+            .add_systems(FixedLast, store_current);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Interpolation
+// ---------------------------------------------------------------------------
+
+/// Draws the entity between its last two simulated positions.  Add it to
+/// the spawn bundle of anything moved in `FixedUpdate`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+    /// Translation last drawn; anything else means the entity was moved
+    /// outside the simulation.
+    rendered: Vec3,
+}
+
+impl Default for Interpolated {
+    fn default() -> Self {
+        // NaN never matches the translation, so a new entity starts where
+        // it was spawned.
+        Self { previous: Vec3::NAN, current: Vec3::NAN, rendered: Vec3::NAN }
+    }
+}
+
+/// Puts back the simulated positions before the fixed steps.  An entity
+/// moved since it was drawn (just spawned, reused from a pool or moved by
+/// an `Update` system) continues from where it is instead.
+fn restore_simulated(mut q: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut tf, mut interp) in &mut q {
+        if tf.translation == interp.rendered {
+            tf.translation = interp.current;
+        } else {
+            interp.previous = tf.translation;
+            interp.current = tf.translation;
+        }
+    }
+}
+
+fn store_previous(mut q: Query<(&Transform, &mut Interpolated)>) {
+    for (tf, mut interp) in &mut q {
+        interp.previous = tf.translation;
+    }
+}
+
+fn store_current(mut q: Query<(&Transform, &mut Interpolated)>) {
+    for (tf, mut interp) in &mut q {
+        // Spawned during this step, after `store_previous` ran.
+        if interp.previous.is_nan() {
+            interp.previous = tf.translation;
+        }
+        interp.current = tf.translation;
+    }
+}
+
+fn interpolate(time: Res<Time<Fixed>>, mut q: Query<(&mut Transform, &mut Interpolated)>) {
+    let alpha = time.overstep_fraction();
+    for (mut tf, mut interp) in &mut q {
+        // Not stepped yet: leave it where it is.
+        if interp.current.is_nan() {
+            continue;
+        }
+        tf.translation = interp.previous.lerp(interp.current, alpha);
+        interp.rendered = tf.translation;
+    }
+}
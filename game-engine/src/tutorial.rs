@@ -0,0 +1,397 @@
+//! First-play tutorials.
+//!
+//! Games with an entry in [`TUTORIALS`] walk new players through their
+//! controls: a hint at the bottom of the screen ("Press SPACE to jump"),
+//! optionally with a bobbing arrow pointing at the player, a target or the
+//! HUD.  Each [`Step`] says what moves it on — a [`Control`], a
+//! [`GameAction`] or just time — and the game keeps running underneath.
+//!
+//! A game's tutorial plays on its first run only; the games that have
+//! been introduced are kept in `localStorage`.  The shell can call
+//! `replay_tutorial()` to play it again, straight away if a game is
+//! running or on the next start otherwise.  Resumed, versus and spectated
+//! runs skip it.
+
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::game_mode::GameMode;
+use crate::games::GameplaySet;
+use crate::input::{ActionState, Control, GameAction};
+use crate::{AppState, BevyBridge, GameOptions};
+
+const STORAGE_KEY: &str = "stem_tutorials";
+
+static REPLAY_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Length of the arrow, tip to tail, in world units.
+const ARROW_LENGTH: f32 = 40.0;
+const ARROW_COLOR: Color = Color::srgb(1.0, 0.84, 0.2);
+/// Where the HUD sits, in pixels from the top-left of the view.
+const HUD_POSITION: Vec2 = Vec2::new(70.0, 45.0);
+
+// ---------------------------------------------------------------------------
+// Tutorial data
+// ---------------------------------------------------------------------------
+
+/// What moves a step on.
+pub enum Advance {
+    /// Any of these controls is pressed.
+    Controls(&'static [Control]),
+    /// Any of these actions is sent.
+    Actions(&'static [GameAction]),
+    /// This many seconds pass.
+    After(f32),
+}
+
+/// Where a step's arrow points.
+pub enum Pointer {
+    /// Down at this spot, in world units.
+    At(Vec2),
+    /// Up at the HUD in the top-left corner.
+    Hud,
+}
+
+pub struct Step {
+    pub text: &'static str,
+    pub advance: Advance,
+    pub pointer: Option<Pointer>,
+}
+
+const fn step(text: &'static str, advance: Advance, pointer: Option<Pointer>) -> Step {
+    Step { text, advance, pointer }
+}
+
+const fn at(x: f32, y: f32) -> Option<Pointer> {
+    Some(Pointer::At(Vec2::new(x, y)))
+}
+
+const ARROWS: &[Control] = &[Control::MoveLeft, Control::MoveRight, Control::MoveUp, Control::MoveDown];
+const LEFT_RIGHT: &[Control] = &[Control::MoveLeft, Control::MoveRight];
+const JUMP: &[GameAction] = &[GameAction::Jump, GameAction::Pointer];
+const FIRE: &[GameAction] = &[GameAction::Fire, GameAction::Pointer];
+const CLICK: &[GameAction] = &[GameAction::Pointer];
+
+pub const TUTORIALS: &[(&str, &[Step])] = &[
+    ("campus_dash", &[
+        step("Press SPACE or tap to jump", Advance::Actions(JUMP), at(-300.0, -180.0)),
+        step("Jump the obstacles for as long as you can", Advance::After(3.0), None),
+        step("The further you run, the higher your score", Advance::After(3.0), Some(Pointer::Hud)),
+    ]),
+    ("gravity_shift_run", &[
+        step("Press SPACE or tap to flip gravity", Advance::Actions(JUMP), at(-200.0, 40.0)),
+        step("Flip between floor and ceiling to dodge obstacles", Advance::After(3.0), None),
+    ]),
+    ("parkour_lab", &[
+        step("Press SPACE or tap to jump", Advance::Actions(JUMP), at(-200.0, -180.0)),
+        step("Clean jumps build momentum, and momentum multiplies your score", Advance::After(4.0), None),
+    ]),
+    ("drone_defense", &[
+        step("Move with the LEFT and RIGHT arrow keys", Advance::Controls(LEFT_RIGHT), at(0.0, -200.0)),
+        step("Press F or click to fire", Advance::Actions(FIRE), None),
+        step("Shoot down the drones before they reach the ground", Advance::After(3.0), None),
+    ]),
+    ("lab_breach", &[
+        step("Press SPACE to jump", Advance::Actions(&[GameAction::Jump]), at(-250.0, -180.0)),
+        step("Press F or click to shoot", Advance::Actions(FIRE), None),
+    ]),
+    ("demo_day", &[
+        step("Click blocks to place up to 5 explosives", Advance::Actions(CLICK), at(0.0, 60.0)),
+        step("Press SPACE to detonate", Advance::Controls(&[Control::Action]), None),
+    ]),
+    ("geology_deep_dive", &[
+        step("Dig with the arrow keys", Advance::Controls(ARROWS), at(20.0, 270.0)),
+        step("Bring minerals back to the surface to sell them", Advance::After(4.0), None),
+        step("Digging burns fuel - don't run dry underground", Advance::After(4.0), Some(Pointer::Hud)),
+    ]),
+    ("logicrons_grid_shift", &[
+        step("Tip the block over with the arrow keys", Advance::Controls(ARROWS), None),
+        step("Stand it upright on the goal to clear the level", Advance::After(4.0), None),
+        step("Every move counts - try to use as few as you can", Advance::After(3.0), Some(Pointer::Hud)),
+    ]),
+    ("hydro_logic_puzzles", &[
+        step("Move with the arrow keys and walk into orbs to push them", Advance::Controls(ARROWS), None),
+        step("Orbs fall until they land - get one onto every target", Advance::After(4.0), None),
+    ]),
+    ("history_vault_escape", &[
+        step("Move with the arrow keys", Advance::Controls(ARROWS), None),
+        step("Keys open the doors of their colour", Advance::After(4.0), Some(Pointer::Hud)),
+        step("Hit switches to disarm traps, then reach the exit", Advance::After(4.0), None),
+    ]),
+    ("physics_master_billiards", &[
+        step("Click and drag from the cue ball, then let go to shoot", Advance::Actions(CLICK), at(-175.0, 30.0)),
+        step("Sink the coloured balls, but not the cue ball", Advance::After(4.0), None),
+    ]),
+    ("bridge_builder", &[
+        step("Click two points to place a beam between them", Advance::Actions(CLICK), None),
+        step("Press SPACE to test the bridge", Advance::Controls(&[Control::Action]), None),
+        step("Press R after a test to go back and rebuild", Advance::After(4.0), None),
+    ]),
+];
+
+pub fn find_tutorial(game_id: &str) -> Option<&'static [Step]> {
+    TUTORIALS.iter().find(|(id, _)| *id == game_id).map(|(_, steps)| *steps)
+}
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorials>()
+            .add_systems(Startup, load_seen)
+            .add_systems(OnEnter(AppState::Playing), begin)
+            .add_systems(
+                Update,
+                (
+                    poll_replay.run_if(in_state(AppState::Playing)),
+                    advance.in_set(GameplaySet),
+                    point_arrow,
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(AppState::Playing), end);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+#[derive(Resource, Default)]
+pub struct Tutorials {
+    /// Games whose tutorial has been shown.
+    pub seen: Vec<String>,
+    active: Option<ActiveTutorial>,
+}
+
+struct ActiveTutorial {
+    steps: &'static [Step],
+    index: usize,
+    elapsed: f32,
+}
+
+/// The hint and the arrow, respawned for every step.
+#[derive(Component)]
+struct TutorialUi;
+
+#[derive(Component)]
+struct TutorialArrow;
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Play the running game's tutorial again, or the next game's if none is
+/// running.
+#[wasm_bindgen]
+pub fn replay_tutorial() {
+    REPLAY_REQUESTED.store(true, Ordering::Release);
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn load_seen(mut tutorials: ResMut<Tutorials>) {
+    let saved = crate::persistence::local_storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok());
+    if let Some(saved) = saved {
+        tutorials.seen = saved;
+    }
+}
+
+fn begin(
+    mut commands: Commands,
+    bridge: Res<BevyBridge>,
+    options: Res<GameOptions>,
+    mut tutorials: ResMut<Tutorials>,
+) {
+    let replay = REPLAY_REQUESTED.swap(false, Ordering::AcqRel);
+    let skip = options.get("resume").is_some()
+        || options.get("spectate").is_some()
+        || GameMode::from_options(&options) == GameMode::Versus;
+    if skip || (!replay && tutorials.seen.contains(&bridge.game_id)) {
+        return;
+    }
+    start(&mut commands, &bridge.game_id, &mut tutorials);
+}
+
+fn poll_replay(
+    mut commands: Commands,
+    bridge: Res<BevyBridge>,
+    mut tutorials: ResMut<Tutorials>,
+    shown: Query<Entity, With<TutorialUi>>,
+) {
+    if !REPLAY_REQUESTED.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    for e in &shown {
+        commands.entity(e).despawn_recursive();
+    }
+    start(&mut commands, &bridge.game_id, &mut tutorials);
+}
+
+fn advance(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<ActionState>,
+    mut actions: EventReader<GameAction>,
+    mut tutorials: ResMut<Tutorials>,
+    shown: Query<Entity, With<TutorialUi>>,
+) {
+    let Some(active) = tutorials.active.as_mut() else {
+        actions.clear();
+        return;
+    };
+    active.elapsed += time.delta_secs();
+    let done = match active.steps[active.index].advance {
+        Advance::Controls(wanted) => wanted.iter().any(|&c| input.just_pressed(c)),
+        Advance::Actions(wanted) => actions.read().any(|a| wanted.contains(a)),
+        Advance::After(secs) => active.elapsed >= secs,
+    };
+    actions.clear();
+    if !done {
+        return;
+    }
+
+    for e in &shown {
+        commands.entity(e).despawn_recursive();
+    }
+    active.index += 1;
+    active.elapsed = 0.0;
+    if active.index < active.steps.len() {
+        spawn_step(&mut commands, active);
+    } else {
+        tutorials.active = None;
+    }
+}
+
+/// Keeps the arrow on its target, bobbing along its length.
+fn point_arrow(
+    time: Res<Time>,
+    tutorials: Res<Tutorials>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut arrows: Query<&mut Transform, With<TutorialArrow>>,
+) {
+    let Some(pointer) = tutorials
+        .active
+        .as_ref()
+        .and_then(|a| a.steps[a.index].pointer.as_ref())
+    else {
+        return;
+    };
+    let (tip, rotation) = match pointer {
+        Pointer::At(pos) => (*pos, 0.0),
+        Pointer::Hud => {
+            let Ok((camera, cam_tf)) = cameras.get_single() else { return };
+            let Ok(hud) = camera.viewport_to_world_2d(cam_tf, HUD_POSITION) else { return };
+            (hud, PI)
+        }
+    };
+    let bob = 6.0 * (time.elapsed_secs() * 6.0).sin().abs();
+    for mut tf in &mut arrows {
+        tf.rotation = Quat::from_rotation_z(rotation);
+        tf.translation = (tip + tf.rotation.mul_vec3(Vec3::Y).truncate() * bob).extend(60.0);
+    }
+}
+
+fn end(
+    mut commands: Commands,
+    mut tutorials: ResMut<Tutorials>,
+    shown: Query<Entity, With<TutorialUi>>,
+) {
+    tutorials.active = None;
+    for e in &shown {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn start(commands: &mut Commands, game_id: &str, tutorials: &mut Tutorials) {
+    let Some(steps) = find_tutorial(game_id) else { return };
+    let active = ActiveTutorial { steps, index: 0, elapsed: 0.0 };
+    spawn_step(commands, &active);
+    tutorials.active = Some(active);
+
+    if !tutorials.seen.iter().any(|id| id == game_id) {
+        tutorials.seen.push(game_id.to_string());
+        if let (Some(storage), Ok(json)) = (
+            crate::persistence::local_storage(),
+            serde_json::to_string(&tutorials.seen),
+        ) {
+            storage.set_item(STORAGE_KEY, &json).ok();
+        }
+    }
+}
+
+fn spawn_step(commands: &mut Commands, active: &ActiveTutorial) {
+    let step = &active.steps[active.index];
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            TutorialUi,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new(format!("{}   {}/{}", step.text, active.index + 1, active.steps.len())),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            ));
+        });
+
+    if step.pointer.is_none() {
+        return;
+    }
+    // Tip at the origin, pointing down; `point_arrow` places and turns it.
+    commands
+        .spawn((
+            Transform::default(),
+            Visibility::default(),
+            TutorialArrow,
+            TutorialUi,
+        ))
+        .with_children(|arrow| {
+            arrow.spawn((
+                Sprite {
+                    color: ARROW_COLOR,
+                    custom_size: Some(Vec2::new(6.0, ARROW_LENGTH * 0.6)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, ARROW_LENGTH * 0.65, 0.0),
+            ));
+            arrow.spawn((
+                Sprite {
+                    color: ARROW_COLOR,
+                    custom_size: Some(Vec2::splat(ARROW_LENGTH * 0.4)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, ARROW_LENGTH * 0.28, 0.0)
+                    .with_rotation(Quat::from_rotation_z(PI / 4.0)),
+            ));
+        });
+}
@@ -0,0 +1,139 @@
+//! Eased movement for grid games.
+//!
+//! Grid games track pieces by cell and used to snap their sprites from
+//! tile to tile.  A piece that carries a [`MoveTo`] instead glides: the
+//! game points it at the new cell's position with [`MoveTo::go`] (or
+//! [`MoveTo::fall`] for drops) and [`animate`] eases the translation there
+//! over [`MOVE_SECS`].  Retargeting mid-move starts from wherever the
+//! sprite is drawn, so quick key repeats and chained falls stay smooth.
+//!
+//! Falls stretch the sprite on the way down and squash it on landing,
+//! unless reduced motion is on.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::games::GameplaySet;
+use crate::pixar::IdleAnimation;
+
+/// How long a move takes.
+pub const MOVE_SECS: f32 = 0.15;
+/// How long the landing squash takes to settle.
+const SQUASH_SECS: f32 = 0.18;
+/// Peak squash on landing, as a share of the sprite's size.
+const SQUASH: f32 = 0.25;
+/// Peak stretch while falling.
+const STRETCH: f32 = 0.12;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate.in_set(GameplaySet).after(IdleAnimation));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Component
+// ---------------------------------------------------------------------------
+
+/// Eases an entity's translation towards a target.  Spawn it with
+/// [`MoveTo::at`] at the entity's starting translation.
+#[derive(Component, Debug)]
+pub struct MoveTo {
+    from: Vec3,
+    to: Vec3,
+    elapsed: f32,
+    /// The move is a fall: stretch on the way, squash on arrival.
+    falling: bool,
+    /// Time left on the landing squash.
+    squash: f32,
+    /// The scale was stretched or squashed and needs restoring.
+    reshaped: bool,
+}
+
+impl MoveTo {
+    /// At rest at `at`.
+    pub fn at(at: Vec3) -> Self {
+        Self { from: at, to: at, elapsed: MOVE_SECS, falling: false, squash: 0.0, reshaped: false }
+    }
+
+    /// Heads for `to`, unless already on the way there.
+    pub fn go(&mut self, to: Vec3) {
+        if to == self.to { return; }
+        self.from = self.position();
+        self.to = to;
+        self.elapsed = 0.0;
+        self.falling = false;
+    }
+
+    /// Like [`go`](Self::go), for a drop or a block tipping over: the
+    /// sprite stretches on the way and squashes when it lands.
+    pub fn fall(&mut self, to: Vec3) {
+        if to == self.to { return; }
+        self.go(to);
+        self.falling = true;
+    }
+
+    /// Where the sprite is drawn now.
+    pub fn position(&self) -> Vec3 {
+        self.from.lerp(self.to, ease_out(self.elapsed / MOVE_SECS))
+    }
+
+    /// Where the sprite is headed.
+    pub fn target(&self) -> Vec3 {
+        self.to
+    }
+}
+
+/// Cubic ease-out: quick off the mark, gentle arrival.
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+/// Advances moves and landing squashes.  Scale is only touched during a
+/// fall or a squash, and put back after; the idle animations own it
+/// otherwise.
+pub fn animate(
+    time: Res<Time>,
+    settings: Res<AccessibilitySettings>,
+    mut q: Query<(&mut Transform, &mut MoveTo)>,
+) {
+    let dt = time.delta_secs();
+    for (mut tf, mut mv) in &mut q {
+        if mv.elapsed < MOVE_SECS {
+            mv.elapsed = (mv.elapsed + dt).min(MOVE_SECS);
+            tf.translation = mv.position();
+            if mv.falling && settings.reduced_motion {
+                mv.falling = false;
+            } else if mv.falling {
+                let s = STRETCH * (PI * mv.elapsed / MOVE_SECS).sin();
+                tf.scale = Vec3::new(1.0 - s, 1.0 + s, 1.0);
+                mv.reshaped = true;
+                if mv.elapsed >= MOVE_SECS {
+                    mv.falling = false;
+                    mv.squash = SQUASH_SECS;
+                }
+            }
+        }
+        if mv.squash > 0.0 {
+            mv.squash = (mv.squash - dt).max(0.0);
+            let s = SQUASH * (mv.squash / SQUASH_SECS).powi(2);
+            tf.scale = Vec3::new(1.0 + s, 1.0 - s, 1.0);
+        } else if mv.reshaped && !mv.falling {
+            // Landed, or a sideways move cut the fall short.
+            tf.scale = Vec3::ONE;
+            mv.reshaped = false;
+        }
+    }
+}
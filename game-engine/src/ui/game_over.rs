@@ -0,0 +1,373 @@
+//! Game over screen.
+//!
+//! When a run ends the engine shows its result over the last frame: the
+//! final score, the stars it earned (campaign levels only, see
+//! [`campaign`](crate::campaign)), how it compares with the player's best
+//! (see [`records`](crate::records)), and three buttons:
+//!
+//! * **Retry** starts the same game again with the same options, straight
+//!   from the engine — the shell isn't involved.  It's left out once the
+//!   day's playtime is used up (see [`wellbeing`](crate::wellbeing)).
+//! * **Share** saves a PNG of the result, drawn on an offscreen canvas.
+//!   The shell can get the same image as a data URL from `share_image()`,
+//!   e.g. for the Web Share API.
+//! * **Quit** clears the screen; the shell finds out from
+//!   `quit_requested()`.
+//!
+//! Versus matches show their winner screen instead, and spectators only
+//! get Quit.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement};
+
+use crate::records::RunRecord;
+use crate::versus::VersusState;
+use crate::wellbeing::Playtime;
+use crate::{campaign, AppState, BevyBridge, GameOptions};
+
+use super::{button_colors, reset_for_restart, BUTTON_IDLE};
+
+/// JS global holding the last [`RunSummary`], read by `share_image`.
+const RESULT_GLOBAL: &str = "__bevy_result";
+const QUIT_GLOBAL: &str = "__bevy_quit";
+
+const STAR_ON: Color = Color::srgb(1.0, 0.84, 0.2);
+const STAR_OFF: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+/// Share images use the common link-preview size.
+const SHARE_WIDTH: u32 = 1200;
+const SHARE_HEIGHT: u32 = 630;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::GameOver), show_result)
+            .add_systems(
+                Update,
+                (game_over_buttons, button_colors::<GameOverButton>)
+                    .chain()
+                    .run_if(in_state(AppState::GameOver)),
+            )
+            .add_systems(OnExit(AppState::GameOver), despawn_screen);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// What the game over screen and the share image show.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub score: i32,
+    /// Stars earned, for campaign levels.
+    pub stars: Option<u8>,
+    /// The best before this run; `None` on the game's first run.
+    #[serde(rename = "previousBest")]
+    pub previous_best: Option<i32>,
+    #[serde(rename = "newBest")]
+    pub new_best: bool,
+}
+
+impl RunSummary {
+    fn best_line(&self) -> String {
+        match self.previous_best {
+            Some(best) if self.new_best => format!("New best! Previous: {}", best),
+            Some(best) => format!("Best: {}", best),
+            None => "First score on the board!".to_string(),
+        }
+    }
+}
+
+/// The summary the Share button draws.
+#[derive(Resource)]
+struct LastSummary(RunSummary);
+
+#[derive(Component)]
+struct GameOverScreen;
+
+#[derive(Component, Clone, Copy)]
+enum GameOverButton {
+    Retry,
+    Share,
+    Quit,
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// The last game's result as a PNG data URL (`data:image/png;base64,...`),
+/// or an empty string before the first game over.
+#[wasm_bindgen]
+pub fn share_image() -> String {
+    crate::get_js_global(RESULT_GLOBAL)
+        .and_then(|json| serde_json::from_str::<RunSummary>(&json).ok())
+        .and_then(|summary| render_share_image(&summary))
+        .unwrap_or_default()
+}
+
+/// Whether Quit was pressed on the game over screen since the last call.
+#[wasm_bindgen]
+pub fn quit_requested() -> bool {
+    let quit = crate::get_js_global(QUIT_GLOBAL).as_deref() == Some("true");
+    if quit {
+        crate::delete_js_global(QUIT_GLOBAL);
+    }
+    quit
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn show_result(
+    mut commands: Commands,
+    bridge: Res<BevyBridge>,
+    run: Res<RunRecord>,
+    versus: Res<VersusState>,
+    options: Res<GameOptions>,
+    playtime: Res<Playtime>,
+) {
+    if versus.active {
+        return;
+    }
+    let score = bridge.current_score;
+    let summary = RunSummary {
+        game_id: bridge.game_id.clone(),
+        score,
+        stars: campaign::find_level(&bridge.game_id).map(|l| l.stars_for(score)),
+        previous_best: run.previous_best,
+        new_best: run.previous_best.is_some_and(|best| score > best),
+    };
+    crate::set_js_global(RESULT_GLOBAL, &serde_json::to_string(&summary).unwrap_or_default());
+
+    let spectating = options.get("spectate").is_some();
+    spawn_screen(&mut commands, &summary, spectating, playtime.exhausted());
+    commands.insert_resource(LastSummary(summary));
+}
+
+fn game_over_buttons(
+    buttons: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    summary: Option<Res<LastSummary>>,
+    mut bridge: ResMut<BevyBridge>,
+    mut options: ResMut<GameOptions>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            // The game's cleanup already ran when it left Playing.
+            GameOverButton::Retry => {
+                reset_for_restart(&mut bridge, &mut options);
+                next.set(AppState::Playing);
+            }
+            GameOverButton::Share => {
+                if let Some(url) = summary.as_ref().and_then(|s| render_share_image(&s.0)) {
+                    download(&url, &format!("{}-{}.png", bridge.game_id, bridge.current_score));
+                }
+            }
+            GameOverButton::Quit => {
+                crate::set_js_global(QUIT_GLOBAL, "true");
+                next.set(AppState::Menu);
+            }
+        }
+    }
+}
+
+fn despawn_screen(mut commands: Commands, q: Query<Entity, With<GameOverScreen>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<LastSummary>();
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+fn spawn_screen(commands: &mut Commands, summary: &RunSummary, spectating: bool, out_of_time: bool) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(14.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.05, 0.6)),
+            GlobalZIndex(90),
+            GameOverScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new("GAME OVER"),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.2, 0.2)),
+            ));
+            root.spawn((
+                Text::new(format!("Score: {}", summary.score)),
+                TextFont {
+                    font_size: 36.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            if let Some(stars) = summary.stars {
+                root.spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for i in 0..3 {
+                        row.spawn((
+                            Node {
+                                width: Val::Px(30.0),
+                                height: Val::Px(30.0),
+                                ..default()
+                            },
+                            BorderRadius::MAX,
+                            BackgroundColor(if i < stars { STAR_ON } else { STAR_OFF }),
+                        ));
+                    }
+                });
+            }
+
+            root.spawn((
+                Text::new(summary.best_line()),
+                TextFont {
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextColor(if summary.new_best { STAR_ON } else { Color::srgb(0.8, 0.82, 0.9) }),
+            ));
+
+            root.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(12.0),
+                margin: UiRect::top(Val::Px(12.0)),
+                ..default()
+            })
+            .with_children(|row| {
+                if !spectating {
+                    if !out_of_time {
+                        spawn_button(row, GameOverButton::Retry, "Retry");
+                    }
+                    spawn_button(row, GameOverButton::Share, "Share");
+                }
+                spawn_button(row, GameOverButton::Quit, "Quit");
+            });
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, button: GameOverButton, text: &str) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(150.0),
+                height: Val::Px(48.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::all(Val::Px(10.0)),
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_children(|b| {
+            b.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+// ---------------------------------------------------------------------------
+// Share image
+// ---------------------------------------------------------------------------
+
+/// `"campus_dash"` → `"Campus Dash"`.
+fn game_title(game_id: &str) -> String {
+    game_id
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Draws the result on a canvas that never joins the page and returns it
+/// as a PNG data URL.
+fn render_share_image(summary: &RunSummary) -> Option<String> {
+    let document = web_sys::window()?.document()?;
+    let canvas: HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+    canvas.set_width(SHARE_WIDTH);
+    canvas.set_height(SHARE_HEIGHT);
+    let ctx: CanvasRenderingContext2d = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+    let (w, h) = (f64::from(SHARE_WIDTH), f64::from(SHARE_HEIGHT));
+
+    ctx.set_fill_style_str("#161a33");
+    ctx.fill_rect(0.0, 0.0, w, h);
+    ctx.set_fill_style_str("#3a5bd9");
+    ctx.fill_rect(0.0, h - 24.0, w, 24.0);
+
+    ctx.set_text_align("center");
+    let line = |text: &str, font: &str, color: &str, y: f64| {
+        ctx.set_font(font);
+        ctx.set_fill_style_str(color);
+        ctx.fill_text(text, w / 2.0, y).ok()
+    };
+    line("STEM School Adventures", "bold 36px sans-serif", "#9aa4d6", 90.0)?;
+    line(&game_title(&summary.game_id), "bold 72px sans-serif", "#ffffff", 190.0)?;
+    line(&summary.score.to_string(), "bold 150px sans-serif", "#ffd633", 360.0)?;
+    let mut y = 450.0;
+    if let Some(stars) = summary.stars {
+        let row: String = (0..3).map(|i| if i < stars { '★' } else { '☆' }).collect();
+        line(&row, "64px sans-serif", "#ffd633", y)?;
+        y += 80.0;
+    }
+    line(&summary.best_line(), "bold 40px sans-serif", "#cfd5f5", y)?;
+
+    canvas.to_data_url_with_type("image/png").ok()
+}
+
+/// Saves `url` as `filename` through a throwaway link.
+fn download(url: &str, filename: &str) {
+    let link = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.create_element("a").ok())
+        .and_then(|e| e.dyn_into::<HtmlAnchorElement>().ok());
+    if let Some(link) = link {
+        link.set_href(url);
+        link.set_download(filename);
+        link.click();
+    }
+}
@@ -0,0 +1,382 @@
+//! Pause menu and player settings.
+//!
+//! Pressing **Escape** or a gamepad's **Start** during a game (or calling
+//! the `open_menu()` export) pauses the game and shows an overlay with Resume, Restart, Quit, a volume
+//! control and a colorblind-mode toggle.  While paused, virtual time is
+//! stopped and every system in [`GameplaySet`](crate::games::GameplaySet)
+//! is skipped, so no game advances or reacts to input behind the overlay.
+//!
+//! Settings are published as JSON in `__bevy_settings` (see
+//! `get_settings()`) so the shell can apply the volume to its own audio.
+//! The colorblind button cycles
+//! [`AccessibilitySettings`](crate::accessibility::AccessibilitySettings).
+
+use bevy::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::accessibility::{AccessibilitySettings, ColorblindMode};
+use crate::input::GameAction;
+use crate::{AppState, BevyBridge, GameOptions};
+
+use super::{button_colors, reset_for_restart, BUTTON_IDLE};
+
+/// Volume change per press of the `-` / `+` buttons.
+const VOLUME_STEP: f32 = 0.1;
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseState>()
+            .init_resource::<PlayerSettings>()
+            // Accessibility can also change from the shell, so publish
+            // outside the menu too.
+            .add_systems(Update, publish_settings)
+            .add_systems(
+                Update,
+                toggle_pause.run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (menu_buttons, button_colors::<MenuButton>, update_labels)
+                    .chain()
+                    .run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(OnEnter(PauseState::Paused), (pause_time, spawn_menu))
+            .add_systems(OnExit(PauseState::Paused), (resume_time, despawn_menu))
+            .add_systems(OnEnter(AppState::Menu), apply_restart);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// States / resources / components
+// ---------------------------------------------------------------------------
+
+/// Whether the running game is paused.  Only exists while `Playing`, and
+/// always starts as `Running` when a game starts.
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(AppState = AppState::Playing)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Player-adjustable settings shared by every game.
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerSettings {
+    /// Master volume, `0.0..=1.0`.
+    pub volume: f32,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.8,
+        }
+    }
+}
+
+/// Set by the Restart button; consumed once the old scene has been torn down.
+#[derive(Resource)]
+struct PendingRestart;
+
+#[derive(Component)]
+struct MenuOverlay;
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Resume,
+    Restart,
+    Quit,
+    VolumeDown,
+    VolumeUp,
+    Colorblind,
+}
+
+#[derive(Component)]
+enum MenuLabel {
+    Volume,
+    Colorblind,
+}
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Open the pause menu, e.g. from a shell button on touch devices.
+/// Ignored when no game is running.
+#[wasm_bindgen]
+pub fn open_menu() {
+    crate::set_js_global("__bevy_open_menu", "true");
+}
+
+/// Current settings as JSON, e.g.
+/// `{"volume":0.8,"colorblind":"off","patterns":false,"high_contrast":false,"reduced_motion":false}`.
+#[wasm_bindgen]
+pub fn get_settings() -> String {
+    crate::get_js_global("__bevy_settings").unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn toggle_pause(
+    mut actions: EventReader<GameAction>,
+    state: Res<State<PauseState>>,
+    mut next: ResMut<NextState<PauseState>>,
+) {
+    let open_requested = crate::get_js_global("__bevy_open_menu").as_deref() == Some("true");
+    if open_requested {
+        crate::delete_js_global("__bevy_open_menu");
+    }
+
+    let pause_pressed = actions.read().any(|a| *a == GameAction::Pause);
+
+    match state.get() {
+        PauseState::Running if open_requested || pause_pressed => {
+            next.set(PauseState::Paused);
+        }
+        PauseState::Paused if pause_pressed => {
+            next.set(PauseState::Running);
+        }
+        _ => {}
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn menu_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut settings: ResMut<PlayerSettings>,
+    mut accessibility: ResMut<AccessibilitySettings>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_app: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MenuButton::Resume => next_pause.set(PauseState::Running),
+            MenuButton::Restart => {
+                // Leave Playing so every game's cleanup runs, then come
+                // straight back in from `apply_restart`.
+                commands.insert_resource(PendingRestart);
+                next_app.set(AppState::Menu);
+            }
+            MenuButton::Quit => next_app.set(AppState::GameOver),
+            MenuButton::VolumeDown => {
+                settings.volume = (settings.volume - VOLUME_STEP).max(0.0);
+            }
+            MenuButton::VolumeUp => {
+                settings.volume = (settings.volume + VOLUME_STEP).min(1.0);
+            }
+            MenuButton::Colorblind => {
+                let mode = accessibility.colorblind.next();
+                accessibility.colorblind = mode;
+                accessibility.patterns = mode != ColorblindMode::Off;
+            }
+        }
+    }
+}
+
+fn apply_restart(
+    mut commands: Commands,
+    pending: Option<Res<PendingRestart>>,
+    mut bridge: ResMut<BevyBridge>,
+    mut options: ResMut<GameOptions>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if pending.is_none() {
+        return;
+    }
+    commands.remove_resource::<PendingRestart>();
+    reset_for_restart(&mut bridge, &mut options);
+    next.set(AppState::Playing);
+}
+
+fn volume_label(settings: &PlayerSettings) -> String {
+    format!("Volume: {}%", (settings.volume * 100.0).round() as i32)
+}
+
+fn colorblind_label(accessibility: &AccessibilitySettings) -> String {
+    format!("Colorblind mode: {}", accessibility.colorblind.label())
+}
+
+fn update_labels(
+    settings: Res<PlayerSettings>,
+    accessibility: Res<AccessibilitySettings>,
+    mut q: Query<(&mut Text, &MenuLabel)>,
+) {
+    if !settings.is_changed() && !accessibility.is_changed() {
+        return;
+    }
+    for (mut text, label) in &mut q {
+        **text = match label {
+            MenuLabel::Volume => volume_label(&settings),
+            MenuLabel::Colorblind => colorblind_label(&accessibility),
+        };
+    }
+}
+
+fn publish_settings(settings: Res<PlayerSettings>, accessibility: Res<AccessibilitySettings>) {
+    if !settings.is_changed() && !accessibility.is_changed() {
+        return;
+    }
+    let json = serde_json::json!({
+        "volume": settings.volume,
+        "colorblind": accessibility.colorblind,
+        "patterns": accessibility.patterns,
+        "high_contrast": accessibility.high_contrast,
+        "reduced_motion": accessibility.reduced_motion,
+    });
+    crate::set_js_global("__bevy_settings", &json.to_string());
+}
+
+fn despawn_menu(mut commands: Commands, q: Query<Entity, With<MenuOverlay>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+fn spawn_menu(
+    mut commands: Commands,
+    settings: Res<PlayerSettings>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.05, 0.7)),
+            GlobalZIndex(100),
+            MenuOverlay,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new("Paused"),
+                TextFont {
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
+            ));
+
+            spawn_button(root, MenuButton::Resume, "Resume", None);
+            spawn_button(root, MenuButton::Restart, "Restart", None);
+
+            root.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(12.0),
+                ..default()
+            })
+            .with_children(|row| {
+                spawn_small_button(row, MenuButton::VolumeDown, "-");
+                row.spawn((
+                    Text::new(volume_label(&settings)),
+                    TextFont {
+                        font_size: 22.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    MenuLabel::Volume,
+                ));
+                spawn_small_button(row, MenuButton::VolumeUp, "+");
+            });
+
+            spawn_button(
+                root,
+                MenuButton::Colorblind,
+                &colorblind_label(&accessibility),
+                Some(MenuLabel::Colorblind),
+            );
+            spawn_button(root, MenuButton::Quit, "Quit", None);
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, button: MenuButton, text: &str, label: Option<MenuLabel>) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(260.0),
+                height: Val::Px(48.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::all(Val::Px(10.0)),
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_children(|b| {
+            let mut text = b.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            if let Some(label) = label {
+                text.insert(label);
+            }
+        });
+}
+
+fn spawn_small_button(parent: &mut ChildBuilder, button: MenuButton, text: &str) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(48.0),
+                height: Val::Px(48.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::all(Val::Px(10.0)),
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_children(|b| {
+            b.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 26.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
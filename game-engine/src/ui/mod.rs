@@ -0,0 +1,35 @@
+//! In-engine UI overlays that don't depend on the React shell.
+
+use bevy::prelude::*;
+
+use crate::{BevyBridge, GameOptions};
+
+pub mod game_over;
+pub mod menu;
+
+const BUTTON_IDLE: Color = Color::srgb(0.18, 0.2, 0.3);
+const BUTTON_HOVER: Color = Color::srgb(0.26, 0.3, 0.45);
+const BUTTON_PRESSED: Color = Color::srgb(0.35, 0.55, 0.85);
+
+/// Colours the buttons marked with `T` by their interaction.
+fn button_colors<T: Component>(
+    mut q: Query<(&Interaction, &mut BackgroundColor), (With<T>, Changed<Interaction>)>,
+) {
+    for (interaction, mut bg) in &mut q {
+        bg.0 = match interaction {
+            Interaction::Pressed => BUTTON_PRESSED,
+            Interaction::Hovered => BUTTON_HOVER,
+            Interaction::None => BUTTON_IDLE,
+        };
+    }
+}
+
+/// Readies the bridge and options for playing the same game again.
+fn reset_for_restart(bridge: &mut BevyBridge, options: &mut GameOptions) {
+    bridge.current_score = 0;
+    bridge.stats = Default::default();
+    // A restart begins from scratch, not from the save it was resumed from.
+    if let Some(raw) = options.raw.as_object_mut() {
+        raw.remove("resume");
+    }
+}
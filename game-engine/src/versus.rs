@@ -0,0 +1,234 @@
+//! Two-player local versus on one keyboard.
+//!
+//! Started with `{"mode":"versus"}` in the `start_game` options (see
+//! [`GameMode::Versus`]).  Games that support it — `stem_project_volley`
+//! and `physics_master_billiards` — replace the computer opponent with a
+//! second player and take turns between the two; other games play as
+//! Classic.  A bot match of those games (see [`ai`](crate::ai)) is a
+//! versus match with the bot in player two's seat.
+//!
+//! Each player has their own half of the keyboard, read into the
+//! [`SplitInput`] resource every frame:
+//!
+//! | Player | Move / aim | Action |
+//! |--------|------------|--------|
+//! | One    | WASD       | Space  |
+//! | Two    | arrow keys | Enter  |
+//!
+//! Games keep each player's score in [`VersusState`] and call
+//! [`VersusState::finish`] before ending the run; the game over screen
+//! then names the winner.
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::ai::{self, Bot};
+use crate::game_mode::GameMode;
+use crate::{catalog, AppState, BevyBridge, GameOptions};
+
+pub const PLAYER_ONE_COLOR: Color = Color::srgb(0.35, 0.6, 1.0);
+pub const PLAYER_TWO_COLOR: Color = Color::srgb(1.0, 0.4, 0.35);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VersusState>()
+            .init_resource::<SplitInput>()
+            .add_systems(PreUpdate, read_split_input.after(InputSystem))
+            .add_systems(OnEnter(AppState::Playing), begin_match)
+            .add_systems(OnEnter(AppState::GameOver), spawn_winner_screen)
+            .add_systems(OnExit(AppState::GameOver), despawn_winner_screen);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VersusPlayer {
+    #[default]
+    One,
+    Two,
+}
+
+impl VersusPlayer {
+    pub fn index(self) -> usize {
+        match self {
+            Self::One => 0,
+            Self::Two => 1,
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Self::One => Self::Two,
+            Self::Two => Self::One,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::One => "P1",
+            Self::Two => "P2",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::One => PLAYER_ONE_COLOR,
+            Self::Two => PLAYER_TWO_COLOR,
+        }
+    }
+}
+
+/// Scores and turn order of a local versus match.
+#[derive(Resource, Debug, Default)]
+pub struct VersusState {
+    pub active: bool,
+    pub scores: [i32; 2],
+    /// Whose turn it is, in turn-based games.
+    pub turn: VersusPlayer,
+    pub finished: bool,
+    /// `None` after a draw.
+    pub winner: Option<VersusPlayer>,
+}
+
+impl VersusState {
+    /// A fresh match if the options ask for versus or a bot match of the
+    /// versus game `game_id`, otherwise inactive.
+    pub fn from_options(options: &GameOptions, game_id: &str) -> Self {
+        let bot_match = ai::bot_match(options, game_id) && catalog::find(game_id).is_some_and(|g| g.versus);
+        Self {
+            active: GameMode::from_options(options) == GameMode::Versus || bot_match,
+            ..default()
+        }
+    }
+
+    pub fn score(&self, player: VersusPlayer) -> i32 {
+        self.scores[player.index()]
+    }
+
+    pub fn add_score(&mut self, player: VersusPlayer, points: i32) {
+        self.scores[player.index()] += points;
+    }
+
+    pub fn pass_turn(&mut self) {
+        self.turn = self.turn.other();
+    }
+
+    /// Ends the match with `winner`, or a draw.
+    pub fn finish(&mut self, winner: Option<VersusPlayer>) {
+        self.finished = true;
+        self.winner = winner;
+    }
+
+    /// Ends the match in favour of the higher score.
+    pub fn finish_on_score(&mut self) {
+        let winner = match self.scores[0].cmp(&self.scores[1]) {
+            std::cmp::Ordering::Greater => Some(VersusPlayer::One),
+            std::cmp::Ordering::Less => Some(VersusPlayer::Two),
+            std::cmp::Ordering::Equal => None,
+        };
+        self.finish(winner);
+    }
+
+    /// The score reported to the shell: the better of the two.
+    pub fn best_score(&self) -> i32 {
+        self.scores[0].max(self.scores[1])
+    }
+}
+
+/// One player's half of the keyboard this frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerInput {
+    /// Each axis in `-1.0..=1.0`, `+y` up.
+    pub movement: Vec2,
+    /// The action key went down this frame.
+    pub action: bool,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct SplitInput(pub [PlayerInput; 2]);
+
+impl SplitInput {
+    pub fn player(&self, player: VersusPlayer) -> PlayerInput {
+        self.0[player.index()]
+    }
+}
+
+#[derive(Component)]
+struct WinnerScreen;
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn read_split_input(keys: Res<ButtonInput<KeyCode>>, mut input: ResMut<SplitInput>) {
+    let axis = |neg: KeyCode, pos: KeyCode| {
+        f32::from(u8::from(keys.pressed(pos))) - f32::from(u8::from(keys.pressed(neg)))
+    };
+    input.0 = [
+        PlayerInput {
+            movement: Vec2::new(axis(KeyCode::KeyA, KeyCode::KeyD), axis(KeyCode::KeyS, KeyCode::KeyW)),
+            action: keys.just_pressed(KeyCode::Space),
+        },
+        PlayerInput {
+            movement: Vec2::new(
+                axis(KeyCode::ArrowLeft, KeyCode::ArrowRight),
+                axis(KeyCode::ArrowDown, KeyCode::ArrowUp),
+            ),
+            action: keys.just_pressed(KeyCode::Enter) || keys.just_pressed(KeyCode::NumpadEnter),
+        },
+    ];
+}
+
+fn begin_match(options: Res<GameOptions>, bridge: Res<BevyBridge>, mut versus: ResMut<VersusState>) {
+    *versus = VersusState::from_options(&options, &bridge.game_id);
+}
+
+/// Replaces the usual game over screen (see [`crate::ui::game_over`]).
+fn spawn_winner_screen(mut commands: Commands, versus: Res<VersusState>, bot: Res<Bot>) {
+    if !versus.active {
+        return;
+    }
+    let (headline, color) = match versus.winner {
+        Some(winner) => (format!("{} WINS!", bot.label(winner)), winner.color()),
+        None => ("DRAW".to_string(), Color::WHITE),
+    };
+    commands.spawn((
+        Text::new(format!(
+            "{}\n{} {}  -  {} {}",
+            headline,
+            bot.label(VersusPlayer::One),
+            versus.score(VersusPlayer::One),
+            bot.label(VersusPlayer::Two),
+            versus.score(VersusPlayer::Two)
+        )),
+        TextFont {
+            font_size: 48.0,
+            ..default()
+        },
+        TextColor(color),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        WinnerScreen,
+    ));
+}
+
+fn despawn_winner_screen(mut commands: Commands, q: Query<Entity, With<WinnerScreen>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,246 @@
+//! Playtime limits and "take a break" nudges.
+//!
+//! The shell passes on how much of today's playtime the player has left —
+//! from the server's `playtime` socket events or `POST /presence/heartbeat`
+//! — with `set_playtime_remaining(seconds)`, or `null` when no limit
+//! applies.  The engine counts it down while a game runs, so it only needs
+//! refreshing now and then:
+//!
+//! * With [`WARN_SECS`] left, a nudge says how many minutes remain.
+//! * At zero a "Time for a break!" banner stays up until the engine goes
+//!   back to the menu.  The game isn't stopped, since the server's limit
+//!   is a soft one, but the game over screen leaves out Retry: the next
+//!   game has to be started by the shell, which the server turns away.
+//!
+//! `playtime_remaining()` reads the engine's count back.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::AppState;
+
+/// Seconds left when the player is told time is running out.
+pub const WARN_SECS: f32 = 300.0;
+const NUDGE_SECS: f32 = 4.0;
+/// How long the nudge takes to fade in, and out.
+const NUDGE_FADE_SECS: f32 = 0.4;
+const NUDGE_COLOR: Color = Color::srgb(1.0, 0.84, 0.2);
+const BREAK_COLOR: Color = Color::srgb(0.55, 0.85, 1.0);
+
+/// Value from the shell not yet applied; the inner `None` clears the limit.
+static PENDING: Mutex<Option<Option<i32>>> = Mutex::new(None);
+/// Whole seconds left as last counted, or -1 without a limit.
+static REMAINING: AtomicI32 = AtomicI32::new(-1);
+
+// ---------------------------------------------------------------------------
+// Plugin
+// ---------------------------------------------------------------------------
+
+pub struct WellbeingPlugin;
+
+impl Plugin for WellbeingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playtime>()
+            .add_systems(
+                Update,
+                (
+                    apply_update,
+                    (count_down, nudge).chain().run_if(in_state(AppState::Playing)),
+                    fade_nudges,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(AppState::Menu), despawn_banners);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Resources / components
+// ---------------------------------------------------------------------------
+
+/// Today's remaining playtime.
+#[derive(Resource, Debug, Default)]
+pub struct Playtime {
+    /// Seconds left; `None` without a limit.
+    pub remaining: Option<f32>,
+    /// The running-out nudge has been shown for this budget.
+    warned: bool,
+}
+
+impl Playtime {
+    pub fn exhausted(&self) -> bool {
+        self.remaining.is_some_and(|secs| secs <= 0.0)
+    }
+}
+
+#[derive(Component)]
+struct Nudge {
+    age: f32,
+}
+
+#[derive(Component)]
+struct BreakBanner;
+
+// ---------------------------------------------------------------------------
+// wasm-bindgen exports
+// ---------------------------------------------------------------------------
+
+/// Sets today's remaining playtime in seconds; `null` or a negative value
+/// means no limit.
+#[wasm_bindgen]
+pub fn set_playtime_remaining(seconds: Option<i32>) {
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(seconds.filter(|s| *s >= 0));
+    }
+}
+
+/// Seconds of playtime left as the engine counts them, or `undefined`
+/// without a limit.
+#[wasm_bindgen]
+pub fn playtime_remaining() -> Option<i32> {
+    let secs = REMAINING.load(Ordering::Relaxed);
+    (secs >= 0).then_some(secs)
+}
+
+// ---------------------------------------------------------------------------
+// Systems
+// ---------------------------------------------------------------------------
+
+fn apply_update(mut playtime: ResMut<Playtime>) {
+    let Some(update) = PENDING.lock().ok().and_then(|mut p| p.take()) else {
+        return;
+    };
+    playtime.remaining = update.map(|s| s as f32);
+    // A fresh budget (e.g. after midnight) earns a fresh warning
+    if !playtime.remaining.is_some_and(|secs| secs <= WARN_SECS) {
+        playtime.warned = false;
+    }
+    publish(&playtime);
+}
+
+fn count_down(time: Res<Time<Real>>, mut playtime: ResMut<Playtime>) {
+    let Some(secs) = playtime.remaining else { return };
+    playtime.remaining = Some((secs - time.delta_secs()).max(0.0));
+    publish(&playtime);
+}
+
+fn publish(playtime: &Playtime) {
+    let secs = playtime.remaining.map_or(-1, |s| s.ceil() as i32);
+    REMAINING.store(secs, Ordering::Relaxed);
+}
+
+fn nudge(mut commands: Commands, mut playtime: ResMut<Playtime>, banners: Query<(), With<BreakBanner>>) {
+    let Some(secs) = playtime.remaining else { return };
+    if secs <= 0.0 {
+        if banners.is_empty() {
+            spawn_break_banner(&mut commands);
+        }
+        return;
+    }
+    if secs <= WARN_SECS && !playtime.warned {
+        playtime.warned = true;
+        let minutes = (secs / 60.0).ceil() as i32;
+        let text = if minutes == 1 {
+            "1 minute of play left today".to_string()
+        } else {
+            format!("{} minutes of play left today", minutes)
+        };
+        commands.spawn((
+            Text::new(text),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(NUDGE_COLOR.with_alpha(0.0)),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(16.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(95),
+            Nudge { age: 0.0 },
+        ));
+    }
+}
+
+fn fade_nudges(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut q: Query<(Entity, &mut Nudge, &mut TextColor)>,
+) {
+    for (entity, mut nudge, mut color) in &mut q {
+        nudge.age += time.delta_secs();
+        if nudge.age >= NUDGE_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let fade = (nudge.age / NUDGE_FADE_SECS)
+            .min((NUDGE_SECS - nudge.age) / NUDGE_FADE_SECS)
+            .min(1.0);
+        color.0 = NUDGE_COLOR.with_alpha(fade);
+    }
+}
+
+fn despawn_banners(mut commands: Commands, q: Query<Entity, Or<(With<Nudge>, With<BreakBanner>)>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+fn spawn_break_banner(commands: &mut Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            // Above the game over screen
+            GlobalZIndex(95),
+            BreakBanner,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::axes(Val::Px(24.0), Val::Px(10.0)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(12.0)),
+                BackgroundColor(Color::srgba(0.05, 0.08, 0.2, 0.85)),
+            ))
+            .with_children(|panel| {
+                panel.spawn((
+                    Text::new("Time for a break!"),
+                    TextFont {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                    TextColor(BREAK_COLOR),
+                ));
+                panel.spawn((
+                    Text::new("You've used today's playtime. Finish this game and rest your eyes."),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.85, 0.88, 0.95)),
+                ));
+            });
+        });
+}
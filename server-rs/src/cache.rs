@@ -2,6 +2,7 @@ use redis::aio::ConnectionManager;
 use redis::{AsyncCommands, Client};
 
 use crate::config::Config;
+use crate::services::metrics;
 
 #[derive(Clone)]
 pub struct Cache {
@@ -28,12 +29,14 @@ impl Cache {
 
     pub async fn get(&self, key: &str) -> Option<String> {
         let mut conn = self.conn.clone();
-        redis::cmd("GET")
+        let value = redis::cmd("GET")
             .arg(self.key(key))
             .query_async::<_, Option<String>>(&mut conn)
             .await
             .ok()
-            .flatten()
+            .flatten();
+        metrics::cache_lookup(key, value.is_some());
+        value
     }
 
     pub async fn get_json<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
@@ -58,6 +61,22 @@ impl Cache {
         }
     }
 
+    /// Sets `key` only if it doesn't exist. Returns whether it was set.
+    pub async fn set_nx(&self, key: &str, value: &str, ttl_secs: u64) -> bool {
+        let mut conn = self.conn.clone();
+        redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async::<_, Option<String>>(&mut conn)
+            .await
+            .ok()
+            .flatten()
+            .is_some()
+    }
+
     pub async fn del(&self, key: &str) {
         let mut conn = self.conn.clone();
         let _: Result<(), _> = conn.del(self.key(key)).await;
@@ -69,6 +88,15 @@ impl Cache {
         let _: Result<(), _> = conn.zadd(&k, member, score).await;
     }
 
+    pub async fn zadd_multiple(&self, key: &str, entries: &[(f64, String)]) {
+        if entries.is_empty() {
+            return;
+        }
+        let mut conn = self.conn.clone();
+        let k = self.key(key);
+        let _: Result<(), _> = conn.zadd_multiple(&k, entries).await;
+    }
+
     pub async fn zrevrange_withscores(
         &self,
         key: &str,
@@ -94,6 +122,13 @@ impl Cache {
         conn.zscore(&k, member).await.ok()
     }
 
+    /// Members scoring strictly above `score`.
+    pub async fn zcount_above(&self, key: &str, score: f64) -> u64 {
+        let mut conn = self.conn.clone();
+        let k = self.key(key);
+        conn.zcount(&k, format!("({}", score), "+inf").await.unwrap_or(0)
+    }
+
     pub async fn zcard(&self, key: &str) -> u64 {
         let mut conn = self.conn.clone();
         let k = self.key(key);
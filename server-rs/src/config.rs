@@ -12,6 +12,17 @@ pub struct Config {
     pub leaderboard: LeaderboardConfig,
     pub tenant: TenantConfig,
     pub stripe: StripeConfig,
+    pub season: SeasonConfig,
+    pub rating: RatingConfig,
+    pub oauth: OAuthConfig,
+    pub presence: PresenceConfig,
+    pub storage: StorageConfig,
+    pub gdpr: GdprConfig,
+    pub jobs: JobsConfig,
+    pub tracing: TracingConfig,
+    pub email: EmailConfig,
+    pub age_gate: AgeGateConfig,
+    pub xapi: XapiConfig,
 }
 
 #[derive(Clone, Debug)]
@@ -34,18 +45,47 @@ pub struct RedisConfig {
     pub key_prefix: String,
 }
 
+/// Tokens are signed with the active key and carry its id in the `kid`
+/// header; any configured key verifies. To rotate, add a new key to
+/// `JWT_KEYS`, make it `JWT_ACTIVE_KID` and drop the old one once its
+/// tokens have expired (`refresh_expiry_secs`).
 #[derive(Clone, Debug)]
 pub struct JwtConfig {
-    pub secret: String,
+    /// `JWT_SECRET` under the id `default`, then `JWT_KEYS`.
+    pub keys: Vec<JwtKey>,
+    pub active_kid: String,
     pub access_expiry_secs: i64,
     pub refresh_expiry_secs: i64,
 }
 
+#[derive(Clone, Debug)]
+pub struct JwtKey {
+    pub kid: String,
+    pub secret: String,
+}
+
+/// Key id of `JWT_SECRET`; also used for tokens issued without a `kid`.
+pub const DEFAULT_JWT_KID: &str = "default";
+
+impl JwtConfig {
+    pub fn key(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|k| k.kid == kid)
+    }
+
+    pub fn active_key(&self) -> &JwtKey {
+        self.key(&self.active_kid)
+            .or_else(|| self.keys.first())
+            .expect("at least one JWT key")
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RateLimitConfig {
     pub window_secs: u64,
     pub max_requests: u32,
     pub score_submit_max: u32,
+    pub telemetry_max: u32,
+    pub chat_max: u32,
 }
 
 #[derive(Clone, Debug)]
@@ -72,6 +112,165 @@ pub struct StripeConfig {
     pub price_starter: String,
     pub price_pro: String,
     pub price_enterprise: String,
+    /// Bill plans per seat: the subscription's quantity follows the
+    /// organisation's member count.
+    pub per_seat: bool,
+    /// Metered price added to new subscriptions, billed by the
+    /// organisation's game sessions. Empty leaves it out.
+    pub price_metered: String,
+    /// How Stripe bills a seat change mid-period: `create_prorations`,
+    /// `always_invoice` or `none`.
+    pub proration_behavior: String,
+    /// Cron expression for reconciling seats and reporting metered usage.
+    pub billing_sync_schedule: String,
+    /// Days an organisation keeps its plan after a failed payment, while
+    /// Stripe retries it.
+    pub dunning_grace_days: i32,
+}
+
+#[derive(Clone, Debug)]
+pub struct SeasonConfig {
+    /// Cron expression for closing expired seasons.
+    pub rotation_schedule: String,
+    pub default_length_days: i64,
+    pub max_rewarded_rank: i64,
+}
+
+/// Glicko-2 defaults for ranked matches; `game_rating_configs` overrides
+/// them per game.
+#[derive(Clone, Debug)]
+pub struct RatingConfig {
+    pub initial_rating: i32,
+    pub initial_deviation: f64,
+    pub initial_volatility: f64,
+    /// Constrains how fast volatility changes; 0.3-1.2 is sensible.
+    pub tau: f64,
+    /// Length of a rating period. Each idle period widens a player's
+    /// deviation by their volatility, up to `initial_deviation`.
+    pub rating_period_days: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PresenceConfig {
+    /// How long a player stays online after their last socket closes, so a
+    /// page reload or brief network drop doesn't flap their status.
+    pub offline_grace_secs: u64,
+    pub ping_interval_secs: u64,
+}
+
+/// S3-compatible object storage (AWS S3, Cloudflare R2, MinIO). Disabled
+/// when `bucket` is empty.
+#[derive(Clone, Debug)]
+pub struct StorageConfig {
+    /// Base URL of the S3 API, e.g. `https://s3.eu-west-1.amazonaws.com`.
+    /// Objects are addressed path-style: `{endpoint}/{bucket}/{key}`.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Lifetime of presigned download URLs.
+    pub download_url_ttl_secs: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct GdprConfig {
+    /// Cron expression for processing exports and due deletions.
+    pub worker_schedule: String,
+    /// How long a finished data export stays downloadable.
+    pub export_expiry_days: i64,
+    /// Days between a deletion request and its execution.
+    pub deletion_grace_days: i32,
+}
+
+/// The background job worker (`services::jobs`).
+#[derive(Clone, Debug)]
+pub struct JobsConfig {
+    pub poll_interval_ms: u64,
+    /// Jobs one instance runs at once.
+    pub concurrency: usize,
+    /// Cron expression for rebuilding leaderboard caches from the database.
+    pub leaderboard_warm_schedule: String,
+    /// Cron expression for materializing decayed leaderboards.
+    pub leaderboard_decay_schedule: String,
+    /// Cron expression for scanning recent scores for cheating.
+    pub anomaly_scan_schedule: String,
+}
+
+/// OTLP trace export (`services::otel`).
+#[derive(Clone, Debug)]
+pub struct TracingConfig {
+    /// Collector base URL; spans go to `{endpoint}/v1/traces`. Empty turns
+    /// export off.
+    pub otlp_endpoint: String,
+    pub service_name: String,
+    /// Fraction of new traces that are recorded. Requests continuing a
+    /// caller's trace follow the caller's decision.
+    pub sample_ratio: f64,
+}
+
+/// Transactional email (`services::mailer`), sent as JSON to an HTTP
+/// email API. Emails are only logged when `api_url` is empty.
+#[derive(Clone, Debug)]
+pub struct EmailConfig {
+    pub api_url: String,
+    /// Sent as a bearer token.
+    pub api_key: String,
+    pub from: String,
+}
+
+/// Parental consent for young players (`middleware::age_gate`).
+#[derive(Clone, Debug)]
+pub struct AgeGateConfig {
+    /// Page the consent email links to, with `?token=...` appended. It
+    /// shows the request and posts the parent's answer back to the API.
+    pub consent_url: String,
+    /// How long a consent link stays valid.
+    pub consent_ttl_days: i64,
+}
+
+/// xAPI statement export to tenants' Learning Record Stores
+/// (`services::xapi`).
+#[derive(Clone, Debug)]
+pub struct XapiConfig {
+    /// Base of the IRIs naming games, achievements and player accounts in
+    /// statements. Changing it makes the LRS see new activities.
+    pub activity_base_url: String,
+    /// Cron expression for delivering pending statements.
+    pub export_schedule: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct OAuthConfig {
+    /// Public base URL of this API, used to build provider callback URLs
+    /// (`{callback_base_url}/api/v1/auth/oauth/:provider/callback`).
+    pub callback_base_url: String,
+    pub state_ttl_secs: u64,
+    pub google: OAuthProviderConfig,
+    pub apple: OAuthProviderConfig,
+    pub microsoft: OAuthProviderConfig,
+}
+
+/// A provider is enabled when its `client_id` is set.
+#[derive(Clone, Debug)]
+pub struct OAuthProviderConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub scope: String,
+}
+
+impl OAuthConfig {
+    pub fn provider(&self, name: &str) -> Option<&OAuthProviderConfig> {
+        let provider = match name {
+            "google" => &self.google,
+            "apple" => &self.apple,
+            "microsoft" => &self.microsoft,
+            _ => return None,
+        };
+        Some(provider).filter(|p| !p.client_id.is_empty())
+    }
 }
 
 fn env_or(key: &str, default: &str) -> String {
@@ -111,7 +310,11 @@ impl Config {
                 key_prefix: "stem:".to_string(),
             },
             jwt: JwtConfig {
-                secret: env_or("JWT_SECRET", "change-me-to-a-secure-random-string"),
+                keys: parse_jwt_keys(
+                    &env_or("JWT_SECRET", "change-me-to-a-secure-random-string"),
+                    &env_or("JWT_KEYS", ""),
+                ),
+                active_kid: env_or("JWT_ACTIVE_KID", DEFAULT_JWT_KID),
                 access_expiry_secs: parse_duration_to_secs(&env_or("JWT_ACCESS_EXPIRY", "1h")),
                 refresh_expiry_secs: parse_duration_to_secs(&env_or("JWT_REFRESH_EXPIRY", "30d")),
             },
@@ -119,6 +322,8 @@ impl Config {
                 window_secs: 60,
                 max_requests: env_or_parse("RATE_LIMIT_MAX", 100),
                 score_submit_max: env_or_parse("RATE_LIMIT_SCORE", 30),
+                telemetry_max: env_or_parse("RATE_LIMIT_TELEMETRY", 30),
+                chat_max: env_or_parse("RATE_LIMIT_CHAT", 20),
             },
             leaderboard: LeaderboardConfig {
                 shard_count: env_or_parse("LEADERBOARD_SHARDS", 8),
@@ -142,6 +347,101 @@ impl Config {
                 price_starter: env_or("STRIPE_PRICE_STARTER", ""),
                 price_pro: env_or("STRIPE_PRICE_PRO", ""),
                 price_enterprise: env_or("STRIPE_PRICE_ENTERPRISE", ""),
+                per_seat: env_or_parse("STRIPE_PER_SEAT", false),
+                price_metered: env_or("STRIPE_PRICE_METERED", ""),
+                proration_behavior: env_or("STRIPE_PRORATION_BEHAVIOR", "create_prorations"),
+                billing_sync_schedule: env_or("STRIPE_BILLING_SYNC_SCHEDULE", "0 * * * *"),
+                dunning_grace_days: env_or_parse("STRIPE_DUNNING_GRACE_DAYS", 7),
+            },
+            season: SeasonConfig {
+                rotation_schedule: env_or("SEASON_ROTATION_SCHEDULE", "*/5 * * * *"),
+                default_length_days: env_or_parse("SEASON_LENGTH_DAYS", 28),
+                max_rewarded_rank: env_or_parse("SEASON_MAX_REWARDED_RANK", 100),
+            },
+            rating: RatingConfig {
+                initial_rating: env_or_parse("RATING_INITIAL", 1000),
+                initial_deviation: env_or_parse("RATING_INITIAL_DEVIATION", 350.0),
+                initial_volatility: env_or_parse("RATING_INITIAL_VOLATILITY", 0.06),
+                tau: env_or_parse("RATING_TAU", 0.5),
+                rating_period_days: env_or_parse("RATING_PERIOD_DAYS", 7.0),
+            },
+            presence: PresenceConfig {
+                offline_grace_secs: env_or_parse("PRESENCE_OFFLINE_GRACE_SEC", 15),
+                ping_interval_secs: env_or_parse("PRESENCE_PING_INTERVAL_SEC", 30),
+            },
+            storage: StorageConfig {
+                endpoint: env_or("STORAGE_ENDPOINT", "https://s3.us-east-1.amazonaws.com"),
+                bucket: env_or("STORAGE_BUCKET", ""),
+                region: env_or("STORAGE_REGION", "us-east-1"),
+                access_key_id: env_or("STORAGE_ACCESS_KEY_ID", ""),
+                secret_access_key: env_or("STORAGE_SECRET_ACCESS_KEY", ""),
+                download_url_ttl_secs: env_or_parse("STORAGE_DOWNLOAD_URL_TTL_SEC", 900),
+            },
+            gdpr: GdprConfig {
+                worker_schedule: env_or("GDPR_WORKER_SCHEDULE", "*/30 * * * * *"),
+                export_expiry_days: env_or_parse("GDPR_EXPORT_EXPIRY_DAYS", 7),
+                deletion_grace_days: env_or_parse("GDPR_DELETION_GRACE_DAYS", 30),
+            },
+            jobs: JobsConfig {
+                poll_interval_ms: env_or_parse("JOBS_POLL_INTERVAL_MS", 1000),
+                concurrency: env_or_parse("JOBS_CONCURRENCY", 4),
+                leaderboard_warm_schedule: env_or("LEADERBOARD_WARM_SCHEDULE", "*/30 * * * *"),
+                leaderboard_decay_schedule: env_or("LEADERBOARD_DECAY_SCHEDULE", "30 3 * * *"),
+                anomaly_scan_schedule: env_or("ANOMALY_SCAN_SCHEDULE", "*/15 * * * *"),
+            },
+            tracing: TracingConfig {
+                otlp_endpoint: env_or("OTEL_EXPORTER_OTLP_ENDPOINT", ""),
+                service_name: env_or("OTEL_SERVICE_NAME", "stem-adventures-api"),
+                sample_ratio: env_or_parse("OTEL_TRACES_SAMPLER_ARG", 1.0),
+            },
+            email: EmailConfig {
+                api_url: env_or("EMAIL_API_URL", ""),
+                api_key: env_or("EMAIL_API_KEY", ""),
+                from: env_or("EMAIL_FROM", "STEM School Adventures <no-reply@minigames.cool>"),
+            },
+            age_gate: AgeGateConfig {
+                consent_url: env_or("PARENTAL_CONSENT_URL", "http://localhost:8080/parental-consent"),
+                consent_ttl_days: env_or_parse("PARENTAL_CONSENT_TTL_DAYS", 14),
+            },
+            xapi: XapiConfig {
+                activity_base_url: env_or("XAPI_ACTIVITY_BASE_URL", "https://minigames.cool"),
+                export_schedule: env_or("XAPI_EXPORT_SCHEDULE", "* * * * *"),
+            },
+            oauth: {
+                let ms_tenant = env_or("MICROSOFT_OAUTH_TENANT", "common");
+                OAuthConfig {
+                    callback_base_url: env_or("OAUTH_CALLBACK_BASE_URL", "http://localhost:3000"),
+                    state_ttl_secs: env_or_parse("OAUTH_STATE_TTL_SEC", 600),
+                    google: OAuthProviderConfig {
+                        client_id: env_or("GOOGLE_CLIENT_ID", ""),
+                        client_secret: env_or("GOOGLE_CLIENT_SECRET", ""),
+                        authorize_url: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
+                        token_url: "https://oauth2.googleapis.com/token".to_string(),
+                        scope: "openid email profile".to_string(),
+                    },
+                    // Apple's client secret is a short-lived ES256 JWT signed with
+                    // the team's key; it is generated out of band and rotated via env.
+                    apple: OAuthProviderConfig {
+                        client_id: env_or("APPLE_CLIENT_ID", ""),
+                        client_secret: env_or("APPLE_CLIENT_SECRET", ""),
+                        authorize_url: "https://appleid.apple.com/auth/authorize".to_string(),
+                        token_url: "https://appleid.apple.com/auth/token".to_string(),
+                        scope: "name email".to_string(),
+                    },
+                    microsoft: OAuthProviderConfig {
+                        client_id: env_or("MICROSOFT_CLIENT_ID", ""),
+                        client_secret: env_or("MICROSOFT_CLIENT_SECRET", ""),
+                        authorize_url: format!(
+                            "https://login.microsoftonline.com/{}/oauth2/v2.0/authorize",
+                            ms_tenant
+                        ),
+                        token_url: format!(
+                            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                            ms_tenant
+                        ),
+                        scope: "openid email profile".to_string(),
+                    },
+                }
             },
         }
     }
@@ -179,6 +479,19 @@ impl Config {
     }
 }
 
+/// `JWT_KEYS` is a comma-separated list of `kid:secret` pairs; malformed
+/// entries are skipped.
+fn parse_jwt_keys(default_secret: &str, extra: &str) -> Vec<JwtKey> {
+    let mut keys = vec![JwtKey { kid: DEFAULT_JWT_KID.to_string(), secret: default_secret.to_string() }];
+    for pair in extra.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if let Some((kid, secret)) = pair.split_once(':').filter(|(k, s)| !k.trim().is_empty() && !s.is_empty()) {
+            keys.retain(|k| k.kid != kid.trim());
+            keys.push(JwtKey { kid: kid.trim().to_string(), secret: secret.to_string() });
+        }
+    }
+    keys
+}
+
 fn parse_duration_to_secs(s: &str) -> i64 {
     let s = s.trim();
     if s.is_empty() {
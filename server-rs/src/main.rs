@@ -1,11 +1,12 @@
 use axum::{
     middleware as axum_mw,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::compression::CompressionLayer;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 mod cache;
 mod config;
@@ -19,6 +20,8 @@ mod services;
 use cache::Cache;
 use config::Config;
 use middleware::rate_limit::RateLimiter;
+use services::object_storage::ObjectStorage;
+use services::realtime::RealtimeGateway;
 use services::room_manager::RoomManager;
 use services::stripe_service::StripeClient;
 
@@ -28,9 +31,13 @@ pub struct AppState {
     pub cache: Cache,
     pub config: Arc<Config>,
     pub stripe: Option<StripeClient>,
+    pub storage: Option<ObjectStorage>,
     pub rate_limiter: RateLimiter,
     pub score_rate_limiter: RateLimiter,
+    pub telemetry_rate_limiter: RateLimiter,
+    pub chat_rate_limiter: RateLimiter,
     pub room_manager: RoomManager,
+    pub realtime: RealtimeGateway,
 }
 
 fn build_router(state: AppState) -> Router {
@@ -44,31 +51,79 @@ fn build_router(state: AppState) -> Router {
         .route("/guest", post(routes::auth::guest))
         .route("/register", post(routes::auth::register))
         .route("/login", post(routes::auth::login))
-        .route("/refresh", post(routes::auth::refresh));
+        .route("/refresh", post(routes::auth::refresh))
+        .route("/logout", post(routes::auth::logout))
+        .route(
+            "/logout-all",
+            post(routes::auth::logout_all).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::auth::authenticate,
+            )),
+        )
+        .route(
+            "/oauth/:provider/start",
+            get(routes::auth::oauth_start).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::auth::optional_auth,
+            )),
+        )
+        .route(
+            "/oauth/:provider/callback",
+            get(routes::auth::oauth_callback).post(routes::auth::oauth_callback_form),
+        )
+        .route(
+            "/parental-consent/:token",
+            get(routes::auth::get_parental_consent).post(routes::auth::answer_parental_consent),
+        );
+
+    let session_routes = Router::new()
+        .route(
+            "/",
+            get(routes::auth::list_sessions).delete(routes::auth::revoke_sessions),
+        )
+        .route("/:id", axum::routing::delete(routes::auth::revoke_session))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
 
     // --- Webhook routes (raw body, no auth) ---
     let webhook_routes = Router::new()
         .route("/stripe", post(routes::webhooks::stripe_webhook));
 
     // --- Authenticated routes ---
+    // Tenant backends can also submit scores on behalf of players with an
+    // API key.
     let score_routes = Router::new()
-        .route(
-            "/:gameId",
-            post(routes::scores::submit_score)
-                .layer(axum_mw::from_fn_with_state(
-                    state.clone(),
-                    middleware::rate_limit::score_rate_limit,
-                ))
-                .get(routes::scores::get_progress),
-        )
+        .route("/:gameId", get(routes::scores::get_progress))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
-        ));
+        ))
+        .merge(
+            Router::new()
+                .route(
+                    "/:gameId",
+                    post(routes::scores::submit_score)
+                        .layer(axum_mw::from_fn_with_state(
+                            state.clone(),
+                            middleware::idempotency::idempotency,
+                        ))
+                        .layer(axum_mw::from_fn_with_state(
+                            state.clone(),
+                            middleware::rate_limit::score_rate_limit,
+                        )),
+                )
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::api_key::require_scores_write,
+                )),
+        );
 
     let leaderboard_routes = Router::new()
         .route("/:gameId", get(routes::leaderboards::get_game_leaderboard))
         .route("/:gameId/me", get(routes::leaderboards::get_my_rank))
+        .route("/:gameId/metrics", get(routes::leaderboards::get_game_metrics))
         .route(
             "/:gameId/around",
             get(routes::leaderboards::get_around_me),
@@ -82,19 +137,35 @@ fn build_router(state: AppState) -> Router {
             "/:gameId/ranked",
             get(routes::leaderboards::get_ranked_leaderboard),
         )
+        .route(
+            "/:gameId/decayed",
+            get(routes::leaderboards::get_decayed_leaderboard),
+        )
+        .route(
+            "/:gameId/distribution",
+            get(routes::leaderboards::get_score_distribution),
+        )
         .route("/seasons", get(routes::leaderboards::get_seasons))
         .route(
             "/seasons/current",
             get(routes::leaderboards::get_current_season),
         )
-        .route(
-            "/submit-match",
-            post(routes::leaderboards::submit_match),
-        )
+        .layer(axum_mw::from_fn(middleware::etag::etag))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::optional_auth,
-        ));
+        ))
+        .merge(
+            Router::new()
+                .route(
+                    "/submit-match",
+                    post(routes::leaderboards::submit_match),
+                )
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::api_key::require_matches_write,
+                )),
+        );
 
     let player_routes = Router::new()
         .route(
@@ -103,11 +174,73 @@ fn build_router(state: AppState) -> Router {
         )
         .route("/progress", get(routes::player::get_all_progress))
         .route("/achievements", get(routes::player::get_achievements))
+        .route(
+            "/privacy",
+            get(routes::player::get_privacy).put(routes::player::update_privacy),
+        )
+        .route(
+            "/consent",
+            get(routes::player::get_consent).post(routes::player::resend_consent),
+        )
+        .route(
+            "/playtime",
+            get(routes::player::get_playtime).put(routes::player::update_playtime),
+        )
+        .route(
+            "/handoff",
+            get(routes::player::get_handoff).post(routes::player::create_handoff),
+        )
+        .route("/handoff/claim", post(routes::player::claim_handoff))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
         ));
 
+    let replay_routes = Router::new()
+        .route(
+            "/:gameId",
+            post(routes::replays::upload_replay).get(routes::replays::get_my_replay),
+        )
+        .route("/:gameId/friends", get(routes::replays::list_friend_replays))
+        .route(
+            "/:gameId/player/:playerId",
+            get(routes::replays::get_player_replay),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let daily_routes = Router::new()
+        .route(
+            "/:gameId",
+            post(routes::daily::submit_daily_run)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::rate_limit::score_rate_limit,
+                ))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::auth::authenticate,
+                ))
+                .get(routes::daily::get_daily),
+        )
+        .route(
+            "/:gameId/start",
+            post(routes::daily::start_daily_run).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::auth::authenticate,
+            )),
+        )
+        .route(
+            "/:gameId/leaderboard",
+            get(routes::daily::get_daily_leaderboard),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::optional_auth,
+        ));
+
     let sync_routes = Router::new()
         .route("/batch", post(routes::sync::batch_sync))
         .layer(axum_mw::from_fn_with_state(
@@ -124,6 +257,10 @@ fn build_router(state: AppState) -> Router {
         .route(
             "/:gameId",
             post(routes::comments::post_comment)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                ))
                 .layer(axum_mw::from_fn_with_state(
                     state.clone(),
                     middleware::auth::authenticate,
@@ -132,12 +269,28 @@ fn build_router(state: AppState) -> Router {
         .route(
             "/:commentId",
             put(routes::comments::edit_comment)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                ))
                 .delete(routes::comments::delete_comment)
                 .layer(axum_mw::from_fn_with_state(
                     state.clone(),
                     middleware::auth::authenticate,
                 )),
         )
+        .route(
+            "/:commentId/react",
+            post(routes::comments::react_comment)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                ))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::auth::authenticate,
+                )),
+        )
         .route(
             "/:commentId/report",
             post(routes::comments::report_comment)
@@ -150,12 +303,24 @@ fn build_router(state: AppState) -> Router {
         .route(
             "/:gameId/reviews",
             post(routes::comments::post_review)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                ))
                 .delete(routes::comments::delete_review)
                 .layer(axum_mw::from_fn_with_state(
                     state.clone(),
                     middleware::auth::authenticate,
                 )),
         )
+        .route(
+            "/:gameId/reviews/:reviewId/vote",
+            post(routes::comments::vote_review)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::auth::authenticate,
+                )),
+        )
         .route(
             "/reviews/:reviewId/report",
             post(routes::comments::report_review)
@@ -163,15 +328,61 @@ fn build_router(state: AppState) -> Router {
                     state.clone(),
                     middleware::auth::authenticate,
                 )),
-        );
+        )
+        .layer(axum_mw::from_fn(middleware::features::require_comments));
 
     let billing_routes = Router::new()
-        .route("/subscribe", post(routes::billing::subscribe))
-        .route("/portal", post(routes::billing::portal))
+        .route(
+            "/subscribe",
+            post(routes::billing::subscribe)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::idempotency::idempotency,
+                ))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                )),
+        )
+        .route(
+            "/portal",
+            post(routes::billing::portal).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
         .route("/plans", get(routes::billing::plans))
         .route("/status", get(routes::billing::subscription_status))
-        .route("/cancel", post(routes::billing::cancel))
-        .route("/resume", post(routes::billing::resume))
+        .route(
+            "/cancel",
+            post(routes::billing::cancel).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route(
+            "/resume",
+            post(routes::billing::resume).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route("/invoices", get(routes::billing::invoices))
+        .route("/seats", get(routes::billing::seats))
+        .route(
+            "/seats",
+            post(routes::billing::sync_seats).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route(
+            "/redeem",
+            post(routes::billing::redeem).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
         .route("/usage", get(routes::billing::usage))
         .route("/entitlements", get(routes::billing::entitlements))
         .route("/upgrade-badge", get(routes::billing::upgrade_badge))
@@ -187,6 +398,79 @@ fn build_router(state: AppState) -> Router {
         )
         .route("/:id", get(routes::organisations::get_org))
         .route("/:id/members", post(routes::organisations::add_member))
+        .route("/:id/playtime", put(routes::organisations::set_playtime_limit))
+        .route(
+            "/:id/classrooms",
+            post(routes::organisations::create_classroom).get(routes::organisations::list_classrooms),
+        )
+        .route(
+            "/:id/classrooms/:classroomId",
+            delete(routes::organisations::delete_classroom),
+        )
+        .route(
+            "/:id/classrooms/:classroomId/students",
+            post(routes::organisations::add_students),
+        )
+        .route(
+            "/:id/classrooms/:classroomId/students/:playerId",
+            delete(routes::organisations::remove_student),
+        )
+        .route(
+            "/:id/classrooms/:classroomId/assignments",
+            post(routes::organisations::create_assignment).get(routes::organisations::list_assignments),
+        )
+        .route(
+            "/:id/classrooms/:classroomId/assignments/:assignmentId",
+            delete(routes::organisations::delete_assignment),
+        )
+        .route(
+            "/:id/classrooms/:classroomId/report",
+            get(routes::organisations::classroom_report),
+        )
+        .route("/:id/reports", post(routes::organisations::request_report))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ))
+        .merge(
+            Router::new()
+                .route(
+                    "/:id/classrooms/:classroomId/roster",
+                    put(routes::organisations::sync_roster),
+                )
+                .route("/:id/roster-imports", post(routes::organisations::import_roster))
+                .route(
+                    "/:id/roster-imports/:importId",
+                    get(routes::organisations::get_roster_import),
+                )
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::api_key::require_roster_write,
+                )),
+        );
+
+    let report_routes = Router::new()
+        .route("/:jobId", get(routes::organisations::get_report))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    // Players appeal their own warnings and bans; moderators work the queue.
+    let appeal_routes = Router::new()
+        .route(
+            "/",
+            get(routes::appeals::my_appeals).post(routes::appeals::create_appeal),
+        )
+        .merge(
+            Router::new()
+                .route("/queue", get(routes::appeals::appeal_queue))
+                .route("/:id/resolve", post(routes::appeals::resolve_appeal))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::admin::require_moderator,
+                )),
+        )
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
@@ -218,6 +502,14 @@ fn build_router(state: AppState) -> Router {
             "/reviews/:id/remove",
             post(routes::admin::remove_review),
         )
+        .route(
+            "/chat/:id/approve",
+            post(routes::admin::approve_chat_message),
+        )
+        .route(
+            "/chat/:id/remove",
+            post(routes::admin::remove_chat_message),
+        )
         .route(
             "/reports/:id/resolve",
             post(routes::admin::resolve_report),
@@ -228,10 +520,14 @@ fn build_router(state: AppState) -> Router {
         )
         .route("/users", get(routes::admin::search_users))
         .route("/users/:id", get(routes::admin::get_user_detail))
+        .route("/consents", get(routes::admin::list_consents))
         .route("/users/:id/warn", post(routes::admin::warn_user))
         .route("/users/:id/ban", post(routes::admin::ban_user))
         .route("/users/:id/role", post(routes::admin::set_role))
         .route("/log", get(routes::admin::moderation_log))
+        .route("/analytics/funnel", get(routes::telemetry::funnel))
+        .route("/analytics/deaths", get(routes::telemetry::death_heatmap))
+        .route("/analytics/sessions", get(routes::telemetry::session_stats))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::admin::require_moderator,
@@ -250,7 +546,24 @@ fn build_router(state: AppState) -> Router {
             "/:id",
             put(routes::games::update_game).delete(routes::games::delete_game),
         )
+        .route(
+            "/manifest",
+            put(routes::games::sync_manifest).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::admin::require_super_admin,
+            )),
+        )
         .route("/:id/toggle", post(routes::games::toggle_game))
+        .route(
+            "/:id/rating-config",
+            get(routes::games::get_rating_config).put(routes::games::set_rating_config),
+        )
+        .route(
+            "/:id/leaderboard-config",
+            get(routes::games::get_leaderboard_config)
+                .put(routes::games::set_leaderboard_config)
+                .delete(routes::games::delete_leaderboard_config),
+        )
         .route(
             "/categories/all",
             get(routes::games::admin_list_categories),
@@ -264,6 +577,16 @@ fn build_router(state: AppState) -> Router {
             "/:id/categories",
             put(routes::games::assign_categories),
         )
+        .route(
+            "/:id/leaderboard-cache",
+            delete(routes::leaderboards::invalidate_cache),
+        )
+        .route("/:id/scores", get(routes::scores::admin_list_scores))
+        .route("/:id/scores/void", post(routes::scores::admin_void_scores))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::admin::require_admin,
@@ -274,15 +597,52 @@ fn build_router(state: AppState) -> Router {
         ));
 
     let multiplayer_routes = Router::new()
-        .route("/rooms", get(routes::multiplayer::list_rooms).post(routes::multiplayer::create_room))
+        .route(
+            "/rooms",
+            post(routes::multiplayer::create_room)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::playtime::require_playtime,
+                ))
+                .get(routes::multiplayer::list_rooms),
+        )
         .route("/rooms/:id", get(routes::multiplayer::get_room))
-        .route("/rooms/:id/join", post(routes::multiplayer::join_room))
-        .route("/matchmake", post(routes::multiplayer::matchmake))
+        .route(
+            "/rooms/:id/join",
+            post(routes::multiplayer::join_room).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::playtime::require_playtime,
+            )),
+        )
+        .route(
+            "/rooms/:id/rematch",
+            post(routes::multiplayer::rematch).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::playtime::require_playtime,
+            )),
+        )
+        .route(
+            "/rooms/:id/turns",
+            get(routes::multiplayer::list_turns).post(routes::multiplayer::submit_turn),
+        )
+        .route("/rooms/:id/forfeit", post(routes::multiplayer::forfeit_turns))
+        .route("/matches", get(routes::multiplayer::match_history))
+        .route(
+            "/matchmake",
+            post(routes::multiplayer::matchmake)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::playtime::require_playtime,
+                ))
+                .get(routes::multiplayer::matchmake_status)
+                .delete(routes::multiplayer::cancel_matchmake),
+        )
         .route("/me", get(routes::multiplayer::my_room))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
-        ));
+        ))
+        .layer(axum_mw::from_fn(middleware::features::require_multiplayer));
 
     let friend_routes = Router::new()
         .route("/", get(routes::friends::list_friends))
@@ -302,34 +662,167 @@ fn build_router(state: AppState) -> Router {
             middleware::auth::authenticate,
         ));
 
+    let chat_routes = Router::new()
+        .route("/dm", get(routes::chat::list_conversations))
+        .route(
+            "/dm/:playerId",
+            get(routes::chat::dm_history).post(routes::chat::send_dm),
+        )
+        .route("/dm/:playerId/read", post(routes::chat::mark_read))
+        .route(
+            "/rooms/:roomId",
+            get(routes::chat::room_history).post(routes::chat::send_room_message),
+        )
+        .route("/messages/:id/report", post(routes::chat::report_message))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::age_gate::require_consent,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
     let economy_routes = Router::new()
         .route("/wallet", get(routes::economy::get_wallet))
         .route("/transactions", get(routes::economy::get_transactions))
         .route("/earn", post(routes::economy::earn))
-        .route("/store", get(routes::economy::list_store))
-        .route("/store/purchase", post(routes::economy::purchase))
+        .route(
+            "/store",
+            get(routes::economy::list_store).layer(axum_mw::from_fn(middleware::etag::etag)),
+        )
+        .route(
+            "/store/featured",
+            get(routes::economy::featured_store).layer(axum_mw::from_fn(middleware::etag::etag)),
+        )
+        .route(
+            "/store/purchase",
+            post(routes::economy::purchase)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::idempotency::idempotency,
+                ))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                )),
+        )
         .route("/inventory", get(routes::economy::inventory))
-        .route("/battlepass", get(routes::economy::get_battlepass))
+        .route(
+            "/crates/open",
+            post(routes::economy::open_crate).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route("/gifts", post(routes::economy::gift_item))
+        .route(
+            "/trades",
+            get(routes::economy::list_trades).post(routes::economy::create_trade),
+        )
+        .route("/trades/:id/counter", post(routes::economy::counter_trade))
+        .route("/trades/:id/accept", post(routes::economy::accept_trade))
+        .route("/trades/:id/cancel", post(routes::economy::cancel_trade))
+        .route(
+            "/battlepass",
+            get(routes::economy::get_battlepass).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::etag::versioned_etag,
+            )),
+        )
         .route(
             "/battlepass/progress",
             get(routes::economy::get_battlepass_progress),
         )
         .route(
             "/battlepass/purchase",
-            post(routes::economy::purchase_battlepass),
+            post(routes::economy::purchase_battlepass)
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::idempotency::idempotency,
+                ))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::age_gate::require_consent,
+                )),
+        )
+        .route(
+            "/battlepass/claim",
+            post(routes::economy::claim_tier).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route(
+            "/redeem",
+            post(routes::promo_codes::redeem).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route("/recipes", get(routes::crafting::list_recipes))
+        .route("/recipes/discover", post(routes::crafting::discover_recipes))
+        .route(
+            "/craft",
+            post(routes::crafting::craft).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
         )
-        .route("/battlepass/claim", post(routes::economy::claim_tier))
-        .route("/battlepass/xp", post(routes::economy::award_xp))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
-        ));
+        ))
+        .merge(
+            Router::new()
+                .route("/battlepass/xp", post(routes::economy::award_xp))
+                .layer(axum_mw::from_fn_with_state(
+                    state.clone(),
+                    middleware::api_key::require_battlepass_write,
+                )),
+        )
+        .layer(axum_mw::from_fn(middleware::features::require_economy));
 
     let presence_routes = Router::new()
         .route("/me", get(routes::presence::get_my_presence))
         .route("/update", post(routes::presence::update_presence))
         .route("/heartbeat", post(routes::presence::heartbeat))
         .route("/:id", get(routes::presence::get_player_presence))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ))
+        // Authenticates via `?token=` since browsers can't send headers on upgrade
+        .route("/ws", get(routes::presence::presence_socket));
+
+    let telemetry_routes = Router::new()
+        .route(
+            "/",
+            post(routes::telemetry::ingest_events).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::rate_limit::telemetry_rate_limit,
+            )),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    // Reports can come from before sign-in.
+    let crash_report_routes = Router::new()
+        .route("/", post(routes::crash_reports::submit_report))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit::crash_report_rate_limit,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::optional_auth,
+        ));
+
+    let config_routes = Router::new()
+        .route("/games/:gameId", get(routes::remote_config::get_game_config))
+        .route("/features", get(routes::remote_config::get_features))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::auth::authenticate,
@@ -349,37 +842,388 @@ fn build_router(state: AppState) -> Router {
             middleware::auth::authenticate,
         ));
 
+    let auction_routes = Router::new()
+        .route("/", get(routes::auctions::list_auctions))
+        .route(
+            "/",
+            post(routes::auctions::create_auction).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route("/mine", get(routes::auctions::my_auctions))
+        .route("/:id", get(routes::auctions::get_auction))
+        .route(
+            "/:id/bids",
+            post(routes::auctions::place_bid).layer(axum_mw::from_fn_with_state(
+                state.clone(),
+                middleware::idempotency::idempotency,
+            )),
+        )
+        .route("/:id/cancel", post(routes::auctions::cancel_auction))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ))
+        .layer(axum_mw::from_fn(middleware::features::require_economy));
+
+    // Odds disclosure is public so it can be shown before sign-in
+    let crate_odds_routes = Router::new()
+        .route("/", get(routes::economy::list_crate_odds))
+        .route("/:crateId/odds", get(routes::economy::get_crate_odds))
+        .layer(axum_mw::from_fn(middleware::features::require_economy));
+
+    let admin_filter_routes = Router::new()
+        .route(
+            "/terms",
+            get(routes::admin::list_filter_terms).post(routes::admin::add_filter_term),
+        )
+        .route("/terms/:id", delete(routes::admin::delete_filter_term))
+        .route("/test", post(routes::admin::test_filter))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_translation_routes = Router::new()
+        .route("/", get(routes::admin::list_translations))
+        .route(
+            "/:locale",
+            put(routes::admin::set_translations).delete(routes::admin::delete_translation),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_crate_routes = Router::new()
+        .route("/:crateId/drops", put(routes::economy::set_crate_drops))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_store_routes = Router::new()
+        .route("/items/:itemId/bundle", put(routes::economy::set_bundle_contents))
+        .route("/items/:itemId/offer", put(routes::economy::set_item_offer))
+        .route("/items/:itemId", delete(routes::economy::delete_store_item))
+        .route(
+            "/sales",
+            get(routes::economy::list_sales).post(routes::economy::create_sale),
+        )
+        .route("/sales/:id", delete(routes::economy::delete_sale))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_promo_routes = Router::new()
+        .route(
+            "/",
+            get(routes::promo_codes::list_codes).post(routes::promo_codes::create_codes),
+        )
+        .route("/:id", delete(routes::promo_codes::deactivate_code))
+        .route("/:id/redemptions", get(routes::promo_codes::list_redemptions))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_webhook_routes = Router::new()
+        .route("/stripe/events", get(routes::webhooks::list_stripe_events))
+        .route(
+            "/stripe/events/:id/replay",
+            post(routes::webhooks::replay_stripe_event),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_super_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_xapi_routes = Router::new()
+        .route(
+            "/lrs",
+            get(routes::xapi::get_lrs)
+                .put(routes::xapi::set_lrs)
+                .delete(routes::xapi::delete_lrs),
+        )
+        .route("/outbox", get(routes::xapi::list_outbox))
+        .route("/outbox/replay", post(routes::xapi::replay_outbox))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_api_key_routes = Router::new()
+        .route(
+            "/",
+            get(routes::api_keys::list_keys).post(routes::api_keys::create_key),
+        )
+        .route("/:id", delete(routes::api_keys::revoke_key))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_battlepass_routes = Router::new()
+        .route(
+            "/",
+            get(routes::economy::admin_list_battlepasses).post(routes::economy::create_battlepass),
+        )
+        .route(
+            "/:id",
+            get(routes::economy::admin_get_battlepass)
+                .put(routes::economy::update_battlepass)
+                .delete(routes::economy::delete_battlepass),
+        )
+        .route("/:id/tiers", put(routes::economy::set_battlepass_tiers))
+        .route("/xp-sources", get(routes::economy::list_xp_sources))
+        .route(
+            "/xp-sources/:source",
+            put(routes::economy::set_xp_source).delete(routes::economy::reset_xp_source),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_entity_routes = Router::new()
+        .route("/history", get(routes::entities::history))
+        .route("/deleted", get(routes::entities::list_deleted))
+        .route("/:entityType/:entityId/restore", post(routes::entities::restore))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_crash_routes = Router::new()
+        .route("/", get(routes::crash_reports::list_groups))
+        .route(
+            "/:id",
+            get(routes::crash_reports::get_group).put(routes::crash_reports::update_group),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_anticheat_routes = Router::new()
+        .route("/", get(routes::anticheat::list_flags))
+        .route("/:id", get(routes::anticheat::get_flag))
+        .route("/:id/void", post(routes::anticheat::void_scores))
+        .route("/:id/dismiss", post(routes::anticheat::dismiss_flag))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_recipe_routes = Router::new()
+        .route("/", get(routes::crafting::admin_list_recipes))
+        .route(
+            "/:recipeId",
+            put(routes::crafting::upsert_recipe).delete(routes::crafting::deactivate_recipe),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_feature_routes = Router::new()
+        .route("/", get(routes::admin::list_features))
+        .route(
+            "/:flag",
+            put(routes::admin::set_feature).delete(routes::admin::delete_feature),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::bump_on_write,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
+    let admin_config_routes = Router::new()
+        .route(
+            "/:gameId",
+            get(routes::remote_config::admin_list_profiles)
+                .post(routes::remote_config::admin_create_profile),
+        )
+        .route(
+            "/:gameId/:version",
+            put(routes::remote_config::admin_update_profile)
+                .delete(routes::remote_config::admin_delete_profile),
+        )
+        .route(
+            "/:gameId/:version/publish",
+            post(routes::remote_config::admin_publish_profile),
+        )
+        .route(
+            "/:gameId/:version/rollout",
+            post(routes::remote_config::admin_set_rollout),
+        )
+        .route(
+            "/:gameId/:version/archive",
+            post(routes::remote_config::admin_archive_profile),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::admin::require_admin,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ));
+
     // Public game endpoints
     let public_game_routes = Router::new()
         .route("/custom", get(routes::games::list_custom_games))
-        .route("/categories", get(routes::games::list_categories));
+        .route("/engine", get(routes::games::list_engine_games))
+        .route("/categories", get(routes::games::list_categories))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::etag::versioned_etag,
+        ));
 
     // --- Compose full API ---
     let api = Router::new()
         .nest("/auth", auth_routes)
+        .nest("/auth/sessions", session_routes)
         .nest("/scores", score_routes)
         .nest("/leaderboards", leaderboard_routes)
         .nest("/player", player_routes)
+        .nest("/replays", replay_routes)
+        .nest("/daily", daily_routes)
         .nest("/sync", sync_routes)
         .nest("/comments", comment_routes)
         .nest("/billing", billing_routes)
         .nest("/organisations", org_routes)
+        .nest("/reports", report_routes)
         .nest("/webhooks", webhook_routes)
+        .nest("/moderation/appeals", appeal_routes)
         .nest("/admin", admin_routes)
         .nest("/admin/games", admin_game_routes)
+        .nest("/admin/crates", admin_crate_routes)
+        .nest("/admin/store", admin_store_routes)
+        .nest("/admin/recipes", admin_recipe_routes)
+        .nest("/admin/promo-codes", admin_promo_routes)
+        .nest("/admin/webhooks", admin_webhook_routes)
+        .nest("/admin/xapi", admin_xapi_routes)
+        .nest("/admin/api-keys", admin_api_key_routes)
+        .nest("/admin/battle-passes", admin_battlepass_routes)
+        .nest("/admin/entities", admin_entity_routes)
+        .nest("/admin/crash-reports", admin_crash_routes)
+        .nest("/admin/anticheat", admin_anticheat_routes)
+        .nest("/admin/filter", admin_filter_routes)
+        .nest("/admin/translations", admin_translation_routes)
+        .nest("/admin/features", admin_feature_routes)
+        .nest("/admin/game-configs", admin_config_routes)
         .nest("/multiplayer", multiplayer_routes)
         .nest("/friends", friend_routes)
+        .nest("/chat", chat_routes)
         .nest("/economy", economy_routes)
+        .nest("/auctions", auction_routes)
+        .nest("/economy/crates", crate_odds_routes)
         .nest("/presence", presence_routes)
         .nest("/compliance", compliance_routes)
-        .nest("/games", public_game_routes);
+        .nest("/telemetry", telemetry_routes)
+        .nest("/crash-reports", crash_report_routes)
+        .nest("/config", config_routes)
+        .nest("/games", public_game_routes)
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::features::resolve_features,
+        ));
 
     Router::new()
         .nest("/api/v1", api)
         .route("/health", get(routes::health::health))
         .route("/metrics", get(routes::health::metrics))
         // Global middleware
-        .layer(axum_mw::from_fn(middleware::localization::locale_detector))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            middleware::localization::locale_detector,
+        ))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             middleware::rate_limit::rate_limit,
@@ -388,6 +1232,8 @@ fn build_router(state: AppState) -> Router {
             state.clone(),
             middleware::tenant::resolve_tenant,
         ))
+        .layer(axum_mw::from_fn(middleware::metrics::track_requests))
+        .layer(axum_mw::from_fn(middleware::trace::trace_requests))
         .layer(CompressionLayer::new())
         .layer(cors)
         .with_state(state)
@@ -398,36 +1244,65 @@ async fn main() -> shuttle_axum::ShuttleAxum {
     let _ = dotenvy::dotenv();
     let config = Config::from_env();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
+    tracing_subscriber::registry()
+        .with(services::otel::layer(&config.tracing))
+        .with(
+            tracing_subscriber::fmt::layer().json().with_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| "info".into()),
+            ),
         )
-        .json()
         .init();
 
     let pool = db::create_pool(&config).await;
     let cache = Cache::new(&config).await;
     let stripe = StripeClient::new(&config.stripe);
+    let storage = ObjectStorage::new(&config.storage);
     let rate_limiter =
         RateLimiter::new(config.rate_limit.max_requests, config.rate_limit.window_secs);
     let score_rate_limiter = RateLimiter::new(
         config.rate_limit.score_submit_max,
         config.rate_limit.window_secs,
     );
+    let telemetry_rate_limiter = RateLimiter::new(
+        config.rate_limit.telemetry_max,
+        config.rate_limit.window_secs,
+    );
+    let chat_rate_limiter =
+        RateLimiter::new(config.rate_limit.chat_max, config.rate_limit.window_secs);
+
+    if config.jwt.key(&config.jwt.active_kid).is_none() {
+        tracing::warn!(
+            "JWT_ACTIVE_KID '{}' is not in JWT_KEYS; signing with '{}'",
+            config.jwt.active_kid,
+            config.jwt.active_key().kid
+        );
+    }
 
     tracing::info!("STEM Adventures API initialized (Rust/Axum on Shuttle)");
 
+    middleware::idempotency::spawn_purge_worker(pool.clone());
+    middleware::auth::spawn_revocation_purge_worker(pool.clone());
+
+    let room_manager = RoomManager::new(pool.clone());
     let state = AppState {
         db: pool,
         cache,
         config: Arc::new(config),
         stripe,
+        storage,
         rate_limiter,
         score_rate_limiter,
-        room_manager: RoomManager::new(),
+        telemetry_rate_limiter,
+        chat_rate_limiter,
+        room_manager,
+        realtime: RealtimeGateway::new(),
     };
 
+    services::jobs::spawn_worker(state.clone());
+    services::room_manager::spawn_matchmaker(state.clone());
+    services::room_manager::spawn_turn_expiry(state.clone());
+
     let router = build_router(state);
     Ok(router.into())
 }
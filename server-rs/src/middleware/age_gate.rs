@@ -0,0 +1,71 @@
+//! Age gate for players who need parental consent.
+//!
+//! Registration asks for a birth year. Anyone who might still be under
+//! [`CONSENT_AGE`] starts with `consent_status` `pending` and a consent
+//! email goes to their parent ([`parental_consent`]); until the parent
+//! grants it, [`require_consent`] turns away chat, posting comments and
+//! reviews, and purchases. A denial keeps them restricted. Accounts that
+//! gave no birth year, including guests, are `not_required`.
+//!
+//! Routes opt in by layering [`require_consent`] inside `authenticate`:
+//!
+//! ```ignore
+//! post(handler)
+//!     .layer(from_fn_with_state(state.clone(), middleware::age_gate::require_consent))
+//!     .layer(from_fn_with_state(state.clone(), middleware::auth::authenticate))
+//! ```
+//!
+//! Paths that don't go through the router, like chat over the presence
+//! socket, call [`check`] themselves.
+//!
+//! [`parental_consent`]: crate::services::parental_consent
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::AppState;
+
+/// Players younger than this need a parent's consent.
+pub const CONSENT_AGE: i32 = 13;
+
+/// Whether a player born in `birth_year` may be under [`CONSENT_AGE`]. With
+/// only the year to go on, a birthday later this year is assumed.
+pub fn needs_consent(birth_year: i32, current_year: i32) -> bool {
+    current_year - birth_year - 1 < CONSENT_AGE
+}
+
+/// Rejects players whose parental consent is pending or was denied.
+pub async fn check(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    let status: Option<String> =
+        sqlx::query_scalar("SELECT consent_status FROM players WHERE id = $1 AND tenant_id = $2")
+            .bind(player_id)
+            .bind(tenant_id)
+            .fetch_optional(db)
+            .await?;
+    match status.as_deref() {
+        Some("pending") => Err(AppError::Forbidden(
+            "This needs a parent's permission first. Ask them to check their email.".into(),
+        )),
+        Some("denied") => Err(AppError::Forbidden("A parent hasn't allowed this on your account".into())),
+        _ => Ok(()),
+    }
+}
+
+/// Requires the authenticated player to be free of the age gate.
+pub async fn require_consent(State(state): State<AppState>, req: Request, next: Next) -> Result<Response, AppError> {
+    let player = req
+        .extensions()
+        .get::<AuthPlayer>()
+        .cloned()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".into()))?;
+
+    check(&state.db, &player.tenant_id, player.id).await?;
+    Ok(next.run(req).await)
+}
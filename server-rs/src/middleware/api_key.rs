@@ -0,0 +1,173 @@
+//! Service-to-service authentication with tenant API keys (`api_keys`).
+//!
+//! A tenant's backend sends `Authorization: Bearer sk_...` in place of a
+//! player's access token, along with the tenant's usual API key header.
+//! Routes open to backends are wrapped in the `require_*` middleware for
+//! the scope they need instead of [`auth::authenticate`]: a request with an
+//! API key must hold that scope, and any other request is authenticated as
+//! a player as usual. With an `X-Player-Id` header the key acts on behalf of
+//! that player of its tenant, who handlers then see as the [`AuthPlayer`].
+//!
+//! Only a SHA-256 hash of each key is stored.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::{self, AuthPlayer};
+use crate::middleware::tenant::TenantId;
+use crate::AppState;
+
+pub const KEY_PREFIX: &str = "sk_";
+pub const PLAYER_HEADER: &str = "x-player-id";
+
+pub const SCORES_WRITE: &str = "scores:write";
+pub const ROSTER_WRITE: &str = "roster:write";
+pub const BATTLEPASS_WRITE: &str = "battlepass:write";
+pub const MATCHES_WRITE: &str = "matches:write";
+
+/// Scopes a key can be given, and what each allows.
+pub const SCOPES: &[(&str, &str)] = &[
+    (SCORES_WRITE, "Submit scores on behalf of players"),
+    (ROSTER_WRITE, "Sync classroom rosters"),
+    (BATTLEPASS_WRITE, "Award battle pass XP on behalf of players"),
+    (MATCHES_WRITE, "Submit multiplayer match results"),
+];
+
+/// The API key a request was authenticated with.
+#[derive(Debug, Clone)]
+pub struct ApiKeyAuth {
+    pub tenant_id: String,
+    pub scopes: Vec<String>,
+}
+
+/// A new key: the prefix and 32 random bytes, hex encoded.
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+pub fn hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The live key with this value, noting that it was used.
+async fn verify(db: &sqlx::PgPool, key: &str) -> AppResult<ApiKeyAuth> {
+    let row: Option<(String, Vec<String>)> = sqlx::query_as(
+        r#"UPDATE api_keys SET last_used_at = NOW()
+        WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+        RETURNING tenant_id, scopes"#,
+    )
+    .bind(hash(key))
+    .fetch_optional(db)
+    .await?;
+    let (tenant_id, scopes) = row.ok_or_else(|| AppError::Unauthorized("Invalid API key".into()))?;
+    Ok(ApiKeyAuth { tenant_id, scopes })
+}
+
+/// Middleware: a player, or an API key with `scores:write` acting on
+/// behalf of one.
+pub async fn require_scores_write(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    require(SCORES_WRITE, true, true, state, req, next).await
+}
+
+/// Middleware: a player, or an API key with `roster:write`.
+pub async fn require_roster_write(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    require(ROSTER_WRITE, false, true, state, req, next).await
+}
+
+/// Middleware: an API key with `battlepass:write` acting on behalf of a
+/// player. Players themselves are refused.
+pub async fn require_battlepass_write(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    require(BATTLEPASS_WRITE, true, false, state, req, next).await
+}
+
+/// Middleware: a player, or an API key with `matches:write`.
+pub async fn require_matches_write(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    require(MATCHES_WRITE, false, true, state, req, next).await
+}
+
+async fn require(
+    scope: &str,
+    needs_player: bool,
+    allow_players: bool,
+    state: AppState,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(key) = auth::extract_bearer(&req).filter(|t| t.starts_with(KEY_PREFIX)) else {
+        if !allow_players {
+            return Err(AppError::Forbidden(format!("This endpoint needs an API key with the {} scope", scope)));
+        }
+        return auth::authenticate(State(state), req, next).await;
+    };
+    let api_key = verify(&state.db, &key).await?;
+
+    // Features and the like were resolved for the request's tenant, so the
+    // key must belong to it rather than switch it.
+    let tenant_id = req.extensions().get::<TenantId>().map(|t| t.0.as_str()).unwrap_or("");
+    if api_key.tenant_id != tenant_id {
+        return Err(AppError::Unauthorized("API key belongs to another tenant".into()));
+    }
+    if !api_key.scopes.iter().any(|s| s == scope) {
+        return Err(AppError::Forbidden(format!("API key lacks the {} scope", scope)));
+    }
+
+    let player_id = req
+        .headers()
+        .get(PLAYER_HEADER)
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| Uuid::parse_str(v).ok())
+                .ok_or_else(|| AppError::BadRequest("Invalid X-Player-Id".into()))
+        })
+        .transpose()?;
+    match player_id {
+        Some(player_id) => {
+            let exists: Option<i32> = sqlx::query_scalar("SELECT 1 FROM players WHERE id = $1 AND tenant_id = $2")
+                .bind(player_id)
+                .bind(&api_key.tenant_id)
+                .fetch_optional(&state.db)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::NotFound("Player not found".into()));
+            }
+            req.extensions_mut().insert(AuthPlayer {
+                id: player_id,
+                tenant_id: api_key.tenant_id.clone(),
+                role: None,
+            });
+        }
+        None if needs_player => {
+            return Err(AppError::BadRequest("X-Player-Id header required".into()));
+        }
+        None => {}
+    }
+
+    req.extensions_mut().insert(api_key);
+    Ok(next.run(req).await)
+}
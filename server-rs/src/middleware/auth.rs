@@ -5,11 +5,16 @@ use axum::{
     response::Response,
 };
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, decode_header, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::cache::Cache;
+use crate::config::{JwtConfig, DEFAULT_JWT_KID};
 use crate::error::{AppError, AppResult};
+use crate::services::sessions;
 use crate::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,8 +27,19 @@ pub struct Claims {
     pub token_type: Option<String>, // "access" or "refresh"
     pub exp: i64,
     pub iat: i64,
+    /// Token id, for revoking a single refresh token. Absent on tokens
+    /// issued before ids were added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Sign-in session the token belongs to (see [`sessions`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
+/// Session of the access token on the request, when it has one.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentSession(pub Uuid);
+
 #[derive(Debug, Clone)]
 pub struct AuthPlayer {
     pub id: Uuid,
@@ -31,57 +47,168 @@ pub struct AuthPlayer {
     pub role: Option<String>,
 }
 
+/// An access/refresh pair for a session, signed with the active key.
+/// Sign-ins go through [`sessions::start`] rather than calling this.
 pub fn generate_tokens(
     player_id: Uuid,
     tenant_id: &str,
     role: Option<&str>,
-    secret: &str,
-    access_expiry_secs: i64,
-    refresh_expiry_secs: i64,
+    session_id: Uuid,
+    jwt: &JwtConfig,
 ) -> AppResult<(String, String)> {
     let now = Utc::now().timestamp();
+    let key = jwt.active_key();
+    let header = Header {
+        kid: Some(key.kid.clone()),
+        ..Header::default()
+    };
+    let encoding_key = EncodingKey::from_secret(key.secret.as_bytes());
 
     let access_claims = Claims {
         sub: player_id.to_string(),
         tenant_id: tenant_id.to_string(),
         role: role.map(String::from),
         token_type: Some("access".to_string()),
-        exp: now + access_expiry_secs,
+        exp: now + jwt.access_expiry_secs,
         iat: now,
+        jti: Some(Uuid::new_v4().to_string()),
+        sid: Some(session_id.to_string()),
     };
-    let access_token = encode(
-        &Header::default(),
-        &access_claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )?;
+    let access_token = encode(&header, &access_claims, &encoding_key)?;
 
     let refresh_claims = Claims {
         sub: player_id.to_string(),
         tenant_id: tenant_id.to_string(),
         role: role.map(String::from),
         token_type: Some("refresh".to_string()),
-        exp: now + refresh_expiry_secs,
+        exp: now + jwt.refresh_expiry_secs,
         iat: now,
+        jti: Some(Uuid::new_v4().to_string()),
+        sid: Some(session_id.to_string()),
     };
-    let refresh_token = encode(
-        &Header::default(),
-        &refresh_claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )?;
+    let refresh_token = encode(&header, &refresh_claims, &encoding_key)?;
 
     Ok((access_token, refresh_token))
 }
 
-pub fn verify_token(token: &str, secret: &str) -> AppResult<Claims> {
+/// Verifies a token against the key named by its `kid`, or the default
+/// key for tokens without one.
+pub fn verify_token(token: &str, jwt: &JwtConfig) -> AppResult<Claims> {
+    let kid = decode_header(token)?.kid;
+    let key = jwt
+        .key(kid.as_deref().unwrap_or(DEFAULT_JWT_KID))
+        .ok_or_else(|| AppError::Unauthorized("Unknown signing key".into()))?;
     let data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(secret.as_bytes()),
+        &DecodingKey::from_secret(key.secret.as_bytes()),
         &Validation::default(),
     )?;
     Ok(data.claims)
 }
 
-fn extract_bearer(req: &Request) -> Option<String> {
+fn revocation_key(tenant_id: &str, player_id: &str) -> String {
+    format!("tokens_revoked:{}:{}", tenant_id, player_id)
+}
+
+/// Invalidates every token issued to the player so far, e.g. on "log out
+/// everywhere" or a ban. The cache marker, checked on every request, lives
+/// as long as the longest-lived token (`ttl_secs`); refresh also checks
+/// `players.tokens_revoked_at`, which survives a cache flush.
+pub async fn revoke_tokens(
+    db: &PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+    player_id: Uuid,
+    ttl_secs: u64,
+) -> AppResult<()> {
+    let now = Utc::now().timestamp().to_string();
+    cache
+        .set(&revocation_key(tenant_id, &player_id.to_string()), &now, ttl_secs)
+        .await;
+    sqlx::query("UPDATE players SET tokens_revoked_at = NOW() WHERE id = $1 AND tenant_id = $2")
+        .bind(player_id)
+        .bind(tenant_id)
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "UPDATE auth_sessions SET revoked_at = NOW() WHERE player_id = $1 AND tenant_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Whether the token was issued before the player's tokens were revoked,
+/// or its session was revoked.
+pub async fn is_revoked(cache: &Cache, claims: &Claims) -> bool {
+    if let Some(sid) = &claims.sid {
+        if sessions::is_revoked(cache, sid).await {
+            return true;
+        }
+    }
+    cache
+        .get(&revocation_key(&claims.tenant_id, &claims.sub))
+        .await
+        .and_then(|at| at.parse::<i64>().ok())
+        .is_some_and(|revoked_at| claims.iat <= revoked_at)
+}
+
+/// Whether a refresh token was issued before the player's tokens were
+/// revoked, per the database.
+pub async fn is_refresh_revoked(db: &PgPool, claims: &Claims, player_id: Uuid) -> AppResult<bool> {
+    let revoked: Option<bool> = sqlx::query_scalar(
+        r#"SELECT tokens_revoked_at >= to_timestamp($3)
+        FROM players WHERE id = $1 AND tenant_id = $2"#,
+    )
+    .bind(player_id)
+    .bind(&claims.tenant_id)
+    .bind(claims.iat as f64)
+    .fetch_optional(db)
+    .await?
+    .flatten();
+    Ok(revoked.unwrap_or(false))
+}
+
+/// Adds a refresh token to the revocation list until it would have
+/// expired anyway. Returns false if it was already on it, i.e. the token
+/// was used or revoked before. Tokens without an id can't be listed and
+/// always return true.
+pub async fn revoke_refresh_token(db: &PgPool, claims: &Claims, player_id: Uuid) -> AppResult<bool> {
+    let Some(jti) = &claims.jti else { return Ok(true) };
+    let inserted = sqlx::query(
+        r#"INSERT INTO revoked_tokens (jti, tenant_id, player_id, expires_at)
+        VALUES ($1, $2, $3, to_timestamp($4))
+        ON CONFLICT (jti) DO NOTHING"#,
+    )
+    .bind(jti)
+    .bind(&claims.tenant_id)
+    .bind(player_id)
+    .bind(claims.exp as f64)
+    .execute(db)
+    .await?;
+    Ok(inserted.rows_affected() > 0)
+}
+
+/// Drops revocation list entries for tokens that have expired anyway.
+pub fn spawn_revocation_purge_worker(db: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < NOW()").execute(&db).await {
+                Ok(r) if r.rows_affected() > 0 => {
+                    tracing::info!("Purged {} expired token revocation(s)", r.rows_affected())
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Token revocation purge failed: {}", e),
+            }
+        }
+    });
+}
+
+pub fn extract_bearer(req: &Request) -> Option<String> {
     req.headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
@@ -98,17 +225,23 @@ pub async fn authenticate(
     let token = extract_bearer(&req)
         .ok_or_else(|| AppError::Unauthorized("No token provided".into()))?;
 
-    let claims = verify_token(&token, &state.config.jwt.secret)?;
+    let claims = verify_token(&token, &state.config.jwt)?;
 
     if claims.token_type.as_deref() == Some("refresh") {
         return Err(AppError::Unauthorized(
             "Access token required".into(),
         ));
     }
+    if is_revoked(&state.cache, &claims).await {
+        return Err(AppError::Unauthorized("Token revoked".into()));
+    }
 
     let player_id = Uuid::parse_str(&claims.sub)
         .map_err(|_| AppError::Unauthorized("Invalid token subject".into()))?;
 
+    if let Some(sid) = claims.sid.as_deref().and_then(|s| Uuid::parse_str(s).ok()) {
+        req.extensions_mut().insert(CurrentSession(sid));
+    }
     req.extensions_mut().insert(AuthPlayer {
         id: player_id,
         tenant_id: claims.tenant_id,
@@ -125,8 +258,10 @@ pub async fn optional_auth(
     next: Next,
 ) -> Result<Response, AppError> {
     if let Some(token) = extract_bearer(&req) {
-        if let Ok(claims) = verify_token(&token, &state.config.jwt.secret) {
-            if claims.token_type.as_deref() != Some("refresh") {
+        if let Ok(claims) = verify_token(&token, &state.config.jwt) {
+            if claims.token_type.as_deref() != Some("refresh")
+                && !is_revoked(&state.cache, &claims).await
+            {
                 if let Ok(player_id) = Uuid::parse_str(&claims.sub) {
                     req.extensions_mut().insert(AuthPlayer {
                         id: player_id,
@@ -0,0 +1,183 @@
+//! Conditional GETs for endpoints that clients poll.
+//!
+//! [`etag`] hashes a `200` response body into a weak `ETag` and answers a
+//! matching `If-None-Match` with `304 Not Modified`. That saves the
+//! transfer, but the handler still runs.
+//!
+//! [`versioned_etag`] is for responses that depend only on the tenant, the
+//! URL and the locale, and that change through admin writes. It remembers
+//! the ETag it served under the tenant's cache version, so an up-to-date
+//! client gets its 304 without the handler running. Admin routers are
+//! wrapped in [`bump_on_write`], which moves the tenant to a new cache
+//! version after every successful write. Remembered ETags also expire
+//! after [`MEMO_TTL_SECS`], which bounds how long a change made outside the
+//! API can go unnoticed.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::localization::LocaleInfo;
+use crate::middleware::tenant::TenantId;
+use crate::AppState;
+
+/// Bodies are buffered to hash them.
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+const VERSION_TTL_SECS: u64 = 300;
+pub const MEMO_TTL_SECS: u64 = 600;
+
+fn version_key(tenant_id: &str) -> String {
+    format!("cache_version:{}", tenant_id)
+}
+
+/// The tenant's current cache version.
+pub async fn cache_version(state: &AppState, tenant_id: &str) -> AppResult<i64> {
+    let key = version_key(tenant_id);
+    if let Some(version) = state.cache.get(&key).await.and_then(|v| v.parse().ok()) {
+        return Ok(version);
+    }
+
+    let version: i64 = sqlx::query_scalar("SELECT version FROM tenant_cache_versions WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_optional(&state.db)
+        .await?
+        .unwrap_or(0);
+    state.cache.set(&key, &version.to_string(), VERSION_TTL_SECS).await;
+    Ok(version)
+}
+
+/// Moves the tenant to a new cache version, invalidating every remembered
+/// ETag.
+pub async fn bump_version(state: &AppState, tenant_id: &str) -> AppResult<i64> {
+    let version: i64 = sqlx::query_scalar(
+        r#"INSERT INTO tenant_cache_versions (tenant_id, version) VALUES ($1, 1)
+        ON CONFLICT (tenant_id) DO UPDATE SET version = tenant_cache_versions.version + 1, updated_at = NOW()
+        RETURNING version"#,
+    )
+    .bind(tenant_id)
+    .fetch_one(&state.db)
+    .await?;
+    state.cache.del(&version_key(tenant_id)).await;
+    Ok(version)
+}
+
+/// Whether an `If-None-Match` header matches `tag`, using the weak
+/// comparison RFC 9110 prescribes for it.
+fn matches(if_none_match: Option<&HeaderValue>, tag: &str) -> bool {
+    let Some(value) = if_none_match.and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let opaque = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    value.split(',').any(|t| t.trim() == "*" || opaque(t) == opaque(tag))
+}
+
+fn not_modified(tag: &HeaderValue) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, tag.clone());
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    *response.headers_mut() = headers;
+    response
+}
+
+/// Tags a `200` response with the hash of its body, or turns it into a
+/// 304 if the client already has it.
+async fn tag_response(response: Response, if_none_match: Option<&HeaderValue>) -> Result<Response, AppError> {
+    if response.status() != StatusCode::OK {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| AppError::Internal("Response too large to tag".into()))?;
+    let digest = Sha256::digest(&bytes);
+    let tag = HeaderValue::from_str(&format!("W/\"{}\"", hex::encode(&digest[..16])))
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    if matches(if_none_match, tag.to_str().unwrap_or_default()) {
+        return Ok(not_modified(&tag));
+    }
+    parts.headers.insert(header::ETAG, tag);
+    parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+/// Middleware: ETags and 304s for GET responses.
+pub async fn etag(req: Request, next: Next) -> Result<Response, AppError> {
+    if req.method() != Method::GET {
+        return Ok(next.run(req).await);
+    }
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(req).await;
+    tag_response(response, if_none_match.as_ref()).await
+}
+
+/// Middleware: like [`etag`], but answers a client holding the current
+/// ETag without running the handler. Only for responses that are the same
+/// for every player of the tenant.
+pub async fn versioned_etag(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if req.method() != Method::GET {
+        return Ok(next.run(req).await);
+    }
+    let tenant_id = req
+        .extensions()
+        .get::<TenantId>()
+        .map(|t| t.0.clone())
+        .unwrap_or_else(|| state.config.tenant.default_tenant_id.clone());
+    let locale = req.extensions().get::<LocaleInfo>().map(|l| l.locale.clone()).unwrap_or_default();
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let version = cache_version(&state, &tenant_id).await?;
+    let mut hasher = Sha256::new();
+    hasher.update(req.uri().to_string());
+    hasher.update(b"\n");
+    hasher.update(&locale);
+    let memo_key = format!("etag:{}:{}:{}", tenant_id, version, hex::encode(&hasher.finalize()[..16]));
+
+    if let Some(tag) = state.cache.get(&memo_key).await {
+        if matches(if_none_match.as_ref(), &tag) {
+            if let Ok(tag) = HeaderValue::from_str(&tag) {
+                return Ok(not_modified(&tag));
+            }
+        }
+    }
+
+    let response = tag_response(next.run(req).await, if_none_match.as_ref()).await?;
+    if let Some(tag) = response.headers().get(header::ETAG).and_then(|t| t.to_str().ok()) {
+        state.cache.set(&memo_key, tag, MEMO_TTL_SECS).await;
+    }
+    Ok(response)
+}
+
+/// Middleware for admin routers: bumps the tenant's cache version after a
+/// successful write.
+pub async fn bump_on_write(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let write = !matches!(*req.method(), Method::GET | Method::HEAD);
+    let tenant_id = req.extensions().get::<TenantId>().map(|t| t.0.clone());
+    let response = next.run(req).await;
+
+    if write && response.status().is_success() {
+        if let Some(tenant_id) = tenant_id {
+            if let Err(e) = bump_version(&state, &tenant_id).await {
+                tracing::error!("Failed to bump cache version for {}: {}", tenant_id, e);
+            }
+        }
+    }
+    response
+}
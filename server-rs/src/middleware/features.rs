@@ -0,0 +1,124 @@
+//! Per-tenant feature flags.
+//!
+//! Every request carries the tenant's [`Features`]: the built-in defaults in
+//! [`FLAGS`] overridden by the tenant's `feature_flags` rows. Route groups
+//! that belong to a feature are wrapped in its `require_*` middleware, so a
+//! school tenant can run without the economy, comments or multiplayer.
+//! Flags not listed in [`FLAGS`] do nothing on the server and are passed to
+//! clients as they are.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+use crate::error::{AppError, AppResult};
+use crate::middleware::tenant::TenantId;
+use crate::AppState;
+
+pub const ECONOMY: &str = "economy";
+pub const COMMENTS: &str = "comments";
+pub const MULTIPLAYER: &str = "multiplayer";
+
+/// Built-in flags: name, default, what turning it off disables.
+pub const FLAGS: &[(&str, bool, &str)] = &[
+    (ECONOMY, true, "Wallets, store, crates, trades, auctions, crafting and the battle pass"),
+    (COMMENTS, true, "Comments and reviews"),
+    (MULTIPLAYER, true, "Rooms, matchmaking and match history"),
+];
+
+const CACHE_TTL_SECS: u64 = 300;
+
+/// The tenant's flag overrides.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Features(HashMap<String, bool>);
+
+impl Features {
+    pub fn enabled(&self, flag: &str) -> bool {
+        self.0.get(flag).copied().unwrap_or_else(|| default_for(flag).unwrap_or(false))
+    }
+
+    /// Every built-in flag and every override, resolved.
+    pub fn resolved(&self) -> BTreeMap<String, bool> {
+        let mut out: BTreeMap<String, bool> = FLAGS.iter().map(|(f, on, _)| (f.to_string(), *on)).collect();
+        out.extend(self.0.iter().map(|(f, on)| (f.clone(), *on)));
+        out
+    }
+}
+
+pub fn default_for(flag: &str) -> Option<bool> {
+    FLAGS.iter().find(|(f, _, _)| *f == flag).map(|(_, on, _)| *on)
+}
+
+/// Flag names are lower snake case, up to 64 characters.
+pub fn is_valid_name(flag: &str) -> bool {
+    let mut chars = flag.chars();
+    flag.len() <= 64
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn cache_key(tenant_id: &str) -> String {
+    format!("features:{}", tenant_id)
+}
+
+pub async fn load(db: &sqlx::PgPool, cache: &Cache, tenant_id: &str) -> AppResult<Features> {
+    let key = cache_key(tenant_id);
+    if let Some(features) = cache.get_json::<Features>(&key).await {
+        return Ok(features);
+    }
+
+    let rows: Vec<(String, bool)> = sqlx::query_as("SELECT flag, enabled FROM feature_flags WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_all(db)
+        .await?;
+    let features = Features(rows.into_iter().collect());
+    cache.set_json(&key, &features, CACHE_TTL_SECS).await;
+    Ok(features)
+}
+
+/// Drops the cached flags after an admin change.
+pub async fn invalidate(cache: &Cache, tenant_id: &str) {
+    cache.del(&cache_key(tenant_id)).await;
+}
+
+/// Middleware: attaches the tenant's [`Features`] to the request.
+pub async fn resolve_features(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let tenant_id = req
+        .extensions()
+        .get::<TenantId>()
+        .map(|t| t.0.clone())
+        .unwrap_or_else(|| state.config.tenant.default_tenant_id.clone());
+    let features = load(&state.db, &state.cache, &tenant_id).await?;
+    req.extensions_mut().insert(features);
+    Ok(next.run(req).await)
+}
+
+async fn require(flag: &str, req: Request, next: Next) -> Result<Response, AppError> {
+    let enabled = req.extensions().get::<Features>().map_or(true, |f| f.enabled(flag));
+    if !enabled {
+        return Err(AppError::Forbidden("This feature is turned off".into()));
+    }
+    Ok(next.run(req).await)
+}
+
+pub async fn require_economy(req: Request, next: Next) -> Result<Response, AppError> {
+    require(ECONOMY, req, next).await
+}
+
+pub async fn require_comments(req: Request, next: Next) -> Result<Response, AppError> {
+    require(COMMENTS, req, next).await
+}
+
+pub async fn require_multiplayer(req: Request, next: Next) -> Result<Response, AppError> {
+    require(MULTIPLAYER, req, next).await
+}
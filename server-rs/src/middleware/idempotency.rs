@@ -0,0 +1,200 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::AppState;
+
+const MAX_KEY_LEN: usize = 255;
+/// Request and response bodies are buffered to hash and snapshot them.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// How long a key (and its stored response) is honored.
+const KEY_TTL_HOURS: i32 = 24;
+/// A first request that hasn't finished after this long is assumed to have
+/// died with the process, and a retry may run it again.
+const IN_FLIGHT_TIMEOUT_SECS: f64 = 60.0;
+
+/// Set on responses replayed from a stored snapshot.
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Middleware: honors an `Idempotency-Key` header on mutating requests.
+/// Must run after `authenticate`; keys are scoped to the player.
+///
+/// The first request with a key runs normally and its response is stored.
+/// A retry with the same key and the same method, path and body gets the
+/// stored response without running the handler again. Reusing a key for a
+/// different request is a 400; retrying while the first request is still
+/// running is a 409. 5xx responses aren't stored, so they can be retried.
+pub async fn idempotency(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(key) = req.headers().get("idempotency-key") else {
+        return Ok(next.run(req).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|k| !k.is_empty() && k.len() <= MAX_KEY_LEN && k.bytes().all(|b| b.is_ascii_graphic()))
+        .ok_or_else(|| AppError::BadRequest("Invalid Idempotency-Key".into()))?
+        .to_string();
+    let player = req
+        .extensions()
+        .get::<AuthPlayer>()
+        .cloned()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".into()))?;
+
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| AppError::BadRequest("Request body too large".into()))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str());
+    hasher.update(b" ");
+    hasher.update(parts.uri.path());
+    hasher.update(b"\n");
+    hasher.update(&body);
+    let request_hash = hex::encode(hasher.finalize());
+
+    if !claim_key(&state.db, &player, &key, &request_hash).await? {
+        return replay(&state.db, &player, &key, &request_hash).await;
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    if response.status().is_server_error() {
+        release_key(&state.db, &player, &key).await;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(b) => b,
+        Err(e) => {
+            release_key(&state.db, &player, &key).await;
+            return Err(AppError::Internal(format!("Failed to buffer response: {}", e)));
+        }
+    };
+
+    let stored = sqlx::query(
+        r#"UPDATE idempotency_keys SET status_code = $4, response_body = $5, completed_at = NOW()
+        WHERE tenant_id = $1 AND player_id = $2 AND idempotency_key = $3"#,
+    )
+    .bind(&player.tenant_id)
+    .bind(player.id)
+    .bind(&key)
+    .bind(i32::from(parts.status.as_u16()))
+    .bind(body.as_ref())
+    .execute(&state.db)
+    .await;
+    if let Err(e) = stored {
+        tracing::error!("Failed to store idempotent response for {}: {}", player.id, e);
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+/// Records the key as in flight. Returns false if it's already taken by a
+/// live or completed request. Abandoned and expired keys are taken over.
+async fn claim_key(db: &PgPool, player: &AuthPlayer, key: &str, request_hash: &str) -> AppResult<bool> {
+    let claimed: Option<bool> = sqlx::query_scalar(
+        r#"INSERT INTO idempotency_keys (tenant_id, player_id, idempotency_key, request_hash)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (tenant_id, player_id, idempotency_key) DO UPDATE SET
+            request_hash = EXCLUDED.request_hash,
+            status_code = NULL,
+            response_body = NULL,
+            created_at = NOW(),
+            completed_at = NULL
+        WHERE (idempotency_keys.status_code IS NULL
+                AND idempotency_keys.created_at < NOW() - make_interval(secs => $5))
+            OR idempotency_keys.created_at < NOW() - make_interval(hours => $6)
+        RETURNING true"#,
+    )
+    .bind(&player.tenant_id)
+    .bind(player.id)
+    .bind(key)
+    .bind(request_hash)
+    .bind(IN_FLIGHT_TIMEOUT_SECS)
+    .bind(KEY_TTL_HOURS)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(claimed.is_some())
+}
+
+/// Answers a request whose key is already taken.
+async fn replay(db: &PgPool, player: &AuthPlayer, key: &str, request_hash: &str) -> Result<Response, AppError> {
+    let existing: Option<(String, Option<i32>, Option<Vec<u8>>)> = sqlx::query_as(
+        "SELECT request_hash, status_code, response_body FROM idempotency_keys WHERE tenant_id = $1 AND player_id = $2 AND idempotency_key = $3",
+    )
+    .bind(&player.tenant_id)
+    .bind(player.id)
+    .bind(key)
+    .fetch_optional(db)
+    .await?;
+
+    match existing {
+        Some((hash, _, _)) if hash != request_hash => Err(AppError::BadRequest(
+            "Idempotency-Key was already used for a different request".into(),
+        )),
+        Some((_, Some(status), body)) => Response::builder()
+            .status(StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(REPLAYED_HEADER, "true")
+            .body(Body::from(body.unwrap_or_default()))
+            .map_err(|e| AppError::Internal(e.to_string())),
+        // Still in flight, or released by a failed first attempt just now
+        _ => Err(AppError::Conflict(
+            "A request with this Idempotency-Key is still in progress".into(),
+        )),
+    }
+}
+
+/// Frees a key whose request failed so the client can retry it.
+async fn release_key(db: &PgPool, player: &AuthPlayer, key: &str) {
+    let released = sqlx::query(
+        "DELETE FROM idempotency_keys WHERE tenant_id = $1 AND player_id = $2 AND idempotency_key = $3",
+    )
+    .bind(&player.tenant_id)
+    .bind(player.id)
+    .bind(key)
+    .execute(db)
+    .await;
+    if let Err(e) = released {
+        tracing::warn!("Failed to release idempotency key for {}: {}", player.id, e);
+    }
+}
+
+/// Spawns the hourly purge of expired keys.
+pub fn spawn_purge_worker(db: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let purged = sqlx::query(
+                "DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(hours => $1)",
+            )
+            .bind(KEY_TTL_HOURS)
+            .execute(&db)
+            .await;
+            match purged {
+                Ok(r) if r.rows_affected() > 0 => {
+                    tracing::info!("Purged {} expired idempotency key(s)", r.rows_affected())
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Idempotency key purge failed: {}", e),
+            }
+        }
+    });
+}
@@ -1,10 +1,18 @@
 use axum::{
-    extract::Request,
+    body::Body,
+    extract::{Request, State},
+    http::header,
     middleware::Next,
     response::Response,
 };
 
 use crate::error::AppError;
+use crate::middleware::tenant::TenantId;
+use crate::services::translations;
+use crate::AppState;
+
+/// Error bodies are a single message; anything bigger isn't one of ours.
+const MAX_ERROR_BODY: usize = 16 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct LocaleInfo {
@@ -105,8 +113,8 @@ fn detect_locale(req: &Request) -> String {
         for pair in query.split('&') {
             let mut kv = pair.splitn(2, '=');
             if kv.next() == Some("locale") {
-                if let Some(val) = kv.next() {
-                    return val.to_string();
+                if let Some(val) = kv.next().and_then(translations::normalize_locale) {
+                    return val;
                 }
             }
         }
@@ -117,22 +125,80 @@ fn detect_locale(req: &Request) -> String {
         // Parse first language tag
         if let Some(lang) = al.split(',').next() {
             let tag = lang.split(';').next().unwrap_or("en-US").trim();
-            return tag.to_string();
+            if let Some(tag) = translations::normalize_locale(tag) {
+                return tag;
+            }
         }
     }
 
     "en-US".to_string()
 }
 
-/// Middleware: detects locale and attaches LocaleInfo to request.
+/// Middleware: detects locale and attaches LocaleInfo to request. Error
+/// responses have their message translated from the tenant's `error`
+/// table, and every response says which locale it was served for.
 pub async fn locale_detector(
+    State(state): State<AppState>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, AppError> {
     let locale = detect_locale(&req);
-    let info = locale_config(&locale);
+    let mut info = locale_config(&locale);
+    // Currency falls back to the default, but keep the requested language
+    info.locale = locale.clone();
     req.extensions_mut().insert(info);
-    Ok(next.run(req).await)
+    let tenant = req.extensions().get::<TenantId>().cloned();
+
+    let mut response = next.run(req).await;
+    if let Some(TenantId(tenant_id)) = tenant {
+        if response.status().is_client_error() || response.status().is_server_error() {
+            response = translate_error(&state, &tenant_id, &locale, response).await;
+        }
+    }
+    if let Ok(value) = header::HeaderValue::from_str(&locale) {
+        response.headers_mut().insert(header::CONTENT_LANGUAGE, value);
+    }
+    Ok(response)
+}
+
+/// Swaps `{"error": "..."}` for the tenant's translation, if it has one.
+async fn translate_error(state: &AppState, tenant_id: &str, locale: &str, response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY).await {
+        Ok(b) => b,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    let mut json: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(v) => v,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    let Some(message) = json["error"].as_str() else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let table = match translations::for_locale(&state.db, &state.cache, tenant_id, locale).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::warn!("Loading translations for {} failed: {}", locale, e);
+            return Response::from_parts(parts, Body::from(bytes));
+        }
+    };
+    let Some(translated) = table.get("error", message) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    json["error"] = translated.into();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
 }
 
 pub fn format_currency(amount_cents: i64, info: &LocaleInfo) -> String {
@@ -0,0 +1,39 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::services::metrics;
+
+/// The route template a request matched, or `unmatched`.
+pub fn route(req: &Request) -> String {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string())
+}
+
+/// Middleware: records each request's latency under its route template
+/// (`/api/v1/leaderboards/:gameId`). Requests that match no route are
+/// recorded as `unmatched`.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let method = match *req.method() {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        _ => "OTHER",
+    };
+    let route = route(&req);
+
+    let started = Instant::now();
+    let response = next.run(req).await;
+    metrics::observe_request(method, &route, response.status().as_u16(), started.elapsed().as_secs_f64());
+    response
+}
@@ -4,3 +4,11 @@ pub mod rate_limit;
 pub mod admin;
 pub mod entitlements;
 pub mod localization;
+pub mod idempotency;
+pub mod features;
+pub mod etag;
+pub mod metrics;
+pub mod trace;
+pub mod age_gate;
+pub mod playtime;
+pub mod api_key;
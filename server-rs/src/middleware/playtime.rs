@@ -0,0 +1,31 @@
+//! Playtime limit on starting games.
+//!
+//! Routes that start a game session (creating or joining a room,
+//! matchmaking, rematches) are layered with [`require_playtime`] inside
+//! `authenticate`, so a player who has used up today's playtime finishes
+//! the game they're in but can't start another. Going `in_game` through
+//! presence is checked by the presence handlers. See
+//! [`services::playtime`](crate::services::playtime).
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::AppError;
+use crate::middleware::auth::AuthPlayer;
+use crate::services::playtime;
+use crate::AppState;
+
+/// Requires the authenticated player to have playtime left today.
+pub async fn require_playtime(State(state): State<AppState>, req: Request, next: Next) -> Result<Response, AppError> {
+    let player = req
+        .extensions()
+        .get::<AuthPlayer>()
+        .cloned()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".into()))?;
+
+    playtime::check_can_start(&state.db, &player.tenant_id, player.id).await?;
+    Ok(next.run(req).await)
+}
@@ -10,6 +10,7 @@ use tokio::sync::Mutex;
 
 use crate::error::AppError;
 use crate::middleware::auth::AuthPlayer;
+use crate::services::metrics;
 use crate::AppState;
 
 #[derive(Clone)]
@@ -51,7 +52,11 @@ impl RateLimiter {
         }
 
         entry.count += 1;
-        entry.count <= self.max_requests
+        let allowed = entry.count <= self.max_requests;
+        if !allowed {
+            metrics::rate_limited(key);
+        }
+        allowed
     }
 }
 
@@ -96,3 +101,30 @@ pub async fn score_rate_limit(
     }
     Ok(next.run(req).await)
 }
+
+/// Middleware: telemetry batch upload rate limiter (30 req/min).
+pub async fn telemetry_rate_limit(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = format!("telemetry:{}", get_client_key(&req));
+    if !state.telemetry_rate_limiter.check(&key).await {
+        return Err(AppError::RateLimited);
+    }
+    Ok(next.run(req).await)
+}
+
+/// Middleware: crash report rate limiter, sharing the telemetry limit
+/// under its own key.
+pub async fn crash_report_rate_limit(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = format!("crash:{}", get_client_key(&req));
+    if !state.telemetry_rate_limiter.check(&key).await {
+        return Err(AppError::RateLimited);
+    }
+    Ok(next.run(req).await)
+}
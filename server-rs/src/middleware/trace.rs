@@ -0,0 +1,31 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use tracing::{field, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::middleware::metrics::route;
+use crate::services::otel;
+
+/// Middleware: runs each request in an `http.request` span named after its
+/// route, continuing the caller's trace if it sent a `traceparent` header.
+pub async fn trace_requests(req: Request, next: Next) -> Response {
+    let method = req.method().clone();
+    let route = route(&req);
+    let span = tracing::info_span!(
+        "http.request",
+        otel.name = %format!("{} {}", method, route),
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.request.method = %method,
+        http.route = %route,
+        http.response.status_code = field::Empty,
+    );
+    span.set_parent(otel::extract(req.headers()));
+
+    let response = next.run(req).instrument(span.clone()).await;
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
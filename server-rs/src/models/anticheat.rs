@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An entry in the suspected cheaters queue.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AnticheatFlag {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub display_name: Option<String>,
+    pub flag_type: String,
+    pub severity: String,
+    pub details: serde_json::Value,
+    pub match_id: Option<Uuid>,
+    /// Set on score flags.
+    pub game_id: Option<String>,
+    /// The suspect `score_history` submissions of a score flag.
+    pub score_ids: Option<Vec<i64>>,
+    pub status: String,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnticheatFlagQuery {
+    pub status: Option<String>,
+    pub flag_type: Option<String>,
+    pub game_id: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Auction {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub tenant_id: String,
+    pub seller_id: Uuid,
+    pub item_id: String,
+    pub quantity: i32,
+    pub currency_type: String,
+    pub min_bid: i64,
+    /// Highest bid so far, held in escrow until settlement.
+    pub current_bid: Option<i64>,
+    pub high_bidder_id: Option<Uuid>,
+    pub bid_count: i32,
+    /// `open`, `sold`, `expired` or `cancelled`.
+    pub status: String,
+    pub ends_at: DateTime<Utc>,
+    /// Times a late bid pushed `ends_at` back.
+    pub extensions: i32,
+    pub created_at: DateTime<Utc>,
+    pub settled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionBid {
+    pub id: Uuid,
+    pub bidder_id: Uuid,
+    pub amount: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAuctionRequest {
+    pub item_id: String,
+    pub quantity: Option<i32>,
+    pub currency_type: Option<String>,
+    pub min_bid: i64,
+    pub duration_minutes: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaceBidRequest {
+    pub amount: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionQuery {
+    pub item_id: Option<String>,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
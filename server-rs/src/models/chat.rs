@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ChatMessage {
+    pub id: Uuid,
+    pub channel: String,
+    #[serde(rename = "senderId")]
+    pub sender_id: Uuid,
+    #[serde(rename = "recipientId")]
+    pub recipient_id: Option<Uuid>,
+    pub body: String,
+    pub status: String,
+    #[serde(rename = "readAt")]
+    pub read_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Latest message of a direct-message conversation.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Conversation {
+    pub partner_id: Uuid,
+    pub display_name: Option<String>,
+    pub sender_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub unread: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageRequest {
+    pub body: String,
+}
+
+/// History is paged backwards: pass the oldest message id already shown as
+/// `before` to get the page preceding it.
+#[derive(Debug, Deserialize)]
+pub struct ChatHistoryQuery {
+    pub limit: Option<i64>,
+    pub before: Option<Uuid>,
+}
+
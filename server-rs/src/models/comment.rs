@@ -28,6 +28,7 @@ pub struct GameReview {
     pub title: Option<String>,
     pub body: Option<String>,
     pub status: String,
+    pub helpful_count: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -72,6 +73,12 @@ pub struct EditCommentRequest {
     pub body: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReactRequest {
+    /// One of the keys in `REACTIONS`, e.g. `like`.
+    pub reaction: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PostReviewRequest {
     pub rating: i32,
@@ -79,6 +86,14 @@ pub struct PostReviewRequest {
     pub body: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReviewListQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    /// `recent` (default), `helpful` or `rating`.
+    pub sort: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ReportRequest {
     pub reason: String,
@@ -100,3 +115,73 @@ pub struct SetRoleRequest {
 pub struct WarnRequest {
     pub reason: String,
 }
+
+/// A warning or ban from `moderation_log`, as shown with its appeal.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ModerationAction {
+    pub id: String,
+    pub action: String,
+    pub reason: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    pub admin_id: Uuid,
+    pub admin_name: Option<String>,
+    pub target_player_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Appeal {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    /// The `moderation_log` entry being appealed.
+    pub action_id: String,
+    pub message: String,
+    /// `open`, `upheld` or `reversed`.
+    pub status: String,
+    pub resolved_by: Option<Uuid>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAppealRequest {
+    pub action_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveAppealRequest {
+    /// `upheld` or `reversed`.
+    pub outcome: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealQueueQuery {
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FilterTerm {
+    pub id: Uuid,
+    pub term: String,
+    pub severity: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddFilterTermRequest {
+    pub term: String,
+    pub severity: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestFilterRequest {
+    pub text: String,
+}
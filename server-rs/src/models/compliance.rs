@@ -49,3 +49,14 @@ pub struct SyncOperation {
 pub struct BatchSyncRequest {
     pub operations: Vec<SyncOperation>,
 }
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ExportStatus {
+    pub status: String,
+    pub storage_key: Option<String>,
+    pub download_url: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A panic as reported by the engine's panic hook.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReportInput {
+    pub game_id: Option<String>,
+    pub message: String,
+    /// `file:line:column` of the panic.
+    #[serde(default)]
+    pub location: String,
+    pub engine_version: Option<String>,
+    /// The player's last inputs, oldest first.
+    #[serde(default)]
+    pub inputs: serde_json::Value,
+    #[serde(default)]
+    pub browser: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashGroup {
+    pub id: Uuid,
+    pub signature: String,
+    pub message: String,
+    pub location: String,
+    pub status: String,
+    pub notes: Option<String>,
+    pub report_count: i32,
+    /// Distinct signed-in players among the kept reports.
+    pub player_count: i64,
+    /// Games the kept reports came from.
+    pub game_ids: Vec<String>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub updated_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: Uuid,
+    pub player_id: Option<Uuid>,
+    pub game_id: Option<String>,
+    pub message: String,
+    pub location: String,
+    pub engine_version: Option<String>,
+    pub inputs: serde_json::Value,
+    pub browser: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashGroupQuery {
+    pub status: Option<String>,
+    pub game_id: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCrashGroupRequest {
+    pub status: Option<String>,
+    pub notes: Option<String>,
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -37,6 +38,40 @@ pub struct StoreItem {
     pub price: i64,
     pub metadata: Option<serde_json::Value>,
     pub is_active: bool,
+    /// Purchases allowed per player; `None` for no limit.
+    pub purchase_limit: Option<i32>,
+    pub featured: bool,
+    pub available_from: Option<DateTime<Utc>>,
+    pub available_until: Option<DateTime<Utc>>,
+}
+
+/// A store item with the biggest sale running on it, if any.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StoreOffer {
+    #[sqlx(flatten)]
+    pub item: StoreItem,
+    pub discount_percent: Option<i32>,
+    pub sale_ends_at: Option<DateTime<Utc>>,
+}
+
+impl StoreOffer {
+    /// What the player pays: the base price less the sale discount,
+    /// rounded in the store's favour.
+    pub fn price(&self) -> i64 {
+        let discount = i64::from(self.discount_percent.unwrap_or(0));
+        self.item.price - self.item.price * discount / 100
+    }
+}
+
+/// One item granted by a bundle, with the item's display name.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BundleContent {
+    #[serde(skip)]
+    pub bundle_id: String,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub name: String,
+    pub quantity: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -50,23 +85,52 @@ pub struct PlayerInventory {
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct BattlePass {
-    pub id: String,
+    pub id: i32,
     pub tenant_id: String,
-    pub season_id: Option<String>,
+    pub season_id: Option<i32>,
+    pub name: String,
     pub max_tier: i32,
     pub xp_per_tier: i32,
+    /// `[{tier, reward_type, reward_data}]`, rewritten from the pass's
+    /// tiers for older clients.
     pub free_rewards: serde_json::Value,
     pub premium_rewards: serde_json::Value,
     pub is_active: bool,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
+/// A tier's free or premium reward: `amount` of `currencyType`, or
+/// `amount` of the store item `itemId`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BattlePassReward {
+    pub reward_type: String,
+    pub currency_type: Option<String>,
+    pub item_id: Option<String>,
+    #[serde(default = "default_quantity")]
+    pub amount: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BattlePassTier {
+    pub tier: i32,
+    /// Total XP on the pass needed to reach the tier.
+    pub xp_threshold: i32,
+    pub free: Option<BattlePassReward>,
+    pub premium: Option<BattlePassReward>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct PlayerBattlePass {
     pub tenant_id: String,
     pub player_id: Uuid,
-    pub battle_pass_id: String,
+    pub battle_pass_id: i32,
     pub current_tier: i32,
+    /// XP towards the next tier.
     pub current_xp: i32,
     pub is_premium: bool,
     pub claimed_tiers: serde_json::Value,
@@ -88,6 +152,9 @@ pub struct EarnRequest {
 pub struct PurchaseRequest {
     #[serde(rename = "itemId")]
     pub item_id: String,
+    /// The price the client showed; the purchase fails if it has changed.
+    #[serde(rename = "expectedPrice")]
+    pub expected_price: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -99,4 +166,304 @@ pub struct ClaimTierRequest {
 pub struct AwardXpRequest {
     pub xp: i32,
     pub source: Option<String>,
+    /// The backend's id for what the XP is for; XP is awarded once per id.
+    #[serde(rename = "referenceId")]
+    pub reference_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TradeOffer {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub from_player_id: Uuid,
+    pub to_player_id: Uuid,
+    pub status: String,
+    pub offer_items: serde_json::Value,
+    pub offer_currency: serde_json::Value,
+    pub request_items: serde_json::Value,
+    pub request_currency: serde_json::Value,
+    pub message: Option<String>,
+    pub parent_offer_id: Option<Uuid>,
+    pub cancelled_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// One line of a gift or trade: a store item and how many of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeItem {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+}
+
+fn default_quantity() -> i32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GiftRequest {
+    #[serde(rename = "friendId")]
+    pub friend_id: Uuid,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub quantity: Option<i32>,
+    pub message: Option<String>,
+}
+
+/// What each side puts on the table, from the point of view of the player
+/// making the offer: `offer*` is given away, `request*` is received.
+#[derive(Debug, Default, Deserialize)]
+pub struct TradeTerms {
+    #[serde(rename = "offerItems", default)]
+    pub offer_items: Vec<TradeItem>,
+    #[serde(rename = "offerCurrency", default)]
+    pub offer_currency: HashMap<String, i64>,
+    #[serde(rename = "requestItems", default)]
+    pub request_items: Vec<TradeItem>,
+    #[serde(rename = "requestCurrency", default)]
+    pub request_currency: HashMap<String, i64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTradeRequest {
+    #[serde(rename = "toPlayerId")]
+    pub to_player_id: Uuid,
+    #[serde(flatten)]
+    pub terms: TradeTerms,
+}
+
+/// One entry of a crate's drop table, with the item's display name.
+/// Drops are rolled in `item_id` order.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CrateDrop {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub name: String,
+    pub quantity: i32,
+    pub weight: i32,
+    pub rarity: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenCrateRequest {
+    #[serde(rename = "crateId")]
+    pub crate_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CrateDropInput {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+    pub weight: i32,
+    pub rarity: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCrateDropsRequest {
+    pub drops: Vec<CrateDropInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBundleContentsRequest {
+    pub contents: Vec<TradeItem>,
+}
+
+/// Store settings of an item; omitted fields are cleared.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetItemOfferRequest {
+    pub purchase_limit: Option<i32>,
+    #[serde(default)]
+    pub featured: bool,
+    pub available_from: Option<DateTime<Utc>>,
+    pub available_until: Option<DateTime<Utc>>,
+}
+
+/// How much battle pass XP a source gives.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BattlePassXpSource {
+    pub source: String,
+    /// XP per event.
+    pub xp: i32,
+    /// Extra XP per 1000 points scored.
+    pub xp_per_1000_points: i32,
+    /// Most XP a player earns from the source per UTC day; `None` for no
+    /// cap.
+    pub daily_cap: Option<i32>,
+    pub is_enabled: bool,
+    /// Whether these are the built-in settings rather than the tenant's.
+    pub is_default: bool,
+}
+
+/// A tenant's settings for an XP source, replacing the built-in ones.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetXpSourceRequest {
+    pub xp: i32,
+    #[serde(default)]
+    pub xp_per_1000_points: i32,
+    /// `None` for no cap.
+    pub daily_cap: Option<i32>,
+    /// Defaults to true.
+    pub is_enabled: Option<bool>,
+}
+
+/// A tier of a battle pass, in a list ordered from tier 1 up.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BattlePassTierInput {
+    pub xp_threshold: i32,
+    pub free: Option<BattlePassReward>,
+    pub premium: Option<BattlePassReward>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBattlePassRequest {
+    pub name: String,
+    pub season_id: Option<i32>,
+    /// When set, the pass switches itself on at `startsAt`.
+    pub starts_at: Option<DateTime<Utc>>,
+    /// When set, the pass closes at `endsAt`.
+    pub ends_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tiers: Vec<BattlePassTierInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBattlePassTiersRequest {
+    pub tiers: Vec<BattlePassTierInput>,
+}
+
+/// Battle pass settings to change; omitted fields are kept. Tiers are set
+/// with [`SetBattlePassTiersRequest`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBattlePassRequest {
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreSale {
+    pub id: Uuid,
+    /// `None` for a storewide sale.
+    pub item_id: Option<String>,
+    pub discount_percent: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSaleRequest {
+    pub item_id: Option<String>,
+    pub discount_percent: i32,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CraftingRecipe {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub output_item_id: String,
+    pub output_quantity: i32,
+    pub currency_type: String,
+    pub currency_cost: i64,
+    /// Chance in percent that crafting produces the output.
+    pub success_percent: i32,
+    /// Listed only to players who have discovered it.
+    pub hidden: bool,
+    pub is_active: bool,
+}
+
+/// One input of a recipe, with the item's display name.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecipeInput {
+    #[serde(skip)]
+    pub recipe_id: String,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub name: String,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CraftRequest {
+    #[serde(rename = "recipeId")]
+    pub recipe_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertRecipeRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub output_item_id: String,
+    #[serde(default = "default_quantity")]
+    pub output_quantity: i32,
+    pub currency_type: Option<String>,
+    #[serde(default)]
+    pub currency_cost: i64,
+    pub success_percent: Option<i32>,
+    #[serde(default)]
+    pub hidden: bool,
+    pub is_active: Option<bool>,
+    pub inputs: Vec<TradeItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCode {
+    pub id: Uuid,
+    pub code: String,
+    /// Amount granted per currency.
+    pub currency: serde_json::Value,
+    /// `[{itemId, quantity}]`.
+    pub items: serde_json::Value,
+    pub battle_pass_tiers: i32,
+    /// `None` for no limit.
+    pub max_redemptions: Option<i32>,
+    pub per_player_limit: i32,
+    pub redemption_count: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Creates `count` codes with the same rewards and limits: `code` itself
+/// if given, otherwise random ones.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePromoCodesRequest {
+    pub code: Option<String>,
+    pub count: Option<u32>,
+    #[serde(default)]
+    pub currency: HashMap<String, i64>,
+    #[serde(default)]
+    pub items: Vec<TradeItem>,
+    #[serde(default)]
+    pub battle_pass_tiers: i32,
+    pub max_redemptions: Option<i32>,
+    pub per_player_limit: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedeemPromoCodeRequest {
+    pub code: String,
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -28,6 +30,40 @@ pub struct ScoreHistory {
     pub created_at: DateTime<Utc>,
 }
 
+/// A `score_history` row as admins see it.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSubmission {
+    pub id: i64,
+    pub player_id: String,
+    pub score: i64,
+    pub level: Option<i32>,
+    /// Milliseconds.
+    pub play_time: Option<i32>,
+    pub device_fingerprint: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub voided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreSubmissionQuery {
+    pub player_id: Option<Uuid>,
+    /// Include voided submissions (default false).
+    pub include_voided: Option<bool>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Either `scoreIds` or `playerId`, for all of the player's submissions.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoidScoresRequest {
+    pub score_ids: Option<Vec<i64>>,
+    pub player_id: Option<Uuid>,
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ScoreSubmitRequest {
     pub score: i64,
@@ -36,6 +72,23 @@ pub struct ScoreSubmitRequest {
     #[serde(rename = "customData")]
     pub custom_data: Option<serde_json::Value>,
     pub timestamp: Option<i64>,
+    /// Values for the game's declared metrics, e.g. `{"distance": 1840}`.
+    pub metrics: Option<HashMap<String, i64>>,
+    /// Stable per-device id from the client, used to spot one device
+    /// submitting for many accounts.
+    #[serde(rename = "deviceFingerprint")]
+    pub device_fingerprint: Option<String>,
+}
+
+/// The result of the one attempt a player gets at a game's daily
+/// challenge.
+#[derive(Debug, Deserialize)]
+pub struct DailyRunRequest {
+    /// As handed out by `POST /daily/:gameId/start`.
+    #[serde(rename = "runToken")]
+    pub run_token: Uuid,
+    pub score: i64,
+    pub level: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -94,3 +147,91 @@ pub fn calculate_stars(game_id: &str, score: i64) -> i32 {
         0
     }
 }
+
+/// Whether a bigger or a smaller value is the better one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricOrder {
+    Higher,
+    Lower,
+}
+
+impl MetricOrder {
+    pub fn is_better(self, value: i64, than: i64) -> bool {
+        match self {
+            MetricOrder::Higher => value > than,
+            MetricOrder::Lower => value < than,
+        }
+    }
+
+    pub fn sql(self) -> &'static str {
+        match self {
+            MetricOrder::Higher => "DESC",
+            MetricOrder::Lower => "ASC",
+        }
+    }
+}
+
+/// A stat dimension a game records alongside its score.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Metric {
+    pub key: &'static str,
+    pub order: MetricOrder,
+    pub unit: &'static str,
+    pub max: i64,
+}
+
+const fn metric(key: &'static str, order: MetricOrder, unit: &'static str, max: i64) -> Metric {
+    Metric { key, order, unit, max }
+}
+
+/// Every game ranks by score; this is the metric for it.
+pub const SCORE_METRIC: Metric = metric("score", MetricOrder::Higher, "points", 999_999);
+
+const TIME: Metric = metric("time", MetricOrder::Lower, "ms", 3_600_000);
+const DISTANCE: Metric = metric("distance", MetricOrder::Higher, "m", 1_000_000);
+const ACCURACY: Metric = metric("accuracy", MetricOrder::Higher, "percent", 100);
+
+/// Metrics each game declares besides the score, by engine game id.
+pub fn game_metrics(game_id: &str) -> &'static [Metric] {
+    match game_id {
+        "formula_stem" => &[TIME],
+        "campus_dash" | "gravity_shift_run" | "parkour_lab" | "rover_field_test" | "rover_3d_expedition"
+        | "heavy_gear_delivery" | "lab_breach" => &[DISTANCE],
+        "aero_engineering" | "drone_defense" | "physics_master_billiards" | "stem_project_volley"
+        | "stem_celebration" => &[ACCURACY],
+        _ => &[],
+    }
+}
+
+/// A metric the game can be ranked by, including the score.
+pub fn find_metric(game_id: &str, key: &str) -> Option<Metric> {
+    if key == SCORE_METRIC.key {
+        return Some(SCORE_METRIC);
+    }
+    game_metrics(game_id).iter().find(|m| m.key == key).copied()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Replay {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub player_id: Uuid,
+    pub game_id: String,
+    pub score: i64,
+    pub duration_ms: i32,
+    pub frames: serde_json::Value,
+    pub engine_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayUploadRequest {
+    pub score: i64,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: i32,
+    pub frames: Vec<serde_json::Value>,
+    #[serde(rename = "engineVersion")]
+    pub engine_version: Option<String>,
+}
@@ -6,3 +6,13 @@ pub mod comment;
 pub mod economy;
 pub mod multiplayer;
 pub mod compliance;
+pub mod telemetry;
+pub mod remote_config;
+pub mod chat;
+pub mod translation;
+pub mod auction;
+pub mod xapi;
+pub mod api_key;
+pub mod entity_audit;
+pub mod crash_report;
+pub mod anticheat;
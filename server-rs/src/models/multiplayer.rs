@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -45,6 +46,20 @@ pub struct CustomGame {
     pub is_active: bool,
 }
 
+/// A game built into the deployed engine, as of the last manifest sync.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EngineGame {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub modes: Vec<String>,
+    pub inputs: Vec<String>,
+    pub thumbnail_color: String,
+    pub render: String,
+    /// When a sync last found the game missing from the build.
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct GameCategory {
     pub id: String,
@@ -61,15 +76,89 @@ pub struct GameCategory {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Room {
     pub id: String,
+    /// Tenant the room was opened in. Kept out of responses; turn rooms
+    /// store it in their own `turn_games` column.
+    #[serde(skip)]
+    pub tenant_id: String,
     pub game_id: String,
     pub host_id: Uuid,
     pub players: Vec<RoomPlayer>,
     pub max_players: i32,
     pub state: String,
     pub is_private: bool,
+    pub allow_spectators: bool,
+    pub spectator_count: usize,
+    /// Spectating player -> their tenant, for relaying frames through the
+    /// realtime gateway. Not exposed in listings; only the count is.
+    #[serde(skip)]
+    pub spectators: HashMap<Uuid, String>,
+    /// Set for turn-based rooms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn: Option<TurnState>,
+    /// Set for bot matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotOpponent>,
     pub created_at: DateTime<Utc>,
 }
 
+/// The computer opponent of a bot match, which the matchmaker forms when
+/// no human opponent turns up in time. The client starts the game with it
+/// as the engine's `bot` option.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BotOpponent {
+    pub display_name: String,
+    /// `easy`, `medium` or `hard`.
+    pub difficulty: String,
+    pub skill_rating: i32,
+}
+
+/// Turn order of a turn-based room. Players take turns in join order once
+/// the room is full, each within `turn_secs` of the turn starting; missing
+/// the deadline forfeits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnState {
+    pub turn_secs: i64,
+    /// Turns taken so far.
+    pub turn_number: i32,
+    pub current_player_id: Option<Uuid>,
+    pub deadline: Option<DateTime<Utc>>,
+    /// Players out of the game, skipped in the turn order.
+    pub forfeited: Vec<Uuid>,
+    pub winner_id: Option<Uuid>,
+    #[serde(skip)]
+    pub turns: Vec<Turn>,
+    /// For notifying players when a turn expires.
+    #[serde(skip)]
+    pub tenant_id: String,
+}
+
+impl TurnState {
+    pub fn new(tenant_id: &str, turn_secs: i64) -> Self {
+        Self {
+            turn_secs,
+            turn_number: 0,
+            current_player_id: None,
+            deadline: None,
+            forfeited: Vec::new(),
+            winner_id: None,
+            turns: Vec::new(),
+            tenant_id: tenant_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Turn {
+    pub number: i32,
+    pub player_id: Uuid,
+    /// Game-specific move, e.g. a billiards shot's angle and power.
+    pub data: serde_json::Value,
+    pub submitted_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoomPlayer {
     pub id: Uuid,
@@ -85,12 +174,35 @@ pub struct CreateRoomRequest {
     pub max_players: Option<i32>,
     #[serde(rename = "isPrivate")]
     pub is_private: Option<bool>,
+    #[serde(rename = "allowSpectators")]
+    pub allow_spectators: Option<bool>,
+    /// Play by turns rather than in real time.
+    #[serde(rename = "turnBased")]
+    pub turn_based: Option<bool>,
+    /// Time each player has for a turn.
+    #[serde(rename = "turnSecs")]
+    pub turn_secs: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitTurnRequest {
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TurnQuery {
+    /// Only turns numbered above this.
+    pub since: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MatchmakeRequest {
     #[serde(rename = "gameId")]
     pub game_id: String,
+    /// Play a bot if no one is found in time, for games with bots.
+    /// Defaults to true.
+    #[serde(rename = "botFallback")]
+    pub bot_fallback: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -98,6 +210,20 @@ pub struct SubmitMatchRequest {
     #[serde(rename = "gameId")]
     pub game_id: String,
     pub players: Vec<MatchPlayerResult>,
+    /// The room the match was played in; it is marked finished and can be
+    /// rematched.
+    #[serde(rename = "roomId")]
+    pub room_id: Option<String>,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchHistoryQuery {
+    #[serde(rename = "gameId")]
+    pub game_id: Option<String>,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -147,6 +273,42 @@ pub struct UpdateGameRequest {
     pub categories: Option<Vec<String>>,
 }
 
+/// One entry of the engine's `get_available_games()` manifest.
+#[derive(Debug, Deserialize)]
+pub struct ManifestGame {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub modes: Vec<String>,
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    #[serde(rename = "thumbnailColor")]
+    pub thumbnail_color: Option<String>,
+    pub render: Option<String>,
+}
+
+/// Per-game Glicko-2 overrides; `None` uses the server default.
+#[derive(Debug, Deserialize)]
+pub struct RatingConfigRequest {
+    #[serde(rename = "initialRating")]
+    pub initial_rating: Option<i32>,
+    #[serde(rename = "initialDeviation")]
+    pub initial_deviation: Option<f64>,
+    #[serde(rename = "initialVolatility")]
+    pub initial_volatility: Option<f64>,
+    pub tau: Option<f64>,
+    #[serde(rename = "ratingPeriodDays")]
+    pub rating_period_days: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardConfigRequest {
+    #[serde(rename = "decayHalfLifeDays")]
+    pub decay_half_life_days: f64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateCategoryRequest {
     pub name: String,
@@ -180,3 +342,10 @@ pub struct PresenceUpdateRequest {
     #[serde(rename = "currentRoomId")]
     pub current_room_id: Option<String>,
 }
+
+/// Browsers can't set headers on a WebSocket handshake, so sockets pass the
+/// access token in the query string.
+#[derive(Debug, Deserialize)]
+pub struct SocketAuthQuery {
+    pub token: String,
+}
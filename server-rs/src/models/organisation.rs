@@ -34,3 +34,116 @@ pub struct AddMemberRequest {
     pub player_id: String,
     pub role: Option<String>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Classroom {
+    pub id: Uuid,
+    #[serde(rename = "organisationId")]
+    pub organisation_id: String,
+    pub name: String,
+    #[serde(rename = "teacherId")]
+    pub teacher_id: Uuid,
+    #[serde(rename = "studentCount")]
+    pub student_count: i64,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Assignment {
+    pub id: Uuid,
+    #[serde(rename = "classroomId")]
+    pub classroom_id: Uuid,
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub title: String,
+    #[serde(rename = "targetScore")]
+    pub target_score: i64,
+    #[serde(rename = "dueAt")]
+    pub due_at: DateTime<Utc>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Uuid,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// One student's standing on one assignment, derived from score_history.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AssignmentProgress {
+    pub assignment_id: Uuid,
+    pub player_id: Uuid,
+    pub best_score: Option<i64>,
+    pub attempts: i64,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateClassroomRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddStudentsRequest {
+    #[serde(rename = "playerIds")]
+    pub player_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAssignmentRequest {
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub title: Option<String>,
+    #[serde(rename = "targetScore")]
+    pub target_score: i64,
+    #[serde(rename = "dueAt")]
+    pub due_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReportRequest {
+    #[serde(rename = "classroomId")]
+    pub classroom_id: Uuid,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReportJob {
+    #[serde(rename = "jobId")]
+    pub id: Uuid,
+    pub status: String,
+    pub format: String,
+    #[serde(rename = "classroomId")]
+    pub classroom_id: Uuid,
+    #[serde(rename = "downloadUrl")]
+    pub download_url: Option<String>,
+    pub error: Option<String>,
+    #[serde(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RosterImportRequest {
+    /// Students CSV: `student_id`, `display_name`.
+    pub students: String,
+    /// Classes CSV: `class_id`, `class_name`, `student_id`.
+    pub classes: Option<String>,
+    #[serde(rename = "dryRun")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RosterImport {
+    #[serde(rename = "importId")]
+    pub id: Uuid,
+    pub status: String,
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub report: Option<serde_json::Value>,
+    pub error: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
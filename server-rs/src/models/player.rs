@@ -23,6 +23,13 @@ pub struct Player {
     pub region: Option<String>,
     pub locale: Option<String>,
     pub data_deletion_requested_at: Option<DateTime<Utc>>,
+    pub equipped_avatar_frame: Option<String>,
+    pub equipped_title: Option<String>,
+    pub equipped_banner: Option<String>,
+    pub birth_year: Option<i16>,
+    /// `not_required`, `pending`, `granted` or `denied`; see
+    /// [`age_gate`](crate::middleware::age_gate).
+    pub consent_status: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,6 +52,11 @@ pub struct RegisterRequest {
     pub avatar_character: Option<String>,
     #[serde(rename = "playerId")]
     pub player_id: Option<String>,
+    #[serde(rename = "birthYear")]
+    pub birth_year: Option<i32>,
+    /// Required when the birth year makes the player possibly under 13.
+    #[serde(rename = "parentEmail")]
+    pub parent_email: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,12 +65,74 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct OAuthStartQuery {
+    #[serde(rename = "redirectUri")]
+    pub redirect_uri: Option<String>,
+}
+
+/// Query string (Google/Microsoft) or form body (Apple) sent to the callback.
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    /// Apple only: JSON with the user's name, sent on first authorization.
+    pub user: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ProfileUpdateRequest {
     #[serde(rename = "displayName")]
     pub display_name: Option<String>,
     #[serde(rename = "avatarCharacter")]
     pub avatar_character: Option<String>,
+    /// Store item ids to equip; `""` empties the slot.
+    #[serde(rename = "avatarFrame")]
+    pub avatar_frame: Option<String>,
+    pub title: Option<String>,
+    pub banner: Option<String>,
+}
+
+/// A player's privacy preferences (see `services::privacy`).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacySettings {
+    /// `public`, `friends` or `private`.
+    pub profile_visibility: String,
+    /// `everyone`, `friends_of_friends` or `nobody`.
+    pub friend_requests: String,
+    pub show_online: bool,
+    pub leaderboard_anonymous: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyUpdateRequest {
+    pub profile_visibility: Option<String>,
+    pub friend_requests: Option<String>,
+    pub show_online: Option<bool>,
+    pub leaderboard_anonymous: Option<bool>,
+}
+
+/// Sets a daily playtime limit; `null` clears it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaytimeLimitRequest {
+    pub daily_limit_minutes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentResendRequest {
+    /// Defaults to the address of the last request.
+    pub parent_email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsentDecisionRequest {
+    /// `grant` or `deny`.
+    pub decision: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -146,3 +220,35 @@ pub struct PlayerSettings {
     pub settings_json: serde_json::Value,
     pub updated_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SessionHandoff {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub player_id: Uuid,
+    pub code: String,
+    pub game_id: String,
+    pub snapshot: serde_json::Value,
+    pub status: String,
+    pub source_device: Option<String>,
+    pub target_device: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HandoffRequest {
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub snapshot: serde_json::Value,
+    #[serde(rename = "deviceId")]
+    pub device_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HandoffClaimRequest {
+    pub code: String,
+    #[serde(rename = "deviceId")]
+    pub device_id: Option<String>,
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GameConfigProfile {
+    pub id: Uuid,
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    pub version: i32,
+    pub name: String,
+    pub params: serde_json::Value,
+    pub notes: Option<String>,
+    pub status: String,
+    #[serde(rename = "rolloutPercent")]
+    pub rollout_percent: i32,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<Uuid>,
+    #[serde(rename = "publishedBy")]
+    pub published_by: Option<Uuid>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(rename = "publishedAt")]
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateConfigProfileRequest {
+    pub name: String,
+    /// Starting params; defaults to a copy of `basedOn` (or `{}`).
+    pub params: Option<serde_json::Value>,
+    pub notes: Option<String>,
+    /// Version to copy params from.
+    #[serde(rename = "basedOn")]
+    pub based_on: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateConfigProfileRequest {
+    pub name: Option<String>,
+    pub params: Option<serde_json::Value>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RolloutRequest {
+    #[serde(rename = "rolloutPercent")]
+    pub rollout_percent: i32,
+}
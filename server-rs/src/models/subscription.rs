@@ -20,6 +20,11 @@ pub struct Subscription {
     pub ended_at: Option<DateTime<Utc>>,
     pub metadata: Option<serde_json::Value>,
     pub updated_at: DateTime<Utc>,
+    /// The Stripe item of the plan's price; its quantity is the seat count.
+    pub seat_item_id: Option<String>,
+    pub seat_quantity: Option<i32>,
+    /// The Stripe item of the metered price, if the plan has one.
+    pub metered_item_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -71,12 +76,18 @@ pub struct PlanDefinition {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct StripeEvent {
     pub id: String,
     pub tenant_id: String,
     pub event_type: String,
     pub payload: serde_json::Value,
+    /// `pending`, `retrying`, `processed` or `failed`.
     pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -118,6 +129,36 @@ pub struct ResumeRequest {
     pub organisation_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SeatsQuery {
+    #[serde(rename = "organisationId")]
+    pub organisation_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoicesQuery {
+    #[serde(rename = "organisationId")]
+    pub organisation_id: String,
+    pub limit: Option<u32>,
+    /// Id of the last invoice of the previous page.
+    #[serde(rename = "startingAfter")]
+    pub starting_after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncSeatsRequest {
+    #[serde(rename = "organisationId")]
+    pub organisation_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedeemCouponRequest {
+    #[serde(rename = "organisationId")]
+    pub organisation_id: String,
+    /// A Stripe promotion code, as customers type it.
+    pub code: String,
+}
+
 pub const ALL_FEATURES: &[&str] = &[
     "organisations",
     "multiplayer",
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+/// One gameplay event as sent by the engine's analytics module.
+#[derive(Debug, Deserialize)]
+pub struct TelemetryEventInput {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(rename = "gameId")]
+    pub game_id: String,
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    /// Client timestamp, milliseconds since the Unix epoch.
+    pub t: i64,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelemetryBatch {
+    pub events: Vec<TelemetryEventInput>,
+    /// Events the client discarded because its buffer overflowed.
+    #[serde(default)]
+    pub dropped: u64,
+}
+
+/// Filters for the admin analytics queries.
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    #[serde(rename = "gameId")]
+    pub game_id: Option<String>,
+    /// Look-back window in days (default 7, max 90).
+    pub days: Option<i32>,
+    /// Heatmap cell size in world units (default 40).
+    pub cell: Option<f64>,
+    /// Zero-based level to limit the heatmap to.
+    pub level: Option<i32>,
+}
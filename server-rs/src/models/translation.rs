@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Translation {
+    pub locale: String,
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TranslationEntry {
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTranslationsRequest {
+    pub entries: Vec<TranslationEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TranslationQuery {
+    pub locale: Option<String>,
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTranslationQuery {
+    pub namespace: String,
+    pub key: String,
+}
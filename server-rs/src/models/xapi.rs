@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A tenant's Learning Record Store.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct LrsEndpoint {
+    #[serde(skip)]
+    pub tenant_id: String,
+    /// The LRS's xAPI base URL; statements are posted to `{endpoint}/statements`.
+    pub endpoint: String,
+    pub username: String,
+    #[serde(skip)]
+    pub password: String,
+    pub is_active: bool,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxStatement {
+    pub id: Uuid,
+    pub statement: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLrsRequest {
+    pub endpoint: String,
+    pub username: String,
+    pub password: String,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Resets statements to pending: the `failed` ones by default, or the ones
+/// already `sent`, for an LRS that lost them. `since` limits it to
+/// statements written from then on.
+#[derive(Debug, Deserialize)]
+pub struct ReplayStatementsRequest {
+    pub status: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+}
@@ -8,8 +8,12 @@ use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::features;
 use crate::middleware::tenant::TenantId;
 use crate::models::comment::*;
+use crate::models::translation::*;
+use crate::services::moderation_filter::{self, Severity};
+use crate::services::{parental_consent, translations};
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -20,21 +24,42 @@ pub struct AdminQuery {
     pub search: Option<String>,
 }
 
+/// Dashboard numbers are cached this long, so a dashboard left open does
+/// not rerun the counts on every refresh.
+const STATS_TTL_SECS: u64 = 30;
+
+pub(crate) fn user_detail_key(tenant_id: &str, player_id: Uuid) -> String {
+    format!("admin_user:{}:{}", tenant_id, player_id)
+}
+
 pub async fn stats(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
-    let comments: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM comments WHERE tenant_id = $1").bind(tid).fetch_one(&state.db).await?;
-    let reviews: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM game_reviews WHERE tenant_id = $1").bind(tid).fetch_one(&state.db).await?;
-    let reports: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM content_reports WHERE tenant_id = $1 AND status = 'open'").bind(tid).fetch_one(&state.db).await?;
-    let players: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM players WHERE tenant_id = $1").bind(tid).fetch_one(&state.db).await?;
-    let flagged: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM comments WHERE tenant_id = $1 AND report_count > 0 AND status = 'published'").bind(tid).fetch_one(&state.db).await?;
+    let key = format!("admin_stats:{}", tid);
+    if let Some(cached) = state.cache.get_json::<Value>(&key).await {
+        return Ok(Json(cached));
+    }
 
-    Ok(Json(json!({
+    let (comments, reviews, reports, players, flagged): (i64, i64, i64, i64, i64) = sqlx::query_as(
+        r#"SELECT
+            (SELECT COUNT(*) FROM comments WHERE tenant_id = $1),
+            (SELECT COUNT(*) FROM game_reviews WHERE tenant_id = $1),
+            (SELECT COUNT(*) FROM content_reports WHERE tenant_id = $1 AND status = 'open'),
+            (SELECT COUNT(*) FROM players WHERE tenant_id = $1),
+            (SELECT COUNT(*) FROM comments WHERE tenant_id = $1 AND report_count > 0 AND status = 'published')"#,
+    )
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
+
+    let body = json!({
         "comments": comments, "reviews": reviews, "openReports": reports,
         "players": players, "flaggedContent": flagged,
-    })))
+    });
+    state.cache.set_json(&key, &body, STATS_TTL_SECS).await;
+    Ok(Json(body))
 }
 
 pub async fn moderation_queue(
@@ -48,8 +73,8 @@ pub async fn moderation_queue(
     let rows: Vec<(Uuid, String, String, i32, chrono::DateTime<chrono::Utc>, String)> = sqlx::query_as(
         r#"SELECT c.id, c.body, c.game_id, c.report_count, c.created_at, p.display_name
         FROM comments c JOIN players p ON p.id = c.player_id AND p.tenant_id = c.tenant_id
-        WHERE c.tenant_id = $1 AND c.report_count > 0 AND c.status = 'published'
-        ORDER BY c.report_count DESC LIMIT $2 OFFSET $3"#,
+        WHERE c.tenant_id = $1 AND (c.status = 'pending' OR (c.report_count > 0 AND c.status = 'published'))
+        ORDER BY c.status = 'pending' DESC, c.report_count DESC LIMIT $2 OFFSET $3"#,
     )
     .bind(&tenant.0 .0)
     .bind(limit)
@@ -116,6 +141,11 @@ async fn moderate_content(
                 .bind(new_status).bind(cid).bind(tenant_id)
                 .execute(&state.db).await?;
         }
+        "chat_message" => {
+            sqlx::query("UPDATE chat_messages SET status = $1 WHERE id = $2 AND tenant_id = $3")
+                .bind(new_status).bind(cid).bind(tenant_id)
+                .execute(&state.db).await?;
+        }
         _ => {}
     }
 
@@ -161,6 +191,14 @@ pub async fn remove_review(State(state): State<AppState>, player: axum::Extensio
     moderate_content(&state, player.id, &tenant.0 .0, "review", &id, "remove", "removed").await?;
     Ok(Json(json!({"success": true})))
 }
+pub async fn approve_chat_message(State(state): State<AppState>, player: axum::Extension<AuthPlayer>, tenant: axum::Extension<TenantId>, Path(id): Path<String>) -> AppResult<Json<Value>> {
+    moderate_content(&state, player.id, &tenant.0 .0, "chat_message", &id, "approve", "visible").await?;
+    Ok(Json(json!({"success": true})))
+}
+pub async fn remove_chat_message(State(state): State<AppState>, player: axum::Extension<AuthPlayer>, tenant: axum::Extension<TenantId>, Path(id): Path<String>) -> AppResult<Json<Value>> {
+    moderate_content(&state, player.id, &tenant.0 .0, "chat_message", &id, "remove", "removed").await?;
+    Ok(Json(json!({"success": true})))
+}
 
 pub async fn resolve_report(
     State(state): State<AppState>,
@@ -216,6 +254,22 @@ pub async fn search_users(
     Ok(Json(json!({ "users": users })))
 }
 
+#[derive(sqlx::FromRow)]
+struct UserDetailRow {
+    id: Uuid,
+    display_name: String,
+    email: Option<String>,
+    total_score: i64,
+    games_played: i32,
+    admin_role: Option<String>,
+    is_guest: bool,
+    birth_year: Option<i16>,
+    consent_status: String,
+    comments: i64,
+    reviews: i64,
+    reports: i64,
+}
+
 pub async fn get_user_detail(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
@@ -224,21 +278,82 @@ pub async fn get_user_detail(
     let uid = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user ID".into()))?;
     let tid = &tenant.0 .0;
 
-    let player: Option<(Uuid, String, Option<String>, i64, i32, Option<String>, bool)> = sqlx::query_as(
-        "SELECT id, display_name, email, total_score, games_played, admin_role, is_guest FROM players WHERE id = $1 AND tenant_id = $2",
+    let key = user_detail_key(tid, uid);
+    if let Some(cached) = state.cache.get_json::<Value>(&key).await {
+        return Ok(Json(cached));
+    }
+
+    let player: Option<UserDetailRow> = sqlx::query_as(
+        r#"SELECT p.id, p.display_name, p.email, p.total_score, p.games_played, p.admin_role, p.is_guest,
+            p.birth_year, p.consent_status,
+            (SELECT COUNT(*) FROM comments WHERE player_id = p.id AND tenant_id = p.tenant_id) AS comments,
+            (SELECT COUNT(*) FROM game_reviews WHERE player_id = p.id AND tenant_id = p.tenant_id) AS reviews,
+            (SELECT COUNT(*) FROM content_reports WHERE reporter_id = p.id AND tenant_id = p.tenant_id) AS reports
+        FROM players p WHERE p.id = $1 AND p.tenant_id = $2"#,
     ).bind(uid).bind(tid).fetch_optional(&state.db).await?;
 
     let player = player.ok_or_else(|| AppError::NotFound("Player not found".into()))?;
 
-    let comments_count: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM comments WHERE player_id = $1 AND tenant_id = $2").bind(uid).bind(tid).fetch_one(&state.db).await?;
-    let reviews_count: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM game_reviews WHERE player_id = $1 AND tenant_id = $2").bind(uid).bind(tid).fetch_one(&state.db).await?;
-    let reports_count: i64 = sqlx::query_scalar("SELECT COUNT(*)::bigint FROM content_reports WHERE reporter_id = $1 AND tenant_id = $2").bind(uid).bind(tid).fetch_one(&state.db).await?;
+    let body = json!({
+        "id": player.id, "displayName": player.display_name, "email": player.email,
+        "totalScore": player.total_score, "gamesPlayed": player.games_played,
+        "adminRole": player.admin_role, "isGuest": player.is_guest,
+        "birthYear": player.birth_year, "consentStatus": player.consent_status,
+        "counts": {"comments": player.comments, "reviews": player.reviews, "reports": player.reports}
+    });
+    state.cache.set_json(&key, &body, STATS_TTL_SECS).await;
+    Ok(Json(body))
+}
+
+#[derive(sqlx::FromRow)]
+struct ConsentRow {
+    id: Uuid,
+    display_name: String,
+    birth_year: Option<i16>,
+    consent_status: String,
+    parent_email: Option<String>,
+    requested_at: Option<chrono::DateTime<chrono::Utc>>,
+    responded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
 
-    Ok(Json(json!({
-        "id": player.0, "displayName": player.1, "email": player.2,
-        "totalScore": player.3, "gamesPlayed": player.4, "adminRole": player.5, "isGuest": player.6,
-        "counts": {"comments": comments_count, "reviews": reviews_count, "reports": reports_count}
-    })))
+/// Players under the age gate, with their latest consent request. Lists
+/// everyone who isn't `not_required` unless `status` narrows it down.
+pub async fn list_consents(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AdminQuery>,
+) -> AppResult<Json<Value>> {
+    if let Some(status) = &q.status {
+        if !parental_consent::STATUSES.contains(&status.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "status must be one of: {}",
+                parental_consent::STATUSES.join(", ")
+            )));
+        }
+    }
+    let limit = q.limit.unwrap_or(20).min(50);
+    let offset = q.page.unwrap_or(0) * limit;
+
+    let rows: Vec<ConsentRow> = sqlx::query_as(
+        r#"SELECT p.id, p.display_name, p.birth_year, p.consent_status, c.parent_email,
+            c.created_at AS requested_at, c.responded_at
+        FROM players p
+        LEFT JOIN LATERAL (
+            SELECT parent_email, created_at, responded_at FROM parental_consents
+            WHERE player_id = p.id AND tenant_id = p.tenant_id ORDER BY created_at DESC LIMIT 1
+        ) c ON TRUE
+        WHERE p.tenant_id = $1 AND (p.consent_status = $2 OR ($2 IS NULL AND p.consent_status <> 'not_required'))
+        ORDER BY c.created_at DESC NULLS LAST LIMIT $3 OFFSET $4"#,
+    )
+    .bind(&tenant.0 .0).bind(&q.status).bind(limit).bind(offset)
+    .fetch_all(&state.db).await?;
+
+    let players: Vec<Value> = rows.iter().map(|r| {
+        json!({"id": r.id, "displayName": r.display_name, "birthYear": r.birth_year, "consentStatus": r.consent_status,
+            "parentEmail": r.parent_email, "requestedAt": r.requested_at, "respondedAt": r.responded_at})
+    }).collect();
+
+    Ok(Json(json!({ "players": players })))
 }
 
 pub async fn warn_user(
@@ -264,14 +379,41 @@ pub async fn ban_user(
     let uid = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid user ID".into()))?;
     let tid = &tenant.0 .0;
 
-    sqlx::query("UPDATE comments SET status = 'hidden' WHERE player_id = $1 AND tenant_id = $2").bind(uid).bind(tid).execute(&state.db).await?;
-    sqlx::query("UPDATE game_reviews SET status = 'hidden' WHERE player_id = $1 AND tenant_id = $2").bind(uid).bind(tid).execute(&state.db).await?;
-    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, target_player_id, created_at) VALUES ($1, $2, 'ban_user', 'player', $3, NOW())")
-        .bind(player.id).bind(tid).bind(uid)
-        .execute(&state.db).await?;
+    // The hidden content and its previous status go into the log entry, so
+    // a ban reversed on appeal can put it back.
+    let mut tx = state.db.begin().await?;
+    let comments = hide_player_content(&mut tx, "comments", tid, uid).await?;
+    let reviews = hide_player_content(&mut tx, "game_reviews", tid, uid).await?;
+    sqlx::query("UPDATE players SET is_banned = TRUE WHERE id = $1 AND tenant_id = $2").bind(uid).bind(tid).execute(&mut *tx).await?;
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, target_player_id, metadata, created_at) VALUES ($1, $2, 'ban_user', 'player', $3, $4, NOW())")
+        .bind(player.id).bind(tid).bind(uid).bind(json!({"hidden": {"comments": comments, "reviews": reviews}}))
+        .execute(&mut *tx).await?;
+    tx.commit().await?;
+    // Sign the player out everywhere
+    crate::middleware::auth::revoke_tokens(&state.db, &state.cache, tid, uid, state.config.jwt.refresh_expiry_secs.max(0) as u64).await?;
     Ok(Json(json!({"success": true})))
 }
 
+/// Hides the player's content in `table`. Returns the previous status of
+/// each row hidden, keyed by id.
+async fn hide_player_content(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    tenant_id: &str,
+    player_id: Uuid,
+) -> AppResult<serde_json::Map<String, Value>> {
+    let rows: Vec<(Uuid, String)> = sqlx::query_as(&format!(
+        r#"UPDATE {table} t SET status = 'hidden' FROM {table} old
+        WHERE t.id = old.id AND t.player_id = $1 AND t.tenant_id = $2 AND t.status <> 'hidden'
+        RETURNING t.id, old.status"#
+    ))
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows.into_iter().map(|(id, status)| (id.to_string(), json!(status))).collect())
+}
+
 pub async fn set_role(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -286,6 +428,7 @@ pub async fn set_role(
     sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, target_player_id, metadata, created_at) VALUES ($1, $2, 'set_role', 'player', $3, $4, NOW())")
         .bind(player.id).bind(&tenant.0 .0).bind(uid).bind(json!({"role": body.role}))
         .execute(&state.db).await?;
+    state.cache.del(&user_detail_key(&tenant.0 .0, uid)).await;
     Ok(Json(json!({"success": true})))
 }
 
@@ -311,3 +454,325 @@ pub async fn moderation_log(
 
     Ok(Json(json!({ "log": entries })))
 }
+
+// ---------------------------------------------------------------------------
+// Content filter lists
+// ---------------------------------------------------------------------------
+
+/// The tenant's own terms, plus the built-in list they apply on top of.
+pub async fn list_filter_terms(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let terms = sqlx::query_as::<_, FilterTerm>(
+        "SELECT id, term, severity, created_at FROM moderation_terms WHERE tenant_id = $1 ORDER BY term",
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+
+    let defaults: Vec<Value> = moderation_filter::DEFAULT_TERMS
+        .iter()
+        .map(|(term, severity)| json!({"term": term, "severity": severity}))
+        .collect();
+
+    Ok(Json(json!({ "terms": terms, "defaults": defaults })))
+}
+
+/// Adds a term, or changes the severity of an existing one.
+pub async fn add_filter_term(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<AddFilterTermRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if Severity::parse(&body.severity).is_none() && body.severity != moderation_filter::ALLOW {
+        return Err(AppError::BadRequest("Severity must be low, medium, high or allow".into()));
+    }
+    let term = moderation_filter::normalize(&body.term);
+    if term.is_empty() || term.len() > 100 {
+        return Err(AppError::BadRequest("Term must be 1-100 characters".into()));
+    }
+
+    let entry = sqlx::query_as::<_, FilterTerm>(
+        r#"INSERT INTO moderation_terms (tenant_id, term, severity, created_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (tenant_id, term) DO UPDATE SET severity = EXCLUDED.severity
+        RETURNING id, term, severity, created_at"#,
+    )
+    .bind(tid)
+    .bind(&term)
+    .bind(&body.severity)
+    .bind(player.id)
+    .fetch_one(&state.db)
+    .await?;
+
+    moderation_filter::invalidate(&state.cache, tid).await;
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, metadata, created_at) VALUES ($1, $2, 'set_filter_term', 'filter_term', $3, $4, NOW())")
+        .bind(player.id).bind(tid).bind(entry.id.to_string()).bind(json!({"term": entry.term, "severity": entry.severity}))
+        .execute(&state.db).await?;
+
+    Ok(Json(json!({ "term": entry })))
+}
+
+pub async fn delete_filter_term(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let term: Option<String> = sqlx::query_scalar(
+        "DELETE FROM moderation_terms WHERE id = $1 AND tenant_id = $2 RETURNING term",
+    )
+    .bind(id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?;
+    let term = term.ok_or_else(|| AppError::NotFound("Term not found".into()))?;
+
+    moderation_filter::invalidate(&state.cache, tid).await;
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, metadata, created_at) VALUES ($1, $2, 'delete_filter_term', 'filter_term', $3, $4, NOW())")
+        .bind(player.id).bind(tid).bind(id.to_string()).bind(json!({"term": term}))
+        .execute(&state.db).await?;
+
+    Ok(Json(json!({"success": true})))
+}
+
+/// Runs text through the tenant's filter without storing anything.
+pub async fn test_filter(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<TestFilterRequest>,
+) -> AppResult<Json<Value>> {
+    let terms = moderation_filter::tenant_terms(&state.db, &state.cache, &tenant.0 .0).await?;
+    let verdict = moderation_filter::evaluate(&body.text, &terms);
+    let action = match verdict.severity {
+        None => "allow",
+        Some(Severity::Low) => "flag",
+        Some(Severity::Medium) => "hold",
+        Some(Severity::High) => "reject",
+    };
+
+    Ok(Json(json!({
+        "normalized": moderation_filter::normalize(&body.text),
+        "severity": verdict.severity,
+        "matched": verdict.matched,
+        "action": action,
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// Translations
+// ---------------------------------------------------------------------------
+
+/// Most entries accepted by one `PUT`.
+const MAX_TRANSLATION_ENTRIES: usize = 500;
+
+fn parse_locale(locale: &str) -> AppResult<String> {
+    translations::normalize_locale(locale)
+        .ok_or_else(|| AppError::BadRequest("Invalid locale".into()))
+}
+
+/// The tenant's translations, optionally for one locale and/or namespace.
+pub async fn list_translations(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<TranslationQuery>,
+) -> AppResult<Json<Value>> {
+    let locale = q.locale.as_deref().map(parse_locale).transpose()?;
+    let rows = sqlx::query_as::<_, Translation>(
+        r#"SELECT locale, namespace, key, value, updated_at FROM translations
+        WHERE tenant_id = $1 AND ($2::text IS NULL OR locale = $2) AND ($3::text IS NULL OR namespace = $3)
+        ORDER BY locale, namespace, key"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(locale)
+    .bind(&q.namespace)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "translations": rows, "namespaces": translations::NAMESPACES })))
+}
+
+/// Adds or replaces translations for one locale.
+pub async fn set_translations(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(locale): Path<String>,
+    Json(body): Json<SetTranslationsRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let locale = parse_locale(&locale)?;
+    if body.entries.is_empty() || body.entries.len() > MAX_TRANSLATION_ENTRIES {
+        return Err(AppError::BadRequest(format!(
+            "Provide 1-{} entries",
+            MAX_TRANSLATION_ENTRIES
+        )));
+    }
+    for e in &body.entries {
+        if !translations::NAMESPACES.contains(&e.namespace.as_str()) {
+            return Err(AppError::BadRequest(format!("Unknown namespace '{}'", e.namespace)));
+        }
+        if e.key.is_empty() || e.key.len() > 500 || e.value.is_empty() || e.value.len() > 2000 {
+            return Err(AppError::BadRequest(
+                "Keys must be 1-500 characters and values 1-2000".into(),
+            ));
+        }
+    }
+
+    let mut tx = state.db.begin().await?;
+    for e in &body.entries {
+        sqlx::query(
+            r#"INSERT INTO translations (tenant_id, locale, namespace, key, value, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            ON CONFLICT (tenant_id, locale, namespace, key) DO UPDATE SET
+                value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = NOW()"#,
+        )
+        .bind(tid)
+        .bind(&locale)
+        .bind(&e.namespace)
+        .bind(&e.key)
+        .bind(&e.value)
+        .bind(player.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    translations::invalidate(&state.cache, tid, &locale).await;
+    Ok(Json(json!({ "locale": locale, "count": body.entries.len() })))
+}
+
+pub async fn delete_translation(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(locale): Path<String>,
+    Query(q): Query<DeleteTranslationQuery>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let locale = parse_locale(&locale)?;
+    let deleted = sqlx::query(
+        "DELETE FROM translations WHERE tenant_id = $1 AND locale = $2 AND namespace = $3 AND key = $4",
+    )
+    .bind(tid)
+    .bind(&locale)
+    .bind(&q.namespace)
+    .bind(&q.key)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound("Translation not found".into()));
+    }
+
+    translations::invalidate(&state.cache, tid, &locale).await;
+    Ok(Json(json!({"success": true})))
+}
+
+// ---------------------------------------------------------------------------
+// Feature flags
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct SetFeatureRequest {
+    pub enabled: bool,
+}
+
+/// Every built-in flag and every override for the tenant.
+pub async fn list_features(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<(String, bool, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT flag, enabled, updated_at FROM feature_flags WHERE tenant_id = $1 ORDER BY flag",
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut flags: Vec<Value> = features::FLAGS
+        .iter()
+        .map(|(flag, default, description)| {
+            let set = rows.iter().find(|(f, _, _)| f == flag);
+            json!({
+                "flag": flag,
+                "description": description,
+                "default": default,
+                "enabled": set.map_or(*default, |(_, on, _)| *on),
+                "overridden": set.is_some(),
+                "updatedAt": set.map(|(_, _, at)| at),
+            })
+        })
+        .collect();
+    flags.extend(
+        rows.iter()
+            .filter(|(f, _, _)| features::default_for(f).is_none())
+            .map(|(flag, enabled, at)| {
+                json!({
+                    "flag": flag,
+                    "description": null,
+                    "default": null,
+                    "enabled": enabled,
+                    "overridden": true,
+                    "updatedAt": at,
+                })
+            }),
+    );
+
+    Ok(Json(json!({ "flags": flags })))
+}
+
+pub async fn set_feature(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(flag): Path<String>,
+    Json(body): Json<SetFeatureRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if !features::is_valid_name(&flag) {
+        return Err(AppError::BadRequest(
+            "Flag names are lower snake case, up to 64 characters".into(),
+        ));
+    }
+
+    sqlx::query(
+        r#"INSERT INTO feature_flags (tenant_id, flag, enabled, updated_by, updated_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (tenant_id, flag) DO UPDATE SET
+            enabled = EXCLUDED.enabled, updated_by = EXCLUDED.updated_by, updated_at = NOW()"#,
+    )
+    .bind(tid)
+    .bind(&flag)
+    .bind(body.enabled)
+    .bind(player.id)
+    .execute(&state.db)
+    .await?;
+
+    features::invalidate(&state.cache, tid).await;
+    Ok(Json(json!({ "flag": flag, "enabled": body.enabled })))
+}
+
+/// Removes the tenant's override, so the flag goes back to its default.
+pub async fn delete_feature(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(flag): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let deleted = sqlx::query("DELETE FROM feature_flags WHERE tenant_id = $1 AND flag = $2")
+        .bind(tid)
+        .bind(&flag)
+        .execute(&state.db)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound("Flag not found".into()));
+    }
+
+    features::invalidate(&state.cache, tid).await;
+    Ok(Json(json!({ "flag": flag, "enabled": features::default_for(&flag) })))
+}
@@ -0,0 +1,184 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::anticheat::*;
+use crate::models::game_progress::ScoreSubmission;
+use crate::services::score_voids::{self, Target};
+use crate::AppState;
+
+const STATUSES: [&str; 4] = ["open", "reviewed", "dismissed", "actioned"];
+
+const FLAG_COLUMNS: &str = r#"f.id, f.player_id, p.display_name, f.flag_type, f.severity, f.details, f.match_id,
+    f.game_id, f.score_ids, f.status, f.reviewed_by, f.reviewed_at, f.created_at"#;
+
+async fn fetch_flag(state: &AppState, tenant_id: &str, id: Uuid) -> AppResult<AnticheatFlag> {
+    sqlx::query_as(&format!(
+        r#"SELECT {} FROM anticheat_flags f
+        LEFT JOIN players p ON p.id = f.player_id AND p.tenant_id = f.tenant_id
+        WHERE f.id = $1 AND f.tenant_id = $2"#,
+        FLAG_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Flag not found".into()))
+}
+
+/// Admin: the suspected cheaters queue, most recently raised first, with
+/// the number of flags in each status. Filter by `status`, `flagType` and
+/// `gameId`.
+pub async fn list_flags(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AnticheatFlagQuery>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if q.status.as_deref().is_some_and(|s| !STATUSES.contains(&s)) {
+        return Err(AppError::BadRequest(format!("status must be one of {}", STATUSES.join(", "))));
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+
+    let counts: Vec<(String, i64)> =
+        sqlx::query_as("SELECT status, COUNT(*) FROM anticheat_flags WHERE tenant_id = $1 GROUP BY status")
+            .bind(tid)
+            .fetch_all(&state.db)
+            .await?;
+    let flags: Vec<AnticheatFlag> = sqlx::query_as(&format!(
+        r#"SELECT {} FROM anticheat_flags f
+        LEFT JOIN players p ON p.id = f.player_id AND p.tenant_id = f.tenant_id
+        WHERE f.tenant_id = $1
+            AND ($2::text IS NULL OR f.status = $2)
+            AND ($3::text IS NULL OR f.flag_type = $3)
+            AND ($4::text IS NULL OR f.game_id = $4)
+        ORDER BY f.created_at DESC LIMIT $5 OFFSET $6"#,
+        FLAG_COLUMNS
+    ))
+    .bind(tid)
+    .bind(&q.status)
+    .bind(&q.flag_type)
+    .bind(&q.game_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let counts: serde_json::Map<String, Value> = STATUSES
+        .iter()
+        .map(|s| {
+            let n = counts.iter().find(|(status, _)| status == s).map_or(0, |(_, n)| *n);
+            (s.to_string(), json!(n))
+        })
+        .collect();
+
+    Ok(Json(json!({ "counts": counts, "flags": flags })))
+}
+
+/// Admin: a flag with the submissions it names.
+pub async fn get_flag(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let flag = fetch_flag(&state, &tenant.0 .0, id).await?;
+    let scores: Vec<ScoreSubmission> = match &flag.score_ids {
+        Some(ids) => {
+            sqlx::query_as(
+                r#"SELECT id, player_id, score, level, play_time, device_fingerprint, created_at, voided_at FROM score_history
+                WHERE tenant_id = $1 AND id = ANY($2) ORDER BY created_at"#,
+            )
+            .bind(&tenant.0 .0)
+            .bind(ids)
+            .fetch_all(&state.db)
+            .await?
+        }
+        None => Vec::new(),
+    };
+    Ok(Json(json!({ "flag": flag, "scores": scores })))
+}
+
+/// Admin: voids an open score flag's submissions (see
+/// [`score_voids`]) and marks it actioned.
+pub async fn void_scores(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let mut tx = state.db.begin().await?;
+
+    let row: Option<(Option<String>, Option<Vec<i64>>)> = sqlx::query_as(
+        "SELECT game_id, score_ids FROM anticheat_flags WHERE id = $1 AND tenant_id = $2 AND status = 'open' FOR UPDATE",
+    )
+    .bind(id)
+    .bind(tid)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((game_id, score_ids)) = row else {
+        return Err(AppError::NotFound("No open flag with that id".into()));
+    };
+    let (Some(game_id), Some(score_ids)) = (game_id, score_ids) else {
+        return Err(AppError::BadRequest("Flag has no score submissions to void".into()));
+    };
+
+    let voided = score_voids::void(&mut tx, tid, &game_id, Target::Scores(&score_ids)).await?;
+    for v in &voided {
+        score_voids::log(&mut tx, tid, player.id, &game_id, v, None, json!({"flagId": id})).await?;
+    }
+    sqlx::query(
+        "UPDATE anticheat_flags SET status = 'actioned', reviewed_by = $3, reviewed_at = NOW() WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(id)
+    .bind(tid)
+    .bind(player.id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if !voided.is_empty() {
+        score_voids::refresh_board(&state, tid, &game_id).await?;
+    }
+
+    let flag = fetch_flag(&state, tid, id).await?;
+    Ok(Json(json!({
+        "flag": flag,
+        "voided": voided.iter().map(|v| v.score_ids.len()).sum::<usize>(),
+        "highScore": voided.first().and_then(|v| v.high_score).map(|(_, to)| to),
+    })))
+}
+
+/// Admin: closes an open flag without touching its submissions.
+pub async fn dismiss_flag(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let updated = sqlx::query(
+        "UPDATE anticheat_flags SET status = 'dismissed', reviewed_by = $3, reviewed_at = NOW() WHERE id = $1 AND tenant_id = $2 AND status = 'open'",
+    )
+    .bind(id)
+    .bind(tid)
+    .bind(player.id)
+    .execute(&state.db)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("No open flag with that id".into()));
+    }
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, created_at) VALUES ($1, $2, 'dismiss_flag', 'anticheat_flag', $3, NOW())")
+        .bind(player.id).bind(tid).bind(id)
+        .execute(&state.db).await?;
+
+    let flag = fetch_flag(&state, tid, id).await?;
+    Ok(Json(json!({ "flag": flag })))
+}
@@ -0,0 +1,126 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::api_key::{self, SCOPES};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::api_key::*;
+use crate::AppState;
+
+/// Characters of a key kept to identify it: the prefix and a few more.
+const KEY_PREFIX_LEN: usize = 10;
+const MAX_ACTIVE_KEYS: i64 = 20;
+const KEY_COLUMNS: &str =
+    "id, name, key_prefix, scopes, created_by, created_at, last_used_at, expires_at, revoked_at";
+
+/// Admin: the tenant's API keys, live ones only unless `includeRevoked`,
+/// and the scopes a key can have.
+pub async fn list_keys(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<ApiKeyQuery>,
+) -> AppResult<Json<Value>> {
+    let keys: Vec<ApiKey> = sqlx::query_as(&format!(
+        r#"SELECT {} FROM api_keys
+        WHERE tenant_id = $1
+            AND ($2 OR (revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())))
+        ORDER BY created_at DESC"#,
+        KEY_COLUMNS
+    ))
+    .bind(&tenant.0 .0)
+    .bind(q.include_revoked.unwrap_or(false))
+    .fetch_all(&state.db)
+    .await?;
+
+    let scopes: Vec<Value> = SCOPES
+        .iter()
+        .map(|(scope, description)| json!({ "scope": scope, "description": description }))
+        .collect();
+
+    Ok(Json(json!({ "keys": keys, "scopes": scopes })))
+}
+
+/// Admin: create a key. The key itself is in this response only.
+pub async fn create_key(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let name = body.name.trim();
+    if name.is_empty() || name.len() > 128 {
+        return Err(AppError::BadRequest("name must be 1-128 characters".into()));
+    }
+    if body.scopes.is_empty() {
+        return Err(AppError::BadRequest("At least one scope is required".into()));
+    }
+    if let Some(scope) = body.scopes.iter().find(|s| !SCOPES.iter().any(|(known, _)| known == s)) {
+        return Err(AppError::BadRequest(format!("Unknown scope: {}", scope)));
+    }
+    if body.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(AppError::BadRequest("expiresAt must be in the future".into()));
+    }
+
+    let active: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM api_keys
+        WHERE tenant_id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())"#,
+    )
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
+    if active >= MAX_ACTIVE_KEYS {
+        return Err(AppError::Conflict(format!(
+            "A tenant can have at most {} active API keys",
+            MAX_ACTIVE_KEYS
+        )));
+    }
+
+    let mut scopes = body.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let key = api_key::generate();
+    let created: ApiKey = sqlx::query_as(&format!(
+        r#"INSERT INTO api_keys (tenant_id, name, key_prefix, key_hash, scopes, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}"#,
+        KEY_COLUMNS
+    ))
+    .bind(tid)
+    .bind(name)
+    .bind(&key[..KEY_PREFIX_LEN])
+    .bind(api_key::hash(&key))
+    .bind(&scopes)
+    .bind(player.id)
+    .bind(body.expires_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "apiKey": created, "key": key })))
+}
+
+/// Admin: revoke a key. Requests with it fail from now on.
+pub async fn revoke_key(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let revoked = sqlx::query(
+        "UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND tenant_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(&tenant.0 .0)
+    .execute(&state.db)
+    .await?;
+    if revoked.rows_affected() == 0 {
+        return Err(AppError::NotFound("API key not found".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
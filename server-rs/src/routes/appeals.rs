@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::comment::*;
+use crate::AppState;
+
+/// Moderation actions a player can appeal.
+const APPEALABLE_ACTIONS: &[&str] = &["warn_user", "ban_user"];
+
+/// How long after an action it can still be appealed.
+const APPEAL_WINDOW_DAYS: i64 = 30;
+
+const MAX_MESSAGE_LEN: usize = 2000;
+
+const ACTION_SQL: &str = r#"SELECT ml.id, ml.action, ml.reason, ml.metadata,
+        ml.admin_id::uuid AS admin_id, p.display_name AS admin_name,
+        ml.target_player_id::uuid AS target_player_id, ml.created_at
+    FROM moderation_log ml
+    LEFT JOIN players p ON p.id::text = ml.admin_id AND p.tenant_id = ml.tenant_id"#;
+
+fn is_appealable(action: &ModerationAction) -> bool {
+    action.created_at > Utc::now() - Duration::days(APPEAL_WINDOW_DAYS)
+}
+
+/// What the player sees of an action taken against them; moderators are
+/// not named.
+fn player_action_json(action: &ModerationAction, appeal: Option<&Appeal>) -> Value {
+    json!({
+        "id": action.id,
+        "action": action.action,
+        "reason": action.reason,
+        "createdAt": action.created_at,
+        "appealable": appeal.is_none() && is_appealable(action),
+        "appeal": appeal,
+    })
+}
+
+/// The player's warnings and bans, each with its appeal if there is one.
+pub async fn my_appeals(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let actions: Vec<ModerationAction> = sqlx::query_as(&format!(
+        "{} WHERE ml.tenant_id = $1 AND ml.target_player_id = $2::text AND ml.action = ANY($3) ORDER BY ml.created_at DESC LIMIT 50",
+        ACTION_SQL
+    ))
+    .bind(tid)
+    .bind(player.id)
+    .bind(APPEALABLE_ACTIONS)
+    .fetch_all(&state.db)
+    .await?;
+
+    let appeals: Vec<Appeal> = sqlx::query_as("SELECT * FROM moderation_appeals WHERE tenant_id = $1 AND player_id = $2")
+        .bind(tid)
+        .bind(player.id)
+        .fetch_all(&state.db)
+        .await?;
+    let appeals: HashMap<&str, &Appeal> = appeals.iter().map(|a| (a.action_id.as_str(), a)).collect();
+
+    let actions: Vec<Value> = actions
+        .iter()
+        .map(|a| player_action_json(a, appeals.get(a.id.as_str()).copied()))
+        .collect();
+    Ok(Json(json!({ "actions": actions, "appealWindowDays": APPEAL_WINDOW_DAYS })))
+}
+
+/// Appeals a warning or ban. One appeal per action, within
+/// [`APPEAL_WINDOW_DAYS`] of it.
+pub async fn create_appeal(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateAppealRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let message = body.message.trim();
+    if message.is_empty() || message.chars().count() > MAX_MESSAGE_LEN {
+        return Err(AppError::BadRequest(format!(
+            "Message must be between 1 and {} characters",
+            MAX_MESSAGE_LEN
+        )));
+    }
+
+    let action: ModerationAction = sqlx::query_as(&format!(
+        "{} WHERE ml.id = $1 AND ml.tenant_id = $2 AND ml.target_player_id = $3::text AND ml.action = ANY($4)",
+        ACTION_SQL
+    ))
+    .bind(&body.action_id)
+    .bind(tid)
+    .bind(player.id)
+    .bind(APPEALABLE_ACTIONS)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Moderation action not found".into()))?;
+
+    if !is_appealable(&action) {
+        return Err(AppError::Conflict(format!(
+            "Actions can only be appealed within {} days",
+            APPEAL_WINDOW_DAYS
+        )));
+    }
+
+    let appeal: Appeal = sqlx::query_as(
+        r#"INSERT INTO moderation_appeals (tenant_id, player_id, action_id, message)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (tenant_id, action_id) DO NOTHING
+        RETURNING *"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&action.id)
+    .bind(message)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::Conflict("This action has already been appealed".into()))?;
+
+    Ok(Json(json!({ "appeal": appeal })))
+}
+
+/// Appeals for moderators, with the action each one is about. Open appeals
+/// come oldest first; resolved ones most recently resolved first.
+pub async fn appeal_queue(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AppealQueueQuery>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+    let status = q.status.as_deref().unwrap_or("open");
+    if !["open", "upheld", "reversed"].contains(&status) {
+        return Err(AppError::BadRequest(format!("Unknown status: {}", status)));
+    }
+
+    let appeals: Vec<Appeal> = sqlx::query_as(
+        r#"SELECT * FROM moderation_appeals
+        WHERE tenant_id = $1 AND status = $2
+        ORDER BY CASE WHEN status = 'open' THEN created_at END, resolved_at DESC
+        LIMIT $3 OFFSET $4"#,
+    )
+    .bind(tid)
+    .bind(status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let action_ids: Vec<&str> = appeals.iter().map(|a| a.action_id.as_str()).collect();
+    let actions: Vec<ModerationAction> = sqlx::query_as(&format!("{} WHERE ml.tenant_id = $1 AND ml.id = ANY($2)", ACTION_SQL))
+        .bind(tid)
+        .bind(&action_ids)
+        .fetch_all(&state.db)
+        .await?;
+    let actions: HashMap<&str, &ModerationAction> = actions.iter().map(|a| (a.id.as_str(), a)).collect();
+
+    // Earlier warnings and bans give the moderator the player's history.
+    let player_ids: Vec<Uuid> = appeals.iter().map(|a| a.player_id).collect();
+    let players: Vec<(Uuid, String, i64)> = sqlx::query_as(
+        r#"SELECT p.id, p.display_name,
+            (SELECT COUNT(*)::bigint FROM moderation_log ml
+             WHERE ml.tenant_id = p.tenant_id AND ml.target_player_id = p.id::text AND ml.action = ANY($3))
+        FROM players p WHERE p.tenant_id = $1 AND p.id = ANY($2)"#,
+    )
+    .bind(tid)
+    .bind(&player_ids)
+    .bind(APPEALABLE_ACTIONS)
+    .fetch_all(&state.db)
+    .await?;
+    let players: HashMap<Uuid, (&str, i64)> = players.iter().map(|(id, name, n)| (*id, (name.as_str(), *n))).collect();
+
+    let queue: Vec<Value> = appeals
+        .iter()
+        .map(|appeal| {
+            let mut v = json!(appeal);
+            let (name, actions_against) = players.get(&appeal.player_id).copied().unwrap_or(("", 0));
+            v["playerName"] = json!(name);
+            v["actionsAgainstPlayer"] = json!(actions_against);
+            v["action"] = match actions.get(appeal.action_id.as_str()) {
+                Some(a) => json!({
+                    "id": a.id,
+                    "action": a.action,
+                    "reason": a.reason,
+                    "metadata": a.metadata,
+                    "adminId": a.admin_id,
+                    "adminName": a.admin_name,
+                    "createdAt": a.created_at,
+                }),
+                None => Value::Null,
+            };
+            v
+        })
+        .collect();
+
+    Ok(Json(json!({ "appeals": queue })))
+}
+
+/// Puts back the content a ban hid, unless a moderator has changed it
+/// since.
+async fn restore_hidden(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    tenant_id: &str,
+    hidden: Option<&Value>,
+) -> AppResult<u64> {
+    let Some(hidden) = hidden.filter(|h| h.is_object()) else { return Ok(0) };
+    let restored = sqlx::query(&format!(
+        r#"UPDATE {table} t SET status = prev.value
+        FROM jsonb_each_text($1) prev
+        WHERE t.id = prev.key::uuid AND t.tenant_id = $2 AND t.status = 'hidden'"#
+    ))
+    .bind(hidden)
+    .bind(tenant_id)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    Ok(restored)
+}
+
+/// Undoes the action. A warning has nothing to undo beyond the record; a
+/// ban is lifted and the content it hid is restored. Returns what was
+/// restored.
+async fn reverse_action(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    tenant_id: &str,
+    action: &ModerationAction,
+) -> AppResult<Value> {
+    if action.action != "ban_user" {
+        return Ok(json!({}));
+    }
+    sqlx::query("UPDATE players SET is_banned = FALSE, ban_reason = NULL, ban_expires_at = NULL WHERE id = $1 AND tenant_id = $2")
+        .bind(action.target_player_id)
+        .bind(tenant_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let hidden = action.metadata.as_ref().and_then(|m| m.get("hidden"));
+    let comments = restore_hidden(tx, "comments", tenant_id, hidden.and_then(|h| h.get("comments"))).await?;
+    let reviews = restore_hidden(tx, "game_reviews", tenant_id, hidden.and_then(|h| h.get("reviews"))).await?;
+    Ok(json!({ "comments": comments, "reviews": reviews }))
+}
+
+/// Upholds or reverses the appealed action. Reversing undoes it straight
+/// away; either way the outcome is logged and the player is told.
+pub async fn resolve_appeal(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ResolveAppealRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if !["upheld", "reversed"].contains(&body.outcome.as_str()) {
+        return Err(AppError::BadRequest("outcome must be 'upheld' or 'reversed'".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let appeal: Appeal = sqlx::query_as("SELECT * FROM moderation_appeals WHERE id = $1 AND tenant_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(tid)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Appeal not found".into()))?;
+
+    if appeal.status != "open" {
+        return Err(AppError::Conflict(format!("Appeal is already {}", appeal.status)));
+    }
+    if appeal.player_id == player.id {
+        return Err(AppError::Forbidden("You can't resolve your own appeal".into()));
+    }
+
+    let action: ModerationAction = sqlx::query_as(&format!("{} WHERE ml.id = $1 AND ml.tenant_id = $2", ACTION_SQL))
+        .bind(&appeal.action_id)
+        .bind(tid)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let restored = if body.outcome == "reversed" {
+        reverse_action(&mut tx, tid, &action).await?
+    } else {
+        json!({})
+    };
+
+    let resolved: Appeal = sqlx::query_as(
+        r#"UPDATE moderation_appeals SET status = $2, resolved_by = $3, resolution_note = $4, resolved_at = NOW()
+        WHERE id = $1
+        RETURNING *"#,
+    )
+    .bind(id)
+    .bind(&body.outcome)
+    .bind(player.id)
+    .bind(&body.note)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, target_player_id, reason, metadata, created_at) VALUES ($1, $2, $3, 'appeal', $4, $5, $6, $7, NOW())")
+        .bind(player.id)
+        .bind(tid)
+        .bind(format!("appeal_{}", body.outcome))
+        .bind(id.to_string())
+        .bind(appeal.player_id)
+        .bind(&body.note)
+        .bind(json!({ "actionId": action.id, "action": action.action, "restored": restored }))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    state
+        .realtime
+        .send_to(tid, appeal.player_id, &json!({ "type": "appeal_resolved", "appeal": resolved }))
+        .await;
+
+    Ok(Json(json!({ "appeal": resolved, "restored": restored })))
+}
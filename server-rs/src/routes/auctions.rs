@@ -0,0 +1,299 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::auction::*;
+use crate::routes::economy::{NON_TRANSFERABLE_TYPES, TRADE_CURRENCIES};
+use crate::services::auction_house::{self as house, auction_json};
+use crate::AppState;
+
+/// Open auctions, ending soonest first.
+pub async fn list_auctions(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AuctionQuery>,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(20).clamp(1, 50);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<Auction> = sqlx::query_as(
+        r#"SELECT * FROM auctions
+        WHERE tenant_id = $1 AND status = 'open' AND ends_at > NOW()
+          AND ($2::text IS NULL OR item_id = $2)
+        ORDER BY ends_at LIMIT $3 OFFSET $4"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(&q.item_id)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let has_more = rows.len() as i64 > limit;
+    let auctions: Vec<Value> = rows.iter().take(limit as usize).map(auction_json).collect();
+    Ok(Json(json!({ "auctions": auctions, "hasMore": has_more })))
+}
+
+/// The player's own listings and the open auctions they have bid on.
+pub async fn my_auctions(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let selling: Vec<Auction> = sqlx::query_as(
+        "SELECT * FROM auctions WHERE tenant_id = $1 AND seller_id = $2 ORDER BY created_at DESC LIMIT 50",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let bidding: Vec<Auction> = sqlx::query_as(
+        r#"SELECT * FROM auctions
+        WHERE tenant_id = $1 AND status = 'open'
+          AND id IN (SELECT auction_id FROM auction_bids WHERE tenant_id = $1 AND bidder_id = $2)
+        ORDER BY ends_at"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let bidding: Vec<Value> = bidding
+        .iter()
+        .map(|a| {
+            let mut v = auction_json(a);
+            v["leading"] = json!(a.high_bidder_id == Some(player.id));
+            v
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "selling": selling.iter().map(auction_json).collect::<Vec<_>>(),
+        "bidding": bidding,
+    })))
+}
+
+pub async fn get_auction(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let auction: Auction = sqlx::query_as("SELECT * FROM auctions WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tid)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Auction not found".into()))?;
+
+    let bids: Vec<AuctionBid> = sqlx::query_as(
+        "SELECT id, bidder_id, amount, created_at FROM auction_bids WHERE auction_id = $1 ORDER BY amount DESC LIMIT 50",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "auction": auction_json(&auction), "bids": bids })))
+}
+
+/// Lists an owned item. The item is held by the auction until it settles
+/// or is cancelled.
+pub async fn create_auction(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateAuctionRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let quantity = body.quantity.unwrap_or(1);
+    if quantity <= 0 || body.min_bid <= 0 {
+        return Err(AppError::BadRequest("Quantity and minimum bid must be positive".into()));
+    }
+    if !(house::MIN_DURATION_MINUTES..=house::MAX_DURATION_MINUTES).contains(&body.duration_minutes) {
+        return Err(AppError::BadRequest(format!(
+            "durationMinutes must be between {} and {}",
+            house::MIN_DURATION_MINUTES,
+            house::MAX_DURATION_MINUTES
+        )));
+    }
+    let currency = body.currency_type.as_deref().unwrap_or("coins");
+    if !TRADE_CURRENCIES.contains(&currency) {
+        return Err(AppError::BadRequest(format!("Unknown currency: {}", currency)));
+    }
+
+    let item_type: String = sqlx::query_scalar("SELECT item_type FROM store_items WHERE id = $1 AND tenant_id = $2")
+        .bind(&body.item_id)
+        .bind(tid)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Item not found: {}", body.item_id)))?;
+    if NON_TRANSFERABLE_TYPES.contains(&item_type.as_str()) {
+        return Err(AppError::BadRequest(format!("Item cannot be traded: {}", body.item_id)));
+    }
+
+    let open: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM auctions WHERE tenant_id = $1 AND seller_id = $2 AND status = 'open'",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_one(&state.db)
+    .await?;
+    if open >= house::MAX_OPEN_PER_SELLER {
+        return Err(AppError::Conflict(format!(
+            "You can have at most {} open auctions",
+            house::MAX_OPEN_PER_SELLER
+        )));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let auction: Auction = sqlx::query_as(
+        r#"INSERT INTO auctions (tenant_id, seller_id, item_id, quantity, currency_type, min_bid, ends_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(mins => $7))
+        RETURNING *"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&body.item_id)
+    .bind(quantity)
+    .bind(currency)
+    .bind(body.min_bid)
+    .bind(body.duration_minutes as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+    house::take_item(&mut tx, &auction, player.id, "auction_escrow").await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "auction": auction_json(&auction) })))
+}
+
+/// Places a bid. The amount is taken from the wallet now and the previous
+/// high bidder is refunded; a bid in the last minute extends the auction.
+pub async fn place_bid(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<PlaceBidRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let mut tx = state.db.begin().await?;
+    let auction: Auction = sqlx::query_as("SELECT * FROM auctions WHERE id = $1 AND tenant_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(tid)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Auction not found".into()))?;
+
+    let now = Utc::now();
+    if auction.status != "open" || auction.ends_at <= now {
+        return Err(AppError::Conflict("Auction has ended".into()));
+    }
+    if auction.seller_id == player.id {
+        return Err(AppError::BadRequest("You can't bid on your own auction".into()));
+    }
+    let min = house::min_next_bid(&auction);
+    if body.amount < min {
+        return Err(AppError::BadRequest(format!("Bid must be at least {}", min)));
+    }
+
+    // Wallets are locked in player id order so that crossing bids on two
+    // auctions can't deadlock. Raising your own bid refunds it first.
+    let outbid = auction.high_bidder_id.zip(auction.current_bid);
+    match outbid {
+        Some((prev, amount)) if prev <= player.id => {
+            house::credit(&mut tx, &auction, prev, amount, "auction_refund").await?;
+            house::debit(&mut tx, &auction, player.id, body.amount, "auction_bid").await?;
+        }
+        Some((prev, amount)) => {
+            house::debit(&mut tx, &auction, player.id, body.amount, "auction_bid").await?;
+            house::credit(&mut tx, &auction, prev, amount, "auction_refund").await?;
+        }
+        None => house::debit(&mut tx, &auction, player.id, body.amount, "auction_bid").await?,
+    }
+
+    let (ends_at, extended) = if auction.ends_at - now < Duration::seconds(house::SNIPE_WINDOW_SECS) {
+        (now + Duration::seconds(house::SNIPE_EXTENSION_SECS), 1)
+    } else {
+        (auction.ends_at, 0)
+    };
+
+    sqlx::query("INSERT INTO auction_bids (tenant_id, auction_id, bidder_id, amount) VALUES ($1, $2, $3, $4)")
+        .bind(tid)
+        .bind(id)
+        .bind(player.id)
+        .bind(body.amount)
+        .execute(&mut *tx)
+        .await?;
+
+    let updated: Auction = sqlx::query_as(
+        r#"UPDATE auctions SET current_bid = $2, high_bidder_id = $3, bid_count = bid_count + 1,
+            ends_at = $4, extensions = extensions + $5
+        WHERE id = $1
+        RETURNING *"#,
+    )
+    .bind(id)
+    .bind(body.amount)
+    .bind(player.id)
+    .bind(ends_at)
+    .bind(extended)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    if let Some((prev, _)) = outbid.filter(|(prev, _)| *prev != player.id) {
+        house::notify(&state, &updated, prev, "auction_outbid").await;
+    }
+    house::notify(&state, &updated, updated.seller_id, "auction_bid").await;
+
+    Ok(Json(json!({ "auction": auction_json(&updated), "extended": extended == 1 })))
+}
+
+/// Withdraws a listing that nobody has bid on yet.
+pub async fn cancel_auction(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let mut tx = state.db.begin().await?;
+    let auction: Auction = sqlx::query_as("SELECT * FROM auctions WHERE id = $1 AND tenant_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(&tenant.0 .0)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Auction not found".into()))?;
+
+    if auction.seller_id != player.id {
+        return Err(AppError::Forbidden("Only the seller can cancel an auction".into()));
+    }
+    if auction.status != "open" {
+        return Err(AppError::Conflict(format!("Auction is already {}", auction.status)));
+    }
+    if auction.bid_count > 0 {
+        return Err(AppError::Conflict("Auctions with bids can't be cancelled".into()));
+    }
+
+    house::give_item(&mut tx, &auction, player.id, "auction_return").await?;
+    let cancelled: Auction = sqlx::query_as(
+        "UPDATE auctions SET status = 'cancelled', settled_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "auction": auction_json(&cancelled) })))
+}
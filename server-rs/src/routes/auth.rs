@@ -1,16 +1,43 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use chrono::{Datelike, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
-use crate::middleware::auth::{generate_tokens, verify_token};
+use crate::middleware::age_gate;
+use crate::middleware::auth::{
+    generate_tokens, is_refresh_revoked, is_revoked, revoke_refresh_token, revoke_tokens, verify_token,
+    AuthPlayer, CurrentSession,
+};
 use crate::middleware::tenant::TenantId;
 use crate::models::player::*;
+use crate::services::oauth::{self, OAuthIdentity};
+use crate::services::parental_consent;
+use crate::services::sessions::{self, ClientInfo};
 use crate::AppState;
 
+/// Sign-in started by `oauth_start`, kept in Redis until the provider
+/// redirects back to the callback.
+#[derive(Debug, Serialize, Deserialize)]
+struct OAuthPending {
+    provider: String,
+    tenant_id: String,
+    guest_player_id: Option<Uuid>,
+    redirect_uri: Option<String>,
+}
+
 pub async fn guest(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
+    headers: HeaderMap,
     Json(body): Json<GuestRequest>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
@@ -36,14 +63,9 @@ pub async fn guest(
     .fetch_one(&state.db)
     .await?;
 
-    let (token, refresh_token) = generate_tokens(
-        player.id,
-        tenant_id,
-        None,
-        &state.config.jwt.secret,
-        state.config.jwt.access_expiry_secs,
-        state.config.jwt.refresh_expiry_secs,
-    )?;
+    let client = ClientInfo::from_headers(&headers);
+    let (token, refresh_token) =
+        sessions::start(&state, player.id, tenant_id, None, &client).await?;
 
     Ok(Json(json!({
         "token": token,
@@ -52,9 +74,13 @@ pub async fn guest(
     })))
 }
 
+/// Birth years further back than this are rejected as typos.
+const MAX_AGE: i32 = 120;
+
 pub async fn register(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
+    headers: HeaderMap,
     Json(body): Json<RegisterRequest>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
@@ -78,6 +104,29 @@ pub async fn register(
         return Err(AppError::Conflict("Email already registered".into()));
     }
 
+    let current_year = Utc::now().year();
+    let needs_consent = match body.birth_year {
+        Some(year) if !(current_year - MAX_AGE..=current_year).contains(&year) => {
+            return Err(AppError::BadRequest("birthYear is out of range".into()));
+        }
+        Some(year) => age_gate::needs_consent(year, current_year),
+        None => false,
+    };
+    let parent_email = if needs_consent {
+        let email = body
+            .parent_email
+            .as_deref()
+            .map(str::trim)
+            .filter(|e| parental_consent::is_valid_email(e))
+            .ok_or_else(|| AppError::BadRequest("A parent's email is required for players under 13".into()))?;
+        if email.eq_ignore_ascii_case(body.email.trim()) {
+            return Err(AppError::BadRequest("parentEmail must be a parent's address, not the player's".into()));
+        }
+        Some(email)
+    } else {
+        None
+    };
+
     let password_hash =
         bcrypt::hash(&body.password, 12).map_err(|e| AppError::Internal(e.to_string()))?;
 
@@ -93,10 +142,12 @@ pub async fn register(
         .unwrap_or_else(|| "robot".to_string());
 
     let player: Player = sqlx::query_as(
-        r#"INSERT INTO players (id, tenant_id, email, password_hash, display_name, avatar_character, is_guest, total_score, games_played)
-        VALUES ($1, $2, $3, $4, $5, $6, false, 0, 0)
+        r#"INSERT INTO players (id, tenant_id, email, password_hash, display_name, avatar_character, is_guest, total_score, games_played,
+            birth_year, consent_status)
+        VALUES ($1, $2, $3, $4, $5, $6, false, 0, 0, $7, $8)
         ON CONFLICT (id, tenant_id) DO UPDATE SET
-            email = EXCLUDED.email, password_hash = EXCLUDED.password_hash, is_guest = false
+            email = EXCLUDED.email, password_hash = EXCLUDED.password_hash, is_guest = false,
+            birth_year = EXCLUDED.birth_year, consent_status = EXCLUDED.consent_status
         RETURNING *"#,
     )
     .bind(player_id)
@@ -105,9 +156,15 @@ pub async fn register(
     .bind(&password_hash)
     .bind(&display_name)
     .bind(&avatar)
+    .bind(body.birth_year.map(|y| y as i16))
+    .bind(if needs_consent { "pending" } else { "not_required" })
     .fetch_one(&state.db)
     .await?;
 
+    if let Some(parent_email) = parent_email {
+        parental_consent::request(&state, tenant_id, player.id, &player.display_name, parent_email).await?;
+    }
+
     let progress: Vec<(String, i64, i32, i32)> = sqlx::query_as(
         "SELECT game_id, high_score, stars, play_count FROM game_progress WHERE player_id = $1 AND tenant_id = $2",
     )
@@ -116,14 +173,9 @@ pub async fn register(
     .fetch_all(&state.db)
     .await?;
 
-    let (token, refresh_token) = generate_tokens(
-        player.id,
-        tenant_id,
-        None,
-        &state.config.jwt.secret,
-        state.config.jwt.access_expiry_secs,
-        state.config.jwt.refresh_expiry_secs,
-    )?;
+    let client = ClientInfo::from_headers(&headers);
+    let (token, refresh_token) =
+        sessions::start(&state, player.id, tenant_id, None, &client).await?;
 
     let progress_map: serde_json::Map<String, Value> = progress
         .into_iter()
@@ -136,6 +188,7 @@ pub async fn register(
         "token": token,
         "refreshToken": refresh_token,
         "player": PlayerPublic::from(&player),
+        "consentStatus": player.consent_status,
         "progress": progress_map,
     })))
 }
@@ -143,6 +196,7 @@ pub async fn register(
 pub async fn login(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
+    headers: HeaderMap,
     Json(body): Json<LoginRequest>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
@@ -184,14 +238,9 @@ pub async fn login(
     .fetch_all(&state.db)
     .await?;
 
-    let (token, refresh_token) = generate_tokens(
-        player.id,
-        tenant_id,
-        player.admin_role.as_deref(),
-        &state.config.jwt.secret,
-        state.config.jwt.access_expiry_secs,
-        state.config.jwt.refresh_expiry_secs,
-    )?;
+    let client = ClientInfo::from_headers(&headers);
+    let (token, refresh_token) =
+        sessions::start(&state, player.id, tenant_id, player.admin_role.as_deref(), &client).await?;
 
     let progress_map: serde_json::Map<String, Value> = progress
         .into_iter()
@@ -204,37 +253,496 @@ pub async fn login(
         "token": token,
         "refreshToken": refresh_token,
         "player": PlayerPublic::from(&player),
+        "consentStatus": player.consent_status,
         "progress": progress_map,
     })))
 }
 
+/// The consent request behind a link from a parental consent email, for
+/// the consent page. Needs no sign-in: the token is the credential.
+pub async fn get_parental_consent(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Json<Value>> {
+    let request = parental_consent::find(&state.db, &token).await?;
+    Ok(Json(json!({
+        "displayName": request.display_name,
+        "status": request.status,
+        "requestedAt": request.created_at,
+        "expiresAt": request.expires_at,
+        "respondedAt": request.responded_at,
+    })))
+}
+
+/// The parent's answer to a consent request.
+pub async fn answer_parental_consent(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Json(body): Json<ConsentDecisionRequest>,
+) -> AppResult<Json<Value>> {
+    let grant = match body.decision.as_str() {
+        "grant" => true,
+        "deny" => false,
+        _ => return Err(AppError::BadRequest("decision must be 'grant' or 'deny'".into())),
+    };
+    let request = parental_consent::respond(&state.db, &token, grant).await?;
+
+    state
+        .cache
+        .del(&crate::routes::admin::user_detail_key(&request.tenant_id, request.player_id))
+        .await;
+    let event = json!({ "type": "parental_consent", "status": request.status });
+    state.realtime.send_to(&request.tenant_id, request.player_id, &event).await;
+
+    Ok(Json(json!({
+        "displayName": request.display_name,
+        "status": request.status,
+        "respondedAt": request.responded_at,
+    })))
+}
+
+/// Exchanges a refresh token for a new pair in the same session. Refresh
+/// tokens are single use: the one presented is revoked, so a stolen copy
+/// stops working as soon as either party refreshes.
 pub async fn refresh(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
 ) -> AppResult<Json<Value>> {
     let token = body["refreshToken"]
         .as_str()
         .ok_or_else(|| AppError::BadRequest("refreshToken required".into()))?;
 
-    let claims = verify_token(token, &state.config.jwt.secret)?;
+    let claims = verify_token(token, &state.config.jwt)?;
     if claims.token_type.as_deref() != Some("refresh") {
         return Err(AppError::Unauthorized("Refresh token required".into()));
     }
-
     let player_id = Uuid::parse_str(&claims.sub)
         .map_err(|_| AppError::Unauthorized("Invalid token".into()))?;
 
-    let (new_token, new_refresh) = generate_tokens(
-        player_id,
-        &claims.tenant_id,
-        claims.role.as_deref(),
-        &state.config.jwt.secret,
-        state.config.jwt.access_expiry_secs,
-        state.config.jwt.refresh_expiry_secs,
-    )?;
+    if is_revoked(&state.cache, &claims).await
+        || is_refresh_revoked(&state.db, &claims, player_id).await?
+        || !revoke_refresh_token(&state.db, &claims, player_id).await?
+    {
+        return Err(AppError::Unauthorized("Token revoked".into()));
+    }
+
+    // Tokens from before sessions existed start one
+    let client = ClientInfo::from_headers(&headers);
+    let role = claims.role.as_deref();
+    let (new_token, new_refresh) = match claims.sid.as_deref().and_then(|s| Uuid::parse_str(s).ok()) {
+        Some(sid) => {
+            if !sessions::touch(&state, sid, player_id, &client).await? {
+                return Err(AppError::Unauthorized("Token revoked".into()));
+            }
+            generate_tokens(player_id, &claims.tenant_id, role, sid, &state.config.jwt)?
+        }
+        None => sessions::start(&state, player_id, &claims.tenant_id, role, &client).await?,
+    };
 
     Ok(Json(json!({
         "token": new_token,
         "refreshToken": new_refresh,
     })))
 }
+
+/// Signs out one device by revoking its refresh token and session.
+pub async fn logout(
+    State(state): State<AppState>,
+    Json(body): Json<Value>,
+) -> AppResult<Json<Value>> {
+    let token = body["refreshToken"]
+        .as_str()
+        .ok_or_else(|| AppError::BadRequest("refreshToken required".into()))?;
+
+    let claims = verify_token(token, &state.config.jwt)?;
+    if claims.token_type.as_deref() != Some("refresh") {
+        return Err(AppError::Unauthorized("Refresh token required".into()));
+    }
+    let player_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Unauthorized("Invalid token".into()))?;
+    revoke_refresh_token(&state.db, &claims, player_id).await?;
+    if let Some(sid) = claims.sid.as_deref().and_then(|s| Uuid::parse_str(s).ok()) {
+        sessions::revoke(&state, &claims.tenant_id, player_id, Some(sid), None).await?;
+    }
+
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Signs the player out everywhere: every access and refresh token issued
+/// so far stops working.
+pub async fn logout_all(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+) -> AppResult<Json<Value>> {
+    revoke_tokens(
+        &state.db,
+        &state.cache,
+        &player.tenant_id,
+        player.id,
+        state.config.jwt.refresh_expiry_secs.max(0) as u64,
+    )
+    .await?;
+    Ok(Json(json!({ "success": true })))
+}
+
+/// The player's active sessions; `current` marks the one making the
+/// request.
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    current: Option<axum::Extension<CurrentSession>>,
+) -> AppResult<Json<Value>> {
+    let current = current.map(|c| c.0 .0);
+    let sessions: Vec<Value> = sessions::list(&state.db, &player.tenant_id, player.id)
+        .await?
+        .into_iter()
+        .map(|s| {
+            let is_current = Some(s.id) == current;
+            let mut v = json!(s);
+            v["current"] = json!(is_current);
+            v
+        })
+        .collect();
+    Ok(Json(json!({ "sessions": sessions })))
+}
+
+/// Signs out one of the player's devices.
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let revoked = sessions::revoke(&state, &player.tenant_id, player.id, Some(id), None).await?;
+    if revoked.is_empty() {
+        return Err(AppError::NotFound("Session not found".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeSessionsQuery {
+    /// Keep the session making the request signed in.
+    #[serde(rename = "keepCurrent")]
+    pub keep_current: Option<bool>,
+}
+
+/// Signs out every device, or every other device with `keepCurrent`.
+pub async fn revoke_sessions(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    current: Option<axum::Extension<CurrentSession>>,
+    Query(q): Query<RevokeSessionsQuery>,
+) -> AppResult<Json<Value>> {
+    let keep = current.map(|c| c.0 .0).filter(|_| q.keep_current.unwrap_or(false));
+    let revoked = match keep {
+        Some(keep) => sessions::revoke(&state, &player.tenant_id, player.id, None, Some(keep)).await?.len(),
+        None => {
+            let revoked = sessions::revoke(&state, &player.tenant_id, player.id, None, None).await?.len();
+            revoke_tokens(
+                &state.db,
+                &state.cache,
+                &player.tenant_id,
+                player.id,
+                state.config.jwt.refresh_expiry_secs.max(0) as u64,
+            )
+            .await?;
+            revoked
+        }
+    };
+    Ok(Json(json!({ "success": true, "revoked": revoked })))
+}
+
+fn oauth_state_key(state: &str) -> String {
+    format!("oauth_state:{}", state)
+}
+
+fn oauth_callback_url(state: &AppState, provider: &str) -> String {
+    format!(
+        "{}/api/v1/auth/oauth/{}/callback",
+        state.config.oauth.callback_base_url.trim_end_matches('/'),
+        provider
+    )
+}
+
+/// Only redirect back to origins we already trust for CORS.
+fn is_allowed_redirect(origins: &[String], uri: &str) -> bool {
+    match reqwest::Url::parse(uri) {
+        Ok(url) => {
+            let origin = url.origin().ascii_serialization();
+            origins.iter().any(|o| o.trim_end_matches('/') == origin)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Begin a Google / Apple / Microsoft sign-in. When called with a guest's
+/// access token, the guest account is upgraded in place on success.
+pub async fn oauth_start(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    Path(provider): Path<String>,
+    Query(query): Query<OAuthStartQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let config = state
+        .config
+        .oauth
+        .provider(&provider)
+        .ok_or_else(|| AppError::NotFound(format!("Sign-in with '{}' is not enabled", provider)))?;
+
+    if let Some(uri) = &query.redirect_uri {
+        if !is_allowed_redirect(&state.config.cors_origins, uri) {
+            return Err(AppError::BadRequest("redirectUri is not an allowed origin".into()));
+        }
+    }
+
+    let nonce: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    let pending = OAuthPending {
+        provider: provider.clone(),
+        tenant_id: tenant_id.clone(),
+        guest_player_id: player
+            .filter(|p| &p.tenant_id == tenant_id)
+            .map(|p| p.id),
+        redirect_uri: query.redirect_uri,
+    };
+    state
+        .cache
+        .set_json(&oauth_state_key(&nonce), &pending, state.config.oauth.state_ttl_secs)
+        .await;
+
+    let url = oauth::authorization_url(
+        &provider,
+        config,
+        &oauth_callback_url(&state, &provider),
+        &nonce,
+    )?;
+
+    Ok(Json(json!({
+        "authorizationUrl": url,
+        "state": nonce,
+    })))
+}
+
+/// Provider redirect target for Google and Microsoft (query string).
+pub async fn oauth_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<OAuthCallbackParams>,
+) -> AppResult<Response> {
+    complete_oauth(state, provider, params, &headers).await
+}
+
+/// Provider redirect target for Apple, which posts the result as a form.
+pub async fn oauth_callback_form(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    Form(params): Form<OAuthCallbackParams>,
+) -> AppResult<Response> {
+    complete_oauth(state, provider, params, &headers).await
+}
+
+async fn complete_oauth(
+    state: AppState,
+    provider: String,
+    params: OAuthCallbackParams,
+    headers: &HeaderMap,
+) -> AppResult<Response> {
+    let nonce = params
+        .state
+        .ok_or_else(|| AppError::BadRequest("Missing state".into()))?;
+
+    // Single use: consume the pending sign-in before doing anything else
+    let key = oauth_state_key(&nonce);
+    let pending: OAuthPending = state
+        .cache
+        .get_json(&key)
+        .await
+        .ok_or_else(|| AppError::Unauthorized("Sign-in expired, please try again".into()))?;
+    state.cache.del(&key).await;
+
+    if pending.provider != provider {
+        return Err(AppError::Unauthorized("Sign-in state mismatch".into()));
+    }
+
+    // The user cancelled or the provider refused; hand the error back to the app
+    if let Some(error) = params.error {
+        let error: String = error
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        return match pending.redirect_uri.as_deref() {
+            Some(uri) => Ok(redirect_with_fragment(uri, &format!("error={}", error))),
+            None => Err(AppError::Unauthorized(format!("Sign-in failed: {}", error))),
+        };
+    }
+
+    let code = params
+        .code
+        .ok_or_else(|| AppError::BadRequest("Missing authorization code".into()))?;
+    let config = state
+        .config
+        .oauth
+        .provider(&provider)
+        .ok_or_else(|| AppError::NotFound(format!("Sign-in with '{}' is not enabled", provider)))?;
+
+    let identity =
+        oauth::exchange_code(config, &oauth_callback_url(&state, &provider), &code).await?;
+
+    // Apple leaves the name out of the ID token and sends it once, alongside
+    // the first authorization
+    let apple_name = params
+        .user
+        .as_deref()
+        .and_then(|u| serde_json::from_str::<Value>(u).ok())
+        .and_then(|u| {
+            let first = u["name"]["firstName"].as_str().unwrap_or("");
+            let last = u["name"]["lastName"].as_str().unwrap_or("");
+            let full = format!("{} {}", first, last).trim().to_string();
+            (!full.is_empty()).then_some(full)
+        });
+    let display_name = identity.name.clone().or(apple_name);
+
+    let (player, linked_by) =
+        resolve_oauth_player(&state, &pending, &provider, &identity, display_name).await?;
+
+    let client = ClientInfo::from_headers(headers);
+    let (token, refresh_token) =
+        sessions::start(&state, player.id, &pending.tenant_id, player.admin_role.as_deref(), &client).await?;
+
+    match pending.redirect_uri.as_deref() {
+        Some(uri) => Ok(redirect_with_fragment(
+            uri,
+            &format!(
+                "token={}&refreshToken={}&linkedBy={}",
+                token, refresh_token, linked_by
+            ),
+        )),
+        None => Ok(Json(json!({
+            "token": token,
+            "refreshToken": refresh_token,
+            "player": PlayerPublic::from(&player),
+            "linkedBy": linked_by,
+        }))
+        .into_response()),
+    }
+}
+
+/// Tokens travel in the fragment so they never reach server logs.
+fn redirect_with_fragment(uri: &str, fragment: &str) -> Response {
+    let base = uri.split('#').next().unwrap_or(uri);
+    Redirect::to(&format!("{}#{}", base, fragment)).into_response()
+}
+
+/// Finds or creates the player for a provider identity, in order:
+/// an account already linked to this provider login, a full account with
+/// the same verified email (linked now), the guest who started the
+/// sign-in (upgraded), or a brand-new account.
+async fn resolve_oauth_player(
+    state: &AppState,
+    pending: &OAuthPending,
+    provider: &str,
+    identity: &OAuthIdentity,
+    display_name: Option<String>,
+) -> AppResult<(Player, &'static str)> {
+    let tenant_id = &pending.tenant_id;
+
+    let existing: Option<Player> = sqlx::query_as(
+        r#"UPDATE players SET last_login_at = NOW()
+        WHERE tenant_id = $1 AND sso_provider = $2 AND sso_provider_id = $3
+        RETURNING *"#,
+    )
+    .bind(tenant_id)
+    .bind(provider)
+    .bind(&identity.subject)
+    .fetch_optional(&state.db)
+    .await?;
+    if let Some(player) = existing {
+        return Ok((player, "sso"));
+    }
+
+    // Unverified emails are never trusted for linking or stored on the account
+    let email = identity
+        .email
+        .as_deref()
+        .filter(|_| identity.email_verified);
+
+    if let Some(email) = email {
+        // An account that already uses another provider keeps that link
+        let linked: Option<Player> = sqlx::query_as(
+            r#"UPDATE players SET
+                sso_provider = COALESCE(sso_provider, $3),
+                sso_provider_id = CASE WHEN sso_provider IS NULL THEN $4 ELSE sso_provider_id END,
+                last_login_at = NOW()
+            WHERE tenant_id = $1 AND LOWER(email) = $2 AND is_guest = false
+            RETURNING *"#,
+        )
+        .bind(tenant_id)
+        .bind(email)
+        .bind(provider)
+        .bind(&identity.subject)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some(player) = linked {
+            return Ok((player, "email"));
+        }
+    }
+
+    if let Some(guest_id) = pending.guest_player_id {
+        let upgraded: Option<Player> = sqlx::query_as(
+            r#"UPDATE players SET
+                is_guest = false,
+                sso_provider = $3,
+                sso_provider_id = $4,
+                email = CASE WHEN $5::text IS NOT NULL AND NOT EXISTS (
+                    SELECT 1 FROM players o WHERE o.tenant_id = $2 AND LOWER(o.email) = $5 AND o.id <> $1
+                ) THEN $5 ELSE email END,
+                last_login_at = NOW()
+            WHERE id = $1 AND tenant_id = $2 AND is_guest = true
+            RETURNING *"#,
+        )
+        .bind(guest_id)
+        .bind(tenant_id)
+        .bind(provider)
+        .bind(&identity.subject)
+        .bind(email)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some(player) = upgraded {
+            return Ok((player, "guest_upgrade"));
+        }
+    }
+
+    let player_id = Uuid::new_v4();
+    let display_name =
+        display_name.unwrap_or_else(|| format!("Player_{}", &player_id.to_string()[..8]));
+
+    let player: Player = sqlx::query_as(
+        r#"INSERT INTO players (id, tenant_id, email, display_name, avatar_character, is_guest,
+            total_score, games_played, sso_provider, sso_provider_id, last_login_at)
+        VALUES ($1, $2,
+            CASE WHEN $3::text IS NOT NULL AND NOT EXISTS (
+                SELECT 1 FROM players WHERE tenant_id = $2 AND LOWER(email) = $3
+            ) THEN $3 END,
+            $4, 'robot', false, 0, 0, $5, $6, NOW())
+        RETURNING *"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(email)
+    .bind(&display_name)
+    .bind(provider)
+    .bind(&identity.subject)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok((player, "created"))
+}
@@ -1,5 +1,10 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
 use serde_json::{json, Value};
+use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
@@ -8,6 +13,25 @@ use crate::models::subscription::*;
 use crate::services::{subscription_sync, usage_meters, storage_quotas};
 use crate::AppState;
 
+/// Most invoices one page lists.
+const MAX_INVOICE_PAGE: u32 = 100;
+
+/// Billing details are for the organisation's owners and admins.
+async fn require_org_manager(state: &AppState, organisation_id: &str, player_id: Uuid, tenant_id: &str) -> AppResult<()> {
+    let role: Option<String> = sqlx::query_scalar(
+        "SELECT role FROM organisation_members WHERE organisation_id = $1 AND player_id = $2 AND tenant_id = $3",
+    )
+    .bind(organisation_id)
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+    match role.as_deref() {
+        Some("owner") | Some("admin") => Ok(()),
+        _ => Err(AppError::Forbidden("Must be org owner or admin".into())),
+    }
+}
+
 pub async fn subscribe(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -72,8 +96,18 @@ pub async fn subscribe(
         None
     };
 
+    let seats = if state.config.stripe.per_seat {
+        Some(subscription_sync::seat_count(&state.db, &org_id, tenant_id).await?)
+    } else {
+        None
+    };
+    let mut items = vec![(price_id.as_str(), seats)];
+    if !state.config.stripe.price_metered.is_empty() {
+        items.push((state.config.stripe.price_metered.as_str(), None));
+    }
+
     let sub = stripe
-        .create_subscription(&customer_id, &price_id, trial_days, &org_id, tenant_id)
+        .create_subscription(&customer_id, &items, trial_days, &org_id, tenant_id)
         .await?;
 
     // Sync to DB
@@ -153,9 +187,26 @@ pub async fn subscription_status(
 
     let trial_eligible = !subscription_sync::has_used_trial(&state.db, player.id, tenant_id).await?;
 
+    // A failed payment still being retried, or past its grace period.
+    let mut dunning = Value::Null;
+    if let Some(sub) = &sub {
+        let flags: Option<(DateTime<Utc>, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"SELECT payment_failed_at, billing_grace_until FROM organisations
+            WHERE id = $1 AND tenant_id = $2 AND payment_failed_at IS NOT NULL"#,
+        )
+        .bind(&sub.organisation_id)
+        .bind(tenant_id)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some((failed_at, grace_until)) = flags {
+            dunning = json!({ "paymentFailedAt": failed_at, "graceUntil": grace_until });
+        }
+    }
+
     Ok(Json(json!({
         "subscription": sub,
         "trialEligible": trial_eligible,
+        "dunning": dunning,
     })))
 }
 
@@ -287,3 +338,215 @@ pub async fn upgrade_badge(
         "meters": meters,
     })))
 }
+
+/// The organisation's seats: what's billed against its member count, and
+/// on a per-seat plan where they differ, what bringing them in line would
+/// add to the next invoice.
+pub async fn seats(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(query): Query<SeatsQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    let sub: Option<(String, String, Option<String>, Option<i32>)> = sqlx::query_as(
+        r#"SELECT stripe_subscription_id, stripe_customer_id, seat_item_id, seat_quantity FROM subscriptions
+        WHERE organisation_id = $1 AND tenant_id = $2 AND status IN ('active', 'trialing', 'past_due')
+        ORDER BY updated_at DESC LIMIT 1"#,
+    )
+    .bind(&query.organisation_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let (sub_id, customer_id, item_id, seats) =
+        sub.ok_or_else(|| AppError::NotFound("No active subscription".into()))?;
+
+    let per_seat = state.config.stripe.per_seat;
+    let members = subscription_sync::seat_count(&state.db, &query.organisation_id, tenant_id).await?;
+
+    let mut proration = Value::Null;
+    if let (true, Some(stripe), Some(item_id)) = (per_seat, state.stripe.as_ref(), item_id.as_deref()) {
+        if seats.map(i64::from) != Some(members) {
+            let invoice = stripe
+                .preview_quantity_change(&customer_id, &sub_id, item_id, members, &state.config.stripe.proration_behavior)
+                .await?;
+            let amount: i64 = invoice["lines"]["data"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|line| line["proration"].as_bool() == Some(true))
+                .filter_map(|line| line["amount"].as_i64())
+                .sum();
+            proration = json!({ "amount": amount, "currency": invoice["currency"] });
+        }
+    }
+
+    Ok(Json(json!({
+        "perSeat": per_seat,
+        "members": members,
+        "seats": seats,
+        "proration": proration,
+    })))
+}
+
+/// Bills the organisation for its current member count now, instead of at
+/// the next billing sync. Org owners and admins only.
+pub async fn sync_seats(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<SyncSeatsRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    require_org_manager(&state, &body.organisation_id, player.id, tenant_id).await?;
+    if !state.config.stripe.per_seat {
+        return Err(AppError::BadRequest("Plans are not billed per seat".into()));
+    }
+
+    let changed = subscription_sync::sync_seats(&state, &body.organisation_id, tenant_id).await?;
+    let seats = match changed {
+        Some(seats) => seats,
+        None => subscription_sync::seat_count(&state.db, &body.organisation_id, tenant_id).await?,
+    };
+
+    Ok(Json(json!({"seats": seats, "changed": changed.is_some()})))
+}
+
+/// The organisation's invoices from Stripe, newest first, with links to
+/// the hosted invoice, its PDF and the payment receipt.
+pub async fn invoices(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Query(query): Query<InvoicesQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let stripe = state.stripe.as_ref()
+        .ok_or_else(|| AppError::Internal("Stripe not configured".into()))?;
+    require_org_manager(&state, &query.organisation_id, player.id, tenant_id).await?;
+
+    let cid: Option<String> = sqlx::query_scalar(
+        "SELECT stripe_customer_id FROM organisations WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(&query.organisation_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?
+    .flatten();
+    let Some(cid) = cid.filter(|c| !c.is_empty()) else {
+        return Ok(Json(json!({"invoices": [], "hasMore": false})));
+    };
+
+    let limit = query.limit.unwrap_or(20).clamp(1, MAX_INVOICE_PAGE);
+    let page = stripe.list_invoices(&cid, limit, query.starting_after.as_deref()).await?;
+
+    let invoices: Vec<Value> = page["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|inv| {
+            json!({
+                "id": inv["id"],
+                "number": inv["number"],
+                "status": inv["status"],
+                "amountDue": inv["amount_due"],
+                "amountPaid": inv["amount_paid"],
+                "currency": inv["currency"],
+                "created": inv["created"],
+                "periodStart": inv["period_start"],
+                "periodEnd": inv["period_end"],
+                "hostedInvoiceUrl": inv["hosted_invoice_url"],
+                "invoicePdf": inv["invoice_pdf"],
+                "receiptUrl": inv["charge"]["receipt_url"],
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "invoices": invoices,
+        "hasMore": page["has_more"].as_bool().unwrap_or(false),
+    })))
+}
+
+/// Redeems a Stripe promotion code for the organisation. The discount goes
+/// on its active subscription, or on the customer for the next one it
+/// starts. Org owners and admins only.
+pub async fn redeem(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<RedeemCouponRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let stripe = state.stripe.as_ref()
+        .ok_or_else(|| AppError::Internal("Stripe not configured".into()))?;
+    require_org_manager(&state, &body.organisation_id, player.id, tenant_id).await?;
+
+    let code = body.code.trim();
+    if code.is_empty() {
+        return Err(AppError::BadRequest("code is required".into()));
+    }
+
+    let cid: Option<String> = sqlx::query_scalar(
+        "SELECT stripe_customer_id FROM organisations WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(&body.organisation_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?
+    .flatten();
+    let cid = cid
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| AppError::BadRequest("Organisation has no billing account yet".into()))?;
+
+    let promo = stripe
+        .find_promotion_code(code)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invalid or expired code".into()))?;
+    if promo["customer"].as_str().is_some_and(|c| c != cid) {
+        return Err(AppError::NotFound("Invalid or expired code".into()));
+    }
+    let promo_id = promo["id"].as_str().unwrap_or_default();
+
+    let sub_id: Option<String> = sqlx::query_scalar(
+        "SELECT stripe_subscription_id FROM subscriptions WHERE organisation_id = $1 AND tenant_id = $2 AND status IN ('active', 'trialing', 'past_due') LIMIT 1",
+    )
+    .bind(&body.organisation_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let applied_to = match sub_id {
+        Some(sub_id) => {
+            let sub = stripe.apply_promotion_code(&sub_id, promo_id).await?;
+            subscription_sync::sync_from_stripe(&state.db, &state.cache, &sub, tenant_id).await?;
+            "subscription"
+        }
+        None => {
+            stripe.apply_customer_promotion_code(&cid, promo_id).await?;
+            "customer"
+        }
+    };
+
+    let coupon = &promo["coupon"];
+    tracing::info!(
+        "Organisation {} redeemed promotion code {} on its {}",
+        body.organisation_id,
+        promo_id,
+        applied_to
+    );
+
+    Ok(Json(json!({
+        "success": true,
+        "appliedTo": applied_to,
+        "coupon": {
+            "id": coupon["id"],
+            "name": coupon["name"],
+            "percentOff": coupon["percent_off"],
+            "amountOff": coupon["amount_off"],
+            "currency": coupon["currency"],
+            "duration": coupon["duration"],
+            "durationInMonths": coupon["duration_in_months"],
+        },
+    })))
+}
@@ -0,0 +1,412 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::age_gate;
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::chat::*;
+use crate::models::comment::ReportRequest;
+use crate::services::moderation_filter;
+use crate::AppState;
+
+const MAX_MESSAGE_CHARS: usize = 500;
+const DEFAULT_PAGE: i64 = 50;
+const MAX_PAGE: i64 = 100;
+
+fn dm_channel(a: Uuid, b: Uuid) -> String {
+    let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+    format!("dm:{}:{}", lo, hi)
+}
+
+fn room_channel(room_id: &str) -> String {
+    format!("room:{}", room_id)
+}
+
+/// Direct messages need an accepted friendship and no block either way.
+async fn require_can_message(state: &AppState, tenant_id: &str, from: Uuid, to: Uuid) -> AppResult<()> {
+    let statuses: Vec<String> = sqlx::query_scalar(
+        r#"SELECT status FROM friendships
+        WHERE tenant_id = $1 AND ((player_id = $2 AND friend_id = $3) OR (player_id = $3 AND friend_id = $2))"#,
+    )
+    .bind(tenant_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&state.db)
+    .await?;
+
+    if statuses.iter().any(|s| s == "blocked") {
+        return Err(AppError::Forbidden("You can't message this player".into()));
+    }
+    if !statuses.iter().any(|s| s == "accepted") {
+        return Err(AppError::Forbidden("You can only message friends".into()));
+    }
+    Ok(())
+}
+
+/// Validates, rate-limits, screens and stores a message. Flagged messages
+/// are reported to the moderation queue; medium-severity ones are stored as
+/// `held` and not delivered.
+async fn store_message(
+    state: &AppState,
+    tenant_id: &str,
+    channel: &str,
+    sender: Uuid,
+    recipient: Option<Uuid>,
+    body: &str,
+) -> AppResult<ChatMessage> {
+    let body = body.trim();
+    if body.is_empty() || body.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "Message must be 1-{} characters",
+            MAX_MESSAGE_CHARS
+        )));
+    }
+    if !state.chat_rate_limiter.check(&format!("chat:{}", sender)).await {
+        return Err(AppError::RateLimited);
+    }
+
+    let verdict = moderation_filter::check(&state.db, &state.cache, tenant_id, &[body]).await?;
+    let status = if verdict.holds() { "held" } else { "visible" };
+    let message = sqlx::query_as::<_, ChatMessage>(
+        r#"INSERT INTO chat_messages (tenant_id, channel, sender_id, recipient_id, body, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, channel, sender_id, recipient_id, body, status, read_at, created_at"#,
+    )
+    .bind(tenant_id)
+    .bind(channel)
+    .bind(sender)
+    .bind(recipient)
+    .bind(body)
+    .bind(status)
+    .fetch_one(&state.db)
+    .await?;
+
+    moderation_filter::report(&state.db, tenant_id, "chat_message", message.id, &verdict).await?;
+
+    Ok(message)
+}
+
+/// Sends a direct message and pushes it to the recipient (and the sender's
+/// other sockets) as a `chat_message` event.
+pub async fn send_direct(
+    state: &AppState,
+    tenant_id: &str,
+    sender: Uuid,
+    recipient: Uuid,
+    body: &str,
+) -> AppResult<ChatMessage> {
+    if sender == recipient {
+        return Err(AppError::BadRequest("Cannot message yourself".into()));
+    }
+    require_can_message(state, tenant_id, sender, recipient).await?;
+
+    let message = store_message(state, tenant_id, &dm_channel(sender, recipient), sender, Some(recipient), body).await?;
+    if message.status == "visible" {
+        let event = json!({"type": "chat_message", "message": message});
+        state.realtime.send_to_many(tenant_id, &[recipient, sender], &event).await;
+    }
+    Ok(message)
+}
+
+/// Sends a message to a multiplayer room. Only the room's players can
+/// talk; spectators receive the chat too.
+pub async fn send_room(
+    state: &AppState,
+    tenant_id: &str,
+    sender: Uuid,
+    room_id: &str,
+    body: &str,
+) -> AppResult<ChatMessage> {
+    let room = state
+        .room_manager
+        .get_room(room_id)
+        .await
+        .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+    let Some(author) = room.players.iter().find(|p| p.id == sender) else {
+        return Err(AppError::Forbidden("Only players in the room can chat".into()));
+    };
+
+    let message = store_message(state, tenant_id, &room_channel(&room.id), sender, None, body).await?;
+    if message.status == "visible" {
+        let event = json!({
+            "type": "chat_message",
+            "roomId": room.id,
+            "displayName": author.display_name,
+            "message": message,
+        });
+        let players: Vec<Uuid> = room.players.iter().map(|p| p.id).collect();
+        state.realtime.send_to_many(tenant_id, &players, &event).await;
+        for (spectator, spectator_tenant) in &room.spectators {
+            state.realtime.send_to(spectator_tenant, *spectator, &event).await;
+        }
+    }
+    Ok(message)
+}
+
+/// One page of a channel, newest first. Held messages are only shown to
+/// their sender.
+async fn history(
+    state: &AppState,
+    tenant_id: &str,
+    channel: &str,
+    viewer: Uuid,
+    q: &ChatHistoryQuery,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+
+    let messages = sqlx::query_as::<_, ChatMessage>(
+        r#"SELECT id, channel, sender_id, recipient_id, body, status, read_at, created_at
+        FROM chat_messages
+        WHERE tenant_id = $1 AND channel = $2
+            AND (status = 'visible' OR (status = 'held' AND sender_id = $3))
+            AND ($4::uuid IS NULL OR created_at < (SELECT created_at FROM chat_messages WHERE id = $4))
+        ORDER BY created_at DESC
+        LIMIT $5"#,
+    )
+    .bind(tenant_id)
+    .bind(channel)
+    .bind(viewer)
+    .bind(q.before)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    let has_more = messages.len() as i64 == limit;
+    Ok(Json(json!({ "messages": messages, "hasMore": has_more })))
+}
+
+// ---------------------------------------------------------------------------
+// Direct messages
+// ---------------------------------------------------------------------------
+
+/// The player's conversations, most recent first, with unread counts.
+pub async fn list_conversations(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let rows = sqlx::query_as::<_, Conversation>(
+        r#"SELECT c.partner_id, p.display_name, c.sender_id, c.body, c.created_at, c.unread
+        FROM (
+            SELECT DISTINCT ON (m.channel)
+                CASE WHEN m.sender_id = $1 THEN m.recipient_id ELSE m.sender_id END AS partner_id,
+                m.sender_id, m.body, m.created_at,
+                (SELECT COUNT(*) FROM chat_messages u
+                 WHERE u.tenant_id = m.tenant_id AND u.channel = m.channel AND u.recipient_id = $1
+                    AND u.status = 'visible' AND u.read_at IS NULL)::bigint AS unread
+            FROM chat_messages m
+            WHERE m.tenant_id = $2 AND m.channel LIKE 'dm:%'
+                AND (m.sender_id = $1 OR (m.recipient_id = $1 AND m.status = 'visible'))
+            ORDER BY m.channel, m.created_at DESC
+        ) c
+        LEFT JOIN players p ON p.id = c.partner_id AND p.tenant_id = $2
+        ORDER BY c.created_at DESC
+        LIMIT 100"#,
+    )
+    .bind(player.id)
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+
+    let conversations: Vec<Value> = rows
+        .iter()
+        .map(|c| {
+            json!({
+                "playerId": c.partner_id, "displayName": c.display_name,
+                "lastMessage": {"senderId": c.sender_id, "body": c.body, "createdAt": c.created_at},
+                "unread": c.unread,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({ "conversations": conversations })))
+}
+
+pub async fn dm_history(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(other): Path<Uuid>,
+    Query(q): Query<ChatHistoryQuery>,
+) -> AppResult<Json<Value>> {
+    history(&state, &tenant.0 .0, &dm_channel(player.id, other), player.id, &q).await
+}
+
+pub async fn send_dm(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(other): Path<Uuid>,
+    Json(body): Json<SendMessageRequest>,
+) -> AppResult<Json<Value>> {
+    let message = send_direct(&state, &tenant.0 .0, player.id, other, &body.body).await?;
+    Ok(Json(json!({ "message": message })))
+}
+
+/// Marks the conversation read and tells the other player with a
+/// `chat_read` event.
+pub async fn mark_read(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(other): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let marked = sqlx::query(
+        r#"UPDATE chat_messages SET read_at = NOW()
+        WHERE tenant_id = $1 AND channel = $2 AND recipient_id = $3 AND read_at IS NULL"#,
+    )
+    .bind(tid)
+    .bind(dm_channel(player.id, other))
+    .bind(player.id)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+
+    if marked > 0 {
+        let event = json!({"type": "chat_read", "playerId": player.id, "at": chrono::Utc::now()});
+        state.realtime.send_to(tid, other, &event).await;
+    }
+
+    Ok(Json(json!({ "marked": marked })))
+}
+
+// ---------------------------------------------------------------------------
+// Room chat
+// ---------------------------------------------------------------------------
+
+/// Players and spectators of a live room can read its chat.
+pub async fn room_history(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(room_id): Path<String>,
+    Query(q): Query<ChatHistoryQuery>,
+) -> AppResult<Json<Value>> {
+    let room = state
+        .room_manager
+        .get_room(&room_id)
+        .await
+        .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+    let present = room.players.iter().any(|p| p.id == player.id) || room.spectators.contains_key(&player.id);
+    if !present {
+        return Err(AppError::Forbidden("Not in this room".into()));
+    }
+
+    history(&state, &tenant.0 .0, &room_channel(&room.id), player.id, &q).await
+}
+
+pub async fn send_room_message(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(room_id): Path<String>,
+    Json(body): Json<SendMessageRequest>,
+) -> AppResult<Json<Value>> {
+    let message = send_room(&state, &tenant.0 .0, player.id, &room_id, &body.body).await?;
+    Ok(Json(json!({ "message": message })))
+}
+
+// ---------------------------------------------------------------------------
+// Reports
+// ---------------------------------------------------------------------------
+
+/// Files a content report for a message. Direct messages can only be
+/// reported by the two players in the conversation.
+pub async fn report_message(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(message_id): Path<Uuid>,
+    Json(body): Json<ReportRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let message: Option<(Uuid, Option<Uuid>)> = sqlx::query_as(
+        "SELECT sender_id, recipient_id FROM chat_messages WHERE id = $1 AND tenant_id = $2 AND status <> 'removed'",
+    )
+    .bind(message_id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match message {
+        Some((sender, Some(recipient))) if player.id != sender && player.id != recipient => {
+            return Err(AppError::NotFound("Message not found".into()));
+        }
+        Some((sender, _)) if sender == player.id => {
+            return Err(AppError::BadRequest("Cannot report your own message".into()));
+        }
+        Some(_) => {}
+        None => return Err(AppError::NotFound("Message not found".into())),
+    }
+
+    let filed = sqlx::query(
+        r#"INSERT INTO content_reports (reporter_id, tenant_id, content_type, content_id, reason, description, status, created_at)
+        VALUES ($1, $2, 'chat_message', $3, $4, $5, 'open', NOW())
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(player.id)
+    .bind(tid)
+    .bind(message_id)
+    .bind(&body.reason)
+    .bind(&body.description)
+    .execute(&state.db)
+    .await?;
+
+    if filed.rows_affected() > 0 {
+        sqlx::query("UPDATE chat_messages SET report_count = report_count + 1 WHERE id = $1")
+            .bind(message_id)
+            .execute(&state.db)
+            .await?;
+    }
+
+    Ok(Json(json!({"success": true})))
+}
+
+// ---------------------------------------------------------------------------
+// Presence socket
+// ---------------------------------------------------------------------------
+
+/// Chat over the presence socket:
+///
+/// * `{"type":"chat","to":"<playerId>","body":"..."}` — direct message
+/// * `{"type":"chat","roomId":"...","body":"..."}` — room chat
+///
+/// Replies `{"type":"chat_sent","message":{...}}`; a held message comes
+/// back with `"status":"held"` and isn't delivered.
+pub async fn handle_chat_message(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    msg: &Value,
+) -> Option<Value> {
+    let body = msg["body"].as_str().unwrap_or_default();
+    // The router's age gate doesn't cover the socket.
+    let sent = if let Err(e) = age_gate::check(&state.db, tenant_id, player_id).await {
+        Err(e)
+    } else if let Some(room_id) = msg["roomId"].as_str() {
+        send_room(state, tenant_id, player_id, room_id, body).await
+    } else if let Some(to) = msg["to"].as_str().and_then(|t| Uuid::parse_str(t).ok()) {
+        send_direct(state, tenant_id, player_id, to, body).await
+    } else {
+        return Some(json!({"type": "error", "message": "chat needs a valid \"to\" or \"roomId\""}));
+    };
+
+    match sent {
+        Ok(message) => Some(json!({"type": "chat_sent", "message": message})),
+        Err(AppError::RateLimited) => Some(json!({"type": "error", "message": "Sending too fast"})),
+        Err(
+            AppError::BadRequest(m) | AppError::Forbidden(m) | AppError::NotFound(m),
+        ) => Some(json!({"type": "error", "message": m})),
+        Err(e) => {
+            tracing::warn!("Chat message from {} failed: {}", player_id, e);
+            Some(json!({"type": "error", "message": "Message failed"}))
+        }
+    }
+}
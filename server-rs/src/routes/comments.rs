@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, Query, State},
     Json,
@@ -10,8 +12,36 @@ use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
 use crate::models::comment::*;
 use crate::routes::leaderboards::PaginationQuery;
+use crate::services::moderation_filter;
 use crate::AppState;
 
+/// Reaction types players can leave on a comment, and the emoji each shows as.
+pub const REACTIONS: &[(&str, &str)] = &[
+    ("like", "👍"),
+    ("love", "❤️"),
+    ("laugh", "😂"),
+    ("wow", "😮"),
+    ("sad", "😢"),
+    ("celebrate", "🎉"),
+];
+
+/// Reaction counts per comment, as `{"like": 3, ...}` with only the types
+/// that have been used.
+async fn reaction_counts(db: &sqlx::PgPool, comment_ids: &[Uuid]) -> AppResult<HashMap<Uuid, Value>> {
+    let rows: Vec<(Uuid, String, i64)> = sqlx::query_as(
+        "SELECT comment_id, reaction, COUNT(*)::bigint FROM comment_reactions WHERE comment_id = ANY($1) GROUP BY comment_id, reaction",
+    )
+    .bind(comment_ids)
+    .fetch_all(db)
+    .await?;
+
+    let mut counts: HashMap<Uuid, Value> = HashMap::new();
+    for (id, reaction, count) in rows {
+        counts.entry(id).or_insert_with(|| json!({}))[reaction] = json!(count);
+    }
+    Ok(counts)
+}
+
 pub async fn list_comments(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
@@ -34,8 +64,12 @@ pub async fn list_comments(
     .fetch_all(&state.db)
     .await?;
 
+    let ids: Vec<Uuid> = rows.iter().map(|r| r.0).collect();
+    let mut reactions = reaction_counts(&state.db, &ids).await?;
+
     let comments: Vec<Value> = rows.iter().map(|(id, pid, gid, parent, body, created, edited, name)| {
-        json!({"id": id, "playerId": pid, "gameId": gid, "parentId": parent, "body": body, "createdAt": created, "editedAt": edited, "displayName": name})
+        let reactions = reactions.remove(id).unwrap_or_else(|| json!({}));
+        json!({"id": id, "playerId": pid, "gameId": gid, "parentId": parent, "body": body, "createdAt": created, "editedAt": edited, "displayName": name, "reactions": reactions})
     }).collect();
 
     Ok(Json(json!({ "comments": comments })))
@@ -60,8 +94,12 @@ pub async fn get_thread(
     .fetch_all(&state.db)
     .await?;
 
+    let ids: Vec<Uuid> = rows.iter().map(|r| r.0).collect();
+    let mut reactions = reaction_counts(&state.db, &ids).await?;
+
     let replies: Vec<Value> = rows.iter().map(|(id, pid, body, created, name)| {
-        json!({"id": id, "playerId": pid, "body": body, "createdAt": created, "displayName": name})
+        let reactions = reactions.remove(id).unwrap_or_else(|| json!({}));
+        json!({"id": id, "playerId": pid, "body": body, "createdAt": created, "displayName": name, "reactions": reactions})
     }).collect();
 
     Ok(Json(json!({ "replies": replies })))
@@ -84,9 +122,12 @@ pub async fn post_comment(
         .transpose()
         .map_err(|_| AppError::BadRequest("Invalid parent ID".into()))?;
 
+    let verdict = moderation_filter::check(&state.db, &state.cache, &tenant.0 .0, &[&body.body]).await?;
+    let status = if verdict.holds() { "pending" } else { "published" };
+
     let id: Uuid = sqlx::query_scalar(
         r#"INSERT INTO comments (id, player_id, tenant_id, game_id, parent_id, body, status, report_count, created_at)
-        VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, 'published', 0, NOW())
+        VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, 0, NOW())
         RETURNING id"#,
     )
     .bind(player.id)
@@ -94,10 +135,13 @@ pub async fn post_comment(
     .bind(&game_id)
     .bind(parent_id)
     .bind(&body.body)
+    .bind(status)
     .fetch_one(&state.db)
     .await?;
 
-    Ok(Json(json!({"id": id, "status": "published"})))
+    moderation_filter::report(&state.db, &tenant.0 .0, "comment", id, &verdict).await?;
+
+    Ok(Json(json!({"id": id, "status": status})))
 }
 
 pub async fn edit_comment(
@@ -114,13 +158,19 @@ pub async fn edit_comment(
         return Err(AppError::BadRequest("Comment must be 1-2000 characters".into()));
     }
 
+    let verdict = moderation_filter::check(&state.db, &state.cache, &tenant.0 .0, &[&body.body]).await?;
+
+    // An edit can put a comment on hold, but never takes it off hold
     let result = sqlx::query(
-        "UPDATE comments SET body = $1, edited_at = NOW() WHERE id = $2 AND player_id = $3 AND tenant_id = $4",
+        r#"UPDATE comments SET body = $1, edited_at = NOW(),
+            status = CASE WHEN $5 THEN 'pending' ELSE status END
+        WHERE id = $2 AND player_id = $3 AND tenant_id = $4"#,
     )
     .bind(&body.body)
     .bind(cid)
     .bind(player.id)
     .bind(&tenant.0 .0)
+    .bind(verdict.holds())
     .execute(&state.db)
     .await?;
 
@@ -128,6 +178,8 @@ pub async fn edit_comment(
         return Err(AppError::NotFound("Comment not found or not yours".into()));
     }
 
+    moderation_filter::report(&state.db, &tenant.0 .0, "comment", cid, &verdict).await?;
+
     Ok(Json(json!({"success": true})))
 }
 
@@ -182,23 +234,120 @@ pub async fn report_comment(
     Ok(Json(json!({"success": true})))
 }
 
+/// Adds the player's reaction to a comment, or takes it back if they had
+/// already left it. The author hears about new reactions other than their
+/// own over the realtime socket.
+pub async fn react_comment(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(comment_id): Path<String>,
+    Json(body): Json<ReactRequest>,
+) -> AppResult<Json<Value>> {
+    let cid = Uuid::parse_str(&comment_id)
+        .map_err(|_| AppError::BadRequest("Invalid comment ID".into()))?;
+    let Some(&(reaction, emoji)) = REACTIONS.iter().find(|(key, _)| *key == body.reaction) else {
+        return Err(AppError::BadRequest(format!("Unknown reaction: {}", body.reaction)));
+    };
+    let tid = &tenant.0 .0;
+
+    let comment: Option<(Uuid, String)> = sqlx::query_as(
+        "SELECT player_id, game_id FROM comments WHERE id = $1 AND tenant_id = $2 AND status = 'published'",
+    )
+    .bind(cid)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some((author, game_id)) = comment else {
+        return Err(AppError::NotFound("Comment not found".into()));
+    };
+
+    let added = sqlx::query(
+        r#"INSERT INTO comment_reactions (comment_id, player_id, tenant_id, reaction, created_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(cid)
+    .bind(player.id)
+    .bind(tid)
+    .bind(reaction)
+    .execute(&state.db)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !added {
+        sqlx::query("DELETE FROM comment_reactions WHERE comment_id = $1 AND player_id = $2 AND reaction = $3")
+            .bind(cid)
+            .bind(player.id)
+            .bind(reaction)
+            .execute(&state.db)
+            .await?;
+    }
+
+    let reactions = reaction_counts(&state.db, &[cid])
+        .await?
+        .remove(&cid)
+        .unwrap_or_else(|| json!({}));
+
+    if added && author != player.id {
+        let name: Option<String> = sqlx::query_scalar("SELECT display_name FROM players WHERE id = $1 AND tenant_id = $2")
+            .bind(player.id)
+            .bind(tid)
+            .fetch_optional(&state.db)
+            .await?;
+        let event = json!({
+            "type": "comment_reaction",
+            "commentId": cid,
+            "gameId": game_id,
+            "reaction": reaction,
+            "emoji": emoji,
+            "playerId": player.id,
+            "displayName": name,
+            "reactions": reactions,
+        });
+        state.realtime.send_to(tid, author, &event).await;
+    }
+
+    Ok(Json(json!({"id": cid, "reaction": reaction, "reacted": added, "reactions": reactions})))
+}
+
 // Reviews
 
+#[derive(sqlx::FromRow)]
+struct ReviewRow {
+    id: Uuid,
+    player_id: Uuid,
+    rating: i32,
+    title: Option<String>,
+    body: Option<String>,
+    helpful_count: i32,
+    created_at: chrono::DateTime<chrono::Utc>,
+    display_name: String,
+}
+
 pub async fn list_reviews(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
     Path(game_id): Path<String>,
-    Query(q): Query<PaginationQuery>,
+    Query(q): Query<ReviewListQuery>,
 ) -> AppResult<Json<Value>> {
     let limit = q.limit.unwrap_or(20).min(50);
     let offset = q.offset.unwrap_or(0);
-
-    let rows: Vec<(Uuid, Uuid, i32, Option<String>, Option<String>, chrono::DateTime<chrono::Utc>, String)> = sqlx::query_as(
-        r#"SELECT r.id, r.player_id, r.rating, r.title, r.body, r.created_at, p.display_name
+    let order = match q.sort.as_deref().unwrap_or("recent") {
+        "recent" => "r.created_at DESC",
+        "helpful" => "r.helpful_count DESC, r.created_at DESC",
+        "rating" => "r.rating DESC, r.created_at DESC",
+        other => return Err(AppError::BadRequest(format!("Unknown sort: {}", other))),
+    };
+
+    let rows: Vec<ReviewRow> = sqlx::query_as(&format!(
+        r#"SELECT r.id, r.player_id, r.rating, r.title, r.body, r.helpful_count, r.created_at, p.display_name
         FROM game_reviews r JOIN players p ON p.id = r.player_id AND p.tenant_id = r.tenant_id
         WHERE r.tenant_id = $1 AND r.game_id = $2 AND r.status = 'published'
-        ORDER BY r.created_at DESC LIMIT $3 OFFSET $4"#,
-    )
+        ORDER BY {} LIMIT $3 OFFSET $4"#,
+        order
+    ))
     .bind(&tenant.0 .0)
     .bind(&game_id)
     .bind(limit)
@@ -215,8 +364,8 @@ pub async fn list_reviews(
     .fetch_all(&state.db)
     .await?;
 
-    let reviews: Vec<Value> = rows.iter().map(|(id, pid, rating, title, body, created, name)| {
-        json!({"id": id, "playerId": pid, "rating": rating, "title": title, "body": body, "createdAt": created, "displayName": name})
+    let reviews: Vec<Value> = rows.iter().map(|r| {
+        json!({"id": r.id, "playerId": r.player_id, "rating": r.rating, "title": r.title, "body": r.body, "helpfulCount": r.helpful_count, "createdAt": r.created_at, "displayName": r.display_name})
     }).collect();
 
     let mut distribution = json!({"1": 0, "2": 0, "3": 0, "4": 0, "5": 0});
@@ -238,12 +387,18 @@ pub async fn post_review(
         return Err(AppError::BadRequest("Rating must be 1-5".into()));
     }
 
-    let id: Uuid = sqlx::query_scalar(
+    let texts = [body.title.as_deref().unwrap_or(""), body.body.as_deref().unwrap_or("")];
+    let verdict = moderation_filter::check(&state.db, &state.cache, &tenant.0 .0, &texts).await?;
+    let status = if verdict.holds() { "pending" } else { "published" };
+
+    // Rewriting a review can put it on hold, but keeps a moderator's decision otherwise
+    let (id, status): (Uuid, String) = sqlx::query_as(
         r#"INSERT INTO game_reviews (id, player_id, tenant_id, game_id, rating, title, body, status, created_at, updated_at)
-        VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, 'published', NOW(), NOW())
+        VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
         ON CONFLICT (player_id, tenant_id, game_id) DO UPDATE SET
-            rating = EXCLUDED.rating, title = EXCLUDED.title, body = EXCLUDED.body, updated_at = NOW()
-        RETURNING id"#,
+            rating = EXCLUDED.rating, title = EXCLUDED.title, body = EXCLUDED.body, updated_at = NOW(),
+            status = CASE WHEN EXCLUDED.status = 'pending' THEN 'pending' ELSE game_reviews.status END
+        RETURNING id, status"#,
     )
     .bind(player.id)
     .bind(&tenant.0 .0)
@@ -251,10 +406,13 @@ pub async fn post_review(
     .bind(body.rating)
     .bind(&body.title)
     .bind(&body.body)
+    .bind(status)
     .fetch_one(&state.db)
     .await?;
 
-    Ok(Json(json!({"id": id, "status": "published"})))
+    moderation_filter::report(&state.db, &tenant.0 .0, "review", id, &verdict).await?;
+
+    Ok(Json(json!({"id": id, "status": status})))
 }
 
 pub async fn delete_review(
@@ -299,3 +457,61 @@ pub async fn report_review(
 
     Ok(Json(json!({"success": true})))
 }
+
+/// Marks a review as helpful. Each player counts once per review; voting
+/// again leaves the count as it is.
+pub async fn vote_review(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, review_id)): Path<(String, String)>,
+) -> AppResult<Json<Value>> {
+    let rid = Uuid::parse_str(&review_id)
+        .map_err(|_| AppError::BadRequest("Invalid review ID".into()))?;
+    let tid = &tenant.0 .0;
+
+    let author: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT player_id FROM game_reviews WHERE id = $1 AND tenant_id = $2 AND game_id = $3 AND status = 'published'",
+    )
+    .bind(rid)
+    .bind(tid)
+    .bind(&game_id)
+    .fetch_optional(&state.db)
+    .await?;
+    match author {
+        Some((author,)) if author == player.id => {
+            return Err(AppError::BadRequest("Cannot vote on your own review".into()));
+        }
+        Some(_) => {}
+        None => return Err(AppError::NotFound("Review not found".into())),
+    }
+
+    let mut tx = state.db.begin().await?;
+    let voted = sqlx::query(
+        r#"INSERT INTO review_votes (review_id, player_id, tenant_id, created_at)
+        VALUES ($1, $2, $3, NOW())
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(rid)
+    .bind(player.id)
+    .bind(tid)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    let (helpful,): (i32,) = if voted {
+        sqlx::query_as("UPDATE game_reviews SET helpful_count = helpful_count + 1 WHERE id = $1 RETURNING helpful_count")
+            .bind(rid)
+            .fetch_one(&mut *tx)
+            .await?
+    } else {
+        sqlx::query_as("SELECT helpful_count FROM game_reviews WHERE id = $1")
+            .bind(rid)
+            .fetch_one(&mut *tx)
+            .await?
+    };
+    tx.commit().await?;
+
+    Ok(Json(json!({"id": rid, "helpfulCount": helpful, "counted": voted})))
+}
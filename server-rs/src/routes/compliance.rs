@@ -9,6 +9,7 @@ use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
 use crate::models::compliance::*;
+use crate::services::jobs;
 use crate::AppState;
 
 pub async fn get_consent(
@@ -48,6 +49,8 @@ pub async fn record_consent(
     Ok(Json(json!({"success": true})))
 }
 
+/// Queues a data export for the GDPR worker. A request that's still
+/// pending or processing is returned instead of queueing another.
 pub async fn request_export(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -55,6 +58,19 @@ pub async fn request_export(
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
 
+    let active: Option<(Uuid, String)> = sqlx::query_as(
+        r#"SELECT id, status FROM gdpr_requests
+        WHERE tenant_id = $1 AND player_id = $2 AND request_type = 'export' AND status IN ('pending', 'processing')
+        ORDER BY created_at DESC LIMIT 1"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_optional(&state.db)
+    .await?;
+    if let Some((req_id, status)) = active {
+        return Ok(Json(json!({"requestId": req_id, "status": status})));
+    }
+
     let req_id: Uuid = sqlx::query_scalar(
         r#"INSERT INTO gdpr_requests (id, tenant_id, player_id, request_type, status, created_at)
         VALUES (gen_random_uuid(), $1, $2, 'export', 'pending', NOW())
@@ -65,75 +81,14 @@ pub async fn request_export(
     .fetch_one(&state.db)
     .await?;
 
-    // Process export inline (in production this would be async)
-    let pid = player.id;
-    let db = state.db.clone();
-    let tenant_id = tid.clone();
-    tokio::spawn(async move {
-        let _ = process_export(&db, pid, &tenant_id, req_id).await;
-    });
+    // Start on it now rather than at the next scheduled run.
+    jobs::run_now(&state.db, jobs::GDPR_EXPORTS, json!({"requestId": req_id})).await?;
 
     Ok(Json(json!({"requestId": req_id, "status": "pending"})))
 }
 
-async fn process_export(
-    db: &sqlx::PgPool,
-    player_id: Uuid,
-    tenant_id: &str,
-    request_id: Uuid,
-) -> AppResult<()> {
-    // Collect player data
-    let profile: Option<Value> = sqlx::query_scalar(
-        "SELECT row_to_json(p) FROM (SELECT id, email, display_name, avatar_character, total_score, games_played, created_at FROM players WHERE id = $1 AND tenant_id = $2) p",
-    )
-    .bind(player_id)
-    .bind(tenant_id)
-    .fetch_optional(db)
-    .await?;
-
-    let progress: Vec<Value> = sqlx::query_scalar(
-        "SELECT row_to_json(gp) FROM (SELECT game_id, high_score, stars, play_count FROM game_progress WHERE player_id = $1 AND tenant_id = $2) gp",
-    )
-    .bind(player_id)
-    .bind(tenant_id)
-    .fetch_all(db)
-    .await?;
-
-    let scores: Vec<Value> = sqlx::query_scalar(
-        "SELECT row_to_json(sh) FROM (SELECT game_id, score, level, created_at FROM score_history WHERE player_id = $1 AND tenant_id = $2 ORDER BY created_at DESC LIMIT 1000) sh",
-    )
-    .bind(player_id)
-    .bind(tenant_id)
-    .fetch_all(db)
-    .await?;
-
-    let export_data = json!({
-        "profile": profile,
-        "gameProgress": progress,
-        "scoreHistory": scores,
-        "exportedAt": chrono::Utc::now(),
-    });
-
-    let download_url = format!("data:application/json;base64,{}", base64_encode(&export_data.to_string()));
-    let expires = chrono::Utc::now() + chrono::Duration::days(7);
-
-    sqlx::query(
-        "UPDATE gdpr_requests SET status = 'completed', download_url = $1, completed_at = NOW(), expires_at = $2 WHERE id = $3",
-    )
-    .bind(&download_url)
-    .bind(expires)
-    .bind(request_id)
-    .execute(db)
-    .await?;
-
-    Ok(())
-}
-
-fn base64_encode(s: &str) -> String {
-    use base64::Engine;
-    base64::engine::general_purpose::STANDARD.encode(s.as_bytes())
-}
-
+/// Export status. Once completed, `downloadUrl` is a presigned URL valid
+/// for `STORAGE_DOWNLOAD_URL_TTL_SEC`; fetch the status again for a new one.
 pub async fn get_export_status(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -143,23 +98,50 @@ pub async fn get_export_status(
     let req_id = Uuid::parse_str(&id)
         .map_err(|_| AppError::BadRequest("Invalid request ID".into()))?;
 
-    let row: Option<(String, Option<String>, Option<chrono::DateTime<chrono::Utc>>, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
-        "SELECT status, download_url, completed_at, expires_at FROM gdpr_requests WHERE id = $1 AND player_id = $2 AND tenant_id = $3 AND request_type = 'export'",
+    let export = sqlx::query_as::<_, ExportStatus>(
+        r#"SELECT status, storage_key, download_url, size_bytes, error, completed_at, expires_at
+        FROM gdpr_requests
+        WHERE id = $1 AND player_id = $2 AND tenant_id = $3 AND request_type = 'export'"#,
     )
     .bind(req_id)
     .bind(player.id)
     .bind(&tenant.0 .0)
     .fetch_optional(&state.db)
-    .await?;
-
-    match row {
-        Some((status, url, completed, expires)) => Ok(Json(json!({
-            "status": status, "downloadUrl": url, "completedAt": completed, "expiresAt": expires,
-        }))),
-        None => Err(AppError::NotFound("Export request not found".into())),
+    .await?
+    .ok_or_else(|| AppError::NotFound("Export request not found".into()))?;
+
+    let now = chrono::Utc::now();
+    let expired = export.expires_at.is_some_and(|at| at <= now);
+    let status = if expired { "expired" } else { export.status.as_str() };
+
+    let mut download_url = None;
+    let mut url_expires_at = None;
+    if status == "completed" {
+        match (&export.storage_key, &state.storage) {
+            (Some(key), Some(storage)) => {
+                let remaining = export.expires_at.map(|at| (at - now).num_seconds().max(1) as u64);
+                let ttl = remaining.map_or(storage.download_url_ttl_secs, |r| r.min(storage.download_url_ttl_secs));
+                download_url = Some(storage.presigned_get(key, ttl));
+                url_expires_at = Some(now + chrono::Duration::seconds(ttl as i64));
+            }
+            _ => download_url = export.download_url.clone(),
+        }
     }
+
+    Ok(Json(json!({
+        "status": status,
+        "downloadUrl": download_url,
+        "downloadUrlExpiresAt": url_expires_at,
+        "sizeBytes": export.size_bytes,
+        "error": export.error,
+        "completedAt": export.completed_at,
+        "expiresAt": export.expires_at,
+    })))
 }
 
+/// Schedules account deletion. The GDPR worker carries it out once
+/// `GDPR_DELETION_GRACE_DAYS` have passed; asking again while one is
+/// scheduled returns the existing request.
 pub async fn request_deletion(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -173,26 +155,46 @@ pub async fn request_deletion(
     }
 
     let tid = &tenant.0 .0;
+    let grace_days = state.config.gdpr.deletion_grace_days;
 
-    sqlx::query(
-        "UPDATE players SET data_deletion_requested_at = NOW() WHERE id = $1 AND tenant_id = $2",
-    )
-    .bind(player.id)
-    .bind(tid)
-    .execute(&state.db)
-    .await?;
-
-    sqlx::query(
-        "INSERT INTO gdpr_requests (id, tenant_id, player_id, request_type, status, created_at) VALUES (gen_random_uuid(), $1, $2, 'delete', 'pending', NOW())",
+    let existing: Option<(Uuid, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        r#"SELECT id, created_at FROM gdpr_requests
+        WHERE tenant_id = $1 AND player_id = $2 AND request_type = 'delete' AND status <> 'completed'
+        ORDER BY created_at DESC LIMIT 1"#,
     )
     .bind(tid)
     .bind(player.id)
-    .execute(&state.db)
+    .fetch_optional(&state.db)
     .await?;
 
+    let (req_id, requested_at) = match existing {
+        Some(row) => row,
+        None => {
+            sqlx::query(
+                "UPDATE players SET data_deletion_requested_at = NOW() WHERE id = $1 AND tenant_id = $2",
+            )
+            .bind(player.id)
+            .bind(tid)
+            .execute(&state.db)
+            .await?;
+
+            sqlx::query_as(
+                r#"INSERT INTO gdpr_requests (id, tenant_id, player_id, request_type, status, created_at)
+                VALUES (gen_random_uuid(), $1, $2, 'delete', 'pending', NOW())
+                RETURNING id, created_at"#,
+            )
+            .bind(tid)
+            .bind(player.id)
+            .fetch_one(&state.db)
+            .await?
+        }
+    };
+
     Ok(Json(json!({
         "success": true,
-        "message": "Deletion scheduled. Your data will be deleted within 30 days.",
+        "requestId": req_id,
+        "scheduledFor": requested_at + chrono::Duration::days(i64::from(grace_days)),
+        "message": format!("Deletion scheduled. Your data will be deleted in {} days.", grace_days),
     })))
 }
 
@@ -201,7 +203,7 @@ pub async fn privacy_policy() -> Json<Value> {
         "version": "1.0",
         "lastUpdated": "2025-01-01",
         "dataCollected": ["email", "display name", "game progress", "scores", "purchase history"],
-        "retention": "Data retained while account active. Deleted 30 days after deletion request.",
+        "retention": "Data retained while account active. Deleted 30 days after deletion request; comments and reviews are kept anonymised.",
         "rights": ["access", "rectification", "erasure", "portability", "restriction"],
         "contact": "privacy@minigames.cool",
     }))
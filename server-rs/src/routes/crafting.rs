@@ -0,0 +1,517 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::localization::LocaleInfo;
+use crate::middleware::tenant::TenantId;
+use crate::models::economy::*;
+use crate::routes::economy::TRADE_CURRENCIES;
+use crate::services::{cosmetics, loot, translations};
+use crate::AppState;
+
+/// Maximum inputs per recipe.
+const MAX_RECIPE_INPUTS: usize = 10;
+
+type PgTx<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// Who an attempt's economy_transactions rows belong to.
+struct CraftCtx<'a> {
+    tenant_id: &'a str,
+    player_id: Uuid,
+    attempt_id: String,
+}
+
+/// Inputs of the given recipes, keyed by recipe id.
+async fn load_inputs(db: &PgPool, tid: &str, recipe_ids: &[String]) -> AppResult<HashMap<String, Vec<RecipeInput>>> {
+    let rows: Vec<RecipeInput> = sqlx::query_as(
+        r#"SELECT ri.recipe_id, ri.item_id, si.name, ri.quantity
+        FROM recipe_inputs ri
+        JOIN store_items si ON si.id = ri.item_id AND si.tenant_id = ri.tenant_id
+        WHERE ri.tenant_id = $1 AND ri.recipe_id = ANY($2)
+        ORDER BY ri.item_id"#,
+    )
+    .bind(tid)
+    .bind(recipe_ids)
+    .fetch_all(db)
+    .await?;
+
+    let mut out: HashMap<String, Vec<RecipeInput>> = HashMap::new();
+    for row in rows {
+        out.entry(row.recipe_id.clone()).or_default().push(row);
+    }
+    Ok(out)
+}
+
+/// Recipes the player can see: every visible recipe plus the hidden ones
+/// they have discovered (see [`discover_recipes`]).
+pub async fn list_recipes(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let recipes: Vec<CraftingRecipe> = sqlx::query_as(
+        "SELECT * FROM crafting_recipes WHERE tenant_id = $1 AND is_active = true ORDER BY name",
+    )
+    .bind(tid)
+    .fetch_all(&state.db)
+    .await?;
+    let ids: Vec<String> = recipes.iter().map(|r| r.id.clone()).collect();
+    let inputs = load_inputs(&state.db, tid, &ids).await?;
+
+    let progress: HashMap<String, (i32, i32)> = sqlx::query_as::<_, (String, i32, i32)>(
+        "SELECT recipe_id, crafted_count, failed_count FROM player_recipes WHERE tenant_id = $1 AND player_id = $2",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .map(|(id, crafted, failed)| (id, (crafted, failed)))
+    .collect();
+
+    let owned: HashMap<String, i32> = sqlx::query_as::<_, (String, i32)>(
+        "SELECT item_id, quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .collect();
+
+    let balances: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT currency_type, balance FROM player_wallets WHERE tenant_id = $1 AND player_id = $2",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .collect();
+
+    let t = translations::for_locale(&state.db, &state.cache, tid, &locale.locale).await?;
+    let no_inputs = Vec::new();
+    let mut out = Vec::new();
+
+    for recipe in &recipes {
+        let inputs = inputs.get(&recipe.id).unwrap_or(&no_inputs);
+        let has_inputs = inputs
+            .iter()
+            .all(|i| owned.get(&i.item_id).copied().unwrap_or(0) >= i.quantity);
+        if recipe.hidden && !progress.contains_key(&recipe.id) {
+            continue;
+        }
+
+        let affordable = balances.get(&recipe.currency_type).copied().unwrap_or(0) >= recipe.currency_cost;
+        let (crafted, failed) = progress.get(&recipe.id).copied().unwrap_or((0, 0));
+        let inputs: Vec<Value> = inputs
+            .iter()
+            .map(|i| {
+                let name = t.text("store_item", &format!("{}.name", i.item_id), &i.name);
+                let have = owned.get(&i.item_id).copied().unwrap_or(0);
+                json!({"itemId": i.item_id, "name": name, "quantity": i.quantity, "owned": have})
+            })
+            .collect();
+
+        let mut v = json!(recipe);
+        v["inputs"] = json!(inputs);
+        v["canCraft"] = json!(has_inputs && affordable);
+        v["craftedCount"] = json!(crafted);
+        v["failedCount"] = json!(failed);
+        out.push(v);
+    }
+
+    Ok(Json(json!({ "recipes": out })))
+}
+
+/// Discovers the hidden recipes whose inputs the player holds all of at
+/// once. Returns the ids discovered by this call.
+pub async fn discover_recipes(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let discovered: Vec<String> = sqlx::query_scalar(
+        r#"INSERT INTO player_recipes (tenant_id, player_id, recipe_id)
+        SELECT r.tenant_id, $2, r.id FROM crafting_recipes r
+        WHERE r.tenant_id = $1 AND r.hidden AND r.is_active
+            AND EXISTS (SELECT 1 FROM recipe_inputs ri WHERE ri.tenant_id = r.tenant_id AND ri.recipe_id = r.id)
+            AND NOT EXISTS (
+                SELECT 1 FROM recipe_inputs ri
+                LEFT JOIN player_inventory pi
+                    ON pi.tenant_id = ri.tenant_id AND pi.player_id = $2 AND pi.item_id = ri.item_id
+                WHERE ri.tenant_id = r.tenant_id AND ri.recipe_id = r.id AND COALESCE(pi.quantity, 0) < ri.quantity)
+        ON CONFLICT DO NOTHING
+        RETURNING recipe_id"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(json!({ "discovered": discovered })))
+}
+
+/// Crafts a recipe once. Inputs and cost are consumed whether or not the
+/// roll succeeds; the roll works like a crate opening (see `services::loot`)
+/// over 100 and succeeds below the recipe's `success_percent`.
+pub async fn craft(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CraftRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let recipe: CraftingRecipe = sqlx::query_as(
+        "SELECT * FROM crafting_recipes WHERE id = $1 AND tenant_id = $2 AND is_active = true",
+    )
+    .bind(&body.recipe_id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Recipe not found".into()))?;
+
+    if recipe.hidden {
+        let discovered: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM player_recipes WHERE tenant_id = $1 AND player_id = $2 AND recipe_id = $3",
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(&recipe.id)
+        .fetch_optional(&state.db)
+        .await?;
+        if discovered.is_none() {
+            return Err(AppError::NotFound("Recipe not found".into()));
+        }
+    }
+
+    let inputs = load_inputs(&state.db, tid, std::slice::from_ref(&recipe.id))
+        .await?
+        .remove(&recipe.id)
+        .unwrap_or_default();
+    if inputs.is_empty() {
+        return Err(AppError::BadRequest("This recipe has no inputs".into()));
+    }
+
+    let attempt_id = Uuid::new_v4();
+    let ctx = CraftCtx { tenant_id: tid, player_id: player.id, attempt_id: attempt_id.to_string() };
+    let mut tx = state.db.begin().await?;
+
+    for input in &inputs {
+        let owned: Option<i32> = sqlx::query_scalar(
+            "SELECT quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3 FOR UPDATE",
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(&input.item_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let remaining = owned.unwrap_or(0) - input.quantity;
+        if remaining < 0 {
+            return Err(AppError::BadRequest(format!("Not enough of {} to craft", input.item_id)));
+        }
+        if remaining == 0 {
+            sqlx::query("DELETE FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+                .bind(tid).bind(player.id).bind(&input.item_id)
+                .execute(&mut *tx).await?;
+        } else {
+            sqlx::query("UPDATE player_inventory SET quantity = $4 WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+                .bind(tid).bind(player.id).bind(&input.item_id).bind(remaining)
+                .execute(&mut *tx).await?;
+        }
+        cosmetics::unequip_if_gone(&mut tx, tid, player.id, &input.item_id).await?;
+        log_craft(
+            &mut tx,
+            &ctx,
+            "item",
+            -i64::from(input.quantity),
+            i64::from(remaining),
+            "craft_consume",
+            Some(&input.item_id),
+        )
+        .await?;
+    }
+
+    if recipe.currency_cost > 0 {
+        let balance: Option<i64> = sqlx::query_scalar(
+            "SELECT balance FROM player_wallets WHERE player_id = $1 AND tenant_id = $2 AND currency_type = $3 FOR UPDATE",
+        )
+        .bind(player.id)
+        .bind(tid)
+        .bind(&recipe.currency_type)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let remaining = balance.unwrap_or(0) - recipe.currency_cost;
+        if remaining < 0 {
+            return Err(AppError::BadRequest("Insufficient balance".into()));
+        }
+        sqlx::query("UPDATE player_wallets SET balance = $1, updated_at = NOW() WHERE player_id = $2 AND tenant_id = $3 AND currency_type = $4")
+            .bind(remaining).bind(player.id).bind(tid).bind(&recipe.currency_type)
+            .execute(&mut *tx).await?;
+        log_craft(
+            &mut tx,
+            &ctx,
+            &recipe.currency_type,
+            -recipe.currency_cost,
+            remaining,
+            "craft_cost",
+            None,
+        )
+        .await?;
+    }
+
+    let seed = loot::new_seed();
+    let roll = loot::roll(&seed, 100);
+    let success = roll < i64::from(recipe.success_percent);
+
+    if success {
+        let received: i32 = sqlx::query_scalar(
+            r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+            VALUES ($1, $2, $3, $4, 'craft', NOW())
+            ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+                quantity = player_inventory.quantity + EXCLUDED.quantity
+            RETURNING quantity"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(&recipe.output_item_id)
+        .bind(recipe.output_quantity)
+        .fetch_one(&mut *tx)
+        .await?;
+        log_craft(
+            &mut tx,
+            &ctx,
+            "item",
+            i64::from(recipe.output_quantity),
+            i64::from(received),
+            "craft_output",
+            Some(&recipe.output_item_id),
+        )
+        .await?;
+    }
+
+    sqlx::query(
+        r#"INSERT INTO crafting_attempts (id, tenant_id, player_id, recipe_id, seed, roll, success_percent, success)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+    )
+    .bind(attempt_id)
+    .bind(tid)
+    .bind(player.id)
+    .bind(&recipe.id)
+    .bind(&seed)
+    .bind(roll)
+    .bind(recipe.success_percent)
+    .bind(success)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"INSERT INTO player_recipes (tenant_id, player_id, recipe_id, crafted_count, failed_count)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (tenant_id, player_id, recipe_id) DO UPDATE SET
+            crafted_count = player_recipes.crafted_count + EXCLUDED.crafted_count,
+            failed_count = player_recipes.failed_count + EXCLUDED.failed_count"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&recipe.id)
+    .bind(i32::from(success))
+    .bind(i32::from(!success))
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let consumed: Vec<Value> = inputs
+        .iter()
+        .map(|i| json!({"itemId": i.item_id, "quantity": i.quantity}))
+        .collect();
+    let output = success.then(|| json!({"itemId": recipe.output_item_id, "quantity": recipe.output_quantity}));
+
+    Ok(Json(json!({
+        "attemptId": attempt_id,
+        "recipeId": recipe.id,
+        "success": success,
+        "output": output,
+        "consumed": consumed,
+        "currencySpent": recipe.currency_cost,
+        "audit": { "seed": seed, "roll": roll, "successPercent": recipe.success_percent },
+    })))
+}
+
+async fn log_craft(
+    tx: &mut PgTx<'_>,
+    ctx: &CraftCtx<'_>,
+    currency: &str,
+    amount: i64,
+    balance_after: i64,
+    tx_type: &str,
+    item_id: Option<&str>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, 'craft', $7, $8, NOW())"#,
+    )
+    .bind(ctx.tenant_id)
+    .bind(ctx.player_id)
+    .bind(currency)
+    .bind(amount)
+    .bind(balance_after)
+    .bind(tx_type)
+    .bind(&ctx.attempt_id)
+    .bind(json!({ "itemId": item_id }))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Admin: every recipe, active or not, with its inputs.
+pub async fn admin_list_recipes(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let recipes: Vec<CraftingRecipe> = sqlx::query_as("SELECT * FROM crafting_recipes WHERE tenant_id = $1 ORDER BY id")
+        .bind(tid)
+        .fetch_all(&state.db)
+        .await?;
+    let ids: Vec<String> = recipes.iter().map(|r| r.id.clone()).collect();
+    let mut inputs = load_inputs(&state.db, tid, &ids).await?;
+
+    let out: Vec<Value> = recipes
+        .iter()
+        .map(|r| {
+            let mut v = json!(r);
+            v["inputs"] = json!(inputs.remove(&r.id).unwrap_or_default());
+            v
+        })
+        .collect();
+    Ok(Json(json!({ "recipes": out })))
+}
+
+/// Admin: create or replace a recipe and its inputs.
+pub async fn upsert_recipe(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(recipe_id): Path<String>,
+    Json(body): Json<UpsertRecipeRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let currency = body.currency_type.as_deref().unwrap_or("coins");
+    let success_percent = body.success_percent.unwrap_or(100);
+    if body.name.trim().is_empty() {
+        return Err(AppError::BadRequest("name is required".into()));
+    }
+    if body.inputs.is_empty() || body.inputs.len() > MAX_RECIPE_INPUTS {
+        return Err(AppError::BadRequest(format!("A recipe needs 1 to {} inputs", MAX_RECIPE_INPUTS)));
+    }
+    if body.output_quantity <= 0 || body.inputs.iter().any(|i| i.quantity <= 0) {
+        return Err(AppError::BadRequest("Quantities must be positive".into()));
+    }
+    if body.currency_cost < 0 || !TRADE_CURRENCIES.contains(&currency) {
+        return Err(AppError::BadRequest("Invalid currency cost".into()));
+    }
+    if !(1..=100).contains(&success_percent) {
+        return Err(AppError::BadRequest("successPercent must be between 1 and 100".into()));
+    }
+
+    let mut item_ids: Vec<&str> = body.inputs.iter().map(|i| i.item_id.as_str()).collect();
+    item_ids.push(&body.output_item_id);
+    item_ids.sort_unstable();
+    item_ids.dedup();
+    if item_ids.len() != body.inputs.len() + 1 {
+        return Err(AppError::BadRequest("Inputs and output must be distinct items".into()));
+    }
+    let valid: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM store_items WHERE tenant_id = $1 AND id = ANY($2) AND item_type NOT IN ('bundle', 'battle_pass')",
+    )
+    .bind(tid)
+    .bind(&item_ids)
+    .fetch_one(&state.db)
+    .await?;
+    if valid != item_ids.len() as i64 {
+        return Err(AppError::BadRequest(
+            "Inputs and output must be store items other than bundles or battle passes".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let recipe: CraftingRecipe = sqlx::query_as(
+        r#"INSERT INTO crafting_recipes (id, tenant_id, name, description, output_item_id, output_quantity,
+            currency_type, currency_cost, success_percent, hidden, is_active)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (id) DO UPDATE SET
+            name = EXCLUDED.name, description = EXCLUDED.description,
+            output_item_id = EXCLUDED.output_item_id, output_quantity = EXCLUDED.output_quantity,
+            currency_type = EXCLUDED.currency_type, currency_cost = EXCLUDED.currency_cost,
+            success_percent = EXCLUDED.success_percent, hidden = EXCLUDED.hidden,
+            is_active = EXCLUDED.is_active
+        WHERE crafting_recipes.tenant_id = EXCLUDED.tenant_id
+        RETURNING *"#,
+    )
+    .bind(&recipe_id)
+    .bind(tid)
+    .bind(body.name.trim())
+    .bind(&body.description)
+    .bind(&body.output_item_id)
+    .bind(body.output_quantity)
+    .bind(currency)
+    .bind(body.currency_cost)
+    .bind(success_percent)
+    .bind(body.hidden)
+    .bind(body.is_active.unwrap_or(true))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Recipe id is taken".into()))?;
+
+    sqlx::query("DELETE FROM recipe_inputs WHERE tenant_id = $1 AND recipe_id = $2")
+        .bind(tid)
+        .bind(&recipe_id)
+        .execute(&mut *tx)
+        .await?;
+    for input in &body.inputs {
+        sqlx::query("INSERT INTO recipe_inputs (tenant_id, recipe_id, item_id, quantity) VALUES ($1, $2, $3, $4)")
+            .bind(tid)
+            .bind(&recipe_id)
+            .bind(&input.item_id)
+            .bind(input.quantity)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    let inputs = load_inputs(&state.db, tid, std::slice::from_ref(&recipe_id)).await?;
+    let mut v = json!(recipe);
+    v["inputs"] = json!(inputs.get(&recipe_id));
+    Ok(Json(json!({ "recipe": v })))
+}
+
+/// Admin: retire a recipe. Past attempts and discoveries are kept.
+pub async fn deactivate_recipe(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(recipe_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let updated = sqlx::query("UPDATE crafting_recipes SET is_active = false WHERE id = $1 AND tenant_id = $2")
+        .bind(&recipe_id)
+        .bind(&tenant.0 .0)
+        .execute(&state.db)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Recipe not found".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::crash_report::*;
+use crate::AppState;
+
+const MAX_MESSAGE_CHARS: usize = 4000;
+const MAX_LOCATION_CHARS: usize = 256;
+/// More than the engine keeps, so older builds with longer histories fit.
+const MAX_INPUTS: usize = 50;
+/// Serialized size limit for `browser`.
+const MAX_BROWSER_BYTES: usize = 2048;
+/// Reports kept per group; older ones are dropped as new ones come in.
+const MAX_REPORTS_PER_GROUP: i64 = 200;
+/// Reports returned with a group.
+const RECENT_REPORTS: i64 = 20;
+
+const STATUSES: [&str; 4] = ["open", "investigating", "resolved", "ignored"];
+
+const GROUP_COLUMNS: &str = r#"g.id, g.signature, g.message, g.location, g.status, g.notes, g.report_count,
+    (SELECT COUNT(DISTINCT r.player_id) FROM crash_reports r WHERE r.group_id = g.id) AS player_count,
+    ARRAY(SELECT DISTINCT r.game_id FROM crash_reports r WHERE r.group_id = g.id AND r.game_id IS NOT NULL ORDER BY 1) AS game_ids,
+    g.first_seen_at, g.last_seen_at, g.resolved_at, g.updated_by"#;
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    s.chars().take(max_chars).collect()
+}
+
+/// Groups reports of the same crash: the panic location and message, with
+/// runs of digits masked so indices and ids in the message don't split it.
+fn signature(message: &str, location: &str) -> String {
+    let mut masked = String::with_capacity(message.len());
+    for c in message.chars() {
+        if !c.is_ascii_digit() {
+            masked.push(c);
+        } else if !masked.ends_with('#') {
+            masked.push('#');
+        }
+    }
+    hex::encode(Sha256::digest(format!("{}\n{}", location, masked).as_bytes()))
+}
+
+/// Record a panic reported by the engine. Signed-in players' reports are
+/// attributed to them; reports before sign-in are anonymous.
+pub async fn submit_report(
+    State(state): State<AppState>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CrashReportInput>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let message = truncate(body.message.trim(), MAX_MESSAGE_CHARS);
+    if message.is_empty() {
+        return Err(AppError::BadRequest("message is required".into()));
+    }
+    let location = truncate(&body.location, MAX_LOCATION_CHARS);
+    let game_id = body.game_id.filter(|id| !id.is_empty() && id.len() <= 64);
+    let engine_version = body.engine_version.map(|v| truncate(&v, 32));
+
+    let inputs = match body.inputs {
+        Value::Null => json!([]),
+        Value::Array(inputs) if inputs.len() <= MAX_INPUTS => Value::Array(inputs),
+        _ => return Err(AppError::BadRequest(format!("inputs must be an array of at most {}", MAX_INPUTS))),
+    };
+    let browser = match body.browser {
+        Value::Null => json!({}),
+        b if b.is_object() && b.to_string().len() <= MAX_BROWSER_BYTES => b,
+        _ => return Err(AppError::BadRequest("browser must be a small object".into())),
+    };
+
+    let mut tx = state.db.begin().await?;
+    let group_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO crash_groups (tenant_id, signature, message, location, report_count)
+        VALUES ($1, $2, $3, $4, 1)
+        ON CONFLICT (tenant_id, signature) DO UPDATE SET
+            report_count = crash_groups.report_count + 1,
+            last_seen_at = NOW(),
+            status = CASE WHEN crash_groups.status = 'resolved' THEN 'open' ELSE crash_groups.status END,
+            resolved_at = CASE WHEN crash_groups.status = 'resolved' THEN NULL ELSE crash_groups.resolved_at END
+        RETURNING id"#,
+    )
+    .bind(tid)
+    .bind(signature(&message, &location))
+    .bind(&message)
+    .bind(&location)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"INSERT INTO crash_reports (tenant_id, group_id, player_id, game_id, message, location, engine_version, inputs, browser)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+    )
+    .bind(tid)
+    .bind(group_id)
+    .bind(player.map(|p| p.id))
+    .bind(&game_id)
+    .bind(&message)
+    .bind(&location)
+    .bind(&engine_version)
+    .bind(&inputs)
+    .bind(&browser)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"DELETE FROM crash_reports WHERE group_id = $1 AND id NOT IN (
+            SELECT id FROM crash_reports WHERE group_id = $1 ORDER BY created_at DESC LIMIT $2)"#,
+    )
+    .bind(group_id)
+    .bind(MAX_REPORTS_PER_GROUP)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "groupId": group_id })))
+}
+
+// ---------------------------------------------------------------------------
+// Admin triage
+// ---------------------------------------------------------------------------
+
+/// Admin: crash groups, most recently seen first, with the number of groups
+/// in each status. Filter by `status` and `gameId`.
+pub async fn list_groups(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<CrashGroupQuery>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if q.status.as_deref().is_some_and(|s| !STATUSES.contains(&s)) {
+        return Err(AppError::BadRequest(format!("status must be one of {}", STATUSES.join(", "))));
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+
+    let counts: Vec<(String, i64)> =
+        sqlx::query_as("SELECT status, COUNT(*) FROM crash_groups WHERE tenant_id = $1 GROUP BY status")
+            .bind(tid)
+            .fetch_all(&state.db)
+            .await?;
+    let groups: Vec<CrashGroup> = sqlx::query_as(&format!(
+        r#"SELECT {} FROM crash_groups g
+        WHERE g.tenant_id = $1
+            AND ($2::text IS NULL OR g.status = $2)
+            AND ($3::text IS NULL OR EXISTS (SELECT 1 FROM crash_reports r WHERE r.group_id = g.id AND r.game_id = $3))
+        ORDER BY g.last_seen_at DESC LIMIT $4 OFFSET $5"#,
+        GROUP_COLUMNS
+    ))
+    .bind(tid)
+    .bind(&q.status)
+    .bind(&q.game_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let counts: serde_json::Map<String, Value> = STATUSES
+        .iter()
+        .map(|s| {
+            let n = counts.iter().find(|(status, _)| status == s).map_or(0, |(_, n)| *n);
+            (s.to_string(), json!(n))
+        })
+        .collect();
+
+    Ok(Json(json!({ "counts": counts, "groups": groups })))
+}
+
+async fn fetch_group(state: &AppState, tenant_id: &str, id: Uuid) -> AppResult<CrashGroup> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM crash_groups g WHERE g.id = $1 AND g.tenant_id = $2",
+        GROUP_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Crash group not found".into()))
+}
+
+/// Admin: a crash group with its most recent reports.
+pub async fn get_group(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let group = fetch_group(&state, &tenant.0 .0, id).await?;
+    let reports: Vec<CrashReport> = sqlx::query_as(
+        r#"SELECT id, player_id, game_id, message, location, engine_version, inputs, browser, created_at
+        FROM crash_reports WHERE group_id = $1
+        ORDER BY created_at DESC LIMIT $2"#,
+    )
+    .bind(id)
+    .bind(RECENT_REPORTS)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "group": group, "reports": reports })))
+}
+
+/// Admin: triage a crash group by setting its status and notes; omitted
+/// fields are kept.
+pub async fn update_group(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateCrashGroupRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if body.status.as_deref().is_some_and(|s| !STATUSES.contains(&s)) {
+        return Err(AppError::BadRequest(format!("status must be one of {}", STATUSES.join(", "))));
+    }
+
+    let updated = sqlx::query(
+        r#"UPDATE crash_groups SET
+            status = COALESCE($3, status),
+            notes = COALESCE($4, notes),
+            resolved_at = CASE
+                WHEN COALESCE($3, status) <> 'resolved' THEN NULL
+                WHEN status = 'resolved' THEN resolved_at
+                ELSE NOW() END,
+            updated_by = $5
+        WHERE id = $1 AND tenant_id = $2"#,
+    )
+    .bind(id)
+    .bind(tid)
+    .bind(&body.status)
+    .bind(&body.notes)
+    .bind(player.id)
+    .execute(&state.db)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Crash group not found".into()));
+    }
+
+    let group = fetch_group(&state, tid, id).await?;
+    Ok(Json(json!({ "group": group })))
+}
@@ -0,0 +1,276 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::game_progress::DailyRunRequest;
+use crate::services::{battle_pass_xp, cosmetics, privacy};
+use crate::AppState;
+
+/// A twist on the usual rules, applied through the engine's `start_game`
+/// options.
+struct Modifier {
+    id: &'static str,
+    label: &'static str,
+    mode: &'static str,
+    time_limit: Option<u32>,
+}
+
+/// One of these is picked per game per day.
+const MODIFIERS: &[Modifier] = &[
+    Modifier { id: "standard", label: "Standard run", mode: "classic", time_limit: None },
+    Modifier { id: "sprint", label: "90-second sprint", mode: "time_attack", time_limit: Some(90) },
+    Modifier { id: "blitz", label: "45-second blitz", mode: "time_attack", time_limit: Some(45) },
+    Modifier { id: "marathon", label: "No level cap", mode: "endless", time_limit: None },
+];
+
+/// Seeds stay below 2^53 so they survive a round trip through a JS number.
+const SEED_MASK: u64 = (1 << 53) - 1;
+
+#[derive(Deserialize)]
+pub struct DailyLeaderboardQuery {
+    /// `YYYY-MM-DD`; today (UTC) if absent.
+    pub day: Option<NaiveDate>,
+    pub limit: Option<i64>,
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// The same for every player and every server: a hash of the game and day.
+fn daily_seed(game_id: &str, day: NaiveDate) -> i64 {
+    let digest = Sha256::digest(format!("daily:{}:{}", game_id, day).as_bytes());
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(head) & SEED_MASK) as i64
+}
+
+fn daily_modifier(seed: i64) -> &'static Modifier {
+    &MODIFIERS[seed as usize % MODIFIERS.len()]
+}
+
+/// What the engine's `start_game` takes to play the day's challenge.
+fn engine_options(seed: i64, day: NaiveDate) -> Value {
+    let modifier = daily_modifier(seed);
+    let mut options = json!({ "daily": { "seed": seed, "day": day }, "mode": modifier.mode });
+    if let Some(limit) = modifier.time_limit {
+        options["timeLimit"] = json!(limit);
+    }
+    options
+}
+
+/// Where a score would place on the day's board.
+async fn daily_rank(state: &AppState, tenant_id: &str, game_id: &str, day: NaiveDate, score: i64) -> AppResult<i64> {
+    let rank: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint + 1 FROM daily_runs WHERE tenant_id = $1 AND game_id = $2 AND day = $3 AND score > $4",
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(day)
+    .bind(score)
+    .fetch_one(&state.db)
+    .await?;
+    Ok(rank)
+}
+
+/// Today's challenge for a game: its seed, modifiers and the options to
+/// start the engine with. Signed-in players also see their attempt, if
+/// they have started it.
+pub async fn get_daily(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let day = today();
+    let seed = daily_seed(&game_id, day);
+    let modifier = daily_modifier(seed);
+
+    let mut attempt = Value::Null;
+    if let Some(player) = player {
+        let run: Option<(Option<i64>, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT score, created_at FROM daily_runs WHERE tenant_id = $1 AND game_id = $2 AND day = $3 AND player_id = $4",
+        )
+        .bind(tenant_id)
+        .bind(&game_id)
+        .bind(day)
+        .bind(player.id)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some((score, started_at)) = run {
+            let rank = match score {
+                Some(score) => Some(daily_rank(&state, tenant_id, &game_id, day, score).await?),
+                None => None,
+            };
+            attempt = json!({ "score": score, "rank": rank, "startedAt": started_at });
+        }
+    }
+
+    let ends_at = (day + Duration::days(1)).and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    Ok(Json(json!({
+        "gameId": game_id,
+        "day": day,
+        "seed": seed,
+        "modifiers": [{ "id": modifier.id, "label": modifier.label }],
+        "options": engine_options(seed, day),
+        "endsAt": ends_at,
+        "attempt": attempt,
+    })))
+}
+
+/// Starts the player's single attempt at today's challenge. The attempt
+/// counts from here, whether or not a score follows; a second start is
+/// rejected. Returns the token the score must be submitted with.
+pub async fn start_daily_run(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let day = today();
+    let seed = daily_seed(&game_id, day);
+
+    let run_token: Option<Uuid> = sqlx::query_scalar(
+        r#"INSERT INTO daily_runs (tenant_id, game_id, day, player_id, seed, run_token)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (tenant_id, game_id, day, player_id) DO NOTHING
+        RETURNING run_token"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(&game_id)
+    .bind(day)
+    .bind(player.id)
+    .bind(seed)
+    .bind(Uuid::new_v4())
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(run_token) = run_token else {
+        return Err(AppError::Conflict(
+            "Today's daily challenge has already been played".into(),
+        ));
+    };
+
+    Ok(Json(json!({
+        "runToken": run_token,
+        "gameId": game_id,
+        "day": day,
+        "seed": seed,
+        "options": engine_options(seed, day),
+    })))
+}
+
+/// Records the score of the run started with the token. Each run takes
+/// one score. This is separate from `POST /scores/:gameId`, which the
+/// shell still calls for the player's regular progress.
+pub async fn submit_daily_run(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Json(body): Json<DailyRunRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    if body.score < 0 || body.score > 999_999 {
+        return Err(AppError::BadRequest(
+            "Score must be between 0 and 999999".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let run: Option<(NaiveDate, Option<DateTime<Utc>>)> = sqlx::query_as(
+        r#"SELECT day, submitted_at FROM daily_runs
+        WHERE tenant_id = $1 AND game_id = $2 AND player_id = $3 AND run_token = $4
+        FOR UPDATE"#,
+    )
+    .bind(tenant_id)
+    .bind(&game_id)
+    .bind(player.id)
+    .bind(body.run_token)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let day = match run {
+        None => return Err(AppError::BadRequest("Unknown run token".into())),
+        Some((_, Some(_))) => {
+            return Err(AppError::Conflict(
+                "This run has already been submitted".into(),
+            ))
+        }
+        Some((day, None)) => day,
+    };
+    sqlx::query(
+        r#"UPDATE daily_runs SET score = $5, level = $6, submitted_at = NOW()
+        WHERE tenant_id = $1 AND game_id = $2 AND player_id = $3 AND run_token = $4"#,
+    )
+    .bind(tenant_id)
+    .bind(&game_id)
+    .bind(player.id)
+    .bind(body.run_token)
+    .bind(body.score)
+    .bind(body.level)
+    .execute(&mut *tx)
+    .await?;
+    let reference = format!("{}:{}", game_id, day);
+    let battle_pass_xp =
+        battle_pass_xp::award(&mut tx, tenant_id, player.id, battle_pass_xp::DAILY_CHALLENGE, body.score, &reference)
+            .await?;
+    tx.commit().await?;
+
+    let rank = daily_rank(&state, tenant_id, &game_id, day, body.score).await?;
+    Ok(Json(json!({
+        "success": true,
+        "day": day,
+        "score": body.score,
+        "rank": rank,
+        "battlePassXp": battle_pass_xp,
+    })))
+}
+
+/// The day's attempts, best first.
+pub async fn get_daily_leaderboard(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Query(q): Query<DailyLeaderboardQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let day = q.day.unwrap_or_else(today);
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+
+    let rows: Vec<(String, i64, Option<i32>, String, i64, Value)> = sqlx::query_as(&format!(
+        r#"SELECT p.id::text, d.score, d.level, p.display_name,
+            RANK() OVER (ORDER BY d.score DESC)::bigint as rank, {}
+        FROM daily_runs d
+        JOIN players p ON p.id = d.player_id AND p.tenant_id = d.tenant_id
+        WHERE d.tenant_id = $1 AND d.game_id = $2 AND d.day = $3 AND d.score IS NOT NULL
+        ORDER BY d.score DESC, d.submitted_at
+        LIMIT $4"#,
+        cosmetics::EQUIPPED_SQL
+    ))
+    .bind(tenant_id)
+    .bind(&game_id)
+    .bind(day)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut entries: Vec<Value> = rows
+        .iter()
+        .map(|(pid, score, level, name, rank, cosmetics)| {
+            json!({"rank": rank, "playerId": pid, "displayName": name, "score": score, "level": level, "cosmetics": cosmetics})
+        })
+        .collect();
+    privacy::anonymize(&state.db, tenant_id, &mut entries).await?;
+
+    Ok(Json(json!({ "gameId": game_id, "day": day, "entries": entries })))
+}
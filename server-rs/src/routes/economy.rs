@@ -1,15 +1,22 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    response::IntoResponse,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::localization::LocaleInfo;
 use crate::middleware::tenant::TenantId;
 use crate::models::economy::*;
 use crate::routes::leaderboards::PaginationQuery;
+use crate::services::entity_audit::{self, Audit};
+use crate::services::{battle_pass, battle_pass_xp, cosmetics, loot, store, translations};
 use crate::AppState;
 
 pub async fn get_wallet(
@@ -113,66 +120,132 @@ pub struct StoreQuery {
     pub item_type: Option<String>,
 }
 
+/// Items on sale now, at server-computed prices, with bundle contents and
+/// how many of each the player has bought.
 pub async fn list_store(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
     Query(q): Query<StoreQuery>,
 ) -> AppResult<Json<Value>> {
-    let rows: Vec<StoreItem> = if let Some(ref t) = q.item_type {
-        sqlx::query_as(
-            "SELECT * FROM store_items WHERE tenant_id = $1 AND is_active = true AND item_type = $2 ORDER BY price",
-        )
-        .bind(&tenant.0 .0)
-        .bind(t)
-        .fetch_all(&state.db)
-        .await?
-    } else {
-        sqlx::query_as(
-            "SELECT * FROM store_items WHERE tenant_id = $1 AND is_active = true ORDER BY price",
-        )
-        .bind(&tenant.0 .0)
-        .fetch_all(&state.db)
-        .await?
-    };
+    let tid = &tenant.0 .0;
+    let offers: Vec<StoreOffer> = sqlx::query_as(&format!(
+        "{} WHERE si.tenant_id = $1 AND {} AND ($2::text IS NULL OR si.item_type = $2) ORDER BY si.price",
+        store::OFFER_SQL,
+        store::AVAILABLE_SQL
+    ))
+    .bind(tid)
+    .bind(&q.item_type)
+    .fetch_all(&state.db)
+    .await?;
 
-    Ok(Json(json!({ "items": rows })))
+    let items = offers_json(&state, tid, player.id, &locale.locale, &offers).await?;
+    Ok(Json(json!({ "items": items })))
 }
 
-pub async fn purchase(
+/// The current featured rotation: up to `store::FEATURED_SLOTS` featured
+/// items, reshuffled every rotation.
+pub async fn featured_store(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
-    Json(body): Json<PurchaseRequest>,
+    locale: axum::Extension<LocaleInfo>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
-
-    // Get item
-    let item: StoreItem = sqlx::query_as(
-        "SELECT * FROM store_items WHERE id = $1 AND tenant_id = $2 AND is_active = true",
-    )
-    .bind(&body.item_id)
-    .bind(tid)
-    .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Item not found".into()))?;
-
-    // Check already owned
-    let owned: bool = sqlx::query_scalar(
-        "SELECT EXISTS(SELECT 1 FROM player_inventory WHERE player_id = $1 AND tenant_id = $2 AND item_id = $3)",
-    )
-    .bind(player.id)
+    let (rotation, rotates_at) = store::featured_rotation(chrono::Utc::now());
+    let offers: Vec<StoreOffer> = sqlx::query_as(&format!(
+        "{} WHERE si.tenant_id = $1 AND {} AND si.featured ORDER BY md5(si.id || ':' || $2) LIMIT $3",
+        store::OFFER_SQL,
+        store::AVAILABLE_SQL
+    ))
     .bind(tid)
-    .bind(&body.item_id)
-    .fetch_one(&state.db)
+    .bind(rotation.to_string())
+    .bind(store::FEATURED_SLOTS)
+    .fetch_all(&state.db)
     .await?;
 
-    if owned {
-        return Err(AppError::Conflict("Already owned".into()));
+    let items = offers_json(&state, tid, player.id, &locale.locale, &offers).await?;
+    Ok(Json(json!({ "items": items, "rotatesAt": rotates_at })))
+}
+
+async fn offers_json(
+    state: &AppState,
+    tid: &str,
+    player_id: Uuid,
+    locale: &str,
+    offers: &[StoreOffer],
+) -> AppResult<Vec<Value>> {
+    let ids: Vec<String> = offers.iter().map(|o| o.item.id.clone()).collect();
+    let bundles: Vec<String> = offers
+        .iter()
+        .filter(|o| o.item.item_type == "bundle")
+        .map(|o| o.item.id.clone())
+        .collect();
+    let contents = store::bundle_contents(&state.db, tid, &bundles).await?;
+    let purchased = store::purchase_counts(&state.db, tid, player_id, &ids).await?;
+    let t = translations::for_locale(&state.db, &state.cache, tid, locale).await?;
+
+    Ok(offers
+        .iter()
+        .map(|o| {
+            let bought = purchased.get(&o.item.id).copied().unwrap_or(0);
+            store::offer_json(o, contents.get(&o.item.id), bought, &t)
+        })
+        .collect())
+}
+
+/// Buys one item at its current price. Bundles grant their contents.
+pub async fn purchase(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<PurchaseRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let offer = store::find_offer(&state.db, tid, &body.item_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Item not found".into()))?;
+    let item = &offer.item;
+    let price = offer.price();
+    if body.expected_price.is_some_and(|p| p != price) {
+        return Err(AppError::Conflict("Price has changed".into()));
     }
 
+    let grants: Vec<(String, i32)> = if item.item_type == "bundle" {
+        let contents = store::bundle_contents(&state.db, tid, std::slice::from_ref(&item.id)).await?;
+        let grants: Vec<(String, i32)> = contents
+            .get(&item.id)
+            .into_iter()
+            .flatten()
+            .map(|c| (c.item_id.clone(), c.quantity))
+            .collect();
+        if grants.is_empty() {
+            return Err(AppError::BadRequest("This bundle is empty".into()));
+        }
+        grants
+    } else {
+        // Check already owned. Crates are consumed when opened, so they
+        // stack instead.
+        let owned: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM player_inventory WHERE player_id = $1 AND tenant_id = $2 AND item_id = $3)",
+        )
+        .bind(player.id)
+        .bind(tid)
+        .bind(&body.item_id)
+        .fetch_one(&state.db)
+        .await?;
+        if owned && item.item_type != "crate" {
+            return Err(AppError::Conflict("Already owned".into()));
+        }
+        vec![(item.id.clone(), 1)]
+    };
+
     let mut tx = state.db.begin().await?;
 
-    // Check and debit balance
+    // Check and debit balance. The wallet lock also serialises the
+    // player's purchases for the limit check below.
     let balance: Option<i64> = sqlx::query_scalar(
         "SELECT balance FROM player_wallets WHERE player_id = $1 AND tenant_id = $2 AND currency_type = $3 FOR UPDATE",
     )
@@ -182,34 +255,49 @@ pub async fn purchase(
     .fetch_optional(&mut *tx)
     .await?;
 
+    if let Some(limit) = item.purchase_limit {
+        let counts = store::purchase_counts(&mut *tx, tid, player.id, std::slice::from_ref(&item.id)).await?;
+        if counts.get(&item.id).copied().unwrap_or(0) >= i64::from(limit) {
+            return Err(AppError::Conflict("Purchase limit reached".into()));
+        }
+    }
+
     let current = balance.unwrap_or(0);
-    if current < item.price {
+    if current < price {
         return Err(AppError::BadRequest("Insufficient balance".into()));
     }
 
-    let new_balance = current - item.price;
+    let new_balance = current - price;
 
     sqlx::query("UPDATE player_wallets SET balance = $1, updated_at = NOW() WHERE player_id = $2 AND tenant_id = $3 AND currency_type = $4")
         .bind(new_balance).bind(player.id).bind(tid).bind(&item.currency_type)
         .execute(&mut *tx).await?;
 
-    sqlx::query("INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, created_at) VALUES ($1, $2, $3, $4, $5, 'spend', 'store', $6, NOW())")
-        .bind(tid).bind(player.id).bind(&item.currency_type).bind(-item.price).bind(new_balance).bind(&body.item_id)
+    sqlx::query("INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at) VALUES ($1, $2, $3, $4, $5, 'spend', 'store', $6, $7, NOW())")
+        .bind(tid).bind(player.id).bind(&item.currency_type).bind(-price).bind(new_balance).bind(&body.item_id)
+        .bind(json!({ "basePrice": item.price, "discountPercent": offer.discount_percent }))
         .execute(&mut *tx).await?;
 
-    sqlx::query("INSERT INTO player_inventory (tenant_id, player_id, item_id, source, acquired_at) VALUES ($1, $2, $3, 'store', NOW())")
-        .bind(tid).bind(player.id).bind(&body.item_id)
-        .execute(&mut *tx).await?;
+    for (item_id, quantity) in &grants {
+        sqlx::query("INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at) VALUES ($1, $2, $3, $4, 'store', NOW()) ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET quantity = player_inventory.quantity + EXCLUDED.quantity")
+            .bind(tid).bind(player.id).bind(item_id).bind(quantity)
+            .execute(&mut *tx).await?;
+    }
 
     tx.commit().await?;
 
-    Ok(Json(json!({"success": true, "newBalance": new_balance})))
+    let granted: Vec<Value> = grants
+        .iter()
+        .map(|(item_id, quantity)| json!({"itemId": item_id, "quantity": quantity}))
+        .collect();
+    Ok(Json(json!({"success": true, "newBalance": new_balance, "price": price, "granted": granted})))
 }
 
 pub async fn inventory(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
 ) -> AppResult<Json<Value>> {
     let rows: Vec<(String, String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
         r#"SELECT pi.item_id, si.name, pi.source, pi.acquired_at
@@ -223,25 +311,527 @@ pub async fn inventory(
     .fetch_all(&state.db)
     .await?;
 
+    let t = translations::for_locale(&state.db, &state.cache, &tenant.0 .0, &locale.locale).await?;
     let items: Vec<Value> = rows.iter().map(|(id, name, source, acquired)| {
+        let name = t.text("store_item", &format!("{}.name", id), name);
         json!({"itemId": id, "name": name, "source": source, "acquiredAt": acquired})
     }).collect();
 
     Ok(Json(json!({ "inventory": items })))
 }
 
+/// Drop table of an active crate, in roll order.
+async fn load_drops(db: &PgPool, tid: &str, crate_id: &str) -> AppResult<Vec<CrateDrop>> {
+    let drops: Vec<CrateDrop> = sqlx::query_as(
+        r#"SELECT cd.item_id, si.name, cd.quantity, cd.weight, cd.rarity
+        FROM crate_drops cd
+        JOIN store_items si ON si.id = cd.item_id AND si.tenant_id = cd.tenant_id
+        WHERE cd.tenant_id = $1 AND cd.crate_id = $2
+        ORDER BY cd.item_id"#,
+    )
+    .bind(tid)
+    .bind(crate_id)
+    .fetch_all(db)
+    .await?;
+    Ok(drops)
+}
+
+fn odds_json(item: &StoreItem, drops: &[CrateDrop]) -> Value {
+    let total = loot::total_weight(drops);
+    let mut by_rarity: HashMap<&str, f64> = HashMap::new();
+    let entries: Vec<Value> = drops
+        .iter()
+        .map(|d| {
+            let probability = d.weight as f64 / total as f64;
+            *by_rarity.entry(d.rarity.as_str()).or_default() += probability;
+            json!({
+                "itemId": d.item_id,
+                "name": d.name,
+                "quantity": d.quantity,
+                "rarity": d.rarity,
+                "weight": d.weight,
+                "probability": probability,
+            })
+        })
+        .collect();
+
+    json!({
+        "crateId": item.id,
+        "name": item.name,
+        "price": item.price,
+        "currencyType": item.currency_type,
+        "totalWeight": total,
+        "drops": entries,
+        "rarityOdds": by_rarity,
+    })
+}
+
+/// Public odds disclosure: every active crate with the exact chance of
+/// each drop.
+pub async fn list_crate_odds(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let crates: Vec<StoreItem> = sqlx::query_as(
+        "SELECT * FROM store_items WHERE tenant_id = $1 AND is_active = true AND item_type = 'crate' ORDER BY price",
+    )
+    .bind(tid)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut out = Vec::with_capacity(crates.len());
+    for item in &crates {
+        let drops = load_drops(&state.db, tid, &item.id).await?;
+        out.push(odds_json(item, &drops));
+    }
+
+    Ok(Json(json!({ "crates": out })))
+}
+
+pub async fn get_crate_odds(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(crate_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let item: StoreItem = sqlx::query_as(
+        "SELECT * FROM store_items WHERE id = $1 AND tenant_id = $2 AND is_active = true AND item_type = 'crate'",
+    )
+    .bind(&crate_id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Crate not found".into()))?;
+
+    let drops = load_drops(&state.db, tid, &item.id).await?;
+    Ok(Json(odds_json(&item, &drops)))
+}
+
+/// Opens one crate from the player's inventory. The roll happens here, from
+/// a fresh seed that is stored with the opening (see `services::loot`).
+pub async fn open_crate(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<OpenCrateRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let is_crate: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM store_items WHERE id = $1 AND tenant_id = $2 AND item_type = 'crate')",
+    )
+    .bind(&body.crate_id)
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
+    if !is_crate {
+        return Err(AppError::NotFound("Crate not found".into()));
+    }
+
+    let drops = load_drops(&state.db, tid, &body.crate_id).await?;
+    let total = loot::total_weight(&drops);
+    if total <= 0 {
+        return Err(AppError::BadRequest("This crate has no drop table".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    let owned: Option<i32> = sqlx::query_scalar(
+        "SELECT quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3 FOR UPDATE",
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&body.crate_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let remaining = owned.unwrap_or(0) - 1;
+    if remaining < 0 {
+        return Err(AppError::BadRequest("You don't have this crate".into()));
+    }
+    if remaining == 0 {
+        sqlx::query("DELETE FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(tid).bind(player.id).bind(&body.crate_id)
+            .execute(&mut *tx).await?;
+    } else {
+        sqlx::query("UPDATE player_inventory SET quantity = $4 WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(tid).bind(player.id).bind(&body.crate_id).bind(remaining)
+            .execute(&mut *tx).await?;
+    }
+    cosmetics::unequip_if_gone(&mut tx, tid, player.id, &body.crate_id).await?;
+
+    let seed = loot::new_seed();
+    let roll = loot::roll(&seed, total);
+    let drop = loot::pick(&drops, roll)
+        .ok_or_else(|| AppError::Internal("Crate roll out of range".into()))?;
+
+    let opening_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO crate_openings (tenant_id, player_id, crate_id, seed, roll, total_weight, drop_table, item_id, quantity)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&body.crate_id)
+    .bind(&seed)
+    .bind(roll)
+    .bind(total)
+    .bind(serde_json::to_value(&drops).unwrap_or_default())
+    .bind(&drop.item_id)
+    .bind(drop.quantity)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let received: i32 = sqlx::query_scalar(
+        r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+        VALUES ($1, $2, $3, $4, 'crate', NOW())
+        ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+            quantity = player_inventory.quantity + EXCLUDED.quantity
+        RETURNING quantity"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .bind(&drop.item_id)
+    .bind(drop.quantity)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (item_id, delta, after, tx_type) in [
+        (&body.crate_id, -1, remaining, "crate_open"),
+        (&drop.item_id, drop.quantity, received, "crate_reward"),
+    ] {
+        sqlx::query(
+            r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+            VALUES ($1, $2, 'item', $3, $4, $5, 'crate', $6, $7, NOW())"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(i64::from(delta))
+        .bind(i64::from(after))
+        .bind(tx_type)
+        .bind(opening_id.to_string())
+        .bind(json!({ "itemId": item_id, "crateId": body.crate_id }))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(json!({
+        "openingId": opening_id,
+        "crateId": body.crate_id,
+        "item": {
+            "itemId": drop.item_id,
+            "name": drop.name,
+            "quantity": drop.quantity,
+            "rarity": drop.rarity,
+        },
+        "remainingCrates": remaining,
+        "audit": { "seed": seed, "roll": roll, "totalWeight": total },
+    })))
+}
+
+const CRATE_RARITIES: &[&str] = &["common", "uncommon", "rare", "epic", "legendary"];
+
+/// Admin: replace a crate's drop table.
+pub async fn set_crate_drops(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(crate_id): Path<String>,
+    Json(body): Json<SetCrateDropsRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let item: StoreItem = sqlx::query_as(
+        "SELECT * FROM store_items WHERE id = $1 AND tenant_id = $2 AND item_type = 'crate'",
+    )
+    .bind(&crate_id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Crate not found".into()))?;
+
+    if body.drops.is_empty() {
+        return Err(AppError::BadRequest("A crate needs at least one drop".into()));
+    }
+    for d in &body.drops {
+        if d.weight <= 0 || d.quantity <= 0 {
+            return Err(AppError::BadRequest("Weights and quantities must be positive".into()));
+        }
+        if let Some(r) = d.rarity.as_deref() {
+            if !CRATE_RARITIES.contains(&r) {
+                return Err(AppError::BadRequest(format!("Unknown rarity: {}", r)));
+            }
+        }
+    }
+
+    let item_ids: Vec<&str> = body.drops.iter().map(|d| d.item_id.as_str()).collect();
+    let valid: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM store_items WHERE tenant_id = $1 AND id = ANY($2) AND item_type NOT IN ('crate', 'battle_pass')",
+    )
+    .bind(tid)
+    .bind(&item_ids)
+    .fetch_one(&state.db)
+    .await?;
+    if valid != item_ids.len() as i64 {
+        return Err(AppError::BadRequest(
+            "Drops must be distinct store items other than crates or battle passes".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query("DELETE FROM crate_drops WHERE tenant_id = $1 AND crate_id = $2")
+        .bind(tid)
+        .bind(&crate_id)
+        .execute(&mut *tx)
+        .await?;
+    for d in &body.drops {
+        sqlx::query(
+            "INSERT INTO crate_drops (tenant_id, crate_id, item_id, quantity, weight, rarity) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(tid)
+        .bind(&crate_id)
+        .bind(&d.item_id)
+        .bind(d.quantity)
+        .bind(d.weight)
+        .bind(d.rarity.as_deref().unwrap_or("common"))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    let drops = load_drops(&state.db, tid, &crate_id).await?;
+    Ok(Json(odds_json(&item, &drops)))
+}
+
+/// Admin: replace a bundle's contents.
+pub async fn set_bundle_contents(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(bundle_id): Path<String>,
+    Json(body): Json<SetBundleContentsRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let is_bundle: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM store_items WHERE id = $1 AND tenant_id = $2 AND item_type = 'bundle' AND deleted_at IS NULL)",
+    )
+    .bind(&bundle_id)
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
+    if !is_bundle {
+        return Err(AppError::NotFound("Bundle not found".into()));
+    }
+
+    if body.contents.is_empty() {
+        return Err(AppError::BadRequest("A bundle needs at least one item".into()));
+    }
+    if body.contents.iter().any(|c| c.quantity <= 0) {
+        return Err(AppError::BadRequest("Quantities must be positive".into()));
+    }
+
+    let item_ids: Vec<&str> = body.contents.iter().map(|c| c.item_id.as_str()).collect();
+    let valid: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM store_items WHERE tenant_id = $1 AND id = ANY($2) AND item_type NOT IN ('bundle', 'battle_pass')",
+    )
+    .bind(tid)
+    .bind(&item_ids)
+    .fetch_one(&state.db)
+    .await?;
+    if valid != item_ids.len() as i64 {
+        return Err(AppError::BadRequest(
+            "Contents must be distinct store items other than bundles or battle passes".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::STORE_ITEM, &bundle_id).await?;
+    sqlx::query("DELETE FROM bundle_contents WHERE tenant_id = $1 AND bundle_id = $2")
+        .bind(tid)
+        .bind(&bundle_id)
+        .execute(&mut *tx)
+        .await?;
+    for c in &body.contents {
+        sqlx::query("INSERT INTO bundle_contents (tenant_id, bundle_id, item_id, quantity) VALUES ($1, $2, $3, $4)")
+            .bind(tid)
+            .bind(&bundle_id)
+            .bind(&c.item_id)
+            .bind(c.quantity)
+            .execute(&mut *tx)
+            .await?;
+    }
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
+    let contents = store::bundle_contents(&state.db, tid, std::slice::from_ref(&bundle_id)).await?;
+    Ok(Json(json!({ "bundleId": bundle_id, "contents": contents.get(&bundle_id) })))
+}
+
+/// Admin: set an item's purchase limit, featured flag and availability
+/// window.
+pub async fn set_item_offer(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(item_id): Path<String>,
+    Json(body): Json<SetItemOfferRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if body.purchase_limit.is_some_and(|l| l <= 0) {
+        return Err(AppError::BadRequest("purchaseLimit must be positive".into()));
+    }
+    if let (Some(from), Some(until)) = (body.available_from, body.available_until) {
+        if until <= from {
+            return Err(AppError::BadRequest("availableUntil must be after availableFrom".into()));
+        }
+    }
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::STORE_ITEM, &item_id).await?;
+    if audit.existing().is_none() {
+        return Err(AppError::NotFound("Item not found".into()));
+    }
+    let item: StoreItem = sqlx::query_as(
+        r#"UPDATE store_items SET purchase_limit = $3, featured = $4, available_from = $5, available_until = $6
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING *"#,
+    )
+    .bind(&item_id)
+    .bind(tid)
+    .bind(body.purchase_limit)
+    .bind(body.featured)
+    .bind(body.available_from)
+    .bind(body.available_until)
+    .fetch_one(&mut *tx)
+    .await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "item": item })))
+}
+
+/// Admin: soft-delete a store item. It leaves the store, while inventories
+/// that hold it are untouched.
+pub async fn delete_store_item(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(item_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let mut tx = state.db.begin().await?;
+    if !entity_audit::soft_delete(&mut tx, &tenant.0 .0, player.id, entity_audit::STORE_ITEM, &item_id).await? {
+        return Err(AppError::NotFound("Item not found".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Admin: sales that are running or scheduled.
+pub async fn list_sales(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let sales: Vec<StoreSale> = sqlx::query_as(
+        "SELECT * FROM store_sales WHERE tenant_id = $1 AND ends_at > NOW() ORDER BY starts_at",
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(json!({ "sales": sales })))
+}
+
+/// Admin: schedule a sale on one item, or on every item without `itemId`.
+pub async fn create_sale(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateSaleRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    if !(1..=store::MAX_DISCOUNT_PERCENT).contains(&body.discount_percent) {
+        return Err(AppError::BadRequest(format!(
+            "discountPercent must be between 1 and {}",
+            store::MAX_DISCOUNT_PERCENT
+        )));
+    }
+    let starts_at = body.starts_at.unwrap_or_else(chrono::Utc::now);
+    if body.ends_at <= starts_at {
+        return Err(AppError::BadRequest("endsAt must be after startsAt".into()));
+    }
+    if let Some(ref item_id) = body.item_id {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM store_items WHERE id = $1 AND tenant_id = $2)",
+        )
+        .bind(item_id)
+        .bind(tid)
+        .fetch_one(&state.db)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Item not found".into()));
+        }
+    }
+
+    let sale: StoreSale = sqlx::query_as(
+        r#"INSERT INTO store_sales (tenant_id, item_id, discount_percent, starts_at, ends_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *"#,
+    )
+    .bind(tid)
+    .bind(&body.item_id)
+    .bind(body.discount_percent)
+    .bind(starts_at)
+    .bind(body.ends_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "sale": sale })))
+}
+
+/// Admin: cancel a sale, ending it at once if it is running.
+pub async fn delete_sale(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let deleted = sqlx::query("DELETE FROM store_sales WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(&tenant.0 .0)
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Sale not found".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+/// The active battle pass and its tiers. Tiers and rewards get a `label`
+/// in the request locale where the tenant has translated their tier.
 pub async fn get_battlepass(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
 ) -> AppResult<Json<Value>> {
-    let bp: Option<BattlePass> = sqlx::query_as(
+    let mut bp: Option<BattlePass> = sqlx::query_as(
         "SELECT * FROM battle_passes WHERE tenant_id = $1 AND is_active = true LIMIT 1",
     )
     .bind(&tenant.0 .0)
     .fetch_optional(&state.db)
     .await?;
 
-    Ok(Json(json!({ "battlePass": bp })))
+    let mut tiers = Value::Null;
+    if let Some(ref mut bp) = bp {
+        let t = translations::for_locale(&state.db, &state.cache, &tenant.0 .0, &locale.locale).await?;
+        let pass_id = bp.id.to_string();
+        translations::label_tiers(&mut bp.free_rewards, &pass_id, &t);
+        translations::label_tiers(&mut bp.premium_rewards, &pass_id, &t);
+        tiers = json!(battle_pass::tiers(&state.db, bp.id).await?);
+        translations::label_tiers(&mut tiers, &pass_id, &t);
+    }
+
+    Ok(Json(json!({ "battlePass": bp, "tiers": tiers })))
 }
 
 pub async fn get_battlepass_progress(
@@ -296,6 +886,8 @@ pub async fn purchase_battlepass(
     Ok(Json(json!({"success": true})))
 }
 
+/// Claims a reached tier: its free reward, and its premium one with the
+/// premium pass. Nothing can be claimed once the pass has ended.
 pub async fn claim_tier(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -304,14 +896,23 @@ pub async fn claim_tier(
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
 
+    let mut tx = state.db.begin().await?;
     let progress: Option<PlayerBattlePass> = sqlx::query_as(
-        "SELECT * FROM player_battle_pass WHERE player_id = $1 AND tenant_id = $2 ORDER BY updated_at DESC LIMIT 1",
+        "SELECT * FROM player_battle_pass WHERE player_id = $1 AND tenant_id = $2 ORDER BY updated_at DESC LIMIT 1 FOR UPDATE",
     )
-    .bind(player.id).bind(tid).fetch_optional(&state.db).await?;
+    .bind(player.id).bind(tid).fetch_optional(&mut *tx).await?;
 
     let progress = progress.ok_or_else(|| AppError::NotFound("No battle pass".into()))?;
 
-    if body.tier > progress.current_tier {
+    let open: bool = sqlx::query_scalar(
+        "SELECT closed_at IS NULL AND (ends_at IS NULL OR ends_at > NOW()) FROM battle_passes WHERE id = $1",
+    )
+    .bind(progress.battle_pass_id).fetch_one(&mut *tx).await?;
+    if !open {
+        return Err(AppError::BadRequest("This battle pass has ended".into()));
+    }
+
+    if body.tier < 1 || body.tier > progress.current_tier {
         return Err(AppError::BadRequest("Tier not yet reached".into()));
     }
 
@@ -320,64 +921,1002 @@ pub async fn claim_tier(
         return Err(AppError::Conflict("Tier already claimed".into()));
     }
 
+    let rewards = battle_pass::tier_rewards(&mut tx, progress.battle_pass_id, body.tier, progress.is_premium).await?;
+    for reward in &rewards {
+        battle_pass::grant(&mut tx, tid, player.id, progress.battle_pass_id, body.tier, reward).await?;
+    }
+
     let mut new_claimed = claimed;
     new_claimed.push(body.tier);
 
     sqlx::query("UPDATE player_battle_pass SET claimed_tiers = $1, updated_at = NOW() WHERE player_id = $2 AND tenant_id = $3 AND battle_pass_id = $4")
-        .bind(json!(new_claimed)).bind(player.id).bind(tid).bind(&progress.battle_pass_id)
-        .execute(&state.db).await?;
+        .bind(json!(new_claimed)).bind(player.id).bind(tid).bind(progress.battle_pass_id)
+        .execute(&mut *tx).await?;
+    tx.commit().await?;
 
-    Ok(Json(json!({"success": true, "claimedTiers": new_claimed})))
+    Ok(Json(json!({"success": true, "claimedTiers": new_claimed, "rewards": rewards})))
 }
 
+/// Deprecated: awards a player raw XP on the active pass. Only backends
+/// with a `battlepass:write` API key may call it; players earn XP from
+/// [`battle_pass_xp`] instead. With a `referenceId`, the XP is awarded once
+/// per id.
 pub async fn award_xp(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Json(body): Json<AwardXpRequest>,
+) -> AppResult<impl IntoResponse> {
+    let tid = &tenant.0 .0;
+    let deprecation = [("deprecation", "true")];
+    if body.xp <= 0 {
+        return Err(AppError::BadRequest("xp must be positive".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let Some(pass_id) = battle_pass::active_pass(&mut *tx, tid).await? else {
+        return Ok((deprecation, Json(json!({"success": false, "message": "No active battle pass"}))));
+    };
+    if !battle_pass_xp::record(&mut tx, tid, player.id, pass_id, battle_pass_xp::API, body.reference_id.as_deref(), body.xp).await? {
+        return Ok((deprecation, Json(json!({"success": false, "message": "XP already awarded for this referenceId"}))));
+    }
+    let gain = battle_pass::add_xp(&mut tx, tid, player.id, pass_id, body.xp).await?;
+    tx.commit().await?;
+
+    Ok((deprecation, Json(json!({"currentTier": gain.current_tier, "currentXp": gain.current_xp, "xpToNextTier": gain.xp_to_next_tier}))))
+}
+
+/// Admin: the tenant's battle passes, newest first.
+pub async fn admin_list_battlepasses(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let passes: Vec<Value> = sqlx::query_scalar(
+        "SELECT to_jsonb(bp) FROM battle_passes bp WHERE tenant_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC",
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(json!({ "battlePasses": passes })))
+}
+
+/// Most tiers a battle pass can have.
+const MAX_BATTLE_PASS_TIERS: usize = 200;
+
+/// A battle pass with its tiers, for the admin routes.
+async fn admin_battlepass_json(db: &PgPool, tenant_id: &str, id: i32) -> AppResult<Value> {
+    let pass: Value = sqlx::query_scalar(
+        "SELECT to_jsonb(bp) FROM battle_passes bp WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Battle pass not found".into()))?;
+    let players: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM player_battle_pass WHERE battle_pass_id = $1")
+        .bind(id)
+        .fetch_one(db)
+        .await?;
+    let tiers = battle_pass::tiers(db, id).await?;
+    Ok(json!({ "battlePass": pass, "tiers": tiers, "players": players }))
+}
+
+/// Checks that thresholds rise from tier to tier and that rewards are
+/// positive amounts of a known currency or a grantable store item.
+async fn validate_tiers(conn: &mut sqlx::PgConnection, tid: &str, tiers: &[BattlePassTierInput]) -> AppResult<()> {
+    if tiers.len() > MAX_BATTLE_PASS_TIERS {
+        return Err(AppError::BadRequest(format!("A battle pass has at most {} tiers", MAX_BATTLE_PASS_TIERS)));
+    }
+    let mut previous = 0;
+    let mut item_ids: Vec<&str> = Vec::new();
+    for t in tiers {
+        if t.xp_threshold <= previous {
+            return Err(AppError::BadRequest("xpThreshold must be positive and rise with each tier".into()));
+        }
+        previous = t.xp_threshold;
+        for reward in [&t.free, &t.premium].into_iter().flatten() {
+            if reward.amount <= 0 {
+                return Err(AppError::BadRequest("Reward amounts must be positive".into()));
+            }
+            match (reward.reward_type.as_str(), &reward.currency_type, &reward.item_id) {
+                ("currency", Some(ct), None) => {
+                    if !TRADE_CURRENCIES.contains(&ct.as_str()) {
+                        return Err(AppError::BadRequest(format!("Unknown currency: {}", ct)));
+                    }
+                }
+                ("item", None, Some(item_id)) => {
+                    if !item_ids.contains(&item_id.as_str()) {
+                        item_ids.push(item_id);
+                    }
+                }
+                _ => {
+                    return Err(AppError::BadRequest(
+                        "A reward is a currency reward with currencyType or an item reward with itemId".into(),
+                    ))
+                }
+            }
+        }
+    }
+
+    let valid: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM store_items WHERE tenant_id = $1 AND id = ANY($2) AND deleted_at IS NULL AND item_type NOT IN ('bundle', 'battle_pass')",
+    )
+    .bind(tid)
+    .bind(&item_ids)
+    .fetch_one(&mut *conn)
+    .await?;
+    if valid != item_ids.len() as i64 {
+        return Err(AppError::BadRequest(
+            "Item rewards must be store items other than bundles or battle passes".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a pass's window and that it doesn't overlap another scheduled
+/// pass that hasn't closed.
+async fn validate_window(
+    conn: &mut sqlx::PgConnection,
+    tid: &str,
+    id: Option<i32>,
+    starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    ends_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> AppResult<()> {
+    if let (Some(from), Some(until)) = (starts_at, ends_at) {
+        if until <= from {
+            return Err(AppError::BadRequest("endsAt must be after startsAt".into()));
+        }
+    }
+    if starts_at.is_none() {
+        return Ok(());
+    }
+    let overlapping: Option<String> = sqlx::query_scalar(
+        r#"SELECT name FROM battle_passes
+        WHERE tenant_id = $1 AND id IS DISTINCT FROM $2 AND starts_at IS NOT NULL
+            AND closed_at IS NULL AND deleted_at IS NULL
+            AND tstzrange(starts_at, ends_at) && tstzrange($3, $4)
+        LIMIT 1"#,
+    )
+    .bind(tid)
+    .bind(id)
+    .bind(starts_at)
+    .bind(ends_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match overlapping {
+        Some(name) => Err(AppError::Conflict(format!("The window overlaps battle pass '{}'", name))),
+        None => Ok(()),
+    }
+}
+
+/// Admin: a battle pass with its tiers and how many players have progress
+/// on it.
+pub async fn admin_get_battlepass(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<Value>> {
+    Ok(Json(admin_battlepass_json(&state.db, &tenant.0 .0, id).await?))
+}
+
+/// Admin: create a battle pass, inactive. One with `startsAt` is switched
+/// on when its window opens; others are switched on by hand.
+pub async fn create_battlepass(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateBattlePassRequest>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name can't be empty".into()));
+    }
+    if body.ends_at.is_some_and(|at| at <= chrono::Utc::now()) {
+        return Err(AppError::BadRequest("endsAt must be in the future".into()));
+    }
 
-    let bp: Option<BattlePass> = sqlx::query_as(
-        "SELECT * FROM battle_passes WHERE tenant_id = $1 AND is_active = true LIMIT 1",
+    let mut tx = state.db.begin().await?;
+    if let Some(season_id) = body.season_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM seasons WHERE id = $1 AND tenant_id = $2)")
+            .bind(season_id)
+            .bind(tid)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("Season not found".into()));
+        }
+    }
+    validate_window(&mut tx, tid, None, body.starts_at, body.ends_at).await?;
+    validate_tiers(&mut tx, tid, &body.tiers).await?;
+
+    let id: i32 = sqlx::query_scalar("SELECT nextval(pg_get_serial_sequence('battle_passes', 'id'))::int")
+        .fetch_one(&mut *tx)
+        .await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::BATTLE_PASS, &id.to_string()).await?;
+    sqlx::query(
+        r#"INSERT INTO battle_passes (id, tenant_id, season_id, name, max_tier, is_active, starts_at, ends_at)
+        VALUES ($1, $2, $3, $4, 0, false, $5, $6)"#,
     )
-    .bind(tid).fetch_optional(&state.db).await?;
+    .bind(id)
+    .bind(tid)
+    .bind(body.season_id)
+    .bind(name)
+    .bind(body.starts_at)
+    .bind(body.ends_at)
+    .execute(&mut *tx)
+    .await?;
+    battle_pass::set_tiers(&mut tx, tid, id, &body.tiers).await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
 
-    let bp = match bp {
-        Some(b) => b,
-        None => return Ok(Json(json!({"success": false, "message": "No active battle pass"}))),
+    Ok(Json(admin_battlepass_json(&state.db, tid, id).await?))
+}
+
+/// Admin: replace a battle pass's tiers, listed from tier 1 up. Players
+/// keep the tier they reached and the tiers they claimed.
+pub async fn set_battlepass_tiers(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<i32>,
+    Json(body): Json<SetBattlePassTiersRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::BATTLE_PASS, &id.to_string()).await?;
+    let Some(existing) = audit.existing() else {
+        return Err(AppError::NotFound("Battle pass not found".into()));
     };
+    if !existing["closed_at"].is_null() {
+        return Err(AppError::Conflict("This battle pass has closed".into()));
+    }
+    validate_tiers(&mut tx, tid, &body.tiers).await?;
+    battle_pass::set_tiers(&mut tx, tid, id, &body.tiers).await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
+    Ok(Json(admin_battlepass_json(&state.db, tid, id).await?))
+}
+
+/// Admin: update a battle pass; omitted fields are kept. Activating one
+/// deactivates the tenant's other passes. Passes with a window are switched
+/// on and off by it instead, and closed passes can only be renamed.
+pub async fn update_battlepass(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateBattlePassRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if body.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::BadRequest("name can't be empty".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::BATTLE_PASS, &id.to_string()).await?;
+    if audit.existing().is_none() {
+        return Err(AppError::NotFound("Battle pass not found".into()));
+    }
+
+    type Window = (Option<chrono::DateTime<chrono::Utc>>, Option<chrono::DateTime<chrono::Utc>>, bool);
+    let (starts_at, ends_at, closed): Window = sqlx::query_as(
+        "SELECT starts_at, ends_at, closed_at IS NOT NULL FROM battle_passes WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let reschedule = body.starts_at.is_some() || body.ends_at.is_some();
+    if closed && (reschedule || body.is_active.is_some()) {
+        return Err(AppError::Conflict("This battle pass has closed".into()));
+    }
+    let starts_at = body.starts_at.or(starts_at);
+    if reschedule {
+        validate_window(&mut tx, tid, Some(id), starts_at, body.ends_at.or(ends_at)).await?;
+    }
+    if body.is_active.is_some() && starts_at.is_some() {
+        return Err(AppError::BadRequest(
+            "This battle pass is switched on and off by its window; change startsAt or endsAt instead".into(),
+        ));
+    }
+
+    if body.is_active == Some(true) {
+        let others: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM battle_passes WHERE tenant_id = $1 AND id <> $2 AND is_active = true",
+        )
+        .bind(tid)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for other in others {
+            let other_audit = Audit::begin(&mut tx, tid, entity_audit::BATTLE_PASS, &other.to_string()).await?;
+            sqlx::query("UPDATE battle_passes SET is_active = false WHERE id = $1")
+                .bind(other)
+                .execute(&mut *tx)
+                .await?;
+            other_audit.record(&mut tx, tid, player.id).await?;
+        }
+    }
 
-    // Upsert progress
     sqlx::query(
-        r#"INSERT INTO player_battle_pass (tenant_id, player_id, battle_pass_id, current_tier, current_xp, is_premium, claimed_tiers, updated_at)
-        VALUES ($1, $2, $3, 0, $4, false, '[]'::jsonb, NOW())
-        ON CONFLICT (tenant_id, player_id, battle_pass_id) DO UPDATE SET
-            current_xp = player_battle_pass.current_xp + $4,
-            updated_at = NOW()"#,
+        r#"UPDATE battle_passes SET
+            name = COALESCE($3, name),
+            is_active = COALESCE($4, is_active),
+            starts_at = COALESCE($5, starts_at),
+            ends_at = COALESCE($6, ends_at)
+        WHERE id = $1 AND tenant_id = $2"#,
+    )
+    .bind(id)
+    .bind(tid)
+    .bind(body.name.as_deref().map(str::trim))
+    .bind(body.is_active)
+    .bind(body.starts_at)
+    .bind(body.ends_at)
+    .execute(&mut *tx)
+    .await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
+    Ok(Json(admin_battlepass_json(&state.db, tid, id).await?))
+}
+
+/// Admin: soft-delete a battle pass. Players' progress on it is kept.
+pub async fn delete_battlepass(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<i32>,
+) -> AppResult<Json<Value>> {
+    let mut tx = state.db.begin().await?;
+    if !entity_audit::soft_delete(&mut tx, &tenant.0 .0, player.id, entity_audit::BATTLE_PASS, &id.to_string()).await? {
+        return Err(AppError::NotFound("Battle pass not found".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Admin: what each XP source gives players of the tenant.
+pub async fn list_xp_sources(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let sources = battle_pass_xp::sources(&state.db, &tenant.0 .0).await?;
+    Ok(Json(json!({ "sources": sources })))
+}
+
+fn known_xp_source(source: &str) -> AppResult<()> {
+    if battle_pass_xp::DEFAULTS.iter().any(|(s, ..)| *s == source) {
+        return Ok(());
+    }
+    let names: Vec<&str> = battle_pass_xp::DEFAULTS.iter().map(|(s, ..)| *s).collect();
+    Err(AppError::BadRequest(format!("source must be one of {}", names.join(", "))))
+}
+
+/// Admin: replaces an XP source's built-in settings for the tenant.
+pub async fn set_xp_source(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(source): Path<String>,
+    Json(body): Json<SetXpSourceRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    known_xp_source(&source)?;
+    if body.xp < 0 || body.xp_per_1000_points < 0 {
+        return Err(AppError::BadRequest("xp and xpPer1000Points can't be negative".into()));
+    }
+    if body.daily_cap.is_some_and(|cap| cap <= 0) {
+        return Err(AppError::BadRequest("dailyCap must be positive".into()));
+    }
+
+    sqlx::query(
+        r#"INSERT INTO battle_pass_xp_sources (tenant_id, source, xp, xp_per_1000_points, daily_cap, is_enabled, updated_by, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        ON CONFLICT (tenant_id, source) DO UPDATE SET
+            xp = $3, xp_per_1000_points = $4, daily_cap = $5, is_enabled = $6, updated_by = $7, updated_at = NOW()"#,
+    )
+    .bind(tid)
+    .bind(&source)
+    .bind(body.xp)
+    .bind(body.xp_per_1000_points)
+    .bind(body.daily_cap)
+    .bind(body.is_enabled.unwrap_or(true))
+    .bind(player.id)
+    .execute(&state.db)
+    .await?;
+
+    let mut conn = state.db.acquire().await?;
+    let source = battle_pass_xp::source(&mut conn, tid, &source).await?;
+    Ok(Json(json!({ "source": source })))
+}
+
+/// Admin: puts an XP source back on its built-in settings.
+pub async fn reset_xp_source(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(source): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    known_xp_source(&source)?;
+    sqlx::query("DELETE FROM battle_pass_xp_sources WHERE tenant_id = $1 AND source = $2")
+        .bind(tid)
+        .bind(&source)
+        .execute(&state.db)
+        .await?;
+
+    let mut conn = state.db.acquire().await?;
+    let source = battle_pass_xp::source(&mut conn, tid, &source).await?;
+    Ok(Json(json!({ "source": source })))
+}
+
+// =========================================
+// Gifts & Trades
+// =========================================
+
+/// Currencies that can be put into a trade.
+pub const TRADE_CURRENCIES: [&str; 3] = ["coins", "gems", "tickets"];
+/// Item types that are bound to the player who bought them.
+pub const NON_TRANSFERABLE_TYPES: [&str; 1] = ["battle_pass"];
+/// Maximum item + currency lines per side of a trade.
+const MAX_TRADE_LINES: usize = 10;
+
+type PgTx<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// What a transfer moves: a store item, or an amount of a currency.
+#[derive(Clone, Copy)]
+enum Asset<'a> {
+    Item(&'a str),
+    Currency(&'a str),
+}
+
+/// How a transfer is labelled in economy_transactions.
+struct TransferCtx<'a> {
+    tenant_id: &'a str,
+    source: &'a str,
+    reference_id: String,
+    message: Option<&'a str>,
+}
+
+pub async fn gift_item(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<GiftRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let item = TradeItem {
+        item_id: body.item_id.clone(),
+        quantity: body.quantity.unwrap_or(1),
+    };
+
+    if item.quantity <= 0 {
+        return Err(AppError::BadRequest("Quantity must be positive".into()));
+    }
+    if body.friend_id == player.id {
+        return Err(AppError::BadRequest("Cannot gift to yourself".into()));
+    }
+    if !are_friends(&state.db, tid, player.id, body.friend_id).await? {
+        return Err(AppError::Forbidden("You can only gift items to friends".into()));
+    }
+    ensure_transferable(&state.db, tid, std::slice::from_ref(&item)).await?;
+
+    let gift_id = Uuid::new_v4();
+    let ctx = TransferCtx {
+        tenant_id: tid,
+        source: "gift",
+        reference_id: gift_id.to_string(),
+        message: body.message.as_deref(),
+    };
+
+    let mut tx = state.db.begin().await?;
+    transfer_item(&mut tx, &ctx, player.id, body.friend_id, &item).await?;
+    tx.commit().await?;
+
+    state
+        .realtime
+        .send_to(
+            tid,
+            body.friend_id,
+            &json!({
+                "type": "gift",
+                "giftId": gift_id,
+                "fromPlayerId": player.id,
+                "itemId": item.item_id,
+                "quantity": item.quantity,
+                "message": body.message,
+            }),
+        )
+        .await;
+
+    Ok(Json(json!({
+        "success": true,
+        "giftId": gift_id,
+        "itemId": item.item_id,
+        "quantity": item.quantity,
+    })))
+}
+
+pub async fn list_trades(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<TradeOffer> = sqlx::query_as(
+        r#"SELECT * FROM trade_offers
+        WHERE tenant_id = $1 AND status = 'pending'
+            AND (from_player_id = $2 OR to_player_id = $2)
+        ORDER BY created_at DESC"#,
     )
-    .bind(tid).bind(player.id).bind(&bp.id).bind(body.xp)
-    .execute(&state.db).await?;
+    .bind(&tenant.0 .0)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let (incoming, outgoing): (Vec<&TradeOffer>, Vec<&TradeOffer>) =
+        rows.iter().partition(|t| t.to_player_id == player.id);
+
+    Ok(Json(json!({
+        "incoming": incoming.into_iter().map(trade_json).collect::<Vec<_>>(),
+        "outgoing": outgoing.into_iter().map(trade_json).collect::<Vec<_>>(),
+    })))
+}
+
+pub async fn create_trade(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreateTradeRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    if body.to_player_id == player.id {
+        return Err(AppError::BadRequest("Cannot trade with yourself".into()));
+    }
+    if !are_friends(&state.db, tid, player.id, body.to_player_id).await? {
+        return Err(AppError::Forbidden("You can only trade with friends".into()));
+    }
+    validate_terms(&state.db, tid, player.id, &body.terms).await?;
+
+    let trade = insert_offer(&state.db, tid, player.id, body.to_player_id, &body.terms, None).await?;
+
+    notify_trade(&state, tid, trade.to_player_id, "trade_offer", &trade).await;
+
+    Ok(Json(json!({ "trade": trade_json(&trade) })))
+}
+
+/// Replaces an incoming offer with new terms going back the other way.
+pub async fn counter_trade(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<TradeTerms>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    validate_terms(&state.db, tid, player.id, &body).await?;
+
+    let mut tx = state.db.begin().await?;
+
+    let original = lock_open_offer(&mut tx, tid, id, player.id).await?;
+    if !are_friends(&mut *tx, tid, player.id, original.from_player_id).await? {
+        return Err(AppError::Forbidden("You can only trade with friends".into()));
+    }
+
+    sqlx::query("UPDATE trade_offers SET status = 'countered', resolved_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    let trade = insert_offer(&mut *tx, tid, player.id, original.from_player_id, &body, Some(id)).await?;
+
+    tx.commit().await?;
+
+    notify_trade(&state, tid, trade.to_player_id, "trade_countered", &trade).await;
 
-    // Check level up
-    let progress: PlayerBattlePass = sqlx::query_as(
-        "SELECT * FROM player_battle_pass WHERE player_id = $1 AND tenant_id = $2 AND battle_pass_id = $3",
+    Ok(Json(json!({ "trade": trade_json(&trade) })))
+}
+
+/// Settles an incoming offer: both sides' items and currency change hands
+/// in one transaction, or nothing moves. The two must still be friends, so
+/// unfriending or blocking strands any open offers between them.
+pub async fn accept_trade(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let mut tx = state.db.begin().await?;
+
+    let offer = lock_open_offer(&mut tx, tid, id, player.id).await?;
+    if !are_friends(&mut *tx, tid, offer.from_player_id, offer.to_player_id).await? {
+        return Err(AppError::Forbidden("You can only trade with friends".into()));
+    }
+    let offer_items: Vec<TradeItem> = serde_json::from_value(offer.offer_items.clone()).unwrap_or_default();
+    let offer_currency: HashMap<String, i64> = serde_json::from_value(offer.offer_currency.clone()).unwrap_or_default();
+    let request_items: Vec<TradeItem> = serde_json::from_value(offer.request_items.clone()).unwrap_or_default();
+    let request_currency: HashMap<String, i64> = serde_json::from_value(offer.request_currency.clone()).unwrap_or_default();
+
+    let ctx = TransferCtx {
+        tenant_id: tid,
+        source: "trade",
+        reference_id: id.to_string(),
+        message: None,
+    };
+    let (offerer, accepter) = (offer.from_player_id, offer.to_player_id);
+
+    for item in &offer_items {
+        transfer_item(&mut tx, &ctx, offerer, accepter, item).await?;
+    }
+    for (currency, amount) in &offer_currency {
+        transfer_currency(&mut tx, &ctx, offerer, accepter, currency, *amount).await?;
+    }
+    for item in &request_items {
+        transfer_item(&mut tx, &ctx, accepter, offerer, item).await?;
+    }
+    for (currency, amount) in &request_currency {
+        transfer_currency(&mut tx, &ctx, accepter, offerer, currency, *amount).await?;
+    }
+
+    let trade: TradeOffer = sqlx::query_as(
+        "UPDATE trade_offers SET status = 'accepted', resolved_at = NOW() WHERE id = $1 RETURNING *",
     )
-    .bind(player.id).bind(tid).bind(&bp.id)
-    .fetch_one(&state.db).await?;
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    notify_trade(&state, tid, offerer, "trade_accepted", &trade).await;
+
+    Ok(Json(json!({ "success": true, "trade": trade_json(&trade) })))
+}
+
+/// Withdraws (sender) or declines (recipient) an open offer.
+pub async fn cancel_trade(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
 
-    let mut tier = progress.current_tier;
-    let mut xp = progress.current_xp;
-    while xp >= bp.xp_per_tier && tier < bp.max_tier {
-        xp -= bp.xp_per_tier;
-        tier += 1;
+    let trade: TradeOffer = sqlx::query_as(
+        r#"UPDATE trade_offers SET status = 'cancelled', cancelled_by = $3, resolved_at = NOW()
+        WHERE id = $1 AND tenant_id = $2 AND status = 'pending'
+            AND (from_player_id = $3 OR to_player_id = $3)
+        RETURNING *"#,
+    )
+    .bind(id)
+    .bind(tid)
+    .bind(player.id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Open trade not found".into()))?;
+
+    let other = if trade.from_player_id == player.id {
+        trade.to_player_id
+    } else {
+        trade.from_player_id
+    };
+    notify_trade(&state, tid, other, "trade_cancelled", &trade).await;
+
+    Ok(Json(json!({ "success": true, "trade": trade_json(&trade) })))
+}
+
+fn trade_json(t: &TradeOffer) -> Value {
+    json!({
+        "id": t.id,
+        "fromPlayerId": t.from_player_id,
+        "toPlayerId": t.to_player_id,
+        "status": t.status,
+        "offerItems": t.offer_items,
+        "offerCurrency": t.offer_currency,
+        "requestItems": t.request_items,
+        "requestCurrency": t.request_currency,
+        "message": t.message,
+        "parentOfferId": t.parent_offer_id,
+        "createdAt": t.created_at,
+        "resolvedAt": t.resolved_at,
+    })
+}
+
+async fn notify_trade(state: &AppState, tid: &str, player_id: Uuid, event: &str, trade: &TradeOffer) {
+    state
+        .realtime
+        .send_to(tid, player_id, &json!({ "type": event, "trade": trade_json(trade) }))
+        .await;
+}
+
+async fn are_friends<'e>(db: impl sqlx::PgExecutor<'e>, tid: &str, a: Uuid, b: Uuid) -> AppResult<bool> {
+    let friends: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM friendships
+            WHERE tenant_id = $1 AND status = 'accepted'
+                AND ((player_id = $2 AND friend_id = $3) OR (player_id = $3 AND friend_id = $2))
+        )"#,
+    )
+    .bind(tid)
+    .bind(a)
+    .bind(b)
+    .fetch_one(db)
+    .await?;
+    Ok(friends)
+}
+
+/// Checks the shape of both sides of an offer and that the offering player
+/// currently holds what they are giving. Balances and stock are checked
+/// again, under lock, when the trade is accepted.
+async fn validate_terms(db: &PgPool, tid: &str, player_id: Uuid, terms: &TradeTerms) -> AppResult<()> {
+    for (items, currency) in [
+        (&terms.offer_items, &terms.offer_currency),
+        (&terms.request_items, &terms.request_currency),
+    ] {
+        if items.len() + currency.len() > MAX_TRADE_LINES {
+            return Err(AppError::BadRequest(format!(
+                "At most {} items and currencies per side",
+                MAX_TRADE_LINES
+            )));
+        }
+        if items.iter().any(|i| i.quantity <= 0) {
+            return Err(AppError::BadRequest("Quantity must be positive".into()));
+        }
+        let mut ids: Vec<&str> = items.iter().map(|i| i.item_id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() != items.len() {
+            return Err(AppError::BadRequest("Each item may only be listed once per side".into()));
+        }
+        for (ct, amount) in currency {
+            if !TRADE_CURRENCIES.contains(&ct.as_str()) {
+                return Err(AppError::BadRequest(format!("Unknown currency: {}", ct)));
+            }
+            if *amount <= 0 {
+                return Err(AppError::BadRequest("Amount must be positive".into()));
+            }
+        }
+    }
+
+    let total = terms.offer_items.len()
+        + terms.offer_currency.len()
+        + terms.request_items.len()
+        + terms.request_currency.len();
+    if total == 0 {
+        return Err(AppError::BadRequest("Trade is empty".into()));
     }
 
-    if tier != progress.current_tier || xp != progress.current_xp {
-        sqlx::query("UPDATE player_battle_pass SET current_tier = $1, current_xp = $2, updated_at = NOW() WHERE player_id = $3 AND tenant_id = $4 AND battle_pass_id = $5")
-            .bind(tier).bind(xp).bind(player.id).bind(tid).bind(&bp.id)
-            .execute(&state.db).await?;
+    ensure_transferable(db, tid, &terms.offer_items).await?;
+    ensure_transferable(db, tid, &terms.request_items).await?;
+
+    for item in &terms.offer_items {
+        let owned: Option<i32> = sqlx::query_scalar(
+            "SELECT quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3",
+        )
+        .bind(tid)
+        .bind(player_id)
+        .bind(&item.item_id)
+        .fetch_optional(db)
+        .await?;
+        if owned.unwrap_or(0) < item.quantity {
+            return Err(AppError::BadRequest(format!("You do not own enough of {}", item.item_id)));
+        }
     }
 
-    Ok(Json(json!({"currentTier": tier, "currentXp": xp, "xpToNextTier": bp.xp_per_tier - xp})))
+    Ok(())
+}
+
+async fn ensure_transferable(db: &PgPool, tid: &str, items: &[TradeItem]) -> AppResult<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<String> = items.iter().map(|i| i.item_id.clone()).collect();
+    let found: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, item_type FROM store_items WHERE tenant_id = $1 AND id = ANY($2)",
+    )
+    .bind(tid)
+    .bind(&ids)
+    .fetch_all(db)
+    .await?;
+
+    for id in &ids {
+        match found.iter().find(|(fid, _)| fid == id) {
+            None => return Err(AppError::NotFound(format!("Item not found: {}", id))),
+            Some((_, item_type)) if NON_TRANSFERABLE_TYPES.contains(&item_type.as_str()) => {
+                return Err(AppError::BadRequest(format!("Item cannot be traded: {}", id)));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+async fn insert_offer<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    tid: &str,
+    from: Uuid,
+    to: Uuid,
+    terms: &TradeTerms,
+    parent: Option<Uuid>,
+) -> AppResult<TradeOffer> {
+    let trade = sqlx::query_as(
+        r#"INSERT INTO trade_offers (tenant_id, from_player_id, to_player_id, offer_items, offer_currency,
+            request_items, request_currency, message, parent_offer_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *"#,
+    )
+    .bind(tid)
+    .bind(from)
+    .bind(to)
+    .bind(json!(terms.offer_items))
+    .bind(json!(terms.offer_currency))
+    .bind(json!(terms.request_items))
+    .bind(json!(terms.request_currency))
+    .bind(&terms.message)
+    .bind(parent)
+    .fetch_one(db)
+    .await?;
+    Ok(trade)
+}
+
+/// Locks a pending offer addressed to `recipient`; only they may counter or
+/// accept it.
+async fn lock_open_offer(tx: &mut PgTx<'_>, tid: &str, id: Uuid, recipient: Uuid) -> AppResult<TradeOffer> {
+    let offer: TradeOffer = sqlx::query_as(
+        "SELECT * FROM trade_offers WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(tid)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Trade not found".into()))?;
+
+    if offer.to_player_id != recipient {
+        return Err(AppError::Forbidden("This offer is not addressed to you".into()));
+    }
+    if offer.status != "pending" {
+        return Err(AppError::Conflict(format!("Trade is already {}", offer.status)));
+    }
+    Ok(offer)
+}
+
+/// Moves items between inventories and logs both sides.
+async fn transfer_item(tx: &mut PgTx<'_>, ctx: &TransferCtx<'_>, from: Uuid, to: Uuid, item: &TradeItem) -> AppResult<()> {
+    let owned: Option<i32> = sqlx::query_scalar(
+        "SELECT quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3 FOR UPDATE",
+    )
+    .bind(ctx.tenant_id)
+    .bind(from)
+    .bind(&item.item_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let remaining = owned.unwrap_or(0) - item.quantity;
+    if remaining < 0 {
+        return Err(AppError::BadRequest(format!("Not enough of {} to transfer", item.item_id)));
+    }
+
+    if remaining == 0 {
+        sqlx::query("DELETE FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(ctx.tenant_id).bind(from).bind(&item.item_id)
+            .execute(&mut **tx).await?;
+    } else {
+        sqlx::query("UPDATE player_inventory SET quantity = $4 WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(ctx.tenant_id).bind(from).bind(&item.item_id).bind(remaining)
+            .execute(&mut **tx).await?;
+    }
+    cosmetics::unequip_if_gone(tx, ctx.tenant_id, from, &item.item_id).await?;
+
+    let received: i32 = sqlx::query_scalar(
+        r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+            quantity = player_inventory.quantity + EXCLUDED.quantity
+        RETURNING quantity"#,
+    )
+    .bind(ctx.tenant_id)
+    .bind(to)
+    .bind(&item.item_id)
+    .bind(item.quantity)
+    .bind(ctx.source)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    log_transfer(
+        tx,
+        ctx,
+        Asset::Item(&item.item_id),
+        i64::from(item.quantity),
+        (from, i64::from(remaining)),
+        (to, i64::from(received)),
+    )
+    .await
+}
+
+/// Moves currency between wallets and logs both sides.
+async fn transfer_currency(
+    tx: &mut PgTx<'_>,
+    ctx: &TransferCtx<'_>,
+    from: Uuid,
+    to: Uuid,
+    currency: &str,
+    amount: i64,
+) -> AppResult<()> {
+    let balance: Option<i64> = sqlx::query_scalar(
+        "SELECT balance FROM player_wallets WHERE player_id = $1 AND tenant_id = $2 AND currency_type = $3 FOR UPDATE",
+    )
+    .bind(from)
+    .bind(ctx.tenant_id)
+    .bind(currency)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let remaining = balance.unwrap_or(0) - amount;
+    if remaining < 0 {
+        return Err(AppError::BadRequest(format!("Insufficient {}", currency)));
+    }
+
+    sqlx::query("UPDATE player_wallets SET balance = $1, updated_at = NOW() WHERE player_id = $2 AND tenant_id = $3 AND currency_type = $4")
+        .bind(remaining).bind(from).bind(ctx.tenant_id).bind(currency)
+        .execute(&mut **tx).await?;
+
+    // Received currency is not "earned", so lifetime_earned is left alone.
+    let received: i64 = sqlx::query_scalar(
+        r#"INSERT INTO player_wallets (player_id, tenant_id, currency_type, balance, lifetime_earned, updated_at)
+        VALUES ($1, $2, $3, $4, 0, NOW())
+        ON CONFLICT (player_id, tenant_id, currency_type) DO UPDATE SET
+            balance = player_wallets.balance + $4,
+            updated_at = NOW()
+        RETURNING balance"#,
+    )
+    .bind(to)
+    .bind(ctx.tenant_id)
+    .bind(currency)
+    .bind(amount)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    log_transfer(tx, ctx, Asset::Currency(currency), amount, (from, remaining), (to, received)).await
+}
+
+/// Writes the audit rows for one transfer: a `transfer_out` for the sender
+/// and a `transfer_in` for the recipient, each with the balance (or item
+/// quantity) left afterwards. Item moves use `currency_type = 'item'`.
+async fn log_transfer(
+    tx: &mut PgTx<'_>,
+    ctx: &TransferCtx<'_>,
+    asset: Asset<'_>,
+    amount: i64,
+    (from, from_after): (Uuid, i64),
+    (to, to_after): (Uuid, i64),
+) -> AppResult<()> {
+    let (currency, item_id) = match asset {
+        Asset::Item(id) => ("item", Some(id)),
+        Asset::Currency(ct) => (ct, None),
+    };
+
+    for (player_id, counterparty, delta, balance_after, tx_type) in [
+        (from, to, -amount, from_after, "transfer_out"),
+        (to, from, amount, to_after, "transfer_in"),
+    ] {
+        sqlx::query(
+            r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())"#,
+        )
+        .bind(ctx.tenant_id)
+        .bind(player_id)
+        .bind(currency)
+        .bind(delta)
+        .bind(balance_after)
+        .bind(tx_type)
+        .bind(ctx.source)
+        .bind(&ctx.reference_id)
+        .bind(json!({ "counterpartyId": counterparty, "itemId": item_id, "message": ctx.message }))
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
 }
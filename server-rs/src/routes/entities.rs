@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::entity_audit::*;
+use crate::services::entity_audit::{self, ENTITIES};
+use crate::AppState;
+
+const ACTIONS: [&str; 4] = ["create", "update", "delete", "restore"];
+
+fn check_entity_type(entity_type: &str) -> AppResult<()> {
+    if ENTITIES.iter().any(|(t, _)| *t == entity_type) {
+        return Ok(());
+    }
+    let types: Vec<&str> = ENTITIES.iter().map(|(t, _)| *t).collect();
+    Err(AppError::BadRequest(format!("entityType must be one of {}", types.join(", "))))
+}
+
+/// Admin: recorded changes to games, categories, store items and battle
+/// passes, newest first. Filter by `entityType`, `entityId` and `action`.
+pub async fn history(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AuditHistoryQuery>,
+) -> AppResult<Json<Value>> {
+    if let Some(ref t) = q.entity_type {
+        check_entity_type(t)?;
+    }
+    if q.action.as_deref().is_some_and(|a| !ACTIONS.contains(&a)) {
+        return Err(AppError::BadRequest(format!("action must be one of {}", ACTIONS.join(", "))));
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+
+    let entries: Vec<EntityAudit> = sqlx::query_as(
+        r#"SELECT a.id, a.entity_type, a.entity_id, a.action, a.before, a.after, a.actor_id,
+            p.display_name AS actor_name, a.created_at
+        FROM entity_audit a
+        LEFT JOIN players p ON p.id = a.actor_id
+        WHERE a.tenant_id = $1
+            AND ($2::text IS NULL OR a.entity_type = $2)
+            AND ($3::text IS NULL OR a.entity_id = $3)
+            AND ($4::text IS NULL OR a.action = $4)
+        ORDER BY a.created_at DESC LIMIT $5 OFFSET $6"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(&q.entity_type)
+    .bind(&q.entity_id)
+    .bind(&q.action)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "history": entries })))
+}
+
+/// Admin: deleted entities of one type, which can be restored.
+pub async fn list_deleted(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<DeletedEntitiesQuery>,
+) -> AppResult<Json<Value>> {
+    check_entity_type(&q.entity_type)?;
+    let deleted = entity_audit::list_deleted(&state.db, &tenant.0 .0, &q.entity_type).await?;
+    Ok(Json(json!({ "entityType": q.entity_type, "deleted": deleted })))
+}
+
+/// Admin: restore a deleted entity. It comes back active if it was when it
+/// was deleted; battle passes always come back inactive.
+pub async fn restore(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((entity_type, entity_id)): Path<(String, String)>,
+) -> AppResult<Json<Value>> {
+    check_entity_type(&entity_type)?;
+    let mut tx = state.db.begin().await?;
+    if !entity_audit::restore(&mut tx, &tenant.0 .0, player.id, &entity_type, &entity_id).await? {
+        return Err(AppError::NotFound("No deleted entity with that ID".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(json!({ "success": true })))
+}
@@ -9,6 +9,7 @@ use uuid::Uuid;
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
+use crate::services::{cosmetics, privacy};
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -21,8 +22,8 @@ pub async fn list_friends(
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
-    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
-        r#"SELECT p.id, p.display_name, p.avatar_character
+    let rows: Vec<(Uuid, String, String, Value)> = sqlx::query_as(&format!(
+        r#"SELECT p.id, p.display_name, p.avatar_character, {}
         FROM friendships f
         JOIN players p ON (
             (f.player_id = $1 AND p.id = f.friend_id) OR
@@ -30,57 +31,66 @@ pub async fn list_friends(
         ) AND p.tenant_id = $2
         WHERE f.tenant_id = $2 AND f.status = 'accepted'
             AND (f.player_id = $1 OR f.friend_id = $1)"#,
-    )
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(player.id)
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
     .await?;
 
-    let friends: Vec<Value> = rows.iter().map(|(id, name, avatar)| {
-        json!({"playerId": id, "displayName": name, "avatarCharacter": avatar})
+    let friends: Vec<Value> = rows.iter().map(|(id, name, avatar, cosmetics)| {
+        json!({"playerId": id, "displayName": name, "avatarCharacter": avatar, "cosmetics": cosmetics})
     }).collect();
 
     Ok(Json(json!({ "friends": friends })))
 }
 
+/// player_id, display_name, created_at, equipped cosmetics
+type RequestRow = (Uuid, String, chrono::DateTime<chrono::Utc>, Value);
+
 pub async fn friend_requests(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
-    let incoming: Vec<(Uuid, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
-        r#"SELECT p.id, p.display_name, f.created_at
+    let incoming: Vec<RequestRow> = sqlx::query_as(&format!(
+        r#"SELECT p.id, p.display_name, f.created_at, {}
         FROM friendships f JOIN players p ON p.id = f.player_id AND p.tenant_id = f.tenant_id
         WHERE f.friend_id = $1 AND f.tenant_id = $2 AND f.status = 'pending'"#,
-    )
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(player.id)
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
     .await?;
 
-    let outgoing: Vec<(Uuid, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
-        r#"SELECT p.id, p.display_name, f.created_at
+    let outgoing: Vec<RequestRow> = sqlx::query_as(&format!(
+        r#"SELECT p.id, p.display_name, f.created_at, {}
         FROM friendships f JOIN players p ON p.id = f.friend_id AND p.tenant_id = f.tenant_id
         WHERE f.player_id = $1 AND f.tenant_id = $2 AND f.status = 'pending'"#,
-    )
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(player.id)
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
     .await?;
 
     Ok(Json(json!({
-        "incoming": incoming.iter().map(|(id, name, created)| json!({"playerId": id, "displayName": name, "createdAt": created})).collect::<Vec<_>>(),
-        "outgoing": outgoing.iter().map(|(id, name, created)| json!({"playerId": id, "displayName": name, "createdAt": created})).collect::<Vec<_>>(),
+        "incoming": incoming.iter().map(|(id, name, created, cosmetics)| json!({"playerId": id, "displayName": name, "createdAt": created, "cosmetics": cosmetics})).collect::<Vec<_>>(),
+        "outgoing": outgoing.iter().map(|(id, name, created, cosmetics)| json!({"playerId": id, "displayName": name, "createdAt": created, "cosmetics": cosmetics})).collect::<Vec<_>>(),
     })))
 }
 
+/// player_id, display_name, status, current_game_id, current_room_id, equipped cosmetics
+type OnlineRow = (Uuid, String, String, Option<String>, Option<String>, Value);
+
 pub async fn online_friends(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
-    let rows: Vec<(Uuid, String, String, Option<String>, Option<String>)> = sqlx::query_as(
-        r#"SELECT p.id, p.display_name, COALESCE(pp.status, 'offline'), pp.current_game_id, pp.current_room_id
+    let rows: Vec<OnlineRow> = sqlx::query_as(&format!(
+        r#"SELECT p.id, p.display_name, COALESCE(pp.status, 'offline'), pp.current_game_id, pp.current_room_id, {}
         FROM friendships f
         JOIN players p ON (
             (f.player_id = $1 AND p.id = f.friend_id) OR
@@ -88,15 +98,16 @@ pub async fn online_friends(
         ) AND p.tenant_id = $2
         LEFT JOIN player_presence pp ON pp.player_id = p.id AND pp.tenant_id = $2
         WHERE f.tenant_id = $2 AND f.status = 'accepted' AND (f.player_id = $1 OR f.friend_id = $1)
-            AND pp.status IS NOT NULL AND pp.status != 'offline'"#,
-    )
+            AND pp.status IS NOT NULL AND pp.status != 'offline' AND p.show_online"#,
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(player.id)
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
     .await?;
 
-    let friends: Vec<Value> = rows.iter().map(|(id, name, status, gid, rid)| {
-        json!({"playerId": id, "displayName": name, "status": status, "currentGameId": gid, "currentRoomId": rid})
+    let friends: Vec<Value> = rows.iter().map(|(id, name, status, gid, rid, cosmetics)| {
+        json!({"playerId": id, "displayName": name, "status": status, "currentGameId": gid, "currentRoomId": rid, "cosmetics": cosmetics})
     }).collect();
 
     Ok(Json(json!({ "friends": friends })))
@@ -129,6 +140,7 @@ pub async fn send_request(
     if existing {
         return Err(AppError::Conflict("Friendship already exists".into()));
     }
+    privacy::check_friend_request(&state.db, tid, player.id, target).await?;
 
     sqlx::query(
         "INSERT INTO friendships (tenant_id, player_id, friend_id, status, created_at) VALUES ($1, $2, $3, 'pending', NOW())",
@@ -258,14 +270,21 @@ pub async fn search_players(
 ) -> AppResult<Json<Value>> {
     let search = format!("%{}%", q.q.as_deref().unwrap_or(""));
 
-    let rows: Vec<(Uuid, String, String)> = sqlx::query_as(
-        "SELECT id, display_name, avatar_character FROM players WHERE tenant_id = $1 AND id != $2 AND display_name ILIKE $3 LIMIT 20",
-    )
+    // Private profiles never show up; friends-only ones only for friends
+    let rows: Vec<(Uuid, String, String, Value)> = sqlx::query_as(&format!(
+        r#"SELECT p.id, p.display_name, p.avatar_character, {} FROM players p
+        WHERE p.tenant_id = $1 AND p.id != $2 AND p.display_name ILIKE $3
+            AND (p.profile_visibility = 'public' OR (p.profile_visibility = 'friends' AND EXISTS(
+                SELECT 1 FROM friendships f WHERE f.tenant_id = $1 AND f.status = 'accepted'
+                    AND ((f.player_id = $2 AND f.friend_id = p.id) OR (f.friend_id = $2 AND f.player_id = p.id)))))
+        LIMIT 20"#,
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(&tenant.0 .0).bind(player.id).bind(&search)
     .fetch_all(&state.db).await?;
 
-    let results: Vec<Value> = rows.iter().map(|(id, name, avatar)| {
-        json!({"playerId": id, "displayName": name, "avatarCharacter": avatar})
+    let results: Vec<Value> = rows.iter().map(|(id, name, avatar, cosmetics)| {
+        json!({"playerId": id, "displayName": name, "avatarCharacter": avatar, "cosmetics": cosmetics})
     }).collect();
 
     Ok(Json(json!({ "players": results })))
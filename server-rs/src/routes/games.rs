@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, State},
     Json,
@@ -8,6 +10,8 @@ use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
 use crate::models::multiplayer::*;
+use crate::services::entity_audit::{self, Audit};
+use crate::services::{jobs, rating, score_decay};
 use crate::AppState;
 
 // Public endpoints
@@ -26,6 +30,25 @@ pub async fn list_custom_games(
     Ok(Json(json!({ "games": rows })))
 }
 
+/// The engine's games the tenant has switched on.
+pub async fn list_engine_games(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<EngineGame> = sqlx::query_as(
+        r#"SELECT eg.id, eg.title, eg.description, eg.modes, eg.inputs, eg.thumbnail_color, eg.render, eg.removed_at
+        FROM engine_games eg
+        LEFT JOIN tenant_engine_games teg ON teg.game_id = eg.id AND teg.tenant_id = $1
+        WHERE eg.removed_at IS NULL AND COALESCE(teg.is_active, true)
+        ORDER BY eg.title"#,
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "games": rows })))
+}
+
 pub async fn list_categories(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
@@ -38,7 +61,10 @@ pub async fn list_categories(
     .await?;
 
     let assignments: Vec<(String, String, i32)> = sqlx::query_as(
-        "SELECT game_id, category_id, sort_order FROM game_category_assignments WHERE tenant_id = $1",
+        r#"SELECT gca.game_id, gca.category_id, gca.sort_order FROM game_category_assignments gca
+        WHERE gca.tenant_id = $1
+          AND NOT EXISTS (SELECT 1 FROM engine_games eg WHERE eg.id = gca.game_id AND eg.removed_at IS NOT NULL)
+          AND NOT EXISTS (SELECT 1 FROM custom_games cg WHERE cg.id = gca.game_id AND cg.tenant_id = gca.tenant_id AND cg.deleted_at IS NOT NULL)"#,
     )
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
@@ -49,6 +75,15 @@ pub async fn list_categories(
 
 // Admin endpoints
 
+#[derive(sqlx::FromRow)]
+struct EngineGameRow {
+    id: String,
+    title: String,
+    render: String,
+    is_active: bool,
+    removed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 pub async fn admin_list_games(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
@@ -58,7 +93,7 @@ pub async fn admin_list_games(
             p.display_name as created_by_name
         FROM custom_games cg
         LEFT JOIN players p ON p.id = cg.created_by AND p.tenant_id = cg.tenant_id
-        WHERE cg.tenant_id = $1
+        WHERE cg.tenant_id = $1 AND cg.deleted_at IS NULL
         ORDER BY cg.sort_order"#,
     )
     .bind(&tenant.0 .0)
@@ -69,7 +104,21 @@ pub async fn admin_list_games(
         json!({"id": id, "title": title, "classic": classic, "sortOrder": sort, "isActive": active, "createdBy": creator})
     }).collect();
 
-    Ok(Json(json!({ "games": games })))
+    let engine: Vec<EngineGameRow> = sqlx::query_as(
+        r#"SELECT eg.id, eg.title, eg.render, COALESCE(teg.is_active, true) AS is_active, eg.removed_at
+        FROM engine_games eg
+        LEFT JOIN tenant_engine_games teg ON teg.game_id = eg.id AND teg.tenant_id = $1
+        ORDER BY eg.removed_at IS NOT NULL, eg.title"#,
+    )
+    .bind(&tenant.0 .0)
+    .fetch_all(&state.db)
+    .await?;
+
+    let engine_games: Vec<Value> = engine.iter().map(|g| {
+        json!({"id": g.id, "title": g.title, "render": g.render, "isActive": g.is_active, "removedAt": g.removed_at})
+    }).collect();
+
+    Ok(Json(json!({ "games": games, "engineGames": engine_games })))
 }
 
 pub async fn create_game(
@@ -84,6 +133,15 @@ pub async fn create_game(
         return Err(AppError::BadRequest("ID and title required".into()));
     }
 
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::GAME, &body.id).await?;
+    if audit.is_deleted() {
+        return Err(AppError::Conflict("A deleted game has this ID; restore it instead".into()));
+    }
+    if audit.existing().is_some() {
+        return Err(AppError::Conflict("A game with this ID already exists".into()));
+    }
+
     sqlx::query(
         r#"INSERT INTO custom_games (id, tenant_id, title, classic, character_id, mechanic, icon_color, icon_emoji, scene_code, sort_order, created_by, is_active)
         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, true)"#,
@@ -99,7 +157,7 @@ pub async fn create_game(
     .bind(&body.scene_code)
     .bind(body.sort_order.unwrap_or(0))
     .bind(player.id)
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
 
     if let Some(ref cats) = body.categories {
@@ -111,22 +169,32 @@ pub async fn create_game(
             .bind(&body.id)
             .bind(cat_id)
             .bind(i as i32)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
         }
     }
 
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
     Ok(Json(json!({"id": body.id, "success": true})))
 }
 
 pub async fn update_game(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
     Json(body): Json<UpdateGameRequest>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
 
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::GAME, &id).await?;
+    if audit.existing().is_none() {
+        return Err(AppError::NotFound("Game not found".into()));
+    }
+
     // Build dynamic update
     let mut sets = Vec::new();
     if body.title.is_some() { sets.push("title = COALESCE($3, title)"); }
@@ -143,55 +211,166 @@ pub async fn update_game(
             .bind(&body.title).bind(body.classic)
             .bind(&body.icon_color).bind(&body.icon_emoji)
             .bind(&body.scene_code).bind(body.sort_order)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await?;
     }
 
     if let Some(ref cats) = body.categories {
         sqlx::query("DELETE FROM game_category_assignments WHERE tenant_id = $1 AND game_id = $2")
-            .bind(tid).bind(&id).execute(&state.db).await?;
+            .bind(tid).bind(&id).execute(&mut *tx).await?;
         for (i, cat_id) in cats.iter().enumerate() {
             sqlx::query(
                 "INSERT INTO game_category_assignments (tenant_id, game_id, category_id, sort_order) VALUES ($1, $2, $3, $4)",
             )
             .bind(tid).bind(&id).bind(cat_id).bind(i as i32)
-            .execute(&state.db).await?;
+            .execute(&mut *tx).await?;
         }
     }
 
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
     Ok(Json(json!({"success": true})))
 }
 
 pub async fn toggle_game(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
 ) -> AppResult<Json<Value>> {
-    sqlx::query("UPDATE custom_games SET is_active = NOT is_active WHERE id = $1 AND tenant_id = $2")
-        .bind(&id).bind(&tenant.0 .0)
-        .execute(&state.db).await?;
+    let tid = &tenant.0 .0;
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::GAME, &id).await?;
+    if audit.is_deleted() {
+        return Err(AppError::NotFound("Game not found".into()));
+    }
+
+    if audit.existing().is_some() {
+        sqlx::query("UPDATE custom_games SET is_active = NOT is_active WHERE id = $1 AND tenant_id = $2")
+            .bind(&id).bind(tid)
+            .execute(&mut *tx).await?;
+        audit.record(&mut tx, tid, player.id).await?;
+    } else {
+        // Not a custom game; engine games are on until the tenant's first toggle.
+        sqlx::query(
+            r#"INSERT INTO tenant_engine_games (tenant_id, game_id, is_active)
+            SELECT $1, id, false FROM engine_games WHERE id = $2
+            ON CONFLICT (tenant_id, game_id) DO UPDATE SET
+                is_active = NOT tenant_engine_games.is_active,
+                updated_at = NOW()"#,
+        )
+        .bind(tid).bind(&id)
+        .execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
     Ok(Json(json!({"success": true})))
 }
 
+/// Soft-deletes a custom game, keeping its category assignments for a
+/// restore.
 pub async fn delete_game(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
-    sqlx::query("DELETE FROM game_category_assignments WHERE tenant_id = $1 AND game_id = $2").bind(tid).bind(&id).execute(&state.db).await?;
-    sqlx::query("DELETE FROM custom_games WHERE id = $1 AND tenant_id = $2").bind(&id).bind(tid).execute(&state.db).await?;
+    let mut tx = state.db.begin().await?;
+    if !entity_audit::soft_delete(&mut tx, tid, player.id, entity_audit::GAME, &id).await? {
+        return Err(AppError::NotFound("Game not found".into()));
+    }
+    tx.commit().await?;
     Ok(Json(json!({"success": true})))
 }
 
+/// Reconciles `engine_games` with the manifest of the deployed engine, as
+/// returned by its `get_available_games()`. Games new to the manifest are
+/// added, games that left it are flagged removed, and removed games that
+/// are back are restored. Tenant toggles and category assignments are kept
+/// either way.
+pub async fn sync_manifest(
+    State(state): State<AppState>,
+    Json(manifest): Json<Vec<ManifestGame>>,
+) -> AppResult<Json<Value>> {
+    // An empty list is far more likely a broken build than a deliberate
+    // removal of every game.
+    if manifest.is_empty() {
+        return Err(AppError::BadRequest("Manifest lists no games".into()));
+    }
+    let mut ids: Vec<String> = Vec::with_capacity(manifest.len());
+    for game in &manifest {
+        if game.id.is_empty() || game.id.len() > 64 || game.title.is_empty() {
+            return Err(AppError::BadRequest(format!("Invalid manifest entry '{}'", game.id)));
+        }
+        if game.render.as_deref().is_some_and(|r| r != "2d" && r != "3d") {
+            return Err(AppError::BadRequest(format!("Invalid render mode for '{}'", game.id)));
+        }
+        if ids.contains(&game.id) {
+            return Err(AppError::BadRequest(format!("Duplicate game '{}'", game.id)));
+        }
+        ids.push(game.id.clone());
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    // Whether each known game is currently flagged removed.
+    let known: HashMap<String, bool> =
+        sqlx::query_as::<_, (String, bool)>("SELECT id, removed_at IS NOT NULL FROM engine_games FOR UPDATE")
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+
+    for game in &manifest {
+        sqlx::query(
+            r#"INSERT INTO engine_games (id, title, description, modes, inputs, thumbnail_color, render)
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, '#333'), COALESCE($7, '2d'))
+            ON CONFLICT (id) DO UPDATE SET
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                modes = EXCLUDED.modes,
+                inputs = EXCLUDED.inputs,
+                thumbnail_color = EXCLUDED.thumbnail_color,
+                render = EXCLUDED.render,
+                removed_at = NULL,
+                updated_at = NOW()"#,
+        )
+        .bind(&game.id).bind(&game.title).bind(&game.description)
+        .bind(&game.modes).bind(&game.inputs)
+        .bind(&game.thumbnail_color).bind(&game.render)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let removed: Vec<String> = sqlx::query_scalar(
+        "UPDATE engine_games SET removed_at = NOW(), updated_at = NOW() WHERE removed_at IS NULL AND id <> ALL($1) RETURNING id",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let created: Vec<&String> = ids.iter().filter(|id| !known.contains_key(*id)).collect();
+    let restored: Vec<&String> = ids.iter().filter(|id| known.get(*id) == Some(&true)).collect();
+    Ok(Json(json!({
+        "games": ids.len(),
+        "created": created,
+        "restored": restored,
+        "removed": removed,
+    })))
+}
+
 pub async fn admin_list_categories(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
     let rows: Vec<(String, String, String, Option<String>, i32, bool, i64)> = sqlx::query_as(
         r#"SELECT gc.id, gc.name, gc.slug, gc.icon_emoji, gc.sort_order, gc.is_active,
-            (SELECT COUNT(*)::bigint FROM game_category_assignments gca WHERE gca.category_id = gc.id AND gca.tenant_id = gc.tenant_id)
-        FROM game_categories gc WHERE gc.tenant_id = $1 ORDER BY gc.sort_order"#,
+            (SELECT COUNT(*)::bigint FROM game_category_assignments gca WHERE gca.category_id = gc.id AND gca.tenant_id = gc.tenant_id
+                AND NOT EXISTS (SELECT 1 FROM engine_games eg WHERE eg.id = gca.game_id AND eg.removed_at IS NOT NULL))
+        FROM game_categories gc WHERE gc.tenant_id = $1 AND gc.deleted_at IS NULL ORDER BY gc.sort_order"#,
     )
     .bind(&tenant.0 .0)
     .fetch_all(&state.db)
@@ -206,75 +385,249 @@ pub async fn admin_list_categories(
 
 pub async fn create_category(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Json(body): Json<CreateCategoryRequest>,
 ) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
     let slug = body.name.to_lowercase().replace(|c: char| !c.is_alphanumeric(), "-");
     let id = uuid::Uuid::new_v4().to_string();
 
+    let taken: Option<bool> =
+        sqlx::query_scalar("SELECT deleted_at IS NOT NULL FROM game_categories WHERE tenant_id = $1 AND slug = $2")
+            .bind(tid)
+            .bind(&slug)
+            .fetch_optional(&state.db)
+            .await?;
+    match taken {
+        Some(true) => return Err(AppError::Conflict("A deleted category has this name; restore it instead".into())),
+        Some(false) => return Err(AppError::Conflict("A category with this name already exists".into())),
+        None => {}
+    }
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::CATEGORY, &id).await?;
     sqlx::query(
         "INSERT INTO game_categories (id, tenant_id, name, slug, description, icon_emoji, icon_color, sort_order, is_active) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, true)",
     )
     .bind(&id)
-    .bind(&tenant.0 .0)
+    .bind(tid)
     .bind(&body.name)
     .bind(&slug)
     .bind(&body.description)
     .bind(&body.icon_emoji)
     .bind(&body.icon_color)
     .bind(body.sort_order.unwrap_or(0))
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
 
     Ok(Json(json!({"id": id, "slug": slug})))
 }
 
 pub async fn update_category(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
     Json(body): Json<CreateCategoryRequest>,
 ) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
     let slug = body.name.to_lowercase().replace(|c: char| !c.is_alphanumeric(), "-");
+
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::CATEGORY, &id).await?;
+    if audit.existing().is_none() {
+        return Err(AppError::NotFound("Category not found".into()));
+    }
     sqlx::query(
         "UPDATE game_categories SET name = $1, slug = $2, description = $3, icon_emoji = $4, icon_color = $5, sort_order = $6 WHERE id = $7 AND tenant_id = $8",
     )
     .bind(&body.name).bind(&slug).bind(&body.description)
     .bind(&body.icon_emoji).bind(&body.icon_color).bind(body.sort_order.unwrap_or(0))
-    .bind(&id).bind(&tenant.0 .0)
-    .execute(&state.db).await?;
+    .bind(&id).bind(tid)
+    .execute(&mut *tx).await?;
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
 
     Ok(Json(json!({"success": true})))
 }
 
+/// Soft-deletes a category. Its game assignments are kept for a restore.
 pub async fn delete_category(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
 ) -> AppResult<Json<Value>> {
-    sqlx::query("DELETE FROM game_categories WHERE id = $1 AND tenant_id = $2")
-        .bind(&id).bind(&tenant.0 .0)
-        .execute(&state.db).await?;
+    let mut tx = state.db.begin().await?;
+    if !entity_audit::soft_delete(&mut tx, &tenant.0 .0, player.id, entity_audit::CATEGORY, &id).await? {
+        return Err(AppError::NotFound("Category not found".into()));
+    }
+    tx.commit().await?;
     Ok(Json(json!({"success": true})))
 }
 
+/// Replaces a game's categories. Changes to custom games are audited;
+/// engine games aren't admin-managed entities.
 pub async fn assign_categories(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
     Json(body): Json<AssignCategoriesRequest>,
 ) -> AppResult<Json<Value>> {
     let tid = &tenant.0 .0;
+    let mut tx = state.db.begin().await?;
+    let audit = Audit::begin(&mut tx, tid, entity_audit::GAME, &id).await?;
     sqlx::query("DELETE FROM game_category_assignments WHERE tenant_id = $1 AND game_id = $2")
-        .bind(tid).bind(&id).execute(&state.db).await?;
+        .bind(tid).bind(&id).execute(&mut *tx).await?;
 
     for cat in &body.categories {
         sqlx::query(
             "INSERT INTO game_category_assignments (tenant_id, game_id, category_id, sort_order) VALUES ($1, $2, $3, $4)",
         )
         .bind(tid).bind(&id).bind(&cat.category_id).bind(cat.sort_order.unwrap_or(0))
-        .execute(&state.db).await?;
+        .execute(&mut *tx).await?;
     }
 
+    audit.record(&mut tx, tid, player.id).await?;
+    tx.commit().await?;
+
     Ok(Json(json!({"success": true})))
 }
+
+/// The game's Glicko-2 parameters: `overrides` as stored, `effective` with
+/// the server defaults filled in.
+pub async fn get_rating_config(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let overrides = rating::overrides(&state.db, tid, &id).await?;
+    let effective = rating::params_for(&state.db, &state.config.rating, tid, &id).await?;
+
+    let (rating, deviation, volatility, tau, period) = overrides.unwrap_or_default();
+    Ok(Json(json!({
+        "gameId": id,
+        "overrides": {
+            "initialRating": rating, "initialDeviation": deviation, "initialVolatility": volatility,
+            "tau": tau, "ratingPeriodDays": period,
+        },
+        "effective": {
+            "initialRating": effective.initial_rating, "initialDeviation": effective.initial_deviation,
+            "initialVolatility": effective.initial_volatility, "tau": effective.tau,
+            "ratingPeriodDays": effective.rating_period_days,
+        },
+    })))
+}
+
+/// Replaces the game's overrides; omitted fields fall back to the server
+/// defaults. Applies to ratings from the next match on.
+pub async fn set_rating_config(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<RatingConfigRequest>,
+) -> AppResult<Json<Value>> {
+    if body.initial_rating.is_some_and(|r| !(0..=5000).contains(&r)) {
+        return Err(AppError::BadRequest("initialRating must be 0-5000".into()));
+    }
+    if body.initial_deviation.is_some_and(|d| !(30.0..=500.0).contains(&d)) {
+        return Err(AppError::BadRequest("initialDeviation must be 30-500".into()));
+    }
+    if body.initial_volatility.is_some_and(|v| !(0.01..=0.2).contains(&v)) {
+        return Err(AppError::BadRequest("initialVolatility must be 0.01-0.2".into()));
+    }
+    if body.tau.is_some_and(|t| !(0.2..=1.5).contains(&t)) {
+        return Err(AppError::BadRequest("tau must be 0.2-1.5".into()));
+    }
+    if body.rating_period_days.is_some_and(|p| !(0.1..=365.0).contains(&p)) {
+        return Err(AppError::BadRequest("ratingPeriodDays must be 0.1-365".into()));
+    }
+
+    sqlx::query(
+        r#"INSERT INTO game_rating_configs (tenant_id, game_id, initial_rating, initial_deviation, initial_volatility, tau, rating_period_days, updated_by, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+        ON CONFLICT (tenant_id, game_id) DO UPDATE SET
+            initial_rating = EXCLUDED.initial_rating,
+            initial_deviation = EXCLUDED.initial_deviation,
+            initial_volatility = EXCLUDED.initial_volatility,
+            tau = EXCLUDED.tau,
+            rating_period_days = EXCLUDED.rating_period_days,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()"#,
+    )
+    .bind(&tenant.0 .0).bind(&id)
+    .bind(body.initial_rating).bind(body.initial_deviation).bind(body.initial_volatility)
+    .bind(body.tau).bind(body.rating_period_days).bind(player.id)
+    .execute(&state.db).await?;
+
+    get_rating_config(State(state), tenant, Path(id)).await
+}
+
+pub async fn get_leaderboard_config(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let half_life = score_decay::half_life_days(&state.db, tid, &id).await?;
+    let computed_at = score_decay::computed_at(&state.db, tid, &id).await?;
+    Ok(Json(json!({ "gameId": id, "decayHalfLifeDays": half_life, "computedAt": computed_at })))
+}
+
+/// Gives the game a decayed board with this half-life. The board is
+/// computed on each read until the queued materialization has run.
+pub async fn set_leaderboard_config(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<LeaderboardConfigRequest>,
+) -> AppResult<Json<Value>> {
+    if !(0.5..=3650.0).contains(&body.decay_half_life_days) {
+        return Err(AppError::BadRequest("decayHalfLifeDays must be 0.5-3650".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query(
+        r#"INSERT INTO game_leaderboard_configs (tenant_id, game_id, decay_half_life_days, updated_by, updated_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (tenant_id, game_id) DO UPDATE SET
+            decay_half_life_days = EXCLUDED.decay_half_life_days,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()"#,
+    )
+    .bind(&tenant.0 .0).bind(&id).bind(body.decay_half_life_days).bind(player.id)
+    .execute(&mut *tx).await?;
+    score_decay::clear(&mut tx, &tenant.0 .0, &id).await?;
+    jobs::run_now(&mut *tx, jobs::DECAY_LEADERBOARDS, json!({ "gameId": id })).await?;
+    tx.commit().await?;
+
+    get_leaderboard_config(State(state), tenant, Path(id)).await
+}
+
+/// Removes the game's decayed board.
+pub async fn delete_leaderboard_config(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let mut tx = state.db.begin().await?;
+    let deleted = sqlx::query("DELETE FROM game_leaderboard_configs WHERE tenant_id = $1 AND game_id = $2")
+        .bind(&tenant.0 .0)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("{} has no decayed leaderboard", id)));
+    }
+    score_decay::clear(&mut tx, &tenant.0 .0, &id).await?;
+    tx.commit().await?;
+    Ok(Json(json!({ "success": true, "gameId": id })))
+}
@@ -1,6 +1,7 @@
-use axum::{extract::State, Json};
+use axum::{extract::State, http::header, response::IntoResponse, Json};
 use serde_json::{json, Value};
 
+use crate::services::metrics;
 use crate::AppState;
 
 pub async fn health(State(state): State<AppState>) -> Json<Value> {
@@ -19,16 +20,18 @@ pub async fn health(State(state): State<AppState>) -> Json<Value> {
     }))
 }
 
-pub async fn metrics(State(state): State<AppState>) -> Json<Value> {
-    let db_ok = sqlx::query_scalar::<_, i32>("SELECT 1")
-        .fetch_one(&state.db)
-        .await
-        .is_ok();
-    let redis_ok = state.cache.health_check().await;
-
-    Json(json!({
-        "uptime": "running",
-        "postgres": db_ok,
-        "redis": redis_ok,
-    }))
+/// Prometheus scrape endpoint.
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let (websocket_connections, websocket_players) = state.realtime.connection_count().await;
+    let gauges = metrics::Gauges {
+        db_pool_size: state.db.size(),
+        db_pool_idle: state.db.num_idle(),
+        db_pool_max: state.db.options().get_max_connections(),
+        websocket_connections,
+        websocket_players,
+    };
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(&gauges),
+    )
 }
@@ -2,13 +2,18 @@ use axum::{
     extract::{Path, Query, State},
     Json,
 };
+use chrono::{DateTime, Datelike, Duration, Utc};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use uuid::Uuid;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::middleware::api_key::ApiKeyAuth;
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
-use crate::services::leaderboard;
+use crate::models::game_progress::{find_metric, game_metrics, Metric, MetricOrder, SCORE_METRIC};
+use crate::models::multiplayer::{MatchPlayerResult, Room, SubmitMatchRequest};
+use crate::services::{battle_pass_xp, cosmetics, jobs, leaderboard, privacy, rating, score_decay, score_distribution};
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -17,15 +22,168 @@ pub struct PaginationQuery {
     pub limit: Option<i64>,
 }
 
+#[derive(Deserialize)]
+pub struct MetricQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    /// Rank by this metric instead of the score.
+    pub metric: Option<String>,
+    /// `daily`, `weekly`, `monthly` or `alltime` (default).
+    pub window: Option<String>,
+    /// Only players in this region, e.g. `eu-west`.
+    pub region: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GlobalQuery {
+    pub limit: Option<i64>,
+    /// Only players in this region, e.g. `eu-west`.
+    pub region: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DistributionQuery {
+    /// Histogram buckets, 20 by default.
+    pub buckets: Option<usize>,
+    /// Place this score, e.g. the run just played, instead of the caller's
+    /// best.
+    pub score: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct InvalidateQuery {
+    /// Queue a rebuild straight away (default). Otherwise the board is
+    /// rebuilt the next time it is read.
+    pub rebuild: Option<bool>,
+}
+
+/// The metric a request asks to rank by, if it isn't the score.
+fn requested_metric(game_id: &str, key: Option<&str>) -> AppResult<Option<Metric>> {
+    match key {
+        None => Ok(None),
+        Some(key) if key == SCORE_METRIC.key => Ok(None),
+        Some(key) => find_metric(game_id, key)
+            .map(Some)
+            .ok_or_else(|| AppError::BadRequest(format!("{} has no metric {}", game_id, key))),
+    }
+}
+
+/// The period a board covers. Windows are calendar periods in UTC, so
+/// "this week" starts on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Window {
+    Daily,
+    Weekly,
+    Monthly,
+    AllTime,
+}
+
+impl Window {
+    fn parse(key: Option<&str>) -> AppResult<Self> {
+        match key.unwrap_or("alltime") {
+            "daily" => Ok(Window::Daily),
+            "weekly" => Ok(Window::Weekly),
+            "monthly" => Ok(Window::Monthly),
+            "alltime" => Ok(Window::AllTime),
+            other => Err(AppError::BadRequest(format!("Unknown window: {}", other))),
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Window::Daily => "daily",
+            Window::Weekly => "weekly",
+            Window::Monthly => "monthly",
+            Window::AllTime => "alltime",
+        }
+    }
+
+    /// When the current period began; `None` for all time.
+    fn start(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let today = now.date_naive();
+        let day = match self {
+            Window::Daily => today,
+            Window::Weekly => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            Window::Monthly => today.with_day(1)?,
+            Window::AllTime => return None,
+        };
+        Some(day.and_hms_opt(0, 0, 0)?.and_utc())
+    }
+}
+
+/// A board narrowed to a period and/or a region. The all-time board of
+/// every region is the plain one, served from the cache.
+struct BoardFilter {
+    window: Window,
+    region: Option<String>,
+}
+
+impl BoardFilter {
+    fn from_query(q: &MetricQuery) -> AppResult<Self> {
+        Ok(Self {
+            window: Window::parse(q.window.as_deref())?,
+            region: q.region.clone().filter(|r| !r.is_empty()),
+        })
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.window != Window::AllTime || self.region.is_some()
+    }
+
+    /// Each player's best score on the board as `(player_id, score)` rows,
+    /// for a query binding tenant as `$1`, game as `$2` and the window start
+    /// (a timestamp, NULL for all time) as `$3`. Windowed boards take the
+    /// best of the period's submissions in `score_history`.
+    fn scores_sql(&self) -> &'static str {
+        match self.window {
+            Window::AllTime => {
+                "SELECT player_id::text AS player_id, high_score AS score FROM game_progress \
+                 WHERE tenant_id = $1 AND game_id = $2 AND $3::timestamptz IS NULL"
+            }
+            _ => {
+                "SELECT player_id::text AS player_id, MAX(score)::bigint AS score FROM score_history \
+                 WHERE tenant_id = $1 AND game_id = $2 AND created_at >= $3 AND voided_at IS NULL GROUP BY player_id"
+            }
+        }
+    }
+
+    fn json(&self) -> Value {
+        json!({"window": self.window.key(), "region": self.region})
+    }
+}
+
+/// The metrics a game can be ranked by, score first.
+pub async fn get_game_metrics(Path(game_id): Path<String>) -> AppResult<Json<Value>> {
+    let metrics: Vec<Metric> = std::iter::once(SCORE_METRIC)
+        .chain(game_metrics(&game_id).iter().copied())
+        .collect();
+    Ok(Json(json!({ "gameId": game_id, "metrics": metrics })))
+}
+
 pub async fn get_game_leaderboard(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
     Path(game_id): Path<String>,
-    Query(q): Query<PaginationQuery>,
+    Query(q): Query<MetricQuery>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
     let limit = q.limit.unwrap_or(50).min(100) as usize;
 
+    let filter = BoardFilter::from_query(&q)?;
+
+    if let Some(metric) = requested_metric(&game_id, q.metric.as_deref())? {
+        if filter.is_filtered() {
+            return Err(AppError::BadRequest("Metric boards can't be filtered by window or region".into()));
+        }
+        let offset = q.offset.unwrap_or(0).max(0);
+        return metric_leaderboard(&state, tenant_id, &game_id, metric, limit as i64, offset).await;
+    }
+
+    if filter.is_filtered() {
+        let offset = q.offset.unwrap_or(0).max(0);
+        return filtered_leaderboard(&state, tenant_id, &game_id, &filter, limit as i64, offset).await;
+    }
+
     // Try cache first
     let entries = leaderboard::get_top_k(
         &state.cache,
@@ -36,51 +194,221 @@ pub async fn get_game_leaderboard(
     )
     .await;
 
-    if !entries.is_empty() {
-        let results: Vec<Value> = entries
+    if let Some(entries) = entries {
+        let ids: Vec<String> = entries.iter().map(|(pid, _)| pid.clone()).collect();
+        let mut equipped = cosmetics::equipped_ids(&state.db, tenant_id, &ids).await?;
+        let mut results: Vec<Value> = entries
             .iter()
             .enumerate()
             .map(|(i, (pid, score))| {
-                json!({"rank": i + 1, "playerId": pid, "score": *score as i64})
+                let cosmetics = equipped.remove(pid).unwrap_or(Value::Null);
+                json!({"rank": i + 1, "playerId": pid, "score": *score as i64, "cosmetics": cosmetics})
             })
             .collect();
+        privacy::anonymize(&state.db, tenant_id, &mut results).await?;
         return Ok(Json(json!({ "entries": results, "source": "cache" })));
     }
 
     // Fallback to DB
-    let rows: Vec<(String, i64, String, i64)> = sqlx::query_as(
+    let rows: Vec<(String, i64, String, i64, Value)> = sqlx::query_as(&format!(
         r#"SELECT p.id::text, gp.high_score, p.display_name,
-            RANK() OVER (ORDER BY gp.high_score DESC)::bigint as rank
+            RANK() OVER (ORDER BY gp.high_score DESC)::bigint as rank, {}
         FROM game_progress gp
         JOIN players p ON p.id = gp.player_id AND p.tenant_id = gp.tenant_id
         WHERE gp.tenant_id = $1 AND gp.game_id = $2
         ORDER BY gp.high_score DESC
         LIMIT $3"#,
-    )
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(tenant_id)
     .bind(&game_id)
     .bind(limit as i64)
     .fetch_all(&state.db)
     .await?;
 
-    let results: Vec<Value> = rows
+    // Build the cached board for next time; games nobody has played don't
+    // get one.
+    if !rows.is_empty() {
+        jobs::rebuild_leaderboard(&state.db, tenant_id, &game_id).await?;
+    }
+
+    let mut results: Vec<Value> = rows
         .iter()
-        .map(|(pid, score, name, rank)| {
-            json!({"rank": rank, "playerId": pid, "displayName": name, "score": score})
+        .map(|(pid, score, name, rank, cosmetics)| {
+            json!({"rank": rank, "playerId": pid, "displayName": name, "score": score, "cosmetics": cosmetics})
         })
         .collect();
 
+    privacy::anonymize(&state.db, tenant_id, &mut results).await?;
     Ok(Json(json!({ "entries": results, "source": "db" })))
 }
 
+/// Admin: drops the game's cached leaderboard, e.g. after scores were
+/// corrected in the database.
+pub async fn invalidate_cache(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Query(q): Query<InvalidateQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    leaderboard::invalidate(&state.cache, tenant_id, &game_id, state.config.leaderboard.shard_count).await;
+
+    let rebuild = q.rebuild.unwrap_or(true);
+    if rebuild {
+        jobs::rebuild_leaderboard(&state.db, tenant_id, &game_id).await?;
+    }
+    Ok(Json(json!({ "success": true, "gameId": game_id, "rebuildQueued": rebuild })))
+}
+
+/// Ranks players by their best score within `filter`; ties share a rank.
+async fn filtered_leaderboard(
+    state: &AppState,
+    tenant_id: &str,
+    game_id: &str,
+    filter: &BoardFilter,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<(String, i64, String, i64, Value)> = sqlx::query_as(&format!(
+        r#"WITH best AS ({scores})
+        SELECT p.id::text, b.score, p.display_name,
+            RANK() OVER (ORDER BY b.score DESC)::bigint as rank, {equipped}
+        FROM best b
+        JOIN players p ON p.id::text = b.player_id AND p.tenant_id = $1
+        WHERE $4::text IS NULL OR p.region = $4
+        ORDER BY b.score DESC
+        LIMIT $5 OFFSET $6"#,
+        scores = filter.scores_sql(),
+        equipped = cosmetics::EQUIPPED_SQL
+    ))
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(filter.window.start(Utc::now()))
+    .bind(&filter.region)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut results: Vec<Value> = rows
+        .iter()
+        .map(|(pid, score, name, rank, cosmetics)| {
+            json!({"rank": rank, "playerId": pid, "displayName": name, "score": score, "cosmetics": cosmetics})
+        })
+        .collect();
+
+    privacy::anonymize(&state.db, tenant_id, &mut results).await?;
+    Ok(Json(json!({ "entries": results, "filter": filter.json(), "source": "db" })))
+}
+
+/// Ranks players by their best value for `metric`; ties share a rank.
+async fn metric_leaderboard(
+    state: &AppState,
+    tenant_id: &str,
+    game_id: &str,
+    metric: Metric,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<(String, i64, String, i64, Value)> = sqlx::query_as(&format!(
+        r#"SELECT p.id::text, gpm.best_value, p.display_name,
+            RANK() OVER (ORDER BY gpm.best_value {order})::bigint as rank, {equipped}
+        FROM game_progress_metrics gpm
+        JOIN players p ON p.id = gpm.player_id AND p.tenant_id = gpm.tenant_id
+        WHERE gpm.tenant_id = $1 AND gpm.game_id = $2 AND gpm.metric = $3
+        ORDER BY gpm.best_value {order}, gpm.updated_at
+        LIMIT $4 OFFSET $5"#,
+        order = metric.order.sql(),
+        equipped = cosmetics::EQUIPPED_SQL
+    ))
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(metric.key)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut results: Vec<Value> = rows
+        .iter()
+        .map(|(pid, value, name, rank, cosmetics)| {
+            json!({"rank": rank, "playerId": pid, "displayName": name, "value": value, "cosmetics": cosmetics})
+        })
+        .collect();
+
+    privacy::anonymize(&state.db, tenant_id, &mut results).await?;
+    Ok(Json(json!({ "entries": results, "metric": metric, "source": "db" })))
+}
+
 pub async fn get_my_rank(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(game_id): Path<String>,
+    Query(q): Query<MetricQuery>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
     let pid = player.id.to_string();
+    let filter = BoardFilter::from_query(&q)?;
+
+    if let Some(metric) = requested_metric(&game_id, q.metric.as_deref())? {
+        if filter.is_filtered() {
+            return Err(AppError::BadRequest("Metric boards can't be filtered by window or region".into()));
+        }
+        let best: Option<i64> = sqlx::query_scalar(
+            "SELECT best_value FROM game_progress_metrics WHERE tenant_id = $1 AND player_id = $2 AND game_id = $3 AND metric = $4",
+        )
+        .bind(tenant_id)
+        .bind(player.id)
+        .bind(&game_id)
+        .bind(metric.key)
+        .fetch_optional(&state.db)
+        .await?;
+        let Some(best) = best else {
+            return Ok(Json(json!({"rank": null, "metric": metric.key, "value": null})));
+        };
+        let comparison = match metric.order {
+            MetricOrder::Higher => ">",
+            MetricOrder::Lower => "<",
+        };
+        let rank: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*)::bigint + 1 FROM game_progress_metrics WHERE tenant_id = $1 AND game_id = $2 AND metric = $3 AND best_value {} $4",
+            comparison
+        ))
+        .bind(tenant_id)
+        .bind(&game_id)
+        .bind(metric.key)
+        .bind(best)
+        .fetch_one(&state.db)
+        .await?;
+        return Ok(Json(json!({"rank": rank, "metric": metric.key, "value": best})));
+    }
+
+    if filter.is_filtered() {
+        let row: Option<(i64, i64)> = sqlx::query_as(&format!(
+            r#"WITH best AS ({scores}),
+            ranked AS (
+                SELECT b.player_id, b.score, RANK() OVER (ORDER BY b.score DESC)::bigint as rank
+                FROM best b
+                JOIN players p ON p.id::text = b.player_id AND p.tenant_id = $1
+                WHERE $4::text IS NULL OR p.region = $4
+            )
+            SELECT rank, score FROM ranked WHERE player_id = $5"#,
+            scores = filter.scores_sql()
+        ))
+        .bind(tenant_id)
+        .bind(&game_id)
+        .bind(filter.window.start(Utc::now()))
+        .bind(&filter.region)
+        .bind(&pid)
+        .fetch_optional(&state.db)
+        .await?;
+        return Ok(Json(match row {
+            Some((rank, score)) => json!({"rank": rank, "score": score, "filter": filter.json()}),
+            None => json!({"rank": null, "score": 0, "filter": filter.json()}),
+        }));
+    }
 
     // Try cache
     if let Some(rank) = leaderboard::get_approx_rank(
@@ -143,55 +471,63 @@ pub async fn get_around_me(
         None => return Ok(Json(json!({"entries": []}))),
     };
 
-    let rows: Vec<(String, i64, String)> = sqlx::query_as(
-        r#"(SELECT p.id::text, gp.high_score, p.display_name
+    let rows: Vec<(String, i64, String, Value)> = sqlx::query_as(&format!(
+        r#"(SELECT p.id::text, gp.high_score, p.display_name, {equipped}
             FROM game_progress gp JOIN players p ON p.id = gp.player_id AND p.tenant_id = gp.tenant_id
             WHERE gp.tenant_id = $1 AND gp.game_id = $2 AND gp.high_score > $3
             ORDER BY gp.high_score ASC LIMIT 5)
         UNION ALL
-        (SELECT p.id::text, gp.high_score, p.display_name
+        (SELECT p.id::text, gp.high_score, p.display_name, {equipped}
             FROM game_progress gp JOIN players p ON p.id = gp.player_id AND p.tenant_id = gp.tenant_id
             WHERE gp.tenant_id = $1 AND gp.game_id = $2 AND gp.high_score <= $3
             ORDER BY gp.high_score DESC LIMIT 5)"#,
-    )
+        equipped = cosmetics::EQUIPPED_SQL
+    ))
     .bind(tenant_id)
     .bind(&game_id)
     .bind(score)
     .fetch_all(&state.db)
     .await?;
 
-    let entries: Vec<Value> = rows
+    let mut entries: Vec<Value> = rows
         .iter()
-        .map(|(pid, s, name)| json!({"playerId": pid, "score": s, "displayName": name}))
+        .map(|(pid, s, name, cosmetics)| {
+            json!({"playerId": pid, "score": s, "displayName": name, "cosmetics": cosmetics})
+        })
         .collect();
 
+    privacy::anonymize(&state.db, tenant_id, &mut entries).await?;
     Ok(Json(json!({ "entries": entries })))
 }
 
 pub async fn get_global_leaderboard(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
-    Query(q): Query<PaginationQuery>,
+    Query(q): Query<GlobalQuery>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
     let limit = q.limit.unwrap_or(50).min(100);
+    let region = q.region.filter(|r| !r.is_empty());
 
-    let rows: Vec<(String, i64, String)> = sqlx::query_as(
-        "SELECT id::text, total_score, display_name FROM players WHERE tenant_id = $1 ORDER BY total_score DESC LIMIT $2",
-    )
+    let rows: Vec<(String, i64, String, Value)> = sqlx::query_as(&format!(
+        "SELECT p.id::text, p.total_score, p.display_name, {} FROM players p WHERE p.tenant_id = $1 AND ($3::text IS NULL OR p.region = $3) ORDER BY p.total_score DESC LIMIT $2",
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(tenant_id)
     .bind(limit)
+    .bind(&region)
     .fetch_all(&state.db)
     .await?;
 
-    let entries: Vec<Value> = rows
+    let mut entries: Vec<Value> = rows
         .iter()
         .enumerate()
-        .map(|(i, (pid, score, name))| {
-            json!({"rank": i + 1, "playerId": pid, "displayName": name, "totalScore": score})
+        .map(|(i, (pid, score, name, cosmetics))| {
+            json!({"rank": i + 1, "playerId": pid, "displayName": name, "totalScore": score, "cosmetics": cosmetics})
         })
         .collect();
 
+    privacy::anonymize(&state.db, tenant_id, &mut entries).await?;
     Ok(Json(json!({ "entries": entries })))
 }
 
@@ -203,8 +539,8 @@ pub async fn get_friends_leaderboard(
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
 
-    let rows: Vec<(String, i64, String)> = sqlx::query_as(
-        r#"SELECT p.id::text, gp.high_score, p.display_name
+    let rows: Vec<(String, i64, String, Value)> = sqlx::query_as(&format!(
+        r#"SELECT p.id::text, gp.high_score, p.display_name, {}
         FROM game_progress gp
         JOIN players p ON p.id = gp.player_id AND p.tenant_id = gp.tenant_id
         WHERE gp.tenant_id = $1 AND gp.game_id = $2 AND (
@@ -216,7 +552,8 @@ pub async fn get_friends_leaderboard(
             )
         )
         ORDER BY gp.high_score DESC LIMIT 50"#,
-    )
+        cosmetics::EQUIPPED_SQL
+    ))
     .bind(tenant_id)
     .bind(&game_id)
     .bind(player.id)
@@ -226,8 +563,8 @@ pub async fn get_friends_leaderboard(
     let entries: Vec<Value> = rows
         .iter()
         .enumerate()
-        .map(|(i, (pid, score, name))| {
-            json!({"rank": i + 1, "playerId": pid, "displayName": name, "score": score})
+        .map(|(i, (pid, score, name, cosmetics))| {
+            json!({"rank": i + 1, "playerId": pid, "displayName": name, "score": score, "cosmetics": cosmetics})
         })
         .collect();
 
@@ -243,10 +580,12 @@ pub async fn get_ranked_leaderboard(
     let tenant_id = &tenant.0 .0;
     let limit = q.limit.unwrap_or(50).min(100);
 
-    let rows: Vec<(String, i64, f64, i32, i32)> = sqlx::query_as(
-        r#"SELECT le.player_id::text, le.score, le.skill_rating, le.wins, le.matches_played
+    let rows: Vec<(String, i64, i32, f64, i32, i32)> = sqlx::query_as(
+        r#"SELECT le.player_id::text, le.score, le.skill_rating, le.rating_deviation, le.wins, le.matches_played
         FROM leaderboard_entries le
         WHERE le.tenant_id = $1 AND le.game_id = $2 AND le.region = 'global'
+          AND le.season_id IS NOT DISTINCT FROM
+              (SELECT id FROM seasons WHERE tenant_id = $1 AND is_active = true LIMIT 1)
         ORDER BY le.skill_rating DESC
         LIMIT $3"#,
     )
@@ -256,22 +595,164 @@ pub async fn get_ranked_leaderboard(
     .fetch_all(&state.db)
     .await?;
 
-    let entries: Vec<Value> = rows
+    let mut entries: Vec<Value> = rows
         .iter()
         .enumerate()
-        .map(|(i, (pid, score, rating, wins, matches))| {
-            json!({"rank": i + 1, "playerId": pid, "score": score, "skillRating": rating, "wins": wins, "matchesPlayed": matches})
+        .map(|(i, (pid, score, rating, deviation, wins, matches))| {
+            json!({"rank": i + 1, "playerId": pid, "score": score, "skillRating": rating, "ratingDeviation": deviation.round(), "wins": wins, "matchesPlayed": matches})
         })
         .collect();
 
+    privacy::anonymize(&state.db, tenant_id, &mut entries).await?;
     Ok(Json(json!({ "entries": entries })))
 }
 
+/// Ranks players by their best submission with older ones decayed (see
+/// [`crate::services::score_decay`]); ties share a rank. Served from the
+/// nightly snapshot once there is one.
+pub async fn get_decayed_leaderboard(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Query(q): Query<PaginationQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let limit = q.limit.unwrap_or(50).min(100);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let Some(half_life) = score_decay::half_life_days(&state.db, tenant_id, &game_id).await? else {
+        return Err(AppError::NotFound(format!("{} has no decayed leaderboard", game_id)));
+    };
+    let computed_at = score_decay::computed_at(&state.db, tenant_id, &game_id).await?;
+
+    let rows: Vec<(String, f64, String, i64, Value)> = if computed_at.is_some() {
+        sqlx::query_as(&format!(
+            r#"SELECT p.id::text, dr.decayed_score, p.display_name, dr.rank, {}
+            FROM decayed_rankings dr
+            JOIN players p ON p.id::text = dr.player_id AND p.tenant_id = dr.tenant_id
+            WHERE dr.tenant_id = $1 AND dr.game_id = $2
+            ORDER BY dr.rank
+            LIMIT $3 OFFSET $4"#,
+            cosmetics::EQUIPPED_SQL
+        ))
+        .bind(tenant_id)
+        .bind(&game_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&state.db)
+        .await?
+    } else {
+        sqlx::query_as(&format!(
+            r#"WITH decayed AS ({scores})
+            SELECT p.id::text, d.score, p.display_name,
+                RANK() OVER (ORDER BY d.score DESC)::bigint as rank, {equipped}
+            FROM decayed d
+            JOIN players p ON p.id::text = d.player_id AND p.tenant_id = $1
+            ORDER BY d.score DESC
+            LIMIT $4 OFFSET $5"#,
+            scores = score_decay::DECAYED_SCORES_SQL,
+            equipped = cosmetics::EQUIPPED_SQL
+        ))
+        .bind(tenant_id)
+        .bind(&game_id)
+        .bind(half_life)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&state.db)
+        .await?
+    };
+
+    let mut results: Vec<Value> = rows
+        .iter()
+        .map(|(pid, score, name, rank, cosmetics)| {
+            json!({"rank": rank, "playerId": pid, "displayName": name, "score": score.round() as i64, "cosmetics": cosmetics})
+        })
+        .collect();
+
+    privacy::anonymize(&state.db, tenant_id, &mut results).await?;
+    Ok(Json(json!({
+        "entries": results,
+        "halfLifeDays": half_life,
+        "computedAt": computed_at,
+        "source": if computed_at.is_some() { "snapshot" } else { "db" },
+    })))
+}
+
+/// How the game's high scores are spread, and where a score falls in it:
+/// `?score=` if given, otherwise the caller's best. Percentiles and
+/// quantiles are approximate (see [`score_distribution`]).
+pub async fn get_score_distribution(
+    State(state): State<AppState>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Query(q): Query<DistributionQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let buckets = q
+        .buckets
+        .unwrap_or(score_distribution::DEFAULT_BUCKETS)
+        .clamp(1, score_distribution::MAX_BUCKETS);
+
+    let (hist, cached) = score_distribution::histogram(&state.db, &state.cache, tenant_id, &game_id, buckets).await?;
+
+    let score = match (q.score, player) {
+        (Some(score), _) => Some(score),
+        (None, Some(player)) => sqlx::query_scalar(
+            "SELECT high_score FROM game_progress WHERE player_id = $1 AND tenant_id = $2 AND game_id = $3",
+        )
+        .bind(player.id)
+        .bind(tenant_id)
+        .bind(&game_id)
+        .fetch_optional(&state.db)
+        .await?
+        .flatten(),
+        (None, None) => None,
+    };
+
+    let histogram: Vec<Value> = hist
+        .counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            json!({"from": hist.lower(i).ceil() as i64, "to": hist.lower(i + 1).ceil() as i64, "count": count})
+        })
+        .collect();
+    let quantile = |p: f64| hist.quantile(p).map(|s| s.round() as i64);
+
+    Ok(Json(json!({
+        "gameId": game_id,
+        "players": hist.total,
+        "min": hist.min,
+        "max": hist.max,
+        "buckets": histogram,
+        "quantiles": {
+            "p25": quantile(0.25),
+            "p50": quantile(0.5),
+            "p75": quantile(0.75),
+            "p90": quantile(0.9),
+            "p99": quantile(0.99),
+        },
+        "score": score,
+        "percentile": score.map(|s| (hist.percentile(s) * 10.0).round() / 10.0),
+        "source": if cached { "cache" } else { "db" },
+    })))
+}
+
+#[derive(sqlx::FromRow)]
+struct SeasonRow {
+    id: i32,
+    name: String,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    is_active: bool,
+}
+
 pub async fn get_seasons(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
-    let rows: Vec<(String, String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>, bool)> = sqlx::query_as(
+    let rows: Vec<SeasonRow> = sqlx::query_as(
         "SELECT id, name, starts_at, ends_at, is_active FROM seasons WHERE tenant_id = $1 ORDER BY starts_at DESC LIMIT 20",
     )
     .bind(&tenant.0 .0)
@@ -280,8 +761,8 @@ pub async fn get_seasons(
 
     let seasons: Vec<Value> = rows
         .iter()
-        .map(|(id, name, start, end, active)| {
-            json!({"id": id, "name": name, "startsAt": start, "endsAt": end, "isActive": active})
+        .map(|s| {
+            json!({"id": s.id, "name": s.name, "startsAt": s.starts_at, "endsAt": s.ends_at, "isActive": s.is_active})
         })
         .collect();
 
@@ -292,7 +773,7 @@ pub async fn get_current_season(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
 ) -> AppResult<Json<Value>> {
-    let row: Option<(String, String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+    let row: Option<(i32, String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
         "SELECT id, name, starts_at, ends_at FROM seasons WHERE tenant_id = $1 AND is_active = true LIMIT 1",
     )
     .bind(&tenant.0 .0)
@@ -307,22 +788,79 @@ pub async fn get_current_season(
     }
 }
 
+/// Records a finished match, updates every player's Glicko-2 rating for
+/// the game (see [`crate::services::rating`]) and keeps the per-player
+/// results for match history. A `roomId` marks that room finished.
+///
+/// Ratings only move for results the server can vouch for (see
+/// [`verified_result`]); other matches are recorded unrated.
 pub async fn submit_match(
     State(state): State<AppState>,
     tenant: axum::Extension<TenantId>,
-    Json(body): Json<crate::models::multiplayer::SubmitMatchRequest>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    key: Option<axum::Extension<ApiKeyAuth>>,
+    Json(body): Json<SubmitMatchRequest>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
 
+    let mut players = Vec::with_capacity(body.players.len());
     for pr in &body.players {
-        let player_id = uuid::Uuid::parse_str(&pr.player_id)
+        let player_id = Uuid::parse_str(&pr.player_id)
             .map_err(|_| crate::error::AppError::BadRequest("Invalid player ID".into()))?;
+        if players.iter().any(|(id, _)| *id == player_id) {
+            return Err(crate::error::AppError::BadRequest("Duplicate player in match".into()));
+        }
+        players.push((player_id, pr));
+    }
+    let mut room_players = Vec::new();
+    let mut room = None;
+    if let Some(room_id) = &body.room_id {
+        room = state.room_manager.get_room(room_id).await;
+        if let Some(room) = &room {
+            if room.game_id != body.game_id {
+                return Err(crate::error::AppError::BadRequest("Room is for a different game".into()));
+            }
+            if room.state == "finished" {
+                return Err(crate::error::AppError::Conflict("Match already submitted for this room".into()));
+            }
+            room_players = room.players.iter().map(|p| p.id).collect();
+        }
+    }
+    // Players only submit matches of a room they played in, so nobody else
+    // can finish it; backends with a key may submit any match
+    if key.is_none() {
+        if body.room_id.is_none() {
+            return Err(AppError::BadRequest("roomId is required".into()));
+        }
+        if room.is_none() {
+            return Err(AppError::NotFound("Room not found".into()));
+        }
+        if !player.as_ref().is_some_and(|p| room_players.contains(&p.id)) {
+            return Err(AppError::Forbidden("Not a player in this room".into()));
+        }
+        if players.iter().any(|(id, _)| !room_players.contains(id)) {
+            return Err(AppError::BadRequest("Every player must be in the room".into()));
+        }
+    }
+
+    let params = rating::params_for(&state.db, &state.config.rating, tenant_id, &body.game_id).await?;
+    let mut tx = state.db.begin().await?;
+
+    // Entries are scoped to the active season so rotation starts a fresh board
+    let season_id: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM seasons WHERE tenant_id = $1 AND is_active = true LIMIT 1",
+    )
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
 
+    for (player_id, pr) in &players {
         // Upsert global leaderboard entry
         sqlx::query(
-            r#"INSERT INTO leaderboard_entries (tenant_id, player_id, game_id, region, score, wins, losses, draws, matches_played, skill_rating, updated_at)
-            VALUES ($1, $2, $3, 'global', $4, $5, $6, 0, 1, 1000, NOW())
-            ON CONFLICT (tenant_id, player_id, game_id, region) DO UPDATE SET
+            r#"INSERT INTO leaderboard_entries (tenant_id, player_id, game_id, season_id, region, score, wins, losses, draws, matches_played,
+                skill_rating, rating_deviation, rating_volatility, updated_at)
+            VALUES ($1, $2, $3, $7, 'global', $4, $5, $6, 0, 1, $8, $9, $10, NOW())
+            ON CONFLICT (tenant_id, player_id, game_id, season_id, region) DO UPDATE SET
                 score = leaderboard_entries.score + EXCLUDED.score,
                 wins = leaderboard_entries.wins + EXCLUDED.wins,
                 losses = leaderboard_entries.losses + EXCLUDED.losses,
@@ -335,9 +873,95 @@ pub async fn submit_match(
         .bind(pr.score)
         .bind(if pr.is_winner { 1i32 } else { 0 })
         .bind(if pr.is_winner { 0i32 } else { 1 })
-        .execute(&state.db)
+        .bind(season_id)
+        .bind(params.initial_rating)
+        .bind(params.initial_deviation)
+        .bind(params.initial_volatility)
+        .execute(&mut *tx)
         .await?;
     }
 
-    Ok(Json(json!({"success": true})))
+    let verified = verified_result(key.is_some(), room.as_ref(), &players);
+    let ratings = if players.len() >= 2 && verified {
+        rating::rate_match(&mut tx, &params, tenant_id, &body.game_id, season_id, &players).await?
+    } else {
+        Vec::new()
+    };
+    let match_id = record_match(&mut tx, tenant_id, &body, &players, &ratings).await?;
+    // Battle pass XP only for verified results, to players the server saw
+    // in the room. A turn game's scores still come from its players, so
+    // only a backend's scores earn the per-point XP.
+    for (player_id, pr) in players.iter().filter(|(id, _)| verified && room_players.contains(id)) {
+        let source = if pr.is_winner { battle_pass_xp::MATCH_WIN } else { battle_pass_xp::MATCH_LOSS };
+        let points = if key.is_some() { pr.score } else { 0 };
+        battle_pass_xp::award(&mut tx, tenant_id, *player_id, source, points, &match_id.to_string()).await?;
+    }
+    tx.commit().await?;
+
+    if let Some(room_id) = &body.room_id {
+        state.room_manager.finish_room(room_id).await;
+    }
+
+    Ok(Json(json!({"success": true, "matchId": match_id, "ratings": ratings})))
+}
+
+/// Whether the server can vouch for a match's results: they come from a
+/// backend with a `matches:write` key, or from a turn-based room the
+/// server refereed to the end, naming exactly its players and its
+/// remaining player as the only winner.
+fn verified_result(by_key: bool, room: Option<&Room>, players: &[(Uuid, &MatchPlayerResult)]) -> bool {
+    if by_key {
+        return true;
+    }
+    let Some(room) = room.filter(|r| r.state == "forfeited") else { return false };
+    let Some(winner) = room.turn.as_ref().and_then(|t| t.winner_id) else { return false };
+    players.len() == room.players.len()
+        && room.players.iter().all(|p| players.iter().any(|(id, _)| *id == p.id))
+        && players.iter().all(|(id, pr)| {
+            let won = *id == winner;
+            pr.is_winner == won && pr.placement.map_or(true, |place| (place == 1) == won)
+        })
+}
+
+/// Stores the match and each player's result, in submission order.
+async fn record_match(
+    conn: &mut sqlx::PgConnection,
+    tenant_id: &str,
+    body: &SubmitMatchRequest,
+    players: &[(Uuid, &MatchPlayerResult)],
+    ratings: &[rating::RatingChange],
+) -> AppResult<Uuid> {
+    let match_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO multiplayer_matches (tenant_id, game_id, room_id, player_count, state, duration_ms, started_at, ended_at)
+        VALUES ($1, $2, $3, $4, 'completed', $5, NOW() - COALESCE($5, 0) * INTERVAL '1 millisecond', NOW())
+        RETURNING id"#,
+    )
+    .bind(tenant_id)
+    .bind(&body.game_id)
+    .bind(&body.room_id)
+    .bind(players.len() as i32)
+    .bind(body.duration_ms)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for (index, (player_id, pr)) in players.iter().enumerate() {
+        let change = ratings.iter().find(|r| r.player_id == *player_id);
+        sqlx::query(
+            r#"INSERT INTO multiplayer_match_players
+                (match_id, player_id, tenant_id, player_index, score, is_winner, placement, skill_rating, rating_change)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+        )
+        .bind(match_id)
+        .bind(player_id)
+        .bind(tenant_id)
+        .bind(index as i32)
+        .bind(pr.score)
+        .bind(pr.is_winner)
+        .bind(pr.placement)
+        .bind(change.map(|c| c.skill_rating))
+        .bind(change.map(|c| c.change))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(match_id)
 }
@@ -15,3 +15,17 @@ pub mod presence;
 pub mod compliance;
 pub mod games;
 pub mod health;
+pub mod replays;
+pub mod telemetry;
+pub mod remote_config;
+pub mod chat;
+pub mod auctions;
+pub mod crafting;
+pub mod appeals;
+pub mod daily;
+pub mod promo_codes;
+pub mod xapi;
+pub mod api_keys;
+pub mod entities;
+pub mod crash_reports;
+pub mod anticheat;
@@ -2,12 +2,18 @@ use axum::{
     extract::{Path, Query, State},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use uuid::Uuid;
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
 use crate::models::multiplayer::*;
+use crate::services::room_manager::{
+    notify_forfeit, run_matchmaker, BOT_GAMES, DEFAULT_TURN_SECS, MAX_TURN_SECS, MIN_TURN_SECS,
+};
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -29,16 +35,42 @@ pub async fn list_rooms(
     Ok(Json(json!({ "rooms": rooms })))
 }
 
+/// Creates a room. Turn-based rooms default to two players and start once
+/// full.
 pub async fn create_room(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
     Json(body): Json<CreateRoomRequest>,
 ) -> AppResult<Json<Value>> {
+    let turn = match body.turn_based {
+        Some(true) => {
+            let secs = body.turn_secs.unwrap_or(DEFAULT_TURN_SECS);
+            if !(MIN_TURN_SECS..=MAX_TURN_SECS).contains(&secs) {
+                return Err(AppError::BadRequest(format!(
+                    "turnSecs must be between {} and {}",
+                    MIN_TURN_SECS, MAX_TURN_SECS
+                )));
+            }
+            Some(TurnState::new(&tenant.0 .0, secs))
+        }
+        _ => None,
+    };
+    let default_players = if turn.is_some() { 2 } else { 4 };
+
     let p = get_room_player(&state, player.id).await?;
     let room = state
         .room_manager
-        .create_room(p, body.game_id, body.max_players.unwrap_or(4), body.is_private.unwrap_or(false))
-        .await;
+        .create_room(
+            &tenant.0 .0,
+            p,
+            body.game_id,
+            body.max_players.unwrap_or(default_players),
+            body.is_private.unwrap_or(false),
+            body.allow_spectators.unwrap_or(true),
+            turn,
+        )
+        .await?;
 
     Ok(Json(json!({ "room": room })))
 }
@@ -64,14 +96,78 @@ pub async fn join_room(
     Ok(Json(json!({ "room": room })))
 }
 
+/// Joins the matchmaking queue for a game. Players are matched server-side
+/// with others whose skill rating falls in a band that widens the longer
+/// they wait; the room arrives as a `match_found` event on the realtime
+/// socket, or through `GET /multiplayer/matchmake` for clients without one.
 pub async fn matchmake(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
     Json(body): Json<MatchmakeRequest>,
 ) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
     let p = get_room_player(&state, player.id).await?;
-    let room = state.room_manager.find_match(p, &body.game_id).await;
-    Ok(Json(json!({ "room": room })))
+    let rating = skill_rating(&state, tenant_id, player.id, &body.game_id).await?;
+    let bot_fallback = body.bot_fallback.unwrap_or(true) && BOT_GAMES.contains(&body.game_id.as_str());
+    state
+        .room_manager
+        .enqueue(tenant_id, &body.game_id, p, rating, bot_fallback)
+        .await;
+
+    // A match may already be waiting; don't hold the player until the next tick
+    run_matchmaker(&state).await;
+    matchmaking_status(&state, player.id).await
+}
+
+pub async fn matchmake_status(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+) -> AppResult<Json<Value>> {
+    matchmaking_status(&state, player.id).await
+}
+
+pub async fn cancel_matchmake(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+) -> AppResult<Json<Value>> {
+    let cancelled = state.room_manager.dequeue(player.id).await;
+    Ok(Json(json!({ "cancelled": cancelled })))
+}
+
+async fn matchmaking_status(state: &AppState, player_id: Uuid) -> AppResult<Json<Value>> {
+    if let Some(queue) = state.room_manager.queue_status(player_id).await {
+        return Ok(Json(json!({ "status": "queued", "queue": queue })));
+    }
+    match state.room_manager.matched_room(player_id).await {
+        Some(room) => Ok(Json(json!({ "status": "matched", "room": room }))),
+        None => Ok(Json(json!({ "status": "idle" }))),
+    }
+}
+
+/// The player's rating for the game in the active season, falling back to
+/// their overall rating.
+async fn skill_rating(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    game_id: &str,
+) -> AppResult<i32> {
+    let rating: Option<i32> = sqlx::query_scalar(
+        r#"SELECT COALESCE(
+            (SELECT le.skill_rating FROM leaderboard_entries le
+             JOIN seasons s ON s.id = le.season_id AND s.is_active = true
+             WHERE le.tenant_id = $1 AND le.player_id = $2 AND le.game_id = $3 AND le.region = 'global'
+             LIMIT 1),
+            p.skill_rating)
+        FROM players p WHERE p.id = $2 AND p.tenant_id = $1"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(game_id)
+    .fetch_optional(&state.db)
+    .await?;
+    rating.ok_or_else(|| AppError::NotFound("Player not found".into()))
 }
 
 pub async fn my_room(
@@ -82,6 +178,215 @@ pub async fn my_room(
     Ok(Json(json!({ "room": room })))
 }
 
+/// match id, game_id, room_id, duration_ms, started_at, ended_at
+type MatchRow = (Uuid, String, Option<String>, Option<i32>, DateTime<Utc>, Option<DateTime<Utc>>);
+/// match id, player_id, display_name, score, is_winner, placement, skill_rating, rating_change
+type MatchResultRow = (Uuid, Uuid, Option<String>, i64, bool, Option<i32>, Option<i32>, Option<i32>);
+
+const DEFAULT_HISTORY_PAGE: i64 = 20;
+const MAX_HISTORY_PAGE: i64 = 50;
+
+/// The player's finished matches, newest first, with every participant's
+/// result.
+pub async fn match_history(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<MatchHistoryQuery>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let limit = q.limit.unwrap_or(DEFAULT_HISTORY_PAGE).clamp(1, MAX_HISTORY_PAGE);
+    let offset = q.offset.unwrap_or(0).max(0);
+
+    let mut matches: Vec<MatchRow> = sqlx::query_as(
+        r#"SELECT m.id, m.game_id, m.room_id, m.duration_ms, m.started_at, m.ended_at
+        FROM multiplayer_matches m
+        JOIN multiplayer_match_players mp ON mp.match_id = m.id AND mp.player_id = $2
+        WHERE m.tenant_id = $1 AND ($3::text IS NULL OR m.game_id = $3)
+        ORDER BY m.started_at DESC
+        OFFSET $4 LIMIT $5"#,
+    )
+    .bind(tenant_id)
+    .bind(player.id)
+    .bind(&q.game_id)
+    .bind(offset)
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+    let has_more = matches.len() as i64 > limit;
+    matches.truncate(limit as usize);
+
+    let ids: Vec<Uuid> = matches.iter().map(|m| m.0).collect();
+    let results: Vec<MatchResultRow> = sqlx::query_as(
+        r#"SELECT mp.match_id, mp.player_id, p.display_name, mp.score, mp.is_winner, mp.placement,
+            mp.skill_rating, mp.rating_change
+        FROM multiplayer_match_players mp
+        LEFT JOIN players p ON p.id = mp.player_id AND p.tenant_id = mp.tenant_id
+        WHERE mp.match_id = ANY($1)
+        ORDER BY mp.player_index"#,
+    )
+    .bind(&ids)
+    .fetch_all(&state.db)
+    .await?;
+
+    let matches: Vec<Value> = matches
+        .iter()
+        .map(|(id, game_id, room_id, duration, started, ended)| {
+            let players: Vec<&MatchResultRow> = results.iter().filter(|r| r.0 == *id).collect();
+            let won = players.iter().any(|r| r.1 == player.id && r.4);
+            let players: Vec<Value> = players
+                .iter()
+                .map(|(_, pid, name, score, winner, placement, rating, change)| {
+                    json!({
+                        "playerId": pid,
+                        "displayName": name,
+                        "score": score,
+                        "isWinner": winner,
+                        "placement": placement,
+                        "skillRating": rating,
+                        "ratingChange": change,
+                    })
+                })
+                .collect();
+            json!({
+                "id": id,
+                "gameId": game_id,
+                "roomId": room_id,
+                "durationMs": duration,
+                "startedAt": started,
+                "endedAt": ended,
+                "won": won,
+                "players": players,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({ "matches": matches, "offset": offset, "limit": limit, "hasMore": has_more })))
+}
+
+/// Opens a new room with the same game and settings as a finished one and
+/// invites the other participants with a `rematch_invite` event. Whoever
+/// asks first hosts; the others asking after join the same room.
+pub async fn rematch(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    // Participants of the room's last recorded match
+    let participants: Vec<(Uuid, String)> = sqlx::query_as(
+        r#"SELECT mp.player_id, m.game_id
+        FROM multiplayer_match_players mp
+        JOIN multiplayer_matches m ON m.id = mp.match_id
+        WHERE m.id = (SELECT id FROM multiplayer_matches
+                      WHERE tenant_id = $1 AND room_id = $2
+                      ORDER BY started_at DESC LIMIT 1)
+        ORDER BY mp.player_index"#,
+    )
+    .bind(tenant_id)
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let Some((_, game_id)) = participants.first().cloned() else {
+        return Err(AppError::NotFound("No finished match for this room".into()));
+    };
+    if !participants.iter().any(|(pid, _)| *pid == player.id) {
+        return Err(AppError::Forbidden("Not a participant of this match".into()));
+    }
+
+    // The room may be gone after a restart; rebuild its settings then
+    let template = match state.room_manager.get_room(&id).await {
+        Some(room) => room,
+        None => Room {
+            id: id.clone(),
+            tenant_id: tenant_id.clone(),
+            game_id,
+            host_id: player.id,
+            players: Vec::new(),
+            max_players: participants.len() as i32,
+            state: "finished".to_string(),
+            is_private: true,
+            allow_spectators: true,
+            spectator_count: 0,
+            spectators: Default::default(),
+            turn: None,
+            bot: None,
+            created_at: Utc::now(),
+        },
+    };
+
+    let host = get_room_player(&state, player.id).await?;
+    let (room, created) = state.room_manager.rematch(&id, &template, host.clone()).await?;
+
+    if created {
+        let others: Vec<Uuid> = participants
+            .iter()
+            .map(|(pid, _)| *pid)
+            .filter(|pid| *pid != player.id)
+            .collect();
+        let event = json!({
+            "type": "rematch_invite",
+            "room": room,
+            "previousRoomId": id,
+            "from": {"id": host.id, "displayName": host.display_name},
+        });
+        state.realtime.send_to_many(tenant_id, &others, &event).await;
+    }
+
+    Ok(Json(json!({ "room": room, "created": created })))
+}
+
+// ---------------------------------------------------------------------------
+// Turn-based rooms
+// ---------------------------------------------------------------------------
+
+/// Plays the caller's turn. The other players get a `turn_submitted` event
+/// with the move and whose turn is next.
+pub async fn submit_turn(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<SubmitTurnRequest>,
+) -> AppResult<Json<Value>> {
+    let (room, turn) = state.room_manager.submit_turn(&id, player.id, body.data).await?;
+
+    let others: Vec<Uuid> = room.players.iter().map(|p| p.id).filter(|pid| *pid != player.id).collect();
+    let event = json!({"type": "turn_submitted", "roomId": room.id, "turn": turn, "room": room});
+    state.realtime.send_to_many(&tenant.0 .0, &others, &event).await;
+
+    Ok(Json(json!({ "turn": turn, "room": room })))
+}
+
+/// Turns taken since the caller last looked, for clients without the
+/// realtime socket or catching up after being away.
+pub async fn list_turns(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    Path(id): Path<String>,
+    Query(q): Query<TurnQuery>,
+) -> AppResult<Json<Value>> {
+    let (room, turns) = state
+        .room_manager
+        .turns_since(&id, player.id, q.since.unwrap_or(0))
+        .await?;
+    Ok(Json(json!({ "turns": turns, "room": room })))
+}
+
+/// Resigns the caller from a turn-based game in progress.
+pub async fn forfeit_turns(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let room = state.room_manager.forfeit(&id, player.id).await?;
+    notify_forfeit(&state, &room, player.id, "resigned").await;
+    Ok(Json(json!({ "room": room })))
+}
+
 async fn get_room_player(
     state: &AppState,
     player_id: uuid::Uuid,
@@ -99,3 +404,84 @@ async fn get_room_player(
         avatar_character: row.1,
     })
 }
+
+// ---------------------------------------------------------------------------
+// Spectating (over the presence socket)
+// ---------------------------------------------------------------------------
+
+/// Presence socket messages for spectating:
+///
+/// * `{"type":"spectate","roomId":"..."}` — start watching a room; replies
+///   `{"type":"spectating","room":{...}}`.
+/// * `{"type":"stop_spectating"}`
+/// * `{"type":"room_frame","roomId":"...","frame":{...}}` — sent by the
+///   room's host; relayed untouched to every spectator as `spectate_frame`.
+pub async fn handle_spectator_message(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    msg: &Value,
+) -> Option<Value> {
+    match msg["type"].as_str() {
+        Some("spectate") => {
+            let room_id = msg["roomId"].as_str()?;
+            match state.room_manager.add_spectator(room_id, tenant_id, player_id).await {
+                Ok(room) => {
+                    broadcast_spectator_count(state, tenant_id, &room).await;
+                    Some(json!({"type": "spectating", "room": room}))
+                }
+                Err(AppError::NotFound(m) | AppError::Forbidden(m) | AppError::Conflict(m)) => {
+                    Some(json!({"type": "error", "message": m}))
+                }
+                Err(_) => Some(json!({"type": "error", "message": "Could not spectate room"})),
+            }
+        }
+        Some("stop_spectating") => {
+            stop_spectating(state, tenant_id, player_id).await;
+            None
+        }
+        Some("room_frame") => {
+            let room_id = msg["roomId"].as_str()?;
+            if !msg["frame"].is_object() {
+                return Some(json!({"type": "error", "message": "frame must be an object"}));
+            }
+            let room = state.room_manager.get_room(room_id).await?;
+            if room.host_id != player_id {
+                return Some(json!({"type": "error", "message": "Only the host can stream a room"}));
+            }
+            let event = json!({
+                "type": "spectate_frame",
+                "roomId": room.id,
+                "playerId": player_id,
+                "frame": msg["frame"],
+            });
+            for (spectator, spectator_tenant) in &room.spectators {
+                state.realtime.send_to(spectator_tenant, *spectator, &event).await;
+            }
+            None
+        }
+        _ => Some(json!({"type": "error", "message": "Unknown message type"})),
+    }
+}
+
+/// Removes the player from the room they are watching, if any. Also called
+/// when their last socket closes.
+pub async fn stop_spectating(state: &AppState, tenant_id: &str, player_id: Uuid) {
+    if let Some(room) = state.room_manager.remove_spectator(player_id).await {
+        broadcast_spectator_count(state, tenant_id, &room).await;
+    }
+}
+
+/// Pushes the room's new spectator count to its players and spectators.
+async fn broadcast_spectator_count(state: &AppState, tenant_id: &str, room: &Room) {
+    let event = json!({
+        "type": "spectators",
+        "roomId": room.id,
+        "count": room.spectator_count,
+    });
+    let players: Vec<Uuid> = room.players.iter().map(|p| p.id).collect();
+    state.realtime.send_to_many(tenant_id, &players, &event).await;
+    for (spectator, spectator_tenant) in &room.spectators {
+        state.realtime.send_to(spectator_tenant, *spectator, &event).await;
+    }
+}
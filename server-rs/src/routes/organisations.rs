@@ -6,11 +6,17 @@ use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
+use crate::middleware::api_key::ApiKeyAuth;
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::entitlements;
 use crate::middleware::tenant::TenantId;
 use crate::models::organisation::*;
-use crate::services::subscription_sync;
+use crate::models::player::PlaytimeLimitRequest;
+use crate::services::classrooms::{
+    assignment_progress, authorize_classroom, fetch_assignments, fetch_classroom, member_role,
+    progress_status, require_teacher, roster, TEACHER_ROLES,
+};
+use crate::services::{jobs, playtime, report_export, subscription_sync};
 use crate::AppState;
 
 pub async fn create_org(
@@ -131,10 +137,18 @@ pub async fn get_org(
     .await?;
 
     let plan = subscription_sync::get_effective_plan(&state.db, &id).await?;
+    let playtime_limit: Option<i32> = sqlx::query_scalar(
+        "SELECT daily_playtime_limit_mins FROM organisations WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(&id)
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
 
     Ok(Json(json!({
         "id": org.0, "name": org.1, "slug": org.2, "ownerId": org.3,
         "createdAt": org.4, "memberCount": member_count, "plan": plan,
+        "dailyPlaytimeLimitMinutes": playtime_limit,
     })))
 }
 
@@ -174,6 +188,9 @@ pub async fn add_member(
     let new_player_id = Uuid::parse_str(&body.player_id)
         .map_err(|_| AppError::BadRequest("Invalid player ID".into()))?;
     let member_role = body.role.as_deref().unwrap_or("member");
+    if !["admin", "teacher", "member"].contains(&member_role) {
+        return Err(AppError::BadRequest("Role must be admin, teacher or member".into()));
+    }
 
     sqlx::query(
         "INSERT INTO organisation_members (organisation_id, player_id, tenant_id, role, joined_at) VALUES ($1, $2, $3, $4, NOW()) ON CONFLICT DO NOTHING",
@@ -185,5 +202,636 @@ pub async fn add_member(
     .execute(&state.db)
     .await?;
 
+    // The billing sync job catches up if Stripe can't be reached now.
+    if let Err(e) = subscription_sync::sync_seats(&state, &id, tenant_id).await {
+        tracing::warn!("Seat sync for organisation {} failed: {}", id, e);
+    }
+
+    Ok(Json(json!({"success": true})))
+}
+
+/// Sets the daily playtime limit of the organisation's `member`s. Members
+/// whose own limit is lower keep theirs.
+pub async fn set_playtime_limit(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<PlaytimeLimitRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    match member_role(&state.db, &id, player.id, tid).await?.as_deref() {
+        Some("owner") | Some("admin") => {}
+        _ => return Err(AppError::Forbidden("Must be org owner or admin".into())),
+    }
+    playtime::validate_limit(body.daily_limit_minutes)?;
+
+    sqlx::query("UPDATE organisations SET daily_playtime_limit_mins = $3, updated_at = NOW() WHERE id = $1 AND tenant_id = $2")
+        .bind(&id)
+        .bind(tid)
+        .bind(body.daily_limit_minutes)
+        .execute(&state.db)
+        .await?;
+
+    Ok(Json(json!({ "id": id, "dailyPlaytimeLimitMinutes": body.daily_limit_minutes })))
+}
+
+// ---------------------------------------------------------------------------
+// Classrooms & assignments
+// ---------------------------------------------------------------------------
+
+const MAX_STUDENTS_PER_REQUEST: usize = 100;
+const MAX_ROSTER_SIZE: usize = 1000;
+
+fn progress_json(progress: &AssignmentProgress, due_at: chrono::DateTime<chrono::Utc>) -> Value {
+    json!({
+        "assignmentId": progress.assignment_id,
+        "bestScore": progress.best_score,
+        "attempts": progress.attempts,
+        "completedAt": progress.completed_at,
+        "status": progress_status(progress, due_at),
+    })
+}
+
+pub async fn create_classroom(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<CreateClassroomRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+
+    let name = body.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(AppError::BadRequest("Classroom name must be 1-100 characters".into()));
+    }
+
+    let classroom_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO classrooms (tenant_id, organisation_id, name, teacher_id) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(tid)
+    .bind(&id)
+    .bind(name)
+    .bind(player.id)
+    .fetch_one(&state.db)
+    .await?;
+
+    let classroom = fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+    Ok(Json(json!({ "classroom": classroom })))
+}
+
+/// Teachers get every classroom in the organisation, students the ones
+/// they're enrolled in.
+pub async fn list_classrooms(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let role = member_role(&state.db, &id, player.id, tid)
+        .await?
+        .ok_or_else(|| AppError::Forbidden("Not a member of this organisation".into()))?;
+    let is_teacher = TEACHER_ROLES.contains(&role.as_str());
+
+    let classrooms = sqlx::query_as::<_, Classroom>(
+        r#"SELECT c.id, c.organisation_id, c.name, c.teacher_id, c.created_at,
+            (SELECT COUNT(*) FROM classroom_students cs WHERE cs.classroom_id = c.id)::bigint AS student_count
+        FROM classrooms c
+        WHERE c.organisation_id = $1 AND c.tenant_id = $2
+            AND ($3 OR EXISTS(SELECT 1 FROM classroom_students cs
+                              WHERE cs.classroom_id = c.id AND cs.player_id = $4))
+        ORDER BY c.created_at"#,
+    )
+    .bind(&id)
+    .bind(tid)
+    .bind(is_teacher)
+    .bind(player.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "classrooms": classrooms, "role": role })))
+}
+
+pub async fn delete_classroom(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+
+    let deleted = sqlx::query("DELETE FROM classrooms WHERE id = $1 AND organisation_id = $2 AND tenant_id = $3")
+        .bind(classroom_id)
+        .bind(&id)
+        .bind(tid)
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Classroom not found".into()));
+    }
+
+    Ok(Json(json!({"success": true})))
+}
+
+/// Adds organisation members to the roster. Players who aren't members of
+/// the organisation are skipped and reported back.
+pub async fn add_students(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+    Json(body): Json<AddStudentsRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    if body.player_ids.is_empty() || body.player_ids.len() > MAX_STUDENTS_PER_REQUEST {
+        return Err(AppError::BadRequest(format!(
+            "playerIds must contain 1-{} players",
+            MAX_STUDENTS_PER_REQUEST
+        )));
+    }
+    let requested = body
+        .player_ids
+        .iter()
+        .map(|p| Uuid::parse_str(p))
+        .collect::<Result<Vec<Uuid>, _>>()
+        .map_err(|_| AppError::BadRequest("Invalid player ID".into()))?;
+
+    let members: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT player_id FROM organisation_members WHERE organisation_id = $1 AND tenant_id = $2 AND player_id = ANY($3)",
+    )
+    .bind(&id)
+    .bind(tid)
+    .bind(&requested)
+    .fetch_all(&state.db)
+    .await?;
+    let not_members: Vec<Uuid> = requested.iter().filter(|p| !members.contains(p)).copied().collect();
+
+    let added = sqlx::query(
+        r#"INSERT INTO classroom_students (classroom_id, tenant_id, player_id)
+        SELECT $1, $2, unnest($3::uuid[])
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(classroom_id)
+    .bind(tid)
+    .bind(&members)
+    .execute(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "added": added.rows_affected(), "notMembers": not_members })))
+}
+
+/// Replaces the roster with the given organisation members, for a school's
+/// backend keeping classrooms in step with its own records. An API key with
+/// `roster:write` may call it as well as teachers. Players who aren't
+/// members are skipped and reported back.
+pub async fn sync_roster(
+    State(state): State<AppState>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    key: Option<axum::Extension<ApiKeyAuth>>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+    Json(body): Json<AddStudentsRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if key.is_none() {
+        let player = player.ok_or_else(|| AppError::Unauthorized("No token provided".into()))?;
+        require_teacher(&state.db, &id, player.id, tid).await?;
+    }
+    fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    if body.player_ids.len() > MAX_ROSTER_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "playerIds can contain at most {} players",
+            MAX_ROSTER_SIZE
+        )));
+    }
+    let requested = body
+        .player_ids
+        .iter()
+        .map(|p| Uuid::parse_str(p))
+        .collect::<Result<Vec<Uuid>, _>>()
+        .map_err(|_| AppError::BadRequest("Invalid player ID".into()))?;
+
+    let members: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT player_id FROM organisation_members WHERE organisation_id = $1 AND tenant_id = $2 AND player_id = ANY($3)",
+    )
+    .bind(&id)
+    .bind(tid)
+    .bind(&requested)
+    .fetch_all(&state.db)
+    .await?;
+    let not_members: Vec<Uuid> = requested.iter().filter(|p| !members.contains(p)).copied().collect();
+
+    let mut tx = state.db.begin().await?;
+    let removed = sqlx::query(
+        "DELETE FROM classroom_students WHERE classroom_id = $1 AND tenant_id = $2 AND NOT (player_id = ANY($3))",
+    )
+    .bind(classroom_id)
+    .bind(tid)
+    .bind(&members)
+    .execute(&mut *tx)
+    .await?;
+    let added = sqlx::query(
+        r#"INSERT INTO classroom_students (classroom_id, tenant_id, player_id)
+        SELECT $1, $2, unnest($3::uuid[])
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(classroom_id)
+    .bind(tid)
+    .bind(&members)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({
+        "added": added.rows_affected(),
+        "removed": removed.rows_affected(),
+        "notMembers": not_members,
+    })))
+}
+
+pub async fn remove_student(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id, student_id)): Path<(String, Uuid, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    let removed = sqlx::query(
+        "DELETE FROM classroom_students WHERE classroom_id = $1 AND player_id = $2 AND tenant_id = $3",
+    )
+    .bind(classroom_id)
+    .bind(student_id)
+    .bind(tid)
+    .execute(&state.db)
+    .await?;
+    if removed.rows_affected() == 0 {
+        return Err(AppError::NotFound("Student not in classroom".into()));
+    }
+
+    Ok(Json(json!({"success": true})))
+}
+
+pub async fn create_assignment(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+    Json(body): Json<CreateAssignmentRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    if body.game_id.is_empty() {
+        return Err(AppError::BadRequest("gameId required".into()));
+    }
+    if body.target_score <= 0 {
+        return Err(AppError::BadRequest("targetScore must be positive".into()));
+    }
+    if body.due_at <= chrono::Utc::now() {
+        return Err(AppError::BadRequest("dueAt must be in the future".into()));
+    }
+    let title = match body.title.as_deref().map(str::trim) {
+        Some(t) if !t.is_empty() => t.to_string(),
+        _ => format!("Reach {} points in {}", body.target_score, body.game_id),
+    };
+
+    let assignment = sqlx::query_as::<_, Assignment>(
+        r#"INSERT INTO assignments (tenant_id, classroom_id, game_id, title, target_score, due_at, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, classroom_id, game_id, title, target_score, due_at, created_by, created_at"#,
+    )
+    .bind(tid)
+    .bind(classroom_id)
+    .bind(&body.game_id)
+    .bind(&title)
+    .bind(body.target_score)
+    .bind(body.due_at)
+    .bind(player.id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "assignment": assignment })))
+}
+
+/// Students get their own progress on each assignment; teachers get how
+/// many students have completed it.
+pub async fn list_assignments(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let is_teacher = authorize_classroom(&state.db, &id, classroom_id, player.id, tid).await?;
+    let classroom = fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    let assignments = fetch_assignments(&state.db, classroom_id, tid).await?;
+    let progress = assignment_progress(
+        &state.db,
+        classroom_id,
+        tid,
+        if is_teacher { None } else { Some(player.id) },
+    )
+    .await?;
+
+    let list: Vec<Value> = assignments
+        .iter()
+        .map(|a| {
+            let mut entry = serde_json::to_value(a).unwrap_or_default();
+            let rows = progress.iter().filter(|p| p.assignment_id == a.id);
+            if is_teacher {
+                let completed = rows.filter(|p| p.completed_at.is_some()).count();
+                entry["completedCount"] = json!(completed);
+                entry["studentCount"] = json!(classroom.student_count);
+            } else if let Some(mine) = rows.into_iter().next() {
+                entry["progress"] = progress_json(mine, a.due_at);
+            }
+            entry
+        })
+        .collect();
+
+    Ok(Json(json!({ "assignments": list })))
+}
+
+pub async fn delete_assignment(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id, assignment_id)): Path<(String, Uuid, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    let deleted = sqlx::query("DELETE FROM assignments WHERE id = $1 AND classroom_id = $2 AND tenant_id = $3")
+        .bind(assignment_id)
+        .bind(classroom_id)
+        .bind(tid)
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Assignment not found".into()));
+    }
+
     Ok(Json(json!({"success": true})))
 }
+
+/// Teacher progress report: every rostered student against every assignment,
+/// with per-assignment and per-student completion totals.
+pub async fn classroom_report(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, classroom_id)): Path<(String, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    let classroom = fetch_classroom(&state.db, &id, classroom_id, tid).await?;
+
+    let roster = roster(&state.db, classroom_id, tid).await?;
+    let assignments = fetch_assignments(&state.db, classroom_id, tid).await?;
+    let progress = assignment_progress(&state.db, classroom_id, tid, None).await?;
+
+    let due_of = |assignment_id: Uuid| assignments.iter().find(|a| a.id == assignment_id).map(|a| a.due_at);
+
+    let students: Vec<Value> = roster
+        .iter()
+        .map(|(student_id, name, added_at)| {
+            let rows: Vec<Value> = progress
+                .iter()
+                .filter(|p| p.player_id == *student_id)
+                .filter_map(|p| due_of(p.assignment_id).map(|due| progress_json(p, due)))
+                .collect();
+            let completed = rows.iter().filter(|r| r["status"] == "completed").count();
+            let late = rows.iter().filter(|r| r["status"] == "late").count();
+            json!({
+                "playerId": student_id, "displayName": name, "addedAt": added_at,
+                "completed": completed, "late": late, "assignments": rows,
+            })
+        })
+        .collect();
+
+    let summary: Vec<Value> = assignments
+        .iter()
+        .map(|a| {
+            let rows: Vec<&AssignmentProgress> = progress.iter().filter(|p| p.assignment_id == a.id).collect();
+            let count = |status: &str| rows.iter().filter(|p| progress_status(p, a.due_at) == status).count();
+            json!({
+                "id": a.id, "title": a.title, "gameId": a.game_id,
+                "targetScore": a.target_score, "dueAt": a.due_at,
+                "completed": count("completed"), "late": count("late"),
+                "missed": count("missed"), "inProgress": count("in_progress"),
+                "notStarted": count("not_started"),
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "classroom": classroom,
+        "assignments": summary,
+        "students": students,
+        "generatedAt": chrono::Utc::now(),
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// Report exports
+// ---------------------------------------------------------------------------
+
+/// Pending or processing export jobs a teacher may have at once.
+const MAX_ACTIVE_REPORT_JOBS: i64 = 3;
+
+/// Queues a CSV or PDF export of a classroom's progress report. Poll
+/// `GET /reports/:jobId` for the download.
+pub async fn request_report(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<CreateReportRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_teacher(&state.db, &id, player.id, tid).await?;
+    fetch_classroom(&state.db, &id, body.classroom_id, tid).await?;
+
+    if !report_export::FORMATS.contains(&body.format.as_str()) {
+        return Err(AppError::BadRequest("format must be csv or pdf".into()));
+    }
+
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM report_jobs WHERE requested_by = $1 AND tenant_id = $2 AND status IN ('pending', 'processing')",
+    )
+    .bind(player.id)
+    .bind(tid)
+    .fetch_one(&state.db)
+    .await?;
+    if active >= MAX_ACTIVE_REPORT_JOBS {
+        return Err(AppError::RateLimited);
+    }
+
+    let job_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO report_jobs (tenant_id, organisation_id, classroom_id, requested_by, format)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id"#,
+    )
+    .bind(tid)
+    .bind(&id)
+    .bind(body.classroom_id)
+    .bind(player.id)
+    .bind(&body.format)
+    .fetch_one(&state.db)
+    .await?;
+
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        report_export::process_job(&db, job_id).await;
+    });
+
+    Ok(Json(json!({"jobId": job_id, "status": "pending"})))
+}
+
+pub async fn get_report(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let job = sqlx::query_as::<_, ReportJob>(
+        r#"SELECT id, status, format, classroom_id, download_url, error, completed_at, expires_at
+        FROM report_jobs WHERE id = $1 AND requested_by = $2 AND tenant_id = $3"#,
+    )
+    .bind(job_id)
+    .bind(player.id)
+    .bind(&tenant.0 .0)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match job {
+        Some(mut job) => {
+            if job.expires_at.is_some_and(|e| e < chrono::Utc::now()) {
+                job.status = "expired".into();
+                job.download_url = None;
+            }
+            Ok(Json(json!(job)))
+        }
+        None => Err(AppError::NotFound("Report job not found".into())),
+    }
+}
+
+/// Owners and admins may import rosters, as may API keys with
+/// `roster:write`. Returns who is importing: `None` for a key.
+async fn require_roster_admin(
+    db: &sqlx::PgPool,
+    org_id: &str,
+    player: Option<axum::Extension<AuthPlayer>>,
+    key: Option<axum::Extension<ApiKeyAuth>>,
+    tenant_id: &str,
+) -> AppResult<Option<Uuid>> {
+    if key.is_some() {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM organisations WHERE id = $1 AND tenant_id = $2)")
+            .bind(org_id)
+            .bind(tenant_id)
+            .fetch_one(db)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("Organisation not found".into()));
+        }
+        return Ok(None);
+    }
+    let player = player.ok_or_else(|| AppError::Unauthorized("No token provided".into()))?;
+    match member_role(db, org_id, player.id, tenant_id).await?.as_deref() {
+        Some("owner") | Some("admin") => Ok(Some(player.id)),
+        _ => Err(AppError::Forbidden("Must be org owner or admin".into())),
+    }
+}
+
+/// Queues an import of CSV rosters (see [`roster_import`](crate::services::roster_import)). Poll
+/// `GET /organisations/:id/roster-imports/:importId` for its report.
+pub async fn import_roster(
+    State(state): State<AppState>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    key: Option<axum::Extension<ApiKeyAuth>>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<String>,
+    Json(body): Json<RosterImportRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let requested_by = require_roster_admin(&state.db, &id, player, key, tid).await?;
+
+    if body.students.trim().is_empty() {
+        return Err(AppError::BadRequest("students is required".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    // One import at a time, so imports apply in the order they were made.
+    sqlx::query("SELECT id FROM organisations WHERE id = $1 AND tenant_id = $2 FOR UPDATE")
+        .bind(&id)
+        .bind(tid)
+        .execute(&mut *tx)
+        .await?;
+    let running: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM roster_imports
+        WHERE organisation_id = $1 AND tenant_id = $2 AND status IN ('pending', 'processing'))"#,
+    )
+    .bind(&id)
+    .bind(tid)
+    .fetch_one(&mut *tx)
+    .await?;
+    if running {
+        return Err(AppError::Conflict("A roster import is already running".into()));
+    }
+
+    let import_id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO roster_imports (tenant_id, organisation_id, requested_by, dry_run, students_csv, classes_csv)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id"#,
+    )
+    .bind(tid)
+    .bind(&id)
+    .bind(requested_by)
+    .bind(body.dry_run.unwrap_or(false))
+    .bind(&body.students)
+    .bind(&body.classes)
+    .fetch_one(&mut *tx)
+    .await?;
+    jobs::enqueue(&mut *tx, jobs::IMPORT_ROSTER, json!({ "importId": import_id }), None).await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "importId": import_id, "status": "pending" })))
+}
+
+pub async fn get_roster_import(
+    State(state): State<AppState>,
+    player: Option<axum::Extension<AuthPlayer>>,
+    key: Option<axum::Extension<ApiKeyAuth>>,
+    tenant: axum::Extension<TenantId>,
+    Path((id, import_id)): Path<(String, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    require_roster_admin(&state.db, &id, player, key, tid).await?;
+
+    let import: RosterImport = sqlx::query_as(
+        r#"SELECT id, status, dry_run, report, error, created_at, completed_at
+        FROM roster_imports WHERE id = $1 AND organisation_id = $2 AND tenant_id = $3"#,
+    )
+    .bind(import_id)
+    .bind(&id)
+    .bind(tid)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Roster import not found".into()))?;
+
+    Ok(Json(json!(import)))
+}
@@ -1,16 +1,21 @@
 use axum::{extract::State, Json};
+use rand::Rng;
 use serde_json::{json, Value};
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::localization::LocaleInfo;
 use crate::middleware::tenant::TenantId;
 use crate::models::player::*;
+use crate::routes::presence;
+use crate::services::{cosmetics, parental_consent, playtime, privacy, translations};
 use crate::AppState;
 
 pub async fn get_profile(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
 
@@ -34,12 +39,18 @@ pub async fn get_profile(
     .fetch_one(&state.db)
     .await?;
 
+    let t = translations::for_locale(&state.db, &state.cache, tenant_id, &locale.locale).await?;
+    let cosmetics = cosmetics::equipped_items(&state.db, tenant_id, player.id, &t).await?;
+
     Ok(Json(json!({
         "player": PlayerPublic::from(&p),
+        "cosmetics": cosmetics,
         "email": p.email,
         "isGuest": p.is_guest,
         "totalPlayTime": p.total_play_time,
         "lastLoginAt": p.last_login_at,
+        "birthYear": p.birth_year,
+        "consentStatus": p.consent_status,
         "stats": {
             "gamesStarted": stats.0,
             "gamesCompleted": stats.1,
@@ -53,25 +64,39 @@ pub async fn update_profile(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
     Json(body): Json<ProfileUpdateRequest>,
 ) -> AppResult<Json<Value>> {
     let tenant_id = &tenant.0 .0;
 
     let mut updates = Vec::new();
-    let mut params: Vec<String> = Vec::new();
+    let mut params: Vec<Option<String>> = Vec::new();
 
     if let Some(ref name) = body.display_name {
         updates.push(format!("display_name = ${}", params.len() + 3));
-        params.push(name.clone());
+        params.push(Some(name.clone()));
     }
     if let Some(ref avatar) = body.avatar_character {
         updates.push(format!("avatar_character = ${}", params.len() + 3));
-        params.push(avatar.clone());
+        params.push(Some(avatar.clone()));
+    }
+
+    // Cosmetics must be owned and fit the slot; "" unequips
+    let equips = [&body.avatar_frame, &body.title, &body.banner];
+    for (slot, item) in cosmetics::SLOTS.iter().zip(equips) {
+        let Some(item_id) = item else { continue };
+        if !item_id.is_empty() {
+            cosmetics::check_equippable(&state.db, tenant_id, player.id, slot, item_id).await?;
+        }
+        updates.push(format!("{} = ${}", slot.column, params.len() + 3));
+        params.push(Some(item_id.clone()).filter(|id| !id.is_empty()));
     }
 
     if updates.is_empty() {
         return Ok(Json(json!({"message": "No fields to update"})));
     }
+    // Offline syncs older than this edit lose (see routes::sync)
+    updates.push("profile_updated_at = NOW()".to_string());
 
     let sql = format!(
         "UPDATE players SET {} WHERE id = $1 AND tenant_id = $2 RETURNING id, display_name, avatar_character, total_score, games_played",
@@ -89,6 +114,9 @@ pub async fn update_profile(
 
     let (id, name, avatar, score, played) = query.fetch_one(&state.db).await?;
 
+    let t = translations::for_locale(&state.db, &state.cache, tenant_id, &locale.locale).await?;
+    let cosmetics = cosmetics::equipped_items(&state.db, tenant_id, player.id, &t).await?;
+
     Ok(Json(json!({
         "player": {
             "playerId": id,
@@ -96,7 +124,8 @@ pub async fn update_profile(
             "avatarCharacter": avatar,
             "totalScore": score,
             "gamesPlayed": played,
-        }
+        },
+        "cosmetics": cosmetics,
     })))
 }
 
@@ -130,25 +159,375 @@ pub async fn get_all_progress(
     Ok(Json(json!({ "progress": progress })))
 }
 
+/// achievement_id, game_id, earned_at, name, description, icon
+type AchievementRow = (String, Option<String>, chrono::DateTime<chrono::Utc>, Option<String>, Option<String>, Option<String>);
+
+/// Earned achievements, with names and descriptions in the request locale.
 pub async fn get_achievements(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
+    locale: axum::Extension<LocaleInfo>,
 ) -> AppResult<Json<Value>> {
-    let rows: Vec<(String, Option<String>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
-        "SELECT pa.achievement_id, pa.game_id, pa.earned_at FROM player_achievements pa WHERE pa.player_id = $1 AND pa.tenant_id = $2",
+    let tid = &tenant.0 .0;
+    let rows: Vec<AchievementRow> = sqlx::query_as(
+        r#"SELECT pa.achievement_id, pa.game_id, pa.earned_at, a.name, a.description, a.icon
+        FROM player_achievements pa
+        LEFT JOIN achievements a ON a.id = pa.achievement_id AND a.tenant_id = pa.tenant_id
+        WHERE pa.player_id = $1 AND pa.tenant_id = $2"#,
     )
     .bind(player.id)
-    .bind(&tenant.0 .0)
+    .bind(tid)
     .fetch_all(&state.db)
     .await?;
+    let t = translations::for_locale(&state.db, &state.cache, tid, &locale.locale).await?;
 
     let achievements: Vec<Value> = rows
         .iter()
-        .map(|(aid, gid, earned)| {
-            json!({"achievementId": aid, "gameId": gid, "earnedAt": earned})
+        .map(|(aid, gid, earned, name, description, icon)| {
+            let name = name.as_deref().map(|n| t.text("achievement", &format!("{}.name", aid), n));
+            let description = description
+                .as_deref()
+                .map(|d| t.text("achievement", &format!("{}.description", aid), d));
+            json!({"achievementId": aid, "gameId": gid, "earnedAt": earned, "name": name, "description": description, "icon": icon})
         })
         .collect();
 
     Ok(Json(json!({ "achievements": achievements })))
 }
+
+pub async fn get_privacy(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let settings = privacy::get(&state.db, &tenant.0 .0, player.id).await?;
+    Ok(Json(json!({ "privacy": settings })))
+}
+
+/// Changes any of the privacy settings. Friends see the player go offline
+/// (or come back) straight away when `showOnline` changes.
+pub async fn update_privacy(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<PrivacyUpdateRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if let Some(v) = body.profile_visibility.as_deref() {
+        if !privacy::PROFILE_VISIBILITIES.contains(&v) {
+            return Err(AppError::BadRequest(format!("profileVisibility must be one of {}", privacy::PROFILE_VISIBILITIES.join(", "))));
+        }
+    }
+    if let Some(v) = body.friend_requests.as_deref() {
+        if !privacy::FRIEND_REQUEST_POLICIES.contains(&v) {
+            return Err(AppError::BadRequest(format!("friendRequests must be one of {}", privacy::FRIEND_REQUEST_POLICIES.join(", "))));
+        }
+    }
+
+    let before = privacy::get(&state.db, tid, player.id).await?;
+    let settings: PrivacySettings = sqlx::query_as(
+        r#"UPDATE players SET
+            profile_visibility = COALESCE($3, profile_visibility),
+            friend_requests = COALESCE($4, friend_requests),
+            show_online = COALESCE($5, show_online),
+            leaderboard_anonymous = COALESCE($6, leaderboard_anonymous)
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING profile_visibility, friend_requests, show_online, leaderboard_anonymous"#,
+    )
+    .bind(player.id)
+    .bind(tid)
+    .bind(&body.profile_visibility)
+    .bind(&body.friend_requests)
+    .bind(body.show_online)
+    .bind(body.leaderboard_anonymous)
+    .fetch_one(&state.db)
+    .await?;
+
+    if settings.show_online != before.show_online {
+        presence::rebroadcast(&state, tid, player.id).await;
+    }
+
+    Ok(Json(json!({ "privacy": settings })))
+}
+
+pub async fn get_playtime(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let budget = playtime::budget(&state.db, &tenant.0 .0, player.id).await?;
+    Ok(Json(json!({ "playtime": budget.json() })))
+}
+
+/// Sets the player's own daily limit. An organisation's limit still
+/// applies if it's lower.
+pub async fn update_playtime(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<PlaytimeLimitRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    playtime::validate_limit(body.daily_limit_minutes)?;
+    sqlx::query("UPDATE players SET daily_playtime_limit_mins = $3 WHERE id = $1 AND tenant_id = $2")
+        .bind(player.id)
+        .bind(tid)
+        .bind(body.daily_limit_minutes)
+        .execute(&state.db)
+        .await?;
+
+    let budget = playtime::budget(&state.db, tid, player.id).await?;
+    Ok(Json(json!({ "playtime": budget.json() })))
+}
+
+pub async fn get_consent(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let status: String = sqlx::query_scalar("SELECT consent_status FROM players WHERE id = $1 AND tenant_id = $2")
+        .bind(player.id)
+        .bind(tid)
+        .fetch_one(&state.db)
+        .await?;
+    let latest: Option<(String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        r#"SELECT parent_email, created_at FROM parental_consents
+        WHERE tenant_id = $1 AND player_id = $2 ORDER BY created_at DESC LIMIT 1"#,
+    )
+    .bind(tid)
+    .bind(player.id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(Json(json!({
+        "status": status,
+        "parentEmail": latest.as_ref().map(|(email, _)| email),
+        "requestedAt": latest.as_ref().map(|(_, at)| at),
+    })))
+}
+
+/// Sends the parental consent email again, optionally to a different
+/// parent address.
+pub async fn resend_consent(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<ConsentResendRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let (status, display_name, email): (String, String, Option<String>) =
+        sqlx::query_as("SELECT consent_status, display_name, email FROM players WHERE id = $1 AND tenant_id = $2")
+            .bind(player.id)
+            .bind(tid)
+            .fetch_one(&state.db)
+            .await?;
+    if status != "pending" && status != "denied" {
+        return Err(AppError::BadRequest("This account doesn't need parental consent".into()));
+    }
+    if parental_consent::recently_requested(&state.db, tid, player.id).await? {
+        return Err(AppError::RateLimited);
+    }
+
+    let parent_email = match body.parent_email.as_deref().map(str::trim) {
+        Some(email) => email.to_string(),
+        None => sqlx::query_scalar(
+            "SELECT parent_email FROM parental_consents WHERE tenant_id = $1 AND player_id = $2 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(tid)
+        .bind(player.id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("parentEmail required".into()))?,
+    };
+    if !parental_consent::is_valid_email(&parent_email) {
+        return Err(AppError::BadRequest("parentEmail is not a valid email address".into()));
+    }
+    if email.is_some_and(|e| e.eq_ignore_ascii_case(&parent_email)) {
+        return Err(AppError::BadRequest("parentEmail must be a parent's address, not the player's".into()));
+    }
+
+    parental_consent::request(&state, tid, player.id, &display_name, &parent_email).await?;
+    state.cache.del(&crate::routes::admin::user_detail_key(tid, player.id)).await;
+    Ok(Json(json!({ "status": "pending", "parentEmail": parent_email })))
+}
+
+/// Handoff codes stay claimable for 15 minutes.
+const HANDOFF_TTL_MINUTES: i64 = 15;
+/// Upper bound on a parked snapshot, serialized.
+const HANDOFF_MAX_SNAPSHOT_BYTES: usize = 64 * 1024;
+/// Unambiguous alphabet (no 0/O, 1/I/L) so codes can be read off a screen.
+const HANDOFF_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+const HANDOFF_CODE_LEN: usize = 6;
+
+fn generate_handoff_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..HANDOFF_CODE_LEN)
+        .map(|_| HANDOFF_ALPHABET[rng.gen_range(0..HANDOFF_ALPHABET.len())] as char)
+        .collect()
+}
+
+pub async fn create_handoff(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<HandoffRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    if body.game_id.is_empty() {
+        return Err(AppError::BadRequest("gameId is required".into()));
+    }
+    if !body.snapshot.is_object() {
+        return Err(AppError::BadRequest("snapshot must be an object".into()));
+    }
+    if body.snapshot.to_string().len() > HANDOFF_MAX_SNAPSHOT_BYTES {
+        return Err(AppError::BadRequest("Snapshot too large".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    // Only one run can be parked at a time; a new handoff supersedes the old
+    sqlx::query(
+        r#"UPDATE session_handoffs SET status = 'superseded'
+        WHERE player_id = $1 AND tenant_id = $2 AND status = 'parked'"#,
+    )
+    .bind(player.id)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(HANDOFF_TTL_MINUTES);
+    let mut code = generate_handoff_code();
+    let mut attempts = 0;
+    loop {
+        let taken: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM session_handoffs WHERE tenant_id = $1 AND code = $2 AND status = 'parked')",
+        )
+        .bind(tenant_id)
+        .bind(&code)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !taken {
+            break;
+        }
+        attempts += 1;
+        if attempts >= 5 {
+            return Err(AppError::Internal("Could not allocate handoff code".into()));
+        }
+        code = generate_handoff_code();
+    }
+
+    sqlx::query(
+        r#"INSERT INTO session_handoffs (tenant_id, player_id, code, game_id, snapshot, status, source_device, expires_at)
+        VALUES ($1, $2, $3, $4, $5, 'parked', $6, $7)"#,
+    )
+    .bind(tenant_id)
+    .bind(player.id)
+    .bind(&code)
+    .bind(&body.game_id)
+    .bind(&body.snapshot)
+    .bind(&body.device_id)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(json!({
+        "code": code,
+        "gameId": body.game_id,
+        "expiresAt": expires_at,
+    })))
+}
+
+pub async fn get_handoff(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let handoff: Option<SessionHandoff> = sqlx::query_as(
+        r#"SELECT * FROM session_handoffs
+        WHERE player_id = $1 AND tenant_id = $2
+        ORDER BY created_at DESC LIMIT 1"#,
+    )
+    .bind(player.id)
+    .bind(&tenant.0 .0)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match handoff {
+        Some(h) => {
+            let status = if h.status == "parked" && h.expires_at < chrono::Utc::now() {
+                "expired".to_string()
+            } else {
+                h.status
+            };
+            Ok(Json(json!({
+                "handoff": {
+                    "code": h.code,
+                    "gameId": h.game_id,
+                    "status": status,
+                    "expiresAt": h.expires_at,
+                    "claimedAt": h.claimed_at,
+                }
+            })))
+        }
+        None => Ok(Json(json!({"handoff": null}))),
+    }
+}
+
+pub async fn claim_handoff(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<HandoffClaimRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+    let code = body.code.trim().to_uppercase();
+
+    let mut tx = state.db.begin().await?;
+
+    let handoff: Option<SessionHandoff> = sqlx::query_as(
+        r#"SELECT * FROM session_handoffs
+        WHERE tenant_id = $1 AND code = $2 AND status = 'parked'
+        FOR UPDATE"#,
+    )
+    .bind(tenant_id)
+    .bind(&code)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    // Codes are only redeemable by the player who parked the run
+    let handoff = match handoff {
+        Some(h) if h.player_id == player.id => h,
+        _ => return Err(AppError::NotFound("Handoff code not found".into())),
+    };
+
+    if handoff.expires_at < chrono::Utc::now() {
+        sqlx::query("UPDATE session_handoffs SET status = 'expired' WHERE id = $1")
+            .bind(handoff.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Err(AppError::BadRequest("Handoff code has expired".into()));
+    }
+
+    sqlx::query(
+        r#"UPDATE session_handoffs SET status = 'claimed', claimed_at = NOW(), target_device = $2
+        WHERE id = $1"#,
+    )
+    .bind(handoff.id)
+    .bind(&body.device_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(json!({
+        "gameId": handoff.game_id,
+        "snapshot": handoff.snapshot,
+        "parkedAt": handoff.created_at,
+    })))
+}
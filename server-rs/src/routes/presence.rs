@@ -1,16 +1,29 @@
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    response::Response,
     Json,
 };
+use futures_util::{SinkExt, StreamExt};
 use serde_json::{json, Value};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
-use crate::middleware::auth::AuthPlayer;
+use crate::middleware::auth::{is_revoked, verify_token, AuthPlayer};
 use crate::middleware::tenant::TenantId;
-use crate::models::multiplayer::PresenceUpdateRequest;
+use crate::models::multiplayer::{PresenceUpdateRequest, SocketAuthQuery};
+use crate::services::{playtime, privacy};
 use crate::AppState;
 
+const VALID_STATUSES: [&str; 5] = ["online", "in_game", "in_lobby", "away", "offline"];
+
+/// A player not seen for this long is reported offline regardless of their
+/// stored status (covers clients that vanished without closing cleanly).
+const STALE_AFTER_SECS: i64 = 300;
+
 pub async fn get_my_presence(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -38,11 +51,44 @@ pub async fn update_presence(
     tenant: axum::Extension<TenantId>,
     Json(body): Json<PresenceUpdateRequest>,
 ) -> AppResult<Json<Value>> {
-    let valid_statuses = ["online", "in_game", "in_lobby", "away", "offline"];
-    if !valid_statuses.contains(&body.status.as_str()) {
+    set_status(&state, &tenant.0 .0, player.id, &body).await?;
+    Ok(Json(json!({"success": true})))
+}
+
+/// Stores a status change and pushes it to the player's friends. Starting
+/// a game (going `in_game`, or switching games) needs playtime left today.
+async fn set_status(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    body: &PresenceUpdateRequest,
+) -> AppResult<()> {
+    if !VALID_STATUSES.contains(&body.status.as_str()) {
         return Err(AppError::BadRequest("Invalid status".into()));
     }
 
+    let previous: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT status, current_game_id FROM player_presence WHERE player_id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let was_playing = previous.as_ref().is_some_and(|(status, _)| status == "in_game");
+    if was_playing {
+        // Count the time since the last heartbeat before it stops counting
+        playtime::tick(&state.db, tenant_id, player_id).await?;
+    }
+    if body.status == "in_game" {
+        let same_game = previous.as_ref().is_some_and(|(_, game)| was_playing && *game == body.current_game_id);
+        if !same_game {
+            playtime::check_can_start(&state.db, tenant_id, player_id).await?;
+        }
+        if !was_playing {
+            playtime::resume(&state.db, tenant_id, player_id).await?;
+        }
+    }
+
     sqlx::query(
         r#"INSERT INTO player_presence (player_id, tenant_id, status, current_game_id, current_room_id, last_seen_at, connected_at, server_node)
         VALUES ($1, $2, $3, $4, $5, NOW(), NOW(), 'api')
@@ -52,17 +98,27 @@ pub async fn update_presence(
             current_room_id = EXCLUDED.current_room_id,
             last_seen_at = NOW()"#,
     )
-    .bind(player.id)
-    .bind(&tenant.0 .0)
+    .bind(player_id)
+    .bind(tenant_id)
     .bind(&body.status)
     .bind(&body.current_game_id)
     .bind(&body.current_room_id)
     .execute(&state.db)
     .await?;
 
-    Ok(Json(json!({"success": true})))
+    broadcast_presence(
+        state,
+        tenant_id,
+        player_id,
+        &body.status,
+        body.current_game_id.as_deref(),
+    )
+    .await;
+    Ok(())
 }
 
+/// Legacy polling keep-alive. Clients on the presence socket don't need it.
+/// Counts playtime like the socket's ping, and returns the player's budget.
 pub async fn heartbeat(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -75,16 +131,24 @@ pub async fn heartbeat(
     .bind(&tenant.0 .0)
     .execute(&state.db)
     .await?;
+    playtime::tick(&state.db, &tenant.0 .0, player.id).await?;
+    let budget = playtime::budget(&state.db, &tenant.0 .0, player.id).await?;
 
-    Ok(Json(json!({"success": true})))
+    Ok(Json(json!({"success": true, "playtime": budget.json()})))
 }
 
+/// Another player's presence. Players who hide their online status are
+/// always reported offline.
 pub async fn get_player_presence(
     State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
     tenant: axum::Extension<TenantId>,
     Path(id): Path<String>,
 ) -> AppResult<Json<Value>> {
     let pid = Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("Invalid player ID".into()))?;
+    if pid != player.id && !privacy::shows_online(&state.db, &tenant.0 .0, pid).await? {
+        return Ok(Json(json!({"status": "offline"})));
+    }
 
     let row: Option<(String, Option<String>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
         "SELECT status, current_game_id, last_seen_at FROM player_presence WHERE player_id = $1 AND tenant_id = $2",
@@ -99,9 +163,308 @@ pub async fn get_player_presence(
             // If last seen > 5 min ago, consider offline
             let now = chrono::Utc::now();
             let diff = now.signed_duration_since(seen);
-            let effective_status = if diff.num_seconds() > 300 { "offline" } else { &status };
+            let effective_status = if diff.num_seconds() > STALE_AFTER_SECS { "offline" } else { &status };
             Ok(Json(json!({"status": effective_status, "currentGameId": gid, "lastSeenAt": seen})))
         }
         None => Ok(Json(json!({"status": "offline"}))),
     }
 }
+
+// ---------------------------------------------------------------------------
+// Presence socket
+// ---------------------------------------------------------------------------
+
+/// `GET /presence/ws?token=...` — holding this socket open keeps the player
+/// online. Friends are pushed `presence` events through the realtime gateway
+/// when the player connects, changes status, or goes offline (after
+/// `PRESENCE_OFFLINE_GRACE_SEC` with no open socket).
+pub async fn presence_socket(
+    State(state): State<AppState>,
+    Query(query): Query<SocketAuthQuery>,
+    ws: WebSocketUpgrade,
+) -> AppResult<Response> {
+    let claims = verify_token(&query.token, &state.config.jwt)?;
+    if claims.token_type.as_deref() == Some("refresh") {
+        return Err(AppError::Unauthorized("Access token required".into()));
+    }
+    if is_revoked(&state.cache, &claims).await {
+        return Err(AppError::Unauthorized("Token revoked".into()));
+    }
+    let player_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Unauthorized("Invalid token subject".into()))?;
+    let tenant_id = claims.tenant_id;
+
+    Ok(ws
+        .max_message_size(64 * 1024)
+        .on_upgrade(move |socket| run_presence_socket(state, tenant_id, player_id, socket)))
+}
+
+async fn run_presence_socket(state: AppState, tenant_id: String, player_id: Uuid, socket: WebSocket) {
+    let (conn_id, mut events, first) = state.realtime.register(&tenant_id, player_id).await;
+    let (mut sink, mut stream) = socket.split();
+
+    if first {
+        if let Err(e) = go_online(&state, &tenant_id, player_id).await {
+            tracing::warn!("Presence connect failed for {}: {}", player_id, e);
+        }
+    }
+
+    let connected = json!({"type": "connected", "playerId": player_id});
+    if sink.send(Message::Text(connected.to_string())).await.is_ok() {
+        let ping_every = Duration::from_secs(state.config.presence.ping_interval_secs.max(5));
+        let mut ping = tokio::time::interval(ping_every);
+        ping.tick().await;
+        let mut last_heard = Instant::now();
+
+        loop {
+            tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        last_heard = Instant::now();
+                        let reply = handle_socket_message(&state, &tenant_id, player_id, &text).await;
+                        if let Some(reply) = reply {
+                            if sink.send(Message::Text(reply.to_string())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => last_heard = Instant::now(),
+                },
+                Some(event) = events.recv() => {
+                    if sink.send(Message::Text(event.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ping.tick() => {
+                    // No pong or message for two intervals: the client is gone
+                    if last_heard.elapsed() > ping_every * 2 {
+                        break;
+                    }
+                    if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                        break;
+                    }
+                    let _ = sqlx::query(
+                        "UPDATE player_presence SET last_seen_at = NOW() WHERE player_id = $1 AND tenant_id = $2",
+                    )
+                    .bind(player_id)
+                    .bind(&tenant_id)
+                    .execute(&state.db)
+                    .await;
+                    if let Some(event) = playtime_event(&state, &tenant_id, player_id).await {
+                        if sink.send(Message::Text(event.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let remaining = state.realtime.unregister(&tenant_id, player_id, conn_id).await;
+    if remaining == 0 {
+        crate::routes::multiplayer::stop_spectating(&state, &tenant_id, player_id).await;
+        let grace = Duration::from_secs(state.config.presence.offline_grace_secs);
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            // Reconnected (e.g. page reload) within the grace period
+            if state.realtime.is_connected(&tenant_id, player_id).await {
+                return;
+            }
+            if let Err(e) = go_offline(&state, &tenant_id, player_id).await {
+                tracing::warn!("Presence disconnect failed for {}: {}", player_id, e);
+            }
+        });
+    }
+}
+
+/// Counts playtime on a socket ping. Players with a limit are sent a
+/// `playtime` event with their budget, for the engine's
+/// `set_playtime_remaining`.
+async fn playtime_event(state: &AppState, tenant_id: &str, player_id: Uuid) -> Option<Value> {
+    let budget = async {
+        playtime::tick(&state.db, tenant_id, player_id).await?;
+        playtime::budget(&state.db, tenant_id, player_id).await
+    };
+    match budget.await {
+        Ok(budget) if budget.limit_mins.is_some() => {
+            let mut event = budget.json();
+            event["type"] = json!("playtime");
+            Some(event)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Playtime update failed for {}: {}", player_id, e);
+            None
+        }
+    }
+}
+
+/// Client messages: `{"type":"status","status":"in_game","currentGameId":"..."}`,
+/// `{"type":"ping"}`, the spectator messages handled by
+/// [`crate::routes::multiplayer::handle_spectator_message`], and chat
+/// (see [`crate::routes::chat::handle_chat_message`]).
+async fn handle_socket_message(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    text: &str,
+) -> Option<Value> {
+    let msg: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(_) => return Some(json!({"type": "error", "message": "Invalid JSON"})),
+    };
+
+    match msg["type"].as_str() {
+        Some("ping") => Some(json!({"type": "pong"})),
+        Some("status") => {
+            let body: PresenceUpdateRequest = match serde_json::from_value(msg) {
+                Ok(b) => b,
+                Err(_) => return Some(json!({"type": "error", "message": "Invalid status message"})),
+            };
+            match set_status(state, tenant_id, player_id, &body).await {
+                Ok(()) => None,
+                Err(AppError::BadRequest(m) | AppError::Forbidden(m)) => Some(json!({"type": "error", "message": m})),
+                Err(e) => {
+                    tracing::warn!("Presence status update failed for {}: {}", player_id, e);
+                    Some(json!({"type": "error", "message": "Status update failed"}))
+                }
+            }
+        }
+        Some("spectate" | "stop_spectating" | "room_frame") => {
+            crate::routes::multiplayer::handle_spectator_message(state, tenant_id, player_id, &msg)
+                .await
+        }
+        Some("chat") => crate::routes::chat::handle_chat_message(state, tenant_id, player_id, &msg).await,
+        _ => Some(json!({"type": "error", "message": "Unknown message type"})),
+    }
+}
+
+/// Marks the player online. Friends are only notified if they currently see
+/// the player as offline, so reconnects within the grace period stay quiet.
+async fn go_online(state: &AppState, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    let previous: Option<(String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT status, last_seen_at FROM player_presence WHERE player_id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let was_offline = match &previous {
+        Some((status, seen)) => {
+            status == "offline"
+                || chrono::Utc::now().signed_duration_since(*seen).num_seconds() > STALE_AFTER_SECS
+        }
+        None => true,
+    };
+
+    sqlx::query(
+        r#"INSERT INTO player_presence (player_id, tenant_id, status, last_seen_at, connected_at, server_node)
+        VALUES ($1, $2, 'online', NOW(), NOW(), 'ws')
+        ON CONFLICT (player_id, tenant_id) DO UPDATE SET
+            status = CASE WHEN $3 THEN 'online' ELSE player_presence.status END,
+            current_game_id = CASE WHEN $3 THEN NULL ELSE player_presence.current_game_id END,
+            current_room_id = CASE WHEN $3 THEN NULL ELSE player_presence.current_room_id END,
+            last_seen_at = NOW(),
+            connected_at = NOW(),
+            server_node = 'ws'"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(was_offline)
+    .execute(&state.db)
+    .await?;
+
+    if was_offline {
+        broadcast_presence(state, tenant_id, player_id, "online", None).await;
+    }
+    Ok(())
+}
+
+async fn go_offline(state: &AppState, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"UPDATE player_presence SET status = 'offline', current_game_id = NULL,
+            current_room_id = NULL, last_seen_at = NOW()
+        WHERE player_id = $1 AND tenant_id = $2"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .execute(&state.db)
+    .await?;
+
+    broadcast_presence(state, tenant_id, player_id, "offline", None).await;
+    Ok(())
+}
+
+/// Pushes the player's current presence to their friends again, e.g. after
+/// they changed whether it is shown.
+pub(crate) async fn rebroadcast(state: &AppState, tenant_id: &str, player_id: Uuid) {
+    let row: Option<(String, Option<String>, chrono::DateTime<chrono::Utc>)> = match sqlx::query_as(
+        "SELECT status, current_game_id, last_seen_at FROM player_presence WHERE player_id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => {
+            tracing::warn!("Presence rebroadcast failed for {}: {}", player_id, e);
+            return;
+        }
+    };
+    let (status, game_id) = match row {
+        Some((status, gid, seen))
+            if chrono::Utc::now().signed_duration_since(seen).num_seconds() <= STALE_AFTER_SECS =>
+        {
+            (status, gid)
+        }
+        _ => ("offline".to_string(), None),
+    };
+    broadcast_presence(state, tenant_id, player_id, &status, game_id.as_deref()).await;
+}
+
+/// Pushes a `presence` event to the player's accepted friends. Players who
+/// hide their online status are always announced as offline.
+async fn broadcast_presence(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    status: &str,
+    game_id: Option<&str>,
+) {
+    let (status, game_id) = match privacy::shows_online(&state.db, tenant_id, player_id).await {
+        Ok(true) => (status, game_id),
+        Ok(false) => ("offline", None),
+        Err(e) => {
+            tracing::warn!("Presence broadcast failed for {}: {}", player_id, e);
+            return;
+        }
+    };
+    let friends: Vec<Uuid> = match sqlx::query_scalar(
+        r#"SELECT CASE WHEN player_id = $1 THEN friend_id ELSE player_id END
+        FROM friendships
+        WHERE tenant_id = $2 AND status = 'accepted' AND (player_id = $1 OR friend_id = $1)"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::warn!("Presence broadcast failed for {}: {}", player_id, e);
+            return;
+        }
+    };
+
+    let event = json!({
+        "type": "presence",
+        "playerId": player_id,
+        "status": status,
+        "currentGameId": game_id,
+        "at": chrono::Utc::now(),
+    });
+    state.realtime.send_to_many(tenant_id, &friends, &event).await;
+}
@@ -0,0 +1,385 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::economy::*;
+use crate::routes::economy::TRADE_CURRENCIES;
+use crate::routes::leaderboards::PaginationQuery;
+use crate::AppState;
+
+/// Generated codes avoid 0/O and 1/I, which players mistype.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const GENERATED_CODE_LEN: usize = 10;
+const MAX_CODE_LEN: usize = 32;
+/// Most codes one request can generate.
+const MAX_CODES_PER_BATCH: u32 = 500;
+/// Maximum item + currency lines a code grants.
+const MAX_REWARD_LINES: usize = 10;
+
+type PgTx<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// Codes are matched case-insensitively, stored upper-case.
+fn normalize(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+fn random_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..GENERATED_CODE_LEN)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+async fn validate_rewards(tx: &mut PgTx<'_>, tid: &str, body: &CreatePromoCodesRequest) -> AppResult<()> {
+    if body.currency.is_empty() && body.items.is_empty() && body.battle_pass_tiers == 0 {
+        return Err(AppError::BadRequest("A code must grant currency, items or battle pass tiers".into()));
+    }
+    if body.currency.len() + body.items.len() > MAX_REWARD_LINES {
+        return Err(AppError::BadRequest(format!("A code grants at most {} currencies and items", MAX_REWARD_LINES)));
+    }
+    for (ct, amount) in &body.currency {
+        if !TRADE_CURRENCIES.contains(&ct.as_str()) {
+            return Err(AppError::BadRequest(format!("Unknown currency: {}", ct)));
+        }
+        if *amount <= 0 {
+            return Err(AppError::BadRequest("Currency amounts must be positive".into()));
+        }
+    }
+    if body.battle_pass_tiers < 0 {
+        return Err(AppError::BadRequest("battlePassTiers can't be negative".into()));
+    }
+
+    let mut seen = HashSet::new();
+    for item in &body.items {
+        if item.quantity <= 0 {
+            return Err(AppError::BadRequest("Item quantities must be positive".into()));
+        }
+        if !seen.insert(item.item_id.as_str()) {
+            return Err(AppError::BadRequest(format!("Item listed twice: {}", item.item_id)));
+        }
+    }
+    let ids: Vec<&str> = seen.into_iter().collect();
+    let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM store_items WHERE tenant_id = $1 AND id = ANY($2)")
+        .bind(tid)
+        .bind(&ids)
+        .fetch_one(&mut **tx)
+        .await?;
+    if found != ids.len() as i64 {
+        return Err(AppError::NotFound("Item not found".into()));
+    }
+    Ok(())
+}
+
+/// Admin: the tenant's codes, newest first.
+pub async fn list_codes(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<PaginationQuery>,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+    let offset = q.offset.unwrap_or(0).max(0);
+    let codes: Vec<PromoCode> = sqlx::query_as(
+        "SELECT * FROM promo_codes WHERE tenant_id = $1 ORDER BY created_at DESC, code LIMIT $2 OFFSET $3",
+    )
+    .bind(&tenant.0 .0)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(json!({ "codes": codes })))
+}
+
+/// Admin: create a code, or a batch of random ones sharing rewards and
+/// limits.
+pub async fn create_codes(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<CreatePromoCodesRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+
+    let count = body.count.unwrap_or(1);
+    if !(1..=MAX_CODES_PER_BATCH).contains(&count) {
+        return Err(AppError::BadRequest(format!("count must be between 1 and {}", MAX_CODES_PER_BATCH)));
+    }
+    let fixed = body.code.as_deref().map(normalize);
+    if let Some(ref code) = fixed {
+        if count != 1 {
+            return Err(AppError::BadRequest("count can't be combined with code".into()));
+        }
+        let valid = (3..=MAX_CODE_LEN).contains(&code.len())
+            && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(AppError::BadRequest(format!(
+                "code must be 3 to {} letters, digits, '-' or '_'",
+                MAX_CODE_LEN
+            )));
+        }
+    }
+    if body.max_redemptions.is_some_and(|m| m < 1) {
+        return Err(AppError::BadRequest("maxRedemptions must be at least 1".into()));
+    }
+    let per_player_limit = body.per_player_limit.unwrap_or(1);
+    if per_player_limit < 1 {
+        return Err(AppError::BadRequest("perPlayerLimit must be at least 1".into()));
+    }
+    if body.expires_at.is_some_and(|at| at <= chrono::Utc::now()) {
+        return Err(AppError::BadRequest("expiresAt must be in the future".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    validate_rewards(&mut tx, tid, &body).await?;
+
+    let currency = json!(body.currency);
+    let items = json!(body.items);
+    let mut created: Vec<PromoCode> = Vec::with_capacity(count as usize);
+    while created.len() < count as usize {
+        let code = fixed.clone().unwrap_or_else(random_code);
+        let row: Option<PromoCode> = sqlx::query_as(
+            r#"INSERT INTO promo_codes (tenant_id, code, currency, items, battle_pass_tiers, max_redemptions, per_player_limit, expires_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (tenant_id, code) DO NOTHING
+            RETURNING *"#,
+        )
+        .bind(tid)
+        .bind(&code)
+        .bind(&currency)
+        .bind(&items)
+        .bind(body.battle_pass_tiers)
+        .bind(body.max_redemptions)
+        .bind(per_player_limit)
+        .bind(body.expires_at)
+        .bind(player.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        match row {
+            Some(row) => created.push(row),
+            // A random code that's taken is simply drawn again.
+            None if fixed.is_some() => return Err(AppError::Conflict("Code already exists".into())),
+            None => {}
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(json!({ "codes": created })))
+}
+
+/// Admin: switch a code off. Its redemptions are kept.
+pub async fn deactivate_code(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Value>> {
+    let updated = sqlx::query("UPDATE promo_codes SET is_active = false WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(&tenant.0 .0)
+        .execute(&state.db)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Promo code not found".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Admin: who redeemed a code, when, and what it granted them.
+pub async fn list_redemptions(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<PaginationQuery>,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 200);
+    let offset = q.offset.unwrap_or(0).max(0);
+    let rows: Vec<(Uuid, Uuid, String, Value, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        r#"SELECT r.id, r.player_id, p.display_name, r.rewards, r.created_at
+        FROM promo_code_redemptions r JOIN players p ON p.id = r.player_id AND p.tenant_id = r.tenant_id
+        WHERE r.promo_code_id = $1 AND r.tenant_id = $2
+        ORDER BY r.created_at DESC LIMIT $3 OFFSET $4"#,
+    )
+    .bind(id)
+    .bind(&tenant.0 .0)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let redemptions: Vec<Value> = rows.iter().map(|(rid, pid, name, rewards, created)| {
+        json!({"id": rid, "playerId": pid, "playerName": name, "rewards": rewards, "createdAt": created})
+    }).collect();
+
+    Ok(Json(json!({ "redemptions": redemptions })))
+}
+
+/// Redeems a promo code, granting its currency, items and battle pass tiers
+/// in one transaction. Nothing is granted if any part fails.
+pub async fn redeem(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<RedeemPromoCodeRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let code = normalize(&body.code);
+
+    let mut tx = state.db.begin().await?;
+
+    let promo: PromoCode = sqlx::query_as(
+        "SELECT * FROM promo_codes WHERE tenant_id = $1 AND code = $2 AND is_active FOR UPDATE",
+    )
+    .bind(tid)
+    .bind(&code)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invalid promo code".into()))?;
+
+    if promo.expires_at.is_some_and(|at| at <= chrono::Utc::now()) {
+        return Err(AppError::BadRequest("This code has expired".into()));
+    }
+    if promo.max_redemptions.is_some_and(|m| promo.redemption_count >= m) {
+        return Err(AppError::Conflict("This code has been fully redeemed".into()));
+    }
+    let used: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM promo_code_redemptions WHERE promo_code_id = $1 AND player_id = $2",
+    )
+    .bind(promo.id)
+    .bind(player.id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if used >= i64::from(promo.per_player_limit) {
+        return Err(AppError::Conflict("You have already redeemed this code".into()));
+    }
+
+    let redemption_id = Uuid::new_v4();
+    let reference = redemption_id.to_string();
+    // Sorted, so wallets are always locked in the same order.
+    let currency: BTreeMap<String, i64> = serde_json::from_value(promo.currency.clone()).unwrap_or_default();
+    let items: Vec<TradeItem> = serde_json::from_value(promo.items.clone()).unwrap_or_default();
+
+    let mut balances = serde_json::Map::new();
+    for (ct, amount) in &currency {
+        let balance: i64 = sqlx::query_scalar(
+            r#"INSERT INTO player_wallets (player_id, tenant_id, currency_type, balance, lifetime_earned, updated_at)
+            VALUES ($1, $2, $3, $4, $4, NOW())
+            ON CONFLICT (player_id, tenant_id, currency_type) DO UPDATE SET
+                balance = player_wallets.balance + $4,
+                lifetime_earned = player_wallets.lifetime_earned + $4,
+                updated_at = NOW()
+            RETURNING balance"#,
+        )
+        .bind(player.id)
+        .bind(tid)
+        .bind(ct)
+        .bind(amount)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+            VALUES ($1, $2, $3, $4, $5, 'promo_reward', 'promo_code', $6, $7, NOW())"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(ct)
+        .bind(amount)
+        .bind(balance)
+        .bind(&reference)
+        .bind(json!({ "code": promo.code }))
+        .execute(&mut *tx)
+        .await?;
+        balances.insert(ct.clone(), json!(balance));
+    }
+
+    for item in &items {
+        let quantity: i32 = sqlx::query_scalar(
+            r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+            VALUES ($1, $2, $3, $4, 'promo_code', NOW())
+            ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+                quantity = player_inventory.quantity + EXCLUDED.quantity
+            RETURNING quantity"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(&item.item_id)
+        .bind(item.quantity)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+            VALUES ($1, $2, 'item', $3, $4, 'promo_reward', 'promo_code', $5, $6, NOW())"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(i64::from(item.quantity))
+        .bind(i64::from(quantity))
+        .bind(&reference)
+        .bind(json!({ "itemId": item.item_id, "code": promo.code }))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut battle_pass = Value::Null;
+    if promo.battle_pass_tiers > 0 {
+        let bp: BattlePass = sqlx::query_as(
+            "SELECT * FROM battle_passes WHERE tenant_id = $1 AND is_active = true LIMIT 1",
+        )
+        .bind(tid)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("This code needs an active battle pass".into()))?;
+
+        // Tiers past the last one are lost, as with XP.
+        let tier: i32 = sqlx::query_scalar(
+            r#"INSERT INTO player_battle_pass (tenant_id, player_id, battle_pass_id, current_tier, current_xp, is_premium, claimed_tiers, updated_at)
+            VALUES ($1, $2, $3, LEAST($4, $5), 0, false, '[]'::jsonb, NOW())
+            ON CONFLICT (tenant_id, player_id, battle_pass_id) DO UPDATE SET
+                current_tier = LEAST(player_battle_pass.current_tier + $4, $5),
+                updated_at = NOW()
+            RETURNING current_tier"#,
+        )
+        .bind(tid)
+        .bind(player.id)
+        .bind(bp.id)
+        .bind(promo.battle_pass_tiers)
+        .bind(bp.max_tier)
+        .fetch_one(&mut *tx)
+        .await?;
+        battle_pass = json!({ "battlePassId": bp.id, "tiers": promo.battle_pass_tiers, "currentTier": tier });
+    }
+
+    let rewards = json!({ "currency": currency, "items": items, "battlePass": battle_pass });
+    sqlx::query(
+        r#"INSERT INTO promo_code_redemptions (id, tenant_id, promo_code_id, player_id, rewards)
+        VALUES ($1, $2, $3, $4, $5)"#,
+    )
+    .bind(redemption_id)
+    .bind(tid)
+    .bind(promo.id)
+    .bind(player.id)
+    .bind(&rewards)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE promo_codes SET redemption_count = redemption_count + 1 WHERE id = $1")
+        .bind(promo.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(json!({
+        "redemptionId": redemption_id,
+        "code": promo.code,
+        "rewards": rewards,
+        "balances": balances,
+    })))
+}
@@ -0,0 +1,403 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::features::Features;
+use crate::middleware::tenant::TenantId;
+use crate::models::remote_config::*;
+use crate::AppState;
+
+/// How long a game's published profiles stay cached in Redis.
+const CONFIG_CACHE_SECS: u64 = 60;
+/// Tries at numbering a new profile before giving up.
+const VERSION_ATTEMPTS: usize = 5;
+
+fn cache_key(tid: &str, game_id: &str) -> String {
+    format!("game_config:{}:{}", tid, game_id)
+}
+
+/// Stable 0–99 bucket for a player in one game. Hashing the game id in
+/// means a player in the test group of one game isn't automatically in
+/// the test group of every other.
+fn rollout_bucket(player_id: Uuid, game_id: &str) -> i32 {
+    let digest = Sha256::digest(format!("{}:{}", game_id, player_id).as_bytes());
+    let head = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    (head % 100) as i32
+}
+
+/// Published profiles for a game, newest first.
+async fn published_profiles(state: &AppState, tid: &str, game_id: &str) -> AppResult<Vec<GameConfigProfile>> {
+    let key = cache_key(tid, game_id);
+    if let Some(cached) = state.cache.get_json::<Vec<GameConfigProfile>>(&key).await {
+        return Ok(cached);
+    }
+
+    let profiles: Vec<GameConfigProfile> = sqlx::query_as(
+        r#"SELECT id, game_id, version, name, params, notes, status, rollout_percent,
+            created_by, published_by, created_at, updated_at, published_at
+        FROM game_config_profiles
+        WHERE tenant_id = $1 AND game_id = $2 AND status = 'published'
+        ORDER BY version DESC"#,
+    )
+    .bind(tid)
+    .bind(game_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    state.cache.set_json(&key, &profiles, CONFIG_CACHE_SECS).await;
+    Ok(profiles)
+}
+
+/// Tuning parameters for one game: the newest published profile whose
+/// rollout covers the player's bucket. Games without one get an empty
+/// `params` object (version 0) and use their built-in defaults.
+pub async fn get_game_config(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let profiles = published_profiles(&state, &tenant.0 .0, &game_id).await?;
+    let bucket = rollout_bucket(player.id, &game_id);
+
+    let body = match profiles.iter().find(|p| bucket < p.rollout_percent) {
+        Some(p) => json!({
+            "gameId": game_id,
+            "version": p.version,
+            "profileId": p.id,
+            "name": p.name,
+            "params": p.params,
+            "updatedAt": p.published_at,
+        }),
+        None => json!({ "gameId": game_id, "version": 0, "params": {} }),
+    };
+
+    Ok(Json(body))
+}
+
+// ---------------------------------------------------------------------------
+// Admin: tuning profiles
+// ---------------------------------------------------------------------------
+
+async fn fetch_profile(db: &PgPool, tid: &str, game_id: &str, version: i32) -> AppResult<GameConfigProfile> {
+    sqlx::query_as(
+        r#"SELECT id, game_id, version, name, params, notes, status, rollout_percent,
+            created_by, published_by, created_at, updated_at, published_at
+        FROM game_config_profiles
+        WHERE tenant_id = $1 AND game_id = $2 AND version = $3"#,
+    )
+    .bind(tid)
+    .bind(game_id)
+    .bind(version)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Config profile not found".into()))
+}
+
+fn validate_params(params: &Value) -> AppResult<()> {
+    if !params.is_object() {
+        return Err(AppError::BadRequest("params must be a JSON object".into()));
+    }
+    Ok(())
+}
+
+fn validate_rollout(percent: i32) -> AppResult<()> {
+    if !(0..=100).contains(&percent) {
+        return Err(AppError::BadRequest("rolloutPercent must be between 0 and 100".into()));
+    }
+    Ok(())
+}
+
+/// Publishes `version` at `percent`. At 100% it covers every bucket, so
+/// older published profiles of the game can never be served again and are
+/// archived.
+async fn apply_rollout(db: &PgPool, tid: &str, profile: &GameConfigProfile, percent: i32, admin_id: Uuid) -> AppResult<()> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query(
+        r#"UPDATE game_config_profiles SET
+            status = 'published',
+            rollout_percent = $4,
+            published_by = $5,
+            published_at = COALESCE(CASE WHEN status = 'published' THEN published_at END, NOW()),
+            updated_at = NOW()
+        WHERE tenant_id = $1 AND game_id = $2 AND version = $3"#,
+    )
+    .bind(tid)
+    .bind(&profile.game_id)
+    .bind(profile.version)
+    .bind(percent)
+    .bind(admin_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if percent == 100 {
+        sqlx::query(
+            r#"UPDATE game_config_profiles SET status = 'archived', updated_at = NOW()
+            WHERE tenant_id = $1 AND game_id = $2 AND version < $3 AND status = 'published'"#,
+        )
+        .bind(tid)
+        .bind(&profile.game_id)
+        .bind(profile.version)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn log_config_action(db: &PgPool, tid: &str, admin_id: Uuid, action: &str, profile: &GameConfigProfile, metadata: Value) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, metadata, created_at) VALUES ($1, $2, $3, 'game_config', $4, $5, NOW())",
+    )
+    .bind(admin_id.to_string())
+    .bind(tid)
+    .bind(action)
+    .bind(profile.id.to_string())
+    .bind(metadata)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn admin_list_profiles(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let profiles: Vec<GameConfigProfile> = sqlx::query_as(
+        r#"SELECT id, game_id, version, name, params, notes, status, rollout_percent,
+            created_by, published_by, created_at, updated_at, published_at
+        FROM game_config_profiles
+        WHERE tenant_id = $1 AND game_id = $2
+        ORDER BY version DESC"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(&game_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "gameId": game_id, "profiles": profiles })))
+}
+
+/// Creates the next version as a draft.
+pub async fn admin_create_profile(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Json(body): Json<CreateConfigProfileRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    if body.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Name required".into()));
+    }
+
+    let params = match (body.params, body.based_on) {
+        (Some(params), _) => params,
+        (None, Some(version)) => fetch_profile(&state.db, tid, &game_id, version).await?.params,
+        (None, None) => json!({}),
+    };
+    validate_params(&params)?;
+
+    // Two drafts created at once can pick the same next version; the one
+    // that loses the unique key tries again with the version after.
+    for _ in 0..VERSION_ATTEMPTS {
+        let profile: Option<GameConfigProfile> = sqlx::query_as(
+            r#"INSERT INTO game_config_profiles (tenant_id, game_id, version, name, params, notes, created_by)
+            SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4, $5, $6
+            FROM game_config_profiles WHERE tenant_id = $1 AND game_id = $2
+            ON CONFLICT (tenant_id, game_id, version) DO NOTHING
+            RETURNING id, game_id, version, name, params, notes, status, rollout_percent,
+                created_by, published_by, created_at, updated_at, published_at"#,
+        )
+        .bind(tid)
+        .bind(&game_id)
+        .bind(body.name.trim())
+        .bind(&params)
+        .bind(&body.notes)
+        .bind(player.id)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some(profile) = profile {
+            return Ok(Json(json!({ "profile": profile })));
+        }
+    }
+    Err(AppError::Conflict("Another version is being created; try again".into()))
+}
+
+/// Edits a draft. Published and archived versions are immutable; copy
+/// them into a new draft with `basedOn` instead.
+pub async fn admin_update_profile(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, version)): Path<(String, i32)>,
+    Json(body): Json<UpdateConfigProfileRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    if profile.status != "draft" {
+        return Err(AppError::Conflict("Only drafts can be edited".into()));
+    }
+    if let Some(ref params) = body.params {
+        validate_params(params)?;
+    }
+
+    let profile: GameConfigProfile = sqlx::query_as(
+        r#"UPDATE game_config_profiles SET
+            name = COALESCE($4, name),
+            params = COALESCE($5, params),
+            notes = COALESCE($6, notes),
+            updated_at = NOW()
+        WHERE tenant_id = $1 AND game_id = $2 AND version = $3
+        RETURNING id, game_id, version, name, params, notes, status, rollout_percent,
+            created_by, published_by, created_at, updated_at, published_at"#,
+    )
+    .bind(tid)
+    .bind(&game_id)
+    .bind(version)
+    .bind(body.name.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+    .bind(&body.params)
+    .bind(&body.notes)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "profile": profile })))
+}
+
+/// Publishes a draft (or re-publishes an archived version) to
+/// `rolloutPercent` of players.
+pub async fn admin_publish_profile(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, version)): Path<(String, i32)>,
+    Json(body): Json<RolloutRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    validate_rollout(body.rollout_percent)?;
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    if profile.status == "published" {
+        return Err(AppError::Conflict("Already published; change its rollout instead".into()));
+    }
+
+    apply_rollout(&state.db, tid, &profile, body.rollout_percent, player.id).await?;
+    state.cache.del(&cache_key(tid, &game_id)).await;
+    log_config_action(
+        &state.db,
+        tid,
+        player.id,
+        "publish_config",
+        &profile,
+        json!({ "gameId": game_id, "version": version, "rolloutPercent": body.rollout_percent }),
+    )
+    .await?;
+
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    Ok(Json(json!({ "profile": profile })))
+}
+
+/// Changes the share of players a published version is served to.
+pub async fn admin_set_rollout(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, version)): Path<(String, i32)>,
+    Json(body): Json<RolloutRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    validate_rollout(body.rollout_percent)?;
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    if profile.status != "published" {
+        return Err(AppError::Conflict("Only published versions have a rollout".into()));
+    }
+
+    apply_rollout(&state.db, tid, &profile, body.rollout_percent, player.id).await?;
+    state.cache.del(&cache_key(tid, &game_id)).await;
+    log_config_action(
+        &state.db,
+        tid,
+        player.id,
+        "set_config_rollout",
+        &profile,
+        json!({
+            "gameId": game_id,
+            "version": version,
+            "from": profile.rollout_percent,
+            "rolloutPercent": body.rollout_percent,
+        }),
+    )
+    .await?;
+
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    Ok(Json(json!({ "profile": profile })))
+}
+
+/// Stops serving a version (or shelves a draft). Players in its rollout
+/// fall back to the next older published version.
+pub async fn admin_archive_profile(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, version)): Path<(String, i32)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    if profile.status == "archived" {
+        return Err(AppError::Conflict("Already archived".into()));
+    }
+
+    sqlx::query(
+        "UPDATE game_config_profiles SET status = 'archived', updated_at = NOW() WHERE tenant_id = $1 AND game_id = $2 AND version = $3",
+    )
+    .bind(tid)
+    .bind(&game_id)
+    .bind(version)
+    .execute(&state.db)
+    .await?;
+    state.cache.del(&cache_key(tid, &game_id)).await;
+    log_config_action(
+        &state.db,
+        tid,
+        player.id,
+        "archive_config",
+        &profile,
+        json!({ "gameId": game_id, "version": version, "previousStatus": profile.status }),
+    )
+    .await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+pub async fn admin_delete_profile(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, version)): Path<(String, i32)>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let profile = fetch_profile(&state.db, tid, &game_id, version).await?;
+    if profile.status != "draft" {
+        return Err(AppError::Conflict("Only drafts can be deleted; archive published versions".into()));
+    }
+
+    sqlx::query("DELETE FROM game_config_profiles WHERE id = $1")
+        .bind(profile.id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+/// The tenant's resolved feature flags, for clients to hide what is
+/// turned off.
+pub async fn get_features(features: axum::Extension<Features>) -> Json<Value> {
+    Json(json!({ "features": features.resolved() }))
+}
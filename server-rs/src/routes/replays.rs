@@ -0,0 +1,207 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::game_progress::{Replay, ReplayUploadRequest};
+use crate::AppState;
+
+/// Frames are sampled at ~20Hz, so this caps a replay at roughly 10 minutes.
+const MAX_REPLAY_FRAMES: usize = 12_000;
+/// Largest `frames` accepted, serialized; ample for 12,000 `[t, x, y, score]`
+/// samples.
+const MAX_REPLAY_BYTES: usize = 640 * 1024;
+
+fn replay_json(r: &Replay) -> Value {
+    json!({
+        "replayId": r.id,
+        "playerId": r.player_id,
+        "gameId": r.game_id,
+        "score": r.score,
+        "durationMs": r.duration_ms,
+        "frames": r.frames,
+        "engineVersion": r.engine_version,
+        "recordedAt": r.updated_at,
+    })
+}
+
+/// A `[t_ms, x, y, score]` sample.
+fn is_frame(frame: &Value) -> bool {
+    frame.as_array().is_some_and(|f| f.len() == 4 && f.iter().all(Value::is_number))
+}
+
+/// Store a replay if it beats the player's current personal-best replay.
+pub async fn upload_replay(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Json(body): Json<ReplayUploadRequest>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    if body.frames.is_empty() || body.frames.len() > MAX_REPLAY_FRAMES {
+        return Err(AppError::BadRequest(format!(
+            "Replay must contain 1-{} frames",
+            MAX_REPLAY_FRAMES
+        )));
+    }
+    if !body.frames.iter().all(is_frame) {
+        return Err(AppError::BadRequest("Each frame must be [tMs, x, y, score]".into()));
+    }
+    if serde_json::to_vec(&body.frames).map_or(true, |b| b.len() > MAX_REPLAY_BYTES) {
+        return Err(AppError::BadRequest(format!(
+            "Replay must be at most {} KB",
+            MAX_REPLAY_BYTES / 1024
+        )));
+    }
+    if body.score < 0 || body.duration_ms < 0 {
+        return Err(AppError::BadRequest("Invalid score or duration".into()));
+    }
+
+    // A replay can't claim more than the server has accepted via score submission
+    let high_score: Option<i64> = sqlx::query_scalar(
+        "SELECT high_score FROM game_progress WHERE player_id = $1 AND tenant_id = $2 AND game_id = $3",
+    )
+    .bind(player.id)
+    .bind(tenant_id)
+    .bind(&game_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if body.score > high_score.unwrap_or(0) {
+        return Err(AppError::BadRequest(
+            "Replay score exceeds recorded high score".into(),
+        ));
+    }
+
+    let stored: Option<Uuid> = sqlx::query_scalar(
+        r#"INSERT INTO replays (tenant_id, player_id, game_id, score, duration_ms, frames, engine_version)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (tenant_id, player_id, game_id) DO UPDATE SET
+            score = EXCLUDED.score,
+            duration_ms = EXCLUDED.duration_ms,
+            frames = EXCLUDED.frames,
+            engine_version = EXCLUDED.engine_version,
+            updated_at = NOW()
+        WHERE replays.score < EXCLUDED.score
+        RETURNING id"#,
+    )
+    .bind(tenant_id)
+    .bind(player.id)
+    .bind(&game_id)
+    .bind(body.score)
+    .bind(body.duration_ms)
+    .bind(Value::Array(body.frames))
+    .bind(&body.engine_version)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(Json(json!({
+        "stored": stored.is_some(),
+        "replayId": stored,
+    })))
+}
+
+/// The caller's own personal-best replay.
+pub async fn get_my_replay(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let replay: Option<Replay> = sqlx::query_as(
+        "SELECT * FROM replays WHERE tenant_id = $1 AND player_id = $2 AND game_id = $3",
+    )
+    .bind(&tenant.0 .0)
+    .bind(player.id)
+    .bind(&game_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match replay {
+        Some(r) => Ok(Json(json!({ "replay": replay_json(&r) }))),
+        None => Err(AppError::NotFound("No replay recorded".into())),
+    }
+}
+
+/// A friend's personal-best replay. Only accepted friends may download it.
+pub async fn get_player_replay(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path((game_id, target_id)): Path<(String, Uuid)>,
+) -> AppResult<Json<Value>> {
+    let tenant_id = &tenant.0 .0;
+
+    if target_id != player.id {
+        let is_friend: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM friendships WHERE tenant_id = $1 AND status = 'accepted'
+                AND ((player_id = $2 AND friend_id = $3) OR (player_id = $3 AND friend_id = $2)))"#,
+        )
+        .bind(tenant_id)
+        .bind(player.id)
+        .bind(target_id)
+        .fetch_one(&state.db)
+        .await?;
+
+        if !is_friend {
+            return Err(AppError::Forbidden("Replays are only shared with friends".into()));
+        }
+    }
+
+    let replay: Option<Replay> = sqlx::query_as(
+        "SELECT * FROM replays WHERE tenant_id = $1 AND player_id = $2 AND game_id = $3",
+    )
+    .bind(tenant_id)
+    .bind(target_id)
+    .bind(&game_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match replay {
+        Some(r) => Ok(Json(json!({ "replay": replay_json(&r) }))),
+        None => Err(AppError::NotFound("No replay recorded".into())),
+    }
+}
+
+/// Best replays among the caller's friends, for picking a ghost to race.
+pub async fn list_friend_replays(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+) -> AppResult<Json<Value>> {
+    let rows: Vec<(Uuid, String, i64, i32, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        r#"SELECT r.player_id, p.display_name, r.score, r.duration_ms, r.updated_at
+        FROM replays r
+        JOIN players p ON p.id = r.player_id AND p.tenant_id = r.tenant_id
+        WHERE r.tenant_id = $2 AND r.game_id = $3 AND r.player_id IN (
+            SELECT CASE WHEN f.player_id = $1 THEN f.friend_id ELSE f.player_id END
+            FROM friendships f
+            WHERE f.tenant_id = $2 AND f.status = 'accepted'
+                AND (f.player_id = $1 OR f.friend_id = $1)
+        )
+        ORDER BY r.score DESC
+        LIMIT 20"#,
+    )
+    .bind(player.id)
+    .bind(&tenant.0 .0)
+    .bind(&game_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let replays: Vec<Value> = rows
+        .iter()
+        .map(|(pid, name, score, duration, at)| {
+            json!({"playerId": pid, "displayName": name, "score": score, "durationMs": duration, "recordedAt": at})
+        })
+        .collect();
+
+    Ok(Json(json!({ "replays": replays })))
+}
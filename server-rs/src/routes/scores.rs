@@ -1,16 +1,25 @@
+use std::collections::HashMap;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
+use chrono::Utc;
 use serde_json::{json, Value};
+use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
 use crate::models::game_progress::*;
-use crate::services::{achievements, leaderboard};
+use crate::services::score_voids::{self, Target};
+use crate::services::{achievements, battle_pass_xp, leaderboard, xapi};
 use crate::AppState;
 
+const MAX_FINGERPRINT_CHARS: usize = 128;
+/// Submissions one void request may name.
+const MAX_VOID_IDS: usize = 1000;
+
 pub async fn submit_score(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -27,6 +36,12 @@ pub async fn submit_score(
             "Score must be between 0 and 999999".into(),
         ));
     }
+    let metrics = submitted_metrics(&game_id, &body)?;
+    let fingerprint = body
+        .device_fingerprint
+        .as_deref()
+        .map(|f| f.trim().chars().take(MAX_FINGERPRINT_CHARS).collect::<String>())
+        .filter(|f| !f.is_empty());
 
     let mut tx = state.db.begin().await?;
 
@@ -92,9 +107,13 @@ pub async fn submit_score(
     .execute(&mut *tx)
     .await?;
 
+    let metric_results = record_metrics(&mut tx, tenant_id, player_id, &game_id, &metrics).await?;
+
     // Insert score history
-    sqlx::query(
-        "INSERT INTO score_history (player_id, tenant_id, game_id, score, level, play_time, created_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())",
+    let history_metrics = (!metrics.is_empty())
+        .then(|| json!(metrics.iter().map(|(m, v)| (m.key, *v)).collect::<HashMap<_, _>>()));
+    let score_id: i64 = sqlx::query_scalar(
+        "INSERT INTO score_history (player_id, tenant_id, game_id, score, level, play_time, metrics, device_fingerprint, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW()) RETURNING id",
     )
     .bind(player_id)
     .bind(tenant_id)
@@ -102,9 +121,26 @@ pub async fn submit_score(
     .bind(body.score)
     .bind(body.level)
     .bind(body.time)
-    .execute(&mut *tx)
+    .bind(history_metrics)
+    .bind(fingerprint)
+    .fetch_one(&mut *tx)
     .await?;
 
+    let battle_pass_xp =
+        battle_pass_xp::award(&mut tx, tenant_id, player_id, battle_pass_xp::SCORE, body.score, &score_id.to_string())
+            .await?;
+
+    let statement = xapi::score_statement(
+        &state.config.xapi.activity_base_url,
+        player_id,
+        &game_id,
+        body.score,
+        body.level,
+        body.time,
+        Utc::now(),
+    );
+    xapi::record(&mut *tx, tenant_id, &statement).await?;
+
     tx.commit().await?;
 
     let is_new_high = body.score > prev_high;
@@ -122,17 +158,93 @@ pub async fn submit_score(
 
     // Evaluate achievements
     let new_achievements =
-        achievements::evaluate(&state.db, player_id, tenant_id).await?;
+        achievements::evaluate(&state, player_id, tenant_id).await?;
 
     Ok(Json(json!({
         "success": true,
         "highScore": new_high,
         "stars": stars,
         "isNewHighScore": is_new_high,
+        "metrics": metric_results,
         "newAchievements": new_achievements,
+        "battlePassXp": battle_pass_xp,
     })))
 }
 
+/// The submission's values for the game's declared metrics. `time` falls
+/// back to the top-level `time` field, which clients already send.
+fn submitted_metrics(game_id: &str, body: &ScoreSubmitRequest) -> AppResult<Vec<(Metric, i64)>> {
+    let sent = body.metrics.clone().unwrap_or_default();
+    if let Some(unknown) = sent.keys().find(|k| !game_metrics(game_id).iter().any(|m| m.key == k.as_str())) {
+        return Err(AppError::BadRequest(format!("Unknown metric for {}: {}", game_id, unknown)));
+    }
+
+    let mut out = Vec::new();
+    for metric in game_metrics(game_id) {
+        let value = match sent.get(metric.key) {
+            Some(v) => Some(*v),
+            None if metric.key == "time" => body.time.map(i64::from),
+            None => None,
+        };
+        let Some(value) = value else { continue };
+        if !(0..=metric.max).contains(&value) {
+            return Err(AppError::BadRequest(format!(
+                "{} must be between 0 and {}",
+                metric.key, metric.max
+            )));
+        }
+        out.push((*metric, value));
+    }
+    Ok(out)
+}
+
+/// Keeps the player's best value per metric. Returns, per metric, the
+/// submitted value, the best after this run and whether it is a new best.
+async fn record_metrics(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    tenant_id: &str,
+    player_id: Uuid,
+    game_id: &str,
+    metrics: &[(Metric, i64)],
+) -> AppResult<Value> {
+    let mut out = serde_json::Map::new();
+    for (metric, value) in metrics {
+        let prev: Option<i64> = sqlx::query_scalar(
+            "SELECT best_value FROM game_progress_metrics WHERE tenant_id = $1 AND player_id = $2 AND game_id = $3 AND metric = $4 FOR UPDATE",
+        )
+        .bind(tenant_id)
+        .bind(player_id)
+        .bind(game_id)
+        .bind(metric.key)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let is_new_best = prev.map_or(true, |p| metric.order.is_better(*value, p));
+        if is_new_best {
+            sqlx::query(
+                r#"INSERT INTO game_progress_metrics (tenant_id, player_id, game_id, metric, best_value, updated_at)
+                VALUES ($1, $2, $3, $4, $5, NOW())
+                ON CONFLICT (tenant_id, player_id, game_id, metric) DO UPDATE SET
+                    best_value = EXCLUDED.best_value, updated_at = NOW()"#,
+            )
+            .bind(tenant_id)
+            .bind(player_id)
+            .bind(game_id)
+            .bind(metric.key)
+            .bind(value)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        let best = if is_new_best { *value } else { prev.unwrap_or(*value) };
+        out.insert(
+            metric.key.to_string(),
+            json!({"value": value, "best": best, "isNewBest": is_new_best}),
+        );
+    }
+    Ok(Value::Object(out))
+}
+
 pub async fn get_progress(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -148,6 +260,16 @@ pub async fn get_progress(
     .fetch_optional(&state.db)
     .await?;
 
+    let bests: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT metric, best_value FROM game_progress_metrics WHERE tenant_id = $1 AND player_id = $2 AND game_id = $3",
+    )
+    .bind(&tenant.0 .0)
+    .bind(player.id)
+    .bind(&game_id)
+    .fetch_all(&state.db)
+    .await?;
+    let metrics: HashMap<String, i64> = bests.into_iter().collect();
+
     match row {
         Some((hs, bt, stars, level, pc)) => Ok(Json(json!({
             "gameId": game_id,
@@ -156,6 +278,7 @@ pub async fn get_progress(
             "stars": stars,
             "level": level,
             "playCount": pc,
+            "metrics": metrics,
         }))),
         None => Ok(Json(json!({
             "gameId": game_id,
@@ -164,6 +287,80 @@ pub async fn get_progress(
             "stars": 0,
             "level": 1,
             "playCount": 0,
+            "metrics": metrics,
         }))),
     }
 }
+
+// ---------------------------------------------------------------------------
+// Admin
+// ---------------------------------------------------------------------------
+
+/// Admin: the game's submissions, newest first. Filter by `playerId`;
+/// voided ones are left out unless `includeVoided` is set.
+pub async fn admin_list_scores(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Query(q): Query<ScoreSubmissionQuery>,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+    let scores: Vec<ScoreSubmission> = sqlx::query_as(
+        r#"SELECT id, player_id, score, level, play_time, device_fingerprint, created_at, voided_at
+        FROM score_history
+        WHERE tenant_id = $1 AND game_id = $2
+            AND ($3::text IS NULL OR player_id = $3)
+            AND ($4 OR voided_at IS NULL)
+        ORDER BY created_at DESC LIMIT $5 OFFSET $6"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(&game_id)
+    .bind(q.player_id.map(|id| id.to_string()))
+    .bind(q.include_voided.unwrap_or(false))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(json!({ "gameId": game_id, "scores": scores })))
+}
+
+/// Admin: voids some of the game's submissions, or all of one player's
+/// (see [`score_voids`]). Each player affected gets a moderation log entry.
+pub async fn admin_void_scores(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Path(game_id): Path<String>,
+    Json(body): Json<VoidScoresRequest>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let target = match (&body.score_ids, body.player_id) {
+        (Some(ids), None) if !ids.is_empty() && ids.len() <= MAX_VOID_IDS => Target::Scores(ids),
+        (None, Some(player_id)) => Target::Player(player_id),
+        _ => {
+            return Err(AppError::BadRequest(format!(
+                "Give either scoreIds (1 to {}) or playerId",
+                MAX_VOID_IDS
+            )))
+        }
+    };
+    let reason = body.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+
+    let mut tx = state.db.begin().await?;
+    let voided = score_voids::void(&mut tx, tid, &game_id, target).await?;
+    for v in &voided {
+        score_voids::log(&mut tx, tid, player.id, &game_id, v, reason, json!({})).await?;
+    }
+    tx.commit().await?;
+
+    if !voided.is_empty() {
+        score_voids::refresh_board(&state, tid, &game_id).await?;
+    }
+
+    Ok(Json(json!({
+        "gameId": game_id,
+        "voided": voided.iter().map(|v| v.score_ids.len()).sum::<usize>(),
+        "players": voided.iter().map(|v| v.json()).collect::<Vec<_>>(),
+    })))
+}
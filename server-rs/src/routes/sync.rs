@@ -1,12 +1,41 @@
 use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
 use serde_json::{json, Value};
+use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthPlayer;
 use crate::middleware::tenant::TenantId;
-use crate::models::compliance::BatchSyncRequest;
+use crate::models::compliance::{BatchSyncRequest, SyncOperation};
+use crate::services::xapi;
 use crate::AppState;
 
+const MAX_OPERATIONS: usize = 50;
+/// `game_progress.stars` is checked to this range.
+const MAX_STARS: i32 = 3;
+/// How far ahead of the server a client clock may run.
+const MAX_CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+
+enum Outcome {
+    Applied(Value),
+    Conflicted(Value),
+    Rejected(String),
+}
+
+/// Batch sync for offline-first clients.
+///
+/// Operations are applied oldest first by their client `timestamp` (ms since
+/// the epoch), with a merge policy per kind of data:
+///
+/// - high scores keep the server maximum; the play is always counted
+/// - profile fields and settings are last-write-wins on the client timestamp
+///   against the time of the last write the server saw
+/// - custom game data is merged key by key, as before
+///
+/// Every operation gets a result: `applied`, `conflicted` (the server kept
+/// its own value, returned in `server` for the client to adopt) or
+/// `rejected` (invalid; retrying won't help). All three are listed in
+/// `processed`, so clients can drop them from their queue.
 pub async fn batch_sync(
     State(state): State<AppState>,
     player: axum::Extension<AuthPlayer>,
@@ -16,97 +45,270 @@ pub async fn batch_sync(
     let player_id = player.id;
     let tenant_id = &tenant.0 .0;
 
-    if body.operations.len() > 50 {
-        return Err(AppError::BadRequest(
-            "Maximum 50 operations per batch".into(),
-        ));
+    if body.operations.len() > MAX_OPERATIONS {
+        return Err(AppError::BadRequest(format!(
+            "Maximum {} operations per batch",
+            MAX_OPERATIONS
+        )));
+    }
+    if body.operations.iter().any(|op| op.stars.is_some_and(|s| !(0..=MAX_STARS).contains(&s))) {
+        return Err(AppError::BadRequest(format!("stars must be between 0 and {}", MAX_STARS)));
     }
 
-    let mut processed = Vec::new();
+    let now = Utc::now();
     let mut ops = body.operations;
     ops.sort_by_key(|op| op.timestamp.unwrap_or(0));
 
+    let mut results = Vec::with_capacity(ops.len());
+    let mut processed = Vec::with_capacity(ops.len());
+    let (mut applied, mut conflicted, mut rejected) = (0, 0, 0);
+
     for op in &ops {
         let op_id = op
             .id
             .clone()
-            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-
-        match op.action.as_str() {
-            "score_submit" => {
-                if let Some(ref game_id) = op.game_id {
-                    let score = op.score.unwrap_or(0);
-                    sqlx::query(
-                        r#"INSERT INTO game_progress (player_id, tenant_id, game_id, high_score, play_count, total_score, stars, level, last_played_at)
-                        VALUES ($1, $2, $3, $4, 1, $4, 0, $5, NOW())
-                        ON CONFLICT (player_id, tenant_id, game_id) DO UPDATE SET
-                            high_score = GREATEST(game_progress.high_score, EXCLUDED.high_score),
-                            play_count = game_progress.play_count + 1,
-                            total_score = game_progress.total_score + EXCLUDED.high_score,
-                            stars = GREATEST(game_progress.stars, $6),
-                            last_played_at = NOW()"#,
-                    )
-                    .bind(player_id)
-                    .bind(tenant_id)
-                    .bind(game_id)
-                    .bind(score)
-                    .bind(op.level.unwrap_or(1))
-                    .bind(op.stars.unwrap_or(0))
-                    .execute(&state.db)
-                    .await?;
-                }
-            }
-            "player_update" => {
-                if let Some(ref player_data) = op.player {
-                    if let Some(name) = player_data.get("displayName").and_then(|v| v.as_str()) {
-                        sqlx::query(
-                            "UPDATE players SET display_name = $1 WHERE id = $2 AND tenant_id = $3",
-                        )
-                        .bind(name)
-                        .bind(player_id)
-                        .bind(tenant_id)
-                        .execute(&state.db)
-                        .await?;
-                    }
-                }
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let outcome = match client_time(op, now) {
+            Ok(at) => apply(&state, player_id, tenant_id, op, at).await?,
+            Err(reason) => Outcome::Rejected(reason),
+        };
+
+        let mut result = json!({"id": op_id, "action": op.action});
+        match outcome {
+            Outcome::Applied(server) => {
+                applied += 1;
+                result["status"] = json!("applied");
+                result["server"] = server;
             }
-            "settings_update" => {
-                if let Some(ref settings) = op.settings {
-                    sqlx::query(
-                        r#"INSERT INTO player_settings (player_id, tenant_id, settings_json, updated_at)
-                        VALUES ($1, $2, $3, NOW())
-                        ON CONFLICT (player_id, tenant_id) DO UPDATE SET
-                            settings_json = player_settings.settings_json || EXCLUDED.settings_json,
-                            updated_at = NOW()"#,
-                    )
-                    .bind(player_id)
-                    .bind(tenant_id)
-                    .bind(settings)
-                    .execute(&state.db)
-                    .await?;
-                }
+            Outcome::Conflicted(server) => {
+                conflicted += 1;
+                result["status"] = json!("conflicted");
+                result["server"] = server;
             }
-            "custom_data" => {
-                if let (Some(ref game_id), Some(ref data)) = (&op.game_id, &op.custom_data) {
-                    sqlx::query(
-                        r#"UPDATE game_progress SET custom_data = COALESCE(custom_data, '{}'::jsonb) || $1
-                        WHERE player_id = $2 AND tenant_id = $3 AND game_id = $4"#,
-                    )
-                    .bind(data)
-                    .bind(player_id)
-                    .bind(tenant_id)
-                    .bind(game_id)
-                    .execute(&state.db)
-                    .await?;
-                }
+            Outcome::Rejected(reason) => {
+                rejected += 1;
+                result["status"] = json!("rejected");
+                result["reason"] = json!(reason);
             }
-            _ => {}
         }
+        results.push(result);
         processed.push(op_id);
     }
 
     Ok(Json(json!({
+        "results": results,
         "processed": processed,
         "count": processed.len(),
+        "applied": applied,
+        "conflicted": conflicted,
+        "rejected": rejected,
     })))
 }
+
+/// The operation's client timestamp; the server time when it has none.
+fn client_time(op: &SyncOperation, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let Some(ms) = op.timestamp else {
+        return Ok(now);
+    };
+    if ms > now.timestamp_millis() + MAX_CLOCK_SKEW_MS {
+        return Err("Timestamp is in the future".into());
+    }
+    DateTime::from_timestamp_millis(ms).ok_or_else(|| "Invalid timestamp".into())
+}
+
+async fn apply(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    op: &SyncOperation,
+    at: DateTime<Utc>,
+) -> AppResult<Outcome> {
+    match op.action.as_str() {
+        "score_submit" => submit_score(state, player_id, tenant_id, op, at).await,
+        "player_update" => update_profile(state, player_id, tenant_id, op, at).await,
+        "settings_update" => update_settings(state, player_id, tenant_id, op, at).await,
+        "custom_data" => merge_custom_data(state, player_id, tenant_id, op).await,
+        other => Ok(Outcome::Rejected(format!("Unknown action '{}'", other))),
+    }
+}
+
+/// Keeps the higher of the stored and submitted high score. Conflicted when
+/// the server's is higher than what the client believes its best to be.
+async fn submit_score(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    op: &SyncOperation,
+    at: DateTime<Utc>,
+) -> AppResult<Outcome> {
+    let Some(ref game_id) = op.game_id else {
+        return Ok(Outcome::Rejected("gameId is required".into()));
+    };
+    let score = op.score.unwrap_or(0);
+    if score < 0 {
+        return Ok(Outcome::Rejected("score must not be negative".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let (high_score, stars): (i64, i32) = sqlx::query_as(
+        r#"INSERT INTO game_progress (player_id, tenant_id, game_id, high_score, play_count, total_score, stars, level, last_played_at)
+        VALUES ($1, $2, $3, $4, 1, $4, $6, $5, NOW())
+        ON CONFLICT (player_id, tenant_id, game_id) DO UPDATE SET
+            high_score = GREATEST(game_progress.high_score, EXCLUDED.high_score),
+            play_count = game_progress.play_count + 1,
+            total_score = game_progress.total_score + EXCLUDED.high_score,
+            stars = GREATEST(game_progress.stars, $6),
+            last_played_at = NOW()
+        RETURNING COALESCE(high_score, 0), COALESCE(stars, 0)::int"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(score)
+    .bind(op.level.unwrap_or(1))
+    .bind(op.stars.unwrap_or(0))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let statement =
+        xapi::score_statement(&state.config.xapi.activity_base_url, player_id, game_id, score, op.level, None, at);
+    xapi::record(&mut *tx, tenant_id, &statement).await?;
+    tx.commit().await?;
+
+    let client_high = op.high_score.unwrap_or(score).max(score);
+    let server = json!({"gameId": game_id, "highScore": high_score, "stars": stars, "isNewHigh": score == high_score});
+    Ok(if high_score > client_high {
+        Outcome::Conflicted(server)
+    } else {
+        Outcome::Applied(server)
+    })
+}
+
+/// Last-write-wins on `players.profile_updated_at`.
+async fn update_profile(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    op: &SyncOperation,
+    at: DateTime<Utc>,
+) -> AppResult<Outcome> {
+    let field = |name: &str| {
+        op.player
+            .as_ref()
+            .and_then(|p| p.get(name))
+            .and_then(Value::as_str)
+            .map(str::trim)
+    };
+    let display_name = field("displayName");
+    let avatar = field("avatarCharacter");
+    if display_name.is_none() && avatar.is_none() {
+        return Ok(Outcome::Rejected("No profile fields to update".into()));
+    }
+    if display_name.is_some_and(|n| n.is_empty() || n.chars().count() > 50) {
+        return Ok(Outcome::Rejected("displayName must be 1-50 characters".into()));
+    }
+
+    let updated: Option<(String, Option<String>, DateTime<Utc>)> = sqlx::query_as(
+        r#"UPDATE players SET
+            display_name = COALESCE($1, display_name),
+            avatar_character = COALESCE($2, avatar_character),
+            profile_updated_at = $3
+        WHERE id = $4 AND tenant_id = $5 AND (profile_updated_at IS NULL OR profile_updated_at <= $3)
+        RETURNING display_name, avatar_character, profile_updated_at"#,
+    )
+    .bind(display_name)
+    .bind(avatar)
+    .bind(at)
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+    if let Some((name, avatar, updated_at)) = updated {
+        return Ok(Outcome::Applied(
+            json!({"displayName": name, "avatarCharacter": avatar, "updatedAt": updated_at}),
+        ));
+    }
+
+    let (name, avatar, updated_at): (String, Option<String>, Option<DateTime<Utc>>) = sqlx::query_as(
+        "SELECT display_name, avatar_character, profile_updated_at FROM players WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Player not found".into()))?;
+    Ok(Outcome::Conflicted(
+        json!({"displayName": name, "avatarCharacter": avatar, "updatedAt": updated_at}),
+    ))
+}
+
+/// Last-write-wins on `player_settings.updated_at`. Applied writes merge
+/// into the stored settings key by key.
+async fn update_settings(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    op: &SyncOperation,
+    at: DateTime<Utc>,
+) -> AppResult<Outcome> {
+    let Some(ref settings) = op.settings.as_ref().filter(|s| s.is_object()) else {
+        return Ok(Outcome::Rejected("settings must be an object".into()));
+    };
+
+    let updated: Option<(Value, DateTime<Utc>)> = sqlx::query_as(
+        r#"INSERT INTO player_settings (player_id, tenant_id, settings_json, updated_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (player_id, tenant_id) DO UPDATE SET
+            settings_json = COALESCE(player_settings.settings_json, '{}'::jsonb) || EXCLUDED.settings_json,
+            updated_at = EXCLUDED.updated_at
+        WHERE player_settings.updated_at IS NULL OR player_settings.updated_at <= EXCLUDED.updated_at
+        RETURNING settings_json, updated_at"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(settings)
+    .bind(at)
+    .fetch_optional(&state.db)
+    .await?;
+    if let Some((settings, updated_at)) = updated {
+        return Ok(Outcome::Applied(json!({"settings": settings, "updatedAt": updated_at})));
+    }
+
+    let (settings, updated_at): (Option<Value>, Option<DateTime<Utc>>) = sqlx::query_as(
+        "SELECT settings_json, updated_at FROM player_settings WHERE player_id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_one(&state.db)
+    .await?;
+    Ok(Outcome::Conflicted(json!({"settings": settings, "updatedAt": updated_at})))
+}
+
+async fn merge_custom_data(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    op: &SyncOperation,
+) -> AppResult<Outcome> {
+    let (Some(ref game_id), Some(ref data)) = (&op.game_id, &op.custom_data) else {
+        return Ok(Outcome::Rejected("gameId and customData are required".into()));
+    };
+
+    let merged: Option<Value> = sqlx::query_scalar(
+        r#"UPDATE game_progress SET custom_data = COALESCE(custom_data, '{}'::jsonb) || $1
+        WHERE player_id = $2 AND tenant_id = $3 AND game_id = $4
+        RETURNING custom_data"#,
+    )
+    .bind(data)
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(game_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(match merged {
+        Some(custom_data) => Outcome::Applied(json!({"gameId": game_id, "customData": custom_data})),
+        None => Outcome::Rejected("No progress for this game yet".into()),
+    })
+}
@@ -0,0 +1,358 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::telemetry::{AnalyticsQuery, TelemetryBatch, TelemetryEventInput};
+use crate::AppState;
+
+/// Matches the engine's upload batch size.
+const MAX_BATCH_EVENTS: usize = 100;
+/// Serialized size limit for one event's `data`.
+const MAX_EVENT_DATA_BYTES: usize = 2048;
+/// Events older than this (by client clock) are rejected, e.g. a buffer
+/// that sat in a closed tab for weeks.
+const MAX_EVENT_AGE_DAYS: i64 = 7;
+/// Allowed client clock skew into the future.
+const MAX_CLOCK_SKEW_MINS: i64 = 5;
+/// Highest zero-based level accepted; the analytics queries cast `level`
+/// to an int.
+const MAX_LEVEL: i64 = 9999;
+
+const KNOWN_EVENT_TYPES: &[&str] = &[
+    "game_start",
+    "game_end",
+    "death",
+    "level_complete",
+    "powerup_used",
+];
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn has_number(data: &Value, key: &str) -> bool {
+    data.get(key).is_some_and(|v| v.is_number())
+}
+
+fn is_level(v: &Value) -> bool {
+    v.as_i64().is_some_and(|level| (0..=MAX_LEVEL).contains(&level))
+}
+
+/// Checks one event and normalizes its `data` to an object. Returns the
+/// client timestamp on success.
+fn validate_event(ev: &mut TelemetryEventInput, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if !KNOWN_EVENT_TYPES.contains(&ev.event_type.as_str())
+        || !is_valid_id(&ev.game_id)
+        || !is_valid_id(&ev.session_id)
+    {
+        return None;
+    }
+
+    let at = DateTime::from_timestamp_millis(ev.t)?;
+    if at < now - Duration::days(MAX_EVENT_AGE_DAYS)
+        || at > now + Duration::minutes(MAX_CLOCK_SKEW_MINS)
+    {
+        return None;
+    }
+
+    if ev.data.is_null() {
+        ev.data = json!({});
+    }
+    if !ev.data.is_object() || ev.data.to_string().len() > MAX_EVENT_DATA_BYTES {
+        return None;
+    }
+
+    // The analytics queries rely on these fields. Deaths from older
+    // engine builds have no level.
+    let complete = match ev.event_type.as_str() {
+        "death" => {
+            has_number(&ev.data, "x")
+                && has_number(&ev.data, "y")
+                && ev.data.get("level").map_or(true, is_level)
+        }
+        "level_complete" => ev.data.get("level").is_some_and(is_level),
+        _ => true,
+    };
+    complete.then_some(at)
+}
+
+/// Store a batch of gameplay events from the engine. Invalid events are
+/// skipped and counted in `rejected` rather than failing the whole batch.
+pub async fn ingest_events(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<TelemetryBatch>,
+) -> AppResult<Json<Value>> {
+    if body.events.len() > MAX_BATCH_EVENTS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} events per batch",
+            MAX_BATCH_EVENTS
+        )));
+    }
+    if body.dropped > 0 {
+        tracing::info!(
+            "Player {} dropped {} telemetry events client-side",
+            player.id,
+            body.dropped
+        );
+    }
+
+    let now = Utc::now();
+    let mut rejected = 0;
+    let mut types = Vec::with_capacity(body.events.len());
+    let mut game_ids = Vec::with_capacity(body.events.len());
+    let mut session_ids = Vec::with_capacity(body.events.len());
+    let mut occurred = Vec::with_capacity(body.events.len());
+    let mut data = Vec::with_capacity(body.events.len());
+
+    for mut ev in body.events {
+        let Some(at) = validate_event(&mut ev, now) else {
+            rejected += 1;
+            continue;
+        };
+        types.push(ev.event_type);
+        game_ids.push(ev.game_id);
+        session_ids.push(ev.session_id);
+        occurred.push(at);
+        data.push(ev.data);
+    }
+
+    if types.is_empty() {
+        return Ok(Json(json!({ "accepted": 0, "rejected": rejected })));
+    }
+
+    let accepted = sqlx::query(
+        r#"INSERT INTO telemetry_events (tenant_id, player_id, event_type, game_id, session_id, occurred_at, data)
+        SELECT $1, $2, e.event_type, e.game_id, e.session_id, e.occurred_at, e.data
+        FROM UNNEST($3::text[], $4::text[], $5::text[], $6::timestamptz[], $7::jsonb[])
+            AS e(event_type, game_id, session_id, occurred_at, data)"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(player.id)
+    .bind(&types)
+    .bind(&game_ids)
+    .bind(&session_ids)
+    .bind(&occurred)
+    .bind(&data)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+
+    Ok(Json(json!({ "accepted": accepted, "rejected": rejected })))
+}
+
+// ---------------------------------------------------------------------------
+// Admin analytics
+// ---------------------------------------------------------------------------
+
+fn window_days(q: &AnalyticsQuery) -> i32 {
+    q.days.unwrap_or(7).clamp(1, 90)
+}
+
+fn rate(part: i64, whole: i64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Start → complete funnel per game: distinct sessions that started, that
+/// completed at least one level, and that reached game over. With `gameId`
+/// the response also breaks completions down by level.
+pub async fn funnel(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AnalyticsQuery>,
+) -> AppResult<Json<Value>> {
+    let days = window_days(&q);
+
+    let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        r#"SELECT game_id,
+            COUNT(DISTINCT session_id) FILTER (WHERE event_type = 'game_start'),
+            COUNT(DISTINCT session_id) FILTER (WHERE event_type = 'level_complete'),
+            COUNT(DISTINCT session_id) FILTER (WHERE event_type = 'game_end')
+        FROM telemetry_events
+        WHERE tenant_id = $1 AND occurred_at >= NOW() - make_interval(days => $2)
+          AND ($3::text IS NULL OR game_id = $3)
+        GROUP BY game_id
+        ORDER BY 2 DESC"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(days)
+    .bind(&q.game_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let games: Vec<Value> = rows
+        .iter()
+        .map(|(game_id, started, completed, ended)| {
+            json!({
+                "gameId": game_id,
+                "started": started,
+                "completed": completed,
+                "ended": ended,
+                "completionRate": rate(*completed, *started),
+            })
+        })
+        .collect();
+
+    let mut response = json!({ "days": days, "games": games });
+
+    if let Some(game_id) = &q.game_id {
+        let started = rows.first().map_or(0, |r| r.1);
+        let levels: Vec<(i32, i64)> = sqlx::query_as(
+            r#"SELECT (data->>'level')::numeric::int AS level, COUNT(DISTINCT session_id)
+            FROM telemetry_events
+            WHERE tenant_id = $1 AND game_id = $2 AND event_type = 'level_complete'
+              AND occurred_at >= NOW() - make_interval(days => $3)
+              AND jsonb_typeof(data->'level') = 'number'
+            GROUP BY level
+            ORDER BY level"#,
+        )
+        .bind(&tenant.0 .0)
+        .bind(game_id)
+        .bind(days)
+        .fetch_all(&state.db)
+        .await?;
+
+        response["levels"] = levels
+            .iter()
+            .map(|(level, sessions)| {
+                json!({
+                    "level": level,
+                    "sessions": sessions,
+                    "rate": rate(*sessions, started),
+                })
+            })
+            .collect();
+    }
+
+    Ok(Json(response))
+}
+
+/// Death positions for one game, bucketed into `cell`-sized squares. Each
+/// cell's `x`/`y` is its lower-left corner in world units. `levels` counts
+/// deaths per level over the whole window, so the busiest level can be
+/// picked with `level`; deaths without a level count as level 0.
+pub async fn death_heatmap(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AnalyticsQuery>,
+) -> AppResult<Json<Value>> {
+    let game_id = q
+        .game_id
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("gameId is required".into()))?;
+    let days = window_days(&q);
+    let cell = q.cell.unwrap_or(40.0).clamp(5.0, 500.0);
+
+    let levels: Vec<(i32, i64)> = sqlx::query_as(
+        r#"SELECT COALESCE((data->>'level')::numeric::int, 0) AS level, COUNT(*)
+        FROM telemetry_events
+        WHERE tenant_id = $1 AND game_id = $2 AND event_type = 'death'
+          AND occurred_at >= NOW() - make_interval(days => $3)
+          AND jsonb_typeof(data->'x') = 'number' AND jsonb_typeof(data->'y') = 'number'
+        GROUP BY level
+        ORDER BY level"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(game_id)
+    .bind(days)
+    .fetch_all(&state.db)
+    .await?;
+
+    let rows: Vec<(i32, i32, i64)> = sqlx::query_as(
+        r#"SELECT floor((data->>'x')::float8 / $4)::int AS cx,
+            floor((data->>'y')::float8 / $4)::int AS cy,
+            COUNT(*)
+        FROM telemetry_events
+        WHERE tenant_id = $1 AND game_id = $2 AND event_type = 'death'
+          AND occurred_at >= NOW() - make_interval(days => $3)
+          AND jsonb_typeof(data->'x') = 'number' AND jsonb_typeof(data->'y') = 'number'
+          AND ($5::int IS NULL OR COALESCE((data->>'level')::numeric::int, 0) = $5)
+        GROUP BY cx, cy
+        ORDER BY 3 DESC
+        LIMIT 2000"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(game_id)
+    .bind(days)
+    .bind(cell)
+    .bind(q.level)
+    .fetch_all(&state.db)
+    .await?;
+
+    let total: i64 = rows.iter().map(|r| r.2).sum();
+    let cells: Vec<Value> = rows
+        .iter()
+        .map(|(cx, cy, count)| {
+            json!({ "x": *cx as f64 * cell, "y": *cy as f64 * cell, "count": count })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "gameId": game_id,
+        "days": days,
+        "cell": cell,
+        "level": q.level,
+        "totalDeaths": total,
+        "cells": cells,
+        "levels": levels
+            .iter()
+            .map(|(level, deaths)| json!({ "level": level, "deaths": deaths }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+/// Session length per game, from the `durationMs` of `game_end` events.
+pub async fn session_stats(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<AnalyticsQuery>,
+) -> AppResult<Json<Value>> {
+    let days = window_days(&q);
+
+    let rows: Vec<(String, i64, f64, f64)> = sqlx::query_as(
+        r#"SELECT game_id, COUNT(*),
+            AVG((data->>'durationMs')::float8),
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY (data->>'durationMs')::float8)
+        FROM telemetry_events
+        WHERE tenant_id = $1 AND event_type = 'game_end'
+          AND occurred_at >= NOW() - make_interval(days => $2)
+          AND ($3::text IS NULL OR game_id = $3)
+          AND jsonb_typeof(data->'durationMs') = 'number'
+        GROUP BY game_id
+        ORDER BY 2 DESC"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(days)
+    .bind(&q.game_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let games: Vec<Value> = rows
+        .iter()
+        .map(|(game_id, sessions, avg, median)| {
+            json!({
+                "gameId": game_id,
+                "sessions": sessions,
+                "avgDurationMs": avg.round() as i64,
+                "medianDurationMs": median.round() as i64,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({ "days": days, "games": games })))
+}
@@ -1,10 +1,15 @@
 use axum::{
     body::Bytes,
-    extract::State,
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
+    Json,
 };
+use serde_json::{json, Value};
 
-use crate::services::subscription_sync;
+use crate::error::{AppError, AppResult};
+use crate::models::subscription::StripeEvent;
+use crate::routes::admin::AdminQuery;
+use crate::services::stripe_events;
 use crate::AppState;
 
 pub async fn stripe_webhook(
@@ -27,87 +32,68 @@ pub async fn stripe_webhook(
         Err(_) => return Err(StatusCode::BAD_REQUEST),
     };
 
-    let event_id = event["id"].as_str().unwrap_or("");
-    let event_type = event["type"].as_str().unwrap_or("");
     let tenant_id = event["data"]["object"]["metadata"]["tenantId"]
         .as_str()
         .unwrap_or("stem_default");
 
-    // Idempotency check
-    let already_processed: bool = sqlx::query_scalar(
-        "SELECT EXISTS(SELECT 1 FROM stripe_events WHERE id = $1)",
-    )
-    .bind(event_id)
-    .fetch_one(&state.db)
-    .await
-    .unwrap_or(false);
-
-    if already_processed {
-        return Ok(StatusCode::OK);
-    }
-
-    let result = match event_type {
-        "customer.subscription.created"
-        | "customer.subscription.updated"
-        | "customer.subscription.deleted"
-        | "customer.subscription.trial_will_end" => {
-            let sub = &event["data"]["object"];
-            subscription_sync::sync_from_stripe(&state.db, &state.cache, sub, tenant_id).await
-        }
-        "invoice.payment_succeeded" | "invoice.payment_failed" => {
-            let sub_id = event["data"]["object"]["subscription"]
-                .as_str()
-                .unwrap_or("");
-            if !sub_id.is_empty() {
-                if let Some(stripe_client) = &state.stripe {
-                    if let Ok(sub) = stripe_client.get_subscription(sub_id).await {
-                        subscription_sync::sync_from_stripe(&state.db, &state.cache, &sub, tenant_id)
-                            .await
-                    } else {
-                        Ok(())
-                    }
-                } else {
-                    Ok(())
-                }
-            } else {
-                Ok(())
-            }
+    // Until the event is stored, a failure is Stripe's to retry; after
+    // that it's ours.
+    let process = match stripe_events::record(&state.db, &event, tenant_id).await {
+        Ok(process) => process,
+        Err(e) => {
+            tracing::error!("Failed to store Stripe event {}: {}", event["id"], e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
-        "checkout.session.completed" => {
-            let sub_id = event["data"]["object"]["subscription"]
-                .as_str()
-                .unwrap_or("");
-            if !sub_id.is_empty() {
-                if let Some(stripe_client) = &state.stripe {
-                    if let Ok(sub) = stripe_client.get_subscription(sub_id).await {
-                        subscription_sync::sync_from_stripe(&state.db, &state.cache, &sub, tenant_id)
-                            .await
-                    } else {
-                        Ok(())
-                    }
-                } else {
-                    Ok(())
-                }
-            } else {
-                Ok(())
-            }
-        }
-        _ => Ok(()),
     };
+    if process {
+        if let Err(e) = stripe_events::process(&state, &event, tenant_id).await {
+            tracing::error!("Failed to process Stripe event {}: {}", event["id"], e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
 
-    let status = if result.is_ok() { "processed" } else { "failed" };
+    Ok(StatusCode::OK)
+}
+
+/// Admin: stored Stripe events in a status, newest first; `failed` ones by
+/// default, the events that ran out of retries.
+pub async fn list_stripe_events(
+    State(state): State<AppState>,
+    Query(q): Query<AdminQuery>,
+) -> AppResult<Json<Value>> {
+    let limit = q.limit.unwrap_or(50).min(100);
+    let offset = q.page.unwrap_or(0) * limit;
+    let status = q.status.as_deref().unwrap_or("failed");
 
-    // Record event
-    let _ = sqlx::query(
-        "INSERT INTO stripe_events (id, tenant_id, event_type, payload, status) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (id) DO NOTHING",
+    let events: Vec<StripeEvent> = sqlx::query_as(
+        r#"SELECT id, tenant_id, event_type, payload, status, attempts, last_error, received_at, processed_at
+        FROM stripe_events WHERE status = $1
+        ORDER BY received_at DESC LIMIT $2 OFFSET $3"#,
     )
-    .bind(event_id)
-    .bind(tenant_id)
-    .bind(event_type)
-    .bind(&event)
     .bind(status)
-    .execute(&state.db)
-    .await;
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
 
-    Ok(StatusCode::OK)
+    Ok(Json(json!({ "events": events })))
+}
+
+/// Admin: retry a failed event with a fresh set of attempts.
+pub async fn replay_stripe_event(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<Value>> {
+    if !stripe_events::replay(&state.db, &id).await? {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM stripe_events WHERE id = $1)")
+            .bind(&id)
+            .fetch_one(&state.db)
+            .await?;
+        return Err(if exists {
+            AppError::Conflict("Only failed events can be replayed".into())
+        } else {
+            AppError::NotFound("Event not found".into())
+        });
+    }
+    Ok(Json(json!({ "success": true, "status": "retrying" })))
 }
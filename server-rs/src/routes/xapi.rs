@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::AuthPlayer;
+use crate::middleware::tenant::TenantId;
+use crate::models::xapi::*;
+use crate::services::{jobs, xapi};
+use crate::AppState;
+
+const OUTBOX_STATUSES: &[&str] = &["pending", "sent", "failed"];
+
+/// Admin: the tenant's LRS, without its password.
+pub async fn get_lrs(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let lrs: Option<LrsEndpoint> = sqlx::query_as("SELECT * FROM lrs_endpoints WHERE tenant_id = $1")
+        .bind(&tenant.0 .0)
+        .fetch_optional(&state.db)
+        .await?;
+    Ok(Json(json!({ "lrs": lrs })))
+}
+
+/// Admin: set the LRS that score submissions and achievements are exported
+/// to. Export starts with events after this; earlier ones aren't sent.
+pub async fn set_lrs(
+    State(state): State<AppState>,
+    player: axum::Extension<AuthPlayer>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<SetLrsRequest>,
+) -> AppResult<Json<Value>> {
+    let endpoint = body.endpoint.trim().trim_end_matches('/');
+    if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+        return Err(AppError::BadRequest("endpoint must be an http(s) URL".into()));
+    }
+    if body.username.is_empty() || body.password.is_empty() {
+        return Err(AppError::BadRequest("username and password are required".into()));
+    }
+
+    let lrs: LrsEndpoint = sqlx::query_as(
+        r#"INSERT INTO lrs_endpoints (tenant_id, endpoint, username, password, is_active, updated_by, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        ON CONFLICT (tenant_id) DO UPDATE SET
+            endpoint = EXCLUDED.endpoint, username = EXCLUDED.username, password = EXCLUDED.password,
+            is_active = EXCLUDED.is_active, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+        RETURNING *"#,
+    )
+    .bind(&tenant.0 .0)
+    .bind(endpoint)
+    .bind(&body.username)
+    .bind(&body.password)
+    .bind(body.is_active.unwrap_or(true))
+    .bind(player.id)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(json!({ "lrs": lrs })))
+}
+
+/// Admin: stop exporting. Statements already in the outbox are kept and
+/// sent if an LRS is set again.
+pub async fn delete_lrs(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+) -> AppResult<Json<Value>> {
+    let deleted = sqlx::query("DELETE FROM lrs_endpoints WHERE tenant_id = $1")
+        .bind(&tenant.0 .0)
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("No LRS configured".into()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Admin: outbox counts by status, and the statements in one status,
+/// newest first; `failed` by default.
+pub async fn list_outbox(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Query(q): Query<OutboxQuery>,
+) -> AppResult<Json<Value>> {
+    let tid = &tenant.0 .0;
+    let status = q.status.as_deref().unwrap_or("failed");
+    if !OUTBOX_STATUSES.contains(&status) {
+        return Err(AppError::BadRequest(format!("status must be one of {}", OUTBOX_STATUSES.join(", "))));
+    }
+    let limit = q.limit.unwrap_or(50).clamp(1, 100);
+    let offset = q.page.unwrap_or(0).max(0) * limit;
+
+    let counts: Vec<(String, i64)> =
+        sqlx::query_as("SELECT status, COUNT(*) FROM xapi_outbox WHERE tenant_id = $1 GROUP BY status")
+            .bind(tid)
+            .fetch_all(&state.db)
+            .await?;
+    let statements: Vec<OutboxStatement> = sqlx::query_as(
+        r#"SELECT id, statement, status, attempts, last_error, created_at, sent_at FROM xapi_outbox
+        WHERE tenant_id = $1 AND status = $2
+        ORDER BY created_at DESC LIMIT $3 OFFSET $4"#,
+    )
+    .bind(tid)
+    .bind(status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let counts: serde_json::Map<String, Value> = OUTBOX_STATUSES
+        .iter()
+        .map(|s| {
+            let n = counts.iter().find(|(status, _)| status == s).map_or(0, |(_, n)| *n);
+            (s.to_string(), json!(n))
+        })
+        .collect();
+
+    Ok(Json(json!({ "counts": counts, "statements": statements })))
+}
+
+/// Admin: queue failed (or already sent) statements for delivery again,
+/// and start a delivery run now.
+pub async fn replay_outbox(
+    State(state): State<AppState>,
+    tenant: axum::Extension<TenantId>,
+    Json(body): Json<ReplayStatementsRequest>,
+) -> AppResult<Json<Value>> {
+    let status = body.status.as_deref().unwrap_or("failed");
+    if status != "failed" && status != "sent" {
+        return Err(AppError::BadRequest("status must be failed or sent".into()));
+    }
+
+    let replayed = xapi::replay(&state.db, &tenant.0 .0, status, body.since).await?;
+    if replayed > 0 {
+        jobs::run_now(&state.db, jobs::EXPORT_XAPI, json!({})).await?;
+    }
+    Ok(Json(json!({ "replayed": replayed })))
+}
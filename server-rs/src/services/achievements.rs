@@ -1,12 +1,20 @@
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::error::AppResult;
+use crate::services::{battle_pass_xp, xapi};
+use crate::AppState;
 
+/// Awards the achievements the player now qualifies for and returns their
+/// ids. Each award is exported as an xAPI statement and earns battle pass
+/// XP.
 pub async fn evaluate(
-    db: &sqlx::PgPool,
+    state: &AppState,
     player_id: Uuid,
     tenant_id: &str,
 ) -> AppResult<Vec<String>> {
+    let db = &state.db;
+
     // Fetch player stats
     let player_stats: Option<(i64, i32)> = sqlx::query_as(
         "SELECT total_score, games_played FROM players WHERE id = $1 AND tenant_id = $2",
@@ -39,8 +47,8 @@ pub async fn evaluate(
     .await?;
 
     // Fetch achievement definitions
-    let achievements: Vec<(String, serde_json::Value, Option<String>)> = sqlx::query_as(
-        "SELECT id, criteria_json, game_id FROM achievements WHERE tenant_id = $1",
+    let achievements: Vec<(String, String, serde_json::Value, Option<String>)> = sqlx::query_as(
+        "SELECT id, name, criteria_json, game_id FROM achievements WHERE tenant_id = $1",
     )
     .bind(tenant_id)
     .fetch_all(db)
@@ -57,7 +65,7 @@ pub async fn evaluate(
 
     let mut newly_awarded = Vec::new();
 
-    for (id, criteria, _game_id) in &achievements {
+    for (id, name, criteria, game_id) in &achievements {
         if earned.contains(id) {
             continue;
         }
@@ -80,15 +88,30 @@ pub async fn evaluate(
         };
 
         if met {
-            sqlx::query(
+            let mut tx = db.begin().await?;
+            let inserted = sqlx::query(
                 "INSERT INTO player_achievements (player_id, tenant_id, achievement_id, game_id, earned_at) VALUES ($1, $2, $3, $4, NOW()) ON CONFLICT DO NOTHING",
             )
             .bind(player_id)
             .bind(tenant_id)
             .bind(id)
-            .bind(_game_id.as_deref())
-            .execute(db)
-            .await?;
+            .bind(game_id.as_deref())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                let statement = xapi::achievement_statement(
+                    &state.config.xapi.activity_base_url,
+                    player_id,
+                    id,
+                    name,
+                    game_id.as_deref(),
+                    Utc::now(),
+                );
+                xapi::record(&mut *tx, tenant_id, &statement).await?;
+                battle_pass_xp::award(&mut tx, tenant_id, player_id, battle_pass_xp::ACHIEVEMENT, 0, id).await?;
+            }
+            tx.commit().await?;
 
             newly_awarded.push(id.clone());
         }
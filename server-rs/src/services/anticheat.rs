@@ -0,0 +1,176 @@
+//! Score anti-cheat (`anticheat_flags`).
+//!
+//! The `detect_anomalies` job scans the last day of `score_history` for
+//! three kinds of suspicious submission, each raised as its own flag type:
+//!
+//! * `score_anomaly` - more than four standard deviations above the game's
+//!   mean over the last 30 days. Games with too few submissions for a
+//!   meaningful mean are skipped.
+//! * `submission_cadence` - submitted sooner after the player's previous
+//!   run of the game than the run itself lasted, or within seconds of it,
+//!   several times over. One fast resubmission is usually a retried
+//!   request.
+//! * `shared_device` - a device fingerprint submitting for many players of
+//!   the same game. Classroom devices are shared too, so the threshold is
+//!   set above a typical class rotation.
+//!
+//! Flags are raised per player, game and type: a player has one open flag
+//! for each, which later detections add their submissions to. Submissions
+//! already flagged under a type, whatever became of the flag, are not
+//! flagged again. Admins void a flag's submissions (see
+//! `routes::anticheat`) or dismiss it; voided submissions keep their row
+//! but leaderboards skip them.
+
+use sqlx::PgPool;
+
+use crate::error::AppResult;
+
+/// Submissions this recent are scanned.
+const SCAN_HOURS: i32 = 24;
+/// The mean and deviation outliers are measured against cover this long.
+const BASELINE_DAYS: i32 = 30;
+/// Submissions a game needs in the baseline before outliers are flagged.
+const MIN_BASELINE: i64 = 30;
+const Z_THRESHOLD: f64 = 4.0;
+/// Runs submitted closer together than this are too fast whatever their
+/// reported length.
+const MIN_GAP_SECS: f64 = 3.0;
+/// Too-fast submissions a player needs in the scan before being flagged.
+const FAST_SUBMISSIONS: i64 = 3;
+/// Players one device can submit for in the scan before being flagged.
+const DEVICE_PLAYERS: i64 = 5;
+
+/// Whether the submission `sh` has been flagged as `$TYPE` before.
+const FLAGGED_SQL: &str = "EXISTS (SELECT 1 FROM anticheat_flags f \
+     WHERE f.tenant_id = sh.tenant_id AND f.flag_type = $TYPE AND f.score_ids @> ARRAY[sh.id])";
+
+/// Raises `(tenant_id, player_id, game_id, score_ids, details)` rows from
+/// the `flagged` CTE as `$TYPE` flags, adding to the player's open flag if
+/// there is one.
+const RAISE_SQL: &str = "INSERT INTO anticheat_flags (tenant_id, player_id, game_id, flag_type, severity, score_ids, details) \
+     SELECT tenant_id, player_id::uuid, game_id, $TYPE, $SEVERITY, score_ids, details FROM flagged \
+     ON CONFLICT (tenant_id, player_id, game_id, flag_type) WHERE status = 'open' DO UPDATE SET \
+         score_ids = anticheat_flags.score_ids || EXCLUDED.score_ids, \
+         details = EXCLUDED.details, \
+         created_at = NOW()";
+
+/// The statement raising `flag_type` flags from `ctes`, which end with
+/// `flagged` and use `$FLAGGED` to skip submissions flagged before.
+fn detection(flag_type: &str, severity: &str, ctes: &str) -> String {
+    let flag_type = format!("'{}'", flag_type);
+    format!(
+        "WITH {}\n{}",
+        ctes.replace("$FLAGGED", &FLAGGED_SQL.replace("$TYPE", &flag_type)),
+        RAISE_SQL.replace("$TYPE", &flag_type).replace("$SEVERITY", &format!("'{}'", severity))
+    )
+}
+
+/// Scans recent submissions in every tenant. Returns the flags raised or
+/// added to.
+pub async fn detect(db: &PgPool) -> AppResult<u64> {
+    let outliers = detection(
+        "score_anomaly",
+        "warning",
+        r#"baseline AS (
+            SELECT tenant_id, game_id, AVG(score)::float8 AS mean, STDDEV_SAMP(score)::float8 AS sd, COUNT(*) AS n
+            FROM score_history
+            WHERE created_at > NOW() - make_interval(days => $2) AND voided_at IS NULL
+            GROUP BY tenant_id, game_id
+        ),
+        suspect AS (
+            SELECT sh.tenant_id, sh.player_id, sh.game_id, sh.id, sh.score, b.mean, b.sd,
+                (sh.score - b.mean) / b.sd AS z
+            FROM score_history sh JOIN baseline b USING (tenant_id, game_id)
+            WHERE sh.created_at > NOW() - make_interval(hours => $1) AND sh.voided_at IS NULL
+                AND b.n >= $3 AND b.sd > 0 AND (sh.score - b.mean) / b.sd > $4
+                AND NOT $FLAGGED
+        ),
+        flagged AS (
+            SELECT tenant_id, player_id, game_id, array_agg(id ORDER BY id) AS score_ids,
+                jsonb_build_object(
+                    'score', MAX(score),
+                    'mean', round(MAX(mean)::numeric, 1),
+                    'stddev', round(MAX(sd)::numeric, 1),
+                    'zScore', round(MAX(z)::numeric, 2)
+                ) AS details
+            FROM suspect GROUP BY tenant_id, player_id, game_id
+        )"#,
+    );
+    let mut raised = sqlx::query(&outliers)
+        .bind(SCAN_HOURS)
+        .bind(BASELINE_DAYS)
+        .bind(MIN_BASELINE)
+        .bind(Z_THRESHOLD)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    let cadence = detection(
+        "submission_cadence",
+        "critical",
+        r#"runs AS (
+            SELECT tenant_id, player_id, game_id, id, play_time,
+                EXTRACT(EPOCH FROM created_at - LAG(created_at) OVER (
+                    PARTITION BY tenant_id, player_id, game_id ORDER BY created_at
+                ))::float8 AS gap_secs
+            FROM score_history
+            WHERE created_at > NOW() - make_interval(hours => $1) AND voided_at IS NULL
+        ),
+        suspect AS (
+            SELECT sh.* FROM runs sh
+            WHERE sh.gap_secs < GREATEST($2, COALESCE(sh.play_time, 0) / 1000.0)
+                AND NOT $FLAGGED
+        ),
+        flagged AS (
+            SELECT tenant_id, player_id, game_id, array_agg(id ORDER BY id) AS score_ids,
+                jsonb_build_object(
+                    'submissions', COUNT(*),
+                    'shortestGapSecs', round(MIN(gap_secs)::numeric, 2)
+                ) AS details
+            FROM suspect GROUP BY tenant_id, player_id, game_id
+            HAVING COUNT(*) >= $3
+        )"#,
+    );
+    raised += sqlx::query(&cadence)
+        .bind(SCAN_HOURS)
+        .bind(MIN_GAP_SECS)
+        .bind(FAST_SUBMISSIONS)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    let devices = detection(
+        "shared_device",
+        "info",
+        r#"devices AS (
+            SELECT tenant_id, game_id, device_fingerprint, COUNT(DISTINCT player_id) AS players
+            FROM score_history
+            WHERE created_at > NOW() - make_interval(hours => $1) AND voided_at IS NULL
+                AND device_fingerprint IS NOT NULL
+            GROUP BY tenant_id, game_id, device_fingerprint
+            HAVING COUNT(DISTINCT player_id) >= $2
+        ),
+        suspect AS (
+            SELECT sh.tenant_id, sh.player_id, sh.game_id, sh.id, d.device_fingerprint, d.players
+            FROM score_history sh JOIN devices d USING (tenant_id, game_id, device_fingerprint)
+            WHERE sh.created_at > NOW() - make_interval(hours => $1) AND sh.voided_at IS NULL
+                AND NOT $FLAGGED
+        ),
+        flagged AS (
+            SELECT tenant_id, player_id, game_id, array_agg(id ORDER BY id) AS score_ids,
+                jsonb_build_object(
+                    'deviceFingerprint', MIN(device_fingerprint),
+                    'players', MAX(players)
+                ) AS details
+            FROM suspect GROUP BY tenant_id, player_id, game_id
+        )"#,
+    );
+    raised += sqlx::query(&devices)
+        .bind(SCAN_HOURS)
+        .bind(DEVICE_PLAYERS)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    Ok(raised)
+}
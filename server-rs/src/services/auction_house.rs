@@ -0,0 +1,278 @@
+//! Escrow and settlement for the auction house.
+//!
+//! A listed item leaves the seller's inventory and the highest bid leaves
+//! the bidder's wallet; both are held by the auction until it settles. An
+//! outbid player gets their bid back straight away. The settlement job
+//! picks up auctions past `ends_at`: the item goes to the highest bidder
+//! and the bid to the seller, or the item back to the seller when nobody
+//! bid. Every move is written to `economy_transactions` with source
+//! `auction` and the auction id as the reference.
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::models::auction::Auction;
+use crate::services::cosmetics;
+use crate::AppState;
+
+pub const MIN_DURATION_MINUTES: i64 = 5;
+pub const MAX_DURATION_MINUTES: i64 = 7 * 24 * 60;
+
+/// Open auctions a player may have at once.
+pub const MAX_OPEN_PER_SELLER: i64 = 20;
+
+/// Each bid must beat the current one by this much (and by at least 1).
+pub const MIN_INCREMENT_PERCENT: i64 = 5;
+
+/// A bid this close to the end...
+pub const SNIPE_WINDOW_SECS: i64 = 60;
+/// ...moves the end to this long after the bid.
+pub const SNIPE_EXTENSION_SECS: i64 = 120;
+
+/// How often due auctions are settled, as a job schedule.
+pub const SETTLE_SCHEDULE: &str = "*/15 * * * * *";
+const SETTLE_BATCH: i64 = 50;
+
+pub type PgTx<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// The lowest bid the auction accepts next.
+pub fn min_next_bid(auction: &Auction) -> i64 {
+    match auction.current_bid {
+        Some(bid) => bid + (bid * MIN_INCREMENT_PERCENT / 100).max(1),
+        None => auction.min_bid,
+    }
+}
+
+pub fn auction_json(auction: &Auction) -> Value {
+    let mut v = json!(auction);
+    if auction.status == "open" {
+        v["minNextBid"] = json!(min_next_bid(auction));
+    }
+    v
+}
+
+/// Moves the auctioned item out of `player`'s inventory into escrow.
+pub async fn take_item(tx: &mut PgTx<'_>, auction: &Auction, player_id: Uuid, tx_type: &str) -> AppResult<()> {
+    let owned: Option<i32> = sqlx::query_scalar(
+        "SELECT quantity FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3 FOR UPDATE",
+    )
+    .bind(&auction.tenant_id)
+    .bind(player_id)
+    .bind(&auction.item_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let remaining = owned.unwrap_or(0) - auction.quantity;
+    if remaining < 0 {
+        return Err(AppError::BadRequest(format!("Not enough of {} to list", auction.item_id)));
+    }
+    if remaining == 0 {
+        sqlx::query("DELETE FROM player_inventory WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(&auction.tenant_id).bind(player_id).bind(&auction.item_id)
+            .execute(&mut **tx).await?;
+    } else {
+        sqlx::query("UPDATE player_inventory SET quantity = $4 WHERE tenant_id = $1 AND player_id = $2 AND item_id = $3")
+            .bind(&auction.tenant_id).bind(player_id).bind(&auction.item_id).bind(remaining)
+            .execute(&mut **tx).await?;
+    }
+    cosmetics::unequip_if_gone(tx, &auction.tenant_id, player_id, &auction.item_id).await?;
+
+    log(tx, auction, player_id, "item", -i64::from(auction.quantity), i64::from(remaining), tx_type).await
+}
+
+/// Releases the auctioned item from escrow to `player`.
+pub async fn give_item(tx: &mut PgTx<'_>, auction: &Auction, player_id: Uuid, tx_type: &str) -> AppResult<()> {
+    let received: i32 = sqlx::query_scalar(
+        r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+        VALUES ($1, $2, $3, $4, 'auction', NOW())
+        ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+            quantity = player_inventory.quantity + EXCLUDED.quantity
+        RETURNING quantity"#,
+    )
+    .bind(&auction.tenant_id)
+    .bind(player_id)
+    .bind(&auction.item_id)
+    .bind(auction.quantity)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    log(tx, auction, player_id, "item", i64::from(auction.quantity), i64::from(received), tx_type).await
+}
+
+/// Takes a bid from `player`'s wallet into escrow.
+pub async fn debit(tx: &mut PgTx<'_>, auction: &Auction, player_id: Uuid, amount: i64, tx_type: &str) -> AppResult<()> {
+    let balance: Option<i64> = sqlx::query_scalar(
+        "SELECT balance FROM player_wallets WHERE player_id = $1 AND tenant_id = $2 AND currency_type = $3 FOR UPDATE",
+    )
+    .bind(player_id)
+    .bind(&auction.tenant_id)
+    .bind(&auction.currency_type)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let remaining = balance.unwrap_or(0) - amount;
+    if remaining < 0 {
+        return Err(AppError::BadRequest(format!("Insufficient {}", auction.currency_type)));
+    }
+    sqlx::query("UPDATE player_wallets SET balance = $1, updated_at = NOW() WHERE player_id = $2 AND tenant_id = $3 AND currency_type = $4")
+        .bind(remaining).bind(player_id).bind(&auction.tenant_id).bind(&auction.currency_type)
+        .execute(&mut **tx).await?;
+
+    log(tx, auction, player_id, &auction.currency_type, -amount, remaining, tx_type).await
+}
+
+/// Pays `amount` out of escrow to `player`. Like trades, this is not
+/// "earned", so lifetime_earned is left alone.
+pub async fn credit(tx: &mut PgTx<'_>, auction: &Auction, player_id: Uuid, amount: i64, tx_type: &str) -> AppResult<()> {
+    let balance: i64 = sqlx::query_scalar(
+        r#"INSERT INTO player_wallets (player_id, tenant_id, currency_type, balance, lifetime_earned, updated_at)
+        VALUES ($1, $2, $3, $4, 0, NOW())
+        ON CONFLICT (player_id, tenant_id, currency_type) DO UPDATE SET
+            balance = player_wallets.balance + $4,
+            updated_at = NOW()
+        RETURNING balance"#,
+    )
+    .bind(player_id)
+    .bind(&auction.tenant_id)
+    .bind(&auction.currency_type)
+    .bind(amount)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    log(tx, auction, player_id, &auction.currency_type, amount, balance, tx_type).await
+}
+
+async fn log(
+    tx: &mut PgTx<'_>,
+    auction: &Auction,
+    player_id: Uuid,
+    currency: &str,
+    amount: i64,
+    balance_after: i64,
+    tx_type: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, 'auction', $7, $8, NOW())"#,
+    )
+    .bind(&auction.tenant_id)
+    .bind(player_id)
+    .bind(currency)
+    .bind(amount)
+    .bind(balance_after)
+    .bind(tx_type)
+    .bind(auction.id.to_string())
+    .bind(json!({ "itemId": auction.item_id }))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Settles one auction if it is still open and past its end. Returns the
+/// settled auction, or `None` if there was nothing to do.
+async fn settle(state: &AppState, id: Uuid) -> AppResult<Option<Auction>> {
+    let mut tx = state.db.begin().await?;
+
+    let auction: Option<Auction> = sqlx::query_as(
+        "SELECT * FROM auctions WHERE id = $1 AND status = 'open' AND ends_at <= NOW() FOR UPDATE SKIP LOCKED",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(auction) = auction else { return Ok(None) };
+
+    let status = match (auction.high_bidder_id, auction.current_bid) {
+        (Some(winner), Some(bid)) => {
+            give_item(&mut tx, &auction, winner, "auction_win").await?;
+            credit(&mut tx, &auction, auction.seller_id, bid, "auction_sale").await?;
+            "sold"
+        }
+        _ => {
+            give_item(&mut tx, &auction, auction.seller_id, "auction_return").await?;
+            "expired"
+        }
+    };
+
+    let settled: Auction = sqlx::query_as(
+        "UPDATE auctions SET status = $2, settled_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(status)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(settled))
+}
+
+/// Takes a player being erased out of open auctions. Their listings are
+/// cancelled and the high bids on them refunded; their own high bids are
+/// dropped, so those items go back to the sellers at settlement. Returns
+/// the number of auctions changed.
+pub async fn withdraw_player(tx: &mut PgTx<'_>, tenant_id: &str, player_id: Uuid) -> AppResult<u64> {
+    let listed: Vec<Auction> = sqlx::query_as(
+        "SELECT * FROM auctions WHERE tenant_id = $1 AND seller_id = $2 AND status = 'open' FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    for auction in &listed {
+        if let (Some(bidder), Some(bid)) = (auction.high_bidder_id, auction.current_bid) {
+            credit(tx, auction, bidder, bid, "auction_refund").await?;
+        }
+    }
+    let cancelled = sqlx::query(
+        "UPDATE auctions SET status = 'cancelled', settled_at = NOW() WHERE tenant_id = $1 AND seller_id = $2 AND status = 'open'",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+
+    let outbid = sqlx::query(
+        "UPDATE auctions SET current_bid = NULL, high_bidder_id = NULL WHERE tenant_id = $1 AND high_bidder_id = $2 AND status = 'open'",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    Ok(cancelled + outbid)
+}
+
+/// Tells a player about an auction they sold, bid on or won.
+pub async fn notify(state: &AppState, auction: &Auction, player_id: Uuid, event: &str) {
+    state
+        .realtime
+        .send_to(&auction.tenant_id, player_id, &json!({ "type": event, "auction": auction_json(auction) }))
+        .await;
+}
+
+/// Settles auctions past their end. Returns the number settled.
+pub async fn settle_due(state: &AppState) -> AppResult<usize> {
+    let due: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT id FROM auctions WHERE status = 'open' AND ends_at <= NOW() ORDER BY ends_at LIMIT $1",
+    )
+    .bind(SETTLE_BATCH)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut settled = 0;
+    for id in due {
+        match settle(state, id).await {
+            Ok(Some(auction)) => {
+                settled += 1;
+                notify(state, &auction, auction.seller_id, "auction_settled").await;
+                if let Some(winner) = auction.high_bidder_id {
+                    notify(state, &auction, winner, "auction_won").await;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to settle auction {}: {}", id, e),
+        }
+    }
+    Ok(settled)
+}
@@ -0,0 +1,458 @@
+//! Battle pass tiers, rewards and windows.
+//!
+//! A pass's tiers (`battle_pass_tiers`) each need a total amount of XP on
+//! the pass, and may carry a free and a premium reward
+//! (`battle_pass_rewards`): currency or a store item. Players claim a
+//! tier's rewards themselves once they reach it, the premium one only with
+//! the premium pass. Setting the tiers also rewrites the pass's
+//! `max_tier`, `free_rewards` and `premium_rewards`, which older clients
+//! read. XP is awarded by the server, through
+//! [`battle_pass_xp`](crate::services::battle_pass_xp).
+//!
+//! A pass with `starts_at` is switched on by the `battle_pass_windows` job
+//! once its window opens, replacing the tenant's active pass. When
+//! `ends_at` passes the job switches it off, grants every player the free
+//! rewards they reached but never claimed, and marks it closed. Closed
+//! passes can't be claimed from or changed.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::middleware::etag;
+use crate::models::economy::{BattlePassReward, BattlePassTier, BattlePassTierInput};
+use crate::services::entity_audit::{self, Audit};
+use crate::AppState;
+
+/// How often battle pass windows are checked, as a job schedule.
+pub const WINDOW_SCHEDULE: &str = "* * * * *";
+
+/// tier, xp_threshold, track, reward_type, currency_type, item_id, amount
+type TierRow = (i32, i32, Option<String>, Option<String>, Option<String>, Option<String>, Option<i32>);
+/// tier, reward_type, currency_type, item_id, amount
+type RewardRow = (i32, String, Option<String>, Option<String>, i32);
+
+/// A pass's tiers with their rewards, from tier 1 up.
+pub async fn tiers<'e>(db: impl sqlx::PgExecutor<'e>, pass_id: i32) -> AppResult<Vec<BattlePassTier>> {
+    let rows: Vec<TierRow> = sqlx::query_as(
+        r#"SELECT t.tier, t.xp_threshold, r.track, r.reward_type, r.currency_type, r.item_id, r.amount
+        FROM battle_pass_tiers t
+        LEFT JOIN battle_pass_rewards r ON r.battle_pass_id = t.battle_pass_id AND r.tier = t.tier
+        WHERE t.battle_pass_id = $1
+        ORDER BY t.tier, r.track"#,
+    )
+    .bind(pass_id)
+    .fetch_all(db)
+    .await?;
+
+    let mut tiers: Vec<BattlePassTier> = Vec::new();
+    for (tier, xp_threshold, track, reward_type, currency_type, item_id, amount) in rows {
+        if tiers.last().map_or(true, |t| t.tier != tier) {
+            tiers.push(BattlePassTier { tier, xp_threshold, free: None, premium: None });
+        }
+        let (Some(track), Some(reward_type)) = (track, reward_type) else { continue };
+        let reward = BattlePassReward { reward_type, currency_type, item_id, amount: amount.unwrap_or(1) };
+        let last = tiers.last_mut().expect("pushed above");
+        match track.as_str() {
+            "free" => last.free = Some(reward),
+            _ => last.premium = Some(reward),
+        }
+    }
+    Ok(tiers)
+}
+
+/// The XP thresholds of a pass's tiers, from tier 1 up.
+pub async fn thresholds<'e>(db: impl sqlx::PgExecutor<'e>, pass_id: i32) -> AppResult<Vec<i32>> {
+    Ok(sqlx::query_scalar("SELECT xp_threshold FROM battle_pass_tiers WHERE battle_pass_id = $1 ORDER BY tier")
+        .bind(pass_id)
+        .fetch_all(db)
+        .await?)
+}
+
+/// XP a player gained on a pass, and where it left them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XpGain {
+    pub battle_pass_id: i32,
+    pub xp: i32,
+    pub current_tier: i32,
+    /// XP towards the next tier.
+    pub current_xp: i32,
+    /// `None` at the last tier.
+    pub xp_to_next_tier: Option<i32>,
+}
+
+/// The tenant's active pass, unless it has ended.
+pub async fn active_pass<'e>(db: impl sqlx::PgExecutor<'e>, tenant_id: &str) -> AppResult<Option<i32>> {
+    Ok(sqlx::query_scalar(
+        "SELECT id FROM battle_passes WHERE tenant_id = $1 AND is_active = true AND (ends_at IS NULL OR ends_at > NOW()) LIMIT 1",
+    )
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?)
+}
+
+/// Adds XP to the player's progress on a pass, levelling them up.
+pub async fn add_xp(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    player_id: Uuid,
+    pass_id: i32,
+    xp: i32,
+) -> AppResult<XpGain> {
+    let (current_tier, current_xp): (i32, i32) = sqlx::query_as(
+        r#"INSERT INTO player_battle_pass (tenant_id, player_id, battle_pass_id, current_tier, current_xp, is_premium, claimed_tiers, updated_at)
+        VALUES ($1, $2, $3, 0, $4, false, '[]'::jsonb, NOW())
+        ON CONFLICT (tenant_id, player_id, battle_pass_id) DO UPDATE SET
+            current_xp = player_battle_pass.current_xp + $4,
+            updated_at = NOW()
+        RETURNING current_tier, current_xp"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(pass_id)
+    .bind(xp)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let thresholds = thresholds(&mut *conn, pass_id).await?;
+    let (tier, left) = level(&thresholds, current_tier, current_xp);
+    if tier != current_tier {
+        sqlx::query(
+            "UPDATE player_battle_pass SET current_tier = $4, current_xp = $5 WHERE tenant_id = $1 AND player_id = $2 AND battle_pass_id = $3",
+        )
+        .bind(tenant_id)
+        .bind(player_id)
+        .bind(pass_id)
+        .bind(tier)
+        .bind(left)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(XpGain {
+        battle_pass_id: pass_id,
+        xp,
+        current_tier: tier,
+        current_xp: left,
+        xp_to_next_tier: xp_to_next(&thresholds, tier).map(|step| step - left),
+    })
+}
+
+/// XP needed to go from `tier` to the next, or `None` at the last tier.
+pub fn xp_to_next(thresholds: &[i32], tier: i32) -> Option<i32> {
+    let tier = usize::try_from(tier).ok()?;
+    let next = *thresholds.get(tier)?;
+    let reached = if tier == 0 { 0 } else { thresholds.get(tier - 1).copied().unwrap_or(0) };
+    Some(next - reached)
+}
+
+/// Moves a player with `xp` towards the next tier up as many tiers as it
+/// covers. Returns the new tier and the XP left over; past the last tier
+/// XP keeps adding up.
+pub fn level(thresholds: &[i32], mut tier: i32, mut xp: i32) -> (i32, i32) {
+    while let Some(step) = xp_to_next(thresholds, tier) {
+        if xp < step {
+            break;
+        }
+        xp -= step;
+        tier += 1;
+    }
+    (tier, xp)
+}
+
+/// Replaces a pass's tiers and rewrites the columns older clients read.
+/// The tiers are expected to be validated.
+pub async fn set_tiers(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    pass_id: i32,
+    input: &[BattlePassTierInput],
+) -> AppResult<()> {
+    sqlx::query("DELETE FROM battle_pass_tiers WHERE battle_pass_id = $1")
+        .bind(pass_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut free_rewards = Vec::new();
+    let mut premium_rewards = Vec::new();
+    for (tier, t) in (1..).zip(input) {
+        sqlx::query("INSERT INTO battle_pass_tiers (tenant_id, battle_pass_id, tier, xp_threshold) VALUES ($1, $2, $3, $4)")
+            .bind(tenant_id)
+            .bind(pass_id)
+            .bind(tier)
+            .bind(t.xp_threshold)
+            .execute(&mut *conn)
+            .await?;
+        for (track, reward, legacy) in [
+            ("free", &t.free, &mut free_rewards),
+            ("premium", &t.premium, &mut premium_rewards),
+        ] {
+            let Some(reward) = reward else { continue };
+            sqlx::query(
+                r#"INSERT INTO battle_pass_rewards (tenant_id, battle_pass_id, tier, track, reward_type, currency_type, item_id, amount)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            )
+            .bind(tenant_id)
+            .bind(pass_id)
+            .bind(tier)
+            .bind(track)
+            .bind(&reward.reward_type)
+            .bind(&reward.currency_type)
+            .bind(&reward.item_id)
+            .bind(reward.amount)
+            .execute(&mut *conn)
+            .await?;
+            legacy.push(legacy_json(tier, reward));
+        }
+    }
+
+    sqlx::query("UPDATE battle_passes SET max_tier = $2, free_rewards = $3, premium_rewards = $4 WHERE id = $1")
+        .bind(pass_id)
+        .bind(input.len() as i32)
+        .bind(json!(free_rewards))
+        .bind(json!(premium_rewards))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// A reward as older clients know it: `{tier, reward_type, reward_data}`.
+fn legacy_json(tier: i32, reward: &BattlePassReward) -> Value {
+    let reward_data = match reward.reward_type.as_str() {
+        "currency" => json!({ "currency_type": reward.currency_type, "amount": reward.amount }),
+        _ => json!({ "item_id": reward.item_id, "quantity": reward.amount }),
+    };
+    json!({ "tier": tier, "reward_type": reward.reward_type, "reward_data": reward_data })
+}
+
+/// A tier's rewards: the free one, and the premium one for premium players.
+pub async fn tier_rewards(
+    conn: &mut PgConnection,
+    pass_id: i32,
+    tier: i32,
+    premium: bool,
+) -> AppResult<Vec<BattlePassReward>> {
+    Ok(sqlx::query_as(
+        r#"SELECT reward_type, currency_type, item_id, amount FROM battle_pass_rewards
+        WHERE battle_pass_id = $1 AND tier = $2 AND (track = 'free' OR $3)
+        ORDER BY track"#,
+    )
+    .bind(pass_id)
+    .bind(tier)
+    .bind(premium)
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Credits a tier reward to the player's wallet or inventory and records
+/// the transaction.
+pub async fn grant(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    player_id: Uuid,
+    pass_id: i32,
+    tier: i32,
+    reward: &BattlePassReward,
+) -> AppResult<()> {
+    let reference = format!("bp_{}_tier_{}", pass_id, tier);
+    let amount = i64::from(reward.amount);
+    let (currency_type, balance, metadata) = match (&reward.currency_type, &reward.item_id) {
+        (Some(ct), _) => {
+            let balance: i64 = sqlx::query_scalar(
+                r#"INSERT INTO player_wallets (player_id, tenant_id, currency_type, balance, lifetime_earned, updated_at)
+                VALUES ($1, $2, $3, $4, $4, NOW())
+                ON CONFLICT (player_id, tenant_id, currency_type) DO UPDATE SET
+                    balance = player_wallets.balance + $4,
+                    lifetime_earned = player_wallets.lifetime_earned + $4,
+                    updated_at = NOW()
+                RETURNING balance"#,
+            )
+            .bind(player_id)
+            .bind(tenant_id)
+            .bind(ct)
+            .bind(amount)
+            .fetch_one(&mut *conn)
+            .await?;
+            (ct.as_str(), balance, json!({ "battlePassId": pass_id, "tier": tier }))
+        }
+        (None, Some(item_id)) => {
+            let quantity: i32 = sqlx::query_scalar(
+                r#"INSERT INTO player_inventory (tenant_id, player_id, item_id, quantity, source, acquired_at)
+                VALUES ($1, $2, $3, $4, 'battle_pass', NOW())
+                ON CONFLICT (tenant_id, player_id, item_id) DO UPDATE SET
+                    quantity = player_inventory.quantity + EXCLUDED.quantity
+                RETURNING quantity"#,
+            )
+            .bind(tenant_id)
+            .bind(player_id)
+            .bind(item_id)
+            .bind(reward.amount)
+            .fetch_one(&mut *conn)
+            .await?;
+            ("item", i64::from(quantity), json!({ "battlePassId": pass_id, "tier": tier, "itemId": item_id }))
+        }
+        (None, None) => return Ok(()),
+    };
+
+    sqlx::query(
+        r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+        VALUES ($1, $2, $3, $4, $5, 'earn', 'battle_pass', $6, $7, NOW())"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(currency_type)
+    .bind(amount)
+    .bind(balance)
+    .bind(&reference)
+    .bind(metadata)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Switches on passes whose window has opened, and off the tenant's other
+/// passes. Returns the number switched on.
+pub async fn open_due(state: &AppState) -> AppResult<usize> {
+    let db = &state.db;
+    // The latest start wins if windows were left overlapping.
+    let due: Vec<(i32, String)> = sqlx::query_as(
+        r#"SELECT DISTINCT ON (tenant_id) id, tenant_id FROM battle_passes
+        WHERE starts_at <= NOW() AND (ends_at IS NULL OR ends_at > NOW())
+            AND NOT is_active AND closed_at IS NULL AND deleted_at IS NULL
+        ORDER BY tenant_id, starts_at DESC"#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    for (id, tenant_id) in &due {
+        let mut tx = db.begin().await?;
+        let others: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM battle_passes WHERE tenant_id = $1 AND id <> $2 AND is_active = true",
+        )
+        .bind(tenant_id)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for pass in others.into_iter().chain([*id]) {
+            let audit = Audit::begin(&mut tx, tenant_id, entity_audit::BATTLE_PASS, &pass.to_string()).await?;
+            sqlx::query("UPDATE battle_passes SET is_active = (id = $2) WHERE id = $1")
+                .bind(pass)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit.record(&mut tx, tenant_id, None).await?;
+        }
+        tx.commit().await?;
+        etag::bump_version(state, tenant_id).await?;
+    }
+    Ok(due.len())
+}
+
+/// Closes passes past their end, granting unclaimed free rewards. Returns
+/// the number of passes closed and of players granted rewards.
+pub async fn close_due(state: &AppState) -> AppResult<(usize, usize)> {
+    let due: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT id, tenant_id FROM battle_passes WHERE ends_at <= NOW() AND closed_at IS NULL AND deleted_at IS NULL ORDER BY ends_at",
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut granted = 0;
+    for (id, tenant_id) in &due {
+        // Switched off first, so nothing is claimed or earned while the
+        // rewards go out. Players are granted in their own transactions; a
+        // failed run picks up the ones left, since granted tiers count as
+        // claimed.
+        sqlx::query("UPDATE battle_passes SET is_active = false WHERE id = $1")
+            .bind(id)
+            .execute(&state.db)
+            .await?;
+        etag::bump_version(state, tenant_id).await?;
+
+        let players: Vec<Uuid> = sqlx::query_scalar(
+            r#"SELECT p.player_id FROM player_battle_pass p
+            WHERE p.battle_pass_id = $1 AND EXISTS (
+                SELECT 1 FROM battle_pass_rewards r
+                WHERE r.battle_pass_id = p.battle_pass_id AND r.track = 'free'
+                    AND r.tier <= p.current_tier AND NOT p.claimed_tiers @> to_jsonb(r.tier)
+            )"#,
+        )
+        .bind(id)
+        .fetch_all(&state.db)
+        .await?;
+        for player_id in players {
+            let tiers = grant_unclaimed(&state.db, tenant_id, player_id, *id).await?;
+            if !tiers.is_empty() {
+                granted += 1;
+                state
+                    .realtime
+                    .send_to(tenant_id, player_id, &json!({ "type": "battle_pass_closed", "battlePassId": id, "grantedTiers": tiers }))
+                    .await;
+            }
+        }
+
+        let mut tx = state.db.begin().await?;
+        let audit = Audit::begin(&mut tx, tenant_id, entity_audit::BATTLE_PASS, &id.to_string()).await?;
+        sqlx::query("UPDATE battle_passes SET is_active = false, closed_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        audit.record(&mut tx, tenant_id, None).await?;
+        tx.commit().await?;
+        etag::bump_version(state, tenant_id).await?;
+    }
+    Ok((due.len(), granted))
+}
+
+/// Grants a player the free rewards of every tier they reached but didn't
+/// claim, marking those tiers claimed. Returns the tiers granted.
+async fn grant_unclaimed(db: &PgPool, tenant_id: &str, player_id: Uuid, pass_id: i32) -> AppResult<Vec<i32>> {
+    let mut tx = db.begin().await?;
+    let Some((current_tier, claimed)): Option<(i32, Value)> = sqlx::query_as(
+        "SELECT current_tier, claimed_tiers FROM player_battle_pass WHERE tenant_id = $1 AND player_id = $2 AND battle_pass_id = $3 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(pass_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(Vec::new());
+    };
+    let mut claimed: Vec<i32> = serde_json::from_value(claimed).unwrap_or_default();
+
+    let rewards: Vec<RewardRow> = sqlx::query_as(
+        r#"SELECT tier, reward_type, currency_type, item_id, amount FROM battle_pass_rewards
+        WHERE battle_pass_id = $1 AND track = 'free' AND tier <= $2 AND tier <> ALL($3)
+        ORDER BY tier"#,
+    )
+    .bind(pass_id)
+    .bind(current_tier)
+    .bind(&claimed)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut tiers = Vec::new();
+    for (tier, reward_type, currency_type, item_id, amount) in rewards {
+        let reward = BattlePassReward { reward_type, currency_type, item_id, amount };
+        grant(&mut tx, tenant_id, player_id, pass_id, tier, &reward).await?;
+        tiers.push(tier);
+    }
+    claimed.extend(&tiers);
+    claimed.sort_unstable();
+
+    sqlx::query(
+        "UPDATE player_battle_pass SET claimed_tiers = $4, updated_at = NOW() WHERE tenant_id = $1 AND player_id = $2 AND battle_pass_id = $3",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(pass_id)
+    .bind(json!(claimed))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(tiers)
+}
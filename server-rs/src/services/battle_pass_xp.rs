@@ -0,0 +1,148 @@
+//! Server-side battle pass XP (`battle_pass_xp_sources`,
+//! `battle_pass_xp_awards`).
+//!
+//! Players earn XP on the active pass for what the server has seen them
+//! do, rather than for what a client says: score submissions, daily
+//! challenge runs, achievements and multiplayer matches played in a server
+//! room whose result the server can vouch for (submitted by a backend, or
+//! a turn-based game it refereed to the end). Each source gives a flat `xp`, plus `xp_per_1000_points` of the
+//! score for scores, runs and matches, capped at `daily_cap` XP per player
+//! and UTC day. Tenants can replace the built-in [`DEFAULTS`] per source.
+//!
+//! Awards are recorded once per source and reference (the score, run,
+//! achievement or match), so seeing the same event twice grants nothing
+//! more. Backends with the `battlepass:write` scope can still award XP
+//! directly, recorded as source `api`.
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::models::economy::BattlePassXpSource;
+use crate::services::battle_pass::{self, XpGain};
+
+pub const SCORE: &str = "score";
+pub const DAILY_CHALLENGE: &str = "daily_challenge";
+pub const ACHIEVEMENT: &str = "achievement";
+pub const MATCH_WIN: &str = "match_win";
+pub const MATCH_LOSS: &str = "match_loss";
+/// XP awarded by a backend through `POST /economy/battlepass/xp`.
+pub const API: &str = "api";
+
+/// Built-in settings: source, XP per event, XP per 1000 points, daily cap.
+pub const DEFAULTS: &[(&str, i32, i32, Option<i32>)] = &[
+    (SCORE, 50, 10, Some(2000)),
+    (DAILY_CHALLENGE, 300, 0, None),
+    (ACHIEVEMENT, 250, 0, None),
+    (MATCH_WIN, 150, 5, Some(3000)),
+    (MATCH_LOSS, 60, 5, Some(3000)),
+];
+
+fn default_source(source: &str) -> Option<BattlePassXpSource> {
+    DEFAULTS.iter().find(|(s, ..)| *s == source).map(|(source, xp, per_1000, cap)| BattlePassXpSource {
+        source: source.to_string(),
+        xp: *xp,
+        xp_per_1000_points: *per_1000,
+        daily_cap: *cap,
+        is_enabled: true,
+        is_default: true,
+    })
+}
+
+/// Every source's settings for the tenant, in [`DEFAULTS`] order.
+pub async fn sources(db: &PgPool, tenant_id: &str) -> AppResult<Vec<BattlePassXpSource>> {
+    let overrides: Vec<BattlePassXpSource> = sqlx::query_as(
+        r#"SELECT source, xp, xp_per_1000_points, daily_cap, is_enabled, false AS is_default
+        FROM battle_pass_xp_sources WHERE tenant_id = $1"#,
+    )
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?;
+    Ok(DEFAULTS
+        .iter()
+        .filter_map(|(source, ..)| {
+            overrides.iter().find(|o| o.source == *source).cloned().or_else(|| default_source(source))
+        })
+        .collect())
+}
+
+/// The tenant's settings for one source, or `None` for an unknown source.
+pub async fn source(conn: &mut PgConnection, tenant_id: &str, source: &str) -> AppResult<Option<BattlePassXpSource>> {
+    let tenant: Option<BattlePassXpSource> = sqlx::query_as(
+        r#"SELECT source, xp, xp_per_1000_points, daily_cap, is_enabled, false AS is_default
+        FROM battle_pass_xp_sources WHERE tenant_id = $1 AND source = $2"#,
+    )
+    .bind(tenant_id)
+    .bind(source)
+    .fetch_optional(conn)
+    .await?;
+    Ok(tenant.or_else(|| default_source(source)))
+}
+
+/// Records an award. Returns false if one was already recorded for the
+/// source and reference.
+pub async fn record(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    player_id: Uuid,
+    pass_id: i32,
+    source: &str,
+    reference: Option<&str>,
+    xp: i32,
+) -> AppResult<bool> {
+    let inserted = sqlx::query(
+        r#"INSERT INTO battle_pass_xp_awards (tenant_id, player_id, battle_pass_id, source, reference_id, xp)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (tenant_id, player_id, source, reference_id) DO NOTHING"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(pass_id)
+    .bind(source)
+    .bind(reference)
+    .bind(xp)
+    .execute(conn)
+    .await?
+    .rows_affected();
+    Ok(inserted > 0)
+}
+
+/// Awards the player XP on the active pass for an event from `source`
+/// that scored `points`. Returns `None` when nothing was awarded: there's
+/// no active pass, the source is switched off, the player hit its daily
+/// cap, or the event was already rewarded.
+pub async fn award(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    player_id: Uuid,
+    source: &str,
+    points: i64,
+    reference: &str,
+) -> AppResult<Option<XpGain>> {
+    let Some(pass_id) = battle_pass::active_pass(&mut *conn, tenant_id).await? else {
+        return Ok(None);
+    };
+    let Some(config) = self::source(conn, tenant_id, source).await?.filter(|c| c.is_enabled) else {
+        return Ok(None);
+    };
+
+    let mut xp = i64::from(config.xp) + i64::from(config.xp_per_1000_points) * points.max(0) / 1000;
+    if let Some(cap) = config.daily_cap {
+        let earned: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(xp), 0)::bigint FROM battle_pass_xp_awards
+            WHERE tenant_id = $1 AND player_id = $2 AND source = $3
+                AND created_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'"#,
+        )
+        .bind(tenant_id)
+        .bind(player_id)
+        .bind(source)
+        .fetch_one(&mut *conn)
+        .await?;
+        xp = xp.min(i64::from(cap) - earned);
+    }
+    let xp = xp.min(i64::from(i32::MAX)) as i32;
+    if xp <= 0 || !record(conn, tenant_id, player_id, pass_id, source, Some(reference), xp).await? {
+        return Ok(None);
+    }
+    Ok(Some(battle_pass::add_xp(conn, tenant_id, player_id, pass_id, xp).await?))
+}
@@ -0,0 +1,158 @@
+//! Classroom queries shared by the organisation routes and report exports.
+//!
+//! Assignment completion isn't stored: it is derived from `score_history`
+//! each time it's asked for, so it always matches the submitted scores.
+
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::models::organisation::{Assignment, AssignmentProgress, Classroom};
+
+/// Organisation roles that may run classrooms. Any of them can manage every
+/// classroom in the organisation, so co-teachers can share a class.
+pub const TEACHER_ROLES: [&str; 3] = ["owner", "admin", "teacher"];
+
+pub async fn member_role(
+    db: &sqlx::PgPool,
+    org_id: &str,
+    player_id: Uuid,
+    tenant_id: &str,
+) -> AppResult<Option<String>> {
+    Ok(sqlx::query_scalar(
+        "SELECT role FROM organisation_members WHERE organisation_id = $1 AND player_id = $2 AND tenant_id = $3",
+    )
+    .bind(org_id)
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?)
+}
+
+pub async fn require_teacher(db: &sqlx::PgPool, org_id: &str, player_id: Uuid, tenant_id: &str) -> AppResult<()> {
+    match member_role(db, org_id, player_id, tenant_id).await?.as_deref() {
+        Some(r) if TEACHER_ROLES.contains(&r) => Ok(()),
+        _ => Err(AppError::Forbidden("Must be an org owner, admin or teacher".into())),
+    }
+}
+
+pub async fn fetch_classroom(
+    db: &sqlx::PgPool,
+    org_id: &str,
+    classroom_id: Uuid,
+    tenant_id: &str,
+) -> AppResult<Classroom> {
+    sqlx::query_as::<_, Classroom>(
+        r#"SELECT c.id, c.organisation_id, c.name, c.teacher_id, c.created_at,
+            (SELECT COUNT(*) FROM classroom_students cs WHERE cs.classroom_id = c.id)::bigint AS student_count
+        FROM classrooms c
+        WHERE c.id = $1 AND c.organisation_id = $2 AND c.tenant_id = $3"#,
+    )
+    .bind(classroom_id)
+    .bind(org_id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Classroom not found".into()))
+}
+
+/// Teachers see any classroom in their organisation; students only the
+/// ones they're on the roster of. Returns whether the caller is a teacher.
+pub async fn authorize_classroom(
+    db: &sqlx::PgPool,
+    org_id: &str,
+    classroom_id: Uuid,
+    player_id: Uuid,
+    tenant_id: &str,
+) -> AppResult<bool> {
+    if let Some(r) = member_role(db, org_id, player_id, tenant_id).await? {
+        if TEACHER_ROLES.contains(&r.as_str()) {
+            return Ok(true);
+        }
+    }
+    let enrolled: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM classroom_students WHERE classroom_id = $1 AND player_id = $2 AND tenant_id = $3)",
+    )
+    .bind(classroom_id)
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await?;
+    if !enrolled {
+        return Err(AppError::Forbidden("Not a member of this classroom".into()));
+    }
+    Ok(false)
+}
+
+pub async fn fetch_assignments(db: &sqlx::PgPool, classroom_id: Uuid, tenant_id: &str) -> AppResult<Vec<Assignment>> {
+    Ok(sqlx::query_as::<_, Assignment>(
+        r#"SELECT id, classroom_id, game_id, title, target_score, due_at, created_by, created_at
+        FROM assignments WHERE classroom_id = $1 AND tenant_id = $2
+        ORDER BY due_at, created_at"#,
+    )
+    .bind(classroom_id)
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?)
+}
+
+/// Best score, attempt count and completion time of every rostered student
+/// (or just `only_player`) on every assignment of the classroom. Only scores
+/// submitted after the assignment was set count towards it.
+pub async fn assignment_progress(
+    db: &sqlx::PgPool,
+    classroom_id: Uuid,
+    tenant_id: &str,
+    only_player: Option<Uuid>,
+) -> AppResult<Vec<AssignmentProgress>> {
+    Ok(sqlx::query_as::<_, AssignmentProgress>(
+        r#"SELECT a.id AS assignment_id, cs.player_id,
+            MAX(sh.score) AS best_score,
+            COUNT(sh.score)::bigint AS attempts,
+            MIN(sh.created_at) FILTER (WHERE sh.score >= a.target_score) AS completed_at
+        FROM assignments a
+        JOIN classroom_students cs ON cs.classroom_id = a.classroom_id
+        LEFT JOIN score_history sh ON sh.player_id::text = cs.player_id::text
+            AND sh.tenant_id = cs.tenant_id
+            AND sh.game_id = a.game_id
+            AND sh.created_at >= a.created_at
+        WHERE a.classroom_id = $1 AND a.tenant_id = $2
+            AND ($3::uuid IS NULL OR cs.player_id = $3)
+        GROUP BY a.id, cs.player_id"#,
+    )
+    .bind(classroom_id)
+    .bind(tenant_id)
+    .bind(only_player)
+    .fetch_all(db)
+    .await?)
+}
+
+/// `completed` (on time), `late`, `missed` (past due, not reached),
+/// `in_progress` or `not_started`.
+pub fn progress_status(progress: &AssignmentProgress, due_at: chrono::DateTime<chrono::Utc>) -> &'static str {
+    match progress.completed_at {
+        Some(at) if at <= due_at => "completed",
+        Some(_) => "late",
+        None if chrono::Utc::now() > due_at => "missed",
+        None if progress.attempts > 0 => "in_progress",
+        None => "not_started",
+    }
+}
+
+/// `(player_id, display_name, added_at)` for every rostered student, by name.
+pub async fn roster(
+    db: &sqlx::PgPool,
+    classroom_id: Uuid,
+    tenant_id: &str,
+) -> AppResult<Vec<(Uuid, String, chrono::DateTime<chrono::Utc>)>> {
+    Ok(sqlx::query_as(
+        r#"SELECT cs.player_id, COALESCE(p.display_name, 'Explorer'), cs.added_at
+        FROM classroom_students cs
+        LEFT JOIN players p ON p.id::text = cs.player_id::text AND p.tenant_id = cs.tenant_id
+        WHERE cs.classroom_id = $1 AND cs.tenant_id = $2
+        ORDER BY 2"#,
+    )
+    .bind(classroom_id)
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?)
+}
@@ -0,0 +1,149 @@
+//! Equippable profile cosmetics.
+//!
+//! Store items of type `avatar_frame`, `title` and `banner` can be equipped
+//! from the player's inventory into the matching slot on `players`. The
+//! equipped item ids travel with the player wherever other players see
+//! them (leaderboards, friends lists); the profile carries the full items.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::translations::Translations;
+
+pub struct Slot {
+    /// `store_items.item_type` that fits the slot.
+    pub item_type: &'static str,
+    /// Column on `players` holding the equipped item id.
+    pub column: &'static str,
+    /// Key in API requests and responses.
+    pub field: &'static str,
+}
+
+pub const SLOTS: &[Slot] = &[
+    Slot { item_type: "avatar_frame", column: "equipped_avatar_frame", field: "avatarFrame" },
+    Slot { item_type: "title", column: "equipped_title", field: "title" },
+    Slot { item_type: "banner", column: "equipped_banner", field: "banner" },
+];
+
+/// Select expression for the equipped item ids of the `players` row
+/// aliased `p`, as a JSON object keyed by slot.
+pub const EQUIPPED_SQL: &str = "jsonb_build_object('avatarFrame', p.equipped_avatar_frame, \
+     'title', p.equipped_title, 'banner', p.equipped_banner)";
+
+/// Checks that `item_id` fits `slot` and that the player owns at least one.
+pub async fn check_equippable(
+    db: &PgPool,
+    tenant_id: &str,
+    player_id: Uuid,
+    slot: &Slot,
+    item_id: &str,
+) -> AppResult<()> {
+    let row: Option<(String, Option<i32>)> = sqlx::query_as(
+        r#"SELECT si.item_type, pi.quantity
+        FROM store_items si
+        LEFT JOIN player_inventory pi
+            ON pi.item_id = si.id AND pi.tenant_id = si.tenant_id AND pi.player_id = $3
+        WHERE si.id = $1 AND si.tenant_id = $2"#,
+    )
+    .bind(item_id)
+    .bind(tenant_id)
+    .bind(player_id)
+    .fetch_optional(db)
+    .await?;
+
+    match row {
+        None => Err(AppError::NotFound("Item not found".into())),
+        Some((item_type, _)) if item_type != slot.item_type => {
+            Err(AppError::BadRequest(format!("Item cannot be equipped as {}", slot.field)))
+        }
+        Some((_, Some(quantity))) if quantity > 0 => Ok(()),
+        Some(_) => Err(AppError::Forbidden("Item not owned".into())),
+    }
+}
+
+/// Clears any slot holding `item_id` once the player no longer owns one.
+/// Runs in the transaction that took the item out of the inventory.
+pub async fn unequip_if_gone(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    player_id: Uuid,
+    item_id: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"UPDATE players SET
+            equipped_avatar_frame = NULLIF(equipped_avatar_frame, $3),
+            equipped_title = NULLIF(equipped_title, $3),
+            equipped_banner = NULLIF(equipped_banner, $3)
+        WHERE id = $1 AND tenant_id = $2
+            AND $3 IN (equipped_avatar_frame, equipped_title, equipped_banner)
+            AND NOT EXISTS (
+                SELECT 1 FROM player_inventory
+                WHERE tenant_id = $2 AND player_id = $1 AND item_id = $3 AND quantity > 0
+            )"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .bind(item_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// id, item_type, name, description, metadata
+type ItemRow = (String, String, String, Option<String>, Option<Value>);
+
+/// The player's equipped items keyed by slot (`null` for empty slots), with
+/// names and descriptions from the `store_item` translations.
+pub async fn equipped_items(
+    db: &PgPool,
+    tenant_id: &str,
+    player_id: Uuid,
+    t: &Translations,
+) -> AppResult<Value> {
+    let rows: Vec<ItemRow> = sqlx::query_as(
+        r#"SELECT si.id, si.item_type, si.name, si.description, si.metadata
+        FROM players p
+        JOIN store_items si ON si.tenant_id = p.tenant_id
+            AND si.id IN (p.equipped_avatar_frame, p.equipped_title, p.equipped_banner)
+        WHERE p.id = $1 AND p.tenant_id = $2"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?;
+
+    let mut by_type: HashMap<String, Value> = rows
+        .into_iter()
+        .map(|(id, item_type, name, description, metadata)| {
+            let name = t.text("store_item", &format!("{}.name", id), &name);
+            let description = description
+                .as_deref()
+                .map(|d| t.text("store_item", &format!("{}.description", id), d));
+            let item = json!({"id": id, "name": name, "description": description, "metadata": metadata});
+            (item_type, item)
+        })
+        .collect();
+
+    let mut out = Map::new();
+    for slot in SLOTS {
+        out.insert(slot.field.into(), by_type.remove(slot.item_type).unwrap_or(Value::Null));
+    }
+    Ok(Value::Object(out))
+}
+
+/// Equipped item ids for a batch of players, keyed by player id as text.
+pub async fn equipped_ids(db: &PgPool, tenant_id: &str, player_ids: &[String]) -> AppResult<HashMap<String, Value>> {
+    let rows: Vec<(String, Value)> = sqlx::query_as(&format!(
+        "SELECT p.id::text, {} FROM players p WHERE p.tenant_id = $1 AND p.id::text = ANY($2)",
+        EQUIPPED_SQL
+    ))
+    .bind(tenant_id)
+    .bind(player_ids)
+    .fetch_all(db)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
@@ -0,0 +1,130 @@
+//! Cron expressions for job schedules, evaluated in UTC.
+//!
+//! Five fields (`minute hour day-of-month month day-of-week`) or six with a
+//! leading seconds field. Each field takes `*`, a number, a range `a-b`, a
+//! step `*/n`, `a-b/n` or `a/n`, or a comma-separated list of those. Day of
+//! week runs 0-6 from Sunday, and 7 is also Sunday. As in classic cron,
+//! when both day fields are restricted a day matches if either does.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Five years of days, hours, minutes and seconds to search before giving
+/// up on an expression that never matches (`0 0 30 2 *`).
+const MAX_STEPS: usize = 5 * 366 + 24 + 60 + 60;
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Cron, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let (seconds, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => return Err(format!("expected 5 or 6 fields, got {}", n)),
+        };
+
+        let mut weekdays = parse_field(rest[4], 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            seconds: parse_field(seconds, 0, 59)?,
+            minutes: parse_field(rest[0], 0, 59)?,
+            hours: parse_field(rest[1], 0, 23)?,
+            days: parse_field(rest[2], 1, 31)?,
+            months: parse_field(rest[3], 1, 12)?,
+            weekdays,
+            any_day: rest[2] == "*",
+            any_weekday: rest[4] == "*",
+        })
+    }
+
+    /// The first matching time strictly after `after`, or `None` if the
+    /// expression never matches.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.duration_trunc(Duration::seconds(1)).ok()? + Duration::seconds(1);
+
+        for _ in 0..MAX_STEPS {
+            if !has(self.months, t.month()) || !self.day_matches(t) {
+                t = t.duration_trunc(Duration::days(1)).ok()? + Duration::days(1);
+            } else if !has(self.hours, t.hour()) {
+                t = t.duration_trunc(Duration::hours(1)).ok()? + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t = t.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+            } else if !has(self.seconds, t.second()) {
+                t += Duration::seconds(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let day = has(self.days, t.day());
+        let weekday = has(self.weekdays, t.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("bad step in '{}'", part))?;
+                if step == 0 {
+                    return Err(format!("step must be positive in '{}'", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            _ => {
+                let (lo, hi) = match range.split_once('-') {
+                    Some((lo, hi)) => (parse_value(lo, part)?, parse_value(hi, part)?),
+                    // `a/n` runs from a to the end of the field.
+                    None if part.contains('/') => (parse_value(range, part)?, max),
+                    None => {
+                        let v = parse_value(range, part)?;
+                        (v, v)
+                    }
+                };
+                if lo < min || hi > max || lo > hi {
+                    return Err(format!("'{}' is outside {}-{}", part, min, max));
+                }
+                (lo, hi)
+            }
+        };
+
+        for v in (lo..=hi).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("bad value in '{}'", part))
+}
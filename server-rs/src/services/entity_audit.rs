@@ -0,0 +1,227 @@
+//! Soft deletion and change history of admin-managed entities
+//! (`entity_audit`).
+//!
+//! Admin writes to games, categories, store items and battle passes go
+//! through an [`Audit`]: it snapshots the row before the change and again
+//! after, in the same transaction, and records both. A game's snapshot
+//! includes its category assignments, a store item's its bundle contents
+//! and a battle pass's its tiers, so changes to those show up too. Changes
+//! made by scheduled jobs are recorded without an actor.
+//!
+//! Deleting an entity sets `deleted_at` and clears `is_active`, which
+//! already hides it everywhere players look, while inventories and progress
+//! that point at it stay valid. Restoring it brings back the `is_active` it
+//! had when it was deleted, except for battle passes: only one can be
+//! active, so they come back inactive.
+
+use serde_json::Value;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+pub const GAME: &str = "game";
+pub const CATEGORY: &str = "category";
+pub const STORE_ITEM: &str = "store_item";
+pub const BATTLE_PASS: &str = "battle_pass";
+
+/// Entity types and their tables.
+pub const ENTITIES: &[(&str, &str)] = &[
+    (GAME, "custom_games"),
+    (CATEGORY, "game_categories"),
+    (STORE_ITEM, "store_items"),
+    (BATTLE_PASS, "battle_passes"),
+];
+
+fn table(entity_type: &str) -> AppResult<&'static str> {
+    ENTITIES
+        .iter()
+        .find(|(t, _)| *t == entity_type)
+        .map(|(_, table)| *table)
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown entity type: {}", entity_type)))
+}
+
+/// The entity's row as JSON, or `None` if there's no such entity.
+async fn snapshot(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    entity_type: &str,
+    entity_id: &str,
+) -> AppResult<Option<Value>> {
+    let sql = match entity_type {
+        GAME => {
+            r#"SELECT to_jsonb(t) || jsonb_build_object('categories', COALESCE(
+                (SELECT jsonb_agg(a.category_id ORDER BY a.sort_order, a.category_id) FROM game_category_assignments a
+                WHERE a.tenant_id = t.tenant_id AND a.game_id = t.id), '[]'::jsonb))
+            FROM custom_games t WHERE t.id = $1 AND t.tenant_id = $2"#
+                .to_string()
+        }
+        STORE_ITEM => {
+            r#"SELECT to_jsonb(t) || jsonb_build_object('bundle_contents', COALESCE(
+                (SELECT jsonb_agg(jsonb_build_object('item_id', b.item_id, 'quantity', b.quantity) ORDER BY b.item_id)
+                FROM bundle_contents b WHERE b.tenant_id = t.tenant_id AND b.bundle_id = t.id), '[]'::jsonb))
+            FROM store_items t WHERE t.id = $1 AND t.tenant_id = $2"#
+                .to_string()
+        }
+        BATTLE_PASS => {
+            r#"SELECT to_jsonb(t) || jsonb_build_object('tiers', COALESCE(
+                (SELECT jsonb_agg(jsonb_build_object('tier', bt.tier, 'xp_threshold', bt.xp_threshold, 'rewards',
+                    (SELECT COALESCE(jsonb_agg(to_jsonb(r) - 'tenant_id' - 'battle_pass_id' - 'tier' ORDER BY r.track), '[]'::jsonb)
+                    FROM battle_pass_rewards r WHERE r.battle_pass_id = bt.battle_pass_id AND r.tier = bt.tier)) ORDER BY bt.tier)
+                FROM battle_pass_tiers bt WHERE bt.battle_pass_id = t.id), '[]'::jsonb))
+            FROM battle_passes t WHERE t.id::text = $1 AND t.tenant_id = $2"#
+                .to_string()
+        }
+        _ => format!(
+            "SELECT to_jsonb(t) FROM {} t WHERE t.id::text = $1 AND t.tenant_id = $2",
+            table(entity_type)?
+        ),
+    };
+    Ok(sqlx::query_scalar(&sql)
+        .bind(entity_id)
+        .bind(tenant_id)
+        .fetch_optional(conn)
+        .await?)
+}
+
+fn is_deleted(snapshot: &Value) -> bool {
+    !snapshot["deleted_at"].is_null()
+}
+
+/// An admin change in progress, holding the entity as it was before.
+pub struct Audit {
+    entity_type: &'static str,
+    entity_id: String,
+    before: Option<Value>,
+}
+
+impl Audit {
+    pub async fn begin(
+        conn: &mut PgConnection,
+        tenant_id: &str,
+        entity_type: &'static str,
+        entity_id: &str,
+    ) -> AppResult<Audit> {
+        let before = snapshot(conn, tenant_id, entity_type, entity_id).await?;
+        Ok(Audit { entity_type, entity_id: entity_id.to_string(), before })
+    }
+
+    /// The entity before the change, if it existed and isn't deleted.
+    pub fn existing(&self) -> Option<&Value> {
+        self.before.as_ref().filter(|b| !is_deleted(b))
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.before.as_ref().is_some_and(is_deleted)
+    }
+
+    /// Snapshots the entity after the change and records it: a create,
+    /// update, delete or restore, depending on what changed. Nothing is
+    /// recorded if nothing did.
+    pub async fn record(
+        self,
+        conn: &mut PgConnection,
+        tenant_id: &str,
+        actor_id: impl Into<Option<Uuid>>,
+    ) -> AppResult<()> {
+        let after = snapshot(conn, tenant_id, self.entity_type, &self.entity_id).await?;
+        let action = match (&self.before, &after) {
+            (before, after) if before == after => return Ok(()),
+            (None, _) => "create",
+            (Some(b), Some(a)) if !is_deleted(b) && is_deleted(a) => "delete",
+            (Some(b), Some(a)) if is_deleted(b) && !is_deleted(a) => "restore",
+            _ => "update",
+        };
+        sqlx::query(
+            r#"INSERT INTO entity_audit (tenant_id, entity_type, entity_id, action, before, after, actor_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(tenant_id)
+        .bind(self.entity_type)
+        .bind(&self.entity_id)
+        .bind(action)
+        .bind(&self.before)
+        .bind(&after)
+        .bind(actor_id.into())
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Soft-deletes an entity. Returns false if there's no such entity or it's
+/// already deleted.
+pub async fn soft_delete(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    actor_id: Uuid,
+    entity_type: &'static str,
+    entity_id: &str,
+) -> AppResult<bool> {
+    let audit = Audit::begin(conn, tenant_id, entity_type, entity_id).await?;
+    if audit.existing().is_none() {
+        return Ok(false);
+    }
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NOW(), is_active = false WHERE id::text = $1 AND tenant_id = $2",
+        table(entity_type)?
+    );
+    sqlx::query(&sql).bind(entity_id).bind(tenant_id).execute(&mut *conn).await?;
+    audit.record(conn, tenant_id, actor_id).await?;
+    Ok(true)
+}
+
+/// Restores a soft-deleted entity. Returns false if there's no such
+/// entity or it isn't deleted.
+pub async fn restore(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    actor_id: Uuid,
+    entity_type: &str,
+    entity_id: &str,
+) -> AppResult<bool> {
+    let entity_type = ENTITIES
+        .iter()
+        .map(|(t, _)| *t)
+        .find(|t| *t == entity_type)
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown entity type: {}", entity_type)))?;
+    let audit = Audit::begin(conn, tenant_id, entity_type, entity_id).await?;
+    if !audit.is_deleted() {
+        return Ok(false);
+    }
+
+    let was_active: Option<bool> = sqlx::query_scalar(
+        r#"SELECT (before->>'is_active')::boolean FROM entity_audit
+        WHERE tenant_id = $1 AND entity_type = $2 AND entity_id = $3 AND action = 'delete'
+        ORDER BY created_at DESC LIMIT 1"#,
+    )
+    .bind(tenant_id)
+    .bind(entity_type)
+    .bind(entity_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .flatten();
+    let is_active = entity_type != BATTLE_PASS && was_active.unwrap_or(true);
+
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NULL, is_active = $3 WHERE id::text = $1 AND tenant_id = $2",
+        table(entity_type)?
+    );
+    sqlx::query(&sql)
+        .bind(entity_id)
+        .bind(tenant_id)
+        .bind(is_active)
+        .execute(&mut *conn)
+        .await?;
+    audit.record(conn, tenant_id, actor_id).await?;
+    Ok(true)
+}
+
+/// The tenant's deleted entities of a type, most recently deleted first.
+pub async fn list_deleted(db: &sqlx::PgPool, tenant_id: &str, entity_type: &str) -> AppResult<Vec<Value>> {
+    let sql = format!(
+        "SELECT to_jsonb(t) FROM {} t WHERE t.tenant_id = $1 AND t.deleted_at IS NOT NULL ORDER BY t.deleted_at DESC",
+        table(entity_type)?
+    );
+    Ok(sqlx::query_scalar(&sql).bind(tenant_id).fetch_all(db).await?)
+}
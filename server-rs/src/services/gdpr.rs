@@ -0,0 +1,671 @@
+//! Background processing of GDPR requests (`gdpr_requests`).
+//!
+//! Exports: every `pending` export request is claimed by the export job,
+//! which gathers the player's rows from every table that holds their data
+//! and packs them into a ZIP of JSON files (one per table, plus
+//! `manifest.json`). The archive is uploaded to object storage and the
+//! status endpoint hands out short-lived presigned URLs for it until it
+//! expires, after which the object is deleted. Without object storage
+//! configured, the archive is stored on the request as a data URL instead.
+//!
+//! Deletions: a `delete` request is executed once `deletion_grace_days`
+//! have passed. Subscriptions of organisations the player owns are
+//! cancelled in Stripe and export archives are deleted. Then, in one
+//! transaction, the player is taken out of open auctions, every personal
+//! row is deleted and the player row becomes an anonymous tombstone that
+//! their comments, reviews and matches keep pointing at.
+//! Outstanding tokens are revoked and an audit row is written to
+//! `gdpr_deletion_audit`. Failed deletions are retried hourly.
+
+use chrono::{Datelike, Timelike, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::GdprConfig;
+use crate::error::{AppError, AppResult};
+use crate::services::auction_house;
+use crate::services::object_storage::ObjectStorage;
+use crate::AppState;
+
+/// A `processing` claim older than this is assumed dead and retried.
+const STALE_CLAIM_MINS: i32 = 60;
+
+/// Rows per table in an export. Larger tables are truncated to the newest
+/// rows and flagged as such in the manifest.
+const MAX_ROWS: i64 = 50_000;
+
+/// One file in the archive: name and a query returning one JSON object
+/// per row. `$1` is the player id as text, `$2` the tenant.
+const EXPORT_SECTIONS: &[(&str, &str)] = &[
+    (
+        "profile",
+        "SELECT to_jsonb(t) - 'password_hash' - 'sso_provider_id' FROM players t WHERE t.id::text = $1 AND t.tenant_id = $2",
+    ),
+    (
+        "parental_consents",
+        "SELECT to_jsonb(t) - 'token_hash' FROM parental_consents t WHERE t.player_id::text = $1 AND t.tenant_id = $2",
+    ),
+    ("settings", "SELECT to_jsonb(t) FROM player_settings t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("game_progress", "SELECT to_jsonb(t) FROM game_progress t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("game_progress_metrics", "SELECT to_jsonb(t) FROM game_progress_metrics t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("playtime", "SELECT to_jsonb(t) FROM playtime_days t WHERE t.player_id::text = $1 AND t.tenant_id = $2 ORDER BY t.day DESC"),
+    (
+        "scores",
+        "SELECT to_jsonb(t) FROM score_history t WHERE t.player_id::text = $1 AND t.tenant_id = $2 ORDER BY t.created_at DESC",
+    ),
+    ("daily_runs", "SELECT to_jsonb(t) - 'run_token' FROM daily_runs t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("achievements", "SELECT to_jsonb(t) FROM player_achievements t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("leaderboard_entries", "SELECT to_jsonb(t) FROM leaderboard_entries t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("season_results", "SELECT to_jsonb(t) FROM season_results t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("replays", "SELECT to_jsonb(t) FROM replays t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("matches", "SELECT to_jsonb(t) FROM multiplayer_match_players t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "comments",
+        "SELECT to_jsonb(t) FROM comments t WHERE t.player_id::text = $1 AND t.tenant_id = $2 ORDER BY t.created_at DESC",
+    ),
+    ("comment_reactions", "SELECT to_jsonb(t) FROM comment_reactions t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("reviews", "SELECT to_jsonb(t) FROM game_reviews t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("review_votes", "SELECT to_jsonb(t) FROM review_votes t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "reports_filed",
+        "SELECT to_jsonb(t) FROM content_reports t WHERE t.reporter_id::text = $1 AND t.tenant_id = $2",
+    ),
+    ("appeals", "SELECT to_jsonb(t) FROM moderation_appeals t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "chat_messages",
+        "SELECT to_jsonb(t) FROM chat_messages t WHERE t.sender_id::text = $1 AND t.tenant_id = $2 ORDER BY t.created_at DESC",
+    ),
+    (
+        "friendships",
+        "SELECT to_jsonb(t) FROM friendships t WHERE (t.player_id::text = $1 OR t.friend_id::text = $1) AND t.tenant_id = $2",
+    ),
+    ("wallet", "SELECT to_jsonb(t) FROM player_wallets t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "transactions",
+        "SELECT to_jsonb(t) FROM economy_transactions t WHERE t.player_id::text = $1 AND t.tenant_id = $2 ORDER BY t.created_at DESC",
+    ),
+    ("inventory", "SELECT to_jsonb(t) FROM player_inventory t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("battle_pass", "SELECT to_jsonb(t) FROM player_battle_pass t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("battle_pass_xp", "SELECT to_jsonb(t) FROM battle_pass_xp_awards t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("crate_openings", "SELECT to_jsonb(t) FROM crate_openings t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "promo_code_redemptions",
+        "SELECT to_jsonb(t) FROM promo_code_redemptions t WHERE t.player_id::text = $1 AND t.tenant_id = $2",
+    ),
+    ("recipes", "SELECT to_jsonb(t) FROM player_recipes t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "crafting_attempts",
+        "SELECT to_jsonb(t) FROM crafting_attempts t WHERE t.player_id::text = $1 AND t.tenant_id = $2 ORDER BY t.created_at DESC",
+    ),
+    (
+        "trades",
+        "SELECT to_jsonb(t) FROM trade_offers t WHERE (t.from_player_id::text = $1 OR t.to_player_id::text = $1) AND t.tenant_id = $2",
+    ),
+    ("auctions", "SELECT to_jsonb(t) FROM auctions t WHERE t.seller_id::text = $1 AND t.tenant_id = $2"),
+    ("auction_bids", "SELECT to_jsonb(t) FROM auction_bids t WHERE t.bidder_id::text = $1 AND t.tenant_id = $2"),
+    (
+        "turn_games",
+        "SELECT to_jsonb(t) FROM turn_games t WHERE $1::uuid = ANY(t.player_ids) AND t.tenant_id = $2",
+    ),
+    (
+        "organisations",
+        "SELECT to_jsonb(t) FROM organisation_members t WHERE t.player_id::text = $1 AND t.tenant_id = $2",
+    ),
+    (
+        "classrooms",
+        "SELECT to_jsonb(t) FROM classroom_students t WHERE t.player_id::text = $1 AND t.tenant_id = $2",
+    ),
+    ("roster_students", "SELECT to_jsonb(t) FROM roster_students t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("crash_reports", "SELECT to_jsonb(t) FROM crash_reports t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+    ("gdpr_requests", "SELECT to_jsonb(t) - 'download_url' FROM gdpr_requests t WHERE t.player_id::text = $1 AND t.tenant_id = $2"),
+];
+
+// ---------------------------------------------------------------------------
+// Exports
+// ---------------------------------------------------------------------------
+
+/// Claims and runs export requests until none are left. Returns the
+/// number completed.
+pub async fn process_pending_exports(
+    db: &sqlx::PgPool,
+    storage: Option<&ObjectStorage>,
+    config: &GdprConfig,
+) -> AppResult<usize> {
+    let mut completed = 0;
+    loop {
+        let claimed: Option<(Uuid, String, Uuid)> = sqlx::query_as(
+            r#"UPDATE gdpr_requests SET status = 'processing', started_at = NOW()
+            WHERE id = (
+                SELECT id FROM gdpr_requests
+                WHERE request_type = 'export'
+                    AND (status = 'pending'
+                        OR (status = 'processing' AND started_at < NOW() - make_interval(mins => $1)))
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, tenant_id, player_id"#,
+        )
+        .bind(STALE_CLAIM_MINS)
+        .fetch_optional(db)
+        .await?;
+        let Some((request_id, tenant_id, player_id)) = claimed else {
+            return Ok(completed);
+        };
+
+        match run_export(db, storage, config, request_id, &tenant_id, player_id).await {
+            Ok(()) => completed += 1,
+            Err(e) => {
+                tracing::error!("GDPR export {} failed: {}", request_id, e);
+                sqlx::query(
+                    "UPDATE gdpr_requests SET status = 'failed', error = $1, completed_at = NOW() WHERE id = $2",
+                )
+                .bind(e.to_string())
+                .bind(request_id)
+                .execute(db)
+                .await?;
+            }
+        }
+    }
+}
+
+async fn run_export(
+    db: &sqlx::PgPool,
+    storage: Option<&ObjectStorage>,
+    config: &GdprConfig,
+    request_id: Uuid,
+    tenant_id: &str,
+    player_id: Uuid,
+) -> AppResult<()> {
+    let archive = build_archive(db, tenant_id, player_id, request_id).await?;
+    let size = archive.len() as i64;
+    let expires = Utc::now() + chrono::Duration::days(config.export_expiry_days);
+
+    let (storage_key, download_url) = match storage {
+        Some(storage) => {
+            let key = export_key(tenant_id, player_id, request_id);
+            storage.put(&key, "application/zip", archive).await?;
+            (Some(key), None)
+        }
+        None => {
+            use base64::Engine;
+            let url = format!(
+                "data:application/zip;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(archive)
+            );
+            (None, Some(url))
+        }
+    };
+
+    sqlx::query(
+        r#"UPDATE gdpr_requests
+        SET status = 'completed', storage_key = $1, download_url = $2, size_bytes = $3,
+            completed_at = NOW(), expires_at = $4, error = NULL
+        WHERE id = $5"#,
+    )
+    .bind(storage_key)
+    .bind(download_url)
+    .bind(size)
+    .bind(expires)
+    .bind(request_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+fn export_key(tenant_id: &str, player_id: Uuid, request_id: Uuid) -> String {
+    format!("gdpr-exports/{}/{}/{}.zip", tenant_id, player_id, request_id)
+}
+
+/// Deletes archives past their expiry and marks the requests `expired`.
+pub async fn expire_exports(db: &sqlx::PgPool, storage: Option<&ObjectStorage>) -> AppResult<()> {
+    let expired: Vec<(Uuid, Option<String>)> = sqlx::query_as(
+        r#"SELECT id, storage_key FROM gdpr_requests
+        WHERE request_type = 'export' AND status = 'completed' AND expires_at < NOW()
+        LIMIT 500"#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    for (id, key) in expired {
+        if let (Some(storage), Some(key)) = (storage, key.as_deref()) {
+            if let Err(e) = storage.delete(key).await {
+                tracing::warn!("Could not delete expired export {}: {}", key, e);
+                continue;
+            }
+        }
+        sqlx::query(
+            "UPDATE gdpr_requests SET status = 'expired', storage_key = NULL, download_url = NULL WHERE id = $1",
+        )
+        .bind(id)
+        .execute(db)
+        .await?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Deletion
+// ---------------------------------------------------------------------------
+
+/// Rows deleted outright: `(table, player column)`. Tables whose rows
+/// involve another player (friendships, trades) match either side.
+const DELETE_TABLES: &[(&str, &str)] = &[
+    ("player_settings", "player_id"),
+    ("parental_consents", "player_id"),
+    ("game_progress", "player_id"),
+    ("game_progress_metrics", "player_id"),
+    ("playtime_days", "player_id"),
+    ("score_history", "player_id"),
+    ("player_achievements", "player_id"),
+    ("daily_runs", "player_id"),
+    ("leaderboard_entries", "player_id"),
+    ("decayed_rankings", "player_id"),
+    ("season_results", "player_id"),
+    ("replays", "player_id"),
+    ("session_handoffs", "player_id"),
+    ("idempotency_keys", "player_id"),
+    ("auth_sessions", "player_id"),
+    ("player_presence", "player_id"),
+    ("player_wallets", "player_id"),
+    ("economy_transactions", "player_id"),
+    ("player_inventory", "player_id"),
+    ("player_battle_pass", "player_id"),
+    ("battle_pass_xp_awards", "player_id"),
+    ("crate_openings", "player_id"),
+    ("promo_code_redemptions", "player_id"),
+    ("player_recipes", "player_id"),
+    ("crafting_attempts", "player_id"),
+    ("anticheat_flags", "player_id"),
+    ("game_action_log", "player_id"),
+    ("organisation_members", "player_id"),
+    ("classroom_students", "player_id"),
+    ("roster_students", "player_id"),
+    ("chat_messages", "sender_id"),
+    ("comment_reactions", "player_id"),
+    ("content_reports", "reporter_id"),
+    ("review_votes", "player_id"),
+    ("moderation_appeals", "player_id"),
+    ("friendships", "player_id"),
+    ("friendships", "friend_id"),
+    ("trade_offers", "from_player_id"),
+    ("trade_offers", "to_player_id"),
+    ("auction_bids", "bidder_id"),
+    ("auctions", "seller_id"),
+];
+
+/// Executes every deletion request past its grace period. Returns the
+/// number of accounts deleted.
+pub async fn process_due_deletions(state: &AppState) -> AppResult<usize> {
+    let mut completed = 0;
+    loop {
+        let claimed: Option<(Uuid, String, Uuid, chrono::DateTime<Utc>)> = sqlx::query_as(
+            r#"UPDATE gdpr_requests SET status = 'processing', started_at = NOW()
+            WHERE id = (
+                SELECT id FROM gdpr_requests
+                WHERE request_type = 'delete'
+                    AND created_at < NOW() - make_interval(days => $1)
+                    AND (status = 'pending'
+                        OR (status = 'processing' AND started_at < NOW() - make_interval(mins => $2))
+                        OR (status = 'failed' AND completed_at < NOW() - make_interval(mins => $2)))
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, tenant_id, player_id, created_at"#,
+        )
+        .bind(state.config.gdpr.deletion_grace_days)
+        .bind(STALE_CLAIM_MINS)
+        .fetch_optional(&state.db)
+        .await?;
+        let Some((request_id, tenant_id, player_id, requested_at)) = claimed else {
+            return Ok(completed);
+        };
+
+        match run_deletion(state, request_id, &tenant_id, player_id, requested_at).await {
+            Ok(()) => completed += 1,
+            Err(e) => {
+                tracing::error!("GDPR deletion {} failed: {}", request_id, e);
+                sqlx::query(
+                    "UPDATE gdpr_requests SET status = 'failed', error = $1, completed_at = NOW() WHERE id = $2",
+                )
+                .bind(e.to_string())
+                .bind(request_id)
+                .execute(&state.db)
+                .await?;
+            }
+        }
+    }
+}
+
+async fn run_deletion(
+    state: &AppState,
+    request_id: Uuid,
+    tenant_id: &str,
+    player_id: Uuid,
+    requested_at: chrono::DateTime<Utc>,
+) -> AppResult<()> {
+    let pid = player_id.to_string();
+
+    // External side effects first, so a failure leaves the data in place
+    // for the retry.
+    let canceled = cancel_owned_subscriptions(state, tenant_id, &pid).await?;
+    let exports_deleted = delete_export_archives(state, tenant_id, player_id).await?;
+
+    let mut counts = serde_json::Map::new();
+    let mut tx = state.db.begin().await?;
+
+    let auctions = auction_house::withdraw_player(&mut tx, tenant_id, player_id).await?;
+    counts.insert("auctions_withdrawn".into(), json!(auctions));
+
+    // Their helpful votes come off the reviews' cached totals
+    sqlx::query(
+        r#"UPDATE game_reviews r SET helpful_count = GREATEST(r.helpful_count - 1, 0)
+        FROM review_votes v
+        WHERE v.review_id = r.id AND v.player_id::text = $1 AND v.tenant_id = $2"#,
+    )
+    .bind(&pid)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?;
+
+    for (table, column) in DELETE_TABLES {
+        let deleted = sqlx::query(&format!(
+            "DELETE FROM {} WHERE {}::text = $1 AND tenant_id = $2",
+            table, column
+        ))
+        .bind(&pid)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let total = counts.get(*table).and_then(Value::as_u64).unwrap_or(0) + deleted;
+        counts.insert(table.to_string(), json!(total));
+    }
+
+    let telemetry = sqlx::query(
+        "UPDATE telemetry_events SET player_id = NULL WHERE player_id::text = $1 AND tenant_id = $2",
+    )
+    .bind(&pid)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    counts.insert("telemetry_events_anonymized".into(), json!(telemetry));
+
+    let crashes = sqlx::query(
+        "UPDATE crash_reports SET player_id = NULL WHERE player_id::text = $1 AND tenant_id = $2",
+    )
+    .bind(&pid)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    counts.insert("crash_reports_anonymized".into(), json!(crashes));
+
+    // Stored turn-based games hold every player's moves; they end here
+    let turn_games = sqlx::query("DELETE FROM turn_games WHERE $1 = ANY(player_ids) AND tenant_id = $2")
+        .bind(player_id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    counts.insert("turn_games".into(), json!(turn_games));
+
+    let exports = sqlx::query(
+        "DELETE FROM gdpr_requests WHERE player_id = $1 AND tenant_id = $2 AND request_type = 'export'",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    counts.insert("gdpr_export_requests".into(), json!(exports));
+
+    // Comments and reviews stay, attributed to the tombstone below
+    for (table, key) in [("comments", "comments_anonymized"), ("game_reviews", "reviews_anonymized")] {
+        let n: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*)::bigint FROM {} WHERE player_id::text = $1 AND tenant_id = $2",
+            table
+        ))
+        .bind(&pid)
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        counts.insert(key.into(), json!(n));
+    }
+
+    let tombstoned = sqlx::query(
+        r#"UPDATE players SET
+            email = NULL, password_hash = NULL, display_name = 'Deleted Player',
+            avatar_character = 'guha', sso_provider = NULL, sso_provider_id = NULL,
+            admin_role = NULL, ban_reason = NULL, gdpr_consent = FALSE, gdpr_consent_at = NULL,
+            total_score = 0, games_played = 0, total_play_time = 0, deleted_at = NOW()
+        WHERE id::text = $1 AND tenant_id = $2"#,
+    )
+    .bind(&pid)
+    .bind(tenant_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if tombstoned == 0 {
+        return Err(AppError::NotFound(format!("Player {} not found", player_id)));
+    }
+
+    sqlx::query(
+        r#"INSERT INTO gdpr_deletion_audit
+            (request_id, tenant_id, player_id, rows_affected, subscriptions_canceled, exports_deleted, requested_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+    )
+    .bind(request_id)
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(Value::Object(counts))
+    .bind(&canceled)
+    .bind(exports_deleted)
+    .bind(requested_at)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE gdpr_requests SET status = 'completed', completed_at = NOW(), error = NULL WHERE id = $1",
+    )
+    .bind(request_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    crate::middleware::auth::revoke_tokens(
+        &state.db,
+        &state.cache,
+        tenant_id,
+        player_id,
+        state.config.jwt.refresh_expiry_secs.max(0) as u64,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Cancels, immediately, the live subscriptions of organisations the
+/// player owns. Returns the Stripe subscription ids cancelled.
+async fn cancel_owned_subscriptions(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: &str,
+) -> AppResult<Vec<String>> {
+    let subscriptions: Vec<(String, Option<String>)> = sqlx::query_as(
+        r#"SELECT s.id, s.stripe_subscription_id
+        FROM subscriptions s JOIN organisations o ON o.id = s.organisation_id
+        WHERE o.owner_id = $1 AND o.tenant_id = $2
+            AND s.status IN ('active', 'trialing', 'past_due', 'unpaid', 'paused', 'incomplete')"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut canceled = Vec::new();
+    for (id, stripe_id) in subscriptions {
+        if let Some(stripe_id) = stripe_id {
+            let stripe = state.stripe.as_ref().ok_or_else(|| {
+                AppError::Internal("Stripe is not configured; cannot cancel subscription".into())
+            })?;
+            stripe.cancel_subscription(&stripe_id, true).await?;
+            canceled.push(stripe_id);
+        }
+        sqlx::query(
+            "UPDATE subscriptions SET status = 'canceled', canceled_at = NOW(), ended_at = NOW(), updated_at = NOW() WHERE id = $1",
+        )
+        .bind(&id)
+        .execute(&state.db)
+        .await?;
+    }
+    Ok(canceled)
+}
+
+async fn delete_export_archives(state: &AppState, tenant_id: &str, player_id: Uuid) -> AppResult<i32> {
+    let keys: Vec<String> = sqlx::query_scalar(
+        r#"SELECT storage_key FROM gdpr_requests
+        WHERE player_id = $1 AND tenant_id = $2 AND request_type = 'export' AND storage_key IS NOT NULL"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    if let Some(storage) = &state.storage {
+        for key in &keys {
+            storage.delete(key).await?;
+        }
+    }
+    Ok(keys.len() as i32)
+}
+
+// ---------------------------------------------------------------------------
+// Archive
+// ---------------------------------------------------------------------------
+
+/// Gathers every [`EXPORT_SECTIONS`] table into a ZIP archive.
+pub async fn build_archive(
+    db: &sqlx::PgPool,
+    tenant_id: &str,
+    player_id: Uuid,
+    request_id: Uuid,
+) -> AppResult<Vec<u8>> {
+    let pid = player_id.to_string();
+    let mut zip = ZipWriter::default();
+    let mut files = serde_json::Map::new();
+
+    for (name, sql) in EXPORT_SECTIONS {
+        let mut rows: Vec<Value> = sqlx::query_scalar(&format!("{} LIMIT {}", sql, MAX_ROWS + 1))
+            .bind(&pid)
+            .bind(tenant_id)
+            .fetch_all(db)
+            .await?;
+        let truncated = rows.len() as i64 > MAX_ROWS;
+        rows.truncate(MAX_ROWS as usize);
+
+        let file = format!("{}.json", name);
+        files.insert(file.clone(), json!({"rows": rows.len(), "truncated": truncated}));
+        let body = if *name == "profile" {
+            serde_json::to_vec_pretty(&rows.into_iter().next())
+        } else {
+            serde_json::to_vec_pretty(&rows)
+        };
+        zip.add(&file, &body.unwrap_or_default());
+    }
+
+    let manifest = json!({
+        "requestId": request_id,
+        "playerId": player_id,
+        "tenantId": tenant_id,
+        "exportedAt": Utc::now(),
+        "files": files,
+    });
+    zip.add("manifest.json", &serde_json::to_vec_pretty(&manifest).unwrap_or_default());
+
+    Ok(zip.finish())
+}
+
+/// Writes an uncompressed ("stored") ZIP archive. JSON compresses well,
+/// but this avoids a compression dependency and exports are small.
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let now = Utc::now();
+        let time = ((now.hour() << 11) | (now.minute() << 5) | (now.second() / 2)) as u16;
+        let date = (((now.year() - 1980).max(0) as u32) << 9 | (now.month() << 5) | now.day()) as u16;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+        let offset = self.out.len() as u32;
+
+        // Local file header
+        self.out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.out.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // stored
+        self.out.extend_from_slice(&time.to_le_bytes());
+        self.out.extend_from_slice(&date.to_le_bytes());
+        self.out.extend_from_slice(&crc.to_le_bytes());
+        self.out.extend_from_slice(&size.to_le_bytes());
+        self.out.extend_from_slice(&size.to_le_bytes());
+        self.out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(data);
+
+        // Central directory entry
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        self.central.extend_from_slice(&0x0800u16.to_le_bytes());
+        self.central.extend_from_slice(&0u16.to_le_bytes());
+        self.central.extend_from_slice(&time.to_le_bytes());
+        self.central.extend_from_slice(&date.to_le_bytes());
+        self.central.extend_from_slice(&crc.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.out.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.out.append(&mut self.central);
+
+        // End of central directory
+        self.out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]); // disk numbers
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&central_size.to_le_bytes());
+        self.out.extend_from_slice(&central_offset.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        self.out
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
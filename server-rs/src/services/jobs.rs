@@ -0,0 +1,512 @@
+//! Background jobs (`jobs`, `job_schedules`).
+//!
+//! Work that has to happen outside a request goes through a queue in
+//! Postgres, so it survives restarts and is shared by every instance. The
+//! worker claims due jobs with `FOR UPDATE SKIP LOCKED` and holds each one
+//! for its kind's visibility timeout; if the instance dies mid-job, the
+//! job becomes claimable again once the timeout passes. A failed job is
+//! retried with exponential backoff and left as `failed` after its last
+//! attempt. Finished jobs are purged after a week, failed ones after 30
+//! days.
+//!
+//! Recurring work runs from cron schedules ([`cron`](crate::services::cron)).
+//! Each schedule has a `job_schedules` row holding the next time it fires;
+//! the instance that moves `next_run_at` forward enqueues the job, and a
+//! schedule whose previous job is still queued or running is skipped.
+//! Handlers are expected to be idempotent: a job can run twice if its
+//! worker dies after finishing but before recording it.
+//!
+//! A job stores the trace context of the span that queued it, and runs in a
+//! `job` span continuing that trace.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::cron::Cron;
+use crate::services::{
+    anticheat, auction_house, battle_pass, gdpr, leaderboard, mailer, otel, roster_import, score_decay, seasons,
+    stripe_events, subscription_sync, xapi,
+};
+use crate::AppState;
+
+pub const ROTATE_SEASONS: &str = "rotate_seasons";
+pub const GDPR_EXPORTS: &str = "gdpr_exports";
+pub const GDPR_DELETIONS: &str = "gdpr_deletions";
+pub const SETTLE_AUCTIONS: &str = "settle_auctions";
+pub const WARM_LEADERBOARDS: &str = "warm_leaderboards";
+pub const REBUILD_LEADERBOARD: &str = "rebuild_leaderboard";
+pub const DECAY_LEADERBOARDS: &str = "decay_leaderboards";
+pub const DETECT_ANOMALIES: &str = "detect_anomalies";
+pub const BATTLE_PASS_WINDOWS: &str = "battle_pass_windows";
+pub const PURGE_JOBS: &str = "purge_jobs";
+pub const SEND_EMAIL: &str = "send_email";
+pub const SYNC_BILLING: &str = "sync_billing";
+pub const RETRY_STRIPE_EVENT: &str = "retry_stripe_event";
+pub const EXPORT_XAPI: &str = "export_xapi";
+pub const IMPORT_ROSTER: &str = "import_roster";
+
+/// Job kinds: name, visibility timeout in seconds, attempts before the job
+/// is marked failed.
+const KINDS: &[(&str, i32, i32)] = &[
+    (ROTATE_SEASONS, 600, 5),
+    (GDPR_EXPORTS, 1800, 5),
+    (GDPR_DELETIONS, 1800, 5),
+    (SETTLE_AUCTIONS, 300, 3),
+    (WARM_LEADERBOARDS, 900, 3),
+    (REBUILD_LEADERBOARD, 300, 3),
+    (DECAY_LEADERBOARDS, 1800, 3),
+    (DETECT_ANOMALIES, 900, 3),
+    (BATTLE_PASS_WINDOWS, 900, 3),
+    (PURGE_JOBS, 300, 3),
+    (SEND_EMAIL, 60, 8),
+    (SYNC_BILLING, 600, 3),
+    (RETRY_STRIPE_EVENT, 120, 8),
+    (EXPORT_XAPI, 600, 3),
+    (IMPORT_ROSTER, 600, 3),
+];
+
+const PURGE_SCHEDULE: &str = "15 4 * * *";
+
+/// Retry delay after the first failure, doubled on every further one.
+const BACKOFF_BASE_SECS: i64 = 30;
+const BACKOFF_MAX_SECS: i64 = 3600;
+
+/// Leaderboards of games played in this many days are warmed.
+const WARM_ACTIVE_DAYS: i32 = 7;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Job {
+    id: Uuid,
+    kind: String,
+    payload: Value,
+    attempts: i32,
+    max_attempts: i32,
+    trace_context: Option<Json<HashMap<String, String>>>,
+}
+
+struct Schedule {
+    kind: &'static str,
+    expr: String,
+    cron: Cron,
+}
+
+fn kind(name: &str) -> Option<(i32, i32)> {
+    KINDS.iter().find(|(k, _, _)| *k == name).map(|(_, timeout, attempts)| (*timeout, *attempts))
+}
+
+/// Queues a job to run at once. Returns `None` if `dedupe_key` is set and
+/// a job with that key is already queued or running.
+pub async fn enqueue<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    job_kind: &str,
+    payload: Value,
+    dedupe_key: Option<&str>,
+) -> AppResult<Option<Uuid>> {
+    let (timeout, attempts) =
+        kind(job_kind).ok_or_else(|| AppError::Internal(format!("Unknown job kind: {}", job_kind)))?;
+    let id = sqlx::query_scalar(
+        r#"INSERT INTO jobs (kind, payload, dedupe_key, max_attempts, timeout_secs, trace_context)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (dedupe_key) WHERE status IN ('queued', 'running') DO NOTHING
+        RETURNING id"#,
+    )
+    .bind(job_kind)
+    .bind(payload)
+    .bind(dedupe_key)
+    .bind(attempts)
+    .bind(timeout)
+    .bind(Json(otel::current_context()))
+    .fetch_optional(db)
+    .await?;
+    Ok(id)
+}
+
+/// Queues a run of a scheduled kind ahead of its schedule, unless one is
+/// already queued or running.
+pub async fn run_now<'e>(db: impl sqlx::PgExecutor<'e>, job_kind: &str, payload: Value) -> AppResult<()> {
+    enqueue(db, job_kind, payload, Some(&schedule_key(job_kind))).await?;
+    Ok(())
+}
+
+/// Queues a rebuild of one game's cached leaderboard, unless one is pending.
+pub async fn rebuild_leaderboard(db: &sqlx::PgPool, tenant_id: &str, game_id: &str) -> AppResult<()> {
+    let payload = json!({ "tenantId": tenant_id, "gameId": game_id });
+    let key = format!("leaderboard:{}:{}", tenant_id, game_id);
+    enqueue(db, REBUILD_LEADERBOARD, payload, Some(&key)).await?;
+    Ok(())
+}
+
+fn schedule_key(job_kind: &str) -> String {
+    format!("schedule:{}", job_kind)
+}
+
+// ---------------------------------------------------------------------------
+// Worker
+// ---------------------------------------------------------------------------
+
+/// Spawns the job worker: every `poll_interval_ms` it enqueues due
+/// schedules and claims as many due jobs as it has free slots.
+pub fn spawn_worker(state: AppState) {
+    tokio::spawn(async move {
+        let config = &state.config.jobs;
+        let schedules = schedules(&state);
+        if let Err(e) = sync_schedules(&state.db, &schedules).await {
+            tracing::error!("Failed to register job schedules: {}", e);
+        }
+
+        let slots = Arc::new(Semaphore::new(config.concurrency.max(1)));
+        let mut interval = tokio::time::interval(Duration::from_millis(config.poll_interval_ms.max(100)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = enqueue_due(&state.db, &schedules).await {
+                tracing::error!("Failed to enqueue scheduled jobs: {}", e);
+            }
+
+            let free = slots.available_permits();
+            if free == 0 {
+                continue;
+            }
+            let jobs = match claim(&state.db, free as i64).await {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    tracing::error!("Failed to claim jobs: {}", e);
+                    continue;
+                }
+            };
+            for job in jobs {
+                let Ok(slot) = slots.clone().acquire_owned().await else { return };
+                let state = state.clone();
+                let span = tracing::info_span!(
+                    "job",
+                    otel.name = %format!("job {}", job.kind),
+                    job.id = %job.id,
+                    job.kind = %job.kind,
+                    job.attempt = job.attempts,
+                );
+                if let Some(Json(cx)) = &job.trace_context {
+                    span.set_parent(otel::stored_context(cx));
+                }
+                tokio::spawn(
+                    async move {
+                        execute(&state, job).await;
+                        drop(slot);
+                    }
+                    .instrument(span),
+                );
+            }
+        }
+    });
+}
+
+/// Claims up to `limit` jobs that are due, or whose worker's visibility
+/// timeout has run out.
+async fn claim(db: &sqlx::PgPool, limit: i64) -> AppResult<Vec<Job>> {
+    let jobs = sqlx::query_as(
+        r#"UPDATE jobs SET status = 'running', attempts = attempts + 1,
+            locked_until = NOW() + make_interval(secs => timeout_secs), updated_at = NOW()
+        WHERE id IN (
+            SELECT id FROM jobs
+            WHERE (status = 'queued' AND run_at <= NOW())
+               OR (status = 'running' AND locked_until <= NOW())
+            ORDER BY run_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, payload, attempts, max_attempts, trace_context"#,
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+    Ok(jobs)
+}
+
+/// Runs a claimed job and records the outcome. `attempts` doubles as the
+/// claim token: if the job timed out and was claimed again meanwhile, this
+/// worker's result is dropped.
+async fn execute(state: &AppState, job: Job) {
+    let result = if job.attempts > job.max_attempts {
+        Err(AppError::Internal("Visibility timeout expired on the last attempt".into()))
+    } else {
+        run(state, &job).await
+    };
+
+    let recorded = match &result {
+        Ok(()) => {
+            sqlx::query(
+                r#"UPDATE jobs SET status = 'completed', locked_until = NULL, last_error = NULL,
+                    updated_at = NOW(), finished_at = NOW()
+                WHERE id = $1 AND attempts = $2 AND status = 'running'"#,
+            )
+            .bind(job.id)
+            .bind(job.attempts)
+            .execute(&state.db)
+            .await
+        }
+        Err(e) => {
+            let final_attempt = job.attempts >= job.max_attempts;
+            if final_attempt {
+                tracing::error!("Job {} ({}) failed permanently: {}", job.id, job.kind, e);
+            } else {
+                tracing::warn!("Job {} ({}) failed on attempt {}: {}", job.id, job.kind, job.attempts, e);
+            }
+            sqlx::query(
+                r#"UPDATE jobs SET status = $3, run_at = NOW() + make_interval(secs => $4),
+                    locked_until = NULL, last_error = $5, updated_at = NOW(),
+                    finished_at = CASE WHEN $3 = 'failed' THEN NOW() END
+                WHERE id = $1 AND attempts = $2 AND status = 'running'"#,
+            )
+            .bind(job.id)
+            .bind(job.attempts)
+            .bind(if final_attempt { "failed" } else { "queued" })
+            .bind(backoff_secs(job.attempts) as f64)
+            .bind(e.to_string())
+            .execute(&state.db)
+            .await
+        }
+    };
+    if let Err(e) = recorded {
+        tracing::error!("Failed to record outcome of job {}: {}", job.id, e);
+    }
+}
+
+fn backoff_secs(attempts: i32) -> i64 {
+    let exp = attempts.clamp(1, 16) as u32 - 1;
+    (BACKOFF_BASE_SECS << exp).min(BACKOFF_MAX_SECS)
+}
+
+async fn run(state: &AppState, job: &Job) -> AppResult<()> {
+    match job.kind.as_str() {
+        ROTATE_SEASONS => {
+            let n = seasons::rotate_expired_seasons(&state.db, &state.config.season).await?;
+            if n > 0 {
+                tracing::info!("Season rotation closed {} season(s)", n);
+            }
+            purge_expired_handoffs(&state.db).await?;
+        }
+        GDPR_EXPORTS => {
+            let n = gdpr::process_pending_exports(&state.db, state.storage.as_ref(), &state.config.gdpr).await?;
+            if n > 0 {
+                tracing::info!("GDPR worker completed {} export(s)", n);
+            }
+            gdpr::expire_exports(&state.db, state.storage.as_ref()).await?;
+        }
+        GDPR_DELETIONS => {
+            let n = gdpr::process_due_deletions(state).await?;
+            if n > 0 {
+                tracing::info!("GDPR worker deleted {} account(s)", n);
+            }
+        }
+        SETTLE_AUCTIONS => {
+            let n = auction_house::settle_due(state).await?;
+            if n > 0 {
+                tracing::info!("Settled {} auction(s)", n);
+            }
+        }
+        WARM_LEADERBOARDS => warm_leaderboards(state).await?,
+        REBUILD_LEADERBOARD => {
+            let (Some(tenant_id), Some(game_id)) = (job.payload["tenantId"].as_str(), job.payload["gameId"].as_str())
+            else {
+                return Err(AppError::Internal("rebuild_leaderboard needs tenantId and gameId".into()));
+            };
+            let shard_count = state.config.leaderboard.shard_count;
+            leaderboard::rebuild_from_db(&state.db, &state.cache, tenant_id, game_id, shard_count).await?;
+        }
+        DECAY_LEADERBOARDS => {
+            let n = score_decay::materialize_all(&state.db).await?;
+            if n > 0 {
+                tracing::info!("Materialized {} decayed leaderboard(s)", n);
+            }
+        }
+        DETECT_ANOMALIES => {
+            let n = anticheat::detect(&state.db).await?;
+            if n > 0 {
+                tracing::info!("Raised {} anti-cheat flag(s)", n);
+            }
+        }
+        BATTLE_PASS_WINDOWS => {
+            let opened = battle_pass::open_due(state).await?;
+            let (closed, granted) = battle_pass::close_due(state).await?;
+            if opened + closed > 0 {
+                tracing::info!(
+                    "Opened {} battle pass(es), closed {} and granted unclaimed rewards to {} player(s)",
+                    opened, closed, granted
+                );
+            }
+        }
+        PURGE_JOBS => purge(&state.db).await?,
+        SEND_EMAIL => mailer::send(&state.config.email, &job.payload).await?,
+        SYNC_BILLING => {
+            let seats = subscription_sync::sync_all_seats(state).await?;
+            let reports = subscription_sync::report_usage(state).await?;
+            let lapsed = subscription_sync::expire_grace_periods(state).await?;
+            if seats + reports + lapsed > 0 {
+                tracing::info!(
+                    "Billing sync changed {} seat count(s), sent {} usage report(s), ended {} grace period(s)",
+                    seats, reports, lapsed
+                );
+            }
+        }
+        RETRY_STRIPE_EVENT => {
+            stripe_events::retry(state, &job.payload, job.attempts >= job.max_attempts).await?
+        }
+        EXPORT_XAPI => {
+            let n = xapi::export(state).await?;
+            if n > 0 {
+                tracing::info!("Exported {} xAPI statement(s)", n);
+            }
+        }
+        IMPORT_ROSTER => {
+            roster_import::process(state, &job.payload, job.attempts >= job.max_attempts).await?
+        }
+        other => return Err(AppError::Internal(format!("No handler for job kind: {}", other))),
+    }
+    Ok(())
+}
+
+/// Deletes session handoffs past their expiry, claimed or not; their
+/// snapshots are of no more use.
+async fn purge_expired_handoffs(db: &sqlx::PgPool) -> AppResult<()> {
+    let n = sqlx::query("DELETE FROM session_handoffs WHERE expires_at < NOW()")
+        .execute(db)
+        .await?
+        .rows_affected();
+    if n > 0 {
+        tracing::info!("Purged {} expired session handoff(s)", n);
+    }
+    Ok(())
+}
+
+/// Rebuilds the cached leaderboards of recently played games from
+/// `game_progress`, so they are refreshed before they expire and pick up
+/// anything write-through missed.
+async fn warm_leaderboards(state: &AppState) -> AppResult<()> {
+    let boards: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT DISTINCT tenant_id, game_id FROM game_progress
+        WHERE last_played_at > NOW() - make_interval(days => $1)"#,
+    )
+    .bind(WARM_ACTIVE_DAYS)
+    .fetch_all(&state.db)
+    .await?;
+
+    let shard_count = state.config.leaderboard.shard_count;
+    let mut warmed = 0;
+    for (tenant_id, game_id) in &boards {
+        if leaderboard::rebuild_from_db(&state.db, &state.cache, tenant_id, game_id, shard_count).await?.is_some() {
+            warmed += 1;
+        }
+    }
+    if warmed > 0 {
+        tracing::info!("Warmed {} leaderboard(s)", warmed);
+    }
+    Ok(())
+}
+
+async fn purge(db: &sqlx::PgPool) -> AppResult<()> {
+    let purged = sqlx::query(
+        r#"DELETE FROM jobs
+        WHERE (status = 'completed' AND finished_at < NOW() - INTERVAL '7 days')
+           OR (status = 'failed' AND finished_at < NOW() - INTERVAL '30 days')"#,
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+    if purged > 0 {
+        tracing::info!("Purged {} finished job(s)", purged);
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Schedules
+// ---------------------------------------------------------------------------
+
+/// The recurring jobs, one schedule per kind. Invalid expressions are
+/// logged and the schedule is left out.
+fn schedules(state: &AppState) -> Vec<Schedule> {
+    let config = &state.config;
+    let defs: [(&'static str, &str); 11] = [
+        (ROTATE_SEASONS, &config.season.rotation_schedule),
+        (GDPR_EXPORTS, &config.gdpr.worker_schedule),
+        (GDPR_DELETIONS, &config.gdpr.worker_schedule),
+        (SETTLE_AUCTIONS, auction_house::SETTLE_SCHEDULE),
+        (WARM_LEADERBOARDS, &config.jobs.leaderboard_warm_schedule),
+        (DECAY_LEADERBOARDS, &config.jobs.leaderboard_decay_schedule),
+        (DETECT_ANOMALIES, &config.jobs.anomaly_scan_schedule),
+        (BATTLE_PASS_WINDOWS, battle_pass::WINDOW_SCHEDULE),
+        (PURGE_JOBS, PURGE_SCHEDULE),
+        (SYNC_BILLING, &config.stripe.billing_sync_schedule),
+        (EXPORT_XAPI, &config.xapi.export_schedule),
+    ];
+
+    defs.into_iter()
+        .filter_map(|(kind, expr)| match Cron::parse(expr) {
+            Ok(cron) => Some(Schedule { kind, expr: expr.to_string(), cron }),
+            Err(e) => {
+                tracing::error!("Invalid schedule for {} ('{}'): {}", kind, expr, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Registers the schedules. A new schedule fires straight away; one whose
+/// expression changed is rescheduled from now.
+async fn sync_schedules(db: &sqlx::PgPool, schedules: &[Schedule]) -> AppResult<()> {
+    for s in schedules {
+        let next = s.cron.next_after(Utc::now());
+        sqlx::query(
+            r#"INSERT INTO job_schedules (name, kind, cron, next_run_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (name) DO UPDATE SET kind = EXCLUDED.kind, cron = EXCLUDED.cron,
+                next_run_at = CASE WHEN job_schedules.cron = EXCLUDED.cron THEN job_schedules.next_run_at
+                                   ELSE COALESCE($4, 'infinity') END"#,
+        )
+        .bind(s.kind)
+        .bind(s.kind)
+        .bind(&s.expr)
+        .bind(next)
+        .execute(db)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Enqueues every schedule that is due and moves it to its next run.
+/// Advancing and enqueueing share a transaction, so a firing is neither
+/// lost nor doubled across instances.
+async fn enqueue_due(db: &sqlx::PgPool, schedules: &[Schedule]) -> AppResult<()> {
+    let due: Vec<String> = sqlx::query_scalar("SELECT name FROM job_schedules WHERE next_run_at <= NOW()")
+        .fetch_all(db)
+        .await?;
+
+    for s in schedules.iter().filter(|s| due.iter().any(|name| name == s.kind)) {
+        let next = s.cron.next_after(Utc::now());
+        let mut tx = db.begin().await?;
+        let advanced = sqlx::query(
+            r#"UPDATE job_schedules SET next_run_at = COALESCE($2, 'infinity'), last_run_at = NOW()
+            WHERE name = $1 AND next_run_at <= NOW()"#,
+        )
+        .bind(s.kind)
+        .bind(next)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if advanced == 0 {
+            continue;
+        }
+
+        run_now(&mut *tx, s.kind, json!({ "schedule": s.kind })).await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
@@ -1,24 +1,79 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+//! Sharded leaderboards in Redis.
+//!
+//! Each game's board is split over `shard_count` sorted sets by a hash of
+//! the player id. Scores are written through on every submission, but a
+//! board is only read from the cache once it has been built from
+//! `game_progress`: the build writes a meta key recording the shard layout
+//! it used, and a board without one, or with a different layout, is
+//! served from the database while a rebuild is queued. Changing
+//! `LEADERBOARD_SHARDS` is therefore safe; each board is rebuilt under the
+//! new layout the next time it is read, and its old shards are dropped.
+//!
+//! The meta key expires before the shards do, so a board whose shards
+//! could have expired is never trusted. Active boards are rebuilt from
+//! scratch every `LEADERBOARD_WARM_SCHEDULE`.
+
+use serde::{Deserialize, Serialize};
 
 use crate::cache::Cache;
 use crate::error::AppResult;
 
+/// Bumped whenever [`shard_index`] changes, so boards built with the old
+/// function are rebuilt.
+const HASH_VERSION: u32 = 1;
+
+/// Players cached per board; lower ranks are looked up in the database.
+const MAX_CACHED: i64 = 10_000;
+
+const META_TTL_SECS: u64 = 7200;
+/// Longer than the meta key, so shards never expire under a live board.
+const SHARD_TTL_SECS: i64 = 7800;
+const BUILD_LOCK_SECS: u64 = 300;
+
+/// How a board was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Layout {
+    shards: u32,
+    hash: u32,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is stable across builds, so every
+/// instance agrees on a player's shard.
 fn shard_index(player_id: &str, shard_count: u32) -> u32 {
-    let mut hasher = DefaultHasher::new();
-    player_id.hash(&mut hasher);
-    let hash = hasher.finish();
-    (hash % shard_count as u64) as u32
+    let hash = player_id.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    (hash % u64::from(shard_count.max(1))) as u32
 }
 
 fn lb_key(tenant_id: &str, game_id: &str, shard: u32) -> String {
     format!("lb:{}:{}:shard:{}", tenant_id, game_id, shard)
 }
 
+fn meta_key(tenant_id: &str, game_id: &str) -> String {
+    format!("lb:{}:{}:meta", tenant_id, game_id)
+}
+
+fn lock_key(tenant_id: &str, game_id: &str) -> String {
+    format!("lb:{}:{}:building", tenant_id, game_id)
+}
+
 fn global_key(tenant_id: &str, shard: u32) -> String {
     format!("lb:{}:global:shard:{}", tenant_id, shard)
 }
 
+fn current_layout(shard_count: u32) -> Layout {
+    Layout { shards: shard_count.max(1), hash: HASH_VERSION }
+}
+
+/// Whether the board was built with the current layout and can be read.
+pub async fn is_ready(cache: &Cache, tenant_id: &str, game_id: &str, shard_count: u32) -> bool {
+    cache.get_json::<Layout>(&meta_key(tenant_id, game_id)).await == Some(current_layout(shard_count))
+}
+
+/// Writes a player's best score through to their shard. Safe while the
+/// board is unbuilt or being rebuilt: readers ignore it until a build
+/// completes, and a build starts from empty shards.
 pub async fn update_score(
     cache: &Cache,
     tenant_id: &str,
@@ -27,10 +82,10 @@ pub async fn update_score(
     score: f64,
     shard_count: u32,
 ) {
-    let shard = shard_index(player_id, shard_count);
+    let shard = shard_index(player_id, shard_count.max(1));
     let key = lb_key(tenant_id, game_id, shard);
     cache.zadd(&key, player_id, score).await;
-    cache.expire(&key, 7200).await;
+    cache.expire(&key, SHARD_TTL_SECS).await;
 }
 
 pub async fn update_global_score(
@@ -46,16 +101,21 @@ pub async fn update_global_score(
     cache.expire(&key, 7200).await;
 }
 
+/// The top `k` players, or `None` if the board isn't built and the caller
+/// should use the database.
 pub async fn get_top_k(
     cache: &Cache,
     tenant_id: &str,
     game_id: &str,
     k: usize,
     shard_count: u32,
-) -> Vec<(String, f64)> {
-    let mut all_entries = Vec::new();
+) -> Option<Vec<(String, f64)>> {
+    if !is_ready(cache, tenant_id, game_id, shard_count).await {
+        return None;
+    }
 
-    for shard in 0..shard_count {
+    let mut all_entries = Vec::new();
+    for shard in 0..shard_count.max(1) {
         let key = lb_key(tenant_id, game_id, shard);
         let entries = cache.zrevrange_withscores(&key, 0, k as isize - 1).await;
         all_entries.extend(entries);
@@ -64,9 +124,11 @@ pub async fn get_top_k(
     // Sort by score descending
     all_entries.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
     all_entries.truncate(k);
-    all_entries
+    Some(all_entries)
 }
 
+/// The player's rank, or `None` if the board isn't built or the player
+/// isn't on it.
 pub async fn get_approx_rank(
     cache: &Cache,
     tenant_id: &str,
@@ -74,20 +136,16 @@ pub async fn get_approx_rank(
     player_id: &str,
     shard_count: u32,
 ) -> Option<usize> {
-    let shard = shard_index(player_id, shard_count);
-    let key = lb_key(tenant_id, game_id, shard);
+    if !is_ready(cache, tenant_id, game_id, shard_count).await {
+        return None;
+    }
 
-    let score = cache.zscore(&key, player_id).await?;
+    let shard = shard_index(player_id, shard_count);
+    let score = cache.zscore(&lb_key(tenant_id, game_id, shard), player_id).await?;
 
     let mut higher_count: usize = 0;
     for s in 0..shard_count {
-        let k = lb_key(tenant_id, game_id, s);
-        let entries = cache.zrevrange_withscores(&k, 0, -1).await;
-        for (_, entry_score) in &entries {
-            if *entry_score > score {
-                higher_count += 1;
-            }
-        }
+        higher_count += cache.zcount_above(&lb_key(tenant_id, game_id, s), score).await as usize;
     }
 
     Some(higher_count + 1)
@@ -112,24 +170,59 @@ pub async fn get_global_top_k(
     all_entries
 }
 
+/// Rebuilds a board from `game_progress` under the current layout,
+/// dropping its old shards first. Returns the number of players cached, or
+/// `None` if another build of the board is already running.
 pub async fn rebuild_from_db(
     db: &sqlx::PgPool,
     cache: &Cache,
     tenant_id: &str,
     game_id: &str,
     shard_count: u32,
-) -> AppResult<usize> {
+) -> AppResult<Option<usize>> {
+    let lock = lock_key(tenant_id, game_id);
+    if !cache.set_nx(&lock, "1", BUILD_LOCK_SECS).await {
+        return Ok(None);
+    }
+    let built = build(db, cache, tenant_id, game_id, current_layout(shard_count)).await;
+    cache.del(&lock).await;
+    built.map(Some)
+}
+
+async fn build(db: &sqlx::PgPool, cache: &Cache, tenant_id: &str, game_id: &str, layout: Layout) -> AppResult<usize> {
+    invalidate(cache, tenant_id, game_id, layout.shards).await;
+
     let rows: Vec<(String, i64)> = sqlx::query_as(
-        "SELECT player_id::text, high_score FROM game_progress WHERE tenant_id = $1 AND game_id = $2 ORDER BY high_score DESC LIMIT 10000",
+        "SELECT player_id::text, high_score FROM game_progress WHERE tenant_id = $1 AND game_id = $2 ORDER BY high_score DESC LIMIT $3",
     )
     .bind(tenant_id)
     .bind(game_id)
+    .bind(MAX_CACHED)
     .fetch_all(db)
     .await?;
 
-    let count = rows.len();
+    let mut shards: Vec<Vec<(f64, String)>> = vec![Vec::new(); layout.shards as usize];
     for (pid, score) in &rows {
-        update_score(cache, tenant_id, game_id, pid, *score as f64, shard_count).await;
+        shards[shard_index(pid, layout.shards) as usize].push((*score as f64, pid.clone()));
+    }
+    for (shard, entries) in shards.iter().enumerate() {
+        let key = lb_key(tenant_id, game_id, shard as u32);
+        cache.zadd_multiple(&key, entries).await;
+        cache.expire(&key, SHARD_TTL_SECS).await;
+    }
+
+    cache.set_json(&meta_key(tenant_id, game_id), &layout, META_TTL_SECS).await;
+    Ok(rows.len())
+}
+
+/// Drops a board and its shards, under the old layout as well as the
+/// current one. It is served from the database until the next rebuild.
+pub async fn invalidate(cache: &Cache, tenant_id: &str, game_id: &str, shard_count: u32) {
+    let meta = meta_key(tenant_id, game_id);
+    let old = cache.get_json::<Layout>(&meta).await;
+    cache.del(&meta).await;
+    let shards = old.map_or(shard_count, |old| old.shards.max(shard_count));
+    for shard in 0..shards {
+        cache.del(&lb_key(tenant_id, game_id, shard)).await;
     }
-    Ok(count)
 }
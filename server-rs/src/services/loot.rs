@@ -0,0 +1,35 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::models::economy::CrateDrop;
+
+/// A fresh random seed for one crate opening, hex-encoded.
+pub fn new_seed() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// The roll for a seed: the first 8 bytes of SHA-256(seed) as a big-endian
+/// integer, modulo the drop table's total weight. Deterministic, so anyone
+/// with the recorded seed and drop table can reproduce an opening.
+pub fn roll(seed: &str, total_weight: i64) -> i64 {
+    let digest = Sha256::digest(seed.as_bytes());
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(head) % total_weight as u64) as i64
+}
+
+/// The drop a roll lands on: drops cover consecutive ranges of
+/// `[0, total_weight)` in table order, each as wide as its weight.
+pub fn pick(drops: &[CrateDrop], roll: i64) -> Option<&CrateDrop> {
+    let mut upper = 0i64;
+    drops.iter().find(|d| {
+        upper += i64::from(d.weight);
+        roll < upper
+    })
+}
+
+pub fn total_weight(drops: &[CrateDrop]) -> i64 {
+    drops.iter().map(|d| i64::from(d.weight)).sum()
+}
@@ -0,0 +1,54 @@
+//! Transactional email.
+//!
+//! Messages are posted as JSON (`from`, `to`, `subject`, `text`) to the HTTP
+//! API in [`EmailConfig`], which most providers accept directly or through
+//! a small relay. Requests don't send mail themselves: they queue a
+//! `send_email` job ([`queue`]), so a provider outage is retried by the job
+//! worker. Without an API URL the message is only logged, which is what
+//! development setups want.
+
+use serde_json::{json, Value};
+
+use crate::config::EmailConfig;
+use crate::error::{AppError, AppResult};
+use crate::services::jobs;
+
+/// Queues an email for the job worker.
+pub async fn queue<'e>(db: impl sqlx::PgExecutor<'e>, to: &str, subject: &str, text: &str) -> AppResult<()> {
+    let payload = json!({ "to": to, "subject": subject, "text": text });
+    jobs::enqueue(db, jobs::SEND_EMAIL, payload, None).await?;
+    Ok(())
+}
+
+/// Sends a queued email (`send_email` job payload).
+#[tracing::instrument(name = "email.send", skip_all, fields(otel.kind = "client"))]
+pub async fn send(config: &EmailConfig, payload: &Value) -> AppResult<()> {
+    let (Some(to), Some(subject), Some(text)) =
+        (payload["to"].as_str(), payload["subject"].as_str(), payload["text"].as_str())
+    else {
+        return Err(AppError::Internal("send_email needs to, subject and text".into()));
+    };
+    if config.api_url.is_empty() {
+        tracing::info!("Email to {} (not sent, EMAIL_API_URL unset): {}\n{}", to, subject, text);
+        return Ok(());
+    }
+
+    let resp = reqwest::Client::new()
+        .post(&config.api_url)
+        .bearer_auth(&config.api_key)
+        .json(&json!({ "from": config.from, "to": to, "subject": subject, "text": text }))
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("Email request failed: {}", e)))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(AppError::Internal(format!(
+            "Email API returned {}: {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Prometheus metrics, served in the text exposition format at
+//! `GET /metrics`.
+//!
+//! Counters and histograms live in a process-wide registry that the request
+//! middleware, the cache and the rate limiters record into. Gauges (the
+//! database pool, open WebSockets) are read when the endpoint is scraped.
+//! Label values come from code — route templates, cache key namespaces,
+//! limiter names — never from request data, so the number of series stays
+//! bounded.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard};
+
+/// Upper bounds of the request latency buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative: each bucket counts every observation at or below it.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+struct Registry {
+    /// By method, route and status.
+    requests: BTreeMap<(&'static str, String, u16), Histogram>,
+    /// By key namespace and hit.
+    cache_lookups: BTreeMap<(String, bool), u64>,
+    /// By limiter.
+    rate_limited: BTreeMap<String, u64>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    requests: BTreeMap::new(),
+    cache_lookups: BTreeMap::new(),
+    rate_limited: BTreeMap::new(),
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The part of a cache key or limiter key before the first `:`.
+fn namespace(key: &str) -> String {
+    key.split(':').next().unwrap_or_default().to_string()
+}
+
+pub fn observe_request(method: &'static str, route: &str, status: u16, secs: f64) {
+    registry()
+        .requests
+        .entry((method, route.to_string(), status))
+        .or_default()
+        .observe(secs);
+}
+
+pub fn cache_lookup(key: &str, hit: bool) {
+    *registry().cache_lookups.entry((namespace(key), hit)).or_default() += 1;
+}
+
+/// A request turned away by the rate limiter whose keys start with the
+/// limiter's name (`global:`, `score:`, ...).
+pub fn rate_limited(key: &str) {
+    *registry().rate_limited.entry(namespace(key)).or_default() += 1;
+}
+
+/// Values read at scrape time.
+pub struct Gauges {
+    pub db_pool_size: u32,
+    pub db_pool_idle: usize,
+    pub db_pool_max: u32,
+    pub websocket_connections: usize,
+    pub websocket_players: usize,
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Everything in the Prometheus text format.
+pub fn render(gauges: &Gauges) -> String {
+    let mut out = String::new();
+    let registry = registry();
+
+    header(&mut out, "http_request_duration_seconds", "histogram", "HTTP request latency by route.");
+    for ((method, route, status), h) in &registry.requests {
+        let labels = format!("method=\"{}\",route=\"{}\",status=\"{}\"", method, escape(route), status);
+        for (count, bound) in h.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, count);
+        }
+        let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, h.count);
+        let _ = writeln!(out, "http_request_duration_seconds_sum{{{}}} {}", labels, h.sum);
+        let _ = writeln!(out, "http_request_duration_seconds_count{{{}}} {}", labels, h.count);
+    }
+
+    header(&mut out, "cache_lookups_total", "counter", "Redis GETs by key namespace and result.");
+    for ((ns, hit), count) in &registry.cache_lookups {
+        let result = if *hit { "hit" } else { "miss" };
+        let _ = writeln!(out, "cache_lookups_total{{namespace=\"{}\",result=\"{}\"}} {}", escape(ns), result, count);
+    }
+
+    header(&mut out, "rate_limit_rejections_total", "counter", "Requests rejected by a rate limiter.");
+    for (limiter, count) in &registry.rate_limited {
+        let _ = writeln!(out, "rate_limit_rejections_total{{limiter=\"{}\"}} {}", escape(limiter), count);
+    }
+    drop(registry);
+
+    header(&mut out, "db_pool_connections", "gauge", "Database pool connections by state.");
+    let in_use = (gauges.db_pool_size as usize).saturating_sub(gauges.db_pool_idle);
+    let _ = writeln!(out, "db_pool_connections{{state=\"idle\"}} {}", gauges.db_pool_idle);
+    let _ = writeln!(out, "db_pool_connections{{state=\"in_use\"}} {}", in_use);
+    header(&mut out, "db_pool_max_connections", "gauge", "Database pool size limit.");
+    let _ = writeln!(out, "db_pool_max_connections {}", gauges.db_pool_max);
+
+    header(&mut out, "websocket_connections", "gauge", "Open realtime WebSockets.");
+    let _ = writeln!(out, "websocket_connections {}", gauges.websocket_connections);
+    header(&mut out, "websocket_players", "gauge", "Players with at least one open realtime WebSocket.");
+    let _ = writeln!(out, "websocket_players {}", gauges.websocket_players);
+
+    out
+}
@@ -2,6 +2,39 @@ pub mod leaderboard;
 pub mod achievements;
 pub mod stripe_service;
 pub mod subscription_sync;
+pub mod stripe_events;
 pub mod usage_meters;
 pub mod storage_quotas;
 pub mod room_manager;
+pub mod seasons;
+pub mod oauth;
+pub mod realtime;
+pub mod loot;
+pub mod classrooms;
+pub mod report_export;
+pub mod roster_import;
+pub mod entity_audit;
+pub mod moderation_filter;
+pub mod object_storage;
+pub mod gdpr;
+pub mod rating;
+pub mod score_decay;
+pub mod score_distribution;
+pub mod score_voids;
+pub mod anticheat;
+pub mod battle_pass;
+pub mod battle_pass_xp;
+pub mod translations;
+pub mod cosmetics;
+pub mod sessions;
+pub mod store;
+pub mod auction_house;
+pub mod cron;
+pub mod jobs;
+pub mod metrics;
+pub mod otel;
+pub mod privacy;
+pub mod mailer;
+pub mod parental_consent;
+pub mod playtime;
+pub mod xapi;
@@ -0,0 +1,285 @@
+//! Content filter for player-written text (comments, reviews and chat).
+//!
+//! Text is normalised before matching: lowercased, leetspeak folded
+//! (`5h1t` → `shit`), punctuation inside words dropped (`f.u.c.k`), runs of
+//! single letters joined (`f u c k`) and repeated letters squeezed
+//! (`fuuuck`). Terms match whole words only, so `class` doesn't trip `ass`.
+//!
+//! Each tenant's list is the built-in [`DEFAULT_TERMS`] plus the tenant's
+//! rows in `moderation_terms`, which can add terms, change the severity of
+//! a built-in one, or exempt it with [`ALLOW`]. The merged list is cached
+//! in Redis.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cache::Cache;
+use crate::error::{AppError, AppResult};
+
+const CACHE_TTL_SECS: u64 = 300;
+
+/// Tenant severity that switches a built-in term off.
+pub const ALLOW: &str = "allow";
+
+/// Reporter id on reports filed by the filter rather than a player.
+pub const SYSTEM_REPORTER: Uuid = Uuid::nil();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Published, and reported for a moderator to look at.
+    Low,
+    /// Held until a moderator approves it.
+    Medium,
+    /// Rejected.
+    High,
+}
+
+impl Severity {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// Applied to every tenant unless overridden.
+pub const DEFAULT_TERMS: &[(&str, Severity)] = &[
+    ("crap", Severity::Low),
+    ("damn", Severity::Low),
+    ("idiot", Severity::Low),
+    ("loser", Severity::Low),
+    ("stupid", Severity::Low),
+    ("ass", Severity::Medium),
+    ("asshole", Severity::Medium),
+    ("bastard", Severity::Medium),
+    ("bitch", Severity::Medium),
+    ("dick", Severity::Medium),
+    ("piss", Severity::Medium),
+    ("shit", Severity::Medium),
+    ("cunt", Severity::High),
+    ("fuck", Severity::High),
+    ("fucking", Severity::High),
+    ("kill yourself", Severity::High),
+    ("kys", Severity::High),
+    ("slut", Severity::High),
+    ("whore", Severity::High),
+];
+
+#[derive(Debug, Default, Serialize)]
+pub struct Verdict {
+    /// Highest severity among the matches; `None` when the text is clean.
+    pub severity: Option<Severity>,
+    pub matched: Vec<String>,
+}
+
+impl Verdict {
+    /// Whether the content should wait for a moderator.
+    pub fn holds(&self) -> bool {
+        self.severity == Some(Severity::Medium)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Normalisation and matching
+// ---------------------------------------------------------------------------
+
+fn fold_leet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' | '|' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        '8' => 'b',
+        '9' => 'g',
+        _ => c,
+    }
+}
+
+/// Normalised words of `text`. Trailing punctuation is trimmed before
+/// folding so `shit!` isn't read as `shiti`.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split_whitespace()
+        .map(|raw| {
+            raw.trim_end_matches(['.', ',', '!', '?', ';', ':', '"', '\'', ')'])
+                .trim_start_matches(['"', '\'', '('])
+                .chars()
+                .map(fold_leet)
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// The form terms are stored and compared in.
+pub fn normalize(text: &str) -> String {
+    words(text).join(" ")
+}
+
+/// Collapses repeated letters, so `fuuuck` and `fuck` compare equal.
+fn squeeze(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars() {
+        if !out.ends_with(c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Matches `text` against a normalised term list.
+pub fn evaluate(text: &str, terms: &[(String, Severity)]) -> Verdict {
+    let mut candidates = words(text);
+    let phrase = format!(" {} ", candidates.join(" "));
+
+    // "f u c k" -> "fuck"
+    let mut spelled = String::new();
+    let mut joined = Vec::new();
+    for w in &candidates {
+        if w.chars().count() == 1 {
+            spelled.push_str(w);
+            continue;
+        }
+        if spelled.chars().count() > 1 {
+            joined.push(spelled.clone());
+        }
+        spelled.clear();
+    }
+    if spelled.chars().count() > 1 {
+        joined.push(spelled);
+    }
+    candidates.extend(joined);
+
+    let mut verdict = Verdict::default();
+    for (term, severity) in terms {
+        let hit = if term.contains(' ') {
+            phrase.contains(&format!(" {} ", term))
+        } else {
+            // Only squeeze words longer than the term, so "as" doesn't match "ass"
+            let term_squeezed = squeeze(term);
+            candidates
+                .iter()
+                .any(|w| w == term || (w.len() > term.len() && squeeze(w) == term_squeezed))
+        };
+        if hit {
+            verdict.matched.push(term.clone());
+            verdict.severity = verdict.severity.max(Some(*severity));
+        }
+    }
+    verdict
+}
+
+// ---------------------------------------------------------------------------
+// Tenant lists
+// ---------------------------------------------------------------------------
+
+fn cache_key(tenant_id: &str) -> String {
+    format!("modfilter:{}", tenant_id)
+}
+
+/// The built-in terms merged with the tenant's own.
+pub async fn tenant_terms(
+    db: &sqlx::PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+) -> AppResult<Vec<(String, Severity)>> {
+    let key = cache_key(tenant_id);
+    if let Some(terms) = cache.get_json::<Vec<(String, Severity)>>(&key).await {
+        return Ok(terms);
+    }
+
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT term, severity FROM moderation_terms WHERE tenant_id = $1",
+    )
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?;
+
+    let mut terms: Vec<(String, Severity)> = DEFAULT_TERMS
+        .iter()
+        .filter(|(t, _)| !rows.iter().any(|(r, _)| r == t))
+        .map(|(t, s)| (t.to_string(), *s))
+        .collect();
+    terms.extend(
+        rows.into_iter()
+            .filter_map(|(t, s)| Severity::parse(&s).map(|s| (t, s))),
+    );
+
+    cache.set_json(&key, &terms, CACHE_TTL_SECS).await;
+    Ok(terms)
+}
+
+/// Call after changing a tenant's `moderation_terms`.
+pub async fn invalidate(cache: &Cache, tenant_id: &str) {
+    cache.del(&cache_key(tenant_id)).await;
+}
+
+// ---------------------------------------------------------------------------
+// Submission hook
+// ---------------------------------------------------------------------------
+
+/// Screens a submission's text fields. High-severity matches are rejected
+/// with `400`; otherwise the verdict says whether to hold the content and
+/// should be passed to [`report`] once it's stored.
+pub async fn check(
+    db: &sqlx::PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+    texts: &[&str],
+) -> AppResult<Verdict> {
+    let terms = tenant_terms(db, cache, tenant_id).await?;
+    let verdict = evaluate(&texts.join("\n"), &terms);
+    if verdict.severity == Some(Severity::High) {
+        return Err(AppError::BadRequest(
+            "This contains language that isn't allowed".into(),
+        ));
+    }
+    Ok(verdict)
+}
+
+/// Files a content report for flagged content so it shows up in the
+/// moderation queue. Does nothing for clean content.
+pub async fn report(
+    db: &sqlx::PgPool,
+    tenant_id: &str,
+    content_type: &str,
+    content_id: Uuid,
+    verdict: &Verdict,
+) -> AppResult<()> {
+    let Some(severity) = verdict.severity else {
+        return Ok(());
+    };
+
+    sqlx::query(
+        r#"INSERT INTO content_reports (reporter_id, tenant_id, content_type, content_id, reason, description, status, created_at)
+        VALUES ($1, $2, $3, $4, 'inappropriate', $5, 'open', NOW())
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(SYSTEM_REPORTER)
+    .bind(tenant_id)
+    .bind(content_type)
+    .bind(content_id)
+    .bind(format!(
+        "Content filter ({}): {}",
+        severity.as_str(),
+        verdict.matched.join(", ")
+    ))
+    .execute(db)
+    .await?;
+    Ok(())
+}
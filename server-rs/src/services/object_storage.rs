@@ -0,0 +1,227 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::config::StorageConfig;
+use crate::error::{AppError, AppResult};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SigV4 caps presigned URLs at 7 days.
+const MAX_PRESIGN_SECS: u64 = 604_800;
+
+/// Minimal S3-compatible client: put, delete and presigned GET, signed with
+/// AWS Signature Version 4 over raw HTTP (same approach as the Stripe
+/// client).
+#[derive(Clone)]
+pub struct ObjectStorage {
+    endpoint: reqwest::Url,
+    host: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    pub download_url_ttl_secs: u64,
+    client: reqwest::Client,
+}
+
+impl ObjectStorage {
+    pub fn new(config: &StorageConfig) -> Option<Self> {
+        if config.bucket.is_empty() {
+            return None;
+        }
+        let endpoint = match reqwest::Url::parse(config.endpoint.trim_end_matches('/')) {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::error!("Invalid STORAGE_ENDPOINT {}: {}", config.endpoint, e);
+                return None;
+            }
+        };
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(h), Some(p)) => format!("{}:{}", h, p),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return None,
+        };
+        Some(Self {
+            endpoint,
+            host,
+            bucket: config.bucket.clone(),
+            region: config.region.clone(),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+            download_url_ttl_secs: config.download_url_ttl_secs,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    pub async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> AppResult<()> {
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let (url, headers) = self.signed_request("PUT", key, &payload_hash);
+        let resp = self
+            .client
+            .put(url)
+            .headers(headers)
+            .header("content-type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Storage upload failed: {}", e)))?;
+        check_status(resp, "upload").await
+    }
+
+    pub async fn delete(&self, key: &str) -> AppResult<()> {
+        let payload_hash = hex::encode(Sha256::digest(b""));
+        let (url, headers) = self.signed_request("DELETE", key, &payload_hash);
+        let resp = self
+            .client
+            .delete(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Storage delete failed: {}", e)))?;
+        check_status(resp, "delete").await
+    }
+
+    /// A URL anyone can GET the object with for `ttl_secs`.
+    pub fn presigned_get(&self, key: &str, ttl_secs: u64) -> String {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = self.scope(&date);
+
+        let mut query = [
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+            ("X-Amz-Credential", format!("{}/{}", self.access_key_id, scope)),
+            ("X-Amz-Date", amz_date.clone()),
+            ("X-Amz-Expires", ttl_secs.clamp(1, MAX_PRESIGN_SECS).to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ];
+        query.sort_by(|a, b| a.0.cmp(b.0));
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let path = self.object_path(key);
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            path, canonical_query, self.host
+        );
+        let signature = self.sign(&amz_date, &date, &canonical_request);
+
+        format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.origin(),
+            path,
+            canonical_query,
+            signature
+        )
+    }
+
+    // -- Signing ---------------------------------------------------------
+
+    fn origin(&self) -> String {
+        format!("{}://{}", self.endpoint.scheme(), self.host)
+    }
+
+    /// `/{endpoint path}/{bucket}/{key}`, URI-encoded per segment.
+    fn object_path(&self, key: &str) -> String {
+        let base = self.endpoint.path().trim_end_matches('/');
+        format!(
+            "{}/{}/{}",
+            base,
+            uri_encode(&self.bucket, true),
+            uri_encode(key, false)
+        )
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.region)
+    }
+
+    fn sign(&self, amz_date: &str, date: &str, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            self.scope(date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = format!("AWS4{}", self.secret_access_key).into_bytes();
+        for part in [date, self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        hex::encode(hmac(&key, string_to_sign.as_bytes()))
+    }
+
+    /// URL and headers for a header-signed request.
+    fn signed_request(
+        &self,
+        method: &str,
+        key: &str,
+        payload_hash: &str,
+    ) -> (String, reqwest::header::HeaderMap) {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let path = self.object_path(key);
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, self.host, payload_hash, amz_date, payload_hash
+        );
+        let signature = self.sign(&amz_date, &date, &canonical_request);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key_id,
+            self.scope(&date),
+            signature
+        );
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in [
+            ("x-amz-content-sha256", payload_hash.to_string()),
+            ("x-amz-date", amz_date),
+            ("authorization", authorization),
+        ] {
+            if let Ok(v) = reqwest::header::HeaderValue::from_str(&value) {
+                headers.insert(name, v);
+            }
+        }
+        (format!("{}{}", self.origin(), path), headers)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 URI encoding: everything but unreserved characters, and `/` too
+/// unless it separates path segments.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+async fn check_status(resp: reqwest::Response, action: &str) -> AppResult<()> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(AppError::Internal(format!(
+        "Storage {} failed ({}): {}",
+        action,
+        status,
+        body.chars().take(200).collect::<String>()
+    )))
+}
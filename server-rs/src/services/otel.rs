@@ -0,0 +1,188 @@
+//! Distributed tracing: OTLP export of `tracing` spans.
+//!
+//! Tracing is on when `OTEL_EXPORTER_OTLP_ENDPOINT` is set. Spans then go
+//! to the collector over OTLP/HTTP in batches:
+//!
+//! - one per request, opened by
+//!   [`trace_requests`](crate::middleware::trace::trace_requests) and
+//!   continuing the caller's trace when it sends a `traceparent` header;
+//! - one per SQL statement, built from the events sqlx logs when a statement
+//!   finishes, as a child of whatever span ran it;
+//! - one per Stripe API call;
+//! - one per job run, continuing the trace that enqueued the job (its
+//!   context is stored on the `jobs` row).
+//!
+//! Without an endpoint the spans still exist for the JSON logs, and nothing
+//! is exported.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use axum::http::HeaderMap;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{Span as _, SpanKind, TraceContextExt, Tracer as _, TracerProvider as _};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::Context as LayerContext;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+use crate::config::TracingConfig;
+
+/// The target sqlx logs finished statements under.
+const SQLX_TARGET: &str = "sqlx::query";
+
+/// The layers that export spans, or `None` if tracing is off. Installs the
+/// W3C trace context propagator as a side effect.
+pub fn layer(config: &TracingConfig) -> Option<Box<dyn Layer<Registry> + Send + Sync>> {
+    if config.otlp_endpoint.is_empty() {
+        return None;
+    }
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", config.otlp_endpoint.trim_end_matches('/')))
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to create the OTLP exporter, tracing is off: {}", e);
+            return None;
+        }
+    };
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio.clamp(0.0, 1.0),
+        ))))
+        .with_resource(Resource::new([KeyValue::new("service.name", config.service_name.clone())]))
+        .build();
+    let tracer = provider.tracer("stem-adventures-api");
+    global::set_tracer_provider(provider);
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let spans = tracing_opentelemetry::layer()
+        .with_tracer(tracer.clone())
+        .with_filter(LevelFilter::INFO);
+    let statements = SqlStatements { tracer }.with_filter(Targets::new().with_target(SQLX_TARGET, Level::DEBUG));
+    Some(spans.and_then(statements).boxed())
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// The trace context a request carries in its `traceparent` header.
+pub fn extract(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(headers)))
+}
+
+/// The current span's trace context, for storing with deferred work.
+/// Empty when tracing is off.
+pub fn current_context() -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    let cx = tracing::Span::current().context();
+    global::get_text_map_propagator(|p| p.inject_context(&cx, &mut carrier));
+    carrier
+}
+
+/// Continues a trace stored by [`current_context`].
+pub fn stored_context(carrier: &HashMap<String, String>) -> Context {
+    global::get_text_map_propagator(|p| p.extract(carrier))
+}
+
+/// Turns sqlx's "statement finished" events into spans. sqlx reports a
+/// statement once it is done, with its duration, so the span is
+/// backdated to when it started.
+struct SqlStatements {
+    tracer: Tracer,
+}
+
+#[derive(Default)]
+struct Statement {
+    summary: String,
+    sql: String,
+    rows_affected: u64,
+    rows_returned: u64,
+    elapsed_secs: f64,
+}
+
+impl Visit for Statement {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" => self.sql = value.trim().to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "rows_affected" => self.rows_affected = value,
+            "rows_returned" => self.rows_returned = value,
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl<S> Layer<S> for SqlStatements
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        if event.metadata().target() != SQLX_TARGET {
+            return;
+        }
+        // Statements outside any traced work (startup, pool upkeep) would
+        // each be a trace of their own.
+        let parent = tracing::Span::current().context();
+        if !parent.span().span_context().is_valid() {
+            return;
+        }
+
+        let mut statement = Statement::default();
+        event.record(&mut statement);
+        // sqlx leaves `db.statement` empty when the summary is the whole
+        // statement.
+        let sql = if statement.sql.is_empty() { statement.summary.clone() } else { statement.sql };
+
+        let end = SystemTime::now();
+        let start = end - Duration::from_secs_f64(statement.elapsed_secs.max(0.0));
+        let mut span = self
+            .tracer
+            .span_builder(statement.summary)
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes([
+                KeyValue::new("db.system", "postgresql"),
+                KeyValue::new("db.statement", sql),
+                KeyValue::new("db.rows_affected", statement.rows_affected as i64),
+                KeyValue::new("db.rows_returned", statement.rows_returned as i64),
+            ])
+            .start_with_context(&self.tracer, &parent);
+        span.end_with_timestamp(end);
+    }
+}
@@ -0,0 +1,176 @@
+//! Parental consent requests (`parental_consents`).
+//!
+//! Asking for consent emails the parent a link to the consent page
+//! carrying a random token, of which only the SHA-256 is stored. The page
+//! shows the request ([`find`]) and posts the parent's answer back
+//! ([`respond`]), which sets the player's `consent_status` for the
+//! [`age_gate`](crate::middleware::age_gate). The parent can change their
+//! answer later through the same link. A new request — a resend, or a
+//! different parent address — supersedes older pending ones.
+
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::services::mailer;
+use crate::AppState;
+
+/// Values of `players.consent_status`.
+pub const STATUSES: &[&str] = &["not_required", "pending", "granted", "denied"];
+
+/// A player can have the email sent again after this long.
+pub const RESEND_COOLDOWN_MINUTES: i64 = 10;
+
+const TOKEN_LEN: usize = 40;
+
+/// A request as the consent page sees it.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ConsentRequest {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub player_id: Uuid,
+    pub display_name: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Very loose check; the parent confirming by email is the real test.
+pub fn is_valid_email(email: &str) -> bool {
+    email.len() <= 254
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.contains('@'))
+}
+
+/// Sends `parent_email` a consent request for the player and marks the
+/// player `pending`.
+pub async fn request(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    display_name: &str,
+    parent_email: &str,
+) -> AppResult<()> {
+    let config = &state.config.age_gate;
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect();
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query(
+        "UPDATE parental_consents SET status = 'superseded' WHERE tenant_id = $1 AND player_id = $2 AND status = 'pending'",
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO parental_consents (tenant_id, player_id, parent_email, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5))"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(parent_email)
+    .bind(hash_token(&token))
+    .bind(config.consent_ttl_days as i32)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE players SET consent_status = 'pending' WHERE id = $1 AND tenant_id = $2")
+        .bind(player_id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let subject = format!("{} is asking for your permission", display_name);
+    let text = format!(
+        "{name} has signed up for STEM School Adventures.\n\n\
+        Because {name} may be under 13, they can play the games but can't chat, post \
+        comments or reviews, or make purchases until you agree. To see the request and \
+        answer it, open:\n\n{url}?token={token}\n\n\
+        The link works for {days} days. If you don't know this account, you can ignore this email.",
+        name = display_name,
+        url = config.consent_url,
+        token = token,
+        days = config.consent_ttl_days,
+    );
+    mailer::queue(&mut *tx, parent_email, &subject, &text).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Whether the player asked for an email within the resend cooldown.
+pub async fn recently_requested(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<bool> {
+    let recent = sqlx::query_scalar(
+        r#"SELECT EXISTS(SELECT 1 FROM parental_consents WHERE tenant_id = $1 AND player_id = $2
+            AND created_at > NOW() - make_interval(mins => $3))"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(RESEND_COOLDOWN_MINUTES as i32)
+    .fetch_one(db)
+    .await?;
+    Ok(recent)
+}
+
+/// The request behind a consent link.
+pub async fn find(db: &PgPool, token: &str) -> AppResult<ConsentRequest> {
+    sqlx::query_as(
+        r#"SELECT c.id, c.tenant_id, c.player_id, p.display_name, c.status, c.created_at, c.expires_at, c.responded_at
+        FROM parental_consents c JOIN players p ON p.id = c.player_id AND p.tenant_id = c.tenant_id
+        WHERE c.token_hash = $1"#,
+    )
+    .bind(hash_token(token))
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("This consent link isn't valid".into()))
+}
+
+/// Records the parent's answer. An unanswered link stops working when it
+/// expires or a newer one is sent; an answered one can be changed.
+pub async fn respond(db: &PgPool, token: &str, grant: bool) -> AppResult<ConsentRequest> {
+    let request = find(db, token).await?;
+    match request.status.as_str() {
+        "superseded" => {
+            return Err(AppError::Conflict(
+                "A newer request has been sent; please use the link in the latest email".into(),
+            ))
+        }
+        "pending" if request.expires_at <= Utc::now() => {
+            return Err(AppError::BadRequest("This consent link has expired".into()))
+        }
+        _ => {}
+    }
+
+    let status = if grant { "granted" } else { "denied" };
+    let mut tx = db.begin().await?;
+    sqlx::query("UPDATE parental_consents SET status = $2, responded_at = NOW() WHERE id = $1")
+        .bind(request.id)
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE players SET consent_status = $3 WHERE id = $1 AND tenant_id = $2")
+        .bind(request.player_id)
+        .bind(&request.tenant_id)
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(ConsentRequest {
+        status: status.to_string(),
+        responded_at: Some(Utc::now()),
+        ..request
+    })
+}
@@ -0,0 +1,141 @@
+//! Daily playtime budgets.
+//!
+//! A player's limit is the smaller of their own `daily_playtime_limit_mins`
+//! and that of any organisation they belong to as a `member` (staff aren't
+//! held to the organisation's limit). Time counts while their presence is
+//! `in_game`: every presence heartbeat — the socket's ping or the legacy
+//! `POST /presence/heartbeat` — adds the time since the previous one to
+//! today's `playtime_days` row, capped at [`MAX_TICK_SECS`] so a client
+//! that vanished isn't charged for the gap. Days are UTC.
+//!
+//! Going over is a soft block: the game in progress carries on, but
+//! [`check_can_start`] turns away new ones.
+
+use chrono::{DateTime, Days, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+
+/// Longest gap between heartbeats that is counted in full.
+pub const MAX_TICK_SECS: i32 = 120;
+
+/// Bounds on a daily limit, in minutes.
+pub const MIN_LIMIT_MINS: i32 = 15;
+pub const MAX_LIMIT_MINS: i32 = 24 * 60;
+
+/// A player's limit and what they've used of it today.
+#[derive(Debug, sqlx::FromRow)]
+pub struct Budget {
+    /// `None` when no limit applies.
+    pub limit_mins: Option<i32>,
+    /// Where the limit comes from: `player` or `organisation`.
+    pub source: Option<String>,
+    pub used_secs: i32,
+}
+
+impl Budget {
+    /// Seconds left today, or `None` without a limit.
+    pub fn remaining_secs(&self) -> Option<i32> {
+        self.limit_mins.map(|mins| (mins * 60 - self.used_secs).max(0))
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.remaining_secs() == Some(0)
+    }
+
+    pub fn json(&self) -> Value {
+        json!({
+            "dailyLimitMinutes": self.limit_mins,
+            "source": self.source,
+            "usedSeconds": self.used_secs,
+            "remainingSeconds": self.remaining_secs(),
+            "resetsAt": resets_at(Utc::now()),
+        })
+    }
+}
+
+/// The next UTC midnight.
+pub fn resets_at(now: DateTime<Utc>) -> DateTime<Utc> {
+    let tomorrow = now.date_naive().checked_add_days(Days::new(1)).unwrap_or(now.date_naive());
+    tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+pub async fn budget(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<Budget> {
+    sqlx::query_as(
+        r#"WITH limits AS (
+            SELECT p.daily_playtime_limit_mins AS own,
+                (SELECT MIN(o.daily_playtime_limit_mins) FROM organisation_members om
+                    JOIN organisations o ON o.id = om.organisation_id
+                    WHERE om.player_id::text = p.id::text AND om.tenant_id = p.tenant_id AND om.role = 'member') AS org
+            FROM players p WHERE p.id = $1 AND p.tenant_id = $2
+        )
+        SELECT LEAST(own, org) AS limit_mins,
+            CASE WHEN own IS NULL AND org IS NULL THEN NULL
+                 WHEN org IS NULL OR own <= org THEN 'player' ELSE 'organisation' END AS source,
+            COALESCE((SELECT seconds FROM playtime_days
+                WHERE tenant_id = $2 AND player_id = $1 AND day = (NOW() AT TIME ZONE 'UTC')::date), 0) AS used_secs
+        FROM limits"#,
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Player not found".into()))
+}
+
+/// Adds the time since the last heartbeat, if the player is in a game.
+pub async fn tick(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"INSERT INTO playtime_days (tenant_id, player_id, day, seconds, last_tick_at)
+        SELECT $1, $2, (NOW() AT TIME ZONE 'UTC')::date, 0, NOW()
+        FROM player_presence WHERE tenant_id = $1 AND player_id = $2 AND status = 'in_game'
+        ON CONFLICT (tenant_id, player_id, day) DO UPDATE SET
+            seconds = playtime_days.seconds
+                + LEAST(EXTRACT(EPOCH FROM NOW() - playtime_days.last_tick_at), $3)::int,
+            last_tick_at = NOW()"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(MAX_TICK_SECS)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Starts counting from now, so the time before a game isn't added by
+/// its first heartbeat.
+pub async fn resume(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"INSERT INTO playtime_days (tenant_id, player_id, day, seconds, last_tick_at)
+        VALUES ($1, $2, (NOW() AT TIME ZONE 'UTC')::date, 0, NOW())
+        ON CONFLICT (tenant_id, player_id, day) DO UPDATE SET last_tick_at = NOW()"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Rejects starting a game once today's playtime is used up.
+pub async fn check_can_start(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<()> {
+    if budget(db, tenant_id, player_id).await?.exhausted() {
+        return Err(AppError::Forbidden(
+            "You've reached today's playtime limit. Time for a break!".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a requested limit: `None` clears it.
+pub fn validate_limit(mins: Option<i32>) -> AppResult<()> {
+    match mins {
+        Some(m) if !(MIN_LIMIT_MINS..=MAX_LIMIT_MINS).contains(&m) => Err(AppError::BadRequest(format!(
+            "dailyLimitMinutes must be between {} and {}",
+            MIN_LIMIT_MINS, MAX_LIMIT_MINS
+        ))),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,116 @@
+//! Player privacy preferences.
+//!
+//! Each player chooses, on their `players` row:
+//!
+//! * **Profile visibility** — who finds them in player search: everyone
+//!   (`public`), only their friends (`friends`) or nobody (`private`).
+//! * **Friend requests** — who may send one: `everyone`, only players they
+//!   share a friend with (`friends_of_friends`), or `nobody`.
+//! * **Show online** — when off, friends always see them as offline.
+//! * **Leaderboard anonymity** — public boards show them as "Anonymous",
+//!   without their id or cosmetics. Their own rank and the friends board
+//!   are unaffected.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::models::player::PrivacySettings;
+
+pub const PROFILE_VISIBILITIES: &[&str] = &["public", "friends", "private"];
+pub const FRIEND_REQUEST_POLICIES: &[&str] = &["everyone", "friends_of_friends", "nobody"];
+
+/// Shown in place of the display name of anonymous leaderboard entries.
+pub const ANONYMOUS_NAME: &str = "Anonymous";
+
+pub async fn get(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<PrivacySettings> {
+    sqlx::query_as(
+        "SELECT profile_visibility, friend_requests, show_online, leaderboard_anonymous FROM players WHERE id = $1 AND tenant_id = $2",
+    )
+    .bind(player_id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Player not found".into()))
+}
+
+/// Whether friends may see the player's presence.
+pub async fn shows_online(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<bool> {
+    let shown: Option<bool> = sqlx::query_scalar("SELECT show_online FROM players WHERE id = $1 AND tenant_id = $2")
+        .bind(player_id)
+        .bind(tenant_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(shown.unwrap_or(true))
+}
+
+/// Rejects a friend request `to` won't accept from `from`.
+pub async fn check_friend_request(db: &PgPool, tenant_id: &str, from: Uuid, to: Uuid) -> AppResult<()> {
+    let policy: Option<String> = sqlx::query_scalar("SELECT friend_requests FROM players WHERE id = $1 AND tenant_id = $2")
+        .bind(to)
+        .bind(tenant_id)
+        .fetch_optional(db)
+        .await?;
+    match policy.as_deref() {
+        None => Err(AppError::NotFound("Player not found".into())),
+        Some("nobody") => Err(AppError::Forbidden("This player isn't accepting friend requests".into())),
+        Some("friends_of_friends") => {
+            let mutual: bool = sqlx::query_scalar(
+                r#"WITH mine AS (
+                    SELECT CASE WHEN player_id = $2 THEN friend_id ELSE player_id END AS id FROM friendships
+                    WHERE tenant_id = $1 AND status = 'accepted' AND (player_id = $2 OR friend_id = $2)
+                ), theirs AS (
+                    SELECT CASE WHEN player_id = $3 THEN friend_id ELSE player_id END AS id FROM friendships
+                    WHERE tenant_id = $1 AND status = 'accepted' AND (player_id = $3 OR friend_id = $3)
+                )
+                SELECT EXISTS(SELECT 1 FROM mine JOIN theirs USING (id))"#,
+            )
+            .bind(tenant_id)
+            .bind(from)
+            .bind(to)
+            .fetch_one(db)
+            .await?;
+            if mutual {
+                Ok(())
+            } else {
+                Err(AppError::Forbidden("This player only accepts requests from friends of friends".into()))
+            }
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Masks the leaderboard `entries` (JSON objects with a `playerId`) of
+/// players who chose to be anonymous.
+pub async fn anonymize(db: &PgPool, tenant_id: &str, entries: &mut [Value]) -> AppResult<()> {
+    let ids: Vec<String> = entries
+        .iter()
+        .filter_map(|e| e["playerId"].as_str().map(str::to_string))
+        .collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let anonymous: HashSet<String> = sqlx::query_scalar(
+        "SELECT id::text FROM players WHERE tenant_id = $1 AND id::text = ANY($2) AND leaderboard_anonymous",
+    )
+    .bind(tenant_id)
+    .bind(&ids)
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .collect();
+
+    for entry in entries {
+        let hidden = entry["playerId"].as_str().is_some_and(|id| anonymous.contains(id));
+        if hidden {
+            entry["playerId"] = Value::Null;
+            entry["displayName"] = Value::from(ANONYMOUS_NAME);
+            entry["cosmetics"] = Value::Null;
+            entry["anonymous"] = Value::Bool(true);
+        }
+    }
+    Ok(())
+}
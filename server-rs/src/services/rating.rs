@@ -0,0 +1,275 @@
+//! Glicko-2 skill ratings for ranked matches.
+//!
+//! Each match is rated as one rating period: every player is scored against
+//! every other (1 for finishing ahead, ½ for a tie, 0 for behind) using
+//! their pre-match ratings. Between matches a player's deviation grows by
+//! their volatility for each idle rating period, so ratings of returning
+//! players move faster until they settle again.
+//!
+//! Defaults come from [`RatingConfig`]; `game_rating_configs` overrides any
+//! of them per game.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use std::f64::consts::PI;
+use uuid::Uuid;
+
+use crate::config::RatingConfig;
+use crate::error::AppResult;
+use crate::models::multiplayer::MatchPlayerResult;
+
+/// Converts between the displayed rating scale and Glicko-2's internal one.
+const SCALE: f64 = 173.7178;
+/// Convergence tolerance of the volatility iteration.
+const EPSILON: f64 = 0.000_001;
+/// Deviation never shrinks below this, so ratings can always still move.
+const MIN_DEVIATION: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+    pub volatility: f64,
+}
+
+impl Rating {
+    pub fn initial(params: &RatingConfig) -> Self {
+        Self {
+            rating: params.initial_rating as f64,
+            deviation: params.initial_deviation,
+            volatility: params.initial_volatility,
+        }
+    }
+
+    /// Widens the deviation for `idle_periods` rating periods without
+    /// matches, capped at the initial deviation.
+    pub fn decayed(self, idle_periods: f64, params: &RatingConfig) -> Self {
+        if idle_periods <= 0.0 {
+            return self;
+        }
+        let growth = SCALE * self.volatility;
+        let deviation = (self.deviation.powi(2) + idle_periods * growth.powi(2)).sqrt();
+        Self {
+            deviation: deviation.min(params.initial_deviation.max(self.deviation)),
+            ..self
+        }
+    }
+
+    /// The rating after one period against `results`: (opponent, score)
+    /// pairs with scores of 1, 0.5 or 0.
+    pub fn updated(self, results: &[(Rating, f64)], tau: f64) -> Self {
+        if results.is_empty() {
+            return self;
+        }
+        let mu = (self.rating - 1500.0) / SCALE;
+        let phi = self.deviation / SCALE;
+
+        let mut v_inv = 0.0;
+        let mut delta_sum = 0.0;
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / SCALE;
+            let g_j = g(opponent.deviation / SCALE);
+            let e = expected(mu, mu_j, g_j);
+            v_inv += g_j * g_j * e * (1.0 - e);
+            delta_sum += g_j * (score - e);
+        }
+        let v = 1.0 / v_inv;
+        let delta = v * delta_sum;
+
+        let sigma = new_volatility(phi, self.volatility, v, delta, tau);
+        let phi_star = (phi * phi + sigma * sigma).sqrt();
+        let phi_new = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let mu_new = mu + phi_new * phi_new * delta_sum;
+
+        Self {
+            rating: mu_new * SCALE + 1500.0,
+            deviation: (phi_new * SCALE).max(MIN_DEVIATION),
+            volatility: sigma,
+        }
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+fn expected(mu: f64, mu_j: f64, g_j: f64) -> f64 {
+    1.0 / (1.0 + (-g_j * (mu - mu_j)).exp())
+}
+
+/// Step 5 of Glicko-2: the new volatility, by the Illinois method.
+fn new_volatility(phi: f64, sigma: f64, v: f64, delta: f64, tau: f64) -> f64 {
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let d = phi * phi + v + ex;
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * d * d) - (x - a) / (tau * tau)
+    };
+
+    let mut big_a = a;
+    let mut big_b = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let mut f_a = f(big_a);
+    let mut f_b = f(big_b);
+    while (big_b - big_a).abs() > EPSILON {
+        let c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = c;
+        f_b = f_c;
+    }
+    (big_a / 2.0).exp()
+}
+
+/// How `a` did against `b`: by placement when both have one, otherwise by
+/// the winner flag.
+fn score_against(a: &MatchPlayerResult, b: &MatchPlayerResult) -> f64 {
+    let (a_rank, b_rank) = match (a.placement, b.placement) {
+        (Some(pa), Some(pb)) => (pa, pb),
+        _ => (!a.is_winner as i32, !b.is_winner as i32),
+    };
+    match a_rank.cmp(&b_rank) {
+        std::cmp::Ordering::Less => 1.0,
+        std::cmp::Ordering::Equal => 0.5,
+        std::cmp::Ordering::Greater => 0.0,
+    }
+}
+
+/// initial_rating, initial_deviation, initial_volatility, tau, rating_period_days
+pub type RatingOverrides = (Option<i32>, Option<f64>, Option<f64>, Option<f64>, Option<f64>);
+
+/// player_id, skill_rating, rating_deviation, rating_volatility, rated_at
+type EntryRow = (Uuid, i32, f64, f64, Option<DateTime<Utc>>);
+
+/// The game's row in `game_rating_configs`, if it has one.
+pub async fn overrides(db: &PgPool, tenant_id: &str, game_id: &str) -> AppResult<Option<RatingOverrides>> {
+    let row = sqlx::query_as(
+        r#"SELECT initial_rating, initial_deviation, initial_volatility, tau, rating_period_days
+        FROM game_rating_configs WHERE tenant_id = $1 AND game_id = $2"#,
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row)
+}
+
+/// Rating parameters for one game: the defaults with any per-game
+/// overrides applied.
+pub async fn params_for(
+    db: &PgPool,
+    defaults: &RatingConfig,
+    tenant_id: &str,
+    game_id: &str,
+) -> AppResult<RatingConfig> {
+    let Some((rating, deviation, volatility, tau, period)) = overrides(db, tenant_id, game_id).await? else {
+        return Ok(defaults.clone());
+    };
+    Ok(RatingConfig {
+        initial_rating: rating.unwrap_or(defaults.initial_rating),
+        initial_deviation: deviation.unwrap_or(defaults.initial_deviation),
+        initial_volatility: volatility.unwrap_or(defaults.initial_volatility),
+        tau: tau.unwrap_or(defaults.tau),
+        rating_period_days: period.unwrap_or(defaults.rating_period_days),
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingChange {
+    pub player_id: Uuid,
+    pub skill_rating: i32,
+    pub deviation: f64,
+    pub change: i32,
+}
+
+/// Rates a finished match and stores the new ratings on the players'
+/// global leaderboard entries for `season_id`, which must already exist.
+pub async fn rate_match(
+    conn: &mut PgConnection,
+    params: &RatingConfig,
+    tenant_id: &str,
+    game_id: &str,
+    season_id: Option<i32>,
+    players: &[(Uuid, &MatchPlayerResult)],
+) -> AppResult<Vec<RatingChange>> {
+    let ids: Vec<Uuid> = players.iter().map(|(id, _)| *id).collect();
+    let rows: Vec<EntryRow> = sqlx::query_as(
+        r#"SELECT player_id, skill_rating, rating_deviation, rating_volatility, rated_at
+        FROM leaderboard_entries
+        WHERE tenant_id = $1 AND game_id = $2 AND season_id IS NOT DISTINCT FROM $3
+          AND region = 'global' AND player_id = ANY($4)
+        FOR UPDATE"#,
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(season_id)
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let now = Utc::now();
+    let period_secs = params.rating_period_days.max(0.01) * 86_400.0;
+    let before: Vec<Rating> = ids
+        .iter()
+        .map(|id| match rows.iter().find(|r| r.0 == *id) {
+            Some((_, rating, deviation, volatility, rated_at)) => {
+                let idle = rated_at
+                    .map(|at| now.signed_duration_since(at).num_seconds() as f64 / period_secs)
+                    .unwrap_or(0.0);
+                Rating { rating: *rating as f64, deviation: *deviation, volatility: *volatility }
+                    .decayed(idle.floor(), params)
+            }
+            None => Rating::initial(params),
+        })
+        .collect();
+
+    let mut changes = Vec::with_capacity(players.len());
+    for (i, (player_id, result)) in players.iter().enumerate() {
+        let results: Vec<(Rating, f64)> = players
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(j, (_, other))| (before[j], score_against(result, other)))
+            .collect();
+        let after = before[i].updated(&results, params.tau);
+        let skill_rating = after.rating.round() as i32;
+
+        sqlx::query(
+            r#"UPDATE leaderboard_entries SET
+                skill_rating = $1, rating_deviation = $2, rating_volatility = $3, rated_at = NOW()
+            WHERE tenant_id = $4 AND player_id = $5 AND game_id = $6
+              AND season_id IS NOT DISTINCT FROM $7 AND region = 'global'"#,
+        )
+        .bind(skill_rating)
+        .bind(after.deviation)
+        .bind(after.volatility)
+        .bind(tenant_id)
+        .bind(player_id)
+        .bind(game_id)
+        .bind(season_id)
+        .execute(&mut *conn)
+        .await?;
+
+        changes.push(RatingChange {
+            player_id: *player_id,
+            skill_rating,
+            deviation: (after.deviation * 10.0).round() / 10.0,
+            change: skill_rating - before[i].rating.round() as i32,
+        });
+    }
+    Ok(changes)
+}
@@ -0,0 +1,111 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use uuid::Uuid;
+
+/// One open WebSocket. Events pushed to `tx` are written to the socket by
+/// the task that owns it.
+struct Connection {
+    id: u64,
+    tx: mpsc::UnboundedSender<Value>,
+}
+
+/// Open connections per (tenant_id, player_id).
+type ConnectionMap = HashMap<(String, Uuid), Vec<Connection>>;
+
+/// Realtime gateway: the in-process registry of connected WebSocket clients,
+/// keyed by tenant and player. Any handler can push a JSON event to a player
+/// without knowing which socket (or how many tabs/devices) they are on.
+#[derive(Clone)]
+pub struct RealtimeGateway {
+    connections: Arc<RwLock<ConnectionMap>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Default for RealtimeGateway {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RealtimeGateway {
+    pub fn new() -> Self {
+        Self {
+            connections: Arc::new(RwLock::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Registers a new socket for the player. Returns the connection id, the
+    /// receiver the socket task should drain, and whether this is the
+    /// player's first open connection.
+    pub async fn register(
+        &self,
+        tenant_id: &str,
+        player_id: Uuid,
+    ) -> (u64, mpsc::UnboundedReceiver<Value>, bool) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut conns = self.connections.write().await;
+        let entry = conns
+            .entry((tenant_id.to_string(), player_id))
+            .or_default();
+        let first = entry.is_empty();
+        entry.push(Connection { id, tx });
+
+        (id, rx, first)
+    }
+
+    /// Removes a socket. Returns how many connections the player still has.
+    pub async fn unregister(&self, tenant_id: &str, player_id: Uuid, conn_id: u64) -> usize {
+        let mut conns = self.connections.write().await;
+        let key = (tenant_id.to_string(), player_id);
+        let remaining = match conns.get_mut(&key) {
+            Some(list) => {
+                list.retain(|c| c.id != conn_id);
+                list.len()
+            }
+            None => 0,
+        };
+        if remaining == 0 {
+            conns.remove(&key);
+        }
+        remaining
+    }
+
+    /// Open sockets and the number of players they belong to.
+    pub async fn connection_count(&self) -> (usize, usize) {
+        let conns = self.connections.read().await;
+        (conns.values().map(Vec::len).sum(), conns.len())
+    }
+
+    pub async fn is_connected(&self, tenant_id: &str, player_id: Uuid) -> bool {
+        let conns = self.connections.read().await;
+        conns.contains_key(&(tenant_id.to_string(), player_id))
+    }
+
+    /// Pushes an event to every open connection of one player.
+    pub async fn send_to(&self, tenant_id: &str, player_id: Uuid, event: &Value) {
+        let conns = self.connections.read().await;
+        if let Some(list) = conns.get(&(tenant_id.to_string(), player_id)) {
+            for c in list {
+                let _ = c.tx.send(event.clone());
+            }
+        }
+    }
+
+    /// Pushes an event to every open connection of each listed player.
+    pub async fn send_to_many(&self, tenant_id: &str, player_ids: &[Uuid], event: &Value) {
+        let conns = self.connections.read().await;
+        for pid in player_ids {
+            if let Some(list) = conns.get(&(tenant_id.to_string(), *pid)) {
+                for c in list {
+                    let _ = c.tx.send(event.clone());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! CSV and PDF exports of classroom progress reports.
+//!
+//! Jobs are rows in `report_jobs`, processed in a background task the same
+//! way GDPR exports are: the rendered file is stored on the job as a data
+//! URL that expires after [`EXPIRY_DAYS`]. Each student row has their best
+//! score and status on every assignment, the time played in the classroom's
+//! assignment games and the achievements earned since they joined the class.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::models::organisation::{Assignment, AssignmentProgress, Classroom};
+use crate::services::classrooms;
+
+pub const FORMATS: [&str; 2] = ["csv", "pdf"];
+pub const EXPIRY_DAYS: i64 = 7;
+
+// PDF page layout, in points (A4)
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 50;
+const FONT_SIZE: u32 = 10;
+const LEADING: u32 = 13;
+const MAX_LINE_CHARS: usize = 95;
+
+pub struct ClassroomReport {
+    pub classroom: Classroom,
+    pub assignments: Vec<Assignment>,
+    pub students: Vec<StudentReport>,
+    pub generated_at: DateTime<Utc>,
+}
+
+pub struct StudentReport {
+    pub player_id: Uuid,
+    pub display_name: String,
+    pub play_time_ms: i64,
+    pub achievements: i64,
+    /// Aligned with [`ClassroomReport::assignments`].
+    pub progress: Vec<Option<AssignmentProgress>>,
+}
+
+impl StudentReport {
+    fn count(&self, assignments: &[Assignment], status: &str) -> usize {
+        self.progress
+            .iter()
+            .zip(assignments)
+            .filter(|(p, a)| p.as_ref().map(|p| classrooms::progress_status(p, a.due_at)) == Some(status))
+            .count()
+    }
+}
+
+pub async fn build_report(
+    db: &sqlx::PgPool,
+    org_id: &str,
+    classroom_id: Uuid,
+    tenant_id: &str,
+) -> AppResult<ClassroomReport> {
+    let classroom = classrooms::fetch_classroom(db, org_id, classroom_id, tenant_id).await?;
+    let assignments = classrooms::fetch_assignments(db, classroom_id, tenant_id).await?;
+    let roster = classrooms::roster(db, classroom_id, tenant_id).await?;
+    let mut progress = classrooms::assignment_progress(db, classroom_id, tenant_id, None).await?;
+
+    let stats: Vec<(Uuid, i64, i64)> = sqlx::query_as(
+        r#"SELECT cs.player_id,
+            (SELECT COALESCE(SUM(sh.play_time), 0)::bigint FROM score_history sh
+             WHERE sh.player_id::text = cs.player_id::text AND sh.tenant_id = cs.tenant_id
+                AND sh.created_at >= cs.added_at
+                AND sh.game_id IN (SELECT a.game_id FROM assignments a WHERE a.classroom_id = cs.classroom_id)),
+            (SELECT COUNT(*) FROM player_achievements pa
+             WHERE pa.player_id::text = cs.player_id::text AND pa.tenant_id = cs.tenant_id
+                AND pa.earned_at >= cs.added_at)::bigint
+        FROM classroom_students cs
+        WHERE cs.classroom_id = $1 AND cs.tenant_id = $2"#,
+    )
+    .bind(classroom_id)
+    .bind(tenant_id)
+    .fetch_all(db)
+    .await?;
+
+    let students = roster
+        .into_iter()
+        .map(|(player_id, display_name, _)| {
+            let (play_time_ms, achievements) = stats
+                .iter()
+                .find(|(id, _, _)| *id == player_id)
+                .map(|(_, t, a)| (*t, *a))
+                .unwrap_or_default();
+            let progress = assignments
+                .iter()
+                .map(|a| {
+                    progress
+                        .iter()
+                        .position(|p| p.assignment_id == a.id && p.player_id == player_id)
+                        .map(|i| progress.swap_remove(i))
+                })
+                .collect();
+            StudentReport { player_id, display_name, play_time_ms, achievements, progress }
+        })
+        .collect();
+
+    Ok(ClassroomReport { classroom, assignments, students, generated_at: Utc::now() })
+}
+
+/// Renders the report as `(mime type, bytes)`. `format` is one of [`FORMATS`].
+pub fn render(report: &ClassroomReport, format: &str) -> (&'static str, Vec<u8>) {
+    match format {
+        "pdf" => ("application/pdf", render_pdf(report)),
+        _ => ("text/csv", render_csv(report).into_bytes()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CSV
+// ---------------------------------------------------------------------------
+
+/// Quotes a field when needed, and defuses values a spreadsheet would run
+/// as a formula (display names are player-controlled).
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+pub fn render_csv(report: &ClassroomReport) -> String {
+    let mut header = vec![
+        "Student".to_string(),
+        "Player ID".to_string(),
+        "Completed".to_string(),
+        "Late".to_string(),
+        "Minutes Played".to_string(),
+        "Achievements".to_string(),
+    ];
+    for a in &report.assignments {
+        header.push(format!("{} - Best Score", a.title));
+        header.push(format!("{} - Status", a.title));
+    }
+
+    let mut out = String::new();
+    out.push_str(&header.iter().map(|h| csv_field(h)).collect::<Vec<_>>().join(","));
+    out.push_str("\r\n");
+
+    for s in &report.students {
+        let mut row = vec![
+            csv_field(&s.display_name),
+            s.player_id.to_string(),
+            s.count(&report.assignments, "completed").to_string(),
+            s.count(&report.assignments, "late").to_string(),
+            (s.play_time_ms / 60_000).to_string(),
+            s.achievements.to_string(),
+        ];
+        for (p, a) in s.progress.iter().zip(&report.assignments) {
+            match p {
+                Some(p) => {
+                    row.push(p.best_score.map(|b| b.to_string()).unwrap_or_default());
+                    row.push(classrooms::progress_status(p, a.due_at).to_string());
+                }
+                None => {
+                    row.push(String::new());
+                    row.push("not_started".to_string());
+                }
+            }
+        }
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// PDF
+// ---------------------------------------------------------------------------
+
+fn report_lines(report: &ClassroomReport) -> Vec<String> {
+    let mut lines = vec![
+        format!("Progress report: {}", report.classroom.name),
+        format!(
+            "{} students, {} assignments. Generated {}",
+            report.students.len(),
+            report.assignments.len(),
+            report.generated_at.format("%Y-%m-%d %H:%M UTC")
+        ),
+        String::new(),
+        "Assignments".to_string(),
+    ];
+    for a in &report.assignments {
+        lines.push(format!(
+            "  {}: {} points in {}, due {}",
+            a.title,
+            a.target_score,
+            a.game_id,
+            a.due_at.format("%Y-%m-%d")
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("Students".to_string());
+    for s in &report.students {
+        lines.push(String::new());
+        lines.push(format!(
+            "{}  ({} completed, {} late, {} min played, {} achievements)",
+            s.display_name,
+            s.count(&report.assignments, "completed"),
+            s.count(&report.assignments, "late"),
+            s.play_time_ms / 60_000,
+            s.achievements
+        ));
+        for (p, a) in s.progress.iter().zip(&report.assignments) {
+            let (best, status) = match p {
+                Some(p) => (p.best_score.unwrap_or(0), classrooms::progress_status(p, a.due_at)),
+                None => (0, "not_started"),
+            };
+            lines.push(format!("  {}: best {} / {} - {}", a.title, best, a.target_score, status));
+        }
+    }
+    lines
+}
+
+/// Escapes a line for a PDF string literal. Helvetica only covers Latin-1
+/// here, so anything else is replaced.
+fn pdf_text(line: &str) -> String {
+    let mut out = String::new();
+    for c in line.chars().take(MAX_LINE_CHARS) {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+/// A minimal single-font PDF: one text object per page.
+pub fn render_pdf(report: &ClassroomReport) -> Vec<u8> {
+    let lines = report_lines(report);
+    let per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+
+    // Objects 1-3 are the catalog, page tree and font; pages follow.
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+    ];
+    let mut kids = Vec::new();
+    for page in lines.chunks(per_page) {
+        let mut content = format!(
+            "BT /F1 {} Tf {} TL {} {} Td\n",
+            FONT_SIZE,
+            LEADING,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN
+        );
+        for line in page {
+            content.push_str(&format!("({}) '\n", pdf_text(line)));
+        }
+        content.push_str("ET");
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            objects.len()
+        ));
+        kids.push(format!("{} 0 R", objects.len()));
+    }
+    objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len());
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, body).as_bytes());
+    }
+    let xref_at = out.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        xref.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    xref.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_at
+    ));
+    out.extend_from_slice(xref.as_bytes());
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Jobs
+// ---------------------------------------------------------------------------
+
+/// Renders a pending job and stores the result. Failures are recorded on
+/// the job rather than returned.
+pub async fn process_job(db: &sqlx::PgPool, job_id: Uuid) {
+    if let Err(e) = run_job(db, job_id).await {
+        tracing::error!("Report job {} failed: {}", job_id, e);
+        let _ = sqlx::query(
+            "UPDATE report_jobs SET status = 'failed', error = $1, completed_at = NOW() WHERE id = $2",
+        )
+        .bind(e.to_string())
+        .bind(job_id)
+        .execute(db)
+        .await;
+    }
+}
+
+async fn run_job(db: &sqlx::PgPool, job_id: Uuid) -> AppResult<()> {
+    let job: Option<(String, Uuid, String, String)> = sqlx::query_as(
+        r#"UPDATE report_jobs SET status = 'processing'
+        WHERE id = $1 AND status = 'pending'
+        RETURNING organisation_id, classroom_id, tenant_id, format"#,
+    )
+    .bind(job_id)
+    .fetch_optional(db)
+    .await?;
+    let Some((org_id, classroom_id, tenant_id, format)) = job else {
+        return Ok(());
+    };
+
+    let report = build_report(db, &org_id, classroom_id, &tenant_id).await?;
+    let (mime, bytes) = render(&report, &format);
+
+    use base64::Engine;
+    let download_url = format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    );
+    let expires = Utc::now() + chrono::Duration::days(EXPIRY_DAYS);
+
+    sqlx::query(
+        "UPDATE report_jobs SET status = 'completed', download_url = $1, completed_at = NOW(), expires_at = $2 WHERE id = $3",
+    )
+    .bind(&download_url)
+    .bind(expires)
+    .bind(job_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
@@ -1,23 +1,157 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::error::{AppError, AppResult};
-use crate::models::multiplayer::{Room, RoomPlayer};
+use crate::models::multiplayer::{BotOpponent, Room, RoomPlayer, Turn, TurnState};
+use crate::AppState;
 
+/// Players per matchmade room.
+const MATCH_SIZE: usize = 2;
+/// Rating difference accepted on joining the queue.
+const BASE_BAND: i32 = 100;
+/// How much the band widens per second waited.
+const BAND_GROWTH_PER_SEC: i32 = 10;
+const MAX_BAND: i32 = 600;
+/// Players still unmatched after this long are dropped from the queue.
+const MAX_QUEUE_SECS: i64 = 300;
+/// Matches nobody picks up through status polling are forgotten after this
+/// long.
+const MAX_MATCHED_SECS: i64 = 300;
+const MATCHMAKER_TICK: Duration = Duration::from_secs(2);
+
+/// Games the engine can play against a bot (`"bot": true` in its catalog).
+pub const BOT_GAMES: &[&str] = &["formula_stem", "physics_master_billiards", "stem_project_volley"];
+/// Players who opted in are matched with a bot after waiting this long.
+const BOT_FALLBACK_SECS: i64 = 30;
+/// Bot difficulty by the player's rating: below the first is easy, below
+/// the second medium, otherwise hard.
+const BOT_MEDIUM_RATING: i32 = 900;
+const BOT_HARD_RATING: i32 = 1200;
+
+/// Turn limits for turn-based rooms.
+pub const DEFAULT_TURN_SECS: i64 = 86_400;
+pub const MIN_TURN_SECS: i64 = 60;
+pub const MAX_TURN_SECS: i64 = 7 * 86_400;
+/// Largest turn payload accepted, serialized.
+const MAX_TURN_BYTES: usize = 16 * 1024;
+const TURN_EXPIRY_TICK: Duration = Duration::from_secs(15);
+
+/// Queues are per tenant and game.
+type QueueKey = (String, String);
+/// Room a player was matched into, and when.
+type MatchedRoom = (String, DateTime<Utc>);
+
+/// A player waiting in the matchmaking queue.
+#[derive(Debug, Clone)]
+pub struct QueueEntry {
+    pub player: RoomPlayer,
+    pub tenant_id: String,
+    pub game_id: String,
+    pub skill_rating: i32,
+    pub joined_at: DateTime<Utc>,
+    /// Play a bot after [`BOT_FALLBACK_SECS`] rather than keep waiting.
+    pub bot_fallback: bool,
+}
+
+impl QueueEntry {
+    fn wait_secs(&self, now: DateTime<Utc>) -> i64 {
+        now.signed_duration_since(self.joined_at).num_seconds().max(0)
+    }
+
+    /// The rating difference this player accepts after waiting until `now`.
+    fn band(&self, now: DateTime<Utc>) -> i32 {
+        let grown = BASE_BAND as i64 + self.wait_secs(now) * BAND_GROWTH_PER_SEC as i64;
+        grown.min(MAX_BAND as i64) as i32
+    }
+
+    /// Whether the two can play each other; the longer waiter's band
+    /// decides, so nobody is stuck behind a newcomer's narrow band.
+    fn accepts(&self, other: &QueueEntry, now: DateTime<Utc>) -> bool {
+        (self.skill_rating - other.skill_rating).abs() <= self.band(now).max(other.band(now))
+    }
+
+    /// Seconds until this player is matched with a bot, if they asked to be.
+    fn bot_in_secs(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.bot_fallback.then(|| (BOT_FALLBACK_SECS - self.wait_secs(now)).max(0))
+    }
+
+    /// A bot at this player's rating.
+    fn bot(&self) -> BotOpponent {
+        let difficulty = if self.skill_rating < BOT_MEDIUM_RATING {
+            "easy"
+        } else if self.skill_rating < BOT_HARD_RATING {
+            "medium"
+        } else {
+            "hard"
+        };
+        BotOpponent {
+            display_name: "Bot".to_string(),
+            difficulty: difficulty.to_string(),
+            skill_rating: self.skill_rating,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub game_id: String,
+    pub skill_rating: i32,
+    pub wait_secs: i64,
+    pub band: i32,
+    pub queue_size: usize,
+    /// Seconds until a bot match, when the player accepts one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot_in_secs: Option<i64>,
+}
+
+/// A player dropped from a turn-based game.
+pub struct Forfeit {
+    pub room: Room,
+    pub player_id: Uuid,
+}
+
+/// A room formed by the matchmaker.
+pub struct Match {
+    pub tenant_id: String,
+    pub room: Room,
+    pub entries: Vec<QueueEntry>,
+}
+
+/// Rooms live in memory. Turn-based rooms are also kept in `turn_games`,
+/// since their games outlast a restart; see [`RoomManager::save_turn_room`].
 #[derive(Clone)]
 pub struct RoomManager {
+    db: PgPool,
     rooms: Arc<RwLock<HashMap<String, Room>>>,
     player_rooms: Arc<RwLock<HashMap<Uuid, String>>>,
+    /// Room each spectator is watching; a player watches at most one.
+    spectator_rooms: Arc<RwLock<HashMap<Uuid, String>>>,
+    queues: Arc<RwLock<HashMap<QueueKey, Vec<QueueEntry>>>>,
+    /// Room the matchmaker last put each player in, and when, for status
+    /// polling.
+    matched: Arc<RwLock<HashMap<Uuid, MatchedRoom>>>,
+    /// Finished room -> the rematch room opened from it.
+    rematches: Arc<RwLock<HashMap<String, String>>>,
 }
 
 impl RoomManager {
-    pub fn new() -> Self {
+    pub fn new(db: PgPool) -> Self {
         Self {
+            db,
             rooms: Arc::new(RwLock::new(HashMap::new())),
             player_rooms: Arc::new(RwLock::new(HashMap::new())),
+            spectator_rooms: Arc::new(RwLock::new(HashMap::new())),
+            queues: Arc::new(RwLock::new(HashMap::new())),
+            matched: Arc::new(RwLock::new(HashMap::new())),
+            rematches: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -49,32 +183,45 @@ impl RoomManager {
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_room(
         &self,
+        tenant_id: &str,
         player: RoomPlayer,
         game_id: String,
         max_players: i32,
         is_private: bool,
-    ) -> Room {
+        allow_spectators: bool,
+        turn: Option<TurnState>,
+    ) -> AppResult<Room> {
         let room_id = Uuid::new_v4().to_string();
         let room = Room {
             id: room_id.clone(),
+            tenant_id: tenant_id.to_string(),
             game_id,
             host_id: player.id,
             players: vec![player.clone()],
             max_players,
             state: "waiting".to_string(),
             is_private,
+            allow_spectators,
+            spectator_count: 0,
+            spectators: HashMap::new(),
+            turn,
+            bot: None,
             created_at: Utc::now(),
         };
 
-        let mut rooms = self.rooms.write().await;
-        rooms.insert(room_id.clone(), room.clone());
-
-        let mut pr = self.player_rooms.write().await;
-        pr.insert(player.id, room_id);
+        // Turn-based games run alongside each other and live play
+        if room.turn.is_some() {
+            self.save_turn_room(&room).await?;
+        } else {
+            let mut pr = self.player_rooms.write().await;
+            pr.insert(player.id, room_id.clone());
+        }
+        self.rooms.write().await.insert(room_id, room.clone());
 
-        room
+        Ok(room)
     }
 
     pub async fn join_room(
@@ -82,6 +229,7 @@ impl RoomManager {
         room_id: &str,
         player: RoomPlayer,
     ) -> AppResult<Room> {
+        self.load_turn_room(room_id).await?;
         let mut rooms = self.rooms.write().await;
         let room = rooms
             .get_mut(room_id)
@@ -97,6 +245,16 @@ impl RoomManager {
             return Err(AppError::Conflict("Already in room".into()));
         }
 
+        if room.turn.is_some() {
+            let mut updated = room.clone();
+            updated.players.push(player);
+            if updated.players.len() == updated.max_players as usize {
+                start_turns(&mut updated);
+            }
+            self.save_turn_room(&updated).await?;
+            *room = updated.clone();
+            return Ok(updated);
+        }
         let pid = player.id;
         room.players.push(player);
         let result = room.clone();
@@ -108,44 +266,648 @@ impl RoomManager {
         Ok(result)
     }
 
+    /// The room, loading a turn-based one from `turn_games` if it isn't in
+    /// memory.
     pub async fn get_room(&self, room_id: &str) -> Option<Room> {
+        match self.load_turn_room(room_id).await {
+            Ok(room) => room,
+            Err(e) => {
+                tracing::warn!("Could not load turn game {}: {}", room_id, e);
+                self.rooms.read().await.get(room_id).cloned()
+            }
+        }
+    }
+
+    pub async fn get_player_room(&self, player_id: Uuid) -> Option<Room> {
+        let pr = self.player_rooms.read().await;
+        let room_id = pr.get(&player_id)?;
         let rooms = self.rooms.read().await;
         rooms.get(room_id).cloned()
     }
 
-    pub async fn find_match(
+    /// Adds a spectator to a room, moving them out of any room they were
+    /// already watching. Players of the room can't spectate it, and only
+    /// their friends may spectate a private room.
+    pub async fn add_spectator(
         &self,
-        player: RoomPlayer,
+        room_id: &str,
+        tenant_id: &str,
+        player_id: Uuid,
+    ) -> AppResult<Room> {
+        self.remove_spectator(player_id).await;
+
+        let room = self
+            .get_room(room_id)
+            .await
+            .filter(|r| r.tenant_id == tenant_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+        if !room.allow_spectators {
+            return Err(AppError::Forbidden("Room does not allow spectators".into()));
+        }
+        if room.players.iter().any(|p| p.id == player_id) {
+            return Err(AppError::Conflict("Players can't spectate their own room".into()));
+        }
+        if room.is_private && !self.is_invited(&room, player_id).await? {
+            return Err(AppError::Forbidden("Room is private".into()));
+        }
+
+        let mut rooms = self.rooms.write().await;
+        let room = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+
+        room.spectators.insert(player_id, tenant_id.to_string());
+        room.spectator_count = room.spectators.len();
+        let result = room.clone();
+
+        drop(rooms);
+        let mut sr = self.spectator_rooms.write().await;
+        sr.insert(player_id, room_id.to_string());
+
+        Ok(result)
+    }
+
+    /// Whether `player_id` is a friend of one of the room's players, which
+    /// lets them into a private room as a spectator.
+    async fn is_invited(&self, room: &Room, player_id: Uuid) -> AppResult<bool> {
+        let player_ids: Vec<Uuid> = room.players.iter().map(|p| p.id).collect();
+        let invited: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(
+                SELECT 1 FROM friendships
+                WHERE tenant_id = $1 AND status = 'accepted'
+                    AND ((player_id = $2 AND friend_id = ANY($3)) OR (friend_id = $2 AND player_id = ANY($3)))
+            )"#,
+        )
+        .bind(&room.tenant_id)
+        .bind(player_id)
+        .bind(&player_ids)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(invited)
+    }
+
+    /// Stops a player spectating. Returns the room they were watching.
+    pub async fn remove_spectator(&self, player_id: Uuid) -> Option<Room> {
+        let room_id = self.spectator_rooms.write().await.remove(&player_id)?;
+        let mut rooms = self.rooms.write().await;
+        let room = rooms.get_mut(&room_id)?;
+        room.spectators.remove(&player_id);
+        room.spectator_count = room.spectators.len();
+        Some(room.clone())
+    }
+
+    // -- Matchmaking -----------------------------------------------------
+
+    /// Puts the player in the queue for `game_id`, leaving any other queue.
+    /// Re-joining the same game keeps their place and wait time.
+    /// `bot_fallback` matches them with a bot if no one turns up in time.
+    pub async fn enqueue(
+        &self,
+        tenant_id: &str,
         game_id: &str,
-    ) -> Room {
-        // Try to find a waiting room
-        let rooms = self.rooms.read().await;
-        for room in rooms.values() {
-            if room.game_id == game_id
-                && room.state == "waiting"
-                && !room.is_private
-                && (room.players.len() as i32) < room.max_players
-                && !room.players.iter().any(|p| p.id == player.id)
-            {
-                let room_id = room.id.clone();
-                drop(rooms);
-                if let Ok(room) = self.join_room(&room_id, player.clone()).await {
-                    return room;
+        player: RoomPlayer,
+        skill_rating: i32,
+        bot_fallback: bool,
+    ) {
+        let key = (tenant_id.to_string(), game_id.to_string());
+        let player_id = player.id;
+        self.matched.write().await.remove(&player_id);
+
+        let mut queues = self.queues.write().await;
+        for (k, queue) in queues.iter_mut() {
+            if *k != key {
+                queue.retain(|e| e.player.id != player_id);
+            }
+        }
+        let queue = queues.entry(key).or_default();
+        match queue.iter_mut().find(|e| e.player.id == player_id) {
+            Some(entry) => {
+                entry.skill_rating = skill_rating;
+                entry.bot_fallback = bot_fallback;
+            }
+            None => queue.push(QueueEntry {
+                player,
+                tenant_id: tenant_id.to_string(),
+                game_id: game_id.to_string(),
+                skill_rating,
+                joined_at: Utc::now(),
+                bot_fallback,
+            }),
+        }
+        queues.retain(|_, q| !q.is_empty());
+    }
+
+    /// Takes the player out of whatever queue they are in. Returns whether
+    /// they were queued.
+    pub async fn dequeue(&self, player_id: Uuid) -> bool {
+        let mut queues = self.queues.write().await;
+        let mut found = false;
+        for queue in queues.values_mut() {
+            let before = queue.len();
+            queue.retain(|e| e.player.id != player_id);
+            found |= queue.len() != before;
+        }
+        queues.retain(|_, q| !q.is_empty());
+        found
+    }
+
+    pub async fn queue_status(&self, player_id: Uuid) -> Option<QueueStatus> {
+        let queues = self.queues.read().await;
+        Self::status_in(&queues, player_id)
+    }
+
+    /// The room the matchmaker put the player in, if they haven't queued
+    /// again or already picked it up since.
+    pub async fn matched_room(&self, player_id: Uuid) -> Option<Room> {
+        let (room_id, _) = self.matched.write().await.remove(&player_id)?;
+        self.get_room(&room_id).await
+    }
+
+    fn status_in(queues: &HashMap<QueueKey, Vec<QueueEntry>>, player_id: Uuid) -> Option<QueueStatus> {
+        let now = Utc::now();
+        queues.values().find_map(|queue| {
+            let entry = queue.iter().find(|e| e.player.id == player_id)?;
+            Some(QueueStatus {
+                game_id: entry.game_id.clone(),
+                skill_rating: entry.skill_rating,
+                wait_secs: entry.wait_secs(now),
+                band: entry.band(now),
+                queue_size: queue.len(),
+                bot_in_secs: entry.bot_in_secs(now),
+            })
+        })
+    }
+
+    /// Forms every match the current bands allow, oldest waiters first,
+    /// matches those still alone after [`BOT_FALLBACK_SECS`] with a bot if
+    /// they asked to be, and drops entries that waited past
+    /// [`MAX_QUEUE_SECS`]. Returns the new rooms and the expired entries.
+    /// Matches older than [`MAX_MATCHED_SECS`] are forgotten.
+    pub async fn form_matches(&self) -> (Vec<Match>, Vec<QueueEntry>) {
+        let now = Utc::now();
+        self.matched
+            .write()
+            .await
+            .retain(|_, (_, at)| now.signed_duration_since(*at).num_seconds() <= MAX_MATCHED_SECS);
+        let mut formed = Vec::new();
+        let mut expired = Vec::new();
+
+        let mut queues = self.queues.write().await;
+        for queue in queues.values_mut() {
+            queue.sort_by_key(|e| e.joined_at);
+            let (stale, waiting): (Vec<_>, Vec<_>) =
+                queue.drain(..).partition(|e| e.wait_secs(now) > MAX_QUEUE_SECS);
+            expired.extend(stale);
+
+            let mut left: Vec<QueueEntry> = Vec::new();
+            let mut pending = waiting;
+            while !pending.is_empty() {
+                let first = pending.remove(0);
+                let mut group = vec![first];
+                let mut i = 0;
+                while i < pending.len() && group.len() < MATCH_SIZE {
+                    if group.iter().all(|g| g.accepts(&pending[i], now)) {
+                        group.push(pending.remove(i));
+                    } else {
+                        i += 1;
+                    }
+                }
+                if group.len() == MATCH_SIZE {
+                    formed.push((group, None));
+                } else if group[0].bot_in_secs(now) == Some(0) {
+                    let bot = group[0].bot();
+                    formed.push((group, Some(bot)));
+                } else {
+                    left.extend(group);
                 }
-                // If join failed, create a new room
-                return self.create_room(player, game_id.to_string(), 4, false).await;
             }
+            left.sort_by_key(|e| e.joined_at);
+            *queue = left;
         }
-        drop(rooms);
+        queues.retain(|_, q| !q.is_empty());
+        drop(queues);
 
-        // No room found, create one
-        self.create_room(player, game_id.to_string(), 4, false).await
+        let mut matches = Vec::with_capacity(formed.len());
+        for (entries, bot) in formed {
+            let room = self.create_match_room(&entries, bot).await;
+            matches.push(Match { tenant_id: entries[0].tenant_id.clone(), room, entries });
+        }
+        (matches, expired)
     }
 
-    pub async fn get_player_room(&self, player_id: Uuid) -> Option<Room> {
-        let pr = self.player_rooms.read().await;
-        let room_id = pr.get(&player_id)?;
+    /// A private room holding exactly the matched players; the longest
+    /// waiter hosts. A bot match holds the one player and the `bot`.
+    async fn create_match_room(&self, entries: &[QueueEntry], bot: Option<BotOpponent>) -> Room {
+        let room = Room {
+            id: Uuid::new_v4().to_string(),
+            tenant_id: entries[0].tenant_id.clone(),
+            game_id: entries[0].game_id.clone(),
+            host_id: entries[0].player.id,
+            players: entries.iter().map(|e| e.player.clone()).collect(),
+            max_players: entries.len() as i32,
+            state: "waiting".to_string(),
+            is_private: true,
+            allow_spectators: true,
+            spectator_count: 0,
+            spectators: HashMap::new(),
+            turn: None,
+            bot,
+            created_at: Utc::now(),
+        };
+
+        self.rooms.write().await.insert(room.id.clone(), room.clone());
+        let mut pr = self.player_rooms.write().await;
+        let mut matched = self.matched.write().await;
+        for e in entries {
+            pr.insert(e.player.id, room.id.clone());
+            matched.insert(e.player.id, (room.id.clone(), room.created_at));
+        }
+        room
+    }
+
+    // -- Rematches -------------------------------------------------------
+
+    /// Marks a room finished once its match result is in; it no longer
+    /// takes joins.
+    pub async fn finish_room(&self, room_id: &str) -> Option<Room> {
+        let room = {
+            let mut rooms = self.rooms.write().await;
+            let room = rooms.get_mut(room_id)?;
+            room.state = "finished".to_string();
+            room.clone()
+        };
+        if room.turn.is_some() {
+            let deleted = sqlx::query("DELETE FROM turn_games WHERE room_id = $1")
+                .bind(room_id)
+                .execute(&self.db)
+                .await;
+            if let Err(e) = deleted {
+                tracing::warn!("Could not delete turn game {}: {}", room_id, e);
+            }
+        }
+        Some(room)
+    }
+
+    /// The rematch room for `finished_room_id`, opened with `template`'s
+    /// game and settings (and bot) and `host` as host. The first
+    /// participant to ask opens it; later ones join it. Returns the room
+    /// and whether it was just opened.
+    pub async fn rematch(
+        &self,
+        finished_room_id: &str,
+        template: &Room,
+        host: RoomPlayer,
+    ) -> AppResult<(Room, bool)> {
+        // Held throughout so two participants asking at once share a room
+        let mut rematches = self.rematches.write().await;
+        if let Some(room_id) = rematches.get(finished_room_id).cloned() {
+            if let Some(room) = self.get_room(&room_id).await {
+                if room.players.iter().any(|p| p.id == host.id) {
+                    return Ok((room, false));
+                }
+                if room.state == "waiting" {
+                    return Ok((self.join_room(&room_id, host).await?, false));
+                }
+            }
+        }
+
+        let mut room = self
+            .create_room(
+                &template.tenant_id,
+                host,
+                template.game_id.clone(),
+                template.max_players,
+                template.is_private,
+                template.allow_spectators,
+                template.turn.as_ref().map(|t| TurnState::new(&t.tenant_id, t.turn_secs)),
+            )
+            .await?;
+        if let Some(bot) = &template.bot {
+            room.bot = Some(bot.clone());
+            if let Some(stored) = self.rooms.write().await.get_mut(&room.id) {
+                stored.bot = room.bot.clone();
+            }
+        }
+        rematches.insert(finished_room_id.to_string(), room.id.clone());
+        Ok((room, true))
+    }
+
+    // -- Turn-based play -------------------------------------------------
+
+    /// Records the current player's turn and passes play to the next.
+    pub async fn submit_turn(
+        &self,
+        room_id: &str,
+        player_id: Uuid,
+        data: serde_json::Value,
+    ) -> AppResult<(Room, Turn)> {
+        if serde_json::to_vec(&data).map_or(0, |b| b.len()) > MAX_TURN_BYTES {
+            return Err(AppError::BadRequest("Turn data is too large".into()));
+        }
+
+        self.load_turn_room(room_id).await?;
+        let mut rooms = self.rooms.write().await;
+        let stored = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+        let mut room = stored.clone();
+        let playing = room.state == "playing";
+        let turns = room
+            .turn
+            .as_mut()
+            .ok_or_else(|| AppError::BadRequest("Room is not turn-based".into()))?;
+        if !playing {
+            return Err(AppError::BadRequest("Game is not in progress".into()));
+        }
+        if turns.current_player_id != Some(player_id) {
+            return Err(AppError::Forbidden("Not your turn".into()));
+        }
+
+        turns.turn_number += 1;
+        let turn = Turn {
+            number: turns.turn_number,
+            player_id,
+            data,
+            submitted_at: Utc::now(),
+        };
+        turns.turns.push(turn.clone());
+        pass_turn(&mut room);
+        self.save_turn_room(&room).await?;
+        *stored = room.clone();
+        Ok((room, turn))
+    }
+
+    /// The room and its turns numbered above `since`, for one of its
+    /// players.
+    pub async fn turns_since(&self, room_id: &str, player_id: Uuid, since: i32) -> AppResult<(Room, Vec<Turn>)> {
+        self.load_turn_room(room_id).await?;
         let rooms = self.rooms.read().await;
-        rooms.get(room_id).cloned()
+        let room = rooms
+            .get(room_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+        let turns = room
+            .turn
+            .as_ref()
+            .ok_or_else(|| AppError::BadRequest("Room is not turn-based".into()))?;
+        if !room.players.iter().any(|p| p.id == player_id) {
+            return Err(AppError::Forbidden("Not a player in this room".into()));
+        }
+        let newer = turns.turns.iter().filter(|t| t.number > since).cloned().collect();
+        Ok((room.clone(), newer))
+    }
+
+    /// Takes a player out of a turn-based game in progress, e.g. when they
+    /// resign.
+    pub async fn forfeit(&self, room_id: &str, player_id: Uuid) -> AppResult<Room> {
+        self.load_turn_room(room_id).await?;
+        let mut rooms = self.rooms.write().await;
+        let stored = rooms
+            .get_mut(room_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".into()))?;
+        let mut room = stored.clone();
+        if room.turn.is_none() {
+            return Err(AppError::BadRequest("Room is not turn-based".into()));
+        }
+        if room.state != "playing" {
+            return Err(AppError::BadRequest("Game is not in progress".into()));
+        }
+        let out = room.turn.as_ref().is_some_and(|t| t.forfeited.contains(&player_id));
+        if out || !room.players.iter().any(|p| p.id == player_id) {
+            return Err(AppError::Forbidden("Not a player in this game".into()));
+        }
+        forfeit_player(&mut room, player_id);
+        self.save_turn_room(&room).await?;
+        *stored = room.clone();
+        Ok(room)
+    }
+
+    /// Forfeits every player whose turn deadline has passed.
+    pub async fn expire_turns(&self) -> Vec<Forfeit> {
+        let now = Utc::now();
+        let mut forfeits = Vec::new();
+        let mut rooms = self.rooms.write().await;
+        for room in rooms.values_mut() {
+            if room.state != "playing" {
+                continue;
+            }
+            let Some(turns) = &room.turn else { continue };
+            let (Some(player_id), Some(deadline)) = (turns.current_player_id, turns.deadline) else {
+                continue;
+            };
+            if deadline < now {
+                forfeit_player(room, player_id);
+                forfeits.push(Forfeit { room: room.clone(), player_id });
+            }
+        }
+        drop(rooms);
+
+        for forfeit in &forfeits {
+            if let Err(e) = self.save_turn_room(&forfeit.room).await {
+                tracing::warn!("Could not save turn game {}: {}", forfeit.room.id, e);
+            }
+        }
+        forfeits
+    }
+
+    // -- Turn game storage -----------------------------------------------
+
+    /// Writes a turn-based room and its turns to `turn_games`. Spectators
+    /// aren't kept; they rejoin over the presence socket.
+    async fn save_turn_room(&self, room: &Room) -> AppResult<()> {
+        let Some(turns) = &room.turn else { return Ok(()) };
+        let player_ids: Vec<Uuid> = room.players.iter().map(|p| p.id).collect();
+        sqlx::query(
+            r#"INSERT INTO turn_games (room_id, tenant_id, player_ids, room, turns)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (room_id) DO UPDATE SET
+                player_ids = EXCLUDED.player_ids,
+                room = EXCLUDED.room,
+                turns = EXCLUDED.turns,
+                updated_at = NOW()"#,
+        )
+        .bind(&room.id)
+        .bind(&turns.tenant_id)
+        .bind(&player_ids)
+        .bind(json!(room))
+        .bind(json!(turns.turns))
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// The room from memory, or else the turn-based room stored under
+    /// `room_id`, which is then kept in memory again.
+    async fn load_turn_room(&self, room_id: &str) -> AppResult<Option<Room>> {
+        if let Some(room) = self.rooms.read().await.get(room_id) {
+            return Ok(Some(room.clone()));
+        }
+        let row: Option<(String, serde_json::Value, serde_json::Value)> = sqlx::query_as(
+            "SELECT tenant_id, room, turns FROM turn_games WHERE room_id = $1",
+        )
+        .bind(room_id)
+        .fetch_optional(&self.db)
+        .await?;
+        let Some(row) = row else { return Ok(None) };
+        let room = restore_room(row)?;
+
+        let mut rooms = self.rooms.write().await;
+        Ok(Some(rooms.entry(room.id.clone()).or_insert(room).clone()))
+    }
+
+    /// Loads every stored turn-based room not already in memory, so their
+    /// deadlines keep running after a restart. Returns how many were
+    /// loaded.
+    pub async fn restore_turn_rooms(&self) -> AppResult<usize> {
+        let rows: Vec<(String, serde_json::Value, serde_json::Value)> =
+            sqlx::query_as("SELECT tenant_id, room, turns FROM turn_games")
+                .fetch_all(&self.db)
+                .await?;
+        let mut rooms = self.rooms.write().await;
+        let mut restored = 0;
+        for row in rows {
+            let room = restore_room(row)?;
+            if !rooms.contains_key(&room.id) {
+                rooms.insert(room.id.clone(), room);
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+}
+
+/// Rebuilds a room from its `turn_games` row.
+fn restore_room((tenant_id, room, turns): (String, serde_json::Value, serde_json::Value)) -> AppResult<Room> {
+    let mut room: Room = serde_json::from_value(room)
+        .map_err(|e| AppError::Internal(format!("Invalid stored turn game: {}", e)))?;
+    room.spectator_count = 0;
+    room.tenant_id = tenant_id.clone();
+    if let Some(state) = room.turn.as_mut() {
+        state.tenant_id = tenant_id;
+        state.turns = serde_json::from_value(turns)
+            .map_err(|e| AppError::Internal(format!("Invalid stored turns: {}", e)))?;
+    }
+    Ok(room)
+}
+
+/// Starts a full turn-based room with the host to move.
+fn start_turns(room: &mut Room) {
+    let first = room.players[0].id;
+    if let Some(turns) = room.turn.as_mut() {
+        room.state = "playing".to_string();
+        turns.current_player_id = Some(first);
+        turns.deadline = Some(Utc::now() + chrono::Duration::seconds(turns.turn_secs));
+    }
+}
+
+/// Hands the turn to the next player still in the game, after the current
+/// one in join order.
+fn pass_turn(room: &mut Room) {
+    let Some(turns) = room.turn.as_mut() else { return };
+    let active: Vec<Uuid> = room
+        .players
+        .iter()
+        .map(|p| p.id)
+        .filter(|id| !turns.forfeited.contains(id))
+        .collect();
+    let current = turns.current_player_id.and_then(|id| room.players.iter().position(|p| p.id == id));
+    let next = (1..=room.players.len())
+        .map(|step| room.players[(current.unwrap_or(0) + step) % room.players.len()].id)
+        .find(|id| active.contains(id));
+    turns.current_player_id = next;
+    turns.deadline = Some(Utc::now() + chrono::Duration::seconds(turns.turn_secs));
+}
+
+/// Drops a player from the turn order. The game ends once one player is
+/// left, who wins; the room is then `forfeited` until its result is
+/// submitted.
+fn forfeit_player(room: &mut Room, player_id: Uuid) {
+    let Some(turns) = room.turn.as_mut() else { return };
+    turns.forfeited.push(player_id);
+    let remaining: Vec<Uuid> = room
+        .players
+        .iter()
+        .map(|p| p.id)
+        .filter(|id| !turns.forfeited.contains(id))
+        .collect();
+    if remaining.len() <= 1 {
+        room.state = "forfeited".to_string();
+        turns.winner_id = remaining.first().copied();
+        turns.current_player_id = None;
+        turns.deadline = None;
+    } else if turns.current_player_id == Some(player_id) {
+        pass_turn(room);
+    }
+}
+
+/// Runs the matchmaker every [`MATCHMAKER_TICK`] so bands widen and
+/// matches form without anyone having to poll.
+pub fn spawn_matchmaker(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MATCHMAKER_TICK);
+        loop {
+            interval.tick().await;
+            run_matchmaker(&state).await;
+        }
+    });
+}
+
+/// Restores stored turn-based rooms, then checks turn deadlines every
+/// [`TURN_EXPIRY_TICK`].
+pub fn spawn_turn_expiry(state: AppState) {
+    tokio::spawn(async move {
+        match state.room_manager.restore_turn_rooms().await {
+            Ok(n) if n > 0 => tracing::info!("Restored {} turn-based rooms", n),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Could not restore turn-based rooms: {}", e),
+        }
+        let mut interval = tokio::time::interval(TURN_EXPIRY_TICK);
+        loop {
+            interval.tick().await;
+            for forfeit in state.room_manager.expire_turns().await {
+                notify_forfeit(&state, &forfeit.room, forfeit.player_id, "expired").await;
+            }
+        }
+    });
+}
+
+/// Tells a turn-based room's players that one of them is out, and who
+/// moves next or won.
+pub async fn notify_forfeit(state: &AppState, room: &Room, player_id: Uuid, reason: &str) {
+    let Some(turns) = &room.turn else { return };
+    let event = json!({
+        "type": "turn_forfeit",
+        "roomId": room.id,
+        "playerId": player_id,
+        "reason": reason,
+        "room": room,
+    });
+    let ids: Vec<Uuid> = room.players.iter().map(|p| p.id).collect();
+    state.realtime.send_to_many(&turns.tenant_id, &ids, &event).await;
+}
+
+/// Forms matches and tells the players over the realtime gateway:
+/// `match_found` with the room (and its bot, for a bot match), or
+/// `queue_timeout` for expired entries.
+pub async fn run_matchmaker(state: &AppState) {
+    let (matches, expired) = state.room_manager.form_matches().await;
+    for m in matches {
+        let players: Vec<_> = m
+            .entries
+            .iter()
+            .map(|e| {
+                json!({
+                    "id": e.player.id,
+                    "displayName": e.player.display_name,
+                    "skillRating": e.skill_rating,
+                })
+            })
+            .collect();
+        let event = json!({"type": "match_found", "room": m.room, "players": players, "bot": m.room.bot});
+        let ids: Vec<Uuid> = m.entries.iter().map(|e| e.player.id).collect();
+        state.realtime.send_to_many(&m.tenant_id, &ids, &event).await;
+    }
+    for e in expired {
+        let event = json!({"type": "queue_timeout", "gameId": e.game_id});
+        state.realtime.send_to(&e.tenant_id, e.player.id, &event).await;
     }
 }
@@ -0,0 +1,580 @@
+//! CSV roster imports (`roster_imports`).
+//!
+//! An organisation uploads a students file and, optionally, a classes file
+//! as exported by its school information system:
+//!
+//! - students: `student_id`, `display_name`
+//! - classes: `class_id`, `class_name`, `student_id`, one row per student
+//!   in each class
+//!
+//! Columns are found by their header, so files can carry others. The
+//! `import_roster` job checks both files in full before changing anything,
+//! and an import with any error changes nothing; its report lists the
+//! errors by file and line. Otherwise the report counts what the import
+//! did, or for a dry run what it would do.
+//!
+//! Students are matched to earlier imports by `student_id` through
+//! `roster_students`, and classes by `class_id` through
+//! `classrooms.external_id`, so importing the same files again changes
+//! nothing. A new student gets a guest account and joins the organisation
+//! as a `member`; a known one is renamed if `display_name` changed. Every
+//! class in the file ends up with exactly the students listed for it.
+//! Students and classes missing from a later import are left as they are.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::entitlements;
+use crate::services::subscription_sync;
+use crate::AppState;
+
+const MAX_STUDENTS: usize = 5000;
+const MAX_REPORTED_ERRORS: usize = 100;
+const MAX_ID_LEN: usize = 128;
+const MAX_STUDENT_NAME_LEN: usize = 100;
+const MAX_CLASS_NAME_LEN: usize = 100;
+
+const STUDENTS: &str = "students";
+const CLASSES: &str = "classes";
+
+/// A problem with the files, on a line of one of them or with the import
+/// as a whole.
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    file: &'static str,
+    line: Option<usize>,
+    message: String,
+}
+
+impl RowError {
+    fn new(file: &'static str, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self { file, line, message: message.into() }
+    }
+}
+
+struct Student {
+    external_id: String,
+    display_name: String,
+}
+
+struct Class {
+    external_id: String,
+    name: String,
+    /// Student ids with the line listing them, without repeats.
+    students: Vec<(usize, String)>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Import {
+    tenant_id: String,
+    organisation_id: String,
+    requested_by: Option<Uuid>,
+    dry_run: bool,
+    students_csv: Option<String>,
+    classes_csv: Option<String>,
+    status: String,
+}
+
+/// Job: runs an import and stores its report. If this is the job's last
+/// attempt and it fails, the import is left `failed`.
+pub async fn process(state: &AppState, payload: &Value, last_attempt: bool) -> AppResult<()> {
+    let import_id = payload["importId"]
+        .as_str()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| AppError::Internal("import_roster needs importId".into()))?;
+    let import: Option<Import> = sqlx::query_as(
+        r#"SELECT tenant_id, organisation_id, requested_by, dry_run, students_csv, classes_csv, status
+        FROM roster_imports WHERE id = $1"#,
+    )
+    .bind(import_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(import) = import else {
+        return Err(AppError::Internal(format!("Roster import {} not found", import_id)));
+    };
+    if import.status != "pending" && import.status != "processing" {
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE roster_imports SET status = 'processing' WHERE id = $1")
+        .bind(import_id)
+        .execute(&state.db)
+        .await?;
+
+    match run(state, &import).await {
+        Ok((status, report)) => {
+            sqlx::query(
+                r#"UPDATE roster_imports SET status = $2, report = $3, error = NULL,
+                    students_csv = NULL, classes_csv = NULL, completed_at = NOW()
+                WHERE id = $1"#,
+            )
+            .bind(import_id)
+            .bind(status)
+            .bind(report)
+            .execute(&state.db)
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            if last_attempt {
+                sqlx::query(
+                    r#"UPDATE roster_imports SET status = 'failed', error = $2,
+                        students_csv = NULL, classes_csv = NULL, completed_at = NOW()
+                    WHERE id = $1"#,
+                )
+                .bind(import_id)
+                .bind(e.to_string())
+                .execute(&state.db)
+                .await?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Checks the files against the organisation's roster and applies them
+/// unless there are errors or it's a dry run. Returns the import's status
+/// and report.
+async fn run(state: &AppState, import: &Import) -> AppResult<(&'static str, Value)> {
+    let db = &state.db;
+    let org_id = &import.organisation_id;
+    let tid = &import.tenant_id;
+
+    let mut errors = Vec::new();
+    let students = parse_students(import.students_csv.as_deref().unwrap_or(""), &mut errors);
+    let classes = import
+        .classes_csv
+        .as_deref()
+        .map(|csv| parse_classes(csv, &mut errors))
+        .unwrap_or_default();
+
+    // Students imported before, whose accounts still exist
+    let rows: Vec<(String, Uuid, String)> = sqlx::query_as(
+        r#"SELECT r.external_id, r.player_id, p.display_name
+        FROM roster_students r
+        JOIN players p ON p.id = r.player_id AND p.tenant_id = r.tenant_id
+        WHERE r.organisation_id = $1 AND r.tenant_id = $2"#,
+    )
+    .bind(org_id)
+    .bind(tid)
+    .fetch_all(db)
+    .await?;
+    let known: HashMap<String, (Uuid, String)> =
+        rows.into_iter().map(|(external_id, player_id, name)| (external_id, (player_id, name))).collect();
+
+    let in_file: HashSet<&str> = students.iter().map(|s| s.external_id.as_str()).collect();
+    for class in &classes {
+        for (line, student_id) in &class.students {
+            if !in_file.contains(student_id.as_str()) && !known.contains_key(student_id) {
+                errors.push(RowError::new(CLASSES, Some(*line), format!("Unknown student_id {}", student_id)));
+            }
+        }
+    }
+
+    let new_students: Vec<&Student> = students.iter().filter(|s| !known.contains_key(&s.external_id)).collect();
+    let renamed: Vec<(Uuid, &str)> = students
+        .iter()
+        .filter_map(|s| {
+            let (player_id, name) = known.get(&s.external_id)?;
+            (*name != s.display_name).then_some((*player_id, s.display_name.as_str()))
+        })
+        .collect();
+
+    // Known students who have since left the organisation join it again.
+    let known_in_file: Vec<Uuid> = students
+        .iter()
+        .filter_map(|s| known.get(&s.external_id).map(|(player_id, _)| *player_id))
+        .collect();
+    let members: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT player_id FROM organisation_members WHERE organisation_id = $1 AND tenant_id = $2 AND player_id = ANY($3)",
+    )
+    .bind(org_id)
+    .bind(tid)
+    .bind(&known_in_file)
+    .fetch_all(db)
+    .await?;
+    let rejoining: Vec<Uuid> = known_in_file.iter().filter(|p| !members.contains(p)).copied().collect();
+    let joining = (new_students.len() + rejoining.len()) as i64;
+
+    if joining > 0 {
+        let limit = entitlements::get_limit(db, &state.cache, org_id, tid, "max_members").await?;
+        let current = entitlements::get_current_usage(db, org_id, tid, "max_members").await?;
+        if let Some(limit) = limit.filter(|l| current + joining > *l) {
+            errors.push(RowError::new(
+                STUDENTS,
+                None,
+                format!(
+                    "{} students would join the organisation, but its plan allows {} members and it has {}",
+                    joining, limit, current
+                ),
+            ));
+        }
+    }
+
+    // Classes imported before
+    let class_ids: Vec<&str> = classes.iter().map(|c| c.external_id.as_str()).collect();
+    let rows: Vec<(String, Uuid, String)> = sqlx::query_as(
+        "SELECT external_id, id, name FROM classrooms WHERE organisation_id = $1 AND tenant_id = $2 AND external_id = ANY($3)",
+    )
+    .bind(org_id)
+    .bind(tid)
+    .bind(&class_ids)
+    .fetch_all(db)
+    .await?;
+    let known_classes: HashMap<String, (Uuid, String)> =
+        rows.into_iter().map(|(external_id, id, name)| (external_id, (id, name))).collect();
+    let classroom_ids: Vec<Uuid> = known_classes.values().map(|(id, _)| *id).collect();
+    let enrolled: Vec<(Uuid, Uuid)> =
+        sqlx::query_as("SELECT classroom_id, player_id FROM classroom_students WHERE classroom_id = ANY($1)")
+            .bind(&classroom_ids)
+            .fetch_all(db)
+            .await?;
+
+    let mut classes_created = 0;
+    let mut classes_renamed = 0;
+    let mut enrollments_added = 0;
+    let mut enrollments_removed = 0;
+    for class in &classes {
+        let current: HashSet<Uuid> = match known_classes.get(&class.external_id) {
+            Some((id, name)) => {
+                if *name != class.name {
+                    classes_renamed += 1;
+                }
+                enrolled.iter().filter(|(c, _)| c == id).map(|(_, p)| *p).collect()
+            }
+            None => {
+                classes_created += 1;
+                HashSet::new()
+            }
+        };
+        let listed: HashSet<Uuid> = class
+            .students
+            .iter()
+            .filter_map(|(_, student_id)| known.get(student_id).map(|(player_id, _)| *player_id))
+            .collect();
+        let listed_new = class.students.iter().filter(|(_, s)| !known.contains_key(s)).count();
+        enrollments_added += listed.difference(&current).count() + listed_new;
+        enrollments_removed += current.difference(&listed).count();
+    }
+
+    let error_count = errors.len();
+    errors.truncate(MAX_REPORTED_ERRORS);
+    let report = json!({
+        "errors": errors,
+        "errorCount": error_count,
+        "students": {
+            "rows": students.len(),
+            "created": new_students.len(),
+            "renamed": renamed.len(),
+            "unchanged": students.len() - new_students.len() - renamed.len(),
+            "joined": joining,
+        },
+        "classes": {
+            "rows": classes.len(),
+            "created": classes_created,
+            "renamed": classes_renamed,
+            "unchanged": classes.len() - classes_created - classes_renamed,
+        },
+        "enrollments": { "added": enrollments_added, "removed": enrollments_removed },
+    });
+    if error_count > 0 {
+        return Ok(("invalid", report));
+    }
+    if import.dry_run {
+        return Ok(("completed", report));
+    }
+
+    let mut tx = db.begin().await?;
+
+    let mut player_ids: HashMap<&str, Uuid> =
+        known.iter().map(|(external_id, (player_id, _))| (external_id.as_str(), *player_id)).collect();
+    let new_ids: Vec<Uuid> = new_students.iter().map(|_| Uuid::new_v4()).collect();
+    let new_external: Vec<&str> = new_students.iter().map(|s| s.external_id.as_str()).collect();
+    let new_names: Vec<&str> = new_students.iter().map(|s| s.display_name.as_str()).collect();
+    player_ids.extend(new_external.iter().copied().zip(new_ids.iter().copied()));
+
+    sqlx::query(
+        r#"INSERT INTO players (id, tenant_id, display_name, avatar_character, is_guest, total_score, games_played)
+        SELECT id, $2, name, 'robot', true, 0, 0 FROM unnest($1::uuid[], $3::text[]) AS t(id, name)"#,
+    )
+    .bind(&new_ids)
+    .bind(tid)
+    .bind(&new_names)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO roster_students (organisation_id, external_id, tenant_id, player_id)
+        SELECT $1, external_id, $2, player_id FROM unnest($3::text[], $4::uuid[]) AS t(external_id, player_id)
+        ON CONFLICT (organisation_id, external_id) DO UPDATE SET player_id = EXCLUDED.player_id, imported_at = NOW()"#,
+    )
+    .bind(org_id)
+    .bind(tid)
+    .bind(&new_external)
+    .bind(&new_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut joining_ids = new_ids.clone();
+    joining_ids.extend(&rejoining);
+    sqlx::query(
+        r#"INSERT INTO organisation_members (organisation_id, player_id, tenant_id, role, joined_at)
+        SELECT $1, unnest($2::uuid[]), $3, 'member', NOW()
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(org_id)
+    .bind(&joining_ids)
+    .bind(tid)
+    .execute(&mut *tx)
+    .await?;
+
+    let (renamed_ids, renamed_names): (Vec<Uuid>, Vec<&str>) = renamed.into_iter().unzip();
+    sqlx::query(
+        r#"UPDATE players p SET display_name = t.name
+        FROM unnest($1::uuid[], $2::text[]) AS t(id, name)
+        WHERE p.id = t.id AND p.tenant_id = $3"#,
+    )
+    .bind(&renamed_ids)
+    .bind(&renamed_names)
+    .bind(tid)
+    .execute(&mut *tx)
+    .await?;
+
+    if !classes.is_empty() {
+        let teacher_id = match import.requested_by {
+            Some(player_id) => player_id,
+            None => sqlx::query_scalar("SELECT owner_id FROM organisations WHERE id = $1 AND tenant_id = $2")
+                .bind(org_id)
+                .bind(tid)
+                .fetch_one(&mut *tx)
+                .await?,
+        };
+
+        for class in &classes {
+            let classroom_id = match known_classes.get(&class.external_id) {
+                Some((id, _)) => {
+                    sqlx::query("UPDATE classrooms SET name = $2 WHERE id = $1")
+                        .bind(id)
+                        .bind(&class.name)
+                        .execute(&mut *tx)
+                        .await?;
+                    *id
+                }
+                None => sqlx::query_scalar(
+                    r#"INSERT INTO classrooms (tenant_id, organisation_id, name, teacher_id, external_id)
+                    VALUES ($1, $2, $3, $4, $5) RETURNING id"#,
+                )
+                .bind(tid)
+                .bind(org_id)
+                .bind(&class.name)
+                .bind(teacher_id)
+                .bind(&class.external_id)
+                .fetch_one(&mut *tx)
+                .await?,
+            };
+
+            let roster: Vec<Uuid> = class.students.iter().filter_map(|(_, s)| player_ids.get(s.as_str()).copied()).collect();
+            sqlx::query("DELETE FROM classroom_students WHERE classroom_id = $1 AND NOT (player_id = ANY($2))")
+                .bind(classroom_id)
+                .bind(&roster)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                r#"INSERT INTO classroom_students (classroom_id, tenant_id, player_id)
+                SELECT $1, $2, unnest($3::uuid[])
+                ON CONFLICT DO NOTHING"#,
+            )
+            .bind(classroom_id)
+            .bind(tid)
+            .bind(&roster)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    if joining > 0 {
+        // The billing sync job catches up if Stripe can't be reached now.
+        if let Err(e) = subscription_sync::sync_seats(state, org_id, tid).await {
+            tracing::warn!("Seat sync for organisation {} failed: {}", org_id, e);
+        }
+    }
+
+    Ok(("completed", report))
+}
+
+fn parse_students(csv: &str, errors: &mut Vec<RowError>) -> Vec<Student> {
+    let Some((rows, cols)) = read_file(STUDENTS, csv, &["student_id", "display_name"], errors) else {
+        return Vec::new();
+    };
+    if rows.len() > MAX_STUDENTS {
+        errors.push(RowError::new(STUDENTS, None, format!("At most {} students per import", MAX_STUDENTS)));
+        return Vec::new();
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut students = Vec::new();
+    for (line, record) in rows {
+        let (external_id, display_name) = (cell(&record, cols[0]), cell(&record, cols[1]));
+        let error = check_id("student_id", external_id)
+            .or_else(|| {
+                (display_name.is_empty() || display_name.chars().count() > MAX_STUDENT_NAME_LEN)
+                    .then(|| format!("display_name must be 1-{} characters", MAX_STUDENT_NAME_LEN))
+            })
+            .or_else(|| {
+                seen.get(external_id)
+                    .map(|first| format!("Duplicate student_id {}, first on line {}", external_id, first))
+            });
+        if let Some(message) = error {
+            errors.push(RowError::new(STUDENTS, Some(line), message));
+            continue;
+        }
+        seen.insert(external_id.to_string(), line);
+        students.push(Student { external_id: external_id.to_string(), display_name: display_name.to_string() });
+    }
+    students
+}
+
+fn parse_classes(csv: &str, errors: &mut Vec<RowError>) -> Vec<Class> {
+    let Some((rows, cols)) = read_file(CLASSES, csv, &["class_id", "class_name", "student_id"], errors) else {
+        return Vec::new();
+    };
+
+    let mut classes: Vec<Class> = Vec::new();
+    for (line, record) in rows {
+        let (class_id, name, student_id) = (cell(&record, cols[0]), cell(&record, cols[1]), cell(&record, cols[2]));
+        let error = check_id("class_id", class_id).or_else(|| check_id("student_id", student_id)).or_else(|| {
+            (name.is_empty() || name.chars().count() > MAX_CLASS_NAME_LEN)
+                .then(|| format!("class_name must be 1-{} characters", MAX_CLASS_NAME_LEN))
+        });
+        if let Some(message) = error {
+            errors.push(RowError::new(CLASSES, Some(line), message));
+            continue;
+        }
+
+        match classes.iter_mut().find(|c| c.external_id == class_id) {
+            Some(class) if class.name != name => {
+                errors.push(RowError::new(
+                    CLASSES,
+                    Some(line),
+                    format!("class {} is named both {} and {}", class_id, class.name, name),
+                ));
+            }
+            Some(class) => {
+                if !class.students.iter().any(|(_, s)| s == student_id) {
+                    class.students.push((line, student_id.to_string()));
+                }
+            }
+            None => classes.push(Class {
+                external_id: class_id.to_string(),
+                name: name.to_string(),
+                students: vec![(line, student_id.to_string())],
+            }),
+        }
+    }
+    classes
+}
+
+fn check_id(column: &str, id: &str) -> Option<String> {
+    (id.is_empty() || id.len() > MAX_ID_LEN).then(|| format!("{} must be 1-{} characters", column, MAX_ID_LEN))
+}
+
+fn cell(record: &[String], col: usize) -> &str {
+    record.get(col).map(|s| s.trim()).unwrap_or("")
+}
+
+type Rows = Vec<(usize, Vec<String>)>;
+
+/// A file's data rows and the positions of the `columns` in its header,
+/// or `None` after recording why it can't be read.
+fn read_file(
+    file: &'static str,
+    csv: &str,
+    columns: &[&str],
+    errors: &mut Vec<RowError>,
+) -> Option<(Rows, Vec<usize>)> {
+    let mut rows = match parse_csv(csv) {
+        Ok(rows) => rows,
+        Err((line, message)) => {
+            errors.push(RowError::new(file, Some(line), message));
+            return None;
+        }
+    };
+    if rows.is_empty() {
+        errors.push(RowError::new(file, None, "The file is empty"));
+        return None;
+    }
+
+    let (header_line, header) = rows.remove(0);
+    let mut cols = Vec::new();
+    for column in columns {
+        match header.iter().position(|h| h.trim().eq_ignore_ascii_case(column)) {
+            Some(i) => cols.push(i),
+            None => {
+                errors.push(RowError::new(file, Some(header_line), format!("Missing column {}", column)));
+                return None;
+            }
+        }
+    }
+    Some((rows, cols))
+}
+
+/// Splits CSV (RFC 4180) into records, each with the line it starts on.
+/// Blank lines are skipped. An unclosed quote is an error at the line of
+/// the record it's in.
+fn parse_csv(text: &str) -> Result<Rows, (usize, String)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                push_record(&mut records, start, std::mem::take(&mut record));
+                line += 1;
+                start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err((start, "Unclosed quote".into()));
+    }
+    record.push(field);
+    push_record(&mut records, start, record);
+    Ok(records)
+}
+
+fn push_record(records: &mut Rows, line: usize, record: Vec<String>) {
+    if record.len() > 1 || record.first().is_some_and(|f| !f.trim().is_empty()) {
+        records.push((line, record));
+    }
+}
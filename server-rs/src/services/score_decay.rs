@@ -0,0 +1,94 @@
+//! Decayed leaderboards (`game_leaderboard_configs`, `decayed_rankings`).
+//!
+//! A game with a decay half-life set ranks players by their best
+//! submission in `score_history`, each weighted by `0.5^(age / half-life)`,
+//! so a record from last year has to keep being matched to stay on top.
+//! Submissions more than 20 half-lives old are worth under a millionth of
+//! their score and are skipped.
+//!
+//! The `decay_leaderboards` job materializes every decayed board nightly
+//! into `decayed_rankings`, which the endpoint serves. A board that hasn't
+//! been materialized yet, e.g. just after its half-life changed, is
+//! computed on each read instead.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::error::AppResult;
+
+/// Each player's best decayed score as `(player_id, score)` rows, for a
+/// query binding tenant as `$1`, game as `$2` and the half-life in days as
+/// `$3`.
+pub const DECAYED_SCORES_SQL: &str = "SELECT player_id, \
+     MAX(score * power(0.5, EXTRACT(EPOCH FROM NOW() - created_at) / 86400.0 / $3))::double precision AS score \
+     FROM score_history \
+     WHERE tenant_id = $1 AND game_id = $2 AND created_at > NOW() - $3 * 20 * INTERVAL '1 day' AND voided_at IS NULL \
+     GROUP BY player_id";
+
+/// The game's decay half-life in days, if it has a decayed board.
+pub async fn half_life_days(db: &PgPool, tenant_id: &str, game_id: &str) -> AppResult<Option<f64>> {
+    let days = sqlx::query_scalar(
+        "SELECT decay_half_life_days FROM game_leaderboard_configs WHERE tenant_id = $1 AND game_id = $2",
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(days)
+}
+
+/// When the game's board was last materialized, if it has been since its
+/// half-life was last set.
+pub async fn computed_at(db: &PgPool, tenant_id: &str, game_id: &str) -> AppResult<Option<DateTime<Utc>>> {
+    let at = sqlx::query_scalar(
+        "SELECT MAX(computed_at) FROM decayed_rankings WHERE tenant_id = $1 AND game_id = $2",
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .fetch_one(db)
+    .await?;
+    Ok(at)
+}
+
+/// Replaces the game's materialized board. Returns the players ranked.
+pub async fn materialize(db: &PgPool, tenant_id: &str, game_id: &str, half_life_days: f64) -> AppResult<u64> {
+    let mut tx = db.begin().await?;
+    clear(&mut tx, tenant_id, game_id).await?;
+    let ranked = sqlx::query(&format!(
+        r#"INSERT INTO decayed_rankings (tenant_id, game_id, player_id, decayed_score, rank, computed_at)
+        SELECT $1, $2, d.player_id, d.score, RANK() OVER (ORDER BY d.score DESC)::bigint, NOW()
+        FROM ({}) d"#,
+        DECAYED_SCORES_SQL
+    ))
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(half_life_days)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(ranked)
+}
+
+/// Drops the game's materialized board, so reads compute it until the
+/// next run.
+pub async fn clear(conn: &mut sqlx::PgConnection, tenant_id: &str, game_id: &str) -> AppResult<()> {
+    sqlx::query("DELETE FROM decayed_rankings WHERE tenant_id = $1 AND game_id = $2")
+        .bind(tenant_id)
+        .bind(game_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Materializes every decayed board. Returns the number of boards.
+pub async fn materialize_all(db: &PgPool) -> AppResult<usize> {
+    let boards: Vec<(String, String, f64)> =
+        sqlx::query_as("SELECT tenant_id, game_id, decay_half_life_days FROM game_leaderboard_configs")
+            .fetch_all(db)
+            .await?;
+    for (tenant_id, game_id, half_life) in &boards {
+        materialize(db, tenant_id, game_id, *half_life).await?;
+    }
+    Ok(boards.len())
+}
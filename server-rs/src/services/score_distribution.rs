@@ -0,0 +1,125 @@
+//! Score distributions for "you beat 82% of players".
+//!
+//! A game's high scores in `game_progress` are counted into equal-width
+//! buckets between the lowest and highest score, and the histogram is
+//! cached for a few minutes. Quantiles and percentiles are read off the
+//! histogram assuming scores are spread evenly within a bucket, so they
+//! are approximate, but no request has to sort every player's score.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::cache::Cache;
+use crate::error::AppResult;
+
+pub const DEFAULT_BUCKETS: usize = 20;
+pub const MAX_BUCKETS: usize = 100;
+
+/// New scores show up in the distribution within this long.
+const CACHE_TTL_SECS: u64 = 300;
+
+/// A game's high scores counted into `counts.len()` buckets of `width`,
+/// the first starting at `min`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Histogram {
+    pub min: i64,
+    pub max: i64,
+    pub width: f64,
+    pub counts: Vec<i64>,
+    pub total: i64,
+}
+
+impl Histogram {
+    /// Where bucket `i` starts.
+    pub fn lower(&self, i: usize) -> f64 {
+        self.min as f64 + self.width * i as f64
+    }
+
+    /// Percentage of players with a lower score than `score`, 0 to 100.
+    pub fn percentile(&self, score: i64) -> f64 {
+        if self.total == 0 || score <= self.min {
+            return 0.0;
+        }
+        if score > self.max {
+            return 100.0;
+        }
+        let at = (score - self.min) as f64 / self.width;
+        let bucket = (at as usize).min(self.counts.len() - 1);
+        let below: i64 = self.counts[..bucket].iter().sum();
+        let within = self.counts[bucket] as f64 * (at - bucket as f64).min(1.0);
+        (100.0 * (below as f64 + within) / self.total as f64).clamp(0.0, 100.0)
+    }
+
+    /// The score a fraction `q` (0 to 1) of players are below.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        let target = q.clamp(0.0, 1.0) * self.total as f64;
+        let mut seen = 0.0;
+        for (i, &count) in self.counts.iter().enumerate() {
+            let count = count as f64;
+            if count > 0.0 && seen + count >= target {
+                let score = self.lower(i) + self.width * (target - seen) / count;
+                return Some(score.clamp(self.min as f64, self.max as f64));
+            }
+            seen += count;
+        }
+        Some(self.max as f64)
+    }
+}
+
+fn cache_key(tenant_id: &str, game_id: &str, buckets: usize) -> String {
+    format!("dist:{}:{}:{}", tenant_id, game_id, buckets)
+}
+
+/// The game's histogram over `buckets` buckets, and whether it came from
+/// the cache.
+pub async fn histogram(
+    db: &PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+    game_id: &str,
+    buckets: usize,
+) -> AppResult<(Histogram, bool)> {
+    let key = cache_key(tenant_id, game_id, buckets);
+    if let Some(hist) = cache.get_json::<Histogram>(&key).await {
+        return Ok((hist, true));
+    }
+
+    let (min, max, total): (Option<i64>, Option<i64>, i64) = sqlx::query_as(
+        "SELECT MIN(high_score)::bigint, MAX(high_score)::bigint, COUNT(high_score)::bigint \
+         FROM game_progress WHERE tenant_id = $1 AND game_id = $2",
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .fetch_one(db)
+    .await?;
+    let (min, max) = (min.unwrap_or(0), max.unwrap_or(0));
+
+    // The upper bound is exclusive, so the top score lands in the last
+    // bucket rather than one past it.
+    let width = (max + 1 - min) as f64 / buckets as f64;
+    let mut counts = vec![0; buckets];
+    if total > 0 {
+        let rows: Vec<(i32, i64)> = sqlx::query_as(
+            "SELECT width_bucket(high_score::float8, $3, $4, $5), COUNT(*)::bigint \
+             FROM game_progress WHERE tenant_id = $1 AND game_id = $2 AND high_score IS NOT NULL GROUP BY 1",
+        )
+        .bind(tenant_id)
+        .bind(game_id)
+        .bind(min as f64)
+        .bind((max + 1) as f64)
+        .bind(buckets as i32)
+        .fetch_all(db)
+        .await?;
+        for (bucket, count) in rows {
+            let i = (bucket - 1).clamp(0, buckets as i32 - 1) as usize;
+            counts[i] += count;
+        }
+    }
+
+    let hist = Histogram { min, max, width, counts, total };
+    cache.set_json(&key, &hist, CACHE_TTL_SECS).await;
+    Ok((hist, false))
+}
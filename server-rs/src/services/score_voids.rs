@@ -0,0 +1,128 @@
+//! Voiding score submissions.
+//!
+//! A voided submission keeps its `score_history` row, with `voided_at`
+//! set, but no longer counts: windowed and decayed boards skip it, and the
+//! player's `game_progress` high score drops to their best submission that
+//! is left. High scores are only ever lowered here, since progress can
+//! also come from outside `score_history` (batch sync, older clients).
+//!
+//! Voiding drops the game's cached board and its decayed snapshot, so both
+//! are rebuilt from the corrected scores.
+
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::services::{jobs, leaderboard, score_decay};
+use crate::AppState;
+
+/// Which of a game's submissions to void.
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    Scores(&'a [i64]),
+    /// Every submission of the player.
+    Player(Uuid),
+}
+
+/// One player's submissions voided by a call to [`void`].
+#[derive(Debug, Clone)]
+pub struct Voided {
+    pub player_id: String,
+    pub score_ids: Vec<i64>,
+    /// The player's high score before and after, if they have progress in
+    /// the game.
+    pub high_score: Option<(i64, i64)>,
+}
+
+impl Voided {
+    pub fn json(&self) -> Value {
+        json!({
+            "playerId": self.player_id,
+            "scoreIds": self.score_ids,
+            "highScore": self.high_score.map(|(from, to)| json!({"from": from, "to": to})),
+        })
+    }
+}
+
+/// Voids the game's submissions picked by `target` that aren't voided yet
+/// and lowers the players' high scores to match. Returns what was voided,
+/// per player.
+pub async fn void(conn: &mut PgConnection, tenant_id: &str, game_id: &str, target: Target<'_>) -> AppResult<Vec<Voided>> {
+    let (score_ids, player_id) = match target {
+        Target::Scores(ids) => (Some(ids.to_vec()), None),
+        Target::Player(id) => (None, Some(id.to_string())),
+    };
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        r#"UPDATE score_history SET voided_at = NOW()
+        WHERE tenant_id = $1 AND game_id = $2 AND voided_at IS NULL
+            AND (id = ANY($3) OR player_id = $4)
+        RETURNING id, player_id"#,
+    )
+    .bind(tenant_id)
+    .bind(game_id)
+    .bind(score_ids.unwrap_or_default())
+    .bind(player_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut voided: Vec<Voided> = Vec::new();
+    for (id, player_id) in rows {
+        match voided.iter_mut().find(|v| v.player_id == player_id) {
+            Some(v) => v.score_ids.push(id),
+            None => voided.push(Voided { player_id, score_ids: vec![id], high_score: None }),
+        }
+    }
+
+    for v in &mut voided {
+        v.score_ids.sort_unstable();
+        v.high_score = sqlx::query_as(
+            r#"UPDATE game_progress gp SET high_score = LEAST(old.high_score, COALESCE((
+                SELECT MAX(sh.score) FROM score_history sh
+                WHERE sh.tenant_id = $1 AND sh.player_id = $2 AND sh.game_id = $3 AND sh.voided_at IS NULL
+            ), 0))
+            FROM game_progress old
+            WHERE gp.player_id = old.player_id AND gp.tenant_id = old.tenant_id AND gp.game_id = old.game_id
+                AND gp.tenant_id = $1 AND gp.player_id::text = $2 AND gp.game_id = $3
+            RETURNING COALESCE(old.high_score, 0), gp.high_score"#,
+        )
+        .bind(tenant_id)
+        .bind(&v.player_id)
+        .bind(game_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    }
+
+    if !voided.is_empty() {
+        score_decay::clear(conn, tenant_id, game_id).await?;
+    }
+    Ok(voided)
+}
+
+/// Records `voided` in the moderation log as `void_scores`, with `extra`
+/// added to its metadata.
+pub async fn log(
+    conn: &mut PgConnection,
+    tenant_id: &str,
+    admin_id: Uuid,
+    game_id: &str,
+    voided: &Voided,
+    reason: Option<&str>,
+    extra: Value,
+) -> AppResult<()> {
+    let mut metadata = voided.json();
+    if let (Some(metadata), Value::Object(extra)) = (metadata.as_object_mut(), extra) {
+        metadata.extend(extra);
+    }
+    sqlx::query("INSERT INTO moderation_log (admin_id, tenant_id, action, content_type, content_id, target_player_id, reason, metadata, created_at) VALUES ($1, $2, 'void_scores', 'game', $3, $4, $5, $6, NOW())")
+        .bind(admin_id).bind(tenant_id).bind(game_id).bind(&voided.player_id).bind(reason).bind(metadata)
+        .execute(conn).await?;
+    Ok(())
+}
+
+/// Drops the game's cached board and queues a rebuild, once the voiding
+/// transaction has committed.
+pub async fn refresh_board(state: &AppState, tenant_id: &str, game_id: &str) -> AppResult<()> {
+    leaderboard::invalidate(&state.cache, tenant_id, game_id, state.config.leaderboard.shard_count).await;
+    jobs::rebuild_leaderboard(&state.db, tenant_id, game_id).await
+}
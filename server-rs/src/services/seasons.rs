@@ -0,0 +1,298 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::config::SeasonConfig;
+use crate::error::AppResult;
+
+/// player_id, game_id, region, final_rank, score, skill_rating, wins, matches_played
+type StandingRow = (uuid::Uuid, String, String, i64, i64, i32, i32, i32);
+
+/// A rank-based payout: every placement up to and including `max_rank`
+/// (that isn't covered by an earlier tier) receives `amount` of `currency`.
+struct RewardTier {
+    max_rank: i64,
+    currency: String,
+    amount: i64,
+}
+
+fn default_reward_tiers() -> Vec<RewardTier> {
+    vec![
+        RewardTier {
+            max_rank: 1,
+            currency: "gems".into(),
+            amount: 500,
+        },
+        RewardTier {
+            max_rank: 3,
+            currency: "gems".into(),
+            amount: 250,
+        },
+        RewardTier {
+            max_rank: 10,
+            currency: "gems".into(),
+            amount: 100,
+        },
+        RewardTier {
+            max_rank: 100,
+            currency: "coins".into(),
+            amount: 500,
+        },
+    ]
+}
+
+/// Reads `config.rewards` (`[{ "maxRank": 1, "currency": "gems", "amount": 500 }, ...]`)
+/// falling back to the default table when absent or malformed.
+fn reward_tiers(config: &Value) -> Vec<RewardTier> {
+    let mut tiers: Vec<RewardTier> = config["rewards"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| {
+                    Some(RewardTier {
+                        max_rank: t["maxRank"].as_i64()?,
+                        currency: t["currency"].as_str()?.to_string(),
+                        amount: t["amount"].as_i64()?,
+                    })
+                })
+                .filter(|t| t.max_rank > 0 && t.amount > 0)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if tiers.is_empty() {
+        tiers = default_reward_tiers();
+    }
+    tiers.sort_by_key(|t| t.max_rank);
+    tiers
+}
+
+fn reward_for_rank(tiers: &[RewardTier], rank: i64) -> Option<&RewardTier> {
+    tiers.iter().find(|t| rank <= t.max_rank)
+}
+
+/// Closes every active season whose `ends_at` has passed and activates its
+/// successor. Activation is separate from closing, so a tenant left
+/// without a season by a failed activation gets one on the next run.
+/// Returns the number of seasons closed.
+pub async fn rotate_expired_seasons(db: &sqlx::PgPool, config: &SeasonConfig) -> AppResult<usize> {
+    let expired: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT id, tenant_id FROM seasons WHERE is_active = true AND ends_at <= NOW() ORDER BY ends_at",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut closed = 0;
+    for (season_id, _) in expired {
+        if close_season(db, season_id, config).await? {
+            closed += 1;
+        }
+    }
+
+    // Tenants whose last season was closed here but that have no active one
+    let unseasoned: Vec<(String, i32)> = sqlx::query_as(
+        r#"SELECT DISTINCT ON (s.tenant_id) s.tenant_id, s.id FROM seasons s
+        WHERE s.closed_at IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM seasons a WHERE a.tenant_id = s.tenant_id AND a.is_active = true)
+        ORDER BY s.tenant_id, s.closed_at DESC"#,
+    )
+    .fetch_all(db)
+    .await?;
+    for (tenant_id, previous_id) in unseasoned {
+        activate_next_season(db, &tenant_id, previous_id, config).await?;
+    }
+
+    Ok(closed)
+}
+
+/// Snapshots final standings into `season_results`, pays out rewards and
+/// deactivates the season, all in one transaction. Returns false if another
+/// instance already closed it.
+async fn close_season(db: &sqlx::PgPool, season_id: i32, config: &SeasonConfig) -> AppResult<bool> {
+    let mut tx = db.begin().await?;
+
+    // SKIP LOCKED so concurrent instances don't both pay out the same season
+    let season: Option<(String, String, Value)> = sqlx::query_as(
+        r#"SELECT tenant_id, name, config FROM seasons
+        WHERE id = $1 AND is_active = true
+        FOR UPDATE SKIP LOCKED"#,
+    )
+    .bind(season_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (tenant_id, name, season_config) = match season {
+        Some(s) => s,
+        None => return Ok(false),
+    };
+
+    let standings: Vec<StandingRow> = sqlx::query_as(
+        r#"SELECT player_id, game_id, region,
+            RANK() OVER (PARTITION BY game_id, region ORDER BY score DESC) as final_rank,
+            score, skill_rating, wins, matches_played
+        FROM leaderboard_entries
+        WHERE tenant_id = $1 AND season_id = $2"#,
+    )
+    .bind(&tenant_id)
+    .bind(season_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let tiers = reward_tiers(&season_config);
+    let reference_id = format!("season:{}", season_id);
+
+    for (player_id, game_id, region, rank, score, rating, wins, matches) in &standings {
+        let reward = if *rank <= config.max_rewarded_rank {
+            reward_for_rank(&tiers, *rank)
+        } else {
+            None
+        };
+
+        sqlx::query(
+            r#"INSERT INTO season_results (tenant_id, season_id, player_id, game_id, region, final_rank,
+                score, skill_rating, wins, matches_played, reward_currency, reward_amount)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (tenant_id, season_id, player_id, game_id, region) DO NOTHING"#,
+        )
+        .bind(&tenant_id)
+        .bind(season_id)
+        .bind(player_id)
+        .bind(game_id)
+        .bind(region)
+        .bind(*rank as i32)
+        .bind(score)
+        .bind(rating)
+        .bind(wins)
+        .bind(matches)
+        .bind(reward.map(|r| r.currency.as_str()))
+        .bind(reward.map(|r| r.amount).unwrap_or(0))
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(r) = reward {
+            let balance: i64 = sqlx::query_scalar(
+                r#"INSERT INTO player_wallets (player_id, tenant_id, currency_type, balance, lifetime_earned, updated_at)
+                VALUES ($1, $2, $3, $4, $4, NOW())
+                ON CONFLICT (player_id, tenant_id, currency_type) DO UPDATE SET
+                    balance = player_wallets.balance + $4,
+                    lifetime_earned = player_wallets.lifetime_earned + $4,
+                    updated_at = NOW()
+                RETURNING balance"#,
+            )
+            .bind(player_id)
+            .bind(&tenant_id)
+            .bind(&r.currency)
+            .bind(r.amount)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"INSERT INTO economy_transactions (tenant_id, player_id, currency_type, amount, balance_after, tx_type, source, reference_id, metadata, created_at)
+                VALUES ($1, $2, $3, $4, $5, 'earn', 'season_reward', $6, $7, NOW())"#,
+            )
+            .bind(&tenant_id)
+            .bind(player_id)
+            .bind(&r.currency)
+            .bind(r.amount)
+            .bind(balance)
+            .bind(&reference_id)
+            .bind(serde_json::json!({"season": name, "gameId": game_id, "region": region, "rank": rank}))
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query("UPDATE seasons SET is_active = false, closed_at = NOW() WHERE id = $1")
+        .bind(season_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "Closed season {} ({}) for tenant {}: {} standings recorded",
+        season_id,
+        name,
+        tenant_id,
+        standings.len()
+    );
+
+    Ok(true)
+}
+
+/// Activates the earliest scheduled season for the tenant, or creates a new
+/// one of the same length that inherits the previous season's config.
+async fn activate_next_season(
+    db: &sqlx::PgPool,
+    tenant_id: &str,
+    previous_id: i32,
+    config: &SeasonConfig,
+) -> AppResult<()> {
+    let already_active: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM seasons WHERE tenant_id = $1 AND is_active = true)",
+    )
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await?;
+    if already_active {
+        return Ok(());
+    }
+
+    let scheduled: Option<i32> = sqlx::query_scalar(
+        r#"SELECT id FROM seasons
+        WHERE tenant_id = $1 AND is_active = false AND closed_at IS NULL AND ends_at > NOW()
+        ORDER BY starts_at LIMIT 1"#,
+    )
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?;
+
+    if let Some(id) = scheduled {
+        sqlx::query("UPDATE seasons SET is_active = true WHERE id = $1")
+            .bind(id)
+            .execute(db)
+            .await?;
+        tracing::info!("Activated scheduled season {} for tenant {}", id, tenant_id);
+        return Ok(());
+    }
+
+    let previous: (DateTime<Utc>, DateTime<Utc>, Value) =
+        sqlx::query_as("SELECT starts_at, ends_at, config FROM seasons WHERE id = $1")
+            .bind(previous_id)
+            .fetch_one(db)
+            .await?;
+
+    let length = {
+        let prev = previous.1 - previous.0;
+        if prev > chrono::Duration::zero() {
+            prev
+        } else {
+            chrono::Duration::days(config.default_length_days)
+        }
+    };
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM seasons WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_one(db)
+        .await?;
+
+    let starts_at = Utc::now();
+    let id: i32 = sqlx::query_scalar(
+        r#"INSERT INTO seasons (tenant_id, name, starts_at, ends_at, is_active, config)
+        VALUES ($1, $2, $3, $4, true, $5)
+        RETURNING id"#,
+    )
+    .bind(tenant_id)
+    .bind(format!("Season {}", count + 1))
+    .bind(starts_at)
+    .bind(starts_at + length)
+    .bind(&previous.2)
+    .fetch_one(db)
+    .await?;
+
+    tracing::info!(
+        "Created and activated season {} for tenant {}",
+        id,
+        tenant_id
+    );
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Sign-in sessions, one per device.
+//!
+//! Every sign-in (guest, password, OAuth) starts a session in
+//! `auth_sessions`, and both of its tokens carry the session id as `sid`.
+//! Refreshing keeps the session and updates its device, IP and last seen
+//! time. Revoking a session stops its refresh token at once (checked in
+//! the database) and its access token at once too, through a cache marker
+//! that outlives any access token issued to it.
+
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cache::Cache;
+use crate::error::AppResult;
+use crate::middleware::auth::generate_tokens;
+use crate::AppState;
+
+/// User agents are cut to this many characters.
+const MAX_DEVICE_LEN: usize = 256;
+
+/// Where a sign-in or refresh came from.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub device: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl ClientInfo {
+    /// The user agent and the client IP as forwarded by the proxy.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let text = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let device = text(header::USER_AGENT.as_str())
+            .map(|ua| ua.chars().take(MAX_DEVICE_LEN).collect());
+        let ip = text("x-forwarded-for")
+            .and_then(|v| v.split(',').next())
+            .or_else(|| text("x-real-ip"))
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty());
+        Self { device, ip }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: Uuid,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+fn revocation_key(session_id: &str) -> String {
+    format!("session_revoked:{}", session_id)
+}
+
+/// Whether the session was revoked since its tokens were issued.
+pub async fn is_revoked(cache: &Cache, session_id: &str) -> bool {
+    cache.get(&revocation_key(session_id)).await.is_some()
+}
+
+/// Starts a session and issues its first token pair.
+pub async fn start(
+    state: &AppState,
+    player_id: Uuid,
+    tenant_id: &str,
+    role: Option<&str>,
+    client: &ClientInfo,
+) -> AppResult<(String, String)> {
+    let session_id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO auth_sessions (id, tenant_id, player_id, device, ip, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $6))"#,
+    )
+    .bind(session_id)
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(&client.device)
+    .bind(&client.ip)
+    .bind(state.config.jwt.refresh_expiry_secs as f64)
+    .execute(&state.db)
+    .await?;
+
+    generate_tokens(player_id, tenant_id, role, session_id, &state.config.jwt)
+}
+
+/// Records a refresh on the session and extends it to the new refresh
+/// token's expiry. Returns false if the session is revoked or unknown.
+pub async fn touch(state: &AppState, session_id: Uuid, player_id: Uuid, client: &ClientInfo) -> AppResult<bool> {
+    let updated = sqlx::query(
+        r#"UPDATE auth_sessions SET
+            device = COALESCE($3, device), ip = COALESCE($4, ip), last_seen_at = NOW(),
+            expires_at = NOW() + make_interval(secs => $5)
+        WHERE id = $1 AND player_id = $2 AND revoked_at IS NULL"#,
+    )
+    .bind(session_id)
+    .bind(player_id)
+    .bind(&client.device)
+    .bind(&client.ip)
+    .bind(state.config.jwt.refresh_expiry_secs as f64)
+    .execute(&state.db)
+    .await?;
+    Ok(updated.rows_affected() > 0)
+}
+
+/// The player's sessions that are neither revoked nor expired, most
+/// recently used first.
+pub async fn list(db: &PgPool, tenant_id: &str, player_id: Uuid) -> AppResult<Vec<Session>> {
+    let sessions = sqlx::query_as(
+        r#"SELECT id, device, ip, created_at, last_seen_at, expires_at
+        FROM auth_sessions
+        WHERE tenant_id = $1 AND player_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_seen_at DESC"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .fetch_all(db)
+    .await?;
+    Ok(sessions)
+}
+
+/// Revokes the player's sessions: the one given, or all but `keep`.
+/// Returns the ids revoked.
+pub async fn revoke(
+    state: &AppState,
+    tenant_id: &str,
+    player_id: Uuid,
+    only: Option<Uuid>,
+    keep: Option<Uuid>,
+) -> AppResult<Vec<Uuid>> {
+    let revoked: Vec<Uuid> = sqlx::query_scalar(
+        r#"UPDATE auth_sessions SET revoked_at = NOW()
+        WHERE tenant_id = $1 AND player_id = $2 AND revoked_at IS NULL
+          AND ($3::uuid IS NULL OR id = $3) AND ($4::uuid IS NULL OR id <> $4)
+        RETURNING id"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(only)
+    .bind(keep)
+    .fetch_all(&state.db)
+    .await?;
+
+    let ttl = state.config.jwt.access_expiry_secs.max(0) as u64;
+    for id in &revoked {
+        state.cache.set(&revocation_key(&id.to_string()), "1", ttl).await;
+    }
+    Ok(revoked)
+}
@@ -0,0 +1,143 @@
+//! Store offers: sales, bundles, purchase limits and the featured rotation.
+//!
+//! Prices are always worked out here from the item's base price and the
+//! biggest sale running on it; clients only send the item id. Items of
+//! type `bundle` grant their `bundle_contents` instead of going into the
+//! inventory. Purchase limits count the player's `spend` transactions from
+//! the store for the item.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::models::economy::{BundleContent, StoreOffer};
+use crate::services::translations::Translations;
+
+/// Items shown by the featured endpoint at a time.
+pub const FEATURED_SLOTS: i64 = 4;
+
+/// How long one featured selection lasts.
+pub const FEATURED_ROTATION_SECS: i64 = 24 * 3600;
+
+pub const MAX_DISCOUNT_PERCENT: i32 = 90;
+
+/// Store items (`si`) with the biggest sale running on each.
+pub const OFFER_SQL: &str = r#"SELECT si.*, sale.discount_percent, sale.ends_at AS sale_ends_at
+    FROM store_items si
+    LEFT JOIN LATERAL (
+        SELECT ss.discount_percent, ss.ends_at FROM store_sales ss
+        WHERE ss.tenant_id = si.tenant_id AND (ss.item_id IS NULL OR ss.item_id = si.id)
+          AND ss.starts_at <= NOW() AND ss.ends_at > NOW()
+        ORDER BY ss.discount_percent DESC, ss.ends_at
+        LIMIT 1
+    ) sale ON TRUE"#;
+
+/// Condition for items that can be bought right now.
+pub const AVAILABLE_SQL: &str = "si.is_active = true \
+     AND (si.available_from IS NULL OR si.available_from <= NOW()) \
+     AND (si.available_until IS NULL OR si.available_until > NOW())";
+
+/// An item that can be bought right now, at its current price.
+pub async fn find_offer(db: &PgPool, tenant_id: &str, item_id: &str) -> AppResult<Option<StoreOffer>> {
+    let offer = sqlx::query_as(&format!(
+        "{} WHERE si.id = $1 AND si.tenant_id = $2 AND {}",
+        OFFER_SQL, AVAILABLE_SQL
+    ))
+    .bind(item_id)
+    .bind(tenant_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(offer)
+}
+
+/// Contents of the given bundles, keyed by bundle id.
+pub async fn bundle_contents(
+    db: &PgPool,
+    tenant_id: &str,
+    bundle_ids: &[String],
+) -> AppResult<HashMap<String, Vec<BundleContent>>> {
+    let rows: Vec<BundleContent> = sqlx::query_as(
+        r#"SELECT bc.bundle_id, bc.item_id, si.name, bc.quantity
+        FROM bundle_contents bc
+        JOIN store_items si ON si.id = bc.item_id AND si.tenant_id = bc.tenant_id
+        WHERE bc.tenant_id = $1 AND bc.bundle_id = ANY($2)
+        ORDER BY bc.item_id"#,
+    )
+    .bind(tenant_id)
+    .bind(bundle_ids)
+    .fetch_all(db)
+    .await?;
+
+    let mut out: HashMap<String, Vec<BundleContent>> = HashMap::new();
+    for row in rows {
+        out.entry(row.bundle_id.clone()).or_default().push(row);
+    }
+    Ok(out)
+}
+
+/// How many of each item the player has bought from the store.
+pub async fn purchase_counts<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    tenant_id: &str,
+    player_id: Uuid,
+    item_ids: &[String],
+) -> AppResult<HashMap<String, i64>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        r#"SELECT reference_id, COUNT(*)::bigint
+        FROM economy_transactions
+        WHERE tenant_id = $1 AND player_id = $2 AND tx_type = 'spend' AND source = 'store'
+          AND reference_id = ANY($3)
+        GROUP BY reference_id"#,
+    )
+    .bind(tenant_id)
+    .bind(player_id)
+    .bind(item_ids)
+    .fetch_all(db)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Start of the featured rotation `now` falls in, and when it ends.
+pub fn featured_rotation(now: DateTime<Utc>) -> (i64, DateTime<Utc>) {
+    let index = now.timestamp().div_euclid(FEATURED_ROTATION_SECS);
+    let ends = DateTime::from_timestamp((index + 1) * FEATURED_ROTATION_SECS, 0).unwrap_or(now);
+    (index, ends)
+}
+
+/// An offer as shown in the store: `price` is what the player pays and
+/// `base_price` the price before any sale. Names are translated from the
+/// `store_item` namespace.
+pub fn offer_json(
+    offer: &StoreOffer,
+    contents: Option<&Vec<BundleContent>>,
+    purchased: i64,
+    t: &Translations,
+) -> Value {
+    let item = &offer.item;
+    let mut v = json!(item);
+    v["name"] = json!(t.text("store_item", &format!("{}.name", item.id), &item.name));
+    if let Some(ref d) = item.description {
+        v["description"] = json!(t.text("store_item", &format!("{}.description", item.id), d));
+    }
+    v["base_price"] = json!(item.price);
+    v["price"] = json!(offer.price());
+    v["discount_percent"] = json!(offer.discount_percent);
+    v["sale_ends_at"] = json!(offer.sale_ends_at);
+    v["purchased"] = json!(purchased);
+    if item.item_type == "bundle" {
+        let contents: Vec<Value> = contents
+            .into_iter()
+            .flatten()
+            .map(|c| {
+                let name = t.text("store_item", &format!("{}.name", c.item_id), &c.name);
+                json!({"itemId": c.item_id, "name": name, "quantity": c.quantity})
+            })
+            .collect();
+        v["contents"] = json!(contents);
+    }
+    v
+}
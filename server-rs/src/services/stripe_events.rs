@@ -0,0 +1,192 @@
+//! Durable processing of Stripe webhook events (`stripe_events`).
+//!
+//! A verified event is stored before it's processed, and its id makes
+//! processing idempotent: a redelivery of an event that was processed, or
+//! is waiting for a retry, is only acknowledged. If processing fails, the
+//! failure is recorded and a `retry_stripe_event` job takes it from there
+//! with the job worker's backoff, so Stripe gets its 200 either way. An
+//! event still failing after the job's last attempt is left `failed` for
+//! an admin to replay.
+//!
+//! Retries of subscription events sync the subscription as Stripe has it
+//! now rather than the event's snapshot, which later events may have
+//! overtaken by then.
+
+use serde_json::{json, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::services::{jobs, subscription_sync};
+use crate::AppState;
+
+fn retry_key(event_id: &str) -> String {
+    format!("stripe_event:{}", event_id)
+}
+
+/// Stores a delivered event. Returns whether to process it now: true for a
+/// new event, or one an earlier delivery stored without recording an
+/// outcome.
+pub async fn record(db: &sqlx::PgPool, event: &Value, tenant_id: &str) -> AppResult<bool> {
+    let event_id = event["id"].as_str().unwrap_or("");
+    let inserted: Option<String> = sqlx::query_scalar(
+        r#"INSERT INTO stripe_events (id, tenant_id, event_type, payload, status, processed_at)
+        VALUES ($1, $2, $3, $4, 'pending', NULL)
+        ON CONFLICT (id) DO NOTHING
+        RETURNING id"#,
+    )
+    .bind(event_id)
+    .bind(tenant_id)
+    .bind(event["type"].as_str().unwrap_or(""))
+    .bind(event)
+    .fetch_optional(db)
+    .await?;
+    if inserted.is_some() {
+        return Ok(true);
+    }
+
+    let status: Option<String> = sqlx::query_scalar("SELECT status FROM stripe_events WHERE id = $1")
+        .bind(event_id)
+        .fetch_optional(db)
+        .await?;
+    Ok(status.as_deref() == Some("pending"))
+}
+
+/// Processes a stored event as it's delivered. A failure is recorded and
+/// queued for a retry; only failing to do that is an error.
+pub async fn process(state: &AppState, event: &Value, tenant_id: &str) -> AppResult<()> {
+    let event_id = event["id"].as_str().unwrap_or("");
+    match dispatch(state, event, tenant_id, false).await {
+        Ok(()) => mark_processed(&state.db, event_id).await,
+        Err(e) => {
+            tracing::warn!("Stripe event {} failed, queued for retry: {}", event_id, e);
+            let mut tx = state.db.begin().await?;
+            mark_failed(&mut *tx, event_id, &e, "retrying").await?;
+            jobs::enqueue(&mut *tx, jobs::RETRY_STRIPE_EVENT, json!({ "eventId": event_id }), Some(&retry_key(event_id)))
+                .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+    }
+}
+
+/// Job: processes a stored event again. If this is the job's last attempt
+/// and it fails, the event is left `failed`.
+pub async fn retry(state: &AppState, payload: &Value, last_attempt: bool) -> AppResult<()> {
+    let event_id = payload["eventId"]
+        .as_str()
+        .ok_or_else(|| AppError::Internal("retry_stripe_event needs eventId".into()))?;
+    let row: Option<(String, Value, String)> =
+        sqlx::query_as("SELECT tenant_id, payload, status FROM stripe_events WHERE id = $1")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await?;
+    let Some((tenant_id, event, status)) = row else {
+        return Err(AppError::Internal(format!("Stripe event {} not found", event_id)));
+    };
+    if status == "processed" {
+        return Ok(());
+    }
+
+    match dispatch(state, &event, &tenant_id, true).await {
+        Ok(()) => mark_processed(&state.db, event_id).await,
+        Err(e) => {
+            let status = if last_attempt { "failed" } else { "retrying" };
+            mark_failed(&state.db, event_id, &e, status).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Queues a failed event for another round of retries. Returns false if
+/// there's no failed event with that id.
+pub async fn replay(db: &sqlx::PgPool, event_id: &str) -> AppResult<bool> {
+    let mut tx = db.begin().await?;
+    let updated = sqlx::query("UPDATE stripe_events SET status = 'retrying' WHERE id = $1 AND status = 'failed'")
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if updated == 0 {
+        return Ok(false);
+    }
+    jobs::enqueue(&mut *tx, jobs::RETRY_STRIPE_EVENT, json!({ "eventId": event_id }), Some(&retry_key(event_id)))
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn mark_processed(db: &sqlx::PgPool, event_id: &str) -> AppResult<()> {
+    sqlx::query(
+        r#"UPDATE stripe_events SET status = 'processed', processed_at = NOW(),
+            attempts = attempts + 1, last_error = NULL
+        WHERE id = $1"#,
+    )
+    .bind(event_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+async fn mark_failed<'e>(
+    db: impl sqlx::PgExecutor<'e>,
+    event_id: &str,
+    error: &AppError,
+    status: &str,
+) -> AppResult<()> {
+    sqlx::query("UPDATE stripe_events SET status = $2, attempts = attempts + 1, last_error = $3 WHERE id = $1")
+        .bind(event_id)
+        .bind(status)
+        .bind(error.to_string())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Applies an event. `latest` syncs subscription events from Stripe's
+/// current copy of the subscription instead of the event's.
+async fn dispatch(state: &AppState, event: &Value, tenant_id: &str, latest: bool) -> AppResult<()> {
+    let stripe = state.stripe.as_ref()
+        .ok_or_else(|| AppError::Internal("Stripe not configured".into()))?;
+    let event_type = event["type"].as_str().unwrap_or("");
+    let object = &event["data"]["object"];
+
+    match event_type {
+        "customer.subscription.created"
+        | "customer.subscription.updated"
+        | "customer.subscription.deleted"
+        | "customer.subscription.trial_will_end" => {
+            if latest {
+                let sub = stripe.get_subscription(object["id"].as_str().unwrap_or("")).await?;
+                subscription_sync::sync_from_stripe(&state.db, &state.cache, &sub, tenant_id).await
+            } else {
+                subscription_sync::sync_from_stripe(&state.db, &state.cache, object, tenant_id).await
+            }
+        }
+        "invoice.payment_succeeded" | "invoice.payment_failed" => {
+            let dunning = if event_type == "invoice.payment_failed" {
+                subscription_sync::flag_payment_failed(state, object, tenant_id).await
+            } else {
+                let customer_id = object["customer"].as_str().unwrap_or("");
+                subscription_sync::clear_payment_failed(&state.db, customer_id, tenant_id).await
+            };
+            let synced = sync_subscription(state, object["subscription"].as_str().unwrap_or(""), tenant_id).await;
+            dunning.and(synced)
+        }
+        "checkout.session.completed" => {
+            sync_subscription(state, object["subscription"].as_str().unwrap_or(""), tenant_id).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Syncs a subscription fetched from Stripe. A subscription Stripe can't
+/// return is skipped.
+async fn sync_subscription(state: &AppState, sub_id: &str, tenant_id: &str) -> AppResult<()> {
+    if sub_id.is_empty() {
+        return Ok(());
+    }
+    let Some(stripe) = &state.stripe else { return Ok(()) };
+    match stripe.get_subscription(sub_id).await {
+        Ok(sub) => subscription_sync::sync_from_stripe(&state.db, &state.cache, &sub, tenant_id).await,
+        Err(_) => Ok(()),
+    }
+}
@@ -25,12 +25,32 @@ impl StripeClient {
     }
 
     async fn post(&self, path: &str, params: &[(&str, &str)]) -> AppResult<Value> {
+        self.post_with_key(path, params, None).await
+    }
+
+    /// A POST that Stripe performs once per `idempotency_key`; a retry with
+    /// the same key gets the first response back.
+    #[tracing::instrument(
+        name = "stripe.request",
+        skip_all,
+        fields(otel.kind = "client", http.request.method = "POST", stripe.path = path)
+    )]
+    async fn post_with_key(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+        idempotency_key: Option<&str>,
+    ) -> AppResult<Value> {
         let url = format!("https://api.stripe.com/v1{}", path);
-        let resp = self
+        let mut req = self
             .client
             .post(&url)
             .basic_auth(&self.secret_key, Option::<&str>::None)
-            .form(params)
+            .form(params);
+        if let Some(key) = idempotency_key {
+            req = req.header("Idempotency-Key", key);
+        }
+        let resp = req
             .send()
             .await
             .map_err(|e| AppError::Internal(format!("Stripe request failed: {}", e)))?;
@@ -50,12 +70,18 @@ impl StripeClient {
         Ok(body)
     }
 
-    async fn get(&self, path: &str) -> AppResult<Value> {
+    #[tracing::instrument(
+        name = "stripe.request",
+        skip_all,
+        fields(otel.kind = "client", http.request.method = "GET", stripe.path = path)
+    )]
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> AppResult<Value> {
         let url = format!("https://api.stripe.com/v1{}", path);
         let resp = self
             .client
             .get(&url)
             .basic_auth(&self.secret_key, Option::<&str>::None)
+            .query(query)
             .send()
             .await
             .map_err(|e| AppError::Internal(format!("Stripe request failed: {}", e)))?;
@@ -75,6 +101,11 @@ impl StripeClient {
         Ok(body)
     }
 
+    #[tracing::instrument(
+        name = "stripe.request",
+        skip_all,
+        fields(otel.kind = "client", http.request.method = "DELETE", stripe.path = path)
+    )]
     async fn delete(&self, path: &str) -> AppResult<Value> {
         let url = format!("https://api.stripe.com/v1{}", path);
         let resp = self
@@ -109,27 +140,101 @@ impl StripeClient {
         .await
     }
 
+    /// Subscribes the customer to `items`: each a price and, for licensed
+    /// prices, a quantity. Metered prices take no quantity.
     pub async fn create_subscription(
         &self,
         customer_id: &str,
-        price_id: &str,
+        items: &[(&str, Option<i64>)],
         trial_days: Option<u32>,
         org_id: &str,
         tenant_id: &str,
     ) -> AppResult<Value> {
         let trial_str = trial_days.map(|d| d.to_string()).unwrap_or_default();
+        let item_params: Vec<(String, String)> = items
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (price, quantity))| {
+                let mut p = vec![(format!("items[{}][price]", i), price.to_string())];
+                if let Some(q) = quantity {
+                    p.push((format!("items[{}][quantity]", i), q.to_string()));
+                }
+                p
+            })
+            .collect();
         let mut params: Vec<(&str, &str)> = vec![
             ("customer", customer_id),
-            ("items[0][price]", price_id),
             ("metadata[organisationId]", org_id),
             ("metadata[tenantId]", tenant_id),
         ];
+        params.extend(item_params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
         if trial_days.is_some() {
             params.push(("trial_period_days", &trial_str));
         }
         self.post("/subscriptions", &params).await
     }
 
+    /// Sets a subscription item's quantity, e.g. the seats of a per-seat
+    /// plan. `proration_behavior` decides how the rest of the period is
+    /// billed: `create_prorations`, `always_invoice` or `none`.
+    pub async fn update_item_quantity(
+        &self,
+        item_id: &str,
+        quantity: i64,
+        proration_behavior: &str,
+    ) -> AppResult<Value> {
+        let quantity = quantity.to_string();
+        self.post(
+            &format!("/subscription_items/{}", item_id),
+            &[("quantity", &quantity), ("proration_behavior", proration_behavior)],
+        )
+        .await
+    }
+
+    /// Adds `quantity` to a metered item's usage for the current period.
+    /// Reports sharing an `idempotency_key` are counted once.
+    pub async fn report_usage(
+        &self,
+        item_id: &str,
+        quantity: i64,
+        idempotency_key: &str,
+    ) -> AppResult<Value> {
+        let quantity = quantity.to_string();
+        self.post_with_key(
+            &format!("/subscription_items/{}/usage_records", item_id),
+            &[("quantity", &quantity), ("action", "increment")],
+            Some(idempotency_key),
+        )
+        .await
+    }
+
+    /// The subscription's upcoming invoice as it would be if the item's
+    /// quantity changed now, including the proration lines for the change.
+    /// Nothing is changed.
+    pub async fn preview_quantity_change(
+        &self,
+        customer_id: &str,
+        subscription_id: &str,
+        item_id: &str,
+        quantity: i64,
+        proration_behavior: &str,
+    ) -> AppResult<Value> {
+        let quantity = quantity.to_string();
+        let now = chrono::Utc::now().timestamp().to_string();
+        self.get(
+            "/invoices/upcoming",
+            &[
+                ("customer", customer_id),
+                ("subscription", subscription_id),
+                ("subscription_items[0][id]", item_id),
+                ("subscription_items[0][quantity]", &quantity),
+                ("subscription_proration_behavior", proration_behavior),
+                ("subscription_proration_date", &now),
+            ],
+        )
+        .await
+    }
+
     pub async fn cancel_subscription(
         &self,
         subscription_id: &str,
@@ -156,10 +261,67 @@ impl StripeClient {
     }
 
     pub async fn get_subscription(&self, subscription_id: &str) -> AppResult<Value> {
-        self.get(&format!("/subscriptions/{}", subscription_id))
+        self.get(&format!("/subscriptions/{}", subscription_id), &[])
             .await
     }
 
+    /// The customer's invoices, newest first, each with its charge
+    /// expanded for the receipt link. Pages after `starting_after`, an
+    /// invoice id.
+    pub async fn list_invoices(
+        &self,
+        customer_id: &str,
+        limit: u32,
+        starting_after: Option<&str>,
+    ) -> AppResult<Value> {
+        let limit = limit.to_string();
+        let mut query = vec![
+            ("customer", customer_id),
+            ("limit", limit.as_str()),
+            ("expand[]", "data.charge"),
+        ];
+        if let Some(id) = starting_after {
+            query.push(("starting_after", id));
+        }
+        self.get("/invoices", &query).await
+    }
+
+    /// The active promotion code a customer typed, if there is one.
+    pub async fn find_promotion_code(&self, code: &str) -> AppResult<Option<Value>> {
+        let page = self
+            .get("/promotion_codes", &[("code", code), ("active", "true"), ("limit", "1")])
+            .await?;
+        Ok(page["data"].as_array().and_then(|d| d.first()).cloned())
+    }
+
+    /// Applies a promotion code's coupon to the subscription's future
+    /// invoices.
+    pub async fn apply_promotion_code(
+        &self,
+        subscription_id: &str,
+        promotion_code_id: &str,
+    ) -> AppResult<Value> {
+        self.post(
+            &format!("/subscriptions/{}", subscription_id),
+            &[("promotion_code", promotion_code_id)],
+        )
+        .await
+    }
+
+    /// Applies a promotion code to the customer, for a customer without a
+    /// subscription; the subscription they start next gets the discount.
+    pub async fn apply_customer_promotion_code(
+        &self,
+        customer_id: &str,
+        promotion_code_id: &str,
+    ) -> AppResult<Value> {
+        self.post(
+            &format!("/customers/{}", customer_id),
+            &[("promotion_code", promotion_code_id)],
+        )
+        .await
+    }
+
     pub async fn create_billing_portal(
         &self,
         customer_id: &str,
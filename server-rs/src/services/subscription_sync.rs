@@ -1,8 +1,14 @@
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::cache::Cache;
 use crate::error::AppResult;
 use crate::models::subscription::{plan_entitlements, ALL_FEATURES};
+use crate::services::mailer;
+use crate::AppState;
+
+/// The usage meter that metered prices bill.
+pub const METERED_METER: &str = "game_sessions";
 
 pub async fn sync_from_stripe(
     db: &sqlx::PgPool,
@@ -46,20 +52,30 @@ pub async fn sync_from_stripe(
     let cancel_at = stripe_sub["cancel_at"].as_i64();
     let canceled_at = stripe_sub["canceled_at"].as_i64();
     let ended_at = stripe_sub["ended_at"].as_i64();
-    let stripe_price_id = stripe_sub["items"]["data"][0]["price"]["id"]
-        .as_str()
+
+    // The plan's own item and, on metered plans, the metered one.
+    let items = stripe_sub["items"]["data"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let is_metered = |item: &&serde_json::Value| item["price"]["recurring"]["usage_type"] == "metered";
+    let seat_item = items.iter().find(|i| !is_metered(i));
+    let metered_item = items.iter().find(is_metered);
+    let stripe_price_id = seat_item
+        .and_then(|i| i["price"]["id"].as_str())
         .unwrap_or("");
+    let seat_item_id = seat_item.and_then(|i| i["id"].as_str());
+    let seat_quantity = seat_item.and_then(|i| i["quantity"].as_i64()).map(|q| q as i32);
+    let metered_item_id = metered_item.and_then(|i| i["id"].as_str());
 
     // Upsert subscription
     sqlx::query(
         r#"INSERT INTO subscriptions (id, organisation_id, tenant_id, stripe_subscription_id, stripe_customer_id,
             stripe_price_id, status, plan_tier, trial_start, trial_end,
-            current_period_start, current_period_end, cancel_at, canceled_at, ended_at, updated_at)
+            current_period_start, current_period_end, cancel_at, canceled_at, ended_at,
+            seat_item_id, seat_quantity, metered_item_id, updated_at)
         VALUES (gen_random_uuid()::text, $1, $2, $3, $4, $5, $6, $7,
             to_timestamp($8::double precision), to_timestamp($9::double precision),
             to_timestamp($10::double precision), to_timestamp($11::double precision),
             to_timestamp($12::double precision), to_timestamp($13::double precision),
-            to_timestamp($14::double precision), NOW())
+            to_timestamp($14::double precision), $15, $16, $17, NOW())
         ON CONFLICT (stripe_subscription_id) DO UPDATE SET
             status = EXCLUDED.status, plan_tier = EXCLUDED.plan_tier,
             stripe_price_id = EXCLUDED.stripe_price_id,
@@ -67,7 +83,9 @@ pub async fn sync_from_stripe(
             current_period_start = EXCLUDED.current_period_start,
             current_period_end = EXCLUDED.current_period_end,
             cancel_at = EXCLUDED.cancel_at, canceled_at = EXCLUDED.canceled_at,
-            ended_at = EXCLUDED.ended_at, updated_at = NOW()"#,
+            ended_at = EXCLUDED.ended_at, seat_item_id = EXCLUDED.seat_item_id,
+            seat_quantity = EXCLUDED.seat_quantity, metered_item_id = EXCLUDED.metered_item_id,
+            updated_at = NOW()"#,
     )
     .bind(&org_id)
     .bind(tenant_id)
@@ -83,6 +101,9 @@ pub async fn sync_from_stripe(
     .bind(cancel_at.map(|t| t as f64))
     .bind(canceled_at.map(|t| t as f64))
     .bind(ended_at.map(|t| t as f64))
+    .bind(seat_item_id)
+    .bind(seat_quantity)
+    .bind(metered_item_id)
     .execute(db)
     .await?;
 
@@ -237,3 +258,222 @@ pub async fn mark_trial_converted(
     .await?;
     Ok(())
 }
+
+/// Seats a per-seat plan bills for: the organisation's members, and at
+/// least one.
+pub async fn seat_count(
+    db: &sqlx::PgPool,
+    organisation_id: &str,
+    tenant_id: &str,
+) -> AppResult<i64> {
+    let members: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*)::bigint FROM organisation_members WHERE organisation_id = $1 AND tenant_id = $2",
+    )
+    .bind(organisation_id)
+    .bind(tenant_id)
+    .fetch_one(db)
+    .await?;
+    Ok(members.max(1))
+}
+
+/// Sets the seats of the organisation's per-seat subscription to its
+/// member count. Stripe bills the change for the rest of the period as
+/// `STRIPE_PRORATION_BEHAVIOR` says. Returns the new seat count, or `None`
+/// if seats aren't billed or were already right.
+pub async fn sync_seats(
+    state: &AppState,
+    organisation_id: &str,
+    tenant_id: &str,
+) -> AppResult<Option<i64>> {
+    let Some(stripe) = state.stripe.as_ref() else { return Ok(None) };
+    if !state.config.stripe.per_seat {
+        return Ok(None);
+    }
+
+    let sub: Option<(String, Option<i32>)> = sqlx::query_as(
+        r#"SELECT seat_item_id, seat_quantity FROM subscriptions
+        WHERE organisation_id = $1 AND tenant_id = $2 AND seat_item_id IS NOT NULL
+          AND status IN ('active', 'trialing', 'past_due')
+        ORDER BY updated_at DESC LIMIT 1"#,
+    )
+    .bind(organisation_id)
+    .bind(tenant_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some((item_id, seats)) = sub else { return Ok(None) };
+
+    let members = seat_count(&state.db, organisation_id, tenant_id).await?;
+    if seats.map(i64::from) == Some(members) {
+        return Ok(None);
+    }
+
+    stripe
+        .update_item_quantity(&item_id, members, &state.config.stripe.proration_behavior)
+        .await?;
+    sqlx::query("UPDATE subscriptions SET seat_quantity = $1, updated_at = NOW() WHERE seat_item_id = $2")
+        .bind(members as i32)
+        .bind(&item_id)
+        .execute(&state.db)
+        .await?;
+    Ok(Some(members))
+}
+
+/// Syncs the seats of every per-seat subscription, catching member changes
+/// that weren't passed on when they happened. Returns how many changed.
+pub async fn sync_all_seats(state: &AppState) -> AppResult<usize> {
+    if state.stripe.is_none() || !state.config.stripe.per_seat {
+        return Ok(0);
+    }
+    let orgs: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT DISTINCT organisation_id, tenant_id FROM subscriptions
+        WHERE seat_item_id IS NOT NULL AND status IN ('active', 'trialing', 'past_due')"#,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut changed = 0;
+    for (org_id, tenant_id) in &orgs {
+        if sync_seats(state, org_id, tenant_id).await?.is_some() {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Reports the [`METERED_METER`] usage Stripe hasn't been told about yet to
+/// each metered subscription. Returns how many reports were sent.
+pub async fn report_usage(state: &AppState) -> AppResult<usize> {
+    let Some(stripe) = state.stripe.as_ref() else { return Ok(0) };
+
+    let pending: Vec<(String, String, DateTime<Utc>, i64, i64)> = sqlx::query_as(
+        r#"SELECT DISTINCT ON (um.organisation_id, um.period_start)
+            um.organisation_id, s.metered_item_id, um.period_start, um.count, um.reported_count
+        FROM usage_meters um
+        JOIN subscriptions s ON s.organisation_id = um.organisation_id AND s.tenant_id = um.tenant_id
+        WHERE um.meter_key = $1 AND um.count > um.reported_count
+          AND s.metered_item_id IS NOT NULL AND s.status IN ('active', 'trialing', 'past_due')
+        ORDER BY um.organisation_id, um.period_start, s.updated_at DESC"#,
+    )
+    .bind(METERED_METER)
+    .fetch_all(&state.db)
+    .await?;
+
+    for (org_id, item_id, period_start, count, reported) in &pending {
+        // Keyed by the total reported up to, so a retry after a lost
+        // response doesn't count the increment twice.
+        let key = format!("usage:{}:{}:{}", item_id, period_start.timestamp(), count);
+        stripe.report_usage(item_id, count - reported, &key).await?;
+        sqlx::query(
+            r#"UPDATE usage_meters SET reported_count = $4
+            WHERE organisation_id = $1 AND meter_key = $2 AND period_start = $3 AND reported_count < $4"#,
+        )
+        .bind(org_id)
+        .bind(METERED_METER)
+        .bind(period_start)
+        .bind(count)
+        .execute(&state.db)
+        .await?;
+    }
+    Ok(pending.len())
+}
+
+// ---------------------------------------------------------------------------
+// Dunning
+// ---------------------------------------------------------------------------
+
+/// Flags the organisation billed by a failed invoice and starts its grace
+/// period. Only the first failure does; Stripe's retries of the same debt
+/// leave the grace period where it is. The organisation's owners and
+/// admins are told in-app and by email.
+pub async fn flag_payment_failed(
+    state: &AppState,
+    invoice: &serde_json::Value,
+    tenant_id: &str,
+) -> AppResult<()> {
+    let customer_id = invoice["customer"].as_str().unwrap_or("");
+
+    let flagged: Option<(String, String, DateTime<Utc>)> = sqlx::query_as(
+        r#"UPDATE organisations
+        SET payment_failed_at = NOW(), billing_grace_until = NOW() + make_interval(days => $3), updated_at = NOW()
+        WHERE stripe_customer_id = $1 AND tenant_id = $2 AND payment_failed_at IS NULL
+        RETURNING id, name, billing_grace_until"#,
+    )
+    .bind(customer_id)
+    .bind(tenant_id)
+    .bind(state.config.stripe.dunning_grace_days)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some((org_id, org_name, grace_until)) = flagged else { return Ok(()) };
+
+    let managers: Vec<(Uuid, Option<String>)> = sqlx::query_as(
+        r#"SELECT om.player_id, p.email FROM organisation_members om
+        JOIN players p ON p.id = om.player_id AND p.tenant_id = om.tenant_id
+        WHERE om.organisation_id = $1 AND om.tenant_id = $2 AND om.role IN ('owner', 'admin')"#,
+    )
+    .bind(&org_id)
+    .bind(tenant_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let invoice_url = invoice["hosted_invoice_url"].as_str().unwrap_or_default();
+    let event = serde_json::json!({
+        "type": "billing_payment_failed",
+        "organisationId": org_id,
+        "graceUntil": grace_until,
+        "amountDue": invoice["amount_due"],
+        "currency": invoice["currency"],
+        "invoiceUrl": invoice_url,
+    });
+    let ids: Vec<Uuid> = managers.iter().map(|(id, _)| *id).collect();
+    state.realtime.send_to_many(tenant_id, &ids, &event).await;
+
+    let subject = format!("Payment failed for {}", org_name);
+    let text = format!(
+        "We couldn't take the latest payment for {}. Stripe will try again over the next few days.\n\n\
+        Please check the payment method on file: {}\n\n\
+        If no payment goes through by {}, the organisation moves to the free plan.",
+        org_name,
+        invoice_url,
+        grace_until.format("%-d %B %Y"),
+    );
+    for email in managers.iter().filter_map(|(_, email)| email.as_deref()) {
+        mailer::queue(&state.db, email, &subject, &text).await?;
+    }
+    Ok(())
+}
+
+/// Clears the failed-payment flag and any grace period once the customer
+/// has paid.
+pub async fn clear_payment_failed(
+    db: &sqlx::PgPool,
+    customer_id: &str,
+    tenant_id: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"UPDATE organisations SET payment_failed_at = NULL, billing_grace_until = NULL, updated_at = NOW()
+        WHERE stripe_customer_id = $1 AND tenant_id = $2 AND payment_failed_at IS NOT NULL"#,
+    )
+    .bind(customer_id)
+    .bind(tenant_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Moves organisations whose grace period ran out without a payment to
+/// the free plan. Returns how many.
+pub async fn expire_grace_periods(state: &AppState) -> AppResult<usize> {
+    let expired: Vec<(String, String)> = sqlx::query_as(
+        r#"UPDATE organisations SET billing_grace_until = NULL, updated_at = NOW()
+        WHERE billing_grace_until <= NOW()
+        RETURNING id, tenant_id"#,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    for (org_id, tenant_id) in &expired {
+        revoke_entitlements(&state.db, org_id, tenant_id).await?;
+        state.cache.del(&format!("entitlements:{}", org_id)).await;
+    }
+    Ok(expired.len())
+}
@@ -0,0 +1,145 @@
+//! Per-tenant translation tables for API responses.
+//!
+//! Rows in `translations` are keyed by locale, namespace and key:
+//!
+//! | Namespace          | Key                                | Translates                          |
+//! |--------------------|------------------------------------|-------------------------------------|
+//! | `error`            | the English error message          | `error` in error responses          |
+//! | `achievement`      | `{id}.name`, `{id}.description`    | achievement names and descriptions  |
+//! | `store_item`       | `{id}.name`, `{id}.description`    | store item names and descriptions   |
+//! | `battle_pass_tier` | `{passId}.{tier}`, or `{tier}`     | the `label` of battle pass rewards  |
+//!
+//! A request for `pt-BR` uses the `pt-BR` rows, then the `pt` rows; text
+//! with neither stays as stored. Each tenant/locale table is cached in
+//! Redis.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+use crate::error::AppResult;
+
+const CACHE_TTL_SECS: u64 = 300;
+
+pub const NAMESPACES: &[&str] = &["error", "achievement", "store_item", "battle_pass_tier"];
+
+/// Translations for one request locale: namespace -> key -> text.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Translations {
+    entries: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    pub fn get(&self, namespace: &str, key: &str) -> Option<&str> {
+        self.entries.get(namespace)?.get(key).map(String::as_str)
+    }
+
+    /// The translation of `key`, or `fallback` when there is none.
+    pub fn text(&self, namespace: &str, key: &str, fallback: &str) -> String {
+        self.get(namespace, key).unwrap_or(fallback).to_string()
+    }
+
+    /// Entries of `other` not already present here.
+    fn fill_from(&mut self, other: Translations) {
+        for (namespace, keys) in other.entries {
+            let ours = self.entries.entry(namespace).or_default();
+            for (key, value) in keys {
+                ours.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+/// Canonical form of a BCP 47-style tag (`pt_br` -> `pt-BR`), or `None` if
+/// it doesn't look like one.
+pub fn normalize_locale(tag: &str) -> Option<String> {
+    let mut parts = tag.trim().split(['-', '_']);
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut out = language.to_ascii_lowercase();
+    for (i, part) in parts.enumerate() {
+        if i >= 2 || part.is_empty() || part.len() > 8 || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        out.push('-');
+        match part.len() {
+            2 => out.push_str(&part.to_ascii_uppercase()),
+            4 => {
+                out.push_str(&part[..1].to_ascii_uppercase());
+                out.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => out.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Some(out)
+}
+
+fn cache_key(tenant_id: &str, locale: &str) -> String {
+    format!("i18n:{}:{}", tenant_id, locale)
+}
+
+/// The tenant's rows for exactly `locale`.
+async fn locale_table(
+    db: &sqlx::PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+    locale: &str,
+) -> AppResult<Translations> {
+    let key = cache_key(tenant_id, locale);
+    if let Some(table) = cache.get_json::<Translations>(&key).await {
+        return Ok(table);
+    }
+
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT namespace, key, value FROM translations WHERE tenant_id = $1 AND locale = $2",
+    )
+    .bind(tenant_id)
+    .bind(locale)
+    .fetch_all(db)
+    .await?;
+
+    let mut table = Translations::default();
+    for (namespace, key, value) in rows {
+        table.entries.entry(namespace).or_default().insert(key, value);
+    }
+    cache.set_json(&key, &table, CACHE_TTL_SECS).await;
+    Ok(table)
+}
+
+/// Translations for a request locale, falling back from region to
+/// language (`pt-BR`, then `pt`).
+pub async fn for_locale(
+    db: &sqlx::PgPool,
+    cache: &Cache,
+    tenant_id: &str,
+    locale: &str,
+) -> AppResult<Translations> {
+    let mut merged = locale_table(db, cache, tenant_id, locale).await?;
+    if let Some((language, _)) = locale.split_once('-') {
+        merged.fill_from(locale_table(db, cache, tenant_id, language).await?);
+    }
+    Ok(merged)
+}
+
+/// Drops the cached table after an admin change.
+pub async fn invalidate(cache: &Cache, tenant_id: &str, locale: &str) {
+    cache.del(&cache_key(tenant_id, locale)).await;
+}
+
+/// Sets `label` on each `{tier, ...}` reward from the `battle_pass_tier`
+/// namespace, trying the pass-specific key before the generic one.
+pub fn label_tiers(rewards: &mut serde_json::Value, pass_id: &str, t: &Translations) {
+    let Some(rewards) = rewards.as_array_mut() else { return };
+    for reward in rewards {
+        let Some(tier) = reward.get("tier").and_then(|v| v.as_i64()) else { continue };
+        let label = t
+            .get("battle_pass_tier", &format!("{}.{}", pass_id, tier))
+            .or_else(|| t.get("battle_pass_tier", &tier.to_string()));
+        if let (Some(label), Some(obj)) = (label, reward.as_object_mut()) {
+            obj.insert("label".into(), label.into());
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! xAPI statement export to tenants' Learning Record Stores.
+//!
+//! Score submissions and earned achievements become xAPI statements
+//! ("scored" a game, "earned" a badge), written to `xapi_outbox` in the
+//! same transaction as what they record, and only for tenants with an
+//! active LRS in `lrs_endpoints`. The `export_xapi` job posts each tenant's
+//! pending statements to its LRS in batches, oldest first. A batch the LRS
+//! rejects stays pending and is tried again on the next run; after
+//! [`MAX_ATTEMPTS`] its statements are marked `failed` and wait for an
+//! admin to replay them.
+//!
+//! Players appear as an account on [`XapiConfig::activity_base_url`]
+//! named by their player id, never by name or email.
+//!
+//! [`XapiConfig::activity_base_url`]: crate::config::XapiConfig
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::models::xapi::LrsEndpoint;
+use crate::AppState;
+
+pub const XAPI_VERSION: &str = "1.0.3";
+/// Statements posted to an LRS per request.
+const BATCH_SIZE: i64 = 100;
+/// Delivery attempts before a statement is marked failed.
+const MAX_ATTEMPTS: i32 = 10;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const VERB_SCORED: &str = "http://adlnet.gov/expapi/verbs/scored";
+const VERB_EARNED: &str = "http://id.tincanapi.com/verb/earned";
+const ACTIVITY_GAME: &str = "http://activitystrea.ms/schema/1.0/game";
+const ACTIVITY_BADGE: &str = "http://id.tincanapi.com/activitytype/badge";
+const PLATFORM: &str = "STEM School Adventures";
+
+fn actor(base: &str, player_id: Uuid) -> Value {
+    json!({
+        "objectType": "Agent",
+        "account": { "homePage": base, "name": player_id.to_string() },
+    })
+}
+
+fn game_activity(base: &str, game_id: &str) -> Value {
+    json!({
+        "objectType": "Activity",
+        "id": format!("{}/games/{}", base, game_id),
+        "definition": { "type": ACTIVITY_GAME },
+    })
+}
+
+/// An ISO 8601 duration from milliseconds.
+fn duration(ms: i32) -> String {
+    format!("PT{}.{:03}S", ms / 1000, ms % 1000)
+}
+
+/// "Player scored `score` in the game", with the level reached and the
+/// run's length when known.
+pub fn score_statement(
+    base: &str,
+    player_id: Uuid,
+    game_id: &str,
+    score: i64,
+    level: Option<i32>,
+    time_ms: Option<i32>,
+    at: DateTime<Utc>,
+) -> Value {
+    let mut result = json!({ "score": { "raw": score, "min": 0 } });
+    if let Some(ms) = time_ms.filter(|ms| *ms >= 0) {
+        result["duration"] = json!(duration(ms));
+    }
+    if let Some(level) = level {
+        result["extensions"] = json!({ format!("{}/xapi/extensions/level", base): level });
+    }
+    json!({
+        "id": Uuid::new_v4(),
+        "actor": actor(base, player_id),
+        "verb": { "id": VERB_SCORED, "display": { "en-US": "scored" } },
+        "object": game_activity(base, game_id),
+        "result": result,
+        "context": { "platform": PLATFORM },
+        "timestamp": at.to_rfc3339(),
+    })
+}
+
+/// "Player earned the achievement", under its game if it has one.
+pub fn achievement_statement(
+    base: &str,
+    player_id: Uuid,
+    achievement_id: &str,
+    name: &str,
+    game_id: Option<&str>,
+    at: DateTime<Utc>,
+) -> Value {
+    let mut context = json!({ "platform": PLATFORM });
+    if let Some(game_id) = game_id {
+        context["contextActivities"] = json!({ "parent": [game_activity(base, game_id)] });
+    }
+    json!({
+        "id": Uuid::new_v4(),
+        "actor": actor(base, player_id),
+        "verb": { "id": VERB_EARNED, "display": { "en-US": "earned" } },
+        "object": {
+            "objectType": "Activity",
+            "id": format!("{}/achievements/{}", base, achievement_id),
+            "definition": { "type": ACTIVITY_BADGE, "name": { "en-US": name } },
+        },
+        "context": context,
+        "timestamp": at.to_rfc3339(),
+    })
+}
+
+/// Writes a statement to the outbox if the tenant exports to an LRS. Run
+/// it in the transaction that records what the statement describes.
+pub async fn record<'e>(db: impl sqlx::PgExecutor<'e>, tenant_id: &str, statement: &Value) -> AppResult<()> {
+    let id = statement["id"]
+        .as_str()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| AppError::Internal("xAPI statement without an id".into()))?;
+    sqlx::query(
+        r#"INSERT INTO xapi_outbox (id, tenant_id, statement)
+        SELECT $1, $2, $3
+        WHERE EXISTS (SELECT 1 FROM lrs_endpoints WHERE tenant_id = $2 AND is_active)"#,
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .bind(statement)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Job: delivers pending statements to every active LRS. Returns how many
+/// were sent. One tenant's LRS failing doesn't hold up the others.
+pub async fn export(state: &AppState) -> AppResult<usize> {
+    let endpoints: Vec<LrsEndpoint> = sqlx::query_as(
+        r#"SELECT * FROM lrs_endpoints l
+        WHERE is_active AND EXISTS (SELECT 1 FROM xapi_outbox o WHERE o.tenant_id = l.tenant_id AND o.status = 'pending')"#,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut sent = 0;
+    for lrs in &endpoints {
+        sent += export_tenant(&state.db, lrs).await?;
+    }
+    Ok(sent)
+}
+
+async fn export_tenant(db: &sqlx::PgPool, lrs: &LrsEndpoint) -> AppResult<usize> {
+    let mut sent = 0;
+    loop {
+        let batch: Vec<(Uuid, Value)> = sqlx::query_as(
+            r#"SELECT id, statement FROM xapi_outbox
+            WHERE tenant_id = $1 AND status = 'pending'
+            ORDER BY created_at, id LIMIT $2"#,
+        )
+        .bind(&lrs.tenant_id)
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+        if batch.is_empty() {
+            return Ok(sent);
+        }
+        let (ids, statements): (Vec<Uuid>, Vec<Value>) = batch.into_iter().unzip();
+
+        if let Err(e) = post_statements(lrs, &statements).await {
+            tracing::warn!("xAPI export for tenant {} failed: {}", lrs.tenant_id, e);
+            sqlx::query(
+                r#"UPDATE xapi_outbox SET attempts = attempts + 1, last_error = $2,
+                    status = CASE WHEN attempts + 1 >= $3 THEN 'failed' ELSE 'pending' END
+                WHERE id = ANY($1)"#,
+            )
+            .bind(&ids)
+            .bind(e.to_string())
+            .bind(MAX_ATTEMPTS)
+            .execute(db)
+            .await?;
+            return Ok(sent);
+        }
+
+        sqlx::query(
+            r#"UPDATE xapi_outbox SET status = 'sent', sent_at = NOW(), attempts = attempts + 1, last_error = NULL
+            WHERE id = ANY($1)"#,
+        )
+        .bind(&ids)
+        .execute(db)
+        .await?;
+        sent += ids.len();
+        if (ids.len() as i64) < BATCH_SIZE {
+            return Ok(sent);
+        }
+    }
+}
+
+#[tracing::instrument(name = "xapi.post", skip_all, fields(otel.kind = "client"))]
+async fn post_statements(lrs: &LrsEndpoint, statements: &[Value]) -> AppResult<()> {
+    let resp = reqwest::Client::new()
+        .post(format!("{}/statements", lrs.endpoint.trim_end_matches('/')))
+        .basic_auth(&lrs.username, Some(&lrs.password))
+        .header("X-Experience-API-Version", XAPI_VERSION)
+        .timeout(REQUEST_TIMEOUT)
+        .json(statements)
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("LRS request failed: {}", e)))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(AppError::Internal(format!(
+            "LRS returned {}: {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        )));
+    }
+    Ok(())
+}
+
+/// Resets the tenant's statements in `status` (written since `since`, if
+/// given) to pending with fresh attempts. Returns how many.
+pub async fn replay(
+    db: &sqlx::PgPool,
+    tenant_id: &str,
+    status: &str,
+    since: Option<DateTime<Utc>>,
+) -> AppResult<u64> {
+    let replayed = sqlx::query(
+        r#"UPDATE xapi_outbox SET status = 'pending', attempts = 0, last_error = NULL, sent_at = NULL
+        WHERE tenant_id = $1 AND status = $2 AND ($3::timestamptz IS NULL OR created_at >= $3)"#,
+    )
+    .bind(tenant_id)
+    .bind(status)
+    .bind(since)
+    .execute(db)
+    .await?
+    .rows_affected();
+    Ok(replayed)
+}